@@ -0,0 +1,137 @@
+use chrono::NaiveDate;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use pulse_trader_rust::processors::field::Field;
+use pulse_trader_rust::processors::kernels;
+use pulse_trader_rust::processors::rolling::RollingEngine;
+use pulse_trader_rust::testing::SyntheticGenerator;
+use rayon::prelude::*;
+
+/// 20年日线约5000根
+const BARS_PER_SYMBOL: usize = 5000;
+/// 全市场股票数
+const SYMBOL_COUNT: usize = 5000;
+
+fn create_test_series(len: usize) -> Vec<f64> {
+    // 按种子生成的模拟日线收盘价，每段5000根，拼接到所需长度
+    let symbols = len.div_ceil(BARS_PER_SYMBOL);
+    SyntheticGenerator::new(42)
+        .with_symbol_count(symbols)
+        .with_period(
+            NaiveDate::from_ymd_opt(2000, 1, 3).unwrap(),
+            BARS_PER_SYMBOL,
+        )
+        .generate()
+        .into_iter()
+        .take(len)
+        .map(|record| record.close)
+        .collect()
+}
+
+fn bench_rolling_mean(c: &mut Criterion) {
+    let values = create_test_series(1_000_000);
+
+    let mut group = c.benchmark_group("rolling_mean_w20");
+    group.bench_function("scalar", |b| {
+        b.iter(|| kernels::scalar::rolling_mean(black_box(&values), black_box(20)))
+    });
+    group.bench_function("kernel", |b| {
+        b.iter(|| kernels::rolling_mean(black_box(&values), black_box(20)))
+    });
+    group.finish();
+}
+
+fn bench_rolling_std(c: &mut Criterion) {
+    let values = create_test_series(1_000_000);
+
+    let mut group = c.benchmark_group("rolling_std_w20");
+    group.bench_function("scalar", |b| {
+        b.iter(|| kernels::scalar::rolling_std(black_box(&values), black_box(20)))
+    });
+    group.bench_function("kernel", |b| {
+        b.iter(|| kernels::rolling_std(black_box(&values), black_box(20)))
+    });
+    group.finish();
+}
+
+fn bench_ema(c: &mut Criterion) {
+    let values = create_test_series(1_000_000);
+
+    c.bench_function("ema_12", |b| {
+        b.iter(|| kernels::ema(black_box(&values), black_box(12)))
+    });
+}
+
+/// 各指标独立求和（改写前的计算方式）：MA逐条序列滚动求和，布林带逐行重算20日窗口
+fn independent_windows(closes: &[f64], volumes: &[f64]) -> f64 {
+    let mut checksum = 0.0;
+    for window in [5, 10, 20, 60] {
+        checksum += kernels::rolling_mean(closes, window)[closes.len() - 1];
+    }
+    checksum += kernels::rolling_mean(volumes, 5)[volumes.len() - 1];
+    for i in 19..closes.len() {
+        let slice = &closes[i - 19..=i];
+        let ma = kernels::sum(slice) / 20.0;
+        checksum += (kernels::sum_squared_deviation(slice, ma) / 20.0).sqrt();
+    }
+    checksum
+}
+
+/// 滚动窗口引擎：MA20与布林带共享窗口，每根K线O(1)更新
+fn shared_windows(closes: &[f64], volumes: &[f64]) -> f64 {
+    let mut engine = RollingEngine::new();
+    let handles: Vec<_> = [5, 10, 20, 60]
+        .iter()
+        .map(|&window| engine.subscribe(Field::Close, window))
+        .collect();
+    let volume = engine.subscribe(Field::Volume, 5);
+    let bollinger = engine.subscribe(Field::Close, 20);
+
+    let mut checksum = 0.0;
+    for (&close, &vol) in closes.iter().zip(volumes) {
+        engine.push_with(|field| match field {
+            Field::Volume => vol,
+            _ => close,
+        });
+        checksum += engine.window(bollinger).std().unwrap_or(0.0);
+    }
+    for handle in handles.into_iter().chain([volume]) {
+        checksum += engine.window(handle).mean().unwrap_or(0.0);
+    }
+    checksum
+}
+
+fn bench_indicator_windows(c: &mut Criterion) {
+    // 每只股票取基准序列的不同偏移，避免为全市场分配独立序列
+    let closes = create_test_series(BARS_PER_SYMBOL + SYMBOL_COUNT);
+    let volumes: Vec<f64> = closes.iter().map(|close| close * 1_000.0).collect();
+    let run = |f: fn(&[f64], &[f64]) -> f64| {
+        (0..SYMBOL_COUNT)
+            .into_par_iter()
+            .map(|s| {
+                f(
+                    &closes[s..s + BARS_PER_SYMBOL],
+                    &volumes[s..s + BARS_PER_SYMBOL],
+                )
+            })
+            .sum::<f64>()
+    };
+
+    let mut group = c.benchmark_group("indicator_windows_20y_x5000");
+    group.sample_size(10);
+    group.bench_function("independent", |b| {
+        b.iter(|| black_box(run(independent_windows)))
+    });
+    group.bench_function("rolling_engine", |b| {
+        b.iter(|| black_box(run(shared_windows)))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_rolling_mean,
+    bench_rolling_std,
+    bench_ema,
+    bench_indicator_windows
+);
+criterion_main!(benches);
@@ -0,0 +1,139 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use pulse_trader_rust::parsers::{SymbolTable, TDXDayRecord};
+use pulse_trader_rust::processors::{DataProcessor, SizeOf};
+use rayon::prelude::*;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+const RECORD_COUNT: usize = 1_000_000;
+
+fn create_test_records(count: usize) -> Vec<TDXDayRecord> {
+    let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+    (0..count)
+        .map(|i| {
+            let close = 10.0 + (i % 100) as f64 * 0.01;
+            TDXDayRecord {
+                date,
+                symbol: format!("{:06}", i % 5000),
+                open: close - 0.05,
+                high: close + 0.1,
+                low: close - 0.1,
+                close,
+                volume: 1000 + i as u64,
+                amount: close * 1000.0,
+                market: "SH".to_string(),
+            }
+        })
+        .collect()
+}
+
+fn amplitude(record: TDXDayRecord) -> Result<f64> {
+    Ok((record.high - record.low) / record.close * 100.0)
+}
+
+/// 改写前的实现：逐元素克隆，信号量只包住同步的rayon调用
+async fn process_parallel_legacy<T, R, F>(
+    semaphore: &Arc<Semaphore>,
+    concurrency_limit: usize,
+    data: Vec<T>,
+    processor: F,
+) -> Result<Vec<R>>
+where
+    T: Send + Sync + Clone + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Result<R> + Send + Sync + 'static,
+{
+    let chunk_size = data.len().div_ceil(concurrency_limit).max(1);
+    let mut results = Vec::with_capacity(data.len());
+
+    for chunk in data.chunks(chunk_size) {
+        let _permit = semaphore.acquire().await?;
+        let chunk_results: Result<Vec<_>> = chunk
+            .par_iter()
+            .map(|item| processor(item.to_owned()))
+            .collect();
+        results.extend(chunk_results?);
+    }
+
+    Ok(results)
+}
+
+fn bench_process_parallel(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let records = create_test_records(RECORD_COUNT);
+    let concurrency_limit = num_cpus::get();
+    let processor = DataProcessor::new(concurrency_limit, 1024 * 1024 * 1024);
+    let semaphore = Arc::new(Semaphore::new(concurrency_limit));
+
+    let mut group = c.benchmark_group("process_parallel_1m");
+    group.sample_size(10);
+
+    group.bench_function("legacy_clone", |b| {
+        b.iter_batched(
+            || records.clone(),
+            |data| {
+                runtime
+                    .block_on(process_parallel_legacy(
+                        &semaphore,
+                        concurrency_limit,
+                        black_box(data),
+                        amplitude,
+                    ))
+                    .unwrap()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("by_value", |b| {
+        b.iter_batched(
+            || records.clone(),
+            |data| {
+                runtime
+                    .block_on(processor.process_parallel(black_box(data), amplitude))
+                    .unwrap()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+/// 驻留代码前后的内存占用与遍历耗时
+fn bench_compact_records(c: &mut Criterion) {
+    let records = create_test_records(RECORD_COUNT);
+    let table = SymbolTable::new();
+    let compact = table.compact(&records);
+
+    let string_bytes = records.size_of();
+    let compact_bytes = compact.size_of();
+    println!(
+        "{}条记录内存占用: TDXDayRecord {:.1} MiB, CompactDayRecord {:.1} MiB（降低{:.0}%）",
+        RECORD_COUNT,
+        string_bytes as f64 / (1024.0 * 1024.0),
+        compact_bytes as f64 / (1024.0 * 1024.0),
+        (1.0 - compact_bytes as f64 / string_bytes as f64) * 100.0
+    );
+
+    let mut group = c.benchmark_group("compact_records_1m");
+    group.sample_size(10);
+
+    group.bench_function("sum_close_records", |b| {
+        b.iter(|| black_box(&records).iter().map(|r| r.close).sum::<f64>())
+    });
+    group.bench_function("sum_close_compact", |b| {
+        b.iter(|| black_box(&compact).iter().map(|r| r.close).sum::<f64>())
+    });
+    group.bench_function("compact", |b| b.iter(|| table.compact(black_box(&records))));
+    group.bench_function("expand", |b| {
+        b.iter(|| table.expand(black_box(&compact)).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_process_parallel, bench_compact_records);
+criterion_main!(benches);
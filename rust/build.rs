@@ -0,0 +1,13 @@
+//! 构建脚本：启用 `serve` 特性时编译gRPC接口定义（使用纯Rust的protox，无需安装protoc）
+
+fn main() {
+    #[cfg(feature = "serve")]
+    {
+        println!("cargo:rerun-if-changed=proto/pulse_trader.proto");
+        let descriptors =
+            protox::compile(["proto/pulse_trader.proto"], ["proto"]).expect("编译proto文件失败");
+        tonic_prost_build::configure()
+            .compile_fds(descriptors)
+            .expect("生成gRPC代码失败");
+    }
+}
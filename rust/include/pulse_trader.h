@@ -0,0 +1,65 @@
+/*
+ * PulseTrader C接口
+ *
+ * 以 `ffi` 特性编译 pulse_trader_rust 动态库后链接使用。
+ * 本库分配的结果内存必须调用对应的 pt_free_* 函数释放。
+ */
+#ifndef PULSE_TRADER_H
+#define PULSE_TRADER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* 状态码 */
+#define PT_OK 0
+#define PT_ERR_NULL_POINTER (-1)
+#define PT_ERR_FAILED (-2)
+
+/* 日线记录 */
+typedef struct PtDayRecord {
+    int32_t date; /* YYYYMMDD */
+    double open;
+    double high;
+    double low;
+    double close;
+    uint64_t volume;
+    double amount;
+} PtDayRecord;
+
+/* 日线记录数组 */
+typedef struct PtRecordArray {
+    PtDayRecord *records;
+    size_t len;
+} PtRecordArray;
+
+/* 指标矩阵，按行存储：values[i * columns + j]，缺失为NaN */
+typedef struct PtIndicatorMatrix {
+    double *values;
+    size_t rows;
+    size_t columns;
+} PtIndicatorMatrix;
+
+/* 当前线程最近一次的错误信息，没有错误时为NULL，调用方不得释放 */
+const char *pt_last_error(void);
+
+/* 解析通达信day文件内容，成功后以 pt_free_records 释放 */
+int32_t pt_parse_day_buffer(const uint8_t *data, size_t len, PtRecordArray *out);
+void pt_free_records(PtRecordArray array);
+
+/* 指标列数与列名（静态字符串，越界时为NULL） */
+size_t pt_indicator_count(void);
+const char *pt_indicator_name(size_t index);
+
+/* 计算单只股票按日期排列的记录的技术指标，成功后以 pt_free_indicators 释放 */
+int32_t pt_calculate_indicators(const PtDayRecord *records, size_t len, PtIndicatorMatrix *out);
+void pt_free_indicators(PtIndicatorMatrix matrix);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PULSE_TRADER_H */
@@ -0,0 +1,42 @@
+"""PulseTrader Rust扩展模块
+
+解析和指标计算在Rust端执行时释放GIL；``*_async`` 协程把这些调用放到线程池中执行，
+夜间批量导入时不会阻塞asyncio事件循环。
+"""
+
+import asyncio
+from typing import Dict, Optional, Sequence
+
+from ._core import TDXDayParser, __version__
+
+try:
+    from ._core import render_kline
+except ImportError:  # 未启用viz特性
+    render_kline = None
+
+__all__ = [
+    "TDXDayParser",
+    "__version__",
+    "render_kline",
+    "parse_file_async",
+    "parse_directory_async",
+    "parse_directory_indicators_async",
+]
+
+
+async def parse_file_async(parser: TDXDayParser, path: str) -> Dict[str, object]:
+    """异步解析单个day文件，按列返回NumPy数组"""
+    return await asyncio.to_thread(parser.parse_file_arrays, path)
+
+
+async def parse_directory_async(parser: TDXDayParser, path: str) -> Dict[str, object]:
+    """异步解析目录下的所有day文件，按列返回NumPy数组"""
+    return await asyncio.to_thread(parser.parse_directory_arrays, path)
+
+
+async def parse_directory_indicators_async(
+    parser: TDXDayParser, path: str, window_sizes: Optional[Sequence[int]] = None
+) -> Dict[str, object]:
+    """异步解析目录并计算技术指标，按列返回NumPy数组"""
+    sizes = list(window_sizes) if window_sizes is not None else None
+    return await asyncio.to_thread(parser.parse_directory_indicators, path, sizes)
@@ -0,0 +1,418 @@
+//! 回测结果的蒙特卡洛自助法重抽样
+//!
+//! 对回测报告的日收益率（逐日或按块）或已平仓交易盈亏有放回地重抽样，
+//! 重新拼出净值路径，统计年化收益率（CAGR）、累计收益率和最大回撤的置信区间，
+//! 用于判断策略的超额收益是否只是收益顺序带来的偶然结果。
+//!
+//! 每条样本路径使用由种子和样本序号派生的独立随机数，结果与线程数无关、可复现。
+
+use super::{BacktestReport, Fill, Side};
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 重抽样方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "method", content = "block")]
+pub enum Resample {
+    /// 逐日有放回抽取日收益率
+    #[default]
+    Returns,
+    /// 按固定长度的连续块抽取日收益率（保留短期自相关）
+    Blocks(usize),
+    /// 抽取已平仓交易的盈亏（按平均成本计算，期末未平仓部分不计）
+    Trades,
+}
+
+/// 置信区间
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceInterval {
+    /// 原始顺序下的取值
+    pub observed: f64,
+    /// 下限
+    pub lower: f64,
+    /// 中位数
+    pub median: f64,
+    /// 上限
+    pub upper: f64,
+}
+
+impl ConfidenceInterval {
+    /// 由样本值和置信水平计算（样本会被排序）
+    fn from_samples(observed: f64, samples: &mut [f64], confidence: f64) -> Self {
+        samples.sort_by(f64::total_cmp);
+        let alpha = (1.0 - confidence) / 2.0;
+        Self {
+            observed,
+            lower: quantile(samples, alpha),
+            median: quantile(samples, 0.5),
+            upper: quantile(samples, 1.0 - alpha),
+        }
+    }
+
+    /// 区间是否包含给定值
+    pub fn contains(&self, value: f64) -> bool {
+        self.lower <= value && value <= self.upper
+    }
+}
+
+/// 重抽样结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrapResult {
+    /// 重抽样方式
+    pub method: Resample,
+    /// 样本路径数
+    pub samples: usize,
+    /// 置信水平
+    pub confidence: f64,
+    /// 年化收益率
+    pub cagr: ConfidenceInterval,
+    /// 累计收益率
+    pub total_return: ConfidenceInterval,
+    /// 最大回撤（正数）
+    pub max_drawdown: ConfidenceInterval,
+    /// 累计收益为负的样本比例
+    pub probability_of_loss: f64,
+}
+
+/// 自助法重抽样器
+#[derive(Debug, Clone)]
+pub struct Bootstrap {
+    /// 样本路径数
+    samples: usize,
+    /// 置信水平
+    confidence: f64,
+    /// 重抽样方式
+    method: Resample,
+    /// 随机种子
+    seed: u64,
+    /// 每年期数
+    periods_per_year: f64,
+}
+
+impl Default for Bootstrap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bootstrap {
+    /// 创建重抽样器（1000条路径，95%置信水平，逐日抽取）
+    pub fn new() -> Self {
+        Self {
+            samples: 1000,
+            confidence: 0.95,
+            method: Resample::default(),
+            seed: 42,
+            periods_per_year: 252.0,
+        }
+    }
+
+    /// 设置样本路径数
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// 设置置信水平（0~1之间）
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// 设置重抽样方式
+    pub fn with_method(mut self, method: Resample) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// 设置随机种子
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// 设置每年期数（日频为252）
+    pub fn with_periods_per_year(mut self, periods_per_year: f64) -> Self {
+        self.periods_per_year = periods_per_year;
+        self
+    }
+
+    /// 对回测报告重抽样
+    pub fn run(&self, report: &BacktestReport) -> Result<BootstrapResult> {
+        if self.samples == 0 {
+            return Err(anyhow::anyhow!("样本路径数必须大于0"));
+        }
+        if !(self.confidence > 0.0 && self.confidence < 1.0) {
+            return Err(anyhow::anyhow!(
+                "置信水平必须在0和1之间: {}",
+                self.confidence
+            ));
+        }
+        let periods = report.equity.len().saturating_sub(1);
+        if periods == 0 {
+            return Err(anyhow::anyhow!("权益序列长度不足，至少需要2个数据点"));
+        }
+
+        // 原始顺序的路径：收益率方式为每期收益率，交易方式为每笔平仓盈亏
+        let observed = match self.method {
+            Resample::Returns | Resample::Blocks(_) => report
+                .equity
+                .windows(2)
+                .map(|w| w[1].1 / w[0].1 - 1.0)
+                .collect::<Vec<_>>(),
+            Resample::Trades => realized_pnl(&report.fills),
+        };
+        if observed.is_empty() {
+            return Err(anyhow::anyhow!("没有可重抽样的数据: {:?}", self.method));
+        }
+        if let Resample::Blocks(0) = self.method {
+            return Err(anyhow::anyhow!("块长度必须大于0"));
+        }
+
+        let stats = |path: &[f64]| self.path_stats(report.initial_cash, periods, path);
+        let observed_stats = stats(&observed);
+        let sampled: Vec<PathStats> = (0..self.samples)
+            .into_par_iter()
+            .map(|i| {
+                let mut rng =
+                    SplitMix64::new(self.seed ^ (i as u64).wrapping_mul(0xA24B_AED4_963E_E407));
+                stats(&self.resample(&observed, &mut rng))
+            })
+            .collect();
+
+        let interval = |observed: f64, f: fn(&PathStats) -> f64| {
+            let mut values: Vec<f64> = sampled.iter().map(f).collect();
+            ConfidenceInterval::from_samples(observed, &mut values, self.confidence)
+        };
+        Ok(BootstrapResult {
+            method: self.method,
+            samples: self.samples,
+            confidence: self.confidence,
+            cagr: interval(observed_stats.cagr, |s| s.cagr),
+            total_return: interval(observed_stats.total_return, |s| s.total_return),
+            max_drawdown: interval(observed_stats.max_drawdown, |s| s.max_drawdown),
+            probability_of_loss: sampled.iter().filter(|s| s.total_return < 0.0).count() as f64
+                / self.samples as f64,
+        })
+    }
+
+    /// 有放回地抽取与原序列等长的样本
+    fn resample(&self, values: &[f64], rng: &mut SplitMix64) -> Vec<f64> {
+        let n = values.len();
+        match self.method {
+            Resample::Blocks(block) => {
+                // 循环块：块越过末尾时从头接上
+                let block = block.min(n);
+                let mut path = Vec::with_capacity(n);
+                while path.len() < n {
+                    let start = rng.below(n);
+                    path.extend(
+                        (0..block)
+                            .map(|k| values[(start + k) % n])
+                            .take(n - path.len()),
+                    );
+                }
+                path
+            }
+            Resample::Returns | Resample::Trades => (0..n).map(|_| values[rng.below(n)]).collect(),
+        }
+    }
+
+    /// 由收益率或盈亏序列拼出净值并计算统计量
+    fn path_stats(&self, initial_cash: f64, periods: usize, path: &[f64]) -> PathStats {
+        let mut equity = initial_cash;
+        let mut peak = equity;
+        let mut max_drawdown: f64 = 0.0;
+        for value in path {
+            equity = match self.method {
+                Resample::Trades => equity + value,
+                Resample::Returns | Resample::Blocks(_) => equity * (1.0 + value),
+            };
+            peak = peak.max(equity);
+            if peak > 0.0 {
+                max_drawdown = max_drawdown.max(1.0 - equity / peak);
+            }
+        }
+        let growth = (equity / initial_cash).max(0.0);
+        PathStats {
+            total_return: growth - 1.0,
+            cagr: growth.powf(self.periods_per_year / periods as f64) - 1.0,
+            max_drawdown: max_drawdown.min(1.0),
+        }
+    }
+}
+
+/// 单条路径的统计量
+struct PathStats {
+    total_return: f64,
+    cagr: f64,
+    max_drawdown: f64,
+}
+
+/// 按平均成本（含买入费用）计算每笔卖出扣除费用后的已实现盈亏
+pub fn realized_pnl(fills: &[Fill]) -> Vec<f64> {
+    let mut holdings: HashMap<(&str, &str), (u64, f64)> = HashMap::new();
+    let mut pnl = Vec::new();
+    for fill in fills {
+        let (shares, cost) = holdings
+            .entry((fill.symbol.as_str(), fill.market.as_str()))
+            .or_insert((0, 0.0));
+        match fill.side {
+            Side::Buy => {
+                *shares += fill.shares;
+                *cost += fill.amount + fill.fees.total();
+            }
+            Side::Sell => {
+                let sold = fill.shares.min(*shares);
+                if sold == 0 {
+                    continue;
+                }
+                let basis = *cost * sold as f64 / *shares as f64;
+                pnl.push(fill.amount - fill.fees.total() - basis);
+                *shares -= sold;
+                *cost -= basis;
+            }
+        }
+    }
+    pnl
+}
+
+/// 已排序样本的线性插值分位数
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+/// SplitMix64伪随机数生成器
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, n) 内的均匀整数
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * n as f64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn report(values: &[f64], fills: Vec<Fill>) -> BacktestReport {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let equity: Vec<(NaiveDate, f64)> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| (start + chrono::Duration::days(i as i64), v))
+            .collect();
+        BacktestReport {
+            start_date: equity[0].0,
+            end_date: equity[equity.len() - 1].0,
+            initial_cash: values[0],
+            final_equity: values[values.len() - 1],
+            equity,
+            orders: Vec::new(),
+            fills,
+            positions: Vec::new(),
+            events: Vec::new(),
+            metrics: None,
+        }
+    }
+
+    fn fill(symbol: &str, side: Side, shares: u64, price: f64) -> Fill {
+        Fill {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            symbol: symbol.to_string(),
+            market: "SH".to_string(),
+            side,
+            shares,
+            price,
+            amount: shares as f64 * price,
+            fees: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_returns() {
+        // 每3天中2天上涨0.4%、1天下跌0.2%
+        let mut values = vec![100.0];
+        for i in 1..60 {
+            let r = if i % 3 == 0 { -0.002 } else { 0.004 };
+            values.push(values[i - 1] * (1.0 + r));
+        }
+        let report = report(&values, Vec::new());
+        let bootstrap = Bootstrap::new().with_samples(500).with_seed(7);
+        let result = bootstrap.run(&report).unwrap();
+
+        // 逐日重抽样不改变收益率的乘积，累计收益的观测值落在区间内
+        assert!(result.total_return.contains(result.total_return.observed));
+        assert!(result.cagr.lower <= result.cagr.median && result.cagr.median <= result.cagr.upper);
+        assert!(result.max_drawdown.lower >= 0.0);
+        assert!(result.max_drawdown.upper >= result.max_drawdown.observed);
+        assert!(result.probability_of_loss < 0.05);
+
+        // 相同种子结果一致
+        let again = bootstrap.run(&report).unwrap();
+        assert_eq!(result.cagr, again.cagr);
+        let blocks = bootstrap
+            .with_method(Resample::Blocks(5))
+            .run(&report)
+            .unwrap();
+        assert_eq!(blocks.samples, 500);
+    }
+
+    #[test]
+    fn test_realized_pnl_and_trades() {
+        let fills = vec![
+            fill("A", Side::Buy, 100, 10.0),
+            fill("A", Side::Buy, 100, 12.0),
+            fill("A", Side::Sell, 100, 13.0),
+            fill("B", Side::Buy, 100, 20.0),
+            fill("B", Side::Sell, 100, 18.0),
+            fill("A", Side::Sell, 100, 10.0),
+        ];
+        assert_eq!(realized_pnl(&fills), vec![200.0, -200.0, -100.0]);
+
+        let report = report(&[10_000.0, 10_200.0, 10_000.0, 9_900.0], fills);
+        let result = Bootstrap::new()
+            .with_method(Resample::Trades)
+            .with_samples(200)
+            .run(&report)
+            .unwrap();
+        assert!((result.total_return.observed + 0.01).abs() < 1e-12);
+        assert!(result.total_return.lower >= -0.06 - 1e-12);
+        assert!(result.total_return.upper <= 0.06 + 1e-12);
+        assert!(result.probability_of_loss > 0.0);
+    }
+
+    #[test]
+    fn test_invalid_bootstrap() {
+        let flat = report(&[100.0, 101.0], Vec::new());
+        assert!(Bootstrap::new().with_samples(0).run(&flat).is_err());
+        assert!(Bootstrap::new().with_confidence(1.0).run(&flat).is_err());
+        assert!(Bootstrap::new()
+            .with_method(Resample::Blocks(0))
+            .run(&flat)
+            .is_err());
+        assert!(Bootstrap::new()
+            .with_method(Resample::Trades)
+            .run(&flat)
+            .is_err());
+        assert!(Bootstrap::new().run(&report(&[100.0], Vec::new())).is_err());
+    }
+}
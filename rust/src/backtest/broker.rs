@@ -0,0 +1,527 @@
+//! 模拟券商
+//!
+//! 维护现金与持仓，按给定价格成交调仓指令。股数按整手（默认100股）取整，
+//! 买入受可用现金（含费用）限制；成交价按成本模型计入滑点，费用从现金中扣除。
+//!
+//! 默认执行A股T+1规则：当日买入的股份下一个交易日才能卖出。超过可卖股数的卖出
+//! 只成交可卖部分，其余按限制策略拒绝或排队到下一交易日，并记入委托事件日志。
+//!
+//! 持仓以（代码, 市场）标识，沪深两市同代码的股票（如000001.SH与000001.SZ）互不影响。
+
+use super::costs::{CostModel, Fees};
+use crate::parsers::TDXDayRecord;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 买卖方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    /// 买入
+    Buy,
+    /// 卖出
+    Sell,
+}
+
+/// 成交记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    /// 成交日期
+    pub date: NaiveDate,
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    #[serde(default)]
+    pub market: String,
+    /// 买卖方向
+    pub side: Side,
+    /// 成交股数
+    pub shares: u64,
+    /// 成交价格（含滑点）
+    pub price: f64,
+    /// 成交金额
+    pub amount: f64,
+    /// 交易费用
+    #[serde(default)]
+    pub fees: Fees,
+}
+
+/// 卖出超过可卖股数时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestrictionPolicy {
+    /// 拒绝超出部分
+    #[default]
+    Reject,
+    /// 超出部分排队，下一交易日再按目标股数委托
+    Queue,
+}
+
+/// 委托事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderEventKind {
+    /// 因T+1限制被拒绝
+    Rejected,
+    /// 因T+1限制排队
+    Queued,
+}
+
+/// 委托事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderEvent {
+    /// 日期
+    pub date: NaiveDate,
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    #[serde(default)]
+    pub market: String,
+    /// 事件类型
+    pub kind: OrderEventKind,
+    /// 委托卖出股数
+    pub requested: u64,
+    /// 当时可卖股数
+    pub available: u64,
+    /// 说明
+    pub message: String,
+}
+
+/// 委托状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// 全部成交
+    Filled,
+    /// 部分成交（现金不足或T+1限制）
+    Partial,
+    /// 未成交
+    Unfilled,
+}
+
+impl OrderStatus {
+    fn of(requested: u64, filled: u64) -> Self {
+        if filled == 0 {
+            OrderStatus::Unfilled
+        } else if filled < requested {
+            OrderStatus::Partial
+        } else {
+            OrderStatus::Filled
+        }
+    }
+}
+
+/// 委托记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    /// 委托日期
+    pub date: NaiveDate,
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    #[serde(default)]
+    pub market: String,
+    /// 买卖方向
+    pub side: Side,
+    /// 委托股数（按整手取整后与当前持仓的差）
+    pub requested: u64,
+    /// 成交股数
+    pub filled: u64,
+    /// 委托价格（不含滑点）
+    pub price: f64,
+    /// 委托状态
+    pub status: OrderStatus,
+}
+
+/// 模拟券商账户
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Broker {
+    /// 现金
+    cash: f64,
+    /// 持仓股数（（代码, 市场）-> 股数）
+    positions: BTreeMap<(String, String), u64>,
+    /// 每手股数
+    lot_size: u64,
+    /// 交易成本模型
+    costs: CostModel,
+    /// 是否执行T+1
+    t_plus_one: bool,
+    /// T+1限制策略
+    restriction: RestrictionPolicy,
+    /// 当前交易日
+    trade_date: Option<NaiveDate>,
+    /// 当日买入、尚不可卖的股数
+    locked: BTreeMap<(String, String), u64>,
+    /// 排队中的目标股数
+    queued: BTreeMap<(String, String), u64>,
+    /// 委托事件日志
+    events: Vec<OrderEvent>,
+    /// 委托记录
+    orders: Vec<Order>,
+}
+
+impl Broker {
+    /// 以初始资金开户
+    pub fn new(cash: f64) -> Self {
+        Self {
+            cash,
+            positions: BTreeMap::new(),
+            lot_size: 100,
+            costs: CostModel::default(),
+            t_plus_one: true,
+            restriction: RestrictionPolicy::default(),
+            trade_date: None,
+            locked: BTreeMap::new(),
+            queued: BTreeMap::new(),
+            events: Vec::new(),
+            orders: Vec::new(),
+        }
+    }
+
+    /// 设置每手股数（至少为1）
+    pub fn with_lot_size(mut self, lot_size: u64) -> Self {
+        self.lot_size = lot_size.max(1);
+        self
+    }
+
+    /// 设置交易成本模型
+    pub fn with_costs(mut self, costs: CostModel) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// 设置是否执行T+1（关闭后当日买入即可卖出）
+    pub fn with_t_plus_one(mut self, enabled: bool) -> Self {
+        self.t_plus_one = enabled;
+        self
+    }
+
+    /// 设置T+1限制策略
+    pub fn with_restriction(mut self, restriction: RestrictionPolicy) -> Self {
+        self.restriction = restriction;
+        self
+    }
+
+    /// 现金
+    pub fn cash(&self) -> f64 {
+        self.cash
+    }
+
+    /// 持仓股数（（代码, 市场）-> 股数）
+    pub fn positions(&self) -> &BTreeMap<(String, String), u64> {
+        &self.positions
+    }
+
+    /// 单只股票的持仓股数
+    pub fn position(&self, symbol: &str, market: &str) -> u64 {
+        self.positions
+            .get(&(symbol.to_string(), market.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// 单只股票的可卖股数
+    pub fn available(&self, symbol: &str, market: &str) -> u64 {
+        let locked = self
+            .locked
+            .get(&(symbol.to_string(), market.to_string()))
+            .copied()
+            .unwrap_or(0);
+        self.position(symbol, market).saturating_sub(locked)
+    }
+
+    /// 排队中的目标股数（（代码, 市场）-> 目标股数）
+    pub fn queued(&self) -> &BTreeMap<(String, String), u64> {
+        &self.queued
+    }
+
+    /// 委托事件日志
+    pub fn events(&self) -> &[OrderEvent] {
+        &self.events
+    }
+
+    /// 委托记录（含未成交的委托）
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    /// 进入新的交易日：前一交易日买入的股份变为可卖
+    pub fn settle(&mut self, date: NaiveDate) {
+        if self.trade_date.is_none_or(|current| date > current) {
+            self.trade_date = Some(date);
+            self.locked.clear();
+        }
+    }
+
+    /// 按价格（代码, 市场 -> 价格）计算账户权益，缺少价格的持仓按0计
+    pub fn equity(&self, prices: &impl Fn(&str, &str) -> Option<f64>) -> f64 {
+        self.cash
+            + self
+                .positions
+                .iter()
+                .map(|((symbol, market), &shares)| {
+                    shares as f64 * prices(symbol, market).unwrap_or(0.0)
+                })
+                .sum::<f64>()
+    }
+
+    /// 按K线把持仓调整到目标股数（按整手向下取整），返回成交记录
+    ///
+    /// `price` 为委托价格（通常为开盘价），K线提供代码、市场和当日成交量。
+    /// 买入股数受现金（含滑点和费用）限制；卖出股数受T+1可卖股数限制；
+    /// 价格无效时不成交。新的委托会取代该股票排队中的目标。
+    pub fn order_target(
+        &mut self,
+        bar: &TDXDayRecord,
+        target_shares: u64,
+        price: f64,
+    ) -> Option<Fill> {
+        if !(price.is_finite() && price > 0.0) {
+            return None;
+        }
+        let symbol = bar.symbol.as_str();
+        let key = (bar.symbol.clone(), bar.market.clone());
+        self.settle(bar.date);
+        self.queued.remove(&key);
+        let volume = Some(bar.volume);
+        let target = target_shares / self.lot_size * self.lot_size;
+        let current = self.position(symbol, &bar.market);
+
+        let wanted = target.abs_diff(current);
+        if wanted == 0 {
+            return None;
+        }
+        let side = if target > current {
+            Side::Buy
+        } else {
+            Side::Sell
+        };
+        let costs = self.costs;
+        let cost = |shares: u64| {
+            let price = costs.execution_price(side, price, shares, volume);
+            let amount = shares as f64 * price;
+            (price, amount, costs.fees(&bar.market, side, amount))
+        };
+        let shares = match side {
+            Side::Buy => {
+                let (estimate, _, _) = cost(wanted);
+                let mut shares =
+                    wanted.min((self.cash / estimate) as u64 / self.lot_size * self.lot_size);
+                while shares > 0 {
+                    let (_, amount, fees) = cost(shares);
+                    if amount + fees.total() <= self.cash {
+                        break;
+                    }
+                    shares -= self.lot_size;
+                }
+                shares
+            }
+            Side::Sell => {
+                let available = self.available(symbol, &bar.market);
+                if wanted > available {
+                    self.restrict(bar, target, wanted, available);
+                }
+                wanted.min(available)
+            }
+        };
+        self.orders.push(Order {
+            date: bar.date,
+            symbol: symbol.to_string(),
+            market: bar.market.clone(),
+            side,
+            requested: wanted,
+            filled: shares,
+            price,
+            status: OrderStatus::of(wanted, shares),
+        });
+        if shares == 0 {
+            return None;
+        }
+
+        let (price, amount, fees) = cost(shares);
+        let remaining = match side {
+            Side::Buy => {
+                self.cash -= amount + fees.total();
+                if self.t_plus_one {
+                    *self.locked.entry(key.clone()).or_insert(0) += shares;
+                }
+                current + shares
+            }
+            Side::Sell => {
+                self.cash += amount - fees.total();
+                current - shares
+            }
+        };
+        if remaining == 0 {
+            self.positions.remove(&key);
+        } else {
+            self.positions.insert(key, remaining);
+        }
+
+        Some(Fill {
+            date: bar.date,
+            symbol: symbol.to_string(),
+            market: bar.market.clone(),
+            side,
+            shares,
+            price,
+            amount,
+            fees,
+        })
+    }
+
+    /// 记录超出可卖股数的卖出委托
+    fn restrict(&mut self, bar: &TDXDayRecord, target: u64, requested: u64, available: u64) {
+        let kind = match self.restriction {
+            RestrictionPolicy::Reject => OrderEventKind::Rejected,
+            RestrictionPolicy::Queue => {
+                self.queued
+                    .insert((bar.symbol.clone(), bar.market.clone()), target);
+                OrderEventKind::Queued
+            }
+        };
+        log::debug!(
+            "T+1限制: {} {}.{} 委托卖出{}股, 可卖{}股, {:?}",
+            bar.date,
+            bar.symbol,
+            bar.market,
+            requested,
+            available,
+            kind
+        );
+        self.events.push(OrderEvent {
+            date: bar.date,
+            symbol: bar.symbol.clone(),
+            market: bar.market.clone(),
+            kind,
+            requested,
+            available,
+            message: format!(
+                "T+1限制: 委托卖出{}股, 可卖{}股, 超出{}股",
+                requested,
+                available,
+                requested - available
+            ),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(day: u32, price: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: "A".to_string(),
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 100_000,
+            amount: price * 100_000.0,
+            market: "SZ".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_t_plus_one() {
+        let mut broker = Broker::new(100_000.0);
+        broker.order_target(&bar(2, 10.0), 1000, 10.0).unwrap();
+        broker.order_target(&bar(3, 10.0), 1500, 10.0).unwrap();
+        assert_eq!(broker.position("A", "SZ"), 1500);
+        assert_eq!(broker.available("A", "SZ"), 1000);
+
+        // 当日买入的500股不能卖出，只成交可卖的1000股
+        let fill = broker.order_target(&bar(3, 10.0), 0, 10.0).unwrap();
+        assert_eq!(fill.shares, 1000);
+        assert_eq!(broker.position("A", "SZ"), 500);
+        assert_eq!(broker.events().len(), 1);
+        let event = &broker.events()[0];
+        assert_eq!(event.kind, OrderEventKind::Rejected);
+        assert_eq!((event.requested, event.available), (1500, 1000));
+        assert!(broker.queued().is_empty());
+        assert!(broker.order_target(&bar(3, 10.0), 0, 10.0).is_none());
+        let statuses: Vec<OrderStatus> = broker.orders().iter().map(|o| o.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                OrderStatus::Filled,
+                OrderStatus::Filled,
+                OrderStatus::Partial,
+                OrderStatus::Unfilled
+            ]
+        );
+
+        // 下一交易日可以卖出
+        broker.settle(NaiveDate::from_ymd_opt(2024, 1, 4).unwrap());
+        assert_eq!(broker.available("A", "SZ"), 500);
+        assert_eq!(
+            broker.order_target(&bar(4, 10.0), 0, 10.0).unwrap().shares,
+            500
+        );
+    }
+
+    #[test]
+    fn test_queue_restriction() {
+        let mut broker = Broker::new(100_000.0).with_restriction(RestrictionPolicy::Queue);
+        broker.order_target(&bar(2, 10.0), 1000, 10.0).unwrap();
+        assert!(broker.order_target(&bar(2, 10.0), 0, 10.0).is_none());
+        assert_eq!(broker.events()[0].kind, OrderEventKind::Queued);
+        assert_eq!(
+            broker.queued().get(&("A".to_string(), "SZ".to_string())),
+            Some(&0)
+        );
+
+        // 新委托取代排队目标
+        broker.order_target(&bar(3, 10.0), 200, 10.0).unwrap();
+        assert!(broker.queued().is_empty());
+        assert_eq!(broker.position("A", "SZ"), 200);
+    }
+
+    #[test]
+    fn test_t_plus_zero() {
+        let mut broker = Broker::new(100_000.0).with_t_plus_one(false);
+        broker.order_target(&bar(2, 10.0), 1000, 10.0).unwrap();
+        assert_eq!(broker.available("A", "SZ"), 1000);
+        assert_eq!(
+            broker.order_target(&bar(2, 10.0), 0, 10.0).unwrap().shares,
+            1000
+        );
+        assert!(broker.events().is_empty());
+        assert_eq!(broker.cash(), 100_000.0);
+    }
+
+    #[test]
+    fn test_same_code_across_markets() {
+        let mut broker = Broker::new(100_000.0);
+        let sh = TDXDayRecord {
+            market: "SH".to_string(),
+            ..bar(2, 10.0)
+        };
+        broker.order_target(&bar(2, 10.0), 1000, 10.0).unwrap();
+        broker.order_target(&sh, 500, 10.0).unwrap();
+        assert_eq!(broker.position("A", "SZ"), 1000);
+        assert_eq!(broker.position("A", "SH"), 500);
+        assert_eq!(broker.positions().len(), 2);
+
+        // 卖出SH不影响SZ的持仓
+        broker.settle(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        let fill = broker
+            .order_target(
+                &TDXDayRecord {
+                    market: "SH".to_string(),
+                    ..bar(3, 10.0)
+                },
+                0,
+                10.0,
+            )
+            .unwrap();
+        assert_eq!((fill.market.as_str(), fill.shares), ("SH", 500));
+        assert_eq!(broker.position("A", "SZ"), 1000);
+        assert_eq!(
+            broker.equity(&|_, market| (market == "SZ").then_some(10.0)),
+            broker.cash() + 10_000.0
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! A股交易成本模型
+//!
+//! - 佣金：按成交金额比例收取，单笔不足最低佣金（通常5元）按最低收取，买卖双向
+//! - 印花税：仅卖出收取（2023-08-28起为0.05%）
+//! - 过户费：仅沪市收取，买卖双向（0.001%）
+//! - 滑点：固定基点，或按成交量占当日成交量的比例（参与率）线性冲击
+//!
+//! `CostModel::default()` 不收任何费用，`CostModel::china_a_share()` 为常见券商费率。
+
+use super::Side;
+use serde::{Deserialize, Serialize};
+
+/// 滑点模型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Slippage {
+    /// 无滑点
+    #[default]
+    None,
+    /// 固定基点（买入价上浮、卖出价下浮）
+    FixedBps {
+        /// 基点数（1基点为0.01%）
+        bps: f64,
+    },
+    /// 按参与率冲击：价格偏移比例 = impact × 成交股数 / 当日成交量
+    VolumeParticipation {
+        /// 冲击系数（0.1表示参与率10%时价格偏移1%）
+        impact: f64,
+        /// 价格偏移比例上限
+        max_slippage: f64,
+    },
+}
+
+impl Slippage {
+    /// 价格偏移比例（非负），缺少成交量时参与率模型不产生滑点
+    pub fn rate(&self, shares: u64, volume: Option<u64>) -> f64 {
+        match *self {
+            Slippage::None => 0.0,
+            Slippage::FixedBps { bps } => bps.max(0.0) / 10_000.0,
+            Slippage::VolumeParticipation {
+                impact,
+                max_slippage,
+            } => match volume {
+                Some(volume) if volume > 0 => {
+                    (impact * shares as f64 / volume as f64).clamp(0.0, max_slippage.max(0.0))
+                }
+                _ => 0.0,
+            },
+        }
+    }
+}
+
+/// 单笔成交的费用明细
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Fees {
+    /// 佣金
+    pub commission: f64,
+    /// 印花税
+    pub stamp_duty: f64,
+    /// 过户费
+    pub transfer_fee: f64,
+}
+
+impl Fees {
+    /// 费用合计
+    pub fn total(&self) -> f64 {
+        self.commission + self.stamp_duty + self.transfer_fee
+    }
+}
+
+/// 交易成本模型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostModel {
+    /// 佣金费率
+    pub commission_rate: f64,
+    /// 单笔最低佣金（元）
+    pub min_commission: f64,
+    /// 卖出印花税率
+    pub stamp_duty_rate: f64,
+    /// 沪市过户费率
+    pub transfer_fee_rate: f64,
+    /// 滑点模型
+    pub slippage: Slippage,
+}
+
+impl CostModel {
+    /// 常见A股费率：佣金万2.5（最低5元）、卖出印花税0.05%、沪市过户费0.001%，无滑点
+    pub fn china_a_share() -> Self {
+        Self {
+            commission_rate: 0.00025,
+            min_commission: 5.0,
+            stamp_duty_rate: 0.0005,
+            transfer_fee_rate: 0.00001,
+            slippage: Slippage::None,
+        }
+    }
+
+    /// 设置佣金费率和单笔最低佣金
+    pub fn with_commission(mut self, rate: f64, minimum: f64) -> Self {
+        self.commission_rate = rate;
+        self.min_commission = minimum;
+        self
+    }
+
+    /// 设置卖出印花税率
+    pub fn with_stamp_duty(mut self, rate: f64) -> Self {
+        self.stamp_duty_rate = rate;
+        self
+    }
+
+    /// 设置沪市过户费率
+    pub fn with_transfer_fee(mut self, rate: f64) -> Self {
+        self.transfer_fee_rate = rate;
+        self
+    }
+
+    /// 设置滑点模型
+    pub fn with_slippage(mut self, slippage: Slippage) -> Self {
+        self.slippage = slippage;
+        self
+    }
+
+    /// 含滑点的成交价
+    pub fn execution_price(&self, side: Side, price: f64, shares: u64, volume: Option<u64>) -> f64 {
+        let rate = self.slippage.rate(shares, volume);
+        match side {
+            Side::Buy => price * (1.0 + rate),
+            Side::Sell => price * (1.0 - rate),
+        }
+    }
+
+    /// 按成交金额计算费用，`market` 为市场代码（SH/SZ/BJ）
+    pub fn fees(&self, market: &str, side: Side, amount: f64) -> Fees {
+        if amount <= 0.0 {
+            return Fees::default();
+        }
+        Fees {
+            commission: (amount * self.commission_rate).max(self.min_commission),
+            stamp_duty: match side {
+                Side::Sell => amount * self.stamp_duty_rate,
+                Side::Buy => 0.0,
+            },
+            transfer_fee: if market.eq_ignore_ascii_case("SH") {
+                amount * self.transfer_fee_rate
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fees() {
+        let model = CostModel::china_a_share();
+        // 1万元买入深市股票：佣金2.5元不足5元按5元
+        let fees = model.fees("SZ", Side::Buy, 10_000.0);
+        assert_eq!(fees.commission, 5.0);
+        assert_eq!(fees.stamp_duty, 0.0);
+        assert_eq!(fees.transfer_fee, 0.0);
+
+        // 100万元卖出沪市股票
+        let fees = model.fees("SH", Side::Sell, 1_000_000.0);
+        assert!((fees.commission - 250.0).abs() < 1e-9);
+        assert!((fees.stamp_duty - 500.0).abs() < 1e-9);
+        assert!((fees.transfer_fee - 10.0).abs() < 1e-9);
+        assert!((fees.total() - 760.0).abs() < 1e-9);
+
+        assert_eq!(
+            CostModel::default().fees("SH", Side::Sell, 1e6).total(),
+            0.0
+        );
+        assert_eq!(model.fees("SH", Side::Buy, 0.0).total(), 0.0);
+    }
+
+    #[test]
+    fn test_slippage() {
+        let fixed = CostModel::default().with_slippage(Slippage::FixedBps { bps: 10.0 });
+        assert!((fixed.execution_price(Side::Buy, 10.0, 100, None) - 10.01).abs() < 1e-12);
+        assert!((fixed.execution_price(Side::Sell, 10.0, 100, None) - 9.99).abs() < 1e-12);
+
+        let participation = Slippage::VolumeParticipation {
+            impact: 0.1,
+            max_slippage: 0.02,
+        };
+        // 参与率10%，偏移1%
+        assert!((participation.rate(10_000, Some(100_000)) - 0.01).abs() < 1e-12);
+        // 超过上限按上限
+        assert_eq!(participation.rate(100_000, Some(100_000)), 0.02);
+        assert_eq!(participation.rate(10_000, None), 0.0);
+        assert_eq!(participation.rate(10_000, Some(0)), 0.0);
+    }
+
+    #[test]
+    fn test_serde() {
+        let model = CostModel::china_a_share().with_slippage(Slippage::FixedBps { bps: 5.0 });
+        let json = serde_json::to_string(&model).unwrap();
+        assert!(json.contains("\"type\":\"fixed_bps\""));
+        let back: CostModel = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, model);
+    }
+}
@@ -0,0 +1,481 @@
+//! 回测模块
+//!
+//! 按调仓日的目标权重模拟组合：调仓日收盘后确定的目标权重在下一个交易日以开盘价成交
+//! （避免使用当日收盘后才知道的信息），每日以收盘价计算权益，最后用
+//! `PerformanceAnalyzer` 汇总绩效。股票以（代码, 市场）标识。
+//!
+//! - `broker`：模拟券商（现金、持仓、整手成交、T+1可卖限制）
+//! - `costs`：A股交易成本（佣金、印花税、过户费）与滑点模型
+//! - `portfolio`：仓位计算与组合约束，把因子得分或交易信号转换为目标权重
+//! - `trade_log`：委托、成交、费用和每日持仓明细，导出为CSV、Parquet或写入ClickHouse
+//! - `splits`：滚动前推与K折切分，按切分评估策略的样本内外绩效
+//! - `bootstrap`：对日收益率或交易盈亏重抽样，给出年化收益与最大回撤的置信区间
+//! - `optimize`：参数网格搜索，并行回测并给出样本内外衰减等过拟合诊断
+//!
+//! 当日没有K线（停牌）的股票不交易，持仓按最近收盘价估值。
+
+pub mod bootstrap;
+pub mod broker;
+pub mod costs;
+pub mod optimize;
+pub mod portfolio;
+pub mod splits;
+pub mod trade_log;
+
+pub use bootstrap::{Bootstrap, BootstrapResult, ConfidenceInterval, Resample};
+pub use broker::{
+    Broker, Fill, Order, OrderEvent, OrderEventKind, OrderStatus, RestrictionPolicy, Side,
+};
+pub use costs::{CostModel, Fees, Slippage};
+pub use optimize::{GridReport, GridResult, GridSearch, Objective, ParameterGrid, Params};
+pub use portfolio::{Candidate, PortfolioConstructor, PositionSizing};
+pub use splits::{PurgedKFold, Split, SplitEvaluation, SplitResult, WalkForward};
+pub use trade_log::{PositionRecord, TradeLog};
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::performance::{DateSeries, PerformanceAnalyzer, PerformanceMetrics};
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 目标权重（（股票代码, 市场）-> 占权益的比例），权重合计不足1的部分为现金
+pub type TargetWeights = BTreeMap<(String, String), f64>;
+
+/// 回测策略
+///
+/// 策略根据日线给出各调仓日（收盘后）的目标权重，第t天的目标只能使用第t天及之前的数据。
+pub trait Strategy: Send + Sync {
+    /// 生成目标权重
+    fn targets(&self, bars: &[TDXDayRecord]) -> Result<BTreeMap<NaiveDate, TargetWeights>>;
+}
+
+impl<F> Strategy for F
+where
+    F: Fn(&[TDXDayRecord]) -> Result<BTreeMap<NaiveDate, TargetWeights>> + Send + Sync,
+{
+    fn targets(&self, bars: &[TDXDayRecord]) -> Result<BTreeMap<NaiveDate, TargetWeights>> {
+        self(bars)
+    }
+}
+
+/// 回测报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    /// 起始日期
+    pub start_date: NaiveDate,
+    /// 结束日期
+    pub end_date: NaiveDate,
+    /// 初始资金
+    pub initial_cash: f64,
+    /// 期末权益
+    pub final_equity: f64,
+    /// 每日收盘权益
+    pub equity: DateSeries,
+    /// 委托记录
+    #[serde(default)]
+    pub orders: Vec<Order>,
+    /// 成交记录
+    pub fills: Vec<Fill>,
+    /// 每日收盘持仓
+    #[serde(default)]
+    pub positions: Vec<PositionRecord>,
+    /// 委托事件（T+1限制等）
+    #[serde(default)]
+    pub events: Vec<OrderEvent>,
+    /// 绩效指标（权益序列不足2天时为None）
+    pub metrics: Option<PerformanceMetrics>,
+}
+
+impl BacktestReport {
+    /// 累计收益率
+    pub fn total_return(&self) -> f64 {
+        self.final_equity / self.initial_cash - 1.0
+    }
+
+    /// 交易明细（委托、成交、事件、每日持仓），用于导出审计
+    pub fn trade_log(&self) -> TradeLog<'_> {
+        TradeLog {
+            orders: &self.orders,
+            fills: &self.fills,
+            events: &self.events,
+            positions: &self.positions,
+        }
+    }
+}
+
+/// 回测引擎
+#[derive(Debug, Clone)]
+pub struct Backtester {
+    /// 初始资金
+    initial_cash: f64,
+    /// 每手股数
+    lot_size: u64,
+    /// 交易成本模型
+    costs: CostModel,
+    /// 是否执行T+1
+    t_plus_one: bool,
+    /// T+1限制策略
+    restriction: RestrictionPolicy,
+    /// 绩效分析器
+    analyzer: PerformanceAnalyzer,
+}
+
+impl Default for Backtester {
+    fn default() -> Self {
+        Self::new(1_000_000.0)
+    }
+}
+
+impl Backtester {
+    /// 以初始资金创建回测引擎
+    pub fn new(initial_cash: f64) -> Self {
+        Self {
+            initial_cash,
+            lot_size: 100,
+            costs: CostModel::default(),
+            t_plus_one: true,
+            restriction: RestrictionPolicy::default(),
+            analyzer: PerformanceAnalyzer::new(),
+        }
+    }
+
+    /// 设置每手股数
+    pub fn with_lot_size(mut self, lot_size: u64) -> Self {
+        self.lot_size = lot_size;
+        self
+    }
+
+    /// 设置交易成本模型（默认不收费用）
+    pub fn with_costs(mut self, costs: CostModel) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// 设置是否执行T+1（默认执行）
+    pub fn with_t_plus_one(mut self, enabled: bool) -> Self {
+        self.t_plus_one = enabled;
+        self
+    }
+
+    /// 设置T+1限制策略（默认拒绝）
+    pub fn with_restriction(mut self, restriction: RestrictionPolicy) -> Self {
+        self.restriction = restriction;
+        self
+    }
+
+    /// 设置绩效分析器（无风险利率等）
+    pub fn with_analyzer(mut self, analyzer: PerformanceAnalyzer) -> Self {
+        self.analyzer = analyzer;
+        self
+    }
+
+    /// 运行回测
+    ///
+    /// `bars` 为全部股票的日线，`targets` 为调仓日（收盘后）的目标权重。
+    pub fn run(
+        &self,
+        bars: &[TDXDayRecord],
+        targets: &BTreeMap<NaiveDate, TargetWeights>,
+    ) -> Result<BacktestReport> {
+        if !(self.initial_cash.is_finite() && self.initial_cash > 0.0) {
+            return Err(anyhow::anyhow!("初始资金必须为正数: {}", self.initial_cash));
+        }
+        let mut days: BTreeMap<NaiveDate, HashMap<(&str, &str), &TDXDayRecord>> = BTreeMap::new();
+        for bar in bars {
+            days.entry(bar.date)
+                .or_default()
+                .insert((bar.symbol.as_str(), bar.market.as_str()), bar);
+        }
+        let (Some(&start_date), Some(&end_date)) = (days.keys().next(), days.keys().last()) else {
+            return Err(anyhow::anyhow!("回测数据为空"));
+        };
+
+        let mut broker = Broker::new(self.initial_cash)
+            .with_lot_size(self.lot_size)
+            .with_costs(self.costs)
+            .with_t_plus_one(self.t_plus_one)
+            .with_restriction(self.restriction);
+        let mut last_close: HashMap<(String, String), f64> = HashMap::new();
+        let mut pending: Option<&TargetWeights> = None;
+        let mut equity = Vec::with_capacity(days.len());
+        let mut fills = Vec::new();
+        let mut positions = Vec::new();
+
+        for (&date, day) in &days {
+            // 开盘先结算T+1，再执行排队中的委托（停牌的继续排队）
+            broker.settle(date);
+            let queued: Vec<((String, String), u64)> = broker
+                .queued()
+                .iter()
+                .map(|(key, &target)| (key.clone(), target))
+                .collect();
+            for ((symbol, market), target) in queued {
+                if let Some(bar) = day.get(&(symbol.as_str(), market.as_str())) {
+                    fills.extend(broker.order_target(bar, target, bar.open));
+                }
+            }
+
+            if let Some(weights) = pending.take() {
+                fills.extend(Self::rebalance(&mut broker, day, &last_close, weights));
+            }
+
+            for (&(symbol, market), bar) in day {
+                last_close.insert((symbol.to_string(), market.to_string()), bar.close);
+            }
+            let close_of = |symbol: &str, market: &str| {
+                last_close
+                    .get(&(symbol.to_string(), market.to_string()))
+                    .copied()
+            };
+            let value = broker.equity(&close_of);
+            for ((symbol, market), &shares) in broker.positions() {
+                let close = close_of(symbol, market).unwrap_or(0.0);
+                let market_value = shares as f64 * close;
+                positions.push(PositionRecord {
+                    date,
+                    symbol: symbol.clone(),
+                    market: market.clone(),
+                    shares,
+                    available: broker.available(symbol, market),
+                    close,
+                    market_value,
+                    weight: market_value / value,
+                });
+            }
+            equity.push((date, value));
+
+            if let Some(weights) = targets.get(&date) {
+                pending = Some(weights);
+            }
+        }
+
+        let final_equity = equity.last().map_or(self.initial_cash, |(_, value)| *value);
+        let metrics = self.analyzer.analyze(&equity).ok();
+        Ok(BacktestReport {
+            start_date,
+            end_date,
+            initial_cash: self.initial_cash,
+            final_equity,
+            equity,
+            orders: broker.orders().to_vec(),
+            fills,
+            positions,
+            events: broker.events().to_vec(),
+            metrics,
+        })
+    }
+
+    /// 以开盘价调仓到目标权重：先卖后买，买入按权重从大到小
+    fn rebalance(
+        broker: &mut Broker,
+        day: &HashMap<(&str, &str), &TDXDayRecord>,
+        last_close: &HashMap<(String, String), f64>,
+        weights: &TargetWeights,
+    ) -> Vec<Fill> {
+        let equity = broker.equity(&|symbol: &str, market: &str| {
+            day.get(&(symbol, market)).map(|bar| bar.open).or_else(|| {
+                last_close
+                    .get(&(symbol.to_string(), market.to_string()))
+                    .copied()
+            })
+        });
+        let target_shares = |bar: &TDXDayRecord| {
+            let weight = weights
+                .get(&(bar.symbol.clone(), bar.market.clone()))
+                .copied()
+                .unwrap_or(0.0);
+            (weight * equity / bar.open).max(0.0) as u64
+        };
+
+        let mut fills = Vec::new();
+        let held: Vec<(String, String)> = broker.positions().keys().cloned().collect();
+        for (symbol, market) in held {
+            let Some(bar) = day.get(&(symbol.as_str(), market.as_str())) else {
+                continue;
+            };
+            let target = target_shares(bar);
+            if target < broker.position(&symbol, &market) {
+                fills.extend(broker.order_target(bar, target, bar.open));
+            }
+        }
+
+        let mut buys: Vec<(&(String, String), f64)> =
+            weights.iter().map(|(key, w)| (key, *w)).collect();
+        buys.sort_by(|a, b| b.1.total_cmp(&a.1));
+        for ((symbol, market), _) in buys {
+            let Some(bar) = day.get(&(symbol.as_str(), market.as_str())) else {
+                continue;
+            };
+            let target = target_shares(bar);
+            if target > broker.position(symbol, market) {
+                fills.extend(broker.order_target(bar, target, bar.open));
+            }
+        }
+        fills
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(symbol: &str, day: u32, open: f64, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open,
+            high: open.max(close),
+            low: open.min(close),
+            close,
+            volume: 100_000,
+            amount: close * 100_000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    fn key(symbol: &str) -> (String, String) {
+        (symbol.to_string(), "SH".to_string())
+    }
+
+    #[test]
+    fn test_buy_and_hold() {
+        let bars = vec![
+            bar("A", 2, 10.0, 10.0),
+            bar("A", 3, 10.0, 11.0),
+            bar("A", 4, 11.0, 12.0),
+        ];
+        let targets = BTreeMap::from([(date(2), TargetWeights::from([(key("A"), 1.0)]))]);
+        let report = Backtester::new(100_000.0).run(&bars, &targets).unwrap();
+
+        // 第2天收盘确定目标，第3天开盘以10元买入10000股
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].date, date(3));
+        assert_eq!(report.fills[0].shares, 10_000);
+        assert_eq!(report.equity[0].1, 100_000.0);
+        assert_eq!(report.final_equity, 120_000.0);
+        assert!((report.total_return() - 0.2).abs() < 1e-12);
+        assert!(report.metrics.is_some());
+    }
+
+    #[test]
+    fn test_rebalance_and_suspension() {
+        let bars = vec![
+            bar("A", 2, 10.0, 10.0),
+            bar("B", 2, 20.0, 20.0),
+            bar("A", 3, 10.0, 10.0),
+            bar("B", 3, 20.0, 20.0),
+            // B在第4天停牌
+            bar("A", 4, 10.0, 10.0),
+            bar("A", 5, 10.0, 10.0),
+            bar("B", 5, 20.0, 20.0),
+        ];
+        let targets = BTreeMap::from([
+            (
+                date(2),
+                TargetWeights::from([(key("A"), 0.5), (key("B"), 0.5)]),
+            ),
+            (date(3), TargetWeights::from([(key("A"), 1.0)])),
+        ]);
+        let report = Backtester::new(100_000.0).run(&bars, &targets).unwrap();
+
+        let on = |day| -> Vec<(String, Side, u64)> {
+            report
+                .fills
+                .iter()
+                .filter(|fill| fill.date == date(day))
+                .map(|fill| (fill.symbol.clone(), fill.side, fill.shares))
+                .collect()
+        };
+        assert_eq!(
+            on(3),
+            vec![
+                ("A".to_string(), Side::Buy, 5000),
+                ("B".to_string(), Side::Buy, 2500)
+            ]
+        );
+        // B停牌无法卖出，现金不足，A无法加仓
+        assert!(on(4).is_empty());
+        assert_eq!(report.final_equity, 100_000.0);
+        assert!(Backtester::new(0.0).run(&bars, &targets).is_err());
+        assert!(Backtester::default().run(&[], &targets).is_err());
+    }
+
+    #[test]
+    fn test_same_code_across_markets() {
+        let sz = |day, price| TDXDayRecord {
+            market: "SZ".to_string(),
+            ..bar("000001", day, price, price)
+        };
+        let bars = vec![
+            bar("000001", 2, 10.0, 10.0),
+            sz(2, 20.0),
+            bar("000001", 3, 10.0, 10.0),
+            sz(3, 20.0),
+            bar("000001", 4, 10.0, 11.0),
+            sz(4, 20.0),
+        ];
+        let sz_key = ("000001".to_string(), "SZ".to_string());
+        let targets = BTreeMap::from([(
+            date(2),
+            TargetWeights::from([(key("000001"), 0.5), (sz_key.clone(), 0.5)]),
+        )]);
+        let report = Backtester::new(100_000.0).run(&bars, &targets).unwrap();
+
+        // 两只股票各自按本市场的开盘价买入，互不覆盖
+        let bought: Vec<(&str, u64)> = report
+            .fills
+            .iter()
+            .map(|fill| (fill.market.as_str(), fill.shares))
+            .collect();
+        assert_eq!(bought.len(), 2);
+        assert!(bought.contains(&("SH", 5000)));
+        assert!(bought.contains(&("SZ", 2500)));
+        let last: Vec<&PositionRecord> = report
+            .positions
+            .iter()
+            .filter(|p| p.date == date(4))
+            .collect();
+        assert_eq!(last.len(), 2);
+        assert_eq!(report.final_equity, 105_000.0);
+    }
+
+    #[test]
+    fn test_trading_costs() {
+        let bars = vec![
+            bar("A", 2, 10.0, 10.0),
+            bar("A", 3, 10.0, 10.0),
+            bar("A", 4, 10.0, 10.0),
+        ];
+        let targets = BTreeMap::from([
+            (date(2), TargetWeights::from([(key("A"), 1.0)])),
+            (date(3), TargetWeights::new()),
+        ]);
+        let costs = CostModel::china_a_share().with_slippage(Slippage::FixedBps { bps: 10.0 });
+        let report = Backtester::new(100_000.0)
+            .with_costs(costs)
+            .run(&bars, &targets)
+            .unwrap();
+
+        // 买入价10.01，含费用后现金只够9900股
+        let buy = &report.fills[0];
+        assert_eq!(buy.shares, 9900);
+        assert!((buy.price - 10.01).abs() < 1e-12);
+        assert!((buy.fees.commission - buy.amount * 0.00025).abs() < 1e-9);
+        assert_eq!(buy.fees.stamp_duty, 0.0);
+        assert!(buy.fees.transfer_fee > 0.0);
+
+        // 卖出价9.99，收印花税
+        let sell = &report.fills[1];
+        assert!((sell.price - 9.99).abs() < 1e-12);
+        assert!((sell.fees.stamp_duty - sell.amount * 0.0005).abs() < 1e-9);
+
+        let fees: f64 = report.fills.iter().map(|fill| fill.fees.total()).sum();
+        let slippage = 9900.0 * 0.02;
+        assert!((report.final_equity - (100_000.0 - fees - slippage)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,407 @@
+//! 参数网格搜索
+//!
+//! 对策略的参数网格（如均线周期5~60）逐组构建策略，按样本切分并行回测，
+//! 输出按样本内得分排序的结果表。每组参数同时给出样本外得分和衰减比例
+//! `(样本内 - 样本外) / |样本内|`，并计算各组参数样本内与样本外得分的秩相关系数：
+//! 样本内越好的参数样本外不再越好（秩相关接近0或为负）通常说明过拟合。
+
+use super::splits::{evaluate, Split};
+use super::{Backtester, Strategy};
+use crate::parsers::TDXDayRecord;
+use crate::processors::cross_section::rank;
+use crate::processors::performance::{PerformanceAnalyzer, PerformanceMetrics};
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 一组参数（参数名 -> 取值）
+pub type Params = BTreeMap<String, f64>;
+
+/// 参数网格
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParameterGrid {
+    axes: BTreeMap<String, Vec<f64>>,
+}
+
+impl ParameterGrid {
+    /// 创建空网格
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加参数及其取值
+    pub fn with_values(mut self, name: &str, values: Vec<f64>) -> Self {
+        self.axes.insert(name.to_string(), values);
+        self
+    }
+
+    /// 添加等间隔取值的参数（两端均含）
+    pub fn with_range(self, name: &str, start: f64, end: f64, step: f64) -> Self {
+        let mut values = Vec::new();
+        if step > 0.0 {
+            let mut i = 0;
+            loop {
+                let value = start + step * i as f64;
+                // 容许浮点累积误差
+                if value > end + step * 1e-9 {
+                    break;
+                }
+                values.push(value);
+                i += 1;
+            }
+        }
+        self.with_values(name, values)
+    }
+
+    /// 参数组合数
+    pub fn len(&self) -> usize {
+        if self.axes.is_empty() {
+            0
+        } else {
+            self.axes.values().map(Vec::len).product()
+        }
+    }
+
+    /// 是否没有参数组合
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 全部参数组合（按参数名的字典序展开，最后一个参数变化最快）
+    pub fn combinations(&self) -> Vec<Params> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let mut combinations = vec![Params::new()];
+        for (name, values) in &self.axes {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|params| {
+                    values.iter().map(move |&value| {
+                        let mut params = params.clone();
+                        params.insert(name.clone(), value);
+                        params
+                    })
+                })
+                .collect();
+        }
+        combinations
+    }
+}
+
+/// 优化目标
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// 夏普比率
+    #[default]
+    Sharpe,
+    /// 索提诺比率
+    Sortino,
+    /// 累计收益率
+    TotalReturn,
+    /// 年化收益率
+    AnnualizedReturn,
+    /// 卡玛比率
+    Calmar,
+}
+
+impl Objective {
+    /// 从绩效指标取得分
+    pub fn score(&self, metrics: &PerformanceMetrics) -> Option<f64> {
+        match self {
+            Objective::Sharpe => metrics.sharpe_ratio,
+            Objective::Sortino => metrics.sortino_ratio,
+            Objective::TotalReturn => Some(metrics.total_return),
+            Objective::AnnualizedReturn => Some(metrics.annualized_return),
+            Objective::Calmar => metrics.calmar_ratio,
+        }
+    }
+
+    /// 各切分得分的平均值（缺失的不计入）
+    fn mean<'a>(
+        &self,
+        metrics: impl Iterator<Item = &'a Option<PerformanceMetrics>>,
+    ) -> Option<f64> {
+        let scores: Vec<f64> = metrics
+            .filter_map(|m| m.as_ref().and_then(|m| self.score(m)))
+            .filter(|s| s.is_finite())
+            .collect();
+        (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64)
+    }
+}
+
+/// 一组参数的搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridResult {
+    /// 参数
+    pub params: Params,
+    /// 样本内得分（各切分平均）
+    pub in_sample: Option<f64>,
+    /// 样本外得分（各切分平均）
+    pub out_of_sample: Option<f64>,
+    /// 样本外相对样本内的衰减比例
+    pub decay: Option<f64>,
+    /// 整体样本外绩效（各测试段首尾相接）
+    pub metrics: Option<PerformanceMetrics>,
+}
+
+/// 网格搜索报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridReport {
+    /// 优化目标
+    pub objective: Objective,
+    /// 各组参数结果，按样本内得分从高到低排序（没有得分的排在最后）
+    pub results: Vec<GridResult>,
+    /// 样本内与样本外得分的秩相关系数（有效结果少于3组时为None）
+    pub rank_correlation: Option<f64>,
+}
+
+impl GridReport {
+    /// 样本内得分最高的参数
+    pub fn best(&self) -> Option<&GridResult> {
+        self.results.first().filter(|r| r.in_sample.is_some())
+    }
+}
+
+/// 网格搜索
+#[derive(Debug, Clone)]
+pub struct GridSearch {
+    backtester: Backtester,
+    splits: Vec<Split>,
+    objective: Objective,
+    analyzer: PerformanceAnalyzer,
+    threads: Option<usize>,
+}
+
+impl GridSearch {
+    /// 以回测引擎和样本切分创建网格搜索
+    pub fn new(backtester: Backtester, splits: Vec<Split>) -> Self {
+        Self {
+            backtester,
+            splits,
+            objective: Objective::default(),
+            analyzer: PerformanceAnalyzer::new(),
+            threads: None,
+        }
+    }
+
+    /// 设置优化目标
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+
+    /// 设置绩效分析器
+    pub fn with_analyzer(mut self, analyzer: PerformanceAnalyzer) -> Self {
+        self.analyzer = analyzer;
+        self
+    }
+
+    /// 使用独立线程池并限制线程数（默认使用全局rayon线程池）
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads.max(1));
+        self
+    }
+
+    /// 运行网格搜索，`build` 由一组参数构建策略
+    pub fn run<S, F>(
+        &self,
+        grid: &ParameterGrid,
+        bars: &[TDXDayRecord],
+        build: F,
+    ) -> Result<GridReport>
+    where
+        S: Strategy,
+        F: Fn(&Params) -> Result<S> + Send + Sync,
+    {
+        if grid.is_empty() {
+            return Err(anyhow::anyhow!("参数网格为空"));
+        }
+        if self.splits.is_empty() {
+            return Err(anyhow::anyhow!("没有样本切分"));
+        }
+
+        let search = || {
+            grid.combinations()
+                .into_par_iter()
+                .map(|params| self.evaluate_params(params, bars, &build))
+                .collect::<Result<Vec<_>>>()
+        };
+        let mut results = match self.threads {
+            Some(threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .thread_name(|i| format!("grid-search-{}", i))
+                .build()
+                .context("创建网格搜索线程池失败")?
+                .install(search)?,
+            None => search()?,
+        };
+
+        results.sort_by(|a, b| match (a.in_sample, b.in_sample) {
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        let rank_correlation = Self::rank_correlation(&results);
+        Ok(GridReport {
+            objective: self.objective,
+            results,
+            rank_correlation,
+        })
+    }
+
+    fn evaluate_params<S, F>(
+        &self,
+        params: Params,
+        bars: &[TDXDayRecord],
+        build: &F,
+    ) -> Result<GridResult>
+    where
+        S: Strategy,
+        F: Fn(&Params) -> Result<S>,
+    {
+        let strategy = build(&params).with_context(|| format!("构建策略失败: {:?}", params))?;
+        let evaluation = evaluate(
+            &self.backtester,
+            &strategy,
+            bars,
+            &self.splits,
+            &self.analyzer,
+        )
+        .with_context(|| format!("回测失败: {:?}", params))?;
+
+        let in_sample = self
+            .objective
+            .mean(evaluation.splits.iter().map(|r| &r.in_sample));
+        let out_of_sample = self
+            .objective
+            .mean(evaluation.splits.iter().map(|r| &r.out_of_sample));
+        let decay = match (in_sample, out_of_sample) {
+            (Some(is), Some(oos)) if is != 0.0 => Some((is - oos) / is.abs()),
+            _ => None,
+        };
+        Ok(GridResult {
+            params,
+            in_sample,
+            out_of_sample,
+            decay,
+            metrics: evaluation.out_of_sample,
+        })
+    }
+
+    /// 样本内与样本外得分的Spearman秩相关
+    fn rank_correlation(results: &[GridResult]) -> Option<f64> {
+        let (is, oos): (Vec<f64>, Vec<f64>) = results
+            .iter()
+            .filter_map(|r| Some((r.in_sample?, r.out_of_sample?)))
+            .unzip();
+        if is.len() < 3 {
+            return None;
+        }
+        let (x, y) = (rank(&is), rank(&oos));
+        let n = x.len() as f64;
+        let (mx, my) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+        let cov: f64 = x.iter().zip(&y).map(|(a, b)| (a - mx) * (b - my)).sum();
+        let vx: f64 = x.iter().map(|a| (a - mx).powi(2)).sum();
+        let vy: f64 = y.iter().map(|b| (b - my).powi(2)).sum();
+        (vx > 0.0 && vy > 0.0).then(|| cov / (vx * vy).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::splits::{trading_dates, WalkForward};
+    use crate::backtest::TargetWeights;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parameter_grid() {
+        let grid = ParameterGrid::new()
+            .with_range("fast", 5.0, 20.0, 5.0)
+            .with_values("slow", vec![30.0, 60.0]);
+        assert_eq!(grid.len(), 8);
+        let combinations = grid.combinations();
+        assert_eq!(combinations.len(), 8);
+        assert_eq!(combinations[0]["fast"], 5.0);
+        assert_eq!(combinations[0]["slow"], 30.0);
+        assert_eq!(combinations[1]["slow"], 60.0);
+        assert_eq!(combinations[7]["fast"], 20.0);
+
+        assert!(ParameterGrid::new().is_empty());
+        assert!(ParameterGrid::new()
+            .with_range("x", 1.0, 0.0, 1.0)
+            .is_empty());
+        assert_eq!(ParameterGrid::new().with_range("x", 0.0, 0.3, 0.1).len(), 4);
+    }
+
+    #[test]
+    fn test_grid_search() {
+        // A每天上涨1%，持仓比例越高收益越高
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let bars: Vec<TDXDayRecord> = (0..24)
+            .map(|i| {
+                let price = 10.0 * 1.01f64.powi(i);
+                TDXDayRecord {
+                    date: start + chrono::Duration::days(i as i64),
+                    symbol: "A".to_string(),
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume: 1000,
+                    amount: price * 1000.0,
+                    market: "SH".to_string(),
+                }
+            })
+            .collect();
+        let splits = WalkForward::new(8, 8)
+            .splits(&trading_dates(&bars))
+            .unwrap();
+        let search = GridSearch::new(Backtester::new(1_000_000.0), splits)
+            .with_objective(Objective::TotalReturn)
+            .with_threads(2);
+        let grid = ParameterGrid::new().with_values("weight", vec![0.2, 1.0, 0.5, 0.0]);
+
+        let report = search
+            .run(&grid, &bars, |params| {
+                let weight = params["weight"];
+                Ok(move |bars: &[TDXDayRecord]| {
+                    Ok(trading_dates(bars)
+                        .into_iter()
+                        .map(|date| {
+                            (
+                                date,
+                                TargetWeights::from([(
+                                    ("A".to_string(), "SH".to_string()),
+                                    weight,
+                                )]),
+                            )
+                        })
+                        .collect())
+                })
+            })
+            .unwrap();
+
+        let weights: Vec<f64> = report.results.iter().map(|r| r.params["weight"]).collect();
+        assert_eq!(weights, vec![1.0, 0.5, 0.2, 0.0]);
+        let best = report.best().unwrap();
+        assert!(best.in_sample.unwrap() > best.out_of_sample.unwrap());
+        assert!(best.decay.unwrap() > 0.0);
+        assert!(report.results[3].decay.is_none());
+        assert!((report.rank_correlation.unwrap() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_invalid_search() {
+        let search = GridSearch::new(Backtester::default(), Vec::new());
+        let grid = ParameterGrid::new().with_values("x", vec![1.0]);
+        let build =
+            |_: &Params| Ok(|_: &[TDXDayRecord]| Ok(BTreeMap::<NaiveDate, TargetWeights>::new()));
+        assert!(search.run(&grid, &[], build).is_err());
+        assert!(search.run(&ParameterGrid::new(), &[], build).is_err());
+    }
+}
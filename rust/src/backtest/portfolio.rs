@@ -0,0 +1,472 @@
+//! 仓位计算与组合构建
+//!
+//! `PortfolioConstructor` 把每个调仓日的候选股票（因子得分或交易信号）转换为目标权重：
+//! 先按得分从高到低选出前N只，再按仓位方法分配权重，最后施加组合约束
+//! （最大持仓数、单只股票权重上限、行业权重上限）。约束削减的权重留作现金，不再分配给其他股票。
+//!
+//! 仓位方法：
+//! - 等权：每只股票 `1/k`
+//! - 固定比例：每只股票固定占权益的比例，合计超过1时等比缩小
+//! - 波动率目标：每只股票分到 `目标波动率/k` 的风险预算，权重为预算除以其年化波动率，
+//!   波动率按调仓日前 `window` 个交易日的收益率计算（含当日），数据不足的股票不入选
+
+use super::TargetWeights;
+use crate::parsers::TDXDayRecord;
+use crate::processors::factors::FactorTable;
+use crate::processors::signals::{Signal, SignalKind};
+use crate::reference::SecurityMaster;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 仓位方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionSizing {
+    /// 等权
+    #[default]
+    EqualWeight,
+    /// 每只股票固定占权益的比例
+    FixedFraction(f64),
+    /// 波动率目标
+    VolatilityTarget {
+        /// 组合年化目标波动率（如0.15）
+        target: f64,
+        /// 计算波动率的交易日数
+        window: usize,
+    },
+}
+
+/// 调仓日的候选股票
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    #[serde(default)]
+    pub market: String,
+    /// 得分（越高越优先）
+    pub score: f64,
+}
+
+/// 组合构建器
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioConstructor {
+    /// 仓位方法
+    sizing: PositionSizing,
+    /// 按得分选出的股票数（None表示全部候选）
+    top_n: Option<usize>,
+    /// 最大持仓数
+    max_positions: Option<usize>,
+    /// 单只股票权重上限
+    max_weight: Option<f64>,
+    /// 单个行业权重上限
+    sector_cap: Option<f64>,
+    /// （股票代码, 市场）-> 行业
+    sectors: HashMap<(String, String), String>,
+    /// 年化期数（计算波动率用）
+    periods_per_year: f64,
+}
+
+impl Default for PortfolioConstructor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PortfolioConstructor {
+    /// 创建等权、无约束的组合构建器
+    pub fn new() -> Self {
+        Self {
+            sizing: PositionSizing::default(),
+            top_n: None,
+            max_positions: None,
+            max_weight: None,
+            sector_cap: None,
+            sectors: HashMap::new(),
+            periods_per_year: 252.0,
+        }
+    }
+
+    /// 设置仓位方法
+    pub fn with_sizing(mut self, sizing: PositionSizing) -> Self {
+        self.sizing = sizing;
+        self
+    }
+
+    /// 每个调仓日按得分选出前N只
+    pub fn with_top_n(mut self, n: usize) -> Self {
+        self.top_n = Some(n);
+        self
+    }
+
+    /// 设置最大持仓数
+    pub fn with_max_positions(mut self, max_positions: usize) -> Self {
+        self.max_positions = Some(max_positions);
+        self
+    }
+
+    /// 设置单只股票权重上限
+    pub fn with_max_weight(mut self, max_weight: f64) -> Self {
+        self.max_weight = Some(max_weight);
+        self
+    }
+
+    /// 设置单个行业权重上限（没有行业信息的股票不受限制）
+    pub fn with_sector_cap(mut self, cap: f64) -> Self {
+        self.sector_cap = Some(cap);
+        self
+    }
+
+    /// 设置股票所属行业（（代码, 市场）-> 行业）
+    pub fn with_sectors(mut self, sectors: HashMap<(String, String), String>) -> Self {
+        self.sectors = sectors;
+        self
+    }
+
+    /// 从证券主数据读取行业
+    pub fn with_security_master(mut self, master: &SecurityMaster) -> Self {
+        self.sectors = master
+            .iter()
+            .filter_map(|meta| {
+                meta.industry
+                    .as_ref()
+                    .map(|industry| ((meta.symbol.clone(), meta.market.clone()), industry.clone()))
+            })
+            .collect();
+        self
+    }
+
+    /// 计算单个调仓日的目标权重
+    ///
+    /// `volatility` 按（代码, 市场）返回股票截至该日的年化波动率，仅波动率目标方法使用。
+    pub fn construct(
+        &self,
+        candidates: &[Candidate],
+        volatility: impl Fn(&str, &str) -> Option<f64>,
+    ) -> TargetWeights {
+        let mut ranked: Vec<&Candidate> =
+            candidates.iter().filter(|c| c.score.is_finite()).collect();
+        ranked.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.symbol.cmp(&b.symbol))
+                .then(a.market.cmp(&b.market))
+        });
+
+        // 波动率目标需要波动率，缺失的股票不入选
+        let vols: HashMap<(&str, &str), f64> = match self.sizing {
+            PositionSizing::VolatilityTarget { .. } => {
+                ranked.retain(|c| volatility(&c.symbol, &c.market).is_some_and(|v| v > 0.0));
+                ranked
+                    .iter()
+                    .map(|c| {
+                        let vol = volatility(&c.symbol, &c.market).unwrap();
+                        ((c.symbol.as_str(), c.market.as_str()), vol)
+                    })
+                    .collect()
+            }
+            _ => HashMap::new(),
+        };
+
+        let limit = self
+            .top_n
+            .into_iter()
+            .chain(self.max_positions)
+            .min()
+            .unwrap_or(ranked.len());
+        ranked.truncate(limit);
+        let k = ranked.len() as f64;
+
+        let mut weights: Vec<((String, String), f64)> = ranked
+            .iter()
+            .map(|c| {
+                let weight = match self.sizing {
+                    PositionSizing::EqualWeight => 1.0 / k,
+                    PositionSizing::FixedFraction(fraction) => fraction,
+                    PositionSizing::VolatilityTarget { target, .. } => {
+                        target / k / vols[&(c.symbol.as_str(), c.market.as_str())]
+                    }
+                };
+                ((c.symbol.clone(), c.market.clone()), weight.max(0.0))
+            })
+            .collect();
+
+        let total: f64 = weights.iter().map(|(_, w)| w).sum();
+        if total > 1.0 {
+            for (_, weight) in weights.iter_mut() {
+                *weight /= total;
+            }
+        }
+
+        // 按得分顺序施加单只和行业上限，削减部分留作现金
+        let mut sector_used: HashMap<&str, f64> = HashMap::new();
+        let mut targets = TargetWeights::new();
+        for (key, mut weight) in weights {
+            if let Some(max_weight) = self.max_weight {
+                weight = weight.min(max_weight);
+            }
+            if let (Some(cap), Some(sector)) = (self.sector_cap, self.sectors.get(&key)) {
+                let used = sector_used.entry(sector.as_str()).or_insert(0.0);
+                weight = weight.min((cap - *used).max(0.0));
+                *used += weight;
+            }
+            if weight > 0.0 {
+                targets.insert(key, weight);
+            }
+        }
+        targets
+    }
+
+    /// 按因子得分构建每个调仓日的目标权重（因子表中出现的每个日期都调仓）
+    ///
+    /// `bars` 用于计算波动率，不使用波动率目标时可以为空。
+    pub fn from_factor(
+        &self,
+        table: &FactorTable,
+        factor: &str,
+        bars: &[TDXDayRecord],
+    ) -> Result<BTreeMap<NaiveDate, TargetWeights>> {
+        let mut by_date: BTreeMap<NaiveDate, Vec<Candidate>> = BTreeMap::new();
+        for row in table.factor(factor) {
+            by_date.entry(row.date).or_default().push(Candidate {
+                symbol: row.symbol.clone(),
+                market: row.market.clone(),
+                score: row.value,
+            });
+        }
+        if by_date.is_empty() {
+            return Err(anyhow::anyhow!("因子表中没有因子: {}", factor));
+        }
+
+        let vols = match self.sizing {
+            PositionSizing::VolatilityTarget { window, .. } => {
+                self.volatility_table(bars, window.max(2))
+            }
+            _ => HashMap::new(),
+        };
+        Ok(by_date
+            .into_iter()
+            .map(|(date, candidates)| {
+                let weights = self.construct(&candidates, |symbol, market| {
+                    vols.get(&(symbol.to_string(), market.to_string(), date))
+                        .copied()
+                });
+                (date, weights)
+            })
+            .collect())
+    }
+
+    /// 按交易信号构建目标权重
+    ///
+    /// 开仓信号把股票加入持有集合（得分为信号强度），平仓信号移出；
+    /// 每个有信号的日期按当前持有集合重新分配权重。
+    pub fn from_signals(
+        &self,
+        signals: &[Signal],
+        bars: &[TDXDayRecord],
+    ) -> Result<BTreeMap<NaiveDate, TargetWeights>> {
+        let mut by_date: BTreeMap<NaiveDate, Vec<&Signal>> = BTreeMap::new();
+        for signal in signals {
+            by_date.entry(signal.date).or_default().push(signal);
+        }
+        let vols = match self.sizing {
+            PositionSizing::VolatilityTarget { window, .. } => {
+                self.volatility_table(bars, window.max(2))
+            }
+            _ => HashMap::new(),
+        };
+
+        let mut held: BTreeMap<(String, String), f64> = BTreeMap::new();
+        let mut targets = BTreeMap::new();
+        for (date, signals) in by_date {
+            // 同一天先处理平仓再处理开仓
+            for signal in signals.iter().filter(|s| s.kind == SignalKind::Exit) {
+                held.remove(&(signal.symbol.clone(), signal.market.clone()));
+            }
+            for signal in signals.iter().filter(|s| s.kind == SignalKind::Entry) {
+                let score = held
+                    .entry((signal.symbol.clone(), signal.market.clone()))
+                    .or_insert(f64::MIN);
+                *score = score.max(signal.strength);
+            }
+            let candidates: Vec<Candidate> = held
+                .iter()
+                .map(|((symbol, market), score)| Candidate {
+                    symbol: symbol.clone(),
+                    market: market.clone(),
+                    score: *score,
+                })
+                .collect();
+            let weights = self.construct(&candidates, |symbol, market| {
+                vols.get(&(symbol.to_string(), market.to_string(), date))
+                    .copied()
+            });
+            targets.insert(date, weights);
+        }
+        Ok(targets)
+    }
+
+    /// （代码, 市场, 日期）-> 截至该日的年化波动率
+    fn volatility_table(
+        &self,
+        bars: &[TDXDayRecord],
+        window: usize,
+    ) -> HashMap<(String, String, NaiveDate), f64> {
+        let mut series: HashMap<(&str, &str), Vec<(NaiveDate, f64)>> = HashMap::new();
+        for bar in bars {
+            series
+                .entry((bar.symbol.as_str(), bar.market.as_str()))
+                .or_default()
+                .push((bar.date, bar.close));
+        }
+
+        let mut table = HashMap::new();
+        for ((symbol, market), mut closes) in series {
+            closes.sort_by_key(|(date, _)| *date);
+            let returns: Vec<f64> = closes
+                .windows(2)
+                .map(|pair| pair[1].1 / pair[0].1 - 1.0)
+                .collect();
+            for end in window..=returns.len() {
+                let slice = &returns[end - window..end];
+                let mean = slice.iter().sum::<f64>() / window as f64;
+                let variance =
+                    slice.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (window - 1) as f64;
+                let vol = (variance * self.periods_per_year).sqrt();
+                if vol.is_finite() {
+                    table.insert((symbol.to_string(), market.to_string(), closes[end].0), vol);
+                }
+            }
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::factors::FactorValue;
+
+    fn candidates(scores: &[(&str, f64)]) -> Vec<Candidate> {
+        scores
+            .iter()
+            .map(|(symbol, score)| Candidate {
+                symbol: symbol.to_string(),
+                market: "SH".to_string(),
+                score: *score,
+            })
+            .collect()
+    }
+
+    fn key(symbol: &str) -> (String, String) {
+        (symbol.to_string(), "SH".to_string())
+    }
+
+    #[test]
+    fn test_sizing_methods() {
+        let list = candidates(&[("A", 3.0), ("B", 2.0), ("C", 1.0), ("D", f64::NAN)]);
+
+        let equal = PortfolioConstructor::new()
+            .with_top_n(2)
+            .construct(&list, |_, _| None);
+        assert_eq!(equal.len(), 2);
+        assert_eq!(equal[&key("A")], 0.5);
+        assert!(!equal.contains_key(&key("C")));
+
+        let fixed = PortfolioConstructor::new()
+            .with_sizing(PositionSizing::FixedFraction(0.5))
+            .construct(&list, |_, _| None);
+        // 3只各0.5合计1.5，等比缩小到1
+        assert!((fixed[&key("A")] - 1.0 / 3.0).abs() < 1e-12);
+
+        let vol = PortfolioConstructor::new()
+            .with_sizing(PositionSizing::VolatilityTarget {
+                target: 0.2,
+                window: 20,
+            })
+            .construct(&list, |symbol, _| match symbol {
+                "A" => Some(0.4),
+                "B" => Some(0.2),
+                _ => None,
+            });
+        assert_eq!(vol.len(), 2);
+        assert!((vol[&key("A")] - 0.25).abs() < 1e-12);
+        assert!((vol[&key("B")] - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_constraints() {
+        let list = candidates(&[("A", 4.0), ("B", 3.0), ("C", 2.0), ("D", 1.0)]);
+        let sectors: HashMap<(String, String), String> =
+            [("A", "银行"), ("B", "银行"), ("C", "钢铁")]
+                .iter()
+                .map(|(s, i)| (key(s), i.to_string()))
+                .collect();
+
+        let targets = PortfolioConstructor::new()
+            .with_max_positions(3)
+            .with_sector_cap(0.4)
+            .with_sectors(sectors)
+            .construct(&list, |_, _| None);
+        // 每只1/3：银行板块A占1/3，B只能再占0.4-1/3，D超出最大持仓数
+        assert!((targets[&key("A")] - 1.0 / 3.0).abs() < 1e-12);
+        assert!((targets[&key("B")] - (0.4 - 1.0 / 3.0)).abs() < 1e-12);
+        assert!((targets[&key("C")] - 1.0 / 3.0).abs() < 1e-12);
+        assert!(!targets.contains_key(&key("D")));
+
+        let capped = PortfolioConstructor::new()
+            .with_max_weight(0.2)
+            .construct(&list[..2], |_, _| None);
+        assert_eq!(capped[&key("A")], 0.2);
+    }
+
+    #[test]
+    fn test_from_factor() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let row = |day, symbol: &str, value| FactorValue {
+            date: date(day),
+            symbol: symbol.to_string(),
+            market: "SH".to_string(),
+            factor: "momentum_20".to_string(),
+            value,
+        };
+        let table = FactorTable::new(vec![
+            row(2, "600000", 0.1),
+            row(2, "600036", 0.3),
+            row(3, "600000", 0.5),
+            row(3, "600036", 0.2),
+        ]);
+        let constructor = PortfolioConstructor::new().with_top_n(1);
+        let targets = constructor.from_factor(&table, "momentum_20", &[]).unwrap();
+        assert_eq!(targets.len(), 2);
+        assert!(targets[&date(2)].contains_key(&key("600036")));
+        assert!(targets[&date(3)].contains_key(&key("600000")));
+        assert!(constructor.from_factor(&table, "size", &[]).is_err());
+
+        // 信号：第2天开仓两只（强度高者入选），第3天平掉入选的一只
+        let signal = |day, symbol: &str, kind, strength| Signal {
+            symbol: symbol.to_string(),
+            market: "SH".to_string(),
+            date: date(day),
+            kind,
+            strength,
+            rule: "test".to_string(),
+        };
+        let signals = vec![
+            signal(2, "600000", SignalKind::Entry, 1.0),
+            signal(2, "600036", SignalKind::Entry, 2.0),
+            signal(3, "600036", SignalKind::Exit, 1.0),
+        ];
+        let targets = constructor.from_signals(&signals, &[]).unwrap();
+        assert_eq!(
+            targets[&date(2)].keys().collect::<Vec<_>>(),
+            vec![&key("600036")]
+        );
+        assert_eq!(
+            targets[&date(3)].keys().collect::<Vec<_>>(),
+            vec![&key("600000")]
+        );
+    }
+}
@@ -0,0 +1,443 @@
+//! 样本切分模块
+//!
+//! 在交易日序列上生成滚动前推（walk-forward）窗口和带清洗期的K折（purged K-fold）切分，
+//! 并按切分运行策略：每个切分在训练段上回测得到样本内绩效，在测试段上回测得到样本外绩效，
+//! 各测试段的日收益率首尾相接得到整体样本外净值。调参时只看样本外结果可以避免过拟合。
+//!
+//! 指标窗口跨越切分边界，训练段末尾的标签会用到测试段开头的数据，
+//! 因此测试段前后分别去掉 `purge`（清洗期）和 `embargo`（禁用期）个交易日不参与训练。
+
+use super::{BacktestReport, Backtester, Strategy};
+use crate::parsers::TDXDayRecord;
+use crate::processors::performance::{DateSeries, PerformanceAnalyzer, PerformanceMetrics};
+use crate::source::DateRange;
+use anyhow::Result;
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// 一次切分：训练段（可能不连续）与测试段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Split {
+    /// 切分序号
+    pub index: usize,
+    /// 训练段
+    pub train: Vec<DateRange>,
+    /// 测试段
+    pub test: DateRange,
+}
+
+impl Split {
+    /// 日期是否在训练段内
+    pub fn in_train(&self, date: NaiveDate) -> bool {
+        self.train.iter().any(|range| range.contains(date))
+    }
+
+    /// 日期是否在测试段内
+    pub fn in_test(&self, date: NaiveDate) -> bool {
+        self.test.contains(date)
+    }
+}
+
+/// 日线中出现的交易日（升序去重）
+pub fn trading_dates(bars: &[TDXDayRecord]) -> Vec<NaiveDate> {
+    bars.iter()
+        .map(|bar| bar.date)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn range(dates: &[NaiveDate], start: usize, end: usize) -> DateRange {
+    DateRange::new(dates[start], dates[end - 1])
+}
+
+/// 滚动前推切分
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalkForward {
+    /// 训练段交易日数
+    pub train: usize,
+    /// 测试段交易日数
+    pub test: usize,
+    /// 相邻切分的间隔交易日数（默认等于测试段长度，测试段首尾相接）
+    pub step: usize,
+    /// 训练段与测试段之间空出的交易日数
+    pub embargo: usize,
+    /// 训练段是否从序列开头起算（扩展窗口）
+    pub anchored: bool,
+}
+
+impl WalkForward {
+    /// 创建滚动窗口切分
+    pub fn new(train: usize, test: usize) -> Self {
+        Self {
+            train,
+            test,
+            step: test,
+            embargo: 0,
+            anchored: false,
+        }
+    }
+
+    /// 设置相邻切分的间隔
+    pub fn with_step(mut self, step: usize) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// 设置训练段与测试段之间的禁用期
+    pub fn with_embargo(mut self, embargo: usize) -> Self {
+        self.embargo = embargo;
+        self
+    }
+
+    /// 训练段从序列开头起算（扩展窗口）
+    pub fn anchored(mut self) -> Self {
+        self.anchored = true;
+        self
+    }
+
+    /// 在交易日序列上生成切分，最后不足一个测试段的日期不使用
+    pub fn splits(&self, dates: &[NaiveDate]) -> Result<Vec<Split>> {
+        if self.train == 0 || self.test == 0 || self.step == 0 {
+            return Err(anyhow::anyhow!("训练段、测试段和间隔都必须大于0"));
+        }
+        let mut splits = Vec::new();
+        let mut start = 0;
+        while start + self.train + self.embargo + self.test <= dates.len() {
+            let train_start = if self.anchored { 0 } else { start };
+            let test_start = start + self.train + self.embargo;
+            splits.push(Split {
+                index: splits.len(),
+                train: vec![range(dates, train_start, start + self.train)],
+                test: range(dates, test_start, test_start + self.test),
+            });
+            start += self.step;
+        }
+        if splits.is_empty() {
+            return Err(anyhow::anyhow!(
+                "交易日数{}不足一个切分（训练{}天 + 禁用{}天 + 测试{}天）",
+                dates.len(),
+                self.train,
+                self.embargo,
+                self.test
+            ));
+        }
+        Ok(splits)
+    }
+}
+
+/// 带清洗期和禁用期的K折切分
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgedKFold {
+    /// 折数
+    pub folds: usize,
+    /// 测试段之前不参与训练的交易日数
+    pub purge: usize,
+    /// 测试段之后不参与训练的交易日数
+    pub embargo: usize,
+}
+
+impl PurgedKFold {
+    /// 创建K折切分
+    pub fn new(folds: usize) -> Self {
+        Self {
+            folds,
+            purge: 0,
+            embargo: 0,
+        }
+    }
+
+    /// 设置清洗期
+    pub fn with_purge(mut self, purge: usize) -> Self {
+        self.purge = purge;
+        self
+    }
+
+    /// 设置禁用期
+    pub fn with_embargo(mut self, embargo: usize) -> Self {
+        self.embargo = embargo;
+        self
+    }
+
+    /// 在交易日序列上生成切分：测试段为连续的一折，训练段为其余日期去掉清洗期和禁用期
+    pub fn splits(&self, dates: &[NaiveDate]) -> Result<Vec<Split>> {
+        if self.folds < 2 || dates.len() < self.folds {
+            return Err(anyhow::anyhow!(
+                "K折切分至少需要2折且每折至少1个交易日: {}折, {}个交易日",
+                self.folds,
+                dates.len()
+            ));
+        }
+        let n = dates.len();
+        let bounds: Vec<usize> = (0..=self.folds).map(|i| i * n / self.folds).collect();
+        Ok(bounds
+            .windows(2)
+            .enumerate()
+            .map(|(index, fold)| {
+                let (test_start, test_end) = (fold[0], fold[1]);
+                let before = test_start.saturating_sub(self.purge);
+                let after = (test_end + self.embargo).min(n);
+                let mut train = Vec::new();
+                if before > 0 {
+                    train.push(range(dates, 0, before));
+                }
+                if after < n {
+                    train.push(range(dates, after, n));
+                }
+                Split {
+                    index,
+                    train,
+                    test: range(dates, test_start, test_end),
+                }
+            })
+            .collect())
+    }
+}
+
+/// 单个切分的评估结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitResult {
+    /// 切分
+    pub split: Split,
+    /// 样本内绩效（训练段各段的日收益率首尾相接）
+    pub in_sample: Option<PerformanceMetrics>,
+    /// 样本外绩效
+    pub out_of_sample: Option<PerformanceMetrics>,
+    /// 样本外回测报告
+    pub report: BacktestReport,
+}
+
+/// 全部切分的评估结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitEvaluation {
+    /// 各切分结果（按切分序号）
+    pub splits: Vec<SplitResult>,
+    /// 各测试段首尾相接的样本外净值（从1开始）
+    pub out_of_sample_equity: DateSeries,
+    /// 整体样本外绩效
+    pub out_of_sample: Option<PerformanceMetrics>,
+}
+
+impl SplitEvaluation {
+    /// 各切分样本内、样本外夏普比率的平均值（缺失的不计入）
+    pub fn mean_sharpe(&self) -> (Option<f64>, Option<f64>) {
+        let mean = |values: Vec<f64>| {
+            (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+        };
+        let sharpe =
+            |metrics: &Option<PerformanceMetrics>| metrics.as_ref().and_then(|m| m.sharpe_ratio);
+        (
+            mean(
+                self.splits
+                    .iter()
+                    .filter_map(|r| sharpe(&r.in_sample))
+                    .collect(),
+            ),
+            mean(
+                self.splits
+                    .iter()
+                    .filter_map(|r| sharpe(&r.out_of_sample))
+                    .collect(),
+            ),
+        )
+    }
+}
+
+/// 把多段权益曲线的日收益率首尾相接成一条从1开始的净值（段内第一天不计收益）
+pub fn chain_equity<'a>(curves: impl IntoIterator<Item = &'a DateSeries>) -> DateSeries {
+    let mut chained: DateSeries = Vec::new();
+    let mut value = 1.0;
+    for curve in curves {
+        if let Some(&(date, _)) = curve.first() {
+            if chained.is_empty() {
+                chained.push((date, value));
+            }
+        }
+        for (date, r) in PerformanceAnalyzer::returns(curve) {
+            value *= 1.0 + r;
+            chained.push((date, value));
+        }
+    }
+    chained
+}
+
+/// 在日期范围内回测：策略使用范围结束前的全部数据生成目标权重，只在范围内交易
+fn backtest_range(
+    backtester: &Backtester,
+    strategy: &dyn Strategy,
+    bars: &[TDXDayRecord],
+    range: &DateRange,
+) -> Result<BacktestReport> {
+    let history: Vec<TDXDayRecord> = bars
+        .iter()
+        .filter(|bar| range.end.is_none_or(|end| bar.date <= end))
+        .cloned()
+        .collect();
+    let mut targets = strategy.targets(&history)?;
+    targets.retain(|date, _| range.contains(*date));
+    let window: Vec<TDXDayRecord> = history
+        .into_iter()
+        .filter(|bar| range.contains(bar.date))
+        .collect();
+    backtester.run(&window, &targets)
+}
+
+/// 按切分并行运行策略，汇总样本内外绩效
+pub fn evaluate(
+    backtester: &Backtester,
+    strategy: &dyn Strategy,
+    bars: &[TDXDayRecord],
+    splits: &[Split],
+    analyzer: &PerformanceAnalyzer,
+) -> Result<SplitEvaluation> {
+    let results = splits
+        .par_iter()
+        .map(|split| {
+            let train = split
+                .train
+                .iter()
+                .map(|range| backtest_range(backtester, strategy, bars, range))
+                .collect::<Result<Vec<_>>>()?;
+            let in_sample = analyzer
+                .analyze(&chain_equity(train.iter().map(|r| &r.equity)))
+                .ok();
+            let report = backtest_range(backtester, strategy, bars, &split.test)?;
+            Ok(SplitResult {
+                split: split.clone(),
+                in_sample,
+                out_of_sample: report.metrics.clone(),
+                report,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let out_of_sample_equity = chain_equity(results.iter().map(|r| &r.report.equity));
+    let out_of_sample = analyzer.analyze(&out_of_sample_equity).ok();
+    Ok(SplitEvaluation {
+        splits: results,
+        out_of_sample_equity,
+        out_of_sample,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::TargetWeights;
+    use std::collections::BTreeMap;
+
+    fn dates(n: usize) -> Vec<NaiveDate> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (0..n as i64)
+            .map(|i| start + chrono::Duration::days(i))
+            .collect()
+    }
+
+    #[test]
+    fn test_walk_forward() {
+        let days = dates(10);
+        let splits = WalkForward::new(4, 2).splits(&days).unwrap();
+        assert_eq!(splits.len(), 3);
+        assert_eq!(splits[0].train, vec![DateRange::new(days[0], days[3])]);
+        assert_eq!(splits[0].test, DateRange::new(days[4], days[5]));
+        assert_eq!(splits[2].test, DateRange::new(days[8], days[9]));
+
+        let anchored = WalkForward::new(4, 2)
+            .with_embargo(1)
+            .anchored()
+            .splits(&days)
+            .unwrap();
+        assert_eq!(anchored.len(), 2);
+        assert_eq!(anchored[1].train, vec![DateRange::new(days[0], days[5])]);
+        assert_eq!(anchored[1].test, DateRange::new(days[7], days[8]));
+        assert!(!anchored[1].in_train(days[6]) && !anchored[1].in_test(days[6]));
+
+        assert!(WalkForward::new(8, 4).splits(&days).is_err());
+        assert!(WalkForward::new(4, 0).splits(&days).is_err());
+    }
+
+    #[test]
+    fn test_purged_kfold() {
+        let days = dates(10);
+        let splits = PurgedKFold::new(5)
+            .with_purge(1)
+            .with_embargo(1)
+            .splits(&days)
+            .unwrap();
+        assert_eq!(splits.len(), 5);
+        // 第2折测试第4~5天，训练段去掉第3天和第6天
+        assert_eq!(splits[1].test, DateRange::new(days[2], days[3]));
+        assert_eq!(
+            splits[1].train,
+            vec![
+                DateRange::new(days[0], days[0]),
+                DateRange::new(days[5], days[9])
+            ]
+        );
+        // 首尾两折只有一侧训练段
+        assert_eq!(splits[0].train, vec![DateRange::new(days[3], days[9])]);
+        assert_eq!(splits[4].train, vec![DateRange::new(days[0], days[6])]);
+        assert!(PurgedKFold::new(1).splits(&days).is_err());
+    }
+
+    /// 每天都满仓持有A
+    struct HoldA;
+
+    impl Strategy for HoldA {
+        fn targets(&self, bars: &[TDXDayRecord]) -> Result<BTreeMap<NaiveDate, TargetWeights>> {
+            Ok(trading_dates(bars)
+                .into_iter()
+                .map(|date| {
+                    (
+                        date,
+                        TargetWeights::from([(("A".to_string(), "SH".to_string()), 1.0)]),
+                    )
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // A每天上涨1%
+        let days = dates(12);
+        let bars: Vec<TDXDayRecord> = days
+            .iter()
+            .enumerate()
+            .map(|(i, &date)| {
+                let price = 10.0 * 1.01f64.powi(i as i32);
+                TDXDayRecord {
+                    date,
+                    symbol: "A".to_string(),
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume: 1000,
+                    amount: price * 1000.0,
+                    market: "SH".to_string(),
+                }
+            })
+            .collect();
+        let splits = WalkForward::new(4, 4).splits(&days).unwrap();
+        let evaluation = evaluate(
+            &Backtester::new(1_000_000.0),
+            &HoldA,
+            &bars,
+            &splits,
+            &PerformanceAnalyzer::new(),
+        )
+        .unwrap();
+
+        assert_eq!(evaluation.splits.len(), 2);
+        assert!(evaluation.splits[0].split.test.contains(days[4]));
+        // 每个测试段第二天开盘建仓，之后每天约1%收益，两段共约4%
+        let equity = &evaluation.out_of_sample_equity;
+        assert_eq!(equity.first().unwrap().1, 1.0);
+        assert!(equity.last().unwrap().1 > 1.03);
+        assert!(evaluation.out_of_sample.is_some());
+        assert!(evaluation.splits.iter().all(|r| r.in_sample.is_some()));
+    }
+}
@@ -0,0 +1,643 @@
+//! 回测交易明细导出
+//!
+//! 回测报告中的委托、成交（含费用明细）、委托事件和每日收盘持仓构成完整的交易明细，
+//! 可导出为CSV（带表头，可直接以 `CSVWithNames` 导入ClickHouse）、zstd压缩的Parquet
+//! （`storage` 特性），或直接写入ClickHouse（`clickhouse` 特性），供外部审计和可视化。
+//!
+//! 四张表的文件名/表名后缀分别为 `orders`、`fills`、`events`、`positions`。
+//! 明细较大时可用 `to_csv_parts`/`to_parquet_parts` 按行数或字节数分片导出。
+
+use super::{Fill, Order, OrderEvent, OrderEventKind, OrderStatus, Side};
+use crate::export::{ExportManifest, RotatingCsvWriter, RotationPolicy};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 收盘持仓记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionRecord {
+    /// 日期
+    pub date: NaiveDate,
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    #[serde(default)]
+    pub market: String,
+    /// 持仓股数
+    pub shares: u64,
+    /// 可卖股数
+    pub available: u64,
+    /// 收盘价（停牌时为最近收盘价）
+    pub close: f64,
+    /// 市值
+    pub market_value: f64,
+    /// 占账户权益的比例
+    pub weight: f64,
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Buy => "Buy",
+        Side::Sell => "Sell",
+    }
+}
+
+fn status_name(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Filled => "filled",
+        OrderStatus::Partial => "partial",
+        OrderStatus::Unfilled => "unfilled",
+    }
+}
+
+fn event_kind_name(kind: OrderEventKind) -> &'static str {
+    match kind {
+        OrderEventKind::Rejected => "rejected",
+        OrderEventKind::Queued => "queued",
+    }
+}
+
+/// 一列数据
+enum Column {
+    Date(Vec<NaiveDate>),
+    Str(Vec<String>),
+    UInt(Vec<u64>),
+    Float(Vec<f64>),
+}
+
+/// 一张表：表名后缀和各列
+struct Table {
+    name: &'static str,
+    columns: Vec<(&'static str, Column)>,
+}
+
+impl Column {
+    /// 第 `row` 行的文本形式
+    fn format(&self, row: usize) -> String {
+        match self {
+            Column::Date(v) => v[row].format("%Y-%m-%d").to_string(),
+            Column::Str(v) => v[row].clone(),
+            Column::UInt(v) => v[row].to_string(),
+            Column::Float(v) => v[row].to_string(),
+        }
+    }
+}
+
+impl Table {
+    fn rows(&self) -> usize {
+        self.columns.first().map_or(0, |(_, column)| match column {
+            Column::Date(v) => v.len(),
+            Column::Str(v) => v.len(),
+            Column::UInt(v) => v.len(),
+            Column::Float(v) => v.len(),
+        })
+    }
+
+    /// 转为Arrow批次
+    #[cfg(feature = "storage")]
+    fn arrow_batch(&self) -> Result<arrow_array::RecordBatch> {
+        use arrow_array::builder::{Date32Builder, Float64Builder, StringBuilder, UInt64Builder};
+        use arrow_array::{ArrayRef, RecordBatch};
+        use arrow_schema::{DataType, Field as ArrowField, Schema};
+        use std::sync::Arc;
+
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        let mut fields = Vec::with_capacity(self.columns.len());
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(self.columns.len());
+        for (name, column) in &self.columns {
+            let (data_type, array): (DataType, ArrayRef) = match column {
+                Column::Date(values) => {
+                    let mut builder = Date32Builder::with_capacity(values.len());
+                    for date in values {
+                        builder.append_value((*date - epoch).num_days() as i32);
+                    }
+                    (DataType::Date32, Arc::new(builder.finish()))
+                }
+                Column::Str(values) => {
+                    let mut builder = StringBuilder::new();
+                    for value in values {
+                        builder.append_value(value);
+                    }
+                    (DataType::Utf8, Arc::new(builder.finish()))
+                }
+                Column::UInt(values) => {
+                    let mut builder = UInt64Builder::with_capacity(values.len());
+                    builder.append_slice(values);
+                    (DataType::UInt64, Arc::new(builder.finish()))
+                }
+                Column::Float(values) => {
+                    let mut builder = Float64Builder::with_capacity(values.len());
+                    builder.append_slice(values);
+                    (DataType::Float64, Arc::new(builder.finish()))
+                }
+            };
+            fields.push(ArrowField::new(*name, data_type, false));
+            arrays.push(array);
+        }
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+    }
+}
+
+/// 回测交易明细
+#[derive(Debug, Clone, Copy)]
+pub struct TradeLog<'a> {
+    /// 委托记录
+    pub orders: &'a [Order],
+    /// 成交记录
+    pub fills: &'a [Fill],
+    /// 委托事件
+    pub events: &'a [OrderEvent],
+    /// 每日收盘持仓
+    pub positions: &'a [PositionRecord],
+}
+
+impl TradeLog<'_> {
+    /// 导出为目录下的四个CSV文件，返回写入的文件路径
+    pub fn to_csv<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("无法创建导出目录: {}", dir.display()))?;
+
+        let mut paths = Vec::with_capacity(4);
+        for table in self.tables() {
+            let path = dir.join(format!("{}.csv", table.name));
+            let mut writer = csv::Writer::from_path(&path)
+                .with_context(|| format!("无法创建CSV文件: {}", path.display()))?;
+            writer.write_record(table.columns.iter().map(|(name, _)| *name))?;
+            for row in 0..table.rows() {
+                writer.write_record(table.columns.iter().map(|(_, column)| column.format(row)))?;
+            }
+            writer.flush()?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// 按行数或字节数分片导出为CSV，每张表一个导出名称（空表不产生分片），返回各表的清单
+    pub fn to_csv_parts<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        policy: &RotationPolicy,
+    ) -> Result<Vec<ExportManifest>> {
+        let mut manifests = Vec::with_capacity(4);
+        for table in self.tables() {
+            let mut writer = RotatingCsvWriter::create(dir.as_ref(), table.name, policy)?
+                .with_header(table.columns.iter().map(|(name, _)| *name));
+            for row in 0..table.rows() {
+                writer.write_record(table.columns.iter().map(|(_, column)| column.format(row)))?;
+            }
+            manifests.push(writer.finish()?);
+        }
+        Ok(manifests)
+    }
+
+    /// 导出为目录下的四个zstd压缩的Parquet文件，返回写入的文件路径
+    #[cfg(feature = "storage")]
+    pub fn to_parquet<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>> {
+        use parquet::arrow::ArrowWriter;
+        use parquet::basic::{Compression, ZstdLevel};
+        use parquet::file::properties::WriterProperties;
+
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("无法创建导出目录: {}", dir.display()))?;
+
+        let mut paths = Vec::with_capacity(4);
+        for table in self.tables() {
+            let batch = table.arrow_batch()?;
+            let path = dir.join(format!("{}.parquet", table.name));
+            let file = std::fs::File::create(&path)
+                .with_context(|| format!("无法创建Parquet文件: {}", path.display()))?;
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::ZSTD(ZstdLevel::default()))
+                .build();
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+            writer.write(&batch)?;
+            writer.close()?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// 按行数或字节数分片导出为zstd压缩的Parquet，返回各表的清单
+    #[cfg(feature = "storage")]
+    pub fn to_parquet_parts<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        policy: &RotationPolicy,
+    ) -> Result<Vec<ExportManifest>> {
+        let mut manifests = Vec::with_capacity(4);
+        for table in self.tables() {
+            let batch = table.arrow_batch()?;
+            let mut writer = crate::export::RotatingParquetWriter::create(
+                dir.as_ref(),
+                table.name,
+                batch.schema(),
+                policy,
+            )?;
+            writer.write(&batch)?;
+            manifests.push(writer.finish()?);
+        }
+        Ok(manifests)
+    }
+
+    /// 写入ClickHouse：表名为 `{prefix}_orders` 等，每行带 `run` 列区分不同回测；返回写入行数
+    #[cfg(feature = "clickhouse")]
+    pub fn to_clickhouse(
+        &self,
+        source: &crate::source::ClickHouseSource,
+        prefix: &str,
+        run: &str,
+    ) -> Result<usize> {
+        for (create, inserts) in self.clickhouse_sql(prefix, run)? {
+            source.execute(create)?;
+            for insert in inserts {
+                source.execute(insert)?;
+            }
+        }
+        Ok(self.tables().iter().map(Table::rows).sum())
+    }
+
+    /// 各表的建表语句和INSERT语句（每条最多10000行）
+    #[cfg(any(feature = "clickhouse", test))]
+    fn clickhouse_sql(&self, prefix: &str, run: &str) -> Result<Vec<(String, Vec<String>)>> {
+        use std::fmt::Write;
+
+        let valid = |value: &str| {
+            !value.is_empty()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        };
+        if !valid(prefix) || prefix.contains('-') {
+            return Err(anyhow::anyhow!("无效的表名前缀: {}", prefix));
+        }
+        if !valid(run) {
+            return Err(anyhow::anyhow!("无效的回测标识: {}", run));
+        }
+        for (symbol, market) in self
+            .orders
+            .iter()
+            .map(|o| (&o.symbol, &o.market))
+            .chain(self.fills.iter().map(|f| (&f.symbol, &f.market)))
+            .chain(self.events.iter().map(|e| (&e.symbol, &e.market)))
+            .chain(self.positions.iter().map(|p| (&p.symbol, &p.market)))
+        {
+            if !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(anyhow::anyhow!("无效的股票代码: {}", symbol));
+            }
+            if !market.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(anyhow::anyhow!("无效的市场代码: {}", market));
+            }
+        }
+
+        let mut statements = Vec::with_capacity(4);
+        for table in self.tables() {
+            let name = format!("{}_{}", prefix, table.name);
+            let mut columns = String::from("run LowCardinality(String)");
+            for (column, values) in &table.columns {
+                let data_type = match values {
+                    Column::Date(_) => "Date",
+                    Column::Str(_) => "LowCardinality(String)",
+                    Column::UInt(_) => "UInt64",
+                    Column::Float(_) => "Float64",
+                };
+                write!(columns, ", {} {}", column, data_type)?;
+            }
+            let create = format!(
+                "CREATE TABLE IF NOT EXISTS {} ({}) ENGINE = MergeTree ORDER BY (run, date)",
+                name, columns
+            );
+
+            let names: Vec<&str> = table.columns.iter().map(|(column, _)| *column).collect();
+            let mut inserts = Vec::new();
+            let rows = table.rows();
+            for start in (0..rows).step_by(10_000) {
+                let mut sql = format!("INSERT INTO {} (run, {}) VALUES ", name, names.join(", "));
+                for row in start..rows.min(start + 10_000) {
+                    if row > start {
+                        sql.push(',');
+                    }
+                    write!(sql, "('{}'", run)?;
+                    for (_, values) in &table.columns {
+                        match values {
+                            Column::Date(_) | Column::Str(_) => {
+                                write!(sql, ",'{}'", values.format(row))?
+                            }
+                            Column::UInt(_) | Column::Float(_) => {
+                                write!(sql, ",{}", values.format(row))?
+                            }
+                        }
+                    }
+                    sql.push(')');
+                }
+                inserts.push(sql);
+            }
+            statements.push((create, inserts));
+        }
+        Ok(statements)
+    }
+
+    /// 按列组织的四张表
+    fn tables(&self) -> Vec<Table> {
+        let orders = Table {
+            name: "orders",
+            columns: vec![
+                (
+                    "date",
+                    Column::Date(self.orders.iter().map(|o| o.date).collect()),
+                ),
+                (
+                    "symbol",
+                    Column::Str(self.orders.iter().map(|o| o.symbol.clone()).collect()),
+                ),
+                (
+                    "market",
+                    Column::Str(self.orders.iter().map(|o| o.market.clone()).collect()),
+                ),
+                (
+                    "side",
+                    Column::Str(
+                        self.orders
+                            .iter()
+                            .map(|o| side_name(o.side).to_string())
+                            .collect(),
+                    ),
+                ),
+                (
+                    "requested",
+                    Column::UInt(self.orders.iter().map(|o| o.requested).collect()),
+                ),
+                (
+                    "filled",
+                    Column::UInt(self.orders.iter().map(|o| o.filled).collect()),
+                ),
+                (
+                    "price",
+                    Column::Float(self.orders.iter().map(|o| o.price).collect()),
+                ),
+                (
+                    "status",
+                    Column::Str(
+                        self.orders
+                            .iter()
+                            .map(|o| status_name(o.status).to_string())
+                            .collect(),
+                    ),
+                ),
+            ],
+        };
+        let fills = Table {
+            name: "fills",
+            columns: vec![
+                (
+                    "date",
+                    Column::Date(self.fills.iter().map(|f| f.date).collect()),
+                ),
+                (
+                    "symbol",
+                    Column::Str(self.fills.iter().map(|f| f.symbol.clone()).collect()),
+                ),
+                (
+                    "market",
+                    Column::Str(self.fills.iter().map(|f| f.market.clone()).collect()),
+                ),
+                (
+                    "side",
+                    Column::Str(
+                        self.fills
+                            .iter()
+                            .map(|f| side_name(f.side).to_string())
+                            .collect(),
+                    ),
+                ),
+                (
+                    "shares",
+                    Column::UInt(self.fills.iter().map(|f| f.shares).collect()),
+                ),
+                (
+                    "price",
+                    Column::Float(self.fills.iter().map(|f| f.price).collect()),
+                ),
+                (
+                    "amount",
+                    Column::Float(self.fills.iter().map(|f| f.amount).collect()),
+                ),
+                (
+                    "commission",
+                    Column::Float(self.fills.iter().map(|f| f.fees.commission).collect()),
+                ),
+                (
+                    "stamp_duty",
+                    Column::Float(self.fills.iter().map(|f| f.fees.stamp_duty).collect()),
+                ),
+                (
+                    "transfer_fee",
+                    Column::Float(self.fills.iter().map(|f| f.fees.transfer_fee).collect()),
+                ),
+            ],
+        };
+        let events = Table {
+            name: "events",
+            columns: vec![
+                (
+                    "date",
+                    Column::Date(self.events.iter().map(|e| e.date).collect()),
+                ),
+                (
+                    "symbol",
+                    Column::Str(self.events.iter().map(|e| e.symbol.clone()).collect()),
+                ),
+                (
+                    "market",
+                    Column::Str(self.events.iter().map(|e| e.market.clone()).collect()),
+                ),
+                (
+                    "kind",
+                    Column::Str(
+                        self.events
+                            .iter()
+                            .map(|e| event_kind_name(e.kind).to_string())
+                            .collect(),
+                    ),
+                ),
+                (
+                    "requested",
+                    Column::UInt(self.events.iter().map(|e| e.requested).collect()),
+                ),
+                (
+                    "available",
+                    Column::UInt(self.events.iter().map(|e| e.available).collect()),
+                ),
+            ],
+        };
+        let positions = Table {
+            name: "positions",
+            columns: vec![
+                (
+                    "date",
+                    Column::Date(self.positions.iter().map(|p| p.date).collect()),
+                ),
+                (
+                    "symbol",
+                    Column::Str(self.positions.iter().map(|p| p.symbol.clone()).collect()),
+                ),
+                (
+                    "market",
+                    Column::Str(self.positions.iter().map(|p| p.market.clone()).collect()),
+                ),
+                (
+                    "shares",
+                    Column::UInt(self.positions.iter().map(|p| p.shares).collect()),
+                ),
+                (
+                    "available",
+                    Column::UInt(self.positions.iter().map(|p| p.available).collect()),
+                ),
+                (
+                    "close",
+                    Column::Float(self.positions.iter().map(|p| p.close).collect()),
+                ),
+                (
+                    "market_value",
+                    Column::Float(self.positions.iter().map(|p| p.market_value).collect()),
+                ),
+                (
+                    "weight",
+                    Column::Float(self.positions.iter().map(|p| p.weight).collect()),
+                ),
+            ],
+        };
+        vec![orders, fills, events, positions]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{Backtester, CostModel, TargetWeights};
+    use crate::parsers::TDXDayRecord;
+    use std::collections::BTreeMap;
+
+    fn report() -> crate::backtest::BacktestReport {
+        let bars: Vec<TDXDayRecord> = (2..6)
+            .map(|day| TDXDayRecord {
+                date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+                symbol: "600000".to_string(),
+                open: 10.0,
+                high: 10.0,
+                low: 10.0,
+                close: 10.0,
+                volume: 100_000,
+                amount: 1_000_000.0,
+                market: "SH".to_string(),
+            })
+            .collect();
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+        let targets = BTreeMap::from([
+            (
+                date(2),
+                TargetWeights::from([(("600000".to_string(), "SH".to_string()), 0.5)]),
+            ),
+            (date(4), TargetWeights::new()),
+        ]);
+        Backtester::new(100_000.0)
+            .with_costs(CostModel::china_a_share())
+            .run(&bars, &targets)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_positions_and_csv() {
+        let report = report();
+        // 第3天买入、第5天卖出，第3、4天收盘有持仓
+        assert_eq!(report.orders.len(), 2);
+        assert_eq!(report.positions.len(), 2);
+        let position = &report.positions[0];
+        assert_eq!(position.shares, 5000);
+        assert_eq!(position.available, 0);
+        assert_eq!(report.positions[1].available, 5000);
+        assert!((position.weight - position.market_value / report.equity[1].1).abs() < 1e-12);
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths = report.trade_log().to_csv(dir.path()).unwrap();
+        assert_eq!(paths.len(), 4);
+        let fills = std::fs::read_to_string(dir.path().join("fills.csv")).unwrap();
+        let mut lines = fills.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "date,symbol,market,side,shares,price,amount,commission,stamp_duty,transfer_fee"
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("2024-01-03,600000,SH,Buy,5000,10,50000,"));
+        let orders = std::fs::read_to_string(dir.path().join("orders.csv")).unwrap();
+        assert_eq!(
+            orders.lines().collect::<Vec<_>>(),
+            vec![
+                "date,symbol,market,side,requested,filled,price,status",
+                "2024-01-03,600000,SH,Buy,5000,5000,10,filled",
+                "2024-01-05,600000,SH,Sell,5000,5000,10,filled",
+            ]
+        );
+
+        let policy = RotationPolicy::new().with_max_rows(1);
+        let manifests = report
+            .trade_log()
+            .to_csv_parts(dir.path(), &policy)
+            .unwrap();
+        assert_eq!(manifests[0].name, "orders");
+        assert_eq!(manifests[0].parts.len(), 2);
+        assert!(manifests[2].parts.is_empty());
+        let second = std::fs::read_to_string(dir.path().join("orders-00001.csv")).unwrap();
+        assert!(
+            second.starts_with("date,symbol,market,side,requested,filled,price,status\n2024-01-05")
+        );
+    }
+
+    #[test]
+    fn test_clickhouse_sql() {
+        let report = report();
+        let statements = report.trade_log().clickhouse_sql("bt", "run-1").unwrap();
+        assert_eq!(statements.len(), 4);
+        let (create, inserts) = &statements[1];
+        assert!(create.starts_with(
+            "CREATE TABLE IF NOT EXISTS bt_fills (run LowCardinality(String), date Date"
+        ));
+        assert_eq!(inserts.len(), 1);
+        assert!(inserts[0].starts_with(
+            "INSERT INTO bt_fills (run, date, symbol, market, side, shares, price, amount, commission, stamp_duty, transfer_fee) VALUES ('run-1','2024-01-03','600000','SH','Buy',5000,"
+        ));
+        // 空表不生成INSERT
+        assert!(statements[2].1.is_empty());
+
+        assert!(report.trade_log().clickhouse_sql("bt;drop", "x").is_err());
+        assert!(report.trade_log().clickhouse_sql("bt", "x'y").is_err());
+    }
+
+    #[cfg(feature = "storage")]
+    #[test]
+    fn test_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let report = report();
+        let dir = tempfile::tempdir().unwrap();
+        let paths = report.trade_log().to_parquet(dir.path()).unwrap();
+        assert_eq!(paths.len(), 4);
+        let reader = SerializedFileReader::new(
+            std::fs::File::open(dir.path().join("positions.parquet")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+        let policy = RotationPolicy::new().with_max_rows(1);
+        let manifests = report
+            .trade_log()
+            .to_parquet_parts(dir.path(), &policy)
+            .unwrap();
+        assert_eq!(manifests[3].row_count, 2);
+        assert_eq!(manifests[3].parts[1].file, "positions-00001.parquet");
+    }
+}
@@ -0,0 +1,39 @@
+//! K线图命令行工具
+//!
+//! 用法：`kline <day文件> <输出.png|输出.svg> [起始日期] [结束日期]`，日期格式 `YYYY-MM-DD`，含端点。
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use pulse_trader_rust::parsers::TDXDayParser;
+use pulse_trader_rust::source::DateRange;
+use pulse_trader_rust::viz::{select, KlineChart};
+
+fn parse_date(text: Option<&String>) -> Result<Option<NaiveDate>> {
+    text.map(|text| {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .with_context(|| format!("日期格式错误: {}", text))
+    })
+    .transpose()
+}
+
+fn main() -> Result<()> {
+    pulse_trader_rust::init_logger();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 || args.len() > 4 {
+        anyhow::bail!("用法: kline <day文件> <输出.png|输出.svg> [起始日期] [结束日期]");
+    }
+    let range = DateRange {
+        start: parse_date(args.get(2))?,
+        end: parse_date(args.get(3))?,
+    };
+
+    let records = TDXDayParser::new(".").parse_file(&args[0])?;
+    let symbol = records
+        .first()
+        .map(|r| r.symbol.clone())
+        .ok_or_else(|| anyhow::anyhow!("文件中没有日线: {}", args[0]))?;
+    let bars = select(&records, &symbol, &range);
+    KlineChart::new().render(&bars, &args[1])?;
+    println!("{} 根K线已绘制到 {}", bars.len(), args[1]);
+    Ok(())
+}
@@ -0,0 +1,327 @@
+//! 结果缓存模块（`cache` 特性）
+//!
+//! 以源文件内容的xxh3哈希（连同文件路径、库版本和计算配置标签）为键，把解析并计算好指标的
+//! `EnhancedDayRecord` 以Parquet快照的形式存入本地缓存目录。源文件未变化时直接读取缓存，
+//! 只重新解析和计算内容发生变化的股票。
+//!
+//! 缓存键不包含指标计算器的参数，窗口、基准指数等配置变化时需通过 `with_tag` 区分。
+
+use crate::parsers::TDXDayParser;
+use crate::processors::calculator::{EnhancedDayRecord, IndicatorCalculator};
+use crate::storage::{Snapshot, SnapshotWriter};
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use xxhash_rust::xxh3::Xxh3;
+
+/// 一次目录处理的结果
+#[derive(Debug, Clone, Default)]
+pub struct CacheRun {
+    /// 全部记录（按日期、股票代码排序）
+    pub records: Vec<EnhancedDayRecord>,
+    /// 命中缓存的文件数
+    pub hits: usize,
+    /// 重新计算的文件数
+    pub misses: usize,
+    /// 本次使用的缓存键
+    pub keys: HashSet<u64>,
+}
+
+/// 指标结果缓存
+#[derive(Debug, Clone)]
+pub struct IndicatorCache {
+    /// 缓存目录
+    dir: PathBuf,
+    /// 计算配置标签
+    tag: String,
+    /// 快照写入器
+    writer: SnapshotWriter,
+}
+
+impl IndicatorCache {
+    /// 打开（必要时创建）缓存目录
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).with_context(|| format!("无法创建缓存目录: {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            tag: String::new(),
+            writer: SnapshotWriter::new().with_compression_level(1),
+        })
+    }
+
+    /// 设置计算配置标签，标签不同的结果互不命中
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    /// 计算源文件的缓存键
+    pub fn key<P: AsRef<Path>>(&self, path: P) -> Result<u64> {
+        let path = path.as_ref();
+        let content =
+            fs::read(path).with_context(|| format!("无法读取文件: {}", path.display()))?;
+        // 市场由路径中的 sh/sz 目录决定，路径参与哈希，内容相同的两个文件不会共用条目
+        let location = path.to_string_lossy();
+
+        let mut hasher = Xxh3::new();
+        for part in [crate::VERSION, &self.tag, &location] {
+            hasher.update(part.as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.update(&content);
+        Ok(hasher.digest())
+    }
+
+    /// 缓存条目目录
+    fn entry_dir(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}", key))
+    }
+
+    /// 读取缓存，未命中时返回None
+    pub fn get(&self, key: u64) -> Result<Option<Vec<EnhancedDayRecord>>> {
+        let entry = self.entry_dir(key);
+        if !entry.exists() {
+            return Ok(None);
+        }
+        let snapshot = Snapshot::load(&entry)?;
+        Ok(snapshot.enhanced_records())
+    }
+
+    /// 写入缓存（先写临时目录再改名，中途失败不会留下残缺条目）
+    pub fn put(&self, key: u64, records: &[EnhancedDayRecord]) -> Result<()> {
+        let entry = self.entry_dir(key);
+        let staging = self.dir.join(format!(
+            "{:016x}.tmp-{}-{:?}",
+            key,
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        self.writer.write_enhanced(&staging, records)?;
+
+        if entry.exists() {
+            fs::remove_dir_all(&entry)
+                .with_context(|| format!("无法替换缓存条目: {}", entry.display()))?;
+        }
+        fs::rename(&staging, &entry)
+            .with_context(|| format!("无法写入缓存条目: {}", entry.display()))?;
+        Ok(())
+    }
+
+    /// 读取缓存或计算并写入，返回（记录, 是否命中）
+    pub fn get_or_compute<P, F>(
+        &self,
+        path: P,
+        compute: F,
+    ) -> Result<(Vec<EnhancedDayRecord>, bool)>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&Path) -> Result<Vec<EnhancedDayRecord>>,
+    {
+        let (_, records, hit) = self.lookup(path.as_ref(), compute)?;
+        Ok((records, hit))
+    }
+
+    /// 读取缓存或计算并写入，返回（缓存键, 记录, 是否命中）
+    fn lookup<F>(&self, path: &Path, compute: F) -> Result<(u64, Vec<EnhancedDayRecord>, bool)>
+    where
+        F: FnOnce(&Path) -> Result<Vec<EnhancedDayRecord>>,
+    {
+        let key = self.key(path)?;
+        match self.get(key) {
+            Ok(Some(records)) => return Ok((key, records, true)),
+            Ok(None) => {}
+            Err(e) => warn!("缓存条目损坏，重新计算 {}: {}", path.display(), e),
+        }
+
+        let records = compute(path)?;
+        self.put(key, &records)?;
+        Ok((key, records, false))
+    }
+
+    /// 解析目录下的所有day文件并计算指标，未变化的文件直接使用缓存
+    ///
+    /// 各文件（即各股票）并行处理，解析失败的文件记录警告后跳过，与 `parse_directory` 一致。
+    pub fn process_directory<P: AsRef<Path>>(
+        &self,
+        parser: &TDXDayParser,
+        calculator: &IndicatorCalculator,
+        dir: P,
+    ) -> Result<CacheRun> {
+        let dir = dir.as_ref();
+        if !dir.exists() {
+            return Err(anyhow::anyhow!("目录不存在: {}", dir.display()));
+        }
+
+        let files: Vec<PathBuf> = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|path| path.extension().and_then(|s| s.to_str()) == Some("day"))
+            .collect();
+
+        let results: Vec<(u64, Vec<EnhancedDayRecord>, bool)> = files
+            .par_iter()
+            .filter_map(|path| {
+                let result = self.lookup(path, |path| {
+                    calculator.calculate_all_indicators(&parser.parse_file(path)?)
+                });
+                match result {
+                    Ok(result) => Some(result),
+                    Err(e) => {
+                        warn!("处理文件失败 {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        let mut run = CacheRun::default();
+        for (key, mut records, hit) in results {
+            if hit {
+                run.hits += 1;
+            } else {
+                run.misses += 1;
+            }
+            run.keys.insert(key);
+            run.records.append(&mut records);
+        }
+        run.records.sort_by(|a, b| {
+            let (a, b) = (&a.base_record, &b.base_record);
+            a.date
+                .cmp(&b.date)
+                .then(a.symbol.cmp(&b.symbol))
+                .then(a.market.cmp(&b.market))
+        });
+        debug!(
+            "缓存处理完成: 命中{}个文件，重新计算{}个文件",
+            run.hits, run.misses
+        );
+
+        Ok(run)
+    }
+
+    /// 删除不在 `keys` 中的缓存条目，返回删除数
+    pub fn retain(&self, keys: &HashSet<u64>) -> Result<usize> {
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+            let keep = name
+                .as_deref()
+                .and_then(|name| u64::from_str_radix(name, 16).ok())
+                .is_some_and(|key| keys.contains(&key));
+            if path.is_dir() && !keep {
+                fs::remove_dir_all(&path)
+                    .with_context(|| format!("无法删除缓存条目: {}", path.display()))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 构造通达信day文件内容
+    fn day_file(closes: &[u32]) -> Vec<u8> {
+        let mut content = Vec::new();
+        for (i, &close) in closes.iter().enumerate() {
+            for value in [20240102 + i as u32, close, close + 10, close - 10, close] {
+                content.extend_from_slice(&value.to_le_bytes());
+            }
+            content.extend_from_slice(&(close as f32 * 100.0).to_le_bytes());
+            content.extend_from_slice(&100u32.to_le_bytes());
+            content.extend_from_slice(&0u32.to_le_bytes());
+        }
+        content
+    }
+
+    fn create_data_dir() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let sh = temp_dir.path().join("sh").join("lday");
+        fs::create_dir_all(&sh).unwrap();
+        fs::write(sh.join("600000.day"), day_file(&[1000, 1010, 1020])).unwrap();
+        fs::write(sh.join("600001.day"), day_file(&[2000, 1990])).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_key_depends_on_content_and_tag() {
+        let data = create_data_dir();
+        let cache_dir = TempDir::new().unwrap();
+        let cache = IndicatorCache::open(cache_dir.path()).unwrap();
+        let path = data.path().join("sh/lday/600000.day");
+
+        let key = cache.key(&path).unwrap();
+        assert_eq!(key, cache.key(&path).unwrap());
+        assert_ne!(key, cache.clone().with_tag("ma60").key(&path).unwrap());
+
+        fs::write(&path, day_file(&[1000, 1010, 1030])).unwrap();
+        assert_ne!(key, cache.key(&path).unwrap());
+    }
+
+    #[test]
+    fn test_process_directory_recomputes_changed_files() {
+        let data = create_data_dir();
+        let cache_dir = TempDir::new().unwrap();
+        let cache = IndicatorCache::open(cache_dir.path()).unwrap();
+        let parser = TDXDayParser::new(data.path());
+        let calculator = IndicatorCalculator::new();
+
+        let first = cache
+            .process_directory(&parser, &calculator, data.path())
+            .unwrap();
+        assert_eq!((first.hits, first.misses), (0, 2));
+        assert_eq!(first.records.len(), 5);
+
+        let second = cache
+            .process_directory(&parser, &calculator, data.path())
+            .unwrap();
+        assert_eq!((second.hits, second.misses), (2, 0));
+        assert_eq!(second.records.len(), 5);
+        assert_eq!(
+            second.records[2].indicators.change_percent,
+            first.records[2].indicators.change_percent
+        );
+
+        let path = data.path().join("sh/lday/600001.day");
+        fs::write(path, day_file(&[2000, 1990, 1980])).unwrap();
+        let third = cache
+            .process_directory(&parser, &calculator, data.path())
+            .unwrap();
+        assert_eq!((third.hits, third.misses), (1, 1));
+        assert_eq!(third.records.len(), 6);
+    }
+
+    #[test]
+    fn test_retain_removes_stale_entries() {
+        let data = create_data_dir();
+        let cache_dir = TempDir::new().unwrap();
+        let cache = IndicatorCache::open(cache_dir.path()).unwrap();
+        let parser = TDXDayParser::new(data.path());
+        let calculator = IndicatorCalculator::new();
+
+        cache
+            .process_directory(&parser, &calculator, data.path())
+            .unwrap();
+        fs::write(
+            data.path().join("sh/lday/600000.day"),
+            day_file(&[1000, 1005]),
+        )
+        .unwrap();
+        let run = cache
+            .process_directory(&parser, &calculator, data.path())
+            .unwrap();
+
+        assert_eq!(cache.retain(&run.keys).unwrap(), 1);
+        assert_eq!(fs::read_dir(cache_dir.path()).unwrap().count(), 2);
+    }
+}
@@ -0,0 +1,118 @@
+//! 交易日历模块
+//!
+//! A股交易日为周一至周五，扣除法定节假日。节假日可显式登记，
+//! 也可以用某个全市场交易的品种（如上证指数）的历史日期直接构建日历，
+//! 该日期范围内以实际交易日为准，范围外回退到“工作日减节假日”规则。
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// 交易日历
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradingCalendar {
+    /// 节假日（工作日休市）
+    holidays: BTreeSet<NaiveDate>,
+    /// 已知交易日（范围内以此为准）
+    known_days: BTreeSet<NaiveDate>,
+}
+
+impl TradingCalendar {
+    /// 创建仅排除周末的日历
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从已知交易日构建日历（如上证指数的全部日期）
+    pub fn from_trading_days<I: IntoIterator<Item = NaiveDate>>(days: I) -> Self {
+        Self {
+            holidays: BTreeSet::new(),
+            known_days: days.into_iter().collect(),
+        }
+    }
+
+    /// 设置节假日
+    pub fn with_holidays<I: IntoIterator<Item = NaiveDate>>(mut self, holidays: I) -> Self {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    /// 添加节假日
+    pub fn add_holiday(&mut self, date: NaiveDate) -> &mut Self {
+        self.holidays.insert(date);
+        self
+    }
+
+    /// 是否为交易日
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        if let (Some(first), Some(last)) = (self.known_days.first(), self.known_days.last()) {
+            if (*first..=*last).contains(&date) {
+                return self.known_days.contains(&date);
+            }
+        }
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// 区间内（含两端）的全部交易日
+    pub fn trading_days(&self, start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+        start
+            .iter_days()
+            .take_while(|date| *date <= end)
+            .filter(|date| self.is_trading_day(*date))
+            .collect()
+    }
+
+    /// 下一个交易日（不含当日）
+    pub fn next_trading_day(&self, date: NaiveDate) -> Option<NaiveDate> {
+        date.iter_days()
+            .skip(1)
+            .take(366)
+            .find(|day| self.is_trading_day(*day))
+    }
+
+    /// 上一个交易日（不含当日）
+    pub fn previous_trading_day(&self, date: NaiveDate) -> Option<NaiveDate> {
+        date.pred_opt()?
+            .iter_days()
+            .rev()
+            .take(366)
+            .find(|day| self.is_trading_day(*day))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_weekends_and_holidays() {
+        let calendar = TradingCalendar::new().with_holidays([date(1, 1)]);
+        // 2024-01-01 元旦（周一），01-06/07 周末
+        assert!(!calendar.is_trading_day(date(1, 1)));
+        assert!(calendar.is_trading_day(date(1, 2)));
+        assert!(!calendar.is_trading_day(date(1, 6)));
+        assert_eq!(calendar.trading_days(date(1, 1), date(1, 8)).len(), 5);
+    }
+
+    #[test]
+    fn test_known_days_take_precedence() {
+        let calendar = TradingCalendar::from_trading_days([date(2, 5), date(2, 7), date(2, 8)]);
+        // 范围内缺失的工作日视为休市
+        assert!(!calendar.is_trading_day(date(2, 6)));
+        assert!(calendar.is_trading_day(date(2, 7)));
+        // 范围外回退到工作日规则
+        assert!(calendar.is_trading_day(date(2, 9)));
+    }
+
+    #[test]
+    fn test_next_and_previous() {
+        let mut calendar = TradingCalendar::new();
+        calendar.add_holiday(date(1, 8));
+        assert_eq!(calendar.next_trading_day(date(1, 5)), Some(date(1, 9)));
+        assert_eq!(calendar.previous_trading_day(date(1, 9)), Some(date(1, 5)));
+    }
+}
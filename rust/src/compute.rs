@@ -0,0 +1,156 @@
+//! 自定义滚动计算接口
+//!
+//! 无需修改 `IndicatorCalculator` 即可用闭包计算任意滚动统计量：`rolling_apply` 作用于单条序列，
+//! `rolling_apply_by_symbol` 在列式数据上按股票分组、组间并行计算，结果与输入行一一对应。
+//! 股票的行在列中连续且按时间排序时（如经过 `sort_by_symbol_and_date`）直接借用列切片，
+//! 否则按行索引收集一次。
+
+use crate::parsers::Bar;
+use crate::processors::columnar::{ColumnRef, ColumnarFrame};
+use crate::processors::field::Field;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::borrow::Cow;
+
+/// 按窗口滚动调用 `f`，输出与输入等长
+///
+/// 窗口以第 `window-1` 个位置为首个终点，之后每隔 `step` 个位置计算一次（`step` 为0时按1处理），
+/// 窗口未满和被跳过的位置为NaN。`f` 收到的切片按时间顺序排列，长度恒为 `window`。
+pub fn rolling_apply<F>(series: &[f64], window: usize, step: usize, f: F) -> Vec<f64>
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let mut output = vec![f64::NAN; series.len()];
+    if window == 0 || series.len() < window {
+        return output;
+    }
+
+    for end in (window..=series.len()).step_by(step.max(1)) {
+        output[end - 1] = f(&series[end - window..end]);
+    }
+    output
+}
+
+/// 按股票分组滚动计算某一列，组间并行，返回值与 `frame` 的行一一对应
+///
+/// 每只股票按时间排序后独立计算，窗口不跨股票。指标列需先通过 `add_indicator_columns` 计算。
+pub fn rolling_apply_by_symbol<F>(
+    frame: &ColumnarFrame,
+    field: &Field,
+    window: usize,
+    step: usize,
+    f: F,
+) -> Result<Vec<f64>>
+where
+    F: Fn(&[f64]) -> f64 + Sync,
+{
+    let column = frame.column(field)?;
+    let results: Vec<(Vec<usize>, Vec<f64>)> = frame
+        .symbol_groups()
+        .into_par_iter()
+        .map(|(_, rows)| {
+            let series = group_series(column, &rows);
+            let values = rolling_apply(&series, window, step, &f);
+            (rows, values)
+        })
+        .collect();
+
+    let mut output = vec![f64::NAN; frame.len()];
+    for (rows, values) in results {
+        for (row, value) in rows.into_iter().zip(values) {
+            output[row] = value;
+        }
+    }
+    Ok(output)
+}
+
+/// 对任意K线记录按股票分组滚动计算，返回值与 `data` 的顺序一致
+pub fn rolling_apply_records<B, F>(
+    data: &[B],
+    field: &Field,
+    window: usize,
+    step: usize,
+    f: F,
+) -> Result<Vec<f64>>
+where
+    B: Bar,
+    F: Fn(&[f64]) -> f64 + Sync,
+{
+    rolling_apply_by_symbol(&ColumnarFrame::from_records(data), field, window, step, f)
+}
+
+/// 一只股票按时间排序的列值，行连续时借用原切片
+fn group_series<'a>(column: ColumnRef<'a>, rows: &[usize]) -> Cow<'a, [f64]> {
+    let contiguous = rows.windows(2).all(|pair| pair[1] == pair[0] + 1);
+    match (column, rows.first()) {
+        (ColumnRef::Float(values), Some(&start)) if contiguous => {
+            Cow::Borrowed(&values[start..start + rows.len()])
+        }
+        _ => Cow::Owned(column.gather(rows)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use chrono::NaiveDate;
+
+    fn create_test_record(symbol: &str, day: i64, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(day),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000 * day as u64,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rolling_apply_step() {
+        let series = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let max = |window: &[f64]| window.iter().cloned().fold(f64::MIN, f64::max);
+
+        let every = rolling_apply(&series, 3, 1, max);
+        assert!(every[1].is_nan());
+        assert_eq!(every[2..], [3.0, 4.0, 5.0, 6.0]);
+
+        let stepped = rolling_apply(&series, 3, 2, max);
+        assert_eq!(stepped[2], 3.0);
+        assert!(stepped[3].is_nan());
+        assert_eq!(stepped[4], 5.0);
+        assert!(rolling_apply(&series, 7, 1, max).iter().all(|v| v.is_nan()));
+        assert!(rolling_apply(&series, 0, 1, max).iter().all(|v| v.is_nan()));
+    }
+
+    #[test]
+    fn test_rolling_apply_by_symbol() {
+        // 两只股票交错、日期倒序
+        let mut data = Vec::new();
+        for day in (0..4).rev() {
+            data.push(create_test_record("600000", day, 10.0 + day as f64));
+            data.push(create_test_record("600036", day, 20.0 * (day + 1) as f64));
+        }
+        let sum = |window: &[f64]| window.iter().sum::<f64>();
+
+        let result = rolling_apply_records(&data, &Field::Close, 2, 1, sum).unwrap();
+        // data[0]为600000第3天，data[1]为600036第3天
+        assert_eq!(result[0], 12.0 + 13.0);
+        assert_eq!(result[1], 60.0 + 80.0);
+        assert!(result[6].is_nan() && result[7].is_nan());
+
+        // 整数列和连续存放的列
+        let mut frame = ColumnarFrame::from_records(&data);
+        frame.sort_by_symbol_and_date();
+        let volumes = rolling_apply_by_symbol(&frame, &Field::Volume, 4, 1, sum).unwrap();
+        assert_eq!(volumes.iter().filter(|v| !v.is_nan()).count(), 2);
+        assert_eq!(volumes[3], 6000.0);
+        let closes = rolling_apply_by_symbol(&frame, &Field::Close, 4, 1, sum).unwrap();
+        assert_eq!(closes[3], 46.0);
+        assert!(rolling_apply_by_symbol(&frame, &"ma5".parse().unwrap(), 2, 1, sum).is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! 分片导出模块
+//!
+//! 导出数千万条增强记录时，单个CSV/Parquet文件难以传输和加载。`RotatingCsvWriter` 与
+//! `RotatingParquetWriter`（`storage` 特性）按 `RotationPolicy` 的行数或字节数上限切换到
+//! 新文件，文件名由模式生成（`{name}`、`{seq}`、`{date}` 占位符），导出结束时在目录中写入
+//! `{name}.manifest.json`，列出各分片的文件名、行数和字节数。
+//!
+//! 因子表、成交记录、相关性矩阵、季节性统计的 `*_parts` 导出方法和分区数据集写入器
+//! 都基于这两个写入器。
+
+#[cfg(feature = "storage")]
+pub mod parquet;
+
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::processors::field::INDICATOR_NAMES;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "storage")]
+pub use self::parquet::{export_enhanced_parquet, RotatingParquetWriter};
+
+/// 分片清单文件名后缀
+pub const EXPORT_MANIFEST_SUFFIX: &str = ".manifest.json";
+
+/// 分片切换策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationPolicy {
+    /// 单个文件的最大行数（不含表头）
+    pub max_rows: Option<usize>,
+    /// 单个文件的最大字节数，达到后下一行写入新文件
+    pub max_bytes: Option<u64>,
+    /// 文件名模式（不含扩展名）
+    pub pattern: String,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RotationPolicy {
+    /// 创建策略：不限行数和字节数，文件名模式为 `{name}-{seq}`
+    pub fn new() -> Self {
+        Self {
+            max_rows: None,
+            max_bytes: None,
+            pattern: "{name}-{seq}".to_string(),
+        }
+    }
+
+    /// 设置单个文件的最大行数
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows.max(1));
+        self
+    }
+
+    /// 设置单个文件的最大字节数
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes.max(1));
+        self
+    }
+
+    /// 设置文件名模式：`{name}` 为导出名称，`{seq}` 为5位分片序号，`{date}` 为导出日期（`YYYYMMDD`）
+    pub fn with_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.pattern = pattern.into();
+        self
+    }
+
+    /// 当前分片是否已满
+    pub(crate) fn is_full(&self, rows: usize, bytes: u64) -> bool {
+        self.max_rows.is_some_and(|max| rows >= max)
+            || self.max_bytes.is_some_and(|max| bytes >= max)
+    }
+}
+
+/// 一个分片文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportPart {
+    /// 文件名
+    pub file: String,
+    /// 行数
+    pub row_count: usize,
+    /// 字节数
+    pub byte_count: u64,
+}
+
+/// 分片清单
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// 导出名称
+    pub name: String,
+    /// 文件格式（`csv` 或 `parquet`）
+    pub format: String,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 总行数
+    pub row_count: usize,
+    /// 分片（按序号排列）
+    pub parts: Vec<ExportPart>,
+}
+
+impl ExportManifest {
+    /// 读取目录中名为 `name` 的导出清单
+    pub fn read<P: AsRef<Path>>(dir: P, name: &str) -> Result<Self> {
+        let path = dir
+            .as_ref()
+            .join(format!("{}{}", name, EXPORT_MANIFEST_SUFFIX));
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("无法读取导出清单: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("导出清单格式错误: {}", path.display()))
+    }
+
+    /// 各分片的完整路径
+    pub fn part_paths<P: AsRef<Path>>(&self, dir: P) -> Vec<PathBuf> {
+        self.parts
+            .iter()
+            .map(|part| dir.as_ref().join(&part.file))
+            .collect()
+    }
+}
+
+/// 分片文件命名与清单写入，CSV和Parquet写入器共用
+#[derive(Debug)]
+pub(crate) struct PartSet {
+    dir: PathBuf,
+    name: String,
+    extension: &'static str,
+    policy: RotationPolicy,
+    created_at: DateTime<Utc>,
+    parts: Vec<ExportPart>,
+}
+
+impl PartSet {
+    pub(crate) fn create(
+        dir: &Path,
+        name: &str,
+        extension: &'static str,
+        policy: &RotationPolicy,
+    ) -> Result<Self> {
+        let limited = policy.max_rows.is_some() || policy.max_bytes.is_some();
+        if limited && !policy.pattern.contains("{seq}") {
+            return Err(anyhow::anyhow!(
+                "按行数或字节数分片时文件名模式必须包含{{seq}}: {}",
+                policy.pattern
+            ));
+        }
+        if policy.pattern.contains(['/', '\\']) {
+            return Err(anyhow::anyhow!(
+                "文件名模式不能包含路径分隔符: {}",
+                policy.pattern
+            ));
+        }
+        fs::create_dir_all(dir).with_context(|| format!("无法创建导出目录: {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            name: name.to_string(),
+            extension,
+            policy: policy.clone(),
+            created_at: Utc::now(),
+            parts: Vec::new(),
+        })
+    }
+
+    pub(crate) fn policy(&self) -> &RotationPolicy {
+        &self.policy
+    }
+
+    /// 下一个分片的文件名和路径
+    pub(crate) fn next_path(&self) -> (String, PathBuf) {
+        let date = self.created_at.date_naive();
+        let file = format!(
+            "{}.{}",
+            self.policy
+                .pattern
+                .replace("{name}", &self.name)
+                .replace("{seq}", &format!("{:05}", self.parts.len()))
+                .replace("{date}", &date.format("%Y%m%d").to_string()),
+            self.extension
+        );
+        let path = self.dir.join(&file);
+        (file, path)
+    }
+
+    pub(crate) fn push(&mut self, part: ExportPart) {
+        self.parts.push(part);
+    }
+
+    #[cfg(feature = "storage")]
+    pub(crate) fn into_parts(self) -> Vec<ExportPart> {
+        self.parts
+    }
+
+    /// 写入清单，先写临时文件再整体替换
+    pub(crate) fn finish(self) -> Result<ExportManifest> {
+        let manifest_path = self
+            .dir
+            .join(format!("{}{}", self.name, EXPORT_MANIFEST_SUFFIX));
+        let manifest = ExportManifest {
+            name: self.name,
+            format: self.extension.to_string(),
+            created_at: self.created_at,
+            row_count: self.parts.iter().map(|part| part.row_count).sum(),
+            parts: self.parts,
+        };
+        let temp_path = manifest_path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| format!("无法写入导出清单: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &manifest_path)
+            .with_context(|| format!("无法写入导出清单: {}", manifest_path.display()))?;
+        Ok(manifest)
+    }
+}
+
+/// 统计写入字节数的文件写入器
+///
+/// `flush` 只记账不落盘：CSV写入器每行之后刷新内部缓冲，字节数即时准确，
+/// 又不会每行触发一次系统调用；文件在分片结束时统一落盘。
+#[derive(Debug)]
+struct CountingFile {
+    inner: BufWriter<File>,
+    bytes: u64,
+}
+
+impl Write for CountingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 当前正在写入的CSV分片
+#[derive(Debug)]
+struct CsvPart {
+    file: String,
+    writer: csv::Writer<CountingFile>,
+    rows: usize,
+}
+
+/// 按行数或字节数切换文件的CSV写入器，每个分片都带表头
+#[derive(Debug)]
+pub struct RotatingCsvWriter {
+    parts: PartSet,
+    header: Option<Vec<String>>,
+    current: Option<CsvPart>,
+}
+
+impl RotatingCsvWriter {
+    /// 在 `dir` 下创建名为 `name` 的分片导出；第一行写入时才创建文件
+    pub fn create<P: AsRef<Path>>(dir: P, name: &str, policy: &RotationPolicy) -> Result<Self> {
+        Ok(Self {
+            parts: PartSet::create(dir.as_ref(), name, "csv", policy)?,
+            header: None,
+            current: None,
+        })
+    }
+
+    /// 设置表头；未设置时 `serialize` 按字段名自动生成表头
+    pub fn with_header<I, T>(mut self, header: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.header = Some(header.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 写入一行
+    pub fn write_record<I, T>(&mut self, record: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let part = self.part()?;
+        part.writer.write_record(record)?;
+        self.commit_row()
+    }
+
+    /// 按serde序列化写入一行
+    pub fn serialize<S: Serialize>(&mut self, record: S) -> Result<()> {
+        let part = self.part()?;
+        part.writer.serialize(record)?;
+        self.commit_row()
+    }
+
+    /// 结束导出，写入清单
+    pub fn finish(mut self) -> Result<ExportManifest> {
+        self.close_part()?;
+        self.parts.finish()
+    }
+
+    /// 当前分片，已满或尚未创建时打开新分片
+    fn part(&mut self) -> Result<&mut CsvPart> {
+        let full = self.current.as_ref().is_some_and(|part| {
+            self.parts
+                .policy()
+                .is_full(part.rows, part.writer.get_ref().bytes)
+        });
+        if full {
+            self.close_part()?;
+        }
+        if self.current.is_none() {
+            let (file, path) = self.parts.next_path();
+            let handle = File::create(&path)
+                .with_context(|| format!("无法创建CSV文件: {}", path.display()))?;
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(self.header.is_none())
+                .from_writer(CountingFile {
+                    inner: BufWriter::new(handle),
+                    bytes: 0,
+                });
+            if let Some(header) = &self.header {
+                writer.write_record(header)?;
+            }
+            self.current = Some(CsvPart {
+                file,
+                writer,
+                rows: 0,
+            });
+        }
+        Ok(self.current.as_mut().unwrap())
+    }
+
+    fn commit_row(&mut self) -> Result<()> {
+        let check_bytes = self.parts.policy().max_bytes.is_some();
+        if let Some(part) = self.current.as_mut() {
+            part.rows += 1;
+            if check_bytes {
+                part.writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn close_part(&mut self) -> Result<()> {
+        if let Some(mut part) = self.current.take() {
+            part.writer.flush()?;
+            let counting = part
+                .writer
+                .into_inner()
+                .map_err(|e| anyhow::anyhow!("CSV分片写入失败: {}", e.error()))?;
+            let bytes = counting.bytes;
+            counting
+                .inner
+                .into_inner()
+                .map_err(|e| anyhow::anyhow!("CSV分片写入失败: {}", e.error()))?
+                .sync_all()?;
+            self.parts.push(ExportPart {
+                file: part.file,
+                row_count: part.rows,
+                byte_count: bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 增强记录的CSV列：基础行情列加全部指标列
+pub fn enhanced_csv_header() -> Vec<String> {
+    [
+        "date", "symbol", "market", "open", "high", "low", "close", "volume", "amount",
+    ]
+    .into_iter()
+    .chain(INDICATOR_NAMES)
+    .map(str::to_string)
+    .collect()
+}
+
+/// 把增强记录导出为分片CSV，指标缺失处留空
+pub fn export_enhanced_csv<P: AsRef<Path>>(
+    dir: P,
+    name: &str,
+    records: &[EnhancedDayRecord],
+    policy: &RotationPolicy,
+) -> Result<ExportManifest> {
+    let mut writer =
+        RotatingCsvWriter::create(dir, name, policy)?.with_header(enhanced_csv_header());
+    for record in records {
+        let base = &record.base_record;
+        let mut line = vec![
+            base.date.format("%Y-%m-%d").to_string(),
+            base.symbol.clone(),
+            base.market.clone(),
+            base.open.to_string(),
+            base.high.to_string(),
+            base.low.to_string(),
+            base.close.to_string(),
+            base.volume.to_string(),
+            base.amount.to_string(),
+        ];
+        line.extend(INDICATOR_NAMES.iter().map(|name| {
+            record
+                .indicators
+                .get(name)
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        }));
+        writer.write_record(&line)?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use crate::processors::calculator::IndicatorValues;
+    use chrono::NaiveDate;
+
+    fn create_test_record(day: u32) -> EnhancedDayRecord {
+        let record = TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: "600000".to_string(),
+            open: 10.0,
+            high: 10.5,
+            low: 9.5,
+            close: 10.2,
+            volume: 1000,
+            amount: 10200.0,
+            market: "SH".to_string(),
+        };
+        let indicators = IndicatorValues {
+            ma5: Some(10.1),
+            ..Default::default()
+        };
+        EnhancedDayRecord::from_record(&record, indicators)
+    }
+
+    #[test]
+    fn test_rotate_by_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let records: Vec<_> = (1..=5).map(create_test_record).collect();
+        let policy = RotationPolicy::new().with_max_rows(2);
+        let manifest = export_enhanced_csv(dir.path(), "enhanced", &records, &policy).unwrap();
+
+        assert_eq!(manifest.row_count, 5);
+        let rows: Vec<usize> = manifest.parts.iter().map(|p| p.row_count).collect();
+        assert_eq!(rows, vec![2, 2, 1]);
+        assert_eq!(manifest.parts[1].file, "enhanced-00001.csv");
+        assert_eq!(
+            ExportManifest::read(dir.path(), "enhanced").unwrap(),
+            manifest
+        );
+
+        // 每个分片都带表头，字节数与文件大小一致
+        for (part, path) in manifest.parts.iter().zip(manifest.part_paths(dir.path())) {
+            let mut reader = csv::Reader::from_path(&path).unwrap();
+            assert_eq!(reader.headers().unwrap().get(9), Some("ma5"));
+            assert_eq!(reader.records().count(), part.row_count);
+            assert_eq!(fs::metadata(&path).unwrap().len(), part.byte_count);
+        }
+    }
+
+    #[test]
+    fn test_rotate_by_bytes_and_pattern() {
+        #[derive(Serialize)]
+        struct Row {
+            id: u32,
+            value: String,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let policy = RotationPolicy::new()
+            .with_max_bytes(64)
+            .with_pattern("{name}_{date}_{seq}");
+        let mut writer = RotatingCsvWriter::create(dir.path(), "rows", &policy).unwrap();
+        for id in 0..10 {
+            writer
+                .serialize(Row {
+                    id,
+                    value: "x".repeat(10),
+                })
+                .unwrap();
+        }
+        let manifest = writer.finish().unwrap();
+        assert_eq!(manifest.row_count, 10);
+        assert!(manifest.parts.len() > 1);
+        let today = Utc::now().format("%Y%m%d").to_string();
+        assert!(manifest.parts[0].file.contains(&today));
+        for part in &manifest.parts[..manifest.parts.len() - 1] {
+            // 达到上限后才切换，单个分片最多超出一行
+            assert!(part.byte_count >= 64 && part.byte_count < 64 + 20);
+        }
+
+        let policy = RotationPolicy::new()
+            .with_max_rows(1)
+            .with_pattern("{name}");
+        assert!(RotatingCsvWriter::create(dir.path(), "rows", &policy).is_err());
+        let policy = RotationPolicy::new().with_pattern("../{name}");
+        assert!(RotatingCsvWriter::create(dir.path(), "rows", &policy).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+//! 分片Parquet写入器
+
+use super::{ExportManifest, ExportPart, PartSet, RotationPolicy};
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::storage::schema::RecordSchema;
+use crate::storage::snapshot::build_batch;
+use anyhow::{Context, Result};
+use arrow_array::RecordBatch;
+use arrow_schema::SchemaRef;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+
+/// 增强记录导出时每个批次的行数
+const ENHANCED_BATCH_SIZE: usize = 65_536;
+
+/// 当前正在写入的Parquet分片
+struct ParquetPart {
+    file: String,
+    writer: ArrowWriter<File>,
+    rows: usize,
+}
+
+/// 按行数或字节数切换文件的Parquet写入器
+///
+/// 字节数按已落盘的字节加上缓冲中行组的估算大小计算，批次在行数上限处拆开，
+/// 字节上限在每个批次写入后检查。
+pub struct RotatingParquetWriter {
+    parts: PartSet,
+    schema: SchemaRef,
+    properties: WriterProperties,
+    current: Option<ParquetPart>,
+}
+
+impl std::fmt::Debug for RotatingParquetWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RotatingParquetWriter")
+            .field("parts", &self.parts)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl RotatingParquetWriter {
+    /// 在 `dir` 下创建名为 `name` 的分片导出，默认zstd压缩；第一批数据写入时才创建文件
+    pub fn create<P: AsRef<Path>>(
+        dir: P,
+        name: &str,
+        schema: SchemaRef,
+        policy: &RotationPolicy,
+    ) -> Result<Self> {
+        Ok(Self {
+            parts: PartSet::create(dir.as_ref(), name, "parquet", policy)?,
+            schema,
+            properties: WriterProperties::builder()
+                .set_compression(Compression::ZSTD(ZstdLevel::default()))
+                .build(),
+            current: None,
+        })
+    }
+
+    /// 设置写入参数（压缩方式、行组大小等）
+    pub fn with_properties(mut self, properties: WriterProperties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// 写入一个批次，必要时拆分到多个分片
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let max_rows = self.parts.policy().max_rows;
+            let part = self.part()?;
+            let len = match max_rows {
+                Some(max) => (max - part.rows).min(batch.num_rows() - offset),
+                None => batch.num_rows() - offset,
+            };
+            part.writer.write(&batch.slice(offset, len))?;
+            part.rows += len;
+            offset += len;
+        }
+        Ok(())
+    }
+
+    /// 结束导出，写入清单
+    pub fn finish(mut self) -> Result<ExportManifest> {
+        self.close_part()?;
+        self.parts.finish()
+    }
+
+    /// 结束导出但不写清单，返回各分片（由调用方记录在自己的清单中）
+    pub fn close(mut self) -> Result<Vec<ExportPart>> {
+        self.close_part()?;
+        Ok(self.parts.into_parts())
+    }
+
+    /// 当前分片，已满或尚未创建时打开新分片
+    fn part(&mut self) -> Result<&mut ParquetPart> {
+        let full = self.current.as_ref().is_some_and(|part| {
+            let bytes = part.writer.bytes_written() + part.writer.in_progress_size();
+            self.parts.policy().is_full(part.rows, bytes as u64)
+        });
+        if full {
+            self.close_part()?;
+        }
+        if self.current.is_none() {
+            let (file, path) = self.parts.next_path();
+            let handle = File::create(&path)
+                .with_context(|| format!("无法创建Parquet文件: {}", path.display()))?;
+            let writer =
+                ArrowWriter::try_new(handle, self.schema.clone(), Some(self.properties.clone()))?;
+            self.current = Some(ParquetPart {
+                file,
+                writer,
+                rows: 0,
+            });
+        }
+        Ok(self.current.as_mut().unwrap())
+    }
+
+    fn close_part(&mut self) -> Result<()> {
+        if let Some(part) = self.current.take() {
+            let mut writer = part.writer;
+            writer.finish()?;
+            let bytes = writer.bytes_written() as u64;
+            writer.inner_mut().sync_all()?;
+            self.parts.push(ExportPart {
+                file: part.file,
+                row_count: part.rows,
+                byte_count: bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 把增强记录导出为分片Parquet（与快照相同的列结构）
+pub fn export_enhanced_parquet<P: AsRef<Path>>(
+    dir: P,
+    name: &str,
+    records: &[EnhancedDayRecord],
+    policy: &RotationPolicy,
+) -> Result<ExportManifest> {
+    let schema = RecordSchema::current(true).arrow_schema();
+    let mut writer = RotatingParquetWriter::create(dir, name, schema.clone(), policy)?;
+    for chunk in records.chunks(ENHANCED_BATCH_SIZE) {
+        let rows: Vec<_> = chunk
+            .iter()
+            .map(|record| (&record.base_record, Some(&record.indicators)))
+            .collect();
+        writer.write(&build_batch(&schema, &rows, true)?)?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use crate::processors::calculator::IndicatorValues;
+    use chrono::NaiveDate;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn test_enhanced_parquet_parts() {
+        let records: Vec<EnhancedDayRecord> = (0..7)
+            .map(|day| {
+                let record = TDXDayRecord {
+                    date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+                        + chrono::Duration::days(day),
+                    symbol: "600000".to_string(),
+                    open: 10.0,
+                    high: 10.5,
+                    low: 9.5,
+                    close: 10.2,
+                    volume: 1000,
+                    amount: 10200.0,
+                    market: "SH".to_string(),
+                };
+                EnhancedDayRecord::from_record(&record, IndicatorValues::default())
+            })
+            .collect();
+
+        let dir = tempfile::tempdir().unwrap();
+        let policy = RotationPolicy::new().with_max_rows(3);
+        let manifest = export_enhanced_parquet(dir.path(), "enhanced", &records, &policy).unwrap();
+        assert_eq!(manifest.format, "parquet");
+        let rows: Vec<usize> = manifest.parts.iter().map(|p| p.row_count).collect();
+        assert_eq!(rows, vec![3, 3, 1]);
+
+        for (part, path) in manifest.parts.iter().zip(manifest.part_paths(dir.path())) {
+            assert_eq!(std::fs::metadata(&path).unwrap().len(), part.byte_count);
+            let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap();
+            let count: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+            assert_eq!(count, part.row_count);
+        }
+    }
+}
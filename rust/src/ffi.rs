@@ -0,0 +1,357 @@
+//! C接口模块（`ffi` 特性）
+//!
+//! 为C/C++/C#等非Python组件提供稳定的C ABI：解析通达信日线缓冲区、计算技术指标、
+//! 释放结果。结构体均为 `#[repr(C)]` 的平铺结构，结果内存由本库分配，必须调用对应的
+//! `pt_free_*` 函数释放。头文件见 `include/pulse_trader.h`。
+//!
+//! 函数返回状态码（`PT_OK` 为成功），失败时可通过 `pt_last_error` 取得当前线程最近一次的
+//! 错误信息。
+
+use crate::parsers::{TDXDayParser, TDXDayRecord};
+use crate::processors::calculator::IndicatorCalculator;
+use crate::processors::field::INDICATOR_NAMES;
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::ptr;
+
+/// 成功
+pub const PT_OK: i32 = 0;
+/// 参数为空指针
+pub const PT_ERR_NULL_POINTER: i32 = -1;
+/// 解析或计算失败
+pub const PT_ERR_FAILED: i32 = -2;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// 日线记录
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PtDayRecord {
+    /// 交易日期（YYYYMMDD）
+    pub date: i32,
+    /// 开盘价（元）
+    pub open: f64,
+    /// 最高价（元）
+    pub high: f64,
+    /// 最低价（元）
+    pub low: f64,
+    /// 收盘价（元）
+    pub close: f64,
+    /// 成交量（股）
+    pub volume: u64,
+    /// 成交额（元）
+    pub amount: f64,
+}
+
+/// 日线记录数组
+#[repr(C)]
+#[derive(Debug)]
+pub struct PtRecordArray {
+    /// 记录
+    pub records: *mut PtDayRecord,
+    /// 记录数
+    pub len: usize,
+}
+
+/// 指标矩阵，按行存储：第 `i` 条记录的第 `j` 个指标为 `values[i * columns + j]`，缺失为NaN
+#[repr(C)]
+#[derive(Debug)]
+pub struct PtIndicatorMatrix {
+    /// 指标值
+    pub values: *mut f64,
+    /// 行数（记录数）
+    pub rows: usize,
+    /// 列数（指标数，列名见 `pt_indicator_name`）
+    pub columns: usize,
+}
+
+impl PtDayRecord {
+    fn from_record(record: &TDXDayRecord) -> Self {
+        Self {
+            date: record.date.year() * 10000
+                + record.date.month() as i32 * 100
+                + record.date.day() as i32,
+            open: record.open,
+            high: record.high,
+            low: record.low,
+            close: record.close,
+            volume: record.volume,
+            amount: record.amount,
+        }
+    }
+
+    fn to_record(self) -> Result<TDXDayRecord> {
+        let date = NaiveDate::from_ymd_opt(
+            self.date / 10000,
+            (self.date / 100 % 100) as u32,
+            (self.date % 100) as u32,
+        )
+        .ok_or_else(|| anyhow::anyhow!("无效的日期: {}", self.date))?;
+        Ok(TDXDayRecord {
+            date,
+            symbol: String::new(),
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            amount: self.amount,
+            market: String::new(),
+        })
+    }
+}
+
+/// 记录错误信息并返回状态码
+fn fail(error: anyhow::Error) -> i32 {
+    let message = CString::new(format!("{:#}", error).replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    PT_ERR_FAILED
+}
+
+/// 把数据移交给调用方，返回（指针, 长度）
+fn into_raw_parts<T>(values: Vec<T>) -> (*mut T, usize) {
+    let boxed = values.into_boxed_slice();
+    let len = boxed.len();
+    (Box::into_raw(boxed) as *mut T, len)
+}
+
+/// 收回由 `into_raw_parts` 移交的数据
+///
+/// # Safety
+///
+/// `data` 与 `len` 必须来自 `into_raw_parts`，且只能收回一次。
+unsafe fn from_raw_parts<T>(data: *mut T, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// 当前线程最近一次的错误信息，没有错误时返回空指针
+///
+/// 返回的字符串在同一线程下一次调用本库函数前有效，调用方不得释放。
+#[no_mangle]
+pub extern "C" fn pt_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// 解析通达信day文件内容
+///
+/// # Safety
+///
+/// `data` 须指向 `len` 字节的可读内存；`out` 须指向可写的 `PtRecordArray`，
+/// 成功后须以 `pt_free_records` 释放。
+#[no_mangle]
+pub unsafe extern "C" fn pt_parse_day_buffer(
+    data: *const u8,
+    len: usize,
+    out: *mut PtRecordArray,
+) -> i32 {
+    if (data.is_null() && len > 0) || out.is_null() {
+        return PT_ERR_NULL_POINTER;
+    }
+    let buffer = if len == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    };
+
+    match TDXDayParser::new("").parse_binary_data(buffer, "", "") {
+        Ok(records) => {
+            let records: Vec<PtDayRecord> = records.iter().map(PtDayRecord::from_record).collect();
+            let (records, len) = into_raw_parts(records);
+            *out = PtRecordArray { records, len };
+            PT_OK
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// 释放 `pt_parse_day_buffer` 返回的记录数组
+///
+/// # Safety
+///
+/// `array` 须来自 `pt_parse_day_buffer` 且未被释放过。
+#[no_mangle]
+pub unsafe extern "C" fn pt_free_records(array: PtRecordArray) {
+    from_raw_parts(array.records, array.len);
+}
+
+/// 指标列数
+#[no_mangle]
+pub extern "C" fn pt_indicator_count() -> usize {
+    INDICATOR_NAMES.len()
+}
+
+/// 第 `index` 个指标的列名（静态字符串），越界时返回空指针
+#[no_mangle]
+pub extern "C" fn pt_indicator_name(index: usize) -> *const c_char {
+    // 以NUL结尾的列名，与 `INDICATOR_NAMES` 一一对应
+    static NAMES: std::sync::OnceLock<Vec<CString>> = std::sync::OnceLock::new();
+    let names = NAMES.get_or_init(|| {
+        INDICATOR_NAMES
+            .iter()
+            .map(|name| CString::new(*name).unwrap())
+            .collect()
+    });
+    names.get(index).map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// 计算单只股票按日期排列的记录的技术指标
+///
+/// # Safety
+///
+/// `records` 须指向 `len` 条可读的 `PtDayRecord`；`out` 须指向可写的 `PtIndicatorMatrix`，
+/// 成功后须以 `pt_free_indicators` 释放。
+#[no_mangle]
+pub unsafe extern "C" fn pt_calculate_indicators(
+    records: *const PtDayRecord,
+    len: usize,
+    out: *mut PtIndicatorMatrix,
+) -> i32 {
+    if (records.is_null() && len > 0) || out.is_null() {
+        return PT_ERR_NULL_POINTER;
+    }
+    let input = if len == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(records, len)
+    };
+
+    let result = input
+        .iter()
+        .map(|record| record.to_record())
+        .collect::<Result<Vec<_>>>()
+        .and_then(|records| IndicatorCalculator::new().calculate_all_indicators(&records));
+    match result {
+        Ok(enhanced) => {
+            let values: Vec<f64> = enhanced
+                .iter()
+                .flat_map(|record| {
+                    INDICATOR_NAMES
+                        .iter()
+                        .map(|name| record.indicators.get(name).unwrap_or(f64::NAN))
+                })
+                .collect();
+            let (values, _) = into_raw_parts(values);
+            *out = PtIndicatorMatrix {
+                values,
+                rows: enhanced.len(),
+                columns: INDICATOR_NAMES.len(),
+            };
+            PT_OK
+        }
+        Err(e) => fail(e),
+    }
+}
+
+/// 释放 `pt_calculate_indicators` 返回的指标矩阵
+///
+/// # Safety
+///
+/// `matrix` 须来自 `pt_calculate_indicators` 且未被释放过。
+#[no_mangle]
+pub unsafe extern "C" fn pt_free_indicators(matrix: PtIndicatorMatrix) {
+    from_raw_parts(matrix.values, matrix.rows * matrix.columns);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    /// 构造通达信day文件内容
+    fn day_buffer(closes: &[u32]) -> Vec<u8> {
+        let mut content = Vec::new();
+        for (i, &close) in closes.iter().enumerate() {
+            for value in [20240102 + i as u32, close, close + 10, close - 10, close] {
+                content.extend_from_slice(&value.to_le_bytes());
+            }
+            content.extend_from_slice(&(close as f32 * 100.0).to_le_bytes());
+            content.extend_from_slice(&100u32.to_le_bytes());
+            content.extend_from_slice(&0u32.to_le_bytes());
+        }
+        content
+    }
+
+    #[test]
+    fn test_parse_and_free() {
+        let buffer = day_buffer(&[1000, 1010, 1020]);
+        let mut out = PtRecordArray {
+            records: ptr::null_mut(),
+            len: 0,
+        };
+        unsafe {
+            assert_eq!(
+                pt_parse_day_buffer(buffer.as_ptr(), buffer.len(), &mut out),
+                PT_OK
+            );
+            let records = std::slice::from_raw_parts(out.records, out.len);
+            assert_eq!(records.len(), 3);
+            assert_eq!(records[0].date, 20240102);
+            assert_eq!(records[2].close, 10.2);
+            pt_free_records(out);
+        }
+    }
+
+    #[test]
+    fn test_calculate_indicators() {
+        let closes: Vec<u32> = (0..30).map(|i| 1000 + i * 5).collect();
+        let buffer = day_buffer(&closes);
+        let mut records = PtRecordArray {
+            records: ptr::null_mut(),
+            len: 0,
+        };
+        let mut matrix = PtIndicatorMatrix {
+            values: ptr::null_mut(),
+            rows: 0,
+            columns: 0,
+        };
+        unsafe {
+            assert_eq!(
+                pt_parse_day_buffer(buffer.as_ptr(), buffer.len(), &mut records),
+                PT_OK
+            );
+            assert_eq!(
+                pt_calculate_indicators(records.records, records.len, &mut matrix),
+                PT_OK
+            );
+            assert_eq!((matrix.rows, matrix.columns), (30, pt_indicator_count()));
+
+            let ma5 = (0..matrix.columns)
+                .find(|&j| CStr::from_ptr(pt_indicator_name(j)).to_str() == Ok("ma5"))
+                .unwrap();
+            let values = std::slice::from_raw_parts(matrix.values, matrix.rows * matrix.columns);
+            assert!(values[ma5].is_nan());
+            assert!((values[29 * matrix.columns + ma5] - 11.35).abs() < 1e-9);
+            assert!(pt_indicator_name(matrix.columns).is_null());
+
+            pt_free_indicators(matrix);
+            pt_free_records(records);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut out = PtRecordArray {
+            records: ptr::null_mut(),
+            len: 0,
+        };
+        unsafe {
+            assert_eq!(
+                pt_parse_day_buffer(ptr::null(), 0, ptr::null_mut()),
+                PT_ERR_NULL_POINTER
+            );
+            let bad = [0u8; 7];
+            assert_eq!(
+                pt_parse_day_buffer(bad.as_ptr(), bad.len(), &mut out),
+                PT_ERR_FAILED
+            );
+            let message = CStr::from_ptr(pt_last_error()).to_str().unwrap();
+            assert!(message.contains("文件大小不正确"));
+        }
+    }
+}
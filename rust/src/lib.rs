@@ -2,20 +2,88 @@
 //!
 //! 本模块提供基于Rust的高性能数据处理能力，包括：
 //! - 通达信二进制数据解析
-//! - 并行数据处理
-//! - Python绑定接口
-//! - ClickHouse高性能存储
+//! - 并行数据处理与可断点恢复的处理流水线，收到SIGINT/SIGTERM时优雅停止（`shutdown`）
+//! - 以闭包按股票并行计算自定义滚动统计量（`compute`）
+//! - 按目标权重调仓的组合回测与自包含的HTML回测/数据质量报告
+//! - Python绑定接口、C接口（`ffi` 特性）与浏览器端WebAssembly接口（`wasm` 特性）
+//! - 多数据源访问（本地通达信文件、CSV目录、ClickHouse）
+//! - 通达信行情服务器客户端与东方财富/新浪日线下载
+//! - WebSocket/HTTP/gRPC服务接口（`serve` 特性）
+//! - Parquet数据集快照与冷热分层存储（`storage` 特性）
+//! - 按行数或字节数切分文件并附带分片清单的CSV/Parquet导出（`export`）
+//! - 真实数据脱敏抽样与按种子生成的模拟日线（`testing::fixtures`）
+//! - 聚合、查询与HTTP服务共享的带有效期内存结果缓存（`result_cache`）
+//! - 按文件内容哈希的指标结果缓存（`cache` 特性）
+//! - Kafka日线与流水线事件推送、日线回放数据源（`kafka` 特性）
+//! - 带均线、布林带与交易信号标注的K线图渲染（`viz` 特性）
+//! - JSON格式、按模块分级与按时间滚动文件的日志输出（`logging`）
+//!
+//! 各部分通过Cargo特性按需编译：`parser`、`archive`、`processors`、`net`、
+//! `watch`、`clickhouse`、`python`、`ffi`、`wasm`、`serve`、`storage`、`cache`、`viz`、`test-util`，默认启用 `parser` 与 `processors`。
 
+#[cfg(feature = "processors")]
+pub mod backtest;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod calendar;
+#[cfg(feature = "processors")]
+pub mod compute;
+#[cfg(feature = "processors")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod logging;
+#[cfg(feature = "kafka")]
+pub mod mq;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(feature = "parser")]
 pub mod parsers;
 
+#[cfg(feature = "processors")]
+pub mod pipeline;
+#[cfg(feature = "processors")]
 pub mod processors; // TODO: 并行数据处理模块
-                    // 重新导出主要接口
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "net")]
+pub mod realtime;
+#[cfg(feature = "processors")]
+pub mod reference;
+#[cfg(feature = "processors")]
+pub mod report;
+#[cfg(feature = "processors")]
+pub mod result_cache;
+#[cfg(feature = "serve")]
+pub mod serve;
+#[cfg(feature = "processors")]
+pub mod shutdown;
+#[cfg(feature = "processors")]
+pub mod source;
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "processors")]
+pub mod testing;
+#[cfg(feature = "viz")]
+pub mod viz;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watcher;
+// 重新导出主要接口
+#[cfg(feature = "parser")]
 pub use parsers::tdx_day::{TDXDayParser, TDXDayRecord, TDXStatistics};
+#[cfg(feature = "parser")]
+pub use parsers::{Bar, Price};
 
 /// 库版本信息
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// 默认日志初始化
+/// 默认日志初始化：stderr文本日志，可用 `PULSE_LOG__*` 环境变量切换为JSON或写入文件
+///
+/// 需要在退出前刷新文件日志时改用 `logging::init_logging` 并持有返回的守卫。
 pub fn init_logger() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let config = logging::LoggingConfig::from_env().unwrap_or_default();
+    let guard = logging::init_logging(&config).expect("日志初始化失败");
+    std::mem::forget(guard);
 }
@@ -0,0 +1,309 @@
+//! 日志输出配置
+//!
+//! 在默认的stderr文本日志之外，支持JSON格式（便于systemd/k8s日志采集）、按模块设置级别
+//! 以及按时间滚动的日志文件。配置可从YAML/TOML/JSON文件加载，并可用 `PULSE_LOG__*`
+//! 环境变量覆盖；设置了 `RUST_LOG` 时以其为准。库内 `log` 宏的输出会一并转发。
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::Subscriber;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+/// 覆盖配置的环境变量前缀，层级以 `__` 分隔，如 `PULSE_LOG__FORMAT=json`
+pub const ENV_PREFIX: &str = "PULSE_LOG";
+
+/// 日志格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// 人类可读的单行文本
+    #[default]
+    Text,
+    /// 每行一个JSON对象
+    Json,
+}
+
+/// 控制台输出目标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTarget {
+    #[default]
+    Stderr,
+    Stdout,
+    /// 不输出到控制台，只写文件
+    Off,
+}
+
+/// 日志文件滚动周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+impl LogRotation {
+    fn rotation(self) -> Rotation {
+        match self {
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+/// 日志文件配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FileLogConfig {
+    /// 日志目录
+    pub directory: PathBuf,
+    /// 文件名前缀，滚动时追加日期
+    pub prefix: String,
+    pub rotation: LogRotation,
+    /// 最多保留的文件数，`None` 表示不清理
+    pub max_files: Option<usize>,
+}
+
+impl Default for FileLogConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("logs"),
+            prefix: "pulse_trader".to_string(),
+            rotation: LogRotation::default(),
+            max_files: None,
+        }
+    }
+}
+
+/// 日志配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// 默认级别
+    pub level: String,
+    /// 按模块覆盖的级别，如 `pulse_trader_rust::pipeline = "debug"`
+    pub modules: BTreeMap<String, String>,
+    pub format: LogFormat,
+    pub target: LogTarget,
+    /// 日志文件，与控制台使用相同格式
+    pub file: Option<FileLogConfig>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            format: LogFormat::default(),
+            target: LogTarget::default(),
+            file: None,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// 从配置文件加载（格式按扩展名识别），再叠加 `PULSE_LOG__*` 环境变量
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        config::Config::builder()
+            .add_source(config::File::from(path))
+            .add_source(config::Environment::with_prefix(ENV_PREFIX).separator("__"))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .with_context(|| format!("加载日志配置失败: {}", path.display()))
+    }
+
+    /// 只从 `PULSE_LOG__*` 环境变量加载，未设置的项使用默认值
+    pub fn from_env() -> Result<Self> {
+        config::Config::builder()
+            .add_source(config::Environment::with_prefix(ENV_PREFIX).separator("__"))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .context("从环境变量加载日志配置失败")
+    }
+
+    pub fn with_level(mut self, level: impl Into<String>) -> Self {
+        self.level = level.into();
+        self
+    }
+
+    pub fn with_module_level(
+        mut self,
+        module: impl Into<String>,
+        level: impl Into<String>,
+    ) -> Self {
+        self.modules.insert(module.into(), level.into());
+        self
+    }
+
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_target(mut self, target: LogTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn with_file(mut self, file: FileLogConfig) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    /// 过滤指令，如 `info,pulse_trader_rust::pipeline=debug`
+    pub fn filter_directives(&self) -> String {
+        std::iter::once(self.level.clone())
+            .chain(
+                self.modules
+                    .iter()
+                    .map(|(module, level)| format!("{}={}", module, level)),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn env_filter(&self) -> Result<EnvFilter> {
+        if let Ok(directives) = std::env::var(EnvFilter::DEFAULT_ENV) {
+            if !directives.is_empty() {
+                return EnvFilter::try_new(&directives)
+                    .with_context(|| format!("RUST_LOG无效: {}", directives));
+            }
+        }
+        let directives = self.filter_directives();
+        EnvFilter::try_new(&directives).with_context(|| format!("日志级别无效: {}", directives))
+    }
+
+    fn layer<W>(&self, writer: W, ansi: bool) -> Box<dyn Layer<Registry> + Send + Sync>
+    where
+        W: for<'w> fmt::MakeWriter<'w> + Send + Sync + 'static,
+    {
+        let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+        match self.format {
+            LogFormat::Text => layer.boxed(),
+            LogFormat::Json => layer.json().flatten_event(true).boxed(),
+        }
+    }
+
+    /// 构建订阅者但不安装，文件日志需持有返回的 `LoggingGuard`
+    fn build(&self) -> Result<(impl Subscriber + Send + Sync, LoggingGuard)> {
+        let mut layers = Vec::new();
+        let console = match self.target {
+            LogTarget::Stderr => Some(BoxMakeWriter::new(std::io::stderr)),
+            LogTarget::Stdout => Some(BoxMakeWriter::new(std::io::stdout)),
+            LogTarget::Off => None,
+        };
+        if let Some(writer) = console {
+            layers.push(self.layer(writer, self.format == LogFormat::Text));
+        }
+
+        let mut guard = None;
+        if let Some(file) = &self.file {
+            let mut builder = RollingFileAppender::builder()
+                .rotation(file.rotation.rotation())
+                .filename_prefix(&file.prefix)
+                .filename_suffix("log");
+            if let Some(max_files) = file.max_files {
+                builder = builder.max_log_files(max_files);
+            }
+            let appender = builder
+                .build(&file.directory)
+                .with_context(|| format!("无法创建日志文件: {}", file.directory.display()))?;
+            let (writer, worker) = tracing_appender::non_blocking(appender);
+            layers.push(self.layer(writer, false));
+            guard = Some(worker);
+        }
+
+        let subscriber = tracing_subscriber::registry()
+            .with(layers)
+            .with(self.env_filter()?);
+        Ok((subscriber, LoggingGuard { _worker: guard }))
+    }
+}
+
+/// 日志后台写入的守卫，丢弃时刷新尚未写入文件的日志，应在 `main` 中持有到退出
+#[must_use = "丢弃后文件日志会停止写入"]
+pub struct LoggingGuard {
+    _worker: Option<WorkerGuard>,
+}
+
+/// 按配置安装全局日志，并转发 `log` 宏的输出；重复初始化返回错误
+pub fn init_logging(config: &LoggingConfig) -> Result<LoggingGuard> {
+    let (subscriber, guard) = config.build()?;
+    subscriber
+        .try_init()
+        .map_err(|e| anyhow::anyhow!("日志已初始化: {}", e))?;
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logging.yaml");
+        std::fs::write(
+            &path,
+            "level: warn\nformat: json\ntarget: stdout\nmodules:\n  pulse_trader_rust::pipeline: debug\nfile:\n  directory: /var/log/pulse\n  rotation: hourly\n  max_files: 24\n",
+        )
+        .unwrap();
+
+        let config = LoggingConfig::load(&path).unwrap();
+        assert_eq!(config.format, LogFormat::Json);
+        assert_eq!(config.target, LogTarget::Stdout);
+        assert_eq!(
+            config.filter_directives(),
+            "warn,pulse_trader_rust::pipeline=debug"
+        );
+        let file = config.file.unwrap();
+        assert_eq!(file.rotation, LogRotation::Hourly);
+        assert_eq!(file.max_files, Some(24));
+        assert_eq!(file.prefix, "pulse_trader");
+    }
+
+    #[test]
+    fn test_json_file_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = LoggingConfig::default()
+            .with_level("warn")
+            .with_module_level("pipeline_test", "debug")
+            .with_format(LogFormat::Json)
+            .with_target(LogTarget::Off)
+            .with_file(FileLogConfig {
+                directory: dir.path().to_path_buf(),
+                rotation: LogRotation::Never,
+                ..FileLogConfig::default()
+            });
+
+        let (subscriber, guard) = config.build().unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "pipeline_test", stage = "clean", "阶段完成");
+            tracing::info!(target: "other", "被过滤");
+        });
+        drop(guard);
+
+        let content = std::fs::read_to_string(dir.path().join("pulse_trader.log")).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "DEBUG");
+        assert_eq!(lines[0]["stage"], "clean");
+        assert_eq!(lines[0]["message"], "阶段完成");
+    }
+}
@@ -0,0 +1,753 @@
+//! Kafka连接器（`kafka` 特性）
+//!
+//! `KafkaProducer` 把解析后的日线推送到日线主题、把流水线事件推送到事件主题，
+//! 下游服务（实时计算、入库、告警）直接订阅即可，不必再读通达信文件。
+//! 消息键可选股票代码或“代码.市场”（同一股票落在同一分区、保持日期顺序），
+//! 日线消息体为JSON或Avro（固定模式 [`BAR_AVRO_SCHEMA`]，不带模式注册表的头部，
+//! 消费方按该模式解码），编码方式写在消息头 `format` 中；流水线事件始终为JSON。
+//!
+//! `KafkaSource` 是回放日线的数据源：从主题所有分区的最早位置读到当前高水位，
+//! 按（代码, 市场, 日期）去重（后到的消息覆盖先到的）后缓存在内存中，
+//! 调用 [`KafkaSource::refresh`] 后下次访问重新读取。回放不提交消费位置。
+
+use crate::parsers::TDXDayRecord;
+use crate::pipeline::{PipelineReport, PipelineSink};
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::source::{DataSource, DateRange};
+use anyhow::{Context, Result};
+use apache_avro::types::Value as AvroValue;
+use apache_avro::Schema;
+use chrono::NaiveDate;
+use log::{debug, warn};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::RDKafkaErrorCode;
+use rdkafka::message::{BorrowedMessage, Header, Headers, Message, OwnedHeaders};
+use rdkafka::producer::{BaseProducer, BaseRecord, DeliveryResult, Producer, ProducerContext};
+use rdkafka::{ClientContext, Offset, TopicPartitionList};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// 日线消息的Avro模式（`date` 为自1970-01-01起的天数）
+pub const BAR_AVRO_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "DayBar",
+    "namespace": "pulse_trader",
+    "fields": [
+        {"name": "symbol", "type": "string"},
+        {"name": "market", "type": "string"},
+        {"name": "date", "type": {"type": "int", "logicalType": "date"}},
+        {"name": "open", "type": "double"},
+        {"name": "high", "type": "double"},
+        {"name": "low", "type": "double"},
+        {"name": "close", "type": "double"},
+        {"name": "volume", "type": "long"},
+        {"name": "amount", "type": "double"}
+    ]
+}"#;
+
+/// 记录编码方式的消息头
+const FORMAT_HEADER: &str = "format";
+
+/// 消息键的生成方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStrategy {
+    /// 股票代码，如 `600000`
+    #[default]
+    Symbol,
+    /// 代码.市场，如 `600000.SH`（沪深代码可能重复时使用）
+    SymbolMarket,
+    /// 不设置键，由Kafka轮询分区（不保证同一股票的顺序）
+    None,
+}
+
+impl KeyStrategy {
+    /// 日线对应的消息键
+    pub fn key(&self, record: &TDXDayRecord) -> Option<String> {
+        match self {
+            KeyStrategy::Symbol => Some(record.symbol.clone()),
+            KeyStrategy::SymbolMarket => Some(format!("{}.{}", record.symbol, record.market)),
+            KeyStrategy::None => None,
+        }
+    }
+}
+
+/// 日线消息体格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueFormat {
+    /// JSON文本
+    #[default]
+    Json,
+    /// Avro二进制（模式见 [`BAR_AVRO_SCHEMA`]）
+    Avro,
+}
+
+impl ValueFormat {
+    /// 消息头 `format` 中的取值
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueFormat::Json => "json",
+            ValueFormat::Avro => "avro",
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"json" => Some(ValueFormat::Json),
+            b"avro" => Some(ValueFormat::Avro),
+            _ => None,
+        }
+    }
+}
+
+/// 流水线事件（JSON，`event` 字段区分类型）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PipelineEvent {
+    /// 一个批次的日线已发送
+    BatchWritten {
+        /// 日线条数
+        records: usize,
+        /// 涉及的股票数
+        symbols: usize,
+    },
+    /// 缓冲已刷新，此前发送的消息均已确认
+    Flushed {
+        /// 累计确认的日线条数
+        bars_delivered: u64,
+    },
+    /// 流水线运行结束
+    Finished {
+        /// 运行报告
+        report: PipelineReport,
+    },
+}
+
+impl PipelineEvent {
+    /// 事件类型名称（作为事件消息的键）
+    pub fn name(&self) -> &'static str {
+        match self {
+            PipelineEvent::BatchWritten { .. } => "batch_written",
+            PipelineEvent::Flushed { .. } => "flushed",
+            PipelineEvent::Finished { .. } => "finished",
+        }
+    }
+}
+
+/// 把日线编码为消息体
+pub fn encode_bar(record: &TDXDayRecord, format: ValueFormat) -> Result<Vec<u8>> {
+    match format {
+        ValueFormat::Json => serde_json::to_vec(record).context("日线JSON编码失败"),
+        ValueFormat::Avro => {
+            let days = (record.date - NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days();
+            let value = AvroValue::Record(vec![
+                (
+                    "symbol".to_string(),
+                    AvroValue::String(record.symbol.clone()),
+                ),
+                (
+                    "market".to_string(),
+                    AvroValue::String(record.market.clone()),
+                ),
+                ("date".to_string(), AvroValue::Date(days as i32)),
+                ("open".to_string(), AvroValue::Double(record.open)),
+                ("high".to_string(), AvroValue::Double(record.high)),
+                ("low".to_string(), AvroValue::Double(record.low)),
+                ("close".to_string(), AvroValue::Double(record.close)),
+                ("volume".to_string(), AvroValue::Long(record.volume as i64)),
+                ("amount".to_string(), AvroValue::Double(record.amount)),
+            ]);
+            apache_avro::to_avro_datum(bar_schema(), value).context("日线Avro编码失败")
+        }
+    }
+}
+
+/// 从消息体解码日线
+pub fn decode_bar(payload: &[u8], format: ValueFormat) -> Result<TDXDayRecord> {
+    match format {
+        ValueFormat::Json => serde_json::from_slice(payload).context("日线JSON解码失败"),
+        ValueFormat::Avro => {
+            let value = apache_avro::from_avro_datum(bar_schema(), &mut &payload[..], None)
+                .context("日线Avro解码失败")?;
+            let AvroValue::Record(fields) = value else {
+                return Err(anyhow::anyhow!("Avro消息不是记录类型"));
+            };
+            let fields: BTreeMap<String, AvroValue> = fields.into_iter().collect();
+            let string = |name: &str| match fields.get(name) {
+                Some(AvroValue::String(s)) => Ok(s.clone()),
+                _ => Err(anyhow::anyhow!("Avro日线缺少字段: {}", name)),
+            };
+            let double = |name: &str| match fields.get(name) {
+                Some(AvroValue::Double(v)) => Ok(*v),
+                _ => Err(anyhow::anyhow!("Avro日线缺少字段: {}", name)),
+            };
+            let date = match fields.get("date") {
+                Some(AvroValue::Date(days)) => NaiveDate::from_ymd_opt(1970, 1, 1)
+                    .unwrap()
+                    .checked_add_signed(chrono::Duration::days(*days as i64))
+                    .ok_or_else(|| anyhow::anyhow!("Avro日线日期越界: {}", days))?,
+                _ => return Err(anyhow::anyhow!("Avro日线缺少字段: date")),
+            };
+            let volume = match fields.get("volume") {
+                Some(AvroValue::Long(v)) => *v as u64,
+                _ => return Err(anyhow::anyhow!("Avro日线缺少字段: volume")),
+            };
+            Ok(TDXDayRecord {
+                date,
+                symbol: string("symbol")?,
+                open: double("open")?,
+                high: double("high")?,
+                low: double("low")?,
+                close: double("close")?,
+                volume,
+                amount: double("amount")?,
+                market: string("market")?,
+            })
+        }
+    }
+}
+
+fn bar_schema() -> &'static Schema {
+    static SCHEMA: std::sync::OnceLock<Schema> = std::sync::OnceLock::new();
+    SCHEMA.get_or_init(|| Schema::parse_str(BAR_AVRO_SCHEMA).expect("内置Avro模式无效"))
+}
+
+/// 统计投递结果的生产者上下文
+#[derive(Default)]
+struct DeliveryStats {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl ClientContext for DeliveryStats {}
+
+impl ProducerContext for DeliveryStats {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        match result {
+            Ok(_) => {
+                self.delivered.fetch_add(1, Ordering::Relaxed);
+            }
+            Err((e, message)) => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+                let error = format!("{}: {}", message.topic(), e);
+                warn!("Kafka消息投递失败: {}", error);
+                *self.last_error.lock().unwrap_or_else(|e| e.into_inner()) = Some(error);
+            }
+        }
+    }
+}
+
+/// Kafka日线与事件生产者
+///
+/// 生产者在第一次发送时创建；`send_*` 只把消息放入本地队列，
+/// [`KafkaProducer::flush`] 等待确认并报告此前的投递失败。
+pub struct KafkaProducer {
+    config: ClientConfig,
+    producer: Mutex<Option<BaseProducer<DeliveryStats>>>,
+    bar_topic: String,
+    event_topic: String,
+    key: KeyStrategy,
+    format: ValueFormat,
+    flush_timeout: Duration,
+    reported_failures: AtomicU64,
+}
+
+impl std::fmt::Debug for KafkaProducer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaProducer")
+            .field("bar_topic", &self.bar_topic)
+            .field("event_topic", &self.event_topic)
+            .field("key", &self.key)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+impl KafkaProducer {
+    /// 创建生产者，`brokers` 形如 `host1:9092,host2:9092`；此时不连接服务器
+    pub fn new(brokers: &str) -> Self {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", brokers)
+            .set("enable.idempotence", "true")
+            .set("compression.type", "zstd")
+            .set("linger.ms", "20");
+        Self {
+            config,
+            producer: Mutex::new(None),
+            bar_topic: "pulse_trader.bars".to_string(),
+            event_topic: "pulse_trader.events".to_string(),
+            key: KeyStrategy::default(),
+            format: ValueFormat::default(),
+            flush_timeout: Duration::from_secs(30),
+            reported_failures: AtomicU64::new(0),
+        }
+    }
+
+    /// 设置日线主题（默认 `pulse_trader.bars`）
+    pub fn with_bar_topic(mut self, topic: &str) -> Self {
+        self.bar_topic = topic.to_string();
+        self
+    }
+
+    /// 设置流水线事件主题（默认 `pulse_trader.events`）
+    pub fn with_event_topic(mut self, topic: &str) -> Self {
+        self.event_topic = topic.to_string();
+        self
+    }
+
+    /// 设置消息键的生成方式
+    pub fn with_key(mut self, key: KeyStrategy) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// 设置日线消息体格式
+    pub fn with_format(mut self, format: ValueFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// 设置刷新时等待确认的超时
+    pub fn with_flush_timeout(mut self, timeout: Duration) -> Self {
+        self.flush_timeout = timeout;
+        self
+    }
+
+    /// 设置librdkafka配置项（如 `security.protocol`、`sasl.username`）
+    pub fn with_config(mut self, key: &str, value: &str) -> Self {
+        self.config.set(key, value);
+        self
+    }
+
+    /// 发送一批日线，返回放入发送队列的条数
+    pub fn send_bars(&self, records: &[TDXDayRecord]) -> Result<usize> {
+        let headers = OwnedHeaders::new().insert(Header {
+            key: FORMAT_HEADER,
+            value: Some(self.format.name()),
+        });
+        let mut producer = self.producer()?;
+        let producer = producer.as_mut().unwrap();
+        for record in records {
+            let payload = encode_bar(record, self.format)?;
+            let key = self.key.key(record);
+            let mut message = BaseRecord::<str, [u8]>::to(&self.bar_topic)
+                .payload(&payload)
+                .headers(headers.clone());
+            if let Some(key) = &key {
+                message = message.key(key.as_str());
+            }
+            send(producer, message)?;
+        }
+        Ok(records.len())
+    }
+
+    /// 发送一个流水线事件，事件类型名称作为消息键
+    pub fn send_event(&self, event: &PipelineEvent) -> Result<()> {
+        let payload = serde_json::to_vec(event).context("流水线事件JSON编码失败")?;
+        let headers = OwnedHeaders::new().insert(Header {
+            key: FORMAT_HEADER,
+            value: Some("json"),
+        });
+        let mut producer = self.producer()?;
+        let message = BaseRecord::to(&self.event_topic)
+            .key(event.name())
+            .payload(&payload)
+            .headers(headers);
+        send(producer.as_mut().unwrap(), message)
+    }
+
+    /// 等待已发送的消息全部确认；此前有消息投递失败时返回错误
+    pub fn flush(&self) -> Result<()> {
+        let producer = self.lock();
+        let Some(producer) = producer.as_ref() else {
+            return Ok(());
+        };
+        producer
+            .flush(self.flush_timeout)
+            .map_err(|e| anyhow::anyhow!("Kafka刷新超时或失败: {}", e))?;
+        let stats = producer.context();
+        let failed = stats.failed.load(Ordering::Relaxed);
+        let reported = self.reported_failures.swap(failed, Ordering::Relaxed);
+        if failed > reported {
+            let last_error = stats
+                .last_error
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+                .unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "{}条Kafka消息投递失败，最后一个错误: {}",
+                failed - reported,
+                last_error
+            ));
+        }
+        Ok(())
+    }
+
+    /// 已确认投递的消息数（日线与事件合计）
+    pub fn delivered(&self) -> u64 {
+        self.lock().as_ref().map_or(0, |producer| {
+            producer.context().delivered.load(Ordering::Relaxed)
+        })
+    }
+
+    fn producer(&self) -> Result<MutexGuard<'_, Option<BaseProducer<DeliveryStats>>>> {
+        let mut producer = self.lock();
+        if producer.is_none() {
+            *producer = Some(
+                self.config
+                    .create_with_context(DeliveryStats::default())
+                    .context("创建Kafka生产者失败")?,
+            );
+        }
+        Ok(producer)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<BaseProducer<DeliveryStats>>> {
+        self.producer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 放入发送队列，本地队列已满时处理投递回调后重试
+fn send<K, P>(
+    producer: &BaseProducer<DeliveryStats>,
+    mut message: BaseRecord<'_, K, P>,
+) -> Result<()>
+where
+    K: rdkafka::message::ToBytes + ?Sized,
+    P: rdkafka::message::ToBytes + ?Sized,
+{
+    loop {
+        match producer.send(message) {
+            Ok(()) => {
+                producer.poll(Duration::ZERO);
+                return Ok(());
+            }
+            Err((e, returned)) if e.rdkafka_error_code() == Some(RDKafkaErrorCode::QueueFull) => {
+                producer.poll(Duration::from_millis(100));
+                message = returned;
+            }
+            Err((e, returned)) => {
+                return Err(anyhow::anyhow!("发送到{}失败: {}", returned.topic, e));
+            }
+        }
+    }
+}
+
+/// 流水线写入：每个批次的日线发送到日线主题并发送 `batch_written` 事件，
+/// 刷新时等待确认并发送 `flushed` 事件
+impl PipelineSink for KafkaProducer {
+    fn write(&mut self, batch: &[EnhancedDayRecord]) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let bars: Vec<TDXDayRecord> = batch.iter().map(|r| r.base_record.clone()).collect();
+        self.send_bars(&bars)?;
+        let symbols: HashSet<(&str, &str)> = bars
+            .iter()
+            .map(|bar| (bar.symbol.as_str(), bar.market.as_str()))
+            .collect();
+        self.send_event(&PipelineEvent::BatchWritten {
+            records: bars.len(),
+            symbols: symbols.len(),
+        })
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        KafkaProducer::flush(self)?;
+        self.send_event(&PipelineEvent::Flushed {
+            bars_delivered: self.delivered(),
+        })?;
+        KafkaProducer::flush(self)
+    }
+}
+
+/// 回放后的日线，按（代码, 市场）分组并按日期排序
+type BarIndex = BTreeMap<(String, String), BTreeMap<NaiveDate, TDXDayRecord>>;
+
+/// 按（代码, 市场, 日期）去重建立索引，后出现的日线覆盖先出现的
+fn index_bars(bars: impl IntoIterator<Item = TDXDayRecord>) -> BarIndex {
+    let mut index = BarIndex::new();
+    for bar in bars {
+        index
+            .entry((bar.symbol.clone(), bar.market.clone()))
+            .or_default()
+            .insert(bar.date, bar);
+    }
+    index
+}
+
+/// 从Kafka主题回放日线的数据源
+pub struct KafkaSource {
+    config: ClientConfig,
+    topic: String,
+    name: String,
+    timeout: Duration,
+    index: Mutex<Option<Arc<BarIndex>>>,
+}
+
+impl std::fmt::Debug for KafkaSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaSource")
+            .field("topic", &self.topic)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl KafkaSource {
+    /// 创建回放数据源；此时不连接服务器
+    pub fn new(brokers: &str, topic: &str) -> Self {
+        let mut config = ClientConfig::new();
+        config
+            .set("bootstrap.servers", brokers)
+            .set("group.id", "pulse_trader_replay")
+            .set("enable.auto.commit", "false")
+            .set("enable.partition.eof", "false");
+        Self {
+            config,
+            topic: topic.to_string(),
+            name: format!("kafka:{}", topic),
+            timeout: Duration::from_secs(60),
+            index: Mutex::new(None),
+        }
+    }
+
+    /// 设置回放超时（获取元数据和读到高水位的总时间）
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 设置librdkafka配置项
+    pub fn with_config(mut self, key: &str, value: &str) -> Self {
+        self.config.set(key, value);
+        self
+    }
+
+    /// 丢弃已回放的数据，下次访问时重新读取主题
+    pub fn refresh(&self) {
+        *self.index.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn bars(&self) -> Result<Arc<BarIndex>> {
+        let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = index.as_ref() {
+            return Ok(index.clone());
+        }
+        let replayed = Arc::new(index_bars(self.replay()?));
+        *index = Some(replayed.clone());
+        Ok(replayed)
+    }
+
+    /// 从所有分区的最早位置读到当前高水位
+    fn replay(&self) -> Result<Vec<TDXDayRecord>> {
+        let deadline = Instant::now() + self.timeout;
+        let consumer: BaseConsumer = self.config.create().context("创建Kafka消费者失败")?;
+        let metadata = consumer
+            .fetch_metadata(Some(&self.topic), self.timeout)
+            .with_context(|| format!("获取主题{}的元数据失败", self.topic))?;
+        let topic = metadata
+            .topics()
+            .iter()
+            .find(|t| t.name() == self.topic)
+            .filter(|t| t.error().is_none() && !t.partitions().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Kafka主题不存在: {}", self.topic))?;
+
+        // 每个分区待读到的位置（不含）
+        let mut assignment = TopicPartitionList::new();
+        let mut pending = BTreeMap::new();
+        for partition in topic.partitions() {
+            let (low, high) = consumer
+                .fetch_watermarks(&self.topic, partition.id(), remaining(deadline))
+                .with_context(|| format!("获取{}[{}]的水位失败", self.topic, partition.id()))?;
+            if high > low {
+                assignment.add_partition_offset(
+                    &self.topic,
+                    partition.id(),
+                    Offset::Offset(low),
+                )?;
+                pending.insert(partition.id(), high);
+            }
+        }
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+        consumer.assign(&assignment)?;
+
+        let mut bars = Vec::new();
+        while !pending.is_empty() {
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "回放主题{}超时，{}个分区未读完",
+                    self.topic,
+                    pending.len()
+                ));
+            }
+            let Some(message) = consumer.poll(Duration::from_millis(200)) else {
+                continue;
+            };
+            let message = message.with_context(|| format!("读取主题{}失败", self.topic))?;
+            match decode_message(&message) {
+                Ok(Some(bar)) => bars.push(bar),
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "跳过无法解码的消息 {}[{}]@{}: {}",
+                    self.topic,
+                    message.partition(),
+                    message.offset(),
+                    e
+                ),
+            }
+            if pending
+                .get(&message.partition())
+                .is_some_and(|&high| message.offset() + 1 >= high)
+            {
+                pending.remove(&message.partition());
+            }
+        }
+        debug!("从主题{}回放{}条日线", self.topic, bars.len());
+        Ok(bars)
+    }
+}
+
+fn remaining(deadline: Instant) -> Duration {
+    deadline.saturating_duration_since(Instant::now())
+}
+
+/// 按消息头中的格式解码日线，空消息（墓碑）返回None
+fn decode_message(message: &BorrowedMessage<'_>) -> Result<Option<TDXDayRecord>> {
+    let Some(payload) = message.payload() else {
+        return Ok(None);
+    };
+    let format = message
+        .headers()
+        .and_then(|headers| {
+            headers
+                .iter()
+                .find(|header| header.key == FORMAT_HEADER)
+                .and_then(|header| header.value)
+        })
+        .map_or(Some(ValueFormat::Json), ValueFormat::from_name)
+        .ok_or_else(|| anyhow::anyhow!("未知的消息格式"))?;
+    decode_bar(payload, format).map(Some)
+}
+
+impl DataSource for KafkaSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        let symbols: BTreeSet<(String, String)> = self.bars()?.keys().cloned().collect();
+        Ok(symbols.into_iter().collect())
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let index = self.bars()?;
+        let key = (symbol.to_string(), market.to_uppercase());
+        Ok(index
+            .get(&key)
+            .map(|bars| {
+                bars.values()
+                    .filter(|bar| range.contains(bar.date))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 11.0,
+            low: 9.5,
+            close,
+            volume: 123_456,
+            amount: 1_234_567.5,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let record = bar("600000", 2, 10.5);
+        for format in [ValueFormat::Json, ValueFormat::Avro] {
+            let payload = encode_bar(&record, format).unwrap();
+            let decoded = decode_bar(&payload, format).unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&record).unwrap()
+            );
+        }
+        let json = encode_bar(&record, ValueFormat::Json).unwrap();
+        let avro = encode_bar(&record, ValueFormat::Avro).unwrap();
+        assert!(avro.len() < json.len());
+        assert!(decode_bar(b"not json", ValueFormat::Json).is_err());
+        assert!(decode_bar(&avro[..5], ValueFormat::Avro).is_err());
+        assert_eq!(ValueFormat::from_name(b"avro"), Some(ValueFormat::Avro));
+        assert_eq!(ValueFormat::from_name(b"xml"), None);
+    }
+
+    #[test]
+    fn test_keys_and_events() {
+        let record = bar("600000", 2, 10.5);
+        assert_eq!(KeyStrategy::Symbol.key(&record).as_deref(), Some("600000"));
+        assert_eq!(
+            KeyStrategy::SymbolMarket.key(&record).as_deref(),
+            Some("600000.SH")
+        );
+        assert_eq!(KeyStrategy::None.key(&record), None);
+
+        let event = PipelineEvent::BatchWritten {
+            records: 10,
+            symbols: 2,
+        };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["event"], event.name());
+        assert_eq!(value["records"], 10);
+    }
+
+    #[test]
+    fn test_index_bars() {
+        let index = index_bars(vec![
+            bar("600000", 3, 10.0),
+            bar("600000", 2, 9.0),
+            bar("600036", 2, 30.0),
+            bar("600000", 3, 10.8),
+        ]);
+        assert_eq!(index.len(), 2);
+        let bars = &index[&("600000".to_string(), "SH".to_string())];
+        let closes: Vec<f64> = bars.values().map(|bar| bar.close).collect();
+        assert_eq!(closes, vec![9.0, 10.8]);
+
+        // 无法连接时回放报错，不缓存结果
+        let source = KafkaSource::new("127.0.0.1:1", "bars")
+            .with_timeout(Duration::from_millis(500))
+            .with_config("socket.connection.setup.timeout.ms", "1000");
+        assert_eq!(source.name(), "kafka:bars");
+        assert!(source.list_symbols().is_err());
+        assert!(source.index.lock().unwrap().is_none());
+    }
+}
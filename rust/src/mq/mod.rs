@@ -0,0 +1,8 @@
+//! 消息队列连接器模块
+//!
+//! 把解析后的日线和流水线事件推送到消息队列，并能从队列回放日线作为数据源。
+//! 目前提供Kafka（`kafka` 特性，librdkafka随crate编译）。
+
+pub mod kafka;
+
+pub use kafka::{KafkaProducer, KafkaSource, KeyStrategy, PipelineEvent, ValueFormat};
@@ -0,0 +1,430 @@
+//! 东方财富/新浪日线下载客户端
+//!
+//! 通过公开的HTTP接口获取A股日线（不复权），供没有安装通达信的用户填充数据仓库，
+//! 也可与本地day文件交叉校验。请求按 `NetPolicy` 限速、超时并在临时错误时退避重试。
+//!
+//! - 东方财富：`push2his.eastmoney.com` K线接口，支持日期范围，成交量单位为手；
+//! - 新浪：`quotes.sina.cn` K线接口，只返回最近1023根，不提供成交额（置为0）。
+//!
+//! 股票列表始终来自东方财富的行情列表接口。
+
+use super::policy::NetPolicy;
+use crate::parsers::TDXDayRecord;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 东方财富K线接口
+const EASTMONEY_KLINE_URL: &str = "https://push2his.eastmoney.com/api/qt/stock/kline/get";
+
+/// 东方财富行情列表接口
+const EASTMONEY_LIST_URL: &str = "https://push2.eastmoney.com/api/qt/clist/get";
+
+/// 新浪K线接口
+const SINA_KLINE_URL: &str =
+    "https://quotes.sina.cn/cn/api/json_v2.php/CN_MarketDataService.getKLineData";
+
+/// 新浪接口单次最多返回的K线数
+const SINA_MAX_BARS: usize = 1023;
+
+/// 沪深A股（沪主板、科创板、深主板、创业板）
+const EASTMONEY_A_SHARES: &str = "m:1+t:2,m:1+t:23,m:0+t:6,m:0+t:80";
+
+/// 日线数据提供方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarProvider {
+    /// 东方财富
+    #[default]
+    Eastmoney,
+    /// 新浪
+    Sina,
+}
+
+/// 东方财富/新浪日线客户端
+#[derive(Debug)]
+pub struct EmClient {
+    http: reqwest::Client,
+    provider: BarProvider,
+    /// 重试、限速与超时策略
+    policy: NetPolicy,
+    /// 同步接口（`DataSource`）使用的运行时，首次使用时创建
+    #[cfg(feature = "processors")]
+    runtime: std::sync::OnceLock<tokio::runtime::Runtime>,
+}
+
+impl EmClient {
+    /// 创建客户端（默认东方财富，同一主机请求间隔200毫秒，按默认策略重试）
+    pub fn new() -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .user_agent("Mozilla/5.0 (compatible; PulseTrader)")
+            .build()
+            .context("无法创建HTTP客户端")?;
+        Ok(Self {
+            http,
+            provider: BarProvider::default(),
+            policy: NetPolicy::new().with_min_interval(Duration::from_millis(200)),
+            #[cfg(feature = "processors")]
+            runtime: std::sync::OnceLock::new(),
+        })
+    }
+
+    /// 设置日线数据提供方
+    pub fn with_provider(mut self, provider: BarProvider) -> Self {
+        self.provider = provider;
+        self
+    }
+
+    /// 设置重试、限速与超时策略
+    pub fn with_policy(mut self, policy: NetPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// 请求策略（可读取重试与超时计数）
+    pub fn policy(&self) -> &NetPolicy {
+        &self.policy
+    }
+
+    /// 获取日线（按日期升序，不复权）
+    pub async fn daily_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        start: Option<NaiveDate>,
+        end: Option<NaiveDate>,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let market = market.to_uppercase();
+        let market_code = eastmoney_market(&market)?;
+        let mut bars = match self.provider {
+            BarProvider::Eastmoney => {
+                let secid = format!("{}.{}", market_code, symbol);
+                let beg = start.map_or("0".to_string(), |d| d.format("%Y%m%d").to_string());
+                let end = end.map_or("20500101".to_string(), |d| d.format("%Y%m%d").to_string());
+                let body = self
+                    .get(
+                        EASTMONEY_KLINE_URL,
+                        &[
+                            ("secid", secid.as_str()),
+                            ("fields1", "f1,f2,f3"),
+                            ("fields2", "f51,f52,f53,f54,f55,f56,f57"),
+                            ("klt", "101"),
+                            ("fqt", "0"),
+                            ("beg", beg.as_str()),
+                            ("end", end.as_str()),
+                        ],
+                    )
+                    .await?;
+                parse_eastmoney_klines(&body, symbol, &market)?
+            }
+            BarProvider::Sina => {
+                let sina_symbol = format!("{}{}", market.to_lowercase(), symbol);
+                let datalen = SINA_MAX_BARS.to_string();
+                let body = self
+                    .get(
+                        SINA_KLINE_URL,
+                        &[
+                            ("symbol", sina_symbol.as_str()),
+                            ("scale", "240"),
+                            ("ma", "no"),
+                            ("datalen", datalen.as_str()),
+                        ],
+                    )
+                    .await?;
+                parse_sina_klines(&body, symbol, &market)?
+            }
+        };
+
+        bars.retain(|bar| {
+            start.is_none_or(|start| bar.date >= start) && end.is_none_or(|end| bar.date <= end)
+        });
+        Ok(bars)
+    }
+
+    /// 沪深A股列表（代码, 市场），按代码排序
+    pub async fn stock_list(&self) -> Result<Vec<(String, String)>> {
+        let body = self
+            .get(
+                EASTMONEY_LIST_URL,
+                &[
+                    ("pn", "1"),
+                    ("pz", "10000"),
+                    ("po", "0"),
+                    ("np", "1"),
+                    ("fid", "f12"),
+                    ("fs", EASTMONEY_A_SHARES),
+                    ("fields", "f12,f13"),
+                ],
+            )
+            .await?;
+        parse_eastmoney_list(&body)
+    }
+
+    /// 按请求策略执行GET请求，返回响应正文
+    async fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<String> {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        self.policy
+            .run(&host, &format!("请求{}", url), || self.send(url, query))
+            .await
+    }
+
+    async fn send(&self, url: &str, query: &[(&str, &str)]) -> Result<String> {
+        debug!("GET {} {:?}", url, query);
+        let response = self
+            .http
+            .get(url)
+            .query(query)
+            .send()
+            .await
+            .context("发送请求失败")?
+            .error_for_status()
+            .context("服务器返回错误状态")?;
+        response.text().await.context("读取响应失败")
+    }
+
+    #[cfg(feature = "processors")]
+    fn block_on<F: std::future::Future>(&self, future: F) -> Result<F::Output> {
+        let runtime = match self.runtime.get() {
+            Some(runtime) => runtime,
+            None => {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .context("无法创建运行时")?;
+                self.runtime.get_or_init(|| runtime)
+            }
+        };
+        Ok(runtime.block_on(future))
+    }
+}
+
+/// 同步接口，内部在独立运行时上阻塞执行；已在异步上下文中时应直接调用异步方法
+#[cfg(feature = "processors")]
+impl crate::source::DataSource for EmClient {
+    fn name(&self) -> &str {
+        match self.provider {
+            BarProvider::Eastmoney => "eastmoney",
+            BarProvider::Sina => "sina",
+        }
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        self.block_on(self.stock_list())?
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &crate::source::DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        self.block_on(self.daily_bars(symbol, market, range.start, range.end))?
+    }
+}
+
+/// 东方财富的市场编号
+fn eastmoney_market(market: &str) -> Result<u8> {
+    match market {
+        "SH" => Ok(1),
+        "SZ" => Ok(0),
+        _ => Err(anyhow::anyhow!("不支持的市场: {}", market)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EastmoneyResponse<T> {
+    data: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EastmoneyKlines {
+    #[serde(default)]
+    klines: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EastmoneyList {
+    diff: EastmoneyDiff,
+}
+
+/// 行情列表在不同参数下返回数组或以序号为键的对象
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EastmoneyDiff {
+    List(Vec<EastmoneyStock>),
+    Map(std::collections::BTreeMap<String, EastmoneyStock>),
+}
+
+#[derive(Debug, Deserialize)]
+struct EastmoneyStock {
+    /// 代码
+    f12: String,
+    /// 市场编号
+    f13: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct SinaKline {
+    day: String,
+    open: String,
+    high: String,
+    low: String,
+    close: String,
+    volume: String,
+}
+
+/// 解析东方财富K线响应，每根K线为“日期,开,收,高,低,成交量(手),成交额”
+fn parse_eastmoney_klines(body: &str, symbol: &str, market: &str) -> Result<Vec<TDXDayRecord>> {
+    let response: EastmoneyResponse<EastmoneyKlines> =
+        serde_json::from_str(body).context("东方财富K线响应格式错误")?;
+    let Some(data) = response.data else {
+        // 代码不存在时data为null
+        return Ok(Vec::new());
+    };
+
+    data.klines
+        .iter()
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            if fields.len() < 7 {
+                return Err(anyhow::anyhow!("东方财富K线字段不足: {}", line));
+            }
+            let number = |i: usize| -> Result<f64> {
+                fields[i]
+                    .parse()
+                    .with_context(|| format!("东方财富K线数值错误: {}", line))
+            };
+            Ok(TDXDayRecord {
+                date: NaiveDate::parse_from_str(fields[0], "%Y-%m-%d")
+                    .with_context(|| format!("东方财富K线日期错误: {}", line))?,
+                symbol: symbol.to_string(),
+                open: number(1)?,
+                high: number(3)?,
+                low: number(4)?,
+                close: number(2)?,
+                volume: (number(5)? * 100.0).round() as u64,
+                amount: number(6)?,
+                market: market.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 解析东方财富行情列表响应
+fn parse_eastmoney_list(body: &str) -> Result<Vec<(String, String)>> {
+    let response: EastmoneyResponse<EastmoneyList> =
+        serde_json::from_str(body).context("东方财富行情列表响应格式错误")?;
+    let stocks = match response.data.map(|data| data.diff) {
+        Some(EastmoneyDiff::List(stocks)) => stocks,
+        Some(EastmoneyDiff::Map(stocks)) => stocks.into_values().collect(),
+        None => Vec::new(),
+    };
+
+    let mut list: Vec<(String, String)> = stocks
+        .into_iter()
+        .filter_map(|stock| {
+            let market = match stock.f13 {
+                1 => "SH",
+                0 => "SZ",
+                _ => return None,
+            };
+            Some((stock.f12, market.to_string()))
+        })
+        .collect();
+    list.sort();
+    Ok(list)
+}
+
+/// 解析新浪K线响应（成交量单位为股）
+fn parse_sina_klines(body: &str, symbol: &str, market: &str) -> Result<Vec<TDXDayRecord>> {
+    // 代码不存在时返回null
+    let klines: Option<Vec<SinaKline>> =
+        serde_json::from_str(body).context("新浪K线响应格式错误")?;
+
+    klines
+        .unwrap_or_default()
+        .iter()
+        .map(|bar| {
+            let number = |value: &str| -> Result<f64> {
+                value
+                    .parse()
+                    .with_context(|| format!("新浪K线数值错误: {} {}", bar.day, value))
+            };
+            Ok(TDXDayRecord {
+                date: NaiveDate::parse_from_str(&bar.day, "%Y-%m-%d")
+                    .with_context(|| format!("新浪K线日期错误: {}", bar.day))?,
+                symbol: symbol.to_string(),
+                open: number(&bar.open)?,
+                high: number(&bar.high)?,
+                low: number(&bar.low)?,
+                close: number(&bar.close)?,
+                volume: number(&bar.volume)?.round() as u64,
+                amount: 0.0,
+                market: market.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_eastmoney_klines() {
+        let body = r#"{"rc":0,"data":{"code":"600000","market":1,"name":"浦发银行","klines":[
+            "2024-01-02,6.60,6.62,6.65,6.57,386548,255000000.00",
+            "2024-01-03,6.61,6.64,6.66,6.59,312000,206800000.00"]}}"#;
+        let bars = parse_eastmoney_klines(body, "600000", "SH").unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert_eq!((bars[0].open, bars[0].close), (6.60, 6.62));
+        assert_eq!((bars[0].high, bars[0].low), (6.65, 6.57));
+        assert_eq!(bars[0].volume, 38_654_800);
+        assert_eq!(bars[1].amount, 206_800_000.0);
+
+        assert!(
+            parse_eastmoney_klines(r#"{"rc":0,"data":null}"#, "600000", "SH")
+                .unwrap()
+                .is_empty()
+        );
+        let truncated = r#"{"data":{"klines":["2024-01-02,6.60"]}}"#;
+        assert!(parse_eastmoney_klines(truncated, "600000", "SH").is_err());
+    }
+
+    #[test]
+    fn test_parse_sina_klines() {
+        let body = r#"[{"day":"2024-01-02","open":"6.600","high":"6.650","low":"6.570",
+            "close":"6.620","volume":"38654800"}]"#;
+        let bars = parse_sina_klines(body, "600000", "SH").unwrap();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].close, 6.62);
+        assert_eq!(bars[0].volume, 38_654_800);
+        assert_eq!(bars[0].amount, 0.0);
+
+        assert!(parse_sina_klines("null", "600000", "SH")
+            .unwrap()
+            .is_empty());
+        assert!(parse_sina_klines("<html>", "600000", "SH").is_err());
+    }
+
+    #[test]
+    fn test_parse_eastmoney_list() {
+        let array = r#"{"data":{"total":3,"diff":[
+            {"f12":"600000","f13":1},{"f12":"000001","f13":0},{"f12":"830799","f13":0}]}}"#;
+        let list = parse_eastmoney_list(array).unwrap();
+        assert_eq!(list[0], ("000001".to_string(), "SZ".to_string()));
+        assert_eq!(list.len(), 3);
+
+        let object = r#"{"data":{"diff":{"0":{"f12":"600000","f13":1}}}}"#;
+        assert_eq!(
+            parse_eastmoney_list(object).unwrap(),
+            vec![("600000".to_string(), "SH".to_string())]
+        );
+        assert!(eastmoney_market("BJ").is_err());
+    }
+}
@@ -0,0 +1,9 @@
+//! 网络数据源模块
+
+pub mod em_client;
+pub mod policy;
+pub mod tdx_client;
+
+pub use em_client::{BarProvider, EmClient};
+pub use policy::{NetMetrics, NetPolicy, NetStats, RateLimiter, RetryPolicy};
+pub use tdx_client::*;
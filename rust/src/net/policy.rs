@@ -0,0 +1,378 @@
+//! 网络请求策略模块
+//!
+//! 通达信行情服务器与HTTP数据源共用的请求策略：指数退避加随机抖动的重试、
+//! 按主机的限速和超时包装。单次临时错误（超时、连接中断、5xx、429）由策略重试吸收，
+//! 重试、超时和最终失败次数记录在 `NetMetrics` 中，而不是直接让处理流水线失败。
+
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// 超时错误
+#[derive(Debug, thiserror::Error)]
+#[error("{what}超时（{timeout:?}）")]
+pub struct TimeoutError {
+    /// 超时的操作
+    pub what: String,
+    /// 超时时间
+    pub timeout: Duration,
+}
+
+/// 为异步操作加上超时，超时时返回 `TimeoutError`
+pub async fn with_timeout<T, F>(timeout: Duration, what: &str, future: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    tokio::time::timeout(timeout, future).await.map_err(|_| {
+        anyhow::Error::new(TimeoutError {
+            what: what.to_string(),
+            timeout,
+        })
+    })?
+}
+
+/// 是否为可重试的临时错误：HTTP 4xx（429除外）视为永久错误，其余均可重试
+pub fn is_transient(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if let Some(status) = e.status() {
+                return status.is_server_error()
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+            }
+        }
+    }
+    true
+}
+
+/// 重试策略：第n次重试前等待 `base_delay * 2^n`，不超过 `max_delay`，再叠加随机抖动
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// 最大重试次数（不含首次请求）
+    pub max_retries: u32,
+    /// 首次重试前的等待时间
+    pub base_delay: Duration,
+    /// 单次等待时间上限
+    pub max_delay: Duration,
+    /// 抖动比例（0~1），实际等待在 `delay * (1 - jitter)` 到 `delay` 之间
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// 默认重试3次，从500毫秒开始退避，上限30秒，抖动50%
+    pub fn new() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+        }
+    }
+
+    /// 不重试
+    pub fn none() -> Self {
+        Self::new().with_max_retries(0)
+    }
+
+    /// 设置最大重试次数
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// 设置首次重试前的等待时间和等待上限
+    pub fn with_delay(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// 设置抖动比例
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 第 `attempt` 次重试（从0开始）前的等待时间
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        if self.jitter <= 0.0 {
+            return backoff;
+        }
+        // 每次创建的RandomState带随机种子，足够作为抖动来源
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        backoff.mul_f64(1.0 - self.jitter * random)
+    }
+}
+
+/// 按主机限速：同一主机两次请求之间至少间隔 `min_interval`
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    min_interval: Duration,
+    /// 主机 -> 下一次允许请求的时间
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    /// 创建限速器，间隔为0时不限速
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            next_slot: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 等待轮到 `host` 发起请求，返回等待的时间
+    pub async fn acquire(&self, host: &str) -> Duration {
+        if self.min_interval.is_zero() {
+            return Duration::ZERO;
+        }
+
+        // 先预约时间片再释放锁等待，不同主机互不阻塞
+        let now = Instant::now();
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = next_slot
+                .get(host)
+                .copied()
+                .map_or(now, |next| next.max(now));
+            next_slot.insert(host.to_string(), slot + self.min_interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+        slot - now
+    }
+}
+
+/// 网络请求计数
+#[derive(Debug, Default)]
+pub struct NetMetrics {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    timeouts: AtomicU64,
+    failures: AtomicU64,
+    throttled: AtomicU64,
+}
+
+/// 网络请求计数快照
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetStats {
+    /// 发出的请求数（含重试）
+    pub requests: u64,
+    /// 重试次数
+    pub retries: u64,
+    /// 超时次数
+    pub timeouts: u64,
+    /// 重试用尽或遇到永久错误后失败的操作数
+    pub failures: u64,
+    /// 因限速而等待的请求数
+    pub throttled: u64,
+}
+
+impl NetMetrics {
+    /// 当前计数
+    pub fn snapshot(&self) -> NetStats {
+        NetStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 网络请求策略：重试、限速、超时与计数
+#[derive(Debug, Clone)]
+pub struct NetPolicy {
+    retry: RetryPolicy,
+    timeout: Duration,
+    limiter: Arc<RateLimiter>,
+    metrics: Arc<NetMetrics>,
+}
+
+impl Default for NetPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NetPolicy {
+    /// 默认重试策略，单次超时10秒，不限速
+    pub fn new() -> Self {
+        Self {
+            retry: RetryPolicy::new(),
+            timeout: Duration::from_secs(10),
+            limiter: Arc::new(RateLimiter::new(Duration::ZERO)),
+            metrics: Arc::new(NetMetrics::default()),
+        }
+    }
+
+    /// 设置重试策略
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// 设置单次尝试的超时时间
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 设置同一主机两次请求的最小间隔
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.limiter = Arc::new(RateLimiter::new(min_interval));
+        self
+    }
+
+    /// 重试策略
+    pub fn retry(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// 单次尝试的超时时间
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// 请求计数（克隆的策略共享同一份计数和限速状态）
+    pub fn metrics(&self) -> &NetMetrics {
+        &self.metrics
+    }
+
+    /// 按策略执行操作：每次尝试前限速并加上超时，临时错误按退避重试
+    pub async fn run<T, F, Fut>(&self, host: &str, what: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            if !self.limiter.acquire(host).await.is_zero() {
+                self.metrics.throttled.fetch_add(1, Ordering::Relaxed);
+            }
+            self.metrics.requests.fetch_add(1, Ordering::Relaxed);
+
+            let error = match with_timeout(self.timeout, what, operation()).await {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            if error.downcast_ref::<TimeoutError>().is_some() {
+                self.metrics.timeouts.fetch_add(1, Ordering::Relaxed);
+            }
+
+            if attempt >= self.retry.max_retries || !is_transient(&error) {
+                self.metrics.failures.fetch_add(1, Ordering::Relaxed);
+                return Err(error.context(format!("{}失败，已重试{}次", what, attempt)));
+            }
+
+            let delay = self.retry.delay(attempt);
+            attempt += 1;
+            self.metrics.retries.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "{}（{}）失败，{:?}后第{}次重试: {:#}",
+                what, host, delay, attempt, error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    #[test]
+    fn test_retry_delay() {
+        let retry = RetryPolicy::new()
+            .with_delay(Duration::from_millis(100), Duration::from_millis(500))
+            .with_jitter(0.0);
+        assert_eq!(retry.delay(0), Duration::from_millis(100));
+        assert_eq!(retry.delay(2), Duration::from_millis(400));
+        assert_eq!(retry.delay(10), Duration::from_millis(500));
+
+        let jittered = retry.with_jitter(0.5);
+        for attempt in 0..5 {
+            let delay = jittered.delay(attempt);
+            assert!(delay <= retry.delay(attempt));
+            assert!(delay >= retry.delay(attempt) / 2);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_retries_transient_errors() {
+        let policy = NetPolicy::new()
+            .with_retry(RetryPolicy::new().with_max_retries(2))
+            .with_timeout(Duration::from_secs(1));
+        let calls = AtomicU32::new(0);
+
+        // 第一次超时，第二次出错，第三次成功
+        let value = policy
+            .run("example.com", "测试请求", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        Ok(0)
+                    }
+                    1 => Err(anyhow::anyhow!("连接被重置")),
+                    n => Ok(n),
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 2);
+        let stats = policy.metrics().snapshot();
+        assert_eq!(
+            (
+                stats.requests,
+                stats.retries,
+                stats.timeouts,
+                stats.failures
+            ),
+            (3, 2, 1, 0)
+        );
+
+        let result: Result<()> = policy
+            .run("example.com", "测试请求", || async {
+                Err(anyhow::anyhow!("服务不可用"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(policy.metrics().snapshot().failures, 1);
+        assert_eq!(policy.metrics().snapshot().requests, 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_per_host() {
+        let limiter = RateLimiter::new(Duration::from_millis(100));
+        let start = Instant::now();
+        assert_eq!(limiter.acquire("a").await, Duration::ZERO);
+        assert_eq!(limiter.acquire("b").await, Duration::ZERO);
+        assert_eq!(limiter.acquire("a").await, Duration::from_millis(100));
+        assert_eq!(limiter.acquire("a").await, Duration::from_millis(100));
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+
+        let unlimited = RateLimiter::new(Duration::ZERO);
+        assert_eq!(unlimited.acquire("a").await, Duration::ZERO);
+        assert_eq!(unlimited.acquire("a").await, Duration::ZERO);
+    }
+}
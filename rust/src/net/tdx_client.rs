@@ -0,0 +1,944 @@
+//! 通达信行情服务器客户端
+//!
+//! 实现通达信行情服务器（hq，默认端口7709）的二进制协议：
+//! 获取证券列表、实时行情和历史K线，用于补齐本地缺失的数据，
+//! 以及将本地day文件与服务器数据比对校验。
+//!
+//! 协议要点：请求为固定格式的小端二进制包；响应为16字节包头加包体，
+//! 包头中压缩长度与原始长度不同时包体为zlib压缩；价格以变长整数差分编码，
+//! 成交量/成交额以通达信自定义浮点格式编码。
+
+use super::policy::{with_timeout, NetPolicy};
+use crate::parsers::TDXDayRecord;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use flate2::read::ZlibDecoder;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// 常用行情服务器地址（服务器列表可能变动，建议由配置提供）
+pub const DEFAULT_SERVERS: &[&str] = &[
+    "119.147.212.81:7709",
+    "114.80.63.12:7709",
+    "180.153.39.51:7709",
+];
+
+/// 单次请求最多返回的K线条数
+pub const MAX_BARS_PER_REQUEST: u16 = 800;
+
+/// 单次请求最多返回的证券数
+pub const SECURITIES_PER_PAGE: u16 = 1000;
+
+/// 响应包头长度
+const RESPONSE_HEADER_SIZE: usize = 16;
+
+/// 连接握手包
+const SETUP_PACKETS: [&[u8]; 3] = [
+    &[
+        0x0c, 0x02, 0x18, 0x93, 0x00, 0x01, 0x03, 0x00, 0x03, 0x00, 0x0d, 0x00, 0x01,
+    ],
+    &[
+        0x0c, 0x02, 0x18, 0x94, 0x00, 0x01, 0x03, 0x00, 0x03, 0x00, 0x0d, 0x00, 0x02,
+    ],
+    &[
+        0x0c, 0x03, 0x18, 0x99, 0x00, 0x01, 0x20, 0x00, 0x20, 0x00, 0xdb, 0x0f, 0xd5, 0xd0, 0xc9,
+        0xcc, 0xd6, 0xa4, 0xa8, 0xaf, 0x00, 0x00, 0x00, 0x8f, 0xc2, 0x25, 0x40, 0x13, 0x00, 0x00,
+        0xd5, 0x00, 0xc9, 0xcc, 0xbd, 0xf0, 0xd7, 0xea, 0x00, 0x00, 0x00, 0x02,
+    ],
+];
+
+/// 市场
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TdxMarket {
+    /// 深圳
+    SZ,
+    /// 上海
+    SH,
+}
+
+impl TdxMarket {
+    /// 协议中的市场编号
+    pub fn code(self) -> u8 {
+        match self {
+            TdxMarket::SZ => 0,
+            TdxMarket::SH => 1,
+        }
+    }
+
+    /// 市场代码字符串（与 `TDXDayRecord::market` 一致）
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TdxMarket::SZ => "SZ",
+            TdxMarket::SH => "SH",
+        }
+    }
+
+    /// 从市场代码字符串解析
+    pub fn parse(market: &str) -> Result<Self> {
+        match market.to_uppercase().as_str() {
+            "SZ" => Ok(TdxMarket::SZ),
+            "SH" => Ok(TdxMarket::SH),
+            _ => Err(anyhow::anyhow!("不支持的市场: {}", market)),
+        }
+    }
+}
+
+/// K线周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KLineCategory {
+    /// 5分钟
+    Min5,
+    /// 15分钟
+    Min15,
+    /// 30分钟
+    Min30,
+    /// 60分钟
+    Hour,
+    /// 日线
+    Daily,
+    /// 周线
+    Weekly,
+    /// 月线
+    Monthly,
+    /// 1分钟
+    Min1,
+    /// 季线
+    Quarterly,
+    /// 年线
+    Yearly,
+}
+
+impl KLineCategory {
+    /// 协议中的周期编号
+    pub fn code(self) -> u16 {
+        match self {
+            KLineCategory::Min5 => 0,
+            KLineCategory::Min15 => 1,
+            KLineCategory::Min30 => 2,
+            KLineCategory::Hour => 3,
+            KLineCategory::Daily => 4,
+            KLineCategory::Weekly => 5,
+            KLineCategory::Monthly => 6,
+            KLineCategory::Min1 => 8,
+            KLineCategory::Quarterly => 10,
+            KLineCategory::Yearly => 11,
+        }
+    }
+
+    /// 是否为分钟级周期（日期字段带时分）
+    pub fn is_intraday(self) -> bool {
+        matches!(
+            self,
+            KLineCategory::Min5
+                | KLineCategory::Min15
+                | KLineCategory::Min30
+                | KLineCategory::Hour
+                | KLineCategory::Min1
+        )
+    }
+}
+
+/// 证券信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityInfo {
+    /// 证券代码
+    pub code: String,
+    /// 证券名称
+    pub name: String,
+    /// 市场
+    pub market: TdxMarket,
+    /// 每手股数
+    pub volume_unit: u16,
+    /// 价格小数位数
+    pub decimal_point: u8,
+    /// 昨收价
+    pub pre_close: f64,
+}
+
+/// 盘口档位
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QuoteLevel {
+    /// 价格
+    pub price: f64,
+    /// 挂单量（手）
+    pub volume: i64,
+}
+
+/// 实时行情
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quote {
+    /// 证券代码
+    pub code: String,
+    /// 市场
+    pub market: TdxMarket,
+    /// 最新价
+    pub price: f64,
+    /// 昨收价
+    pub last_close: f64,
+    /// 开盘价
+    pub open: f64,
+    /// 最高价
+    pub high: f64,
+    /// 最低价
+    pub low: f64,
+    /// 成交量（手）
+    pub volume: i64,
+    /// 现量（手）
+    pub current_volume: i64,
+    /// 成交额（元）
+    pub amount: f64,
+    /// 买盘（五档）
+    pub bids: Vec<QuoteLevel>,
+    /// 卖盘（五档）
+    pub asks: Vec<QuoteLevel>,
+}
+
+/// 历史K线
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KLine {
+    /// 时间（日线及以上周期为收盘时间15:00）
+    pub datetime: NaiveDateTime,
+    /// 开盘价
+    pub open: f64,
+    /// 最高价
+    pub high: f64,
+    /// 最低价
+    pub low: f64,
+    /// 收盘价
+    pub close: f64,
+    /// 成交量
+    pub volume: f64,
+    /// 成交额（元）
+    pub amount: f64,
+}
+
+impl KLine {
+    /// 转换为日线记录
+    pub fn to_day_record(&self, symbol: &str, market: TdxMarket) -> TDXDayRecord {
+        TDXDayRecord {
+            date: self.datetime.date(),
+            symbol: symbol.to_string(),
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume.max(0.0).round() as u64,
+            amount: self.amount,
+            market: market.as_str().to_string(),
+        }
+    }
+}
+
+/// 本地数据与服务器数据的差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordMismatch {
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 本地记录（本地缺失时为None）
+    pub local: Option<TDXDayRecord>,
+    /// 服务器记录（服务器缺失时为None）
+    pub remote: Option<TDXDayRecord>,
+}
+
+/// 通达信行情服务器客户端
+#[derive(Debug)]
+pub struct TdxClient {
+    /// TCP连接
+    stream: TcpStream,
+    /// 单次请求超时
+    timeout: Duration,
+}
+
+impl TdxClient {
+    /// 连接行情服务器并完成握手
+    pub async fn connect(addr: &str) -> Result<Self> {
+        Self::connect_with_timeout(addr, Duration::from_secs(10)).await
+    }
+
+    /// 以指定超时连接行情服务器
+    pub async fn connect_with_timeout(addr: &str, timeout: Duration) -> Result<Self> {
+        let stream = with_timeout(timeout, &format!("连接行情服务器{}", addr), async {
+            TcpStream::connect(addr)
+                .await
+                .with_context(|| format!("无法连接行情服务器: {}", addr))
+        })
+        .await?;
+        stream.set_nodelay(true)?;
+
+        let mut client = Self { stream, timeout };
+        for packet in SETUP_PACKETS {
+            client
+                .request(packet)
+                .await
+                .with_context(|| format!("行情服务器握手失败: {}", addr))?;
+        }
+        info!("已连接行情服务器: {}", addr);
+        Ok(client)
+    }
+
+    /// 依次尝试服务器列表，返回第一个连接成功的客户端
+    pub async fn connect_any(servers: &[&str]) -> Result<Self> {
+        let mut last_error = None;
+        for server in servers {
+            match Self::connect(server).await {
+                Ok(client) => return Ok(client),
+                Err(e) => {
+                    debug!("连接 {} 失败: {}", server, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("服务器列表为空")))
+    }
+
+    /// 按请求策略轮流连接服务器列表，临时错误时退避后尝试下一个服务器
+    pub async fn connect_with_policy(servers: &[&str], policy: &NetPolicy) -> Result<Self> {
+        if servers.is_empty() {
+            return Err(anyhow::anyhow!("服务器列表为空"));
+        }
+
+        let mut next = 0;
+        policy
+            .run("tdx-hq", "连接行情服务器", || {
+                let server = servers[next % servers.len()];
+                next += 1;
+                Self::connect_with_timeout(server, policy.timeout())
+            })
+            .await
+    }
+
+    /// 市场内证券数量
+    pub async fn security_count(&mut self, market: TdxMarket) -> Result<u16> {
+        let mut packet = vec![
+            0x0c, 0x0c, 0x18, 0x6c, 0x00, 0x01, 0x08, 0x00, 0x08, 0x00, 0x4e, 0x04,
+        ];
+        packet.extend_from_slice(&u16::from(market.code()).to_le_bytes());
+        packet.extend_from_slice(&[0x75, 0xc7, 0x33, 0x01]);
+
+        let body = self.request(&packet).await?;
+        Reader::new(&body).u16()
+    }
+
+    /// 分页获取证券列表，`start` 为起始序号，每页最多1000条
+    pub async fn security_list(
+        &mut self,
+        market: TdxMarket,
+        start: u16,
+    ) -> Result<Vec<SecurityInfo>> {
+        let mut packet = vec![
+            0x0c, 0x01, 0x18, 0x64, 0x01, 0x01, 0x06, 0x00, 0x06, 0x00, 0x50, 0x04,
+        ];
+        packet.extend_from_slice(&u16::from(market.code()).to_le_bytes());
+        packet.extend_from_slice(&start.to_le_bytes());
+
+        let body = self.request(&packet).await?;
+        parse_security_list(&body, market)
+    }
+
+    /// 获取市场内全部证券
+    pub async fn all_securities(&mut self, market: TdxMarket) -> Result<Vec<SecurityInfo>> {
+        let count = self.security_count(market).await?;
+        let mut securities = Vec::with_capacity(count as usize);
+        let mut start = 0u16;
+        while start < count {
+            let page = self.security_list(market, start).await?;
+            if page.is_empty() {
+                break;
+            }
+            securities.extend(page);
+            start = start.saturating_add(SECURITIES_PER_PAGE);
+        }
+        Ok(securities)
+    }
+
+    /// 批量获取实时行情
+    pub async fn quotes(&mut self, securities: &[(TdxMarket, &str)]) -> Result<Vec<Quote>> {
+        if securities.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let count = securities.len() as u16;
+        let length = count * 7 + 12;
+        let mut packet = Vec::with_capacity(22 + securities.len() * 7);
+        packet.extend_from_slice(&0x010cu16.to_le_bytes());
+        packet.extend_from_slice(&0x0200_6320u32.to_le_bytes());
+        packet.extend_from_slice(&length.to_le_bytes());
+        packet.extend_from_slice(&length.to_le_bytes());
+        packet.extend_from_slice(&0x0005_053eu32.to_le_bytes());
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.extend_from_slice(&0u16.to_le_bytes());
+        packet.extend_from_slice(&count.to_le_bytes());
+        for (market, code) in securities {
+            packet.push(market.code());
+            packet.extend_from_slice(&encode_code(code)?);
+        }
+
+        let body = self.request(&packet).await?;
+        parse_quotes(&body)
+    }
+
+    /// 获取历史K线，`start` 为从最新一根往前的偏移，`count` 最多800
+    pub async fn bars(
+        &mut self,
+        category: KLineCategory,
+        market: TdxMarket,
+        code: &str,
+        start: u16,
+        count: u16,
+    ) -> Result<Vec<KLine>> {
+        let mut packet = Vec::with_capacity(38);
+        packet.extend_from_slice(&0x010cu16.to_le_bytes());
+        packet.extend_from_slice(&0x0101_6408u32.to_le_bytes());
+        packet.extend_from_slice(&0x1cu16.to_le_bytes());
+        packet.extend_from_slice(&0x1cu16.to_le_bytes());
+        packet.extend_from_slice(&0x052du16.to_le_bytes());
+        packet.extend_from_slice(&u16::from(market.code()).to_le_bytes());
+        packet.extend_from_slice(&encode_code(code)?);
+        packet.extend_from_slice(&category.code().to_le_bytes());
+        packet.extend_from_slice(&1u16.to_le_bytes());
+        packet.extend_from_slice(&start.to_le_bytes());
+        packet.extend_from_slice(&count.min(MAX_BARS_PER_REQUEST).to_le_bytes());
+        packet.extend_from_slice(&[0u8; 10]);
+
+        let body = self.request(&packet).await?;
+        parse_bars(&body, category)
+    }
+
+    /// 获取日线记录（按日期升序），`since` 之后的数据，为None时获取全部历史
+    ///
+    /// 用于补齐本地缺失或落后的股票数据。
+    pub async fn daily_records(
+        &mut self,
+        market: TdxMarket,
+        code: &str,
+        since: Option<NaiveDate>,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let mut bars = Vec::new();
+        let mut start = 0u16;
+        loop {
+            let page = self
+                .bars(
+                    KLineCategory::Daily,
+                    market,
+                    code,
+                    start,
+                    MAX_BARS_PER_REQUEST,
+                )
+                .await?;
+            let page_len = page.len();
+            let reached_since = match (since, page.first()) {
+                (Some(since), Some(first)) => first.datetime.date() <= since,
+                _ => false,
+            };
+            // 分页从最新往前，页内按时间升序
+            bars.splice(0..0, page);
+
+            if reached_since || page_len < MAX_BARS_PER_REQUEST as usize {
+                break;
+            }
+            match start.checked_add(MAX_BARS_PER_REQUEST) {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+
+        Ok(bars
+            .iter()
+            .filter(|bar| since.is_none_or(|since| bar.datetime.date() > since))
+            .map(|bar| bar.to_day_record(code, market))
+            .collect())
+    }
+
+    /// 将本地日线与服务器数据比对，返回价格或成交量不一致以及缺失的交易日
+    ///
+    /// 仅比对本地数据覆盖的日期范围。
+    pub async fn verify_records(
+        &mut self,
+        market: TdxMarket,
+        code: &str,
+        local: &[TDXDayRecord],
+        tolerance: f64,
+    ) -> Result<Vec<RecordMismatch>> {
+        let Some(first) = local.iter().map(|r| r.date).min() else {
+            return Ok(Vec::new());
+        };
+        let since = first.pred_opt();
+        let remote = self.daily_records(market, code, since).await?;
+        let last = local.iter().map(|r| r.date).max().unwrap_or(first);
+        let remote: Vec<TDXDayRecord> = remote.into_iter().filter(|r| r.date <= last).collect();
+        Ok(compare_records(local, &remote, tolerance))
+    }
+
+    /// 发送请求并读取响应包体
+    async fn request(&mut self, packet: &[u8]) -> Result<Vec<u8>> {
+        let timeout = self.timeout;
+        with_timeout(timeout, "行情服务器响应", async {
+            self.stream
+                .write_all(packet)
+                .await
+                .context("发送请求失败")?;
+            read_response(&mut self.stream).await
+        })
+        .await
+    }
+}
+
+/// 比对两组日线记录（均视为同一股票），按日期升序返回差异
+pub fn compare_records(
+    local: &[TDXDayRecord],
+    remote: &[TDXDayRecord],
+    tolerance: f64,
+) -> Vec<RecordMismatch> {
+    use std::collections::BTreeMap;
+
+    let mut by_date: BTreeMap<NaiveDate, (Option<&TDXDayRecord>, Option<&TDXDayRecord>)> =
+        BTreeMap::new();
+    for record in local {
+        by_date.entry(record.date).or_default().0 = Some(record);
+    }
+    for record in remote {
+        by_date.entry(record.date).or_default().1 = Some(record);
+    }
+
+    by_date
+        .into_iter()
+        .filter(|(_, pair)| match pair {
+            (Some(l), Some(r)) => {
+                (l.open - r.open).abs() > tolerance
+                    || (l.high - r.high).abs() > tolerance
+                    || (l.low - r.low).abs() > tolerance
+                    || (l.close - r.close).abs() > tolerance
+                    || l.volume != r.volume
+            }
+            _ => true,
+        })
+        .map(|(date, (l, r))| RecordMismatch {
+            date,
+            local: l.cloned(),
+            remote: r.cloned(),
+        })
+        .collect()
+}
+
+/// 读取一个完整响应，返回（解压后的）包体
+async fn read_response<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut header = [0u8; RESPONSE_HEADER_SIZE];
+    reader
+        .read_exact(&mut header)
+        .await
+        .context("读取响应包头失败")?;
+
+    let zip_size = u16::from_le_bytes([header[12], header[13]]) as usize;
+    let unzip_size = u16::from_le_bytes([header[14], header[15]]) as usize;
+
+    let mut body = vec![0u8; zip_size];
+    reader
+        .read_exact(&mut body)
+        .await
+        .context("读取响应包体失败")?;
+
+    if zip_size == unzip_size {
+        return Ok(body);
+    }
+
+    let mut decompressed = Vec::with_capacity(unzip_size);
+    ZlibDecoder::new(body.as_slice())
+        .read_to_end(&mut decompressed)
+        .context("解压响应包体失败")?;
+    if decompressed.len() != unzip_size {
+        return Err(anyhow::anyhow!(
+            "响应包体长度不符: 期望{}，实际{}",
+            unzip_size,
+            decompressed.len()
+        ));
+    }
+    Ok(decompressed)
+}
+
+/// 编码6位证券代码
+fn encode_code(code: &str) -> Result<[u8; 6]> {
+    code.as_bytes()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("证券代码必须为6位: {}", code))
+}
+
+/// 解析证券列表响应
+fn parse_security_list(body: &[u8], market: TdxMarket) -> Result<Vec<SecurityInfo>> {
+    let mut reader = Reader::new(body);
+    let count = reader.u16()?;
+    let mut securities = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let code = reader.string(6)?;
+        let volume_unit = reader.u16()?;
+        let name = decode_gbk(reader.take(8)?);
+        reader.take(4)?;
+        let decimal_point = reader.u8()?;
+        let pre_close = decode_volume(reader.u32()?);
+        reader.take(4)?;
+        securities.push(SecurityInfo {
+            code,
+            name,
+            market,
+            volume_unit,
+            decimal_point,
+            pre_close,
+        });
+    }
+    Ok(securities)
+}
+
+/// 解析实时行情响应
+fn parse_quotes(body: &[u8]) -> Result<Vec<Quote>> {
+    let mut reader = Reader::new(body);
+    reader.take(2)?;
+    let count = reader.u16()?;
+    let mut quotes = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let market = match reader.u8()? {
+            0 => TdxMarket::SZ,
+            1 => TdxMarket::SH,
+            other => return Err(anyhow::anyhow!("未知市场编号: {}", other)),
+        };
+        let code = reader.string(6)?;
+        reader.u16()?;
+
+        let price = reader.price()?;
+        let last_close = reader.price()?;
+        let open = reader.price()?;
+        let high = reader.price()?;
+        let low = reader.price()?;
+        // 服务器时间等保留字段
+        reader.price()?;
+        reader.price()?;
+        let volume = reader.price()?;
+        let current_volume = reader.price()?;
+        let amount = decode_volume(reader.u32()?);
+        // 内外盘与保留字段
+        for _ in 0..4 {
+            reader.price()?;
+        }
+
+        let to_price = |diff: i64| (price + diff) as f64 / 100.0;
+        let mut bids = Vec::with_capacity(5);
+        let mut asks = Vec::with_capacity(5);
+        for _ in 0..5 {
+            let bid = reader.price()?;
+            let ask = reader.price()?;
+            let bid_volume = reader.price()?;
+            let ask_volume = reader.price()?;
+            bids.push(QuoteLevel {
+                price: to_price(bid),
+                volume: bid_volume,
+            });
+            asks.push(QuoteLevel {
+                price: to_price(ask),
+                volume: ask_volume,
+            });
+        }
+
+        reader.u16()?;
+        for _ in 0..4 {
+            reader.price()?;
+        }
+        reader.take(4)?;
+
+        quotes.push(Quote {
+            code,
+            market,
+            price: to_price(0),
+            last_close: to_price(last_close),
+            open: to_price(open),
+            high: to_price(high),
+            low: to_price(low),
+            volume,
+            current_volume,
+            amount,
+            bids,
+            asks,
+        });
+    }
+    Ok(quotes)
+}
+
+/// 解析K线响应
+fn parse_bars(body: &[u8], category: KLineCategory) -> Result<Vec<KLine>> {
+    let mut reader = Reader::new(body);
+    let count = reader.u16()?;
+    let mut bars = Vec::with_capacity(count as usize);
+    // 价格以千分之一元为单位，开盘价相对上一根收盘价差分
+    let mut base = 0i64;
+    for _ in 0..count {
+        let datetime = reader.datetime(category)?;
+        let open_diff = reader.price()?;
+        let close_diff = reader.price()?;
+        let high_diff = reader.price()?;
+        let low_diff = reader.price()?;
+        let volume = decode_volume(reader.u32()?);
+        let amount = decode_volume(reader.u32()?);
+
+        let open = base + open_diff;
+        base = open + close_diff;
+        bars.push(KLine {
+            datetime,
+            open: open as f64 / 1000.0,
+            high: (open + high_diff) as f64 / 1000.0,
+            low: (open + low_diff) as f64 / 1000.0,
+            close: (open + close_diff) as f64 / 1000.0,
+            volume,
+            amount,
+        });
+    }
+    Ok(bars)
+}
+
+/// 解码通达信浮点格式的成交量/成交额
+fn decode_volume(raw: u32) -> f64 {
+    let exponent = (raw >> 24) as i32;
+    let high = ((raw >> 16) & 0xff) as i32;
+    let mid = ((raw >> 8) & 0xff) as f64;
+    let low = (raw & 0xff) as f64;
+
+    let base = 2f64.powi(exponent * 2 - 0x7f);
+    let high_exp = exponent * 2 - 0x86;
+    let high_part = if high > 0x80 {
+        2f64.powi(high_exp) * 128.0 + (high & 0x7f) as f64 * 2f64.powi(high_exp + 1)
+    } else {
+        2f64.powi(high_exp) * high as f64
+    };
+    let mut mid_part = 2f64.powi(exponent * 2 - 0x8e) * mid;
+    let mut low_part = 2f64.powi(exponent * 2 - 0x96) * low;
+    if high & 0x80 != 0 {
+        mid_part *= 2.0;
+        low_part *= 2.0;
+    }
+    base + high_part + mid_part + low_part
+}
+
+/// GBK解码（去除尾部填充）
+fn decode_gbk(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let (text, _, _) = encoding_rs::GBK.decode(&bytes[..end]);
+    text.trim().to_string()
+}
+
+/// 响应包体读取器
+struct Reader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buffer: &'a [u8]) -> Self {
+        Self {
+            buffer,
+            position: 0,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.position + len;
+        if end > self.buffer.len() {
+            return Err(anyhow::anyhow!(
+                "响应数据不完整: 位置{}需要{}字节，剩余{}字节",
+                self.position,
+                len,
+                self.buffer.len() - self.position
+            ));
+        }
+        let bytes = &self.buffer[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        Ok(decode_gbk(self.take(len)?))
+    }
+
+    /// 变长有符号整数：首字节低6位为数值、0x40为符号位，
+    /// 后续字节各7位，0x80表示还有后续字节
+    fn price(&mut self) -> Result<i64> {
+        let mut byte = self.u8()?;
+        let negative = byte & 0x40 != 0;
+        let mut value = (byte & 0x3f) as i64;
+        let mut shift = 6;
+        while byte & 0x80 != 0 {
+            if shift > 56 {
+                return Err(anyhow::anyhow!("价格编码过长: 位置{}", self.position));
+            }
+            byte = self.u8()?;
+            value += ((byte & 0x7f) as i64) << shift;
+            shift += 7;
+        }
+        Ok(if negative { -value } else { value })
+    }
+
+    fn datetime(&mut self, category: KLineCategory) -> Result<NaiveDateTime> {
+        let (year, month, day, hour, minute) = if category.is_intraday() {
+            let packed_day = self.u16()? as i32;
+            let minutes = self.u16()? as u32;
+            (
+                (packed_day >> 11) + 2004,
+                ((packed_day % 2048) / 100) as u32,
+                ((packed_day % 2048) % 100) as u32,
+                minutes / 60,
+                minutes % 60,
+            )
+        } else {
+            let packed = self.u32()?;
+            (
+                (packed / 10000) as i32,
+                (packed % 10000) / 100,
+                packed % 100,
+                15,
+                0,
+            )
+        };
+
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "无效的K线时间: {}-{}-{} {}:{}",
+                    year,
+                    month,
+                    day,
+                    hour,
+                    minute
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    /// 变长价格编码（解码的逆过程）
+    fn encode_price(value: i64) -> Vec<u8> {
+        let mut magnitude = value.unsigned_abs();
+        let mut first = (magnitude & 0x3f) as u8;
+        if value < 0 {
+            first |= 0x40;
+        }
+        magnitude >>= 6;
+        let mut bytes = vec![first];
+        while magnitude > 0 {
+            *bytes.last_mut().unwrap() |= 0x80;
+            bytes.push((magnitude & 0x7f) as u8);
+            magnitude >>= 7;
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_price_and_volume_decoding() {
+        for value in [0, 5, -5, 63, 64, -1000, 123_456, -9_876_543] {
+            let bytes = encode_price(value);
+            assert_eq!(Reader::new(&bytes).price().unwrap(), value);
+        }
+        assert!(Reader::new(&[0x80]).price().is_err());
+
+        // 与pytdx的get_volume结果一致
+        assert_eq!(decode_volume(0x4B18_9680), 10_000_000.0);
+        assert_eq!(decode_volume(0x4E4C_4B40), 856_870_912.0);
+    }
+
+    #[test]
+    fn test_parse_daily_bars() {
+        let mut body = 2u16.to_le_bytes().to_vec();
+        // 第一根：开10.00 收10.50 高10.80 低9.90
+        body.extend_from_slice(&20240102u32.to_le_bytes());
+        for diff in [10_000, 500, 800, -100] {
+            body.extend(encode_price(diff));
+        }
+        body.extend_from_slice(&0x4B18_9680u32.to_le_bytes());
+        body.extend_from_slice(&0x4E4C_4B40u32.to_le_bytes());
+        // 第二根：开盘相对上一根收盘差分，开10.40 收10.20
+        body.extend_from_slice(&20240103u32.to_le_bytes());
+        for diff in [-100, -200, 100, -300] {
+            body.extend(encode_price(diff));
+        }
+        body.extend_from_slice(&0x4B18_9680u32.to_le_bytes());
+        body.extend_from_slice(&0x4E4C_4B40u32.to_le_bytes());
+
+        let bars = parse_bars(&body, KLineCategory::Daily).unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].open, 10.0);
+        assert_eq!(bars[0].close, 10.5);
+        assert_eq!(bars[0].high, 10.8);
+        assert_eq!(bars[0].low, 9.9);
+        assert_eq!(bars[1].open, 10.4);
+        assert_eq!(bars[1].close, 10.2);
+        assert_eq!(bars[1].low, 10.1);
+
+        let record = bars[1].to_day_record("600000", TdxMarket::SH);
+        assert_eq!(record.date, NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        assert_eq!(record.volume, 10_000_000);
+        assert_eq!(record.market, "SH");
+
+        // 截断的响应
+        assert!(parse_bars(&body[..body.len() - 1], KLineCategory::Daily).is_err());
+
+        let mismatches = compare_records(std::slice::from_ref(&record), &[], 0.001);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].remote.is_none());
+        assert!(compare_records(
+            std::slice::from_ref(&record),
+            std::slice::from_ref(&record),
+            0.001
+        )
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_compressed_response() {
+        let mut body = 1u16.to_le_bytes().to_vec();
+        body.extend_from_slice(b"600000");
+        body.extend_from_slice(&100u16.to_le_bytes());
+        let (name, _, _) = encoding_rs::GBK.encode("浦发银行");
+        body.extend_from_slice(&name);
+        body.extend_from_slice(&[0u8; 4]);
+        body.push(2);
+        body.extend_from_slice(&0x4B18_9680u32.to_le_bytes());
+        body.extend_from_slice(&[0u8; 4]);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&body).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut packet = vec![0u8; 12];
+        packet.extend_from_slice(&(compressed.len() as u16).to_le_bytes());
+        packet.extend_from_slice(&(body.len() as u16).to_le_bytes());
+        packet.extend_from_slice(&compressed);
+
+        let decoded = read_response(&mut packet.as_slice()).await.unwrap();
+        assert_eq!(decoded, body);
+
+        let securities = parse_security_list(&decoded, TdxMarket::SH).unwrap();
+        assert_eq!(securities[0].code, "600000");
+        assert_eq!(securities[0].name, "浦发银行");
+        assert_eq!(securities[0].volume_unit, 100);
+        assert_eq!(securities[0].decimal_point, 2);
+    }
+}
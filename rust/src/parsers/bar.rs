@@ -0,0 +1,171 @@
+//! 通用K线模型
+//!
+//! `Bar` 统一日线、分钟线及带指标记录的字段访问，清洗、聚合和指标计算
+//! 基于该trait实现，日线与分钟线共用同一套处理流程。
+
+use super::price::PriceValue;
+use super::tdx_day::TDXDayRecord;
+use super::tdx_minute::TDXMinuteRecord;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+/// K线记录
+pub trait Bar {
+    /// 股票代码
+    fn symbol(&self) -> &str;
+    /// 市场（SH/SZ）
+    fn market(&self) -> &str;
+    /// K线时间（日线为当日零点，分钟线为结束时间）
+    fn timestamp(&self) -> NaiveDateTime;
+    /// 交易日期
+    fn date(&self) -> NaiveDate {
+        self.timestamp().date()
+    }
+    /// 开盘价
+    fn open(&self) -> f64;
+    /// 最高价
+    fn high(&self) -> f64;
+    /// 最低价
+    fn low(&self) -> f64;
+    /// 收盘价
+    fn close(&self) -> f64;
+    /// 成交量（股）
+    fn volume(&self) -> u64;
+    /// 成交额（元）
+    fn amount(&self) -> f64;
+    /// 技术指标值（仅带指标的记录提供）
+    fn indicator(&self, _name: &str) -> Option<f64> {
+        None
+    }
+
+    /// 设置开盘价
+    fn set_open(&mut self, value: f64);
+    /// 设置最高价
+    fn set_high(&mut self, value: f64);
+    /// 设置最低价
+    fn set_low(&mut self, value: f64);
+    /// 设置收盘价
+    fn set_close(&mut self, value: f64);
+    /// 设置成交量
+    fn set_volume(&mut self, value: u64);
+    /// 设置成交额
+    fn set_amount(&mut self, value: f64);
+}
+
+/// 为字段名一致的记录实现价格与成交量访问（价格字段可为浮点或定点）
+macro_rules! impl_bar_fields {
+    () => {
+        fn symbol(&self) -> &str {
+            &self.symbol
+        }
+
+        fn market(&self) -> &str {
+            &self.market
+        }
+
+        fn open(&self) -> f64 {
+            PriceValue::to_f64(self.open)
+        }
+
+        fn high(&self) -> f64 {
+            PriceValue::to_f64(self.high)
+        }
+
+        fn low(&self) -> f64 {
+            PriceValue::to_f64(self.low)
+        }
+
+        fn close(&self) -> f64 {
+            PriceValue::to_f64(self.close)
+        }
+
+        fn volume(&self) -> u64 {
+            self.volume
+        }
+
+        fn amount(&self) -> f64 {
+            self.amount
+        }
+
+        fn set_open(&mut self, value: f64) {
+            self.open = PriceValue::from_f64(value);
+        }
+
+        fn set_high(&mut self, value: f64) {
+            self.high = PriceValue::from_f64(value);
+        }
+
+        fn set_low(&mut self, value: f64) {
+            self.low = PriceValue::from_f64(value);
+        }
+
+        fn set_close(&mut self, value: f64) {
+            self.close = PriceValue::from_f64(value);
+        }
+
+        fn set_volume(&mut self, value: u64) {
+            self.volume = value;
+        }
+
+        fn set_amount(&mut self, value: f64) {
+            self.amount = value;
+        }
+    };
+}
+
+impl<P: PriceValue> Bar for TDXDayRecord<P> {
+    impl_bar_fields!();
+
+    fn timestamp(&self) -> NaiveDateTime {
+        self.date.and_time(NaiveTime::MIN)
+    }
+
+    fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+impl Bar for TDXMinuteRecord {
+    impl_bar_fields!();
+
+    fn timestamp(&self) -> NaiveDateTime {
+        self.datetime
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_and_minute_bars() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let mut day = TDXDayRecord {
+            date,
+            symbol: "600000".to_string(),
+            open: 10.0,
+            high: 10.5,
+            low: 9.8,
+            close: 10.2,
+            volume: 1000,
+            amount: 10_200.0,
+            market: "SH".to_string(),
+        };
+        assert_eq!(day.timestamp(), date.and_hms_opt(0, 0, 0).unwrap());
+        day.set_close(10.4);
+        assert_eq!(Bar::close(&day), 10.4);
+
+        let minute = TDXMinuteRecord {
+            datetime: date.and_hms_opt(9, 31, 0).unwrap(),
+            symbol: day.symbol.clone(),
+            open: 10.0,
+            high: 10.1,
+            low: 9.9,
+            close: 10.05,
+            volume: 100,
+            amount: 1005.0,
+            market: day.market.clone(),
+        };
+        assert_eq!(minute.date(), date);
+        assert_eq!(Bar::symbol(&minute), "600000");
+    }
+}
@@ -0,0 +1,74 @@
+//! 解析器模糊测试入口
+//!
+//! 入口函数接收任意字节，只返回结构化的错误而不会panic，可直接在cargo-fuzz的
+//! `fuzz_target!` 中调用（`let _ = fuzz_parse_binary_data(data);`），不需要导出C符号。
+//! 首字节用于选择股票代码和市场，使不同品种的价格校验规则都能被覆盖，其余字节作为文件内容。
+
+use super::tdx_day::TDXDayParser;
+use super::tdx_minute::TDXMinuteParser;
+use anyhow::Result;
+
+/// 首字节可选的（股票代码, 市场），覆盖股票、B股、指数、基金和未知市场
+const TARGETS: [(&str, &str); 5] = [
+    ("600000", "SH"),
+    ("900901", "SH"),
+    ("399001", "SZ"),
+    ("159915", "SZ"),
+    ("830799", "BJ"),
+];
+
+/// 拆分出代码、市场和文件内容
+fn split_input(data: &[u8]) -> (&str, &str, &[u8]) {
+    match data.split_first() {
+        Some((selector, rest)) => {
+            let (symbol, market) = TARGETS[*selector as usize % TARGETS.len()];
+            (symbol, market, rest)
+        }
+        None => (TARGETS[0].0, TARGETS[0].1, data),
+    }
+}
+
+/// 日线解析入口，返回解析出的记录数
+pub fn fuzz_parse_binary_data(data: &[u8]) -> Result<usize> {
+    let (symbol, market, buffer) = split_input(data);
+    let records = TDXDayParser::new(".").parse_binary_data(buffer, symbol, market)?;
+    Ok(records.len())
+}
+
+/// 分钟线解析入口，返回解析出的记录数
+pub fn fuzz_parse_minute_data(data: &[u8]) -> Result<usize> {
+    let (symbol, market, buffer) = split_input(data);
+    let records = TDXMinuteParser::new(".").parse_binary_data(buffer, symbol, market)?;
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_bytes_do_not_panic() {
+        // 确定性的xorshift序列，覆盖各种长度和取值
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for len in 0..200 {
+            let data: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+            let _ = fuzz_parse_binary_data(&data);
+            let _ = fuzz_parse_minute_data(&data);
+        }
+
+        // 日期字段为极值或非8位数时返回错误
+        for date in [0u32, 9_999_999, 100_000_000, u32::MAX, 20241301] {
+            let mut data = vec![0u8];
+            data.extend_from_slice(&date.to_le_bytes());
+            data.extend_from_slice(&[0u8; 28]);
+            assert!(fuzz_parse_binary_data(&data).is_err());
+        }
+        assert_eq!(fuzz_parse_binary_data(&[]).unwrap(), 0);
+    }
+}
@@ -1,7 +1,24 @@
 //! 数据解析器模块
 
+pub mod bar;
+pub mod fuzz;
+pub mod price;
+pub mod projection;
+pub mod query;
+pub mod symbol;
 pub mod tdx_day;
+pub mod tdx_minute;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod utils;
+pub mod validation;
 
+pub use bar::Bar;
+pub use price::{Price, PriceValue};
+pub use projection::{Column, ProjectedColumns, SymbolSegment};
+pub use query::DayQuery;
+pub use symbol::{CompactDayRecord, SymbolId, SymbolTable};
 pub use tdx_day::*;
+pub use tdx_minute::*;
 pub use utils::*;
+pub use validation::{AssetClass, PriceBounds, ProfileRule, ValidationProfile};
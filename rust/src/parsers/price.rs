@@ -0,0 +1,195 @@
+//! 定点价格模块
+//!
+//! `Price` 以0.001元为单位的整数存放价格，避免f64在写入ClickHouse
+//! （Decimal64(3)）或与交易所数据比对时产生舍入误差。
+//! 日线记录通过价格类型参数选择浮点或定点表示：`TDXDayRecord<Price>`。
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+use std::str::FromStr;
+
+/// 定点价格（单位：0.001元），序列化为整数
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Price(pub i64);
+
+impl Price {
+    /// 每元对应的最小单位数
+    pub const SCALE: i64 = 1000;
+    /// 小数位数
+    pub const DECIMALS: usize = 3;
+    /// 零
+    pub const ZERO: Price = Price(0);
+
+    /// 由最小单位数创建
+    pub fn from_raw(raw: i64) -> Self {
+        Price(raw)
+    }
+
+    /// 最小单位数
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+
+    /// 由分创建（通达信日线文件的存储单位）
+    pub fn from_cents(cents: i64) -> Self {
+        Price(cents * (Self::SCALE / 100))
+    }
+
+    /// 由浮点价格创建，四舍五入到0.001元
+    pub fn from_f64(value: f64) -> Self {
+        Price((value * Self::SCALE as f64).round() as i64)
+    }
+
+    /// 转换为浮点价格
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::SCALE as f64
+    }
+}
+
+impl Add for Price {
+    type Output = Price;
+
+    fn add(self, rhs: Price) -> Price {
+        Price(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Price {
+    type Output = Price;
+
+    fn sub(self, rhs: Price) -> Price {
+        Price(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Price {
+    type Output = Price;
+
+    fn neg(self) -> Price {
+        Price(-self.0)
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let scale = Self::SCALE as u64;
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            abs / scale,
+            abs % scale,
+            width = Self::DECIMALS
+        )
+    }
+}
+
+impl FromStr for Price {
+    type Err = anyhow::Error;
+
+    /// 按十进制解析，不经过浮点，超过3位小数时报错
+    fn from_str(s: &str) -> Result<Self> {
+        let text = s.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if fraction.len() > Self::DECIMALS {
+            return Err(anyhow::anyhow!(
+                "价格精度超过{}位小数: {}",
+                Self::DECIMALS,
+                s
+            ));
+        }
+
+        let parse = |part: &str| -> Result<i64> {
+            if part.is_empty() {
+                return Ok(0);
+            }
+            if !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(anyhow::anyhow!("无效的价格: {}", s));
+            }
+            part.parse::<i64>()
+                .map_err(|e| anyhow::anyhow!("无效的价格 {}: {}", s, e))
+        };
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(anyhow::anyhow!("无效的价格: {}", s));
+        }
+
+        let padded = format!("{:0<width$}", fraction, width = Self::DECIMALS);
+        let fraction = parse(&padded)?;
+        let raw = parse(integer)?
+            .checked_mul(Self::SCALE)
+            .and_then(|value| value.checked_add(fraction))
+            .ok_or_else(|| anyhow::anyhow!("价格超出范围: {}", s))?;
+        Ok(Price(if negative { -raw } else { raw }))
+    }
+}
+
+impl From<Price> for f64 {
+    fn from(price: Price) -> f64 {
+        price.to_f64()
+    }
+}
+
+/// 记录中的价格类型（浮点或定点）
+pub trait PriceValue: Copy + PartialOrd + fmt::Debug {
+    /// 转换为浮点
+    fn to_f64(self) -> f64;
+    /// 由浮点创建
+    fn from_f64(value: f64) -> Self;
+}
+
+impl PriceValue for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+impl PriceValue for Price {
+    fn to_f64(self) -> f64 {
+        Price::to_f64(self)
+    }
+
+    fn from_f64(value: f64) -> Self {
+        Price::from_f64(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let price: Price = "10.23".parse().unwrap();
+        assert_eq!(price.raw(), 10_230);
+        assert_eq!(price.to_string(), "10.230");
+        assert_eq!("-0.005".parse::<Price>().unwrap(), Price(-5));
+        assert_eq!(Price(-5).to_string(), "-0.005");
+        assert!("1.2345".parse::<Price>().is_err());
+        assert!("1.2a".parse::<Price>().is_err());
+        assert!(".".parse::<Price>().is_err());
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        // 0.1 + 0.2 在浮点下不等于0.3，定点下精确相等
+        let sum = Price::from_f64(0.1) + Price::from_f64(0.2);
+        assert_eq!(sum, "0.3".parse().unwrap());
+        assert_eq!(Price::from_cents(1023), Price(10_230));
+        assert_eq!(Price::from_f64(10.2349), Price(10_235));
+    }
+}
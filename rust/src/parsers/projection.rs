@@ -0,0 +1,344 @@
+//! 列投影解析
+//!
+//! 因子任务通常只需要收盘价和成交量，完整的 `TDXDayRecord` 每行还带两个 `String` 和其余
+//! 价格字段。投影解析按记录校验后只保存选中的列，股票代码和市场按股票分段保存一次，
+//! 日期列始终保留。校验规则与 `parse_binary_data` 一致，未选中的价格字段同样参与校验。
+//!
+//! 结果按（代码, 市场）分段，段内按日期升序，不受解析器排序方式影响。
+
+use super::query::DayQuery;
+use super::tdx_day::{BinaryDayRecord, DayFile, TDXDayParser};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 可投影的日线字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Column {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    Amount,
+}
+
+impl Column {
+    /// 全部字段
+    pub const ALL: [Column; 6] = [
+        Column::Open,
+        Column::High,
+        Column::Low,
+        Column::Close,
+        Column::Volume,
+        Column::Amount,
+    ];
+
+    /// 字段名
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Open => "open",
+            Column::High => "high",
+            Column::Low => "low",
+            Column::Close => "close",
+            Column::Volume => "volume",
+            Column::Amount => "amount",
+        }
+    }
+}
+
+/// 一只股票在结果中的连续行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolSegment {
+    pub symbol: String,
+    pub market: String,
+    /// 起始行
+    pub start: usize,
+    /// 行数
+    pub len: usize,
+}
+
+/// 投影后的列式日线，未选中的列为 `None`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectedColumns {
+    segments: Vec<SymbolSegment>,
+    dates: Vec<NaiveDate>,
+    open: Option<Vec<f64>>,
+    high: Option<Vec<f64>>,
+    low: Option<Vec<f64>>,
+    close: Option<Vec<f64>>,
+    volume: Option<Vec<u64>>,
+    amount: Option<Vec<f64>>,
+}
+
+impl ProjectedColumns {
+    /// 创建只包含指定列的空结果
+    pub fn new(columns: &[Column]) -> Self {
+        let mut projected = Self::default();
+        for column in columns {
+            match column {
+                Column::Open => projected.open = Some(Vec::new()),
+                Column::High => projected.high = Some(Vec::new()),
+                Column::Low => projected.low = Some(Vec::new()),
+                Column::Close => projected.close = Some(Vec::new()),
+                Column::Volume => projected.volume = Some(Vec::new()),
+                Column::Amount => projected.amount = Some(Vec::new()),
+            }
+        }
+        projected
+    }
+
+    /// 已选中的列
+    pub fn columns(&self) -> Vec<Column> {
+        Column::ALL
+            .into_iter()
+            .filter(|column| match column {
+                Column::Open => self.open.is_some(),
+                Column::High => self.high.is_some(),
+                Column::Low => self.low.is_some(),
+                Column::Close => self.close.is_some(),
+                Column::Volume => self.volume.is_some(),
+                Column::Amount => self.amount.is_some(),
+            })
+            .collect()
+    }
+
+    /// 总行数
+    pub fn len(&self) -> usize {
+        self.dates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dates.is_empty()
+    }
+
+    pub fn segments(&self) -> &[SymbolSegment] {
+        &self.segments
+    }
+
+    /// 指定股票的行范围
+    pub fn segment(&self, symbol: &str, market: &str) -> Option<&SymbolSegment> {
+        self.segments
+            .iter()
+            .find(|segment| segment.symbol == symbol && segment.market == market)
+    }
+
+    pub fn dates(&self) -> &[NaiveDate] {
+        &self.dates
+    }
+
+    pub fn open(&self) -> Option<&[f64]> {
+        self.open.as_deref()
+    }
+
+    pub fn high(&self) -> Option<&[f64]> {
+        self.high.as_deref()
+    }
+
+    pub fn low(&self) -> Option<&[f64]> {
+        self.low.as_deref()
+    }
+
+    pub fn close(&self) -> Option<&[f64]> {
+        self.close.as_deref()
+    }
+
+    pub fn volume(&self) -> Option<&[u64]> {
+        self.volume.as_deref()
+    }
+
+    pub fn amount(&self) -> Option<&[f64]> {
+        self.amount.as_deref()
+    }
+
+    /// 追加一只股票的二进制日线；校验失败时结果不变
+    fn append(
+        &mut self,
+        parser: &TDXDayParser,
+        buffer: &[u8],
+        symbol: &str,
+        market: &str,
+    ) -> Result<()> {
+        let records = parser.decode_validated(buffer, symbol, market)?;
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let price = |cents: u32| cents as f64 / 100.0;
+        self.segments.push(SymbolSegment {
+            symbol: symbol.to_string(),
+            market: market.to_string(),
+            start: self.dates.len(),
+            len: records.len(),
+        });
+        self.dates.extend(records.iter().map(|(date, _)| *date));
+        if let Some(open) = &mut self.open {
+            open.extend(records.iter().map(|(_, r)| price(r.open)));
+        }
+        if let Some(high) = &mut self.high {
+            high.extend(records.iter().map(|(_, r)| price(r.high)));
+        }
+        if let Some(low) = &mut self.low {
+            low.extend(records.iter().map(|(_, r)| price(r.low)));
+        }
+        if let Some(close) = &mut self.close {
+            close.extend(records.iter().map(|(_, r)| price(r.close)));
+        }
+        if let Some(volume) = &mut self.volume {
+            volume.extend(records.iter().map(|(_, r)| r.volume as u64));
+        }
+        if let Some(amount) = &mut self.amount {
+            amount.extend(records.iter().map(|(_, r)| r.amount as f64));
+        }
+        Ok(())
+    }
+}
+
+impl TDXDayParser {
+    /// 解析单个day文件，只保存指定列
+    pub fn parse_file_columns<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+        columns: &[Column],
+    ) -> Result<ProjectedColumns> {
+        let file_path = file_path.as_ref();
+        let (symbol, market) = self.extract_symbol_market(file_path)?;
+        let buffer = std::fs::read(file_path)
+            .with_context(|| format!("无法读取文件: {}", file_path.display()))?;
+        let mut projected = ProjectedColumns::new(columns);
+        projected.append(self, &buffer, &symbol, &market)?;
+        Ok(projected)
+    }
+
+    /// 解码并校验二进制日线，按日期排序，不构造 `TDXDayRecord`
+    fn decode_validated(
+        &self,
+        buffer: &[u8],
+        symbol: &str,
+        market: &str,
+    ) -> Result<Vec<(NaiveDate, BinaryDayRecord)>> {
+        if !buffer.len().is_multiple_of(BinaryDayRecord::SIZE) {
+            return Err(anyhow::anyhow!(
+                "文件大小不正确，期望{}的倍数，实际{}字节",
+                BinaryDayRecord::SIZE,
+                buffer.len()
+            ));
+        }
+
+        let profile = self.validation_profile();
+        let mut records = Vec::with_capacity(buffer.len() / BinaryDayRecord::SIZE);
+        for chunk in buffer.chunks_exact(BinaryDayRecord::SIZE) {
+            let record = BinaryDayRecord::from_bytes(chunk.try_into()?);
+            let date = TDXDayParser::parse_date(record.date)?;
+            let (open, high, low, close) = (record.open, record.high, record.low, record.close);
+            profile.validate_prices(
+                symbol,
+                market,
+                open as f64 / 100.0,
+                high as f64 / 100.0,
+                low as f64 / 100.0,
+                close as f64 / 100.0,
+            )?;
+            records.push((date, record));
+        }
+
+        records.sort_by_key(|(date, _)| *date);
+        let bounds = profile.bounds(symbol, market);
+        if bounds.max_daily_move_percent.is_some() {
+            for pair in records.windows(2) {
+                let (prev, next) = (pair[0].1.close, pair[1].1.close);
+                bounds
+                    .check_move(prev as f64 / 100.0, next as f64 / 100.0)
+                    .map_err(|e| anyhow::anyhow!("{} {}: {}", symbol, pair[1].0, e))?;
+            }
+        }
+        Ok(records)
+    }
+}
+
+impl DayQuery<'_> {
+    /// 执行查询并只保存 `select` 选中的列（未调用 `select` 时保存全部列）
+    pub fn execute_columns(&self) -> Result<ProjectedColumns> {
+        self.check_date_range()?;
+        let mut projected = ProjectedColumns::new(self.projection());
+        let mut candidates = self.candidates()?;
+        candidates.sort();
+        for (symbol, market) in candidates {
+            let file_path = self.parser().symbol_file_path(&symbol, &market);
+            let result = self
+                .read_range(&file_path)
+                .and_then(|buffer| projected.append(self.parser(), &buffer, &symbol, &market));
+            if let Err(e) = result {
+                warn!("查询文件失败 {}: {}", file_path.display(), e);
+            }
+        }
+        Ok(projected)
+    }
+
+    /// 按日期范围读取原始记录字节
+    pub(super) fn read_range(&self, file_path: &Path) -> Result<Vec<u8>> {
+        let mut file = DayFile::open(file_path)?;
+        let (start, end) = self.date_bounds(&mut file)?;
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        file.read_range(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn day_buffer(closes: &[u32]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for (i, close) in closes.iter().enumerate() {
+            for value in [20240102 + i as u32, 1000, 1100, 900, *close] {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+            buffer.extend_from_slice(&50_000.0f32.to_le_bytes());
+            buffer.extend_from_slice(&(100 * (i as u32 + 1)).to_le_bytes());
+            buffer.extend_from_slice(&0u32.to_le_bytes());
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_select_close_and_volume() {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path().join("vipdoc").join("sh").join("day");
+        std::fs::create_dir_all(&day_dir).unwrap();
+        std::fs::write(day_dir.join("600001.day"), day_buffer(&[1000, 1010])).unwrap();
+        std::fs::write(day_dir.join("600000.day"), day_buffer(&[950, 960, 970])).unwrap();
+        // 最低价高于收盘价，整只股票被跳过
+        std::fs::write(day_dir.join("600002.day"), day_buffer(&[800])).unwrap();
+
+        let parser = TDXDayParser::new(temp_dir.path());
+        let projected = parser
+            .query()
+            .select([Column::Close, Column::Volume])
+            .with_start_date(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap())
+            .execute_columns()
+            .unwrap();
+
+        assert_eq!(projected.columns(), vec![Column::Close, Column::Volume]);
+        assert!(projected.open().is_none() && projected.amount().is_none());
+        assert_eq!(projected.len(), 3);
+        assert_eq!(projected.close().unwrap(), &[9.6, 9.7, 10.1]);
+        assert_eq!(projected.volume().unwrap(), &[200, 300, 200]);
+        let segment = projected.segment("600001", "SH").unwrap();
+        assert_eq!((segment.start, segment.len), (2, 1));
+        assert!(projected.segment("600002", "SH").is_none());
+
+        // 单文件投影与完整解析一致
+        let file = day_dir.join("600000.day");
+        let full = parser.parse_file(&file).unwrap();
+        let closes = parser.parse_file_columns(&file, &[Column::Close]).unwrap();
+        let expected: Vec<f64> = full.iter().map(|r| r.close).collect();
+        assert_eq!(closes.close().unwrap(), expected.as_slice());
+        assert_eq!(closes.dates()[0], full[0].date);
+    }
+}
@@ -0,0 +1,341 @@
+//! 本地日线查询模块
+//!
+//! `TDXDayParser::query()` 按股票代码集合、市场和日期范围筛选本地day文件。
+//! 代码和市场在文件层面筛选；日期范围下推到文件读取：day文件记录定长且按日期顺序写入，
+//! 先二分查找范围的起止位置，只读取并解析范围内的记录。
+//! 查询“全市场最近30天”时每个文件只读取约30条记录，无需解析整个文件。
+//! 启用 `processors` 特性时，`execute_cached` 把结果存入共享的 `ResultCache`，
+//! 以候选文件的大小和修改时间为指纹，文件未变化时重复查询直接返回缓存。
+//! `select` 指定只需要的列后，`execute_columns` 返回按股票分段的列式结果（见 `projection`）。
+
+use super::projection::Column;
+use super::tdx_day::{DayFile, TDXDayParser, TDXDayRecord};
+use anyhow::Result;
+use chrono::NaiveDate;
+use log::warn;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// 本地日线查询
+#[derive(Debug)]
+pub struct DayQuery<'a> {
+    parser: &'a TDXDayParser,
+    /// 股票代码集合（None表示全部）
+    symbols: Option<HashSet<String>>,
+    /// 市场（SH/SZ，None表示全部）
+    market: Option<String>,
+    /// 起始日期（含）
+    start_date: Option<NaiveDate>,
+    /// 结束日期（含）
+    end_date: Option<NaiveDate>,
+    /// `execute_columns` 保存的列
+    projection: Vec<Column>,
+}
+
+impl TDXDayParser {
+    /// 创建查询，结果按解析器的排序方式排列
+    pub fn query(&self) -> DayQuery<'_> {
+        DayQuery {
+            parser: self,
+            symbols: None,
+            market: None,
+            start_date: None,
+            end_date: None,
+            projection: Column::ALL.to_vec(),
+        }
+    }
+}
+
+impl<'a> DayQuery<'a> {
+    /// 只查询指定股票代码
+    pub fn with_symbols<I, S>(mut self, symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.symbols = Some(symbols.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 只查询指定市场（SH/SZ）
+    pub fn with_market(mut self, market: &str) -> Self {
+        self.market = Some(market.to_uppercase());
+        self
+    }
+
+    /// 起始日期（含）
+    pub fn with_start_date(mut self, date: NaiveDate) -> Self {
+        self.start_date = Some(date);
+        self
+    }
+
+    /// 结束日期（含）
+    pub fn with_end_date(mut self, date: NaiveDate) -> Self {
+        self.end_date = Some(date);
+        self
+    }
+
+    /// 日期范围（两端均含）
+    pub fn with_date_range(self, start: NaiveDate, end: NaiveDate) -> Self {
+        self.with_start_date(start).with_end_date(end)
+    }
+
+    /// 只保存指定列，用于 `execute_columns`
+    pub fn select<I: IntoIterator<Item = Column>>(mut self, columns: I) -> Self {
+        self.projection = columns.into_iter().collect();
+        self
+    }
+
+    /// 执行查询，无法读取的文件记录警告后跳过
+    pub fn execute(&self) -> Result<Vec<TDXDayRecord>> {
+        self.check_date_range()?;
+
+        let mut runs = Vec::new();
+        for (symbol, market) in self.candidates()? {
+            let file_path = self.parser.symbol_file_path(&symbol, &market);
+            match self.read_file(&file_path, &symbol, &market) {
+                Ok(records) if !records.is_empty() => runs.push(records),
+                Ok(_) => {}
+                Err(e) => warn!("查询文件失败 {}: {}", file_path.display(), e),
+            }
+        }
+
+        Ok(self.parser.merge_runs(runs))
+    }
+
+    /// 执行查询，条件和候选文件都未变化时返回共享缓存中的结果
+    #[cfg(feature = "processors")]
+    pub fn execute_cached(
+        &self,
+        cache: &crate::result_cache::ResultCache,
+    ) -> Result<std::sync::Arc<Vec<TDXDayRecord>>> {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for (symbol, market) in self.candidates()? {
+            let file_path = self.parser.symbol_file_path(&symbol, &market);
+            file_path.hash(&mut hasher);
+            if let Ok(metadata) = std::fs::metadata(&file_path) {
+                metadata.len().hash(&mut hasher);
+                metadata.modified().ok().hash(&mut hasher);
+            }
+        }
+        let mut symbols: Option<Vec<&String>> = self.symbols.as_ref().map(|s| s.iter().collect());
+        if let Some(symbols) = symbols.as_mut() {
+            symbols.sort();
+        }
+        let params = format!(
+            "{:?}|{:?}|{:?}|{:?}|{:?}",
+            self.parser, symbols, self.market, self.start_date, self.end_date
+        );
+
+        let key = crate::result_cache::CacheKey::new("day_query", params, hasher.finish());
+        cache.get_or_try_insert_with(key, || self.execute())
+    }
+
+    pub(super) fn parser(&self) -> &TDXDayParser {
+        self.parser
+    }
+
+    pub(super) fn projection(&self) -> &[Column] {
+        &self.projection
+    }
+
+    pub(super) fn check_date_range(&self) -> Result<()> {
+        if let (Some(start), Some(end)) = (self.start_date, self.end_date) {
+            if start > end {
+                return Err(anyhow::anyhow!("起始日期晚于结束日期: {} > {}", start, end));
+            }
+        }
+        Ok(())
+    }
+
+    /// 符合代码与市场条件的股票；两者都已指定时直接检查文件，无需列出整个目录
+    pub(super) fn candidates(&self) -> Result<Vec<(String, String)>> {
+        if let (Some(symbols), Some(market)) = (&self.symbols, &self.market) {
+            let mut stocks: Vec<(String, String)> = symbols
+                .iter()
+                .filter(|symbol| self.parser.symbol_file_path(symbol, market).is_file())
+                .map(|symbol| (symbol.clone(), market.clone()))
+                .collect();
+            stocks.sort();
+            return Ok(stocks);
+        }
+
+        Ok(self
+            .parser
+            .get_stock_list()?
+            .into_iter()
+            .filter(|(symbol, market)| {
+                self.market.as_ref().is_none_or(|m| m == market)
+                    && self.symbols.as_ref().is_none_or(|s| s.contains(symbol))
+            })
+            .collect())
+    }
+
+    /// 二分定位日期范围的起止记录位置
+    pub(super) fn date_bounds(&self, file: &mut DayFile) -> Result<(usize, usize)> {
+        let start = match self.start_date {
+            Some(date) => file.lower_bound(date)?,
+            None => 0,
+        };
+        let end = match self.end_date.and_then(|date| date.succ_opt()) {
+            Some(next_day) => file.lower_bound(next_day)?,
+            None => file.len(),
+        };
+        Ok((start, end))
+    }
+
+    /// 二分定位日期范围后只解析范围内的记录
+    fn read_file(&self, file_path: &Path, symbol: &str, market: &str) -> Result<Vec<TDXDayRecord>> {
+        let buffer = self.read_range(file_path)?;
+        if buffer.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.parser.parse_binary_data(&buffer, symbol, market)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn day_buffer(dates: impl IntoIterator<Item = u32>) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for date in dates {
+            for value in [date, 1023, 1050, 1001, 1033] {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+            buffer.extend_from_slice(&10_330.0f32.to_le_bytes());
+            buffer.extend_from_slice(&1000u32.to_le_bytes());
+            buffer.extend_from_slice(&0u32.to_le_bytes());
+        }
+        buffer
+    }
+
+    /// 三只股票，2024年1月1日至30日每天一条记录
+    fn data_root() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        for (market, symbol) in [("sh", "600000"), ("sh", "600001"), ("sz", "000001")] {
+            let day_dir = temp_dir.path().join("vipdoc").join(market).join("day");
+            std::fs::create_dir_all(&day_dir).unwrap();
+            std::fs::write(
+                day_dir.join(format!("{}.day", symbol)),
+                day_buffer(20240101..=20240130),
+            )
+            .unwrap();
+        }
+        temp_dir
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    #[test]
+    fn test_query_date_range() {
+        let temp_dir = data_root();
+        let parser = TDXDayParser::new(temp_dir.path());
+
+        let records = parser
+            .query()
+            .with_date_range(date(10), date(12))
+            .execute()
+            .unwrap();
+        assert_eq!(records.len(), 9);
+        assert!(records
+            .iter()
+            .all(|r| r.date >= date(10) && r.date <= date(12)));
+        assert_eq!(records[0].date, date(10));
+        assert_eq!(records[8].date, date(12));
+
+        let recent = parser.query().with_start_date(date(28)).execute().unwrap();
+        assert_eq!(recent.len(), 9);
+        let early = parser.query().with_end_date(date(1)).execute().unwrap();
+        assert_eq!(early.len(), 3);
+        assert!(parser
+            .query()
+            .with_start_date(date(31))
+            .execute()
+            .unwrap()
+            .is_empty());
+        assert!(parser
+            .query()
+            .with_date_range(date(12), date(10))
+            .execute()
+            .is_err());
+    }
+
+    #[test]
+    fn test_query_symbols_and_market() {
+        let temp_dir = data_root();
+        let parser = TDXDayParser::new(temp_dir.path());
+
+        let sh = parser.query().with_market("sh").execute().unwrap();
+        assert_eq!(sh.len(), 60);
+        assert!(sh.iter().all(|r| r.market == "SH"));
+
+        let records = parser
+            .query()
+            .with_symbols(["000001", "600001"])
+            .with_market("SZ")
+            .with_date_range(date(5), date(6))
+            .execute()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.symbol == "000001"));
+    }
+
+    #[test]
+    fn test_query_matches_full_parse() {
+        let temp_dir = data_root();
+        // 非连续日期：二分查找须落在范围内的第一条记录
+        std::fs::write(
+            temp_dir.path().join("vipdoc/sz/day/000002.day"),
+            day_buffer([20231229, 20240105, 20240115, 20240201]),
+        )
+        .unwrap();
+        let parser = TDXDayParser::new(temp_dir.path());
+
+        let queried = parser
+            .query()
+            .with_date_range(date(2), date(15))
+            .execute()
+            .unwrap();
+        let expected: Vec<_> = parser
+            .parse_directory(temp_dir.path())
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.date >= date(2) && r.date <= date(15))
+            .collect();
+        assert_eq!(queried.len(), expected.len());
+        for (a, b) in queried.iter().zip(&expected) {
+            assert_eq!(
+                (&a.symbol, &a.market, a.date),
+                (&b.symbol, &b.market, b.date)
+            );
+        }
+    }
+
+    #[cfg(feature = "processors")]
+    #[test]
+    fn test_execute_cached() {
+        use crate::result_cache::ResultCache;
+
+        let temp_dir = data_root();
+        let parser = TDXDayParser::new(temp_dir.path());
+        let cache = ResultCache::new();
+        let query = parser.query().with_symbols(["600000"]).with_market("SH");
+
+        assert_eq!(query.execute_cached(&cache).unwrap().len(), 30);
+        assert_eq!(query.execute_cached(&cache).unwrap().len(), 30);
+        assert_eq!(cache.stats().hits, 1);
+
+        // 文件追加数据后指纹变化
+        let path = parser.symbol_file_path("600000", "SH");
+        std::fs::write(&path, day_buffer(20240101..=20240131)).unwrap();
+        assert_eq!(query.execute_cached(&cache).unwrap().len(), 31);
+        assert_eq!(cache.stats().hits, 1);
+    }
+}
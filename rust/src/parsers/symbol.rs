@@ -0,0 +1,276 @@
+//! 股票代码驻留模块
+//!
+//! 每条 `TDXDayRecord` 都持有代码和市场两个堆上 `String`，数千万条记录时这部分开销
+//! 超过行情数据本身。`SymbolTable` 把（代码, 市场）驻留为 `SymbolId(u32)`，
+//! 内部处理使用只保存编号的 `CompactDayRecord`，在对外接口处与 `TDXDayRecord` 互相转换。
+//! 可使用进程级的 `SymbolTable::global()`，也可按会话创建独立的表；编号只在所属的表内有效。
+
+use super::tdx_day::TDXDayRecord;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// 驻留后的股票编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SymbolId(pub u32);
+
+/// 股票代码驻留表（线程安全）
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    inner: RwLock<SymbolTableInner>,
+}
+
+#[derive(Debug, Default)]
+struct SymbolTableInner {
+    /// 编号 -> （代码, 市场）
+    entries: Vec<(Arc<str>, Arc<str>)>,
+    /// 代码 -> 各市场的编号（同代码不同市场对应多个编号）
+    index: HashMap<Arc<str>, Vec<SymbolId>>,
+}
+
+impl SymbolTableInner {
+    fn lookup(&self, symbol: &str, market: &str) -> Option<SymbolId> {
+        self.index
+            .get(symbol)?
+            .iter()
+            .copied()
+            .find(|id| &*self.entries[id.0 as usize].1 == market)
+    }
+
+    fn resolve(&self, id: SymbolId) -> Result<&(Arc<str>, Arc<str>)> {
+        self.entries
+            .get(id.0 as usize)
+            .ok_or_else(|| anyhow::anyhow!("未知的股票编号: {}", id.0))
+    }
+}
+
+impl SymbolTable {
+    /// 创建空的驻留表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 进程级共享的驻留表
+    pub fn global() -> &'static SymbolTable {
+        static GLOBAL: OnceLock<SymbolTable> = OnceLock::new();
+        GLOBAL.get_or_init(SymbolTable::new)
+    }
+
+    /// 驻留（代码, 市场），已存在时返回原编号
+    pub fn intern(&self, symbol: &str, market: &str) -> SymbolId {
+        if let Some(id) = self.lookup(symbol, market) {
+            return id;
+        }
+
+        let mut inner = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        // 获取写锁期间可能已被其他线程驻留
+        if let Some(id) = inner.lookup(symbol, market) {
+            return id;
+        }
+        let id = SymbolId(u32::try_from(inner.entries.len()).expect("驻留的股票数量超出u32范围"));
+        let symbol: Arc<str> = Arc::from(symbol);
+        inner.entries.push((symbol.clone(), Arc::from(market)));
+        inner.index.entry(symbol).or_default().push(id);
+        id
+    }
+
+    /// 查找已驻留的编号
+    pub fn lookup(&self, symbol: &str, market: &str) -> Option<SymbolId> {
+        self.read().lookup(symbol, market)
+    }
+
+    /// 编号对应的（代码, 市场）
+    pub fn resolve(&self, id: SymbolId) -> Option<(Arc<str>, Arc<str>)> {
+        self.read().resolve(id).ok().cloned()
+    }
+
+    /// 已驻留的股票数
+    pub fn len(&self) -> usize {
+        self.read().entries.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 转换为紧凑记录
+    pub fn compact(&self, records: &[TDXDayRecord]) -> Vec<CompactDayRecord> {
+        // 同一文件的记录代码相同，连续相同时复用上一个编号，免去加锁查找
+        let mut last: Option<(&TDXDayRecord, SymbolId)> = None;
+        records
+            .iter()
+            .map(|record| {
+                let id = match last {
+                    Some((prev, id))
+                        if prev.symbol == record.symbol && prev.market == record.market =>
+                    {
+                        id
+                    }
+                    _ => self.intern(&record.symbol, &record.market),
+                };
+                last = Some((record, id));
+                CompactDayRecord::with_id(record, id)
+            })
+            .collect()
+    }
+
+    /// 还原为日线记录，编号不属于本表时报错
+    pub fn expand(&self, records: &[CompactDayRecord]) -> Result<Vec<TDXDayRecord>> {
+        let inner = self.read();
+        records
+            .iter()
+            .map(|record| {
+                let (symbol, market) = inner.resolve(record.symbol)?;
+                Ok(record.with_symbol(symbol, market))
+            })
+            .collect()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, SymbolTableInner> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 紧凑日线记录：代码与市场以驻留编号表示，不含堆分配
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CompactDayRecord {
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 股票编号
+    pub symbol: SymbolId,
+    /// 开盘价（元）
+    pub open: f64,
+    /// 最高价（元）
+    pub high: f64,
+    /// 最低价（元）
+    pub low: f64,
+    /// 收盘价（元）
+    pub close: f64,
+    /// 成交量（股）
+    pub volume: u64,
+    /// 成交额（元）
+    pub amount: f64,
+}
+
+impl CompactDayRecord {
+    /// 由日线记录转换，代码与市场驻留到 `table`
+    pub fn from_record(record: &TDXDayRecord, table: &SymbolTable) -> Self {
+        Self::with_id(record, table.intern(&record.symbol, &record.market))
+    }
+
+    fn with_id(record: &TDXDayRecord, symbol: SymbolId) -> Self {
+        Self {
+            date: record.date,
+            symbol,
+            open: record.open,
+            high: record.high,
+            low: record.low,
+            close: record.close,
+            volume: record.volume,
+            amount: record.amount,
+        }
+    }
+
+    /// 还原为日线记录
+    pub fn to_record(&self, table: &SymbolTable) -> Result<TDXDayRecord> {
+        let (symbol, market) = table
+            .resolve(self.symbol)
+            .ok_or_else(|| anyhow::anyhow!("未知的股票编号: {}", self.symbol.0))?;
+        Ok(self.with_symbol(&symbol, &market))
+    }
+
+    fn with_symbol(&self, symbol: &str, market: &str) -> TDXDayRecord {
+        TDXDayRecord {
+            date: self.date,
+            symbol: symbol.to_string(),
+            open: self.open,
+            high: self.high,
+            low: self.low,
+            close: self.close,
+            volume: self.volume,
+            amount: self.amount,
+            market: market.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(symbol: &str, market: &str, day: u32) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 10.5,
+            low: 9.8,
+            close: 10.2,
+            volume: 1000,
+            amount: 10200.0,
+            market: market.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_intern_and_resolve() {
+        let table = SymbolTable::new();
+        let a = table.intern("000001", "SZ");
+        let b = table.intern("000001", "SH");
+        assert_ne!(a, b);
+        assert_eq!(table.intern("000001", "SZ"), a);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.lookup("000001", "SH"), Some(b));
+        assert_eq!(table.lookup("600000", "SH"), None);
+
+        let (symbol, market) = table.resolve(b).unwrap();
+        assert_eq!((&*symbol, &*market), ("000001", "SH"));
+        assert!(table.resolve(SymbolId(2)).is_none());
+    }
+
+    #[test]
+    fn test_compact_round_trip() {
+        let table = SymbolTable::new();
+        let records = vec![
+            record("600000", "SH", 2),
+            record("000001", "SZ", 2),
+            record("600000", "SH", 3),
+        ];
+        let compact = table.compact(&records);
+        assert_eq!(compact[0].symbol, compact[2].symbol);
+        assert_eq!(table.len(), 2);
+
+        let expanded = table.expand(&compact).unwrap();
+        assert_eq!(expanded[1].symbol, "000001");
+        assert_eq!(expanded[2].market, "SH");
+        assert_eq!(expanded[2].date, records[2].date);
+
+        // 编号只在所属的表内有效
+        assert!(SymbolTable::new().expand(&compact).is_err());
+        assert!(compact[0].to_record(&SymbolTable::new()).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_intern() {
+        let table = SymbolTable::new();
+        let ids: Vec<Vec<SymbolId>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..100)
+                            .map(|i| table.intern(&format!("{:06}", i), "SH"))
+                            .collect()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(table.len(), 100);
+        assert!(ids.iter().all(|thread_ids| *thread_ids == ids[0]));
+    }
+}
@@ -1,28 +1,37 @@
 //! 通达信日线数据解析器
 
 use anyhow::{Context, Result};
-use chrono::{NaiveDate, Utc};
+use chrono::{Datelike, NaiveDate};
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
+
+use super::price::{Price, PriceValue};
+use super::symbol::{CompactDayRecord, SymbolTable};
+use super::validation::ValidationProfile;
+
 /// 通达信日线记录结构
+///
+/// 价格类型默认为f64，需要精确计价时使用 `TDXDayRecord<Price>`（0.001元定点）。
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TDXDayRecord {
+pub struct TDXDayRecord<P = f64> {
     /// 交易日期
     pub date: NaiveDate,
     /// 股票代码
     pub symbol: String,
     /// 开盘价（元）
-    pub open: f64,
+    pub open: P,
     /// 最高价（元）
-    pub high: f64,
+    pub high: P,
     /// 最低价（元）
-    pub low: f64,
+    pub low: P,
     /// 收盘价（元）
-    pub close: f64,
+    pub close: P,
     /// 成交量（股）
     pub volume: u64,
     /// 成交额（元）
@@ -31,23 +40,223 @@ pub struct TDXDayRecord {
     pub market: String,
 }
 
-/// 二进制格式的日线记录（内存中）
+impl<P: PriceValue> TDXDayRecord<P> {
+    /// 转换价格表示，其余字段不变
+    pub fn convert<Q: PriceValue>(&self) -> TDXDayRecord<Q> {
+        TDXDayRecord {
+            date: self.date,
+            symbol: self.symbol.clone(),
+            open: Q::from_f64(self.open.to_f64()),
+            high: Q::from_f64(self.high.to_f64()),
+            low: Q::from_f64(self.low.to_f64()),
+            close: Q::from_f64(self.close.to_f64()),
+            volume: self.volume,
+            amount: self.amount,
+            market: self.market.clone(),
+        }
+    }
+
+    /// 转换为定点价格记录（四舍五入到0.001元）
+    pub fn to_fixed(&self) -> TDXDayRecord<Price> {
+        self.convert()
+    }
+
+    /// 转换为浮点价格记录
+    pub fn to_float(&self) -> TDXDayRecord {
+        self.convert()
+    }
+}
+
+impl From<TDXDayRecord> for TDXDayRecord<Price> {
+    fn from(record: TDXDayRecord) -> Self {
+        record.to_fixed()
+    }
+}
+
+impl From<TDXDayRecord<Price>> for TDXDayRecord {
+    fn from(record: TDXDayRecord<Price>) -> Self {
+        record.to_float()
+    }
+}
+
+/// 二进制格式的日线记录（通达信 `.day` 文件中每条32字节，小端）
 #[repr(C, packed)]
-#[derive(Debug)]
-struct BinaryDayRecord {
-    date: u32,
-    open: u32,
-    high: u32,
-    low: u32,
-    close: u32,
-    amount: f32,
-    volume: u32,
-    reserved: u32,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinaryDayRecord {
+    /// 日期（YYYYMMDD）
+    pub date: u32,
+    /// 开盘价（分）
+    pub open: u32,
+    /// 最高价（分）
+    pub high: u32,
+    /// 最低价（分）
+    pub low: u32,
+    /// 收盘价（分）
+    pub close: u32,
+    /// 成交额（元）
+    pub amount: f32,
+    /// 成交量（股）
+    pub volume: u32,
+    /// 保留字段
+    pub reserved: u32,
 }
 
 impl BinaryDayRecord {
     /// 字节大小
-    const SIZE: usize = std::mem::size_of::<BinaryDayRecord>();
+    pub const SIZE: usize = std::mem::size_of::<BinaryDayRecord>();
+
+    /// 从32字节解码
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let word = |i: usize| {
+            [
+                bytes[i * 4],
+                bytes[i * 4 + 1],
+                bytes[i * 4 + 2],
+                bytes[i * 4 + 3],
+            ]
+        };
+        Self {
+            date: u32::from_le_bytes(word(0)),
+            open: u32::from_le_bytes(word(1)),
+            high: u32::from_le_bytes(word(2)),
+            low: u32::from_le_bytes(word(3)),
+            close: u32::from_le_bytes(word(4)),
+            amount: f32::from_le_bytes(word(5)),
+            volume: u32::from_le_bytes(word(6)),
+            reserved: u32::from_le_bytes(word(7)),
+        }
+    }
+
+    /// 编码为32字节
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let Self {
+            date,
+            open,
+            high,
+            low,
+            close,
+            amount,
+            volume,
+            reserved,
+        } = *self;
+        let mut bytes = [0u8; Self::SIZE];
+        let words = [
+            date.to_le_bytes(),
+            open.to_le_bytes(),
+            high.to_le_bytes(),
+            low.to_le_bytes(),
+            close.to_le_bytes(),
+            amount.to_le_bytes(),
+            volume.to_le_bytes(),
+            reserved.to_le_bytes(),
+        ];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+            chunk.copy_from_slice(&word);
+        }
+        bytes
+    }
+
+    /// 由日线记录编码：价格四舍五入到分，成交额转为f32，成交量须在u32范围内
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn from_record(record: &TDXDayRecord) -> Result<Self> {
+        let cents = |price: f64| -> Result<u32> {
+            let cents = (price * 100.0).round();
+            if !(0.0..=u32::MAX as f64).contains(&cents) {
+                return Err(anyhow::anyhow!("价格超出二进制格式范围: {}", price));
+            }
+            Ok(cents as u32)
+        };
+        Ok(Self {
+            date: record.date.year() as u32 * 10_000
+                + record.date.month() * 100
+                + record.date.day(),
+            open: cents(record.open)?,
+            high: cents(record.high)?,
+            low: cents(record.low)?,
+            close: cents(record.close)?,
+            amount: record.amount as f32,
+            volume: u32::try_from(record.volume)
+                .map_err(|_| anyhow::anyhow!("成交量超出二进制格式范围: {}", record.volume))?,
+            reserved: 0,
+        })
+    }
+}
+
+/// 批量解析结果的排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortPolicy {
+    /// 按日期、股票代码、市场排序
+    #[default]
+    ByDate,
+    /// 按股票代码、市场、日期排序
+    BySymbol,
+    /// 不排序，按文件遍历顺序拼接（各文件内仍按日期排列）
+    None,
+}
+
+/// 按记录随机访问的day文件（记录定长且按日期顺序写入）
+#[derive(Debug)]
+pub(super) struct DayFile {
+    file: File,
+    count: usize,
+}
+
+impl DayFile {
+    /// 打开文件并校验大小
+    pub(super) fn open(file_path: &Path) -> Result<Self> {
+        let file = File::open(file_path)
+            .with_context(|| format!("无法打开文件: {}", file_path.display()))?;
+        let size = file.metadata()?.len() as usize;
+        if !size.is_multiple_of(BinaryDayRecord::SIZE) {
+            return Err(anyhow::anyhow!(
+                "文件大小不正确，期望{}的倍数，实际{}字节",
+                BinaryDayRecord::SIZE,
+                size
+            ));
+        }
+        Ok(Self {
+            file,
+            count: size / BinaryDayRecord::SIZE,
+        })
+    }
+
+    /// 记录数
+    pub(super) fn len(&self) -> usize {
+        self.count
+    }
+
+    /// 第 `index` 条记录的日期（日期是每条记录的前4个字节）
+    pub(super) fn date_at(&mut self, index: usize) -> Result<NaiveDate> {
+        let mut bytes = [0u8; 4];
+        self.file
+            .seek(SeekFrom::Start((index * BinaryDayRecord::SIZE) as u64))?;
+        self.file.read_exact(&mut bytes)?;
+        TDXDayParser::parse_date(u32::from_le_bytes(bytes))
+    }
+
+    /// 第一条日期不早于 `date` 的记录位置（二分查找），都早于时返回记录数
+    pub(super) fn lower_bound(&mut self, date: NaiveDate) -> Result<usize> {
+        let (mut lo, mut hi) = (0, self.count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.date_at(mid)? < date {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(lo)
+    }
+
+    /// 读取 `[start, end)` 范围内记录的原始字节
+    pub(super) fn read_range(&mut self, start: usize, end: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![0u8; (end - start) * BinaryDayRecord::SIZE];
+        self.file
+            .seek(SeekFrom::Start((start * BinaryDayRecord::SIZE) as u64))?;
+        self.file.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
 }
 
 /// 通达信解析器
@@ -55,6 +264,10 @@ impl BinaryDayRecord {
 pub struct TDXDayParser {
     /// 数据根目录
     pub data_root: PathBuf,
+    /// 批量解析结果的排序方式
+    sort_policy: SortPolicy,
+    /// 价格校验配置
+    profile: ValidationProfile,
 }
 
 impl TDXDayParser {
@@ -62,9 +275,28 @@ impl TDXDayParser {
     pub fn new<P: AsRef<Path>>(data_root: P) -> Self {
         Self {
             data_root: data_root.as_ref().to_path_buf(),
+            sort_policy: SortPolicy::default(),
+            profile: ValidationProfile::default(),
         }
     }
 
+    /// 设置 `parse_directory` 等批量解析结果的排序方式
+    pub fn with_sort_policy(mut self, sort_policy: SortPolicy) -> Self {
+        self.sort_policy = sort_policy;
+        self
+    }
+
+    /// 设置价格校验配置
+    pub fn with_validation_profile(mut self, profile: ValidationProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// 价格校验配置
+    pub(super) fn validation_profile(&self) -> &ValidationProfile {
+        &self.profile
+    }
+
     /// 解析单个day文件
     pub fn parse_file<P: AsRef<Path>>(&self, file_path: P) -> Result<Vec<TDXDayRecord>> {
         let file_path = file_path.as_ref();
@@ -84,6 +316,20 @@ impl TDXDayParser {
         self.parse_binary_data(&buffer, &symbol, &market)
     }
 
+    /// 解析单个day文件，价格为定点表示
+    ///
+    /// 文件中的价格以分存储，换算为 `Price` 时四舍五入到0.001元，结果与原始分值精确一致。
+    pub fn parse_file_fixed<P: AsRef<Path>>(
+        &self,
+        file_path: P,
+    ) -> Result<Vec<TDXDayRecord<Price>>> {
+        Ok(self
+            .parse_file(file_path)?
+            .iter()
+            .map(TDXDayRecord::to_fixed)
+            .collect())
+    }
+
     /// 解析二进制数据
     pub fn parse_binary_data(
         &self,
@@ -91,7 +337,7 @@ impl TDXDayParser {
         symbol: &str,
         market: &str,
     ) -> Result<Vec<TDXDayRecord>> {
-        if buffer.len() % BinaryDayRecord::SIZE != 0 {
+        if !buffer.len().is_multiple_of(BinaryDayRecord::SIZE) {
             return Err(anyhow::anyhow!(
                 "文件大小不正确，期望{}的倍数，实际{}字节",
                 BinaryDayRecord::SIZE,
@@ -105,10 +351,7 @@ impl TDXDayParser {
         for i in 0..record_count {
             let offset = i * BinaryDayRecord::SIZE;
             let record_slice = &buffer[offset..offset + BinaryDayRecord::SIZE];
-
-            // 安全地转换字节数组到结构体
-            let binary_record: BinaryDayRecord =
-                unsafe { std::ptr::read_unaligned(record_slice.as_ptr() as *const _) };
+            let binary_record = BinaryDayRecord::from_bytes(record_slice.try_into()?);
 
             // 转换为高级数据结构
             let record = self.convert_binary_record(&binary_record, symbol, market)?;
@@ -116,7 +359,8 @@ impl TDXDayParser {
         }
 
         // 按日期排序（通达信数据通常是正序的，但确保一致性）
-        records.sort_by(|a, b| a.date.cmp(&b.date));
+        records.sort_by_key(|r| r.date);
+        self.profile.validate_moves(&records)?;
 
         Ok(records)
     }
@@ -128,26 +372,7 @@ impl TDXDayParser {
         symbol: &str,
         market: &str,
     ) -> Result<TDXDayRecord> {
-        // 验证日期有效性
-        // 安全地读取日期字段
-        let date = binary.date;
-        let date_str = date.to_string();
-        if date_str.len() != 8 {
-            return Err(anyhow::anyhow!("无效的日期格式: {}", date_str));
-        }
-
-        let year = date_str[0..4]
-            .parse::<i32>()
-            .with_context(|| format!("无效的年份: {}", &date_str[0..4]))?;
-        let month = date_str[4..6]
-            .parse::<u32>()
-            .with_context(|| format!("无效的月份: {}", &date_str[4..6]))?;
-        let day = date_str[6..8]
-            .parse::<u32>()
-            .with_context(|| format!("无效的日期: {}", &date_str[6..8]))?;
-
-        let date = NaiveDate::from_ymd_opt(year, month, day)
-            .ok_or_else(|| anyhow::anyhow!("无效的日期: {}", date_str))?;
+        let date = Self::parse_date(binary.date)?;
 
         // 价格转换（分为单位转换为元）
         let open = binary.open as f64 / 100.0;
@@ -156,7 +381,8 @@ impl TDXDayParser {
         let close = binary.close as f64 / 100.0;
 
         // 验证价格合理性
-        self.validate_prices(open, high, low, close)?;
+        self.profile
+            .validate_prices(symbol, market, open, high, low, close)?;
 
         Ok(TDXDayRecord {
             date,
@@ -171,29 +397,13 @@ impl TDXDayParser {
         })
     }
 
-    /// 验证价格数据合理性
-    fn validate_prices(&self, open: f64, high: f64, low: f64, close: f64) -> Result<()> {
-        // 检查价格是否为正数
-        if open <= 0.0 || high <= 0.0 || low <= 0.0 || close <= 0.0 {
-            return Err(anyhow::anyhow!("价格必须为正数"));
+    /// 解析YYYYMMDD格式的日期字段（按数值拆分，任意输入都不会panic）
+    pub(super) fn parse_date(date: u32) -> Result<NaiveDate> {
+        if !(10_000_000..=99_999_999).contains(&date) {
+            return Err(anyhow::anyhow!("无效的日期格式: {}", date));
         }
-
-        // 检查高低价关系
-        if high < low {
-            return Err(anyhow::anyhow!("最高价不能低于最低价"));
-        }
-
-        // 检查开收盘价是否在高低价范围内
-        if open > high || open < low || close > high || close < low {
-            return Err(anyhow::anyhow!("开收盘价超出高低价范围"));
-        }
-
-        // 检查价格是否合理（1分-10000元）
-        if open < 0.01 || high > 10000.0 || low < 0.01 || close > 10000.0 {
-            return Err(anyhow::anyhow!("价格超出合理范围"));
-        }
-
-        Ok(())
+        NaiveDate::from_ymd_opt((date / 10_000) as i32, date / 100 % 100, date % 100)
+            .ok_or_else(|| anyhow::anyhow!("无效的日期: {}", date))
     }
 
     /// 从文件路径提取股票代码和市场
@@ -223,7 +433,7 @@ impl TDXDayParser {
     /// 解析目录下的所有day文件
     pub fn parse_directory<P: AsRef<Path>>(&self, dir_path: P) -> Result<Vec<TDXDayRecord>> {
         let dir_path = dir_path.as_ref();
-        let mut all_records = Vec::new();
+        let mut runs = Vec::new();
 
         if !dir_path.exists() {
             return Err(anyhow::anyhow!("目录不存在: {}", dir_path.display()));
@@ -235,9 +445,9 @@ impl TDXDayParser {
 
             if path.extension().and_then(|s| s.to_str()) == Some("day") {
                 match self.parse_file(path) {
-                    Ok(mut records) => {
+                    Ok(records) => {
                         info!("解析文件成功: {}, {}条记录", path.display(), records.len());
-                        all_records.append(&mut records);
+                        runs.push(records);
                     }
                     Err(e) => {
                         warn!("解析文件失败 {}: {}", path.display(), e);
@@ -247,17 +457,175 @@ impl TDXDayParser {
             }
         }
 
-        // 按日期和股票代码排序
-        all_records.sort_by(|a, b| {
-            a.date
-                .cmp(&b.date)
-                .then(a.symbol.cmp(&b.symbol))
-                .then(a.market.cmp(&b.market))
-        });
+        Ok(self.merge_runs(runs))
+    }
+
+    /// 解析目录下的所有day文件为紧凑记录，代码与市场驻留到 `table`
+    ///
+    /// 逐个文件转换，不会同时持有全部记录的 `String`；排序方式中的股票顺序按驻留编号而非代码。
+    pub fn parse_directory_compact<P: AsRef<Path>>(
+        &self,
+        dir_path: P,
+        table: &SymbolTable,
+    ) -> Result<Vec<CompactDayRecord>> {
+        let dir_path = dir_path.as_ref();
+        let mut all_records = Vec::new();
+
+        if !dir_path.exists() {
+            return Err(anyhow::anyhow!("目录不存在: {}", dir_path.display()));
+        }
+
+        for entry in WalkDir::new(dir_path).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+
+            if path.extension().and_then(|s| s.to_str()) == Some("day") {
+                match self.parse_file(path) {
+                    Ok(records) => {
+                        info!("解析文件成功: {}, {}条记录", path.display(), records.len());
+                        all_records.extend(table.compact(&records));
+                    }
+                    Err(e) => warn!("解析文件失败 {}: {}", path.display(), e),
+                }
+            }
+        }
 
+        match self.sort_policy {
+            SortPolicy::ByDate => all_records.sort_by_key(|record| (record.date, record.symbol)),
+            SortPolicy::BySymbol => all_records.sort_by_key(|record| (record.symbol, record.date)),
+            SortPolicy::None => {}
+        }
         Ok(all_records)
     }
 
+    /// 直接解析压缩备份中的day文件（`archive` 特性）
+    ///
+    /// 支持 .zip 与 .tar.gz/.tgz，条目逐个读入内存交给二进制解析器，不解压到磁盘。
+    /// 市场从条目路径中的 sh/sz 目录判断，与 `parse_directory` 一致。
+    #[cfg(feature = "archive")]
+    pub fn parse_archive<P: AsRef<Path>>(&self, archive_path: P) -> Result<Vec<TDXDayRecord>> {
+        let archive_path = archive_path.as_ref();
+        let file_name = archive_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let file = File::open(archive_path)
+            .with_context(|| format!("无法打开压缩文件: {}", archive_path.display()))?;
+        let mut runs = Vec::new();
+
+        if file_name.ends_with(".zip") {
+            let mut archive = zip::ZipArchive::new(file)
+                .with_context(|| format!("无法读取zip归档: {}", archive_path.display()))?;
+            for i in 0..archive.len() {
+                let mut entry = archive
+                    .by_index(i)
+                    .with_context(|| format!("无法获取zip文件索引: {}", i))?;
+                if !entry.is_file() || !entry.name().ends_with(".day") {
+                    continue;
+                }
+
+                let name = entry.name().to_string();
+                let mut buffer = Vec::with_capacity(entry.size() as usize);
+                entry
+                    .read_to_end(&mut buffer)
+                    .with_context(|| format!("无法读取zip条目: {}", name))?;
+                self.parse_archive_entry(&name, &buffer, &mut runs);
+            }
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            let entries = archive
+                .entries()
+                .with_context(|| format!("无法读取tar归档: {}", archive_path.display()))?;
+            for entry in entries {
+                let mut entry = entry.with_context(|| "无法读取tar条目")?;
+                let name = entry.path()?.to_string_lossy().into_owned();
+                if !entry.header().entry_type().is_file() || !name.ends_with(".day") {
+                    continue;
+                }
+
+                let mut buffer = Vec::with_capacity(entry.size() as usize);
+                entry
+                    .read_to_end(&mut buffer)
+                    .with_context(|| format!("无法读取tar条目: {}", name))?;
+                self.parse_archive_entry(&name, &buffer, &mut runs);
+            }
+        } else {
+            return Err(anyhow::anyhow!(
+                "不支持的压缩格式: {}",
+                archive_path.display()
+            ));
+        }
+
+        Ok(self.merge_runs(runs))
+    }
+
+    /// 解析压缩包中的单个条目，失败时记录警告并跳过
+    #[cfg(feature = "archive")]
+    fn parse_archive_entry(&self, name: &str, buffer: &[u8], runs: &mut Vec<Vec<TDXDayRecord>>) {
+        // 条目路径是相对路径，补上前导分隔符以便匹配 /sh/、/sz/ 目录
+        let entry_path = PathBuf::from(format!("/{}", name));
+        let parsed = self
+            .extract_symbol_market(&entry_path)
+            .and_then(|(symbol, market)| self.parse_binary_data(buffer, &symbol, &market));
+
+        match parsed {
+            Ok(parsed) => {
+                info!("解析压缩条目成功: {}, {}条记录", name, parsed.len());
+                runs.push(parsed);
+            }
+            Err(e) => warn!("解析压缩条目失败 {}: {}", name, e),
+        }
+    }
+
+    /// 按排序方式合并各文件的解析结果
+    ///
+    /// 每个文件只含一只股票且已按日期排列，先按（代码, 市场）给文件排名，再以
+    /// （日期, 排名）或（排名, 日期）为键做k路归并，复杂度O(N log k)，无需对全部记录重新排序。
+    /// 代码相同的文件排名相同，归并后与稳定排序的结果一致。
+    pub(super) fn merge_runs(&self, mut runs: Vec<Vec<TDXDayRecord>>) -> Vec<TDXDayRecord> {
+        if self.sort_policy == SortPolicy::None {
+            return runs.into_iter().flatten().collect();
+        }
+
+        runs.retain(|run| !run.is_empty());
+        runs.sort_by(|a, b| (&a[0].symbol, &a[0].market).cmp(&(&b[0].symbol, &b[0].market)));
+        let mut ranks = Vec::with_capacity(runs.len());
+        for i in 0..runs.len() {
+            let same = i > 0
+                && runs[i][0].symbol == runs[i - 1][0].symbol
+                && runs[i][0].market == runs[i - 1][0].market;
+            ranks.push(if same { ranks[i - 1] } else { i });
+        }
+
+        let policy = self.sort_policy;
+        let key = |date: NaiveDate, run: usize| {
+            let day = date.num_days_from_ce() as i64;
+            match policy {
+                SortPolicy::BySymbol => Reverse((ranks[run] as i64, day, run)),
+                _ => Reverse((day, ranks[run] as i64, run)),
+            }
+        };
+
+        let total = runs.iter().map(Vec::len).sum();
+        let mut iters: Vec<_> = runs.into_iter().map(Vec::into_iter).collect();
+        let mut heads: Vec<Option<TDXDayRecord>> = iters.iter_mut().map(Iterator::next).collect();
+        let mut heap: BinaryHeap<_> = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(run, head)| head.as_ref().map(|record| key(record.date, run)))
+            .collect();
+
+        let mut merged = Vec::with_capacity(total);
+        while let Some(Reverse((_, _, run))) = heap.pop() {
+            merged.extend(heads[run].take());
+            heads[run] = iters[run].next();
+            if let Some(record) = &heads[run] {
+                heap.push(key(record.date, run));
+            }
+        }
+        merged
+    }
+
     /// 获取所有股票列表
     pub fn get_stock_list(&self) -> Result<Vec<(String, String)>> {
         let mut stocks = Vec::new();
@@ -309,72 +677,68 @@ impl TDXDayParser {
 
     /// 获取指定股票的历史数据
     pub fn get_data_by_symbol(&self, symbol: &str, market: &str) -> Result<Vec<TDXDayRecord>> {
-        let file_path = self
-            .data_root
+        self.parse_file(self.symbol_file_path(symbol, market))
+    }
+
+    /// 指定股票的day文件路径
+    pub(super) fn symbol_file_path(&self, symbol: &str, market: &str) -> PathBuf {
+        self.data_root
             .join("vipdoc")
             .join(market.to_lowercase())
             .join("day")
-            .join(format!("{}.day", symbol));
-
-        self.parse_file(file_path)
+            .join(format!("{}.day", symbol))
     }
 
     /// 获取数据统计信息
+    ///
+    /// 不解析文件内容：记录数由文件大小推算，日期范围只读取每个文件的首尾两条记录
+    /// （通达信按日期顺序写入）。无法读取的文件记录警告后跳过。
     pub fn get_statistics(&self) -> Result<TDXStatistics> {
         let stocks = self.get_stock_list()?;
-        let total_stocks = stocks.len();
-        let mut total_records = 0;
-        let mut earliest_date = None;
-        let mut latest_date = None;
-        let mut sh_count = 0;
-        let mut sz_count = 0;
+        let mut statistics = TDXStatistics {
+            total_stocks: stocks.len(),
+            total_records: 0,
+            sh_count: 0,
+            sz_count: 0,
+            earliest_date: None,
+            latest_date: None,
+            data_size_bytes: self.calculate_data_size()?,
+        };
 
         for (symbol, market) in &stocks {
-            match self.get_data_by_symbol(symbol, market) {
-                Ok(records) => {
-                    total_records += records.len();
-
-                    if let Some(first_record) = records.first() {
-                        match &earliest_date {
-                            None => earliest_date = Some(first_record.date),
-                            Some(current) => {
-                                if first_record.date < *current {
-                                    earliest_date = Some(first_record.date);
-                                }
-                            }
-                        }
+            match market.as_str() {
+                "SH" => statistics.sh_count += 1,
+                "SZ" => statistics.sz_count += 1,
+                _ => {}
+            }
 
-                        match &latest_date {
-                            None => latest_date = Some(first_record.date),
-                            Some(current) => {
-                                if first_record.date > *current {
-                                    latest_date = Some(first_record.date);
-                                }
-                            }
-                        }
+            let file_path = self.symbol_file_path(symbol, market);
+            match Self::read_file_summary(&file_path) {
+                Ok((count, range)) => {
+                    statistics.total_records += count;
+                    if let Some((first, last)) = range {
+                        statistics.earliest_date =
+                            Some(statistics.earliest_date.map_or(first, |d| d.min(first)));
+                        statistics.latest_date =
+                            Some(statistics.latest_date.map_or(last, |d| d.max(last)));
                     }
-
-                    // match market {
-                    //     "SH" => sh_count += 1,
-                    //     "SZ" => sz_count += 1,
-                    //     _ => {}
-                    // }
-                }
-                Err(_) => {
-                    // 忽略无法读取的股票数据
                 }
+                Err(e) => warn!("读取文件摘要失败 {}: {}", file_path.display(), e),
             }
         }
 
-        Ok(TDXStatistics {
-            total_stocks,
-            total_records,
-            sh_count,
-            sz_count,
-            earliest_date,
-            latest_date,
-            data_size_bytes: self.calculate_data_size()?,
-        })
+        Ok(statistics)
+    }
+
+    /// 读取day文件的记录数及首尾记录的日期
+    fn read_file_summary(file_path: &Path) -> Result<(usize, Option<(NaiveDate, NaiveDate)>)> {
+        let mut file = DayFile::open(file_path)?;
+        if file.len() == 0 {
+            return Ok((0, None));
+        }
+        let first = file.date_at(0)?;
+        let last = file.date_at(file.len() - 1)?;
+        Ok((file.len(), Some((first, last))))
     }
 
     /// 计算数据文件总大小
@@ -387,7 +751,7 @@ impl TDXDayParser {
         {
             let path = entry.path();
             if path.is_file() {
-                if let Some(metadata) = path.metadata().ok() {
+                if let Ok(metadata) = path.metadata() {
                     total_size += metadata.len();
                 }
             }
@@ -419,7 +783,6 @@ pub struct TDXStatistics {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
     use tempfile::TempDir;
 
     #[test]
@@ -451,5 +814,224 @@ mod tests {
     #[test]
     fn test_binary_record_size() {
         assert_eq!(BinaryDayRecord::SIZE, 32);
+
+        let record = TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 3, 8).unwrap(),
+            symbol: "600000".to_string(),
+            open: 10.01,
+            high: 10.5,
+            low: 9.99,
+            close: 10.2,
+            volume: 123_400,
+            amount: 1_258_680.0,
+            market: "SH".to_string(),
+        };
+        let binary = BinaryDayRecord::from_record(&record).unwrap();
+        assert_eq!({ binary.date }, 20240308);
+        assert_eq!(BinaryDayRecord::from_bytes(&binary.to_bytes()), binary);
+        let parsed = TDXDayParser::new(".")
+            .parse_binary_data(&binary.to_bytes(), "600000", "SH")
+            .unwrap();
+        assert_eq!(parsed[0].open, 10.01);
+        assert_eq!(parsed[0].volume, 123_400);
+        assert!(BinaryDayRecord::from_record(&TDXDayRecord {
+            volume: u64::MAX,
+            ..record
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_fixed_price_records() {
+        let parser = TDXDayParser::new(".");
+        let mut buffer = Vec::new();
+        for value in [20240102u32, 1023, 1050, 1001, 1033] {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        buffer.extend_from_slice(&10_330.0f32.to_le_bytes());
+        buffer.extend_from_slice(&1000u32.to_le_bytes());
+        buffer.extend_from_slice(&0u32.to_le_bytes());
+
+        let records = parser.parse_binary_data(&buffer, "600000", "SH").unwrap();
+        let fixed = records[0].to_fixed();
+        assert_eq!(fixed.open, Price::from_raw(10_230));
+        assert_eq!(fixed.close.to_string(), "10.330");
+        assert_eq!(fixed.to_float().close, records[0].close);
+
+        // 定点记录同样实现Bar，写入时按0.001元取整
+        let mut fixed = fixed;
+        crate::parsers::Bar::set_close(&mut fixed, 10.3349);
+        assert_eq!(fixed.close, Price::from_raw(10_335));
+    }
+
+    #[test]
+    fn test_statistics_from_file_headers() {
+        let day_buffer = |dates: &[u32]| {
+            let mut buffer = Vec::new();
+            for &date in dates {
+                for value in [date, 1023, 1050, 1001, 1033] {
+                    buffer.extend_from_slice(&value.to_le_bytes());
+                }
+                buffer.extend_from_slice(&10_330.0f32.to_le_bytes());
+                buffer.extend_from_slice(&1000u32.to_le_bytes());
+                buffer.extend_from_slice(&0u32.to_le_bytes());
+            }
+            buffer
+        };
+
+        let temp_dir = TempDir::new().unwrap();
+        let files = [
+            ("sh", "600000", vec![20240102, 20240103, 20240104]),
+            ("sh", "600001", vec![20230601, 20230602]),
+            ("sz", "000001", vec![20240103, 20240105]),
+        ];
+        for (market, symbol, dates) in &files {
+            let day_dir = temp_dir.path().join("vipdoc").join(market).join("day");
+            std::fs::create_dir_all(&day_dir).unwrap();
+            std::fs::write(day_dir.join(format!("{}.day", symbol)), day_buffer(dates)).unwrap();
+        }
+        // 大小不是32的倍数的文件计入股票数，但不计记录数
+        std::fs::write(temp_dir.path().join("vipdoc/sz/day/000002.day"), b"bad").unwrap();
+
+        let stats = TDXDayParser::new(temp_dir.path()).get_statistics().unwrap();
+        assert_eq!(stats.total_stocks, 4);
+        assert_eq!((stats.sh_count, stats.sz_count), (2, 2));
+        assert_eq!(stats.total_records, 7);
+        assert_eq!(stats.earliest_date, NaiveDate::from_ymd_opt(2023, 6, 1));
+        assert_eq!(stats.latest_date, NaiveDate::from_ymd_opt(2024, 1, 5));
+    }
+
+    #[test]
+    fn test_sort_policies() {
+        let day_buffer = |dates: &[u32]| {
+            let mut buffer = Vec::new();
+            for &date in dates {
+                for value in [date, 1023, 1050, 1001, 1033] {
+                    buffer.extend_from_slice(&value.to_le_bytes());
+                }
+                buffer.extend_from_slice(&10_330.0f32.to_le_bytes());
+                buffer.extend_from_slice(&1000u32.to_le_bytes());
+                buffer.extend_from_slice(&0u32.to_le_bytes());
+            }
+            buffer
+        };
+
+        let temp_dir = TempDir::new().unwrap();
+        let files = [
+            ("sh", "600000", vec![20240102, 20240104]),
+            ("sz", "000001", vec![20240103, 20240104, 20240105]),
+            ("sh", "000001", vec![20240102]),
+        ];
+        for (market, symbol, dates) in &files {
+            let day_dir = temp_dir.path().join(market).join("day");
+            std::fs::create_dir_all(&day_dir).unwrap();
+            std::fs::write(day_dir.join(format!("{}.day", symbol)), day_buffer(dates)).unwrap();
+        }
+        let keys = |records: &[TDXDayRecord]| -> Vec<(String, String, NaiveDate)> {
+            records
+                .iter()
+                .map(|r| (r.symbol.clone(), r.market.clone(), r.date))
+                .collect()
+        };
+
+        let unsorted = TDXDayParser::new(temp_dir.path())
+            .with_sort_policy(SortPolicy::None)
+            .parse_directory(temp_dir.path())
+            .unwrap();
+        assert_eq!(unsorted.len(), 6);
+
+        let mut expected = unsorted.clone();
+        expected
+            .sort_by(|a, b| (a.date, &a.symbol, &a.market).cmp(&(b.date, &b.symbol, &b.market)));
+        let by_date = TDXDayParser::new(temp_dir.path())
+            .parse_directory(temp_dir.path())
+            .unwrap();
+        assert_eq!(keys(&by_date), keys(&expected));
+
+        expected
+            .sort_by(|a, b| (&a.symbol, &a.market, a.date).cmp(&(&b.symbol, &b.market, b.date)));
+        let by_symbol = TDXDayParser::new(temp_dir.path())
+            .with_sort_policy(SortPolicy::BySymbol)
+            .parse_directory(temp_dir.path())
+            .unwrap();
+        assert_eq!(keys(&by_symbol), keys(&expected));
+    }
+
+    #[test]
+    fn test_parse_directory_compact() {
+        let mut buffer = Vec::new();
+        for value in [20240102u32, 1023, 1050, 1001, 1033] {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        buffer.extend_from_slice(&10_330.0f32.to_le_bytes());
+        buffer.extend_from_slice(&1000u32.to_le_bytes());
+        buffer.extend_from_slice(&0u32.to_le_bytes());
+
+        let temp_dir = TempDir::new().unwrap();
+        for (market, symbol) in [("sh", "600000"), ("sz", "000001")] {
+            let day_dir = temp_dir.path().join(market).join("day");
+            std::fs::create_dir_all(&day_dir).unwrap();
+            std::fs::write(day_dir.join(format!("{}.day", symbol)), &buffer).unwrap();
+        }
+
+        let parser = TDXDayParser::new(temp_dir.path());
+        let table = SymbolTable::new();
+        let compact = parser
+            .parse_directory_compact(temp_dir.path(), &table)
+            .unwrap();
+        assert_eq!(compact.len(), 2);
+        assert_eq!(table.len(), 2);
+
+        let mut expanded = table.expand(&compact).unwrap();
+        let mut expected = parser.parse_directory(temp_dir.path()).unwrap();
+        expanded.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        expected.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        for (a, b) in expanded.iter().zip(&expected) {
+            assert_eq!(
+                (&a.symbol, &a.market, a.close),
+                (&b.symbol, &b.market, b.close)
+            );
+        }
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_parse_archive() {
+        use crate::parsers::CompressionUtils;
+
+        let mut buffer = Vec::new();
+        for value in [20240102u32, 1023, 1050, 1001, 1033] {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        buffer.extend_from_slice(&10_330.0f32.to_le_bytes());
+        buffer.extend_from_slice(&1000u32.to_le_bytes());
+        buffer.extend_from_slice(&0u32.to_le_bytes());
+
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("vipdoc");
+        for market in ["sh", "sz"] {
+            let day_dir = source.join(market).join("day");
+            std::fs::create_dir_all(&day_dir).unwrap();
+            let symbol = if market == "sh" { "600000" } else { "000001" };
+            std::fs::write(day_dir.join(format!("{}.day", symbol)), &buffer).unwrap();
+        }
+        // 损坏的条目被跳过
+        std::fs::write(source.join("sh").join("day").join("600001.day"), b"bad").unwrap();
+
+        let parser = TDXDayParser::new(temp_dir.path());
+        let zip_path = temp_dir.path().join("backup.zip");
+        CompressionUtils::compress_to_zip(&source, &zip_path).unwrap();
+        let tar_path = temp_dir.path().join("backup.tar.gz");
+        CompressionUtils::compress_to_tar_gz(&source, &tar_path).unwrap();
+
+        for path in [&zip_path, &tar_path] {
+            let records = parser.parse_archive(path).unwrap();
+            assert_eq!(records.len(), 2);
+            assert_eq!(records[0].symbol, "000001");
+            assert_eq!(records[0].market, "SZ");
+            assert_eq!(records[1].market, "SH");
+            assert_eq!(records[1].close, 10.33);
+        }
+        assert!(parser.parse_archive(temp_dir.path().join("a.rar")).is_err());
     }
 }
@@ -0,0 +1,229 @@
+//! 通达信分钟线数据解析器
+//!
+//! 解析vipdoc目录下 `minline/*.lc1`（1分钟）和 `fzline/*.lc5`（5分钟）文件。
+//! 每条记录32字节：u16日期、u16分钟数（自零点起）、f32开高低收、f32成交额、u32成交量、4字节保留。
+
+use super::tdx_day::TDXDayParser;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// 分钟线记录大小（字节）
+const MINUTE_RECORD_SIZE: usize = 32;
+
+/// 分钟线记录（时间为该分钟K线的结束时间）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TDXMinuteRecord {
+    /// K线结束时间
+    pub datetime: NaiveDateTime,
+    /// 股票代码
+    pub symbol: String,
+    /// 开盘价（元）
+    pub open: f64,
+    /// 最高价（元）
+    pub high: f64,
+    /// 最低价（元）
+    pub low: f64,
+    /// 收盘价（元）
+    pub close: f64,
+    /// 成交量（股）
+    pub volume: u64,
+    /// 成交额（元）
+    pub amount: f64,
+    /// 市场（SH/SZ）
+    pub market: String,
+}
+
+/// 通达信分钟线解析器
+#[derive(Debug)]
+pub struct TDXMinuteParser {
+    /// 数据根目录
+    pub data_root: PathBuf,
+}
+
+impl TDXMinuteParser {
+    /// 创建新的解析器
+    pub fn new<P: AsRef<Path>>(data_root: P) -> Self {
+        Self {
+            data_root: data_root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// 解析单个分钟线文件
+    pub fn parse_file<P: AsRef<Path>>(&self, file_path: P) -> Result<Vec<TDXMinuteRecord>> {
+        let file_path = file_path.as_ref();
+        let (symbol, market) =
+            TDXDayParser::new(&self.data_root).extract_symbol_market(file_path)?;
+        let buffer = std::fs::read(file_path)
+            .with_context(|| format!("无法读取文件: {}", file_path.display()))?;
+        self.parse_binary_data(&buffer, &symbol, &market)
+    }
+
+    /// 解析二进制数据
+    pub fn parse_binary_data(
+        &self,
+        buffer: &[u8],
+        symbol: &str,
+        market: &str,
+    ) -> Result<Vec<TDXMinuteRecord>> {
+        if !buffer.len().is_multiple_of(MINUTE_RECORD_SIZE) {
+            return Err(anyhow::anyhow!(
+                "文件大小不正确，期望{}的倍数，实际{}字节",
+                MINUTE_RECORD_SIZE,
+                buffer.len()
+            ));
+        }
+
+        let mut records = buffer
+            .chunks_exact(MINUTE_RECORD_SIZE)
+            .map(|chunk| decode_record(chunk, symbol, market))
+            .collect::<Result<Vec<_>>>()?;
+        records.sort_by_key(|r| r.datetime);
+        Ok(records)
+    }
+
+    /// 解析目录下的所有分钟线文件（.lc1/.lc5）
+    pub fn parse_directory<P: AsRef<Path>>(&self, dir_path: P) -> Result<Vec<TDXMinuteRecord>> {
+        let dir_path = dir_path.as_ref();
+        if !dir_path.exists() {
+            return Err(anyhow::anyhow!("目录不存在: {}", dir_path.display()));
+        }
+
+        let mut all_records = Vec::new();
+        for entry in WalkDir::new(dir_path).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if matches!(
+                path.extension().and_then(|s| s.to_str()),
+                Some("lc1") | Some("lc5")
+            ) {
+                match self.parse_file(path) {
+                    Ok(mut records) => {
+                        info!("解析文件成功: {}, {}条记录", path.display(), records.len());
+                        all_records.append(&mut records);
+                    }
+                    Err(e) => warn!("解析文件失败 {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        all_records.sort_by(|a, b| {
+            a.datetime
+                .cmp(&b.datetime)
+                .then(a.symbol.cmp(&b.symbol))
+                .then(a.market.cmp(&b.market))
+        });
+        Ok(all_records)
+    }
+}
+
+/// 解码单条记录
+fn decode_record(chunk: &[u8], symbol: &str, market: &str) -> Result<TDXMinuteRecord> {
+    let u16_at = |offset: usize| u16::from_le_bytes([chunk[offset], chunk[offset + 1]]);
+    let f32_at = |offset: usize| {
+        f32::from_le_bytes([
+            chunk[offset],
+            chunk[offset + 1],
+            chunk[offset + 2],
+            chunk[offset + 3],
+        ]) as f64
+    };
+
+    let packed_date = u16_at(0) as i32;
+    let minutes = u16_at(2) as u32;
+    let year = packed_date / 2048 + 2004;
+    let month = ((packed_date % 2048) / 100) as u32;
+    let day = ((packed_date % 2048) % 100) as u32;
+
+    let datetime = NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.and_hms_opt(minutes / 60, minutes % 60, 0))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "无效的分钟线时间: {}-{}-{} 第{}分钟",
+                year,
+                month,
+                day,
+                minutes
+            )
+        })?;
+
+    // 价格保留两位小数，消除f32精度误差
+    let price = |offset: usize| (f32_at(offset) * 100.0).round() / 100.0;
+
+    Ok(TDXMinuteRecord {
+        datetime,
+        symbol: symbol.to_string(),
+        open: price(4),
+        high: price(8),
+        low: price(12),
+        close: price(16),
+        amount: f32_at(20),
+        volume: u32::from_le_bytes([chunk[24], chunk[25], chunk[26], chunk[27]]) as u64,
+        market: market.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 构造一条分钟线二进制记录
+    fn record_bytes(year: i32, month: u32, day: u32, minutes: u16, close: f32) -> Vec<u8> {
+        let packed = ((year - 2004) * 2048 + (month * 100 + day) as i32) as u16;
+        let mut bytes = Vec::with_capacity(MINUTE_RECORD_SIZE);
+        bytes.extend_from_slice(&packed.to_le_bytes());
+        bytes.extend_from_slice(&minutes.to_le_bytes());
+        for value in [close, close, close, close, close * 100.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&100u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_decode_minute_record() {
+        let parser = TDXMinuteParser::new(".");
+        // 9:31 = 571分钟
+        let bytes = record_bytes(2024, 1, 2, 571, 10.12);
+        let records = parser.parse_binary_data(&bytes, "600000", "SH").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(
+            records[0].datetime,
+            NaiveDate::from_ymd_opt(2024, 1, 2)
+                .unwrap()
+                .and_hms_opt(9, 31, 0)
+                .unwrap()
+        );
+        assert_eq!(records[0].close, 10.12);
+        assert_eq!(records[0].volume, 100);
+    }
+
+    #[test]
+    fn test_invalid_size() {
+        let parser = TDXMinuteParser::new(".");
+        assert!(parser
+            .parse_binary_data(&[0u8; 20], "600000", "SH")
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("sz").join("minline");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut bytes = record_bytes(2024, 1, 2, 572, 12.0);
+        bytes.extend(record_bytes(2024, 1, 2, 571, 11.9));
+        std::fs::write(dir.join("000001.lc1"), bytes).unwrap();
+
+        let records = TDXMinuteParser::new(temp_dir.path())
+            .parse_directory(temp_dir.path())
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].market, "SZ");
+        assert_eq!(records[0].close, 11.9);
+    }
+}
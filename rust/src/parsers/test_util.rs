@@ -0,0 +1,132 @@
+//! 二进制日线的属性测试工具（`test-util` 特性）
+//!
+//! 提供生成合法 `BinaryDayRecord` 的proptest策略、`.day` 文件编码和往返校验，
+//! 下游crate可以用完全相同的通达信布局对自己的解析或导入流程做属性测试和模糊测试。
+//!
+//! 生成的价格满足A股股票的默认校验（0.01~10000元，最低价 <= 开收盘价 <= 最高价），
+//! 同一文件中的日期严格递增。
+
+use super::tdx_day::{BinaryDayRecord, TDXDayParser};
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate};
+use proptest::collection::btree_set;
+use proptest::prelude::*;
+
+/// 生成日期的起点
+fn base_date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1990, 12, 19).unwrap()
+}
+
+/// 生成日期的天数范围（约到2099年）
+const DATE_SPAN_DAYS: u32 = 40_000;
+
+/// 最高价上限（分），对应默认校验的10000元
+const MAX_PRICE_CENTS: u32 = 1_000_000;
+
+/// 日期编码为YYYYMMDD
+fn encode_date(date: NaiveDate) -> u32 {
+    date.year() as u32 * 10_000 + date.month() * 100 + date.day()
+}
+
+/// 某一日期的合法记录
+fn record_on(date: NaiveDate) -> impl Strategy<Value = BinaryDayRecord> {
+    (1..=MAX_PRICE_CENTS, 1..=MAX_PRICE_CENTS)
+        .prop_flat_map(|(a, b)| {
+            let (low, high) = (a.min(b), a.max(b));
+            (
+                Just(low),
+                Just(high),
+                low..=high,
+                low..=high,
+                any::<u32>(),
+                0.0f32..1.0e12,
+            )
+        })
+        .prop_map(
+            move |(low, high, open, close, volume, amount)| BinaryDayRecord {
+                date: encode_date(date),
+                open,
+                high,
+                low,
+                close,
+                amount,
+                volume,
+                reserved: 0,
+            },
+        )
+}
+
+/// 单条合法记录
+pub fn binary_day_record() -> impl Strategy<Value = BinaryDayRecord> {
+    (0..DATE_SPAN_DAYS)
+        .prop_flat_map(|offset| record_on(base_date() + Duration::days(offset as i64)))
+}
+
+/// 一个 `.day` 文件的记录，日期严格递增，条数在 `0..max_len` 之间
+pub fn binary_day_file(max_len: usize) -> impl Strategy<Value = Vec<BinaryDayRecord>> {
+    btree_set(0..DATE_SPAN_DAYS, 0..max_len.max(1)).prop_flat_map(|offsets| {
+        offsets
+            .into_iter()
+            .map(|offset| record_on(base_date() + Duration::days(offset as i64)))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// 编码为 `.day` 文件内容
+pub fn encode_day_file(records: &[BinaryDayRecord]) -> Vec<u8> {
+    records
+        .iter()
+        .flat_map(|record| record.to_bytes())
+        .collect()
+}
+
+/// 往返校验：编码后用 `TDXDayParser` 解析，再编码回二进制记录，要求与输入完全一致
+pub fn check_round_trip(records: &[BinaryDayRecord], symbol: &str, market: &str) -> Result<()> {
+    let parsed =
+        TDXDayParser::new(".").parse_binary_data(&encode_day_file(records), symbol, market)?;
+    if parsed.len() != records.len() {
+        return Err(anyhow::anyhow!(
+            "解析出{}条记录，期望{}条",
+            parsed.len(),
+            records.len()
+        ));
+    }
+    for (index, (record, original)) in parsed.iter().zip(records).enumerate() {
+        if record.symbol != symbol || record.market != market {
+            return Err(anyhow::anyhow!(
+                "第{}条记录的代码或市场不一致: {} {}",
+                index,
+                record.symbol,
+                record.market
+            ));
+        }
+        let encoded = BinaryDayRecord::from_record(record)?;
+        if encoded != *original {
+            return Err(anyhow::anyhow!(
+                "第{}条记录往返后不一致: {:?} != {:?}",
+                index,
+                encoded,
+                original
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_day_file_round_trip(records in binary_day_file(64)) {
+            let result = check_round_trip(&records, "600000", "SH");
+            prop_assert!(result.is_ok(), "{:?}", result);
+        }
+
+        #[test]
+        fn test_single_record_bytes(record in binary_day_record()) {
+            prop_assert_eq!(BinaryDayRecord::from_bytes(&record.to_bytes()), record);
+        }
+    }
+}
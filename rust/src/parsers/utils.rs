@@ -1,11 +1,15 @@
 //! 解析器工具模块
 
+use super::validation::{AssetClass, ValidationProfile};
 use anyhow::{Context, Result};
-use flate2::read::GzDecoder;
+#[cfg(feature = "archive")]
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use std::fs::{self, File};
-use std::io::{BufReader, Read};
-use std::path::{Path, PathBuf};
-use zip::ZipArchive;
+use std::path::Path;
+#[cfg(feature = "archive")]
+use zip::write::FileOptions;
+#[cfg(feature = "archive")]
+use zip::{ZipArchive, ZipWriter};
 
 /// 文件处理工具
 pub struct FileUtils;
@@ -95,9 +99,11 @@ impl FileUtils {
     }
 }
 
-/// 压缩文件处理工具
+/// 压缩文件处理工具（`archive` 特性）
+#[cfg(feature = "archive")]
 pub struct CompressionUtils;
 
+#[cfg(feature = "archive")]
 impl CompressionUtils {
     /// 解压gzip文件
     pub fn extract_gzip<P: AsRef<Path>, Q: AsRef<Path>>(
@@ -107,17 +113,17 @@ impl CompressionUtils {
         let gzip_file = File::open(gzip_path.as_ref())
             .with_context(|| format!("无法打开gzip文件: {}", gzip_path.as_ref().display()))?;
 
-        let decoder = GzDecoder::new(gzip_file);
+        let mut decoder = GzDecoder::new(gzip_file);
         let mut output_file = File::create(output_path.as_ref())
             .with_context(|| format!("无法创建输出文件: {}", output_path.as_ref().display()))?;
 
-        // std::io::copy(decoder, &mut output_file).with_context(|| "解压gzip文件失败")?;
+        std::io::copy(&mut decoder, &mut output_file).with_context(|| "解压gzip文件失败")?;
 
         Ok(())
     }
 
     /// 解压zip文件到指定目录
-    pub fn extract_zip<P: AsRef<Path>>(zip_path: P, extract_dir: P) -> Result<()> {
+    pub fn extract_zip<P: AsRef<Path>, Q: AsRef<Path>>(zip_path: P, extract_dir: Q) -> Result<()> {
         let zip_file = File::open(zip_path.as_ref())
             .with_context(|| format!("无法打开zip文件: {}", zip_path.as_ref().display()))?;
 
@@ -128,7 +134,12 @@ impl CompressionUtils {
                 .by_index(i)
                 .with_context(|| format!("无法获取zip文件索引: {}", i))?;
 
-            let output_path = extract_dir.as_ref().join(file.name());
+            // 拒绝包含 `..` 或绝对路径的条目，避免写出目标目录
+            let name = file
+                .enclosed_name()
+                .map(Path::to_path_buf)
+                .ok_or_else(|| anyhow::anyhow!("zip条目路径不安全: {}", file.name()))?;
+            let output_path = extract_dir.as_ref().join(name);
 
             if file.name().ends_with('/') {
                 // 创建目录
@@ -151,6 +162,41 @@ impl CompressionUtils {
         Ok(())
     }
 
+    /// 解压tar.gz文件到指定目录
+    pub fn extract_tar_gz<P: AsRef<Path>, Q: AsRef<Path>>(
+        tar_gz_path: P,
+        extract_dir: Q,
+    ) -> Result<()> {
+        let file = File::open(tar_gz_path.as_ref())
+            .with_context(|| format!("无法打开tar.gz文件: {}", tar_gz_path.as_ref().display()))?;
+
+        FileUtils::ensure_dir_exists(extract_dir.as_ref())?;
+        // unpack 会跳过包含 `..` 的条目
+        tar::Archive::new(GzDecoder::new(file))
+            .unpack(extract_dir.as_ref())
+            .with_context(|| "解压tar.gz文件失败")?;
+
+        Ok(())
+    }
+
+    /// 压缩目录为tar.gz文件
+    pub fn compress_to_tar_gz<P: AsRef<Path>, Q: AsRef<Path>>(
+        source_dir: P,
+        tar_gz_path: Q,
+    ) -> Result<()> {
+        let file = File::create(tar_gz_path.as_ref())
+            .with_context(|| format!("无法创建tar.gz文件: {}", tar_gz_path.as_ref().display()))?;
+
+        let encoder = GzEncoder::new(file, Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder
+            .append_dir_all(".", source_dir.as_ref())
+            .with_context(|| format!("无法打包目录: {}", source_dir.as_ref().display()))?;
+        builder.into_inner()?.finish()?;
+
+        Ok(())
+    }
+
     /// 压缩目录为zip文件
     pub fn compress_to_zip<P: AsRef<Path>, Q: AsRef<Path>>(
         source_dir: P,
@@ -160,29 +206,29 @@ impl CompressionUtils {
         let zip_file = File::create(zip_path.as_ref())
             .with_context(|| format!("无法创建zip文件: {}", zip_path.as_ref().display()))?;
 
-        // let mut zip = ZipWriter::new(zip_file);
-        // let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
-
-        // // 添加目录中的所有文件
-        // for entry in walkdir::WalkDir::new(source_path)
-        //     .into_iter()
-        //     .filter_map(|e| e.ok())
-        // {
-        //     let path = entry.path();
-        //     let name = path
-        //         .strip_prefix(source_path)
-        //         .with_context(|| "路径前缀处理失败")?;
-
-        //     if path.is_file() {
-        //         zip.start_file(name.to_string_lossy(), options)?;
-        //         let mut file = File::open(path)?;
-        //         std::io::copy(&mut file, &mut zip)?;
-        //     } else if path != source_path {
-        //         zip.add_directory(name.to_string_lossy(), options)?;
-        //     }
-        // }
-
-        // zip.finish()?;
+        let mut zip = ZipWriter::new(zip_file);
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+        // 添加目录中的所有文件
+        for entry in walkdir::WalkDir::new(source_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            let name = path
+                .strip_prefix(source_path)
+                .with_context(|| "路径前缀处理失败")?;
+
+            if path.is_file() {
+                zip.start_file(name.to_string_lossy(), options)?;
+                let mut file = File::open(path)?;
+                std::io::copy(&mut file, &mut zip)?;
+            } else if path != source_path {
+                zip.add_directory(name.to_string_lossy(), options)?;
+            }
+        }
+
+        zip.finish()?;
         Ok(())
     }
 }
@@ -214,7 +260,8 @@ impl ValidationUtils {
 
     /// 验证日期格式
     pub fn validate_date(date_str: &str) -> Result<chrono::NaiveDate> {
-        if date_str.len() != 8 {
+        // 先确认全为ASCII数字，再按字节切片，多字节字符不会落在切片边界上
+        if date_str.len() != 8 || !date_str.bytes().all(|b| b.is_ascii_digit()) {
             return Err(anyhow::anyhow!("日期格式错误，期望YYYYMMDD: {}", date_str));
         }
 
@@ -232,29 +279,24 @@ impl ValidationUtils {
             .ok_or_else(|| anyhow::anyhow!("无效的日期: {}", date_str))
     }
 
-    /// 验证价格数据
+    /// 验证价格数据（按A股默认边界）
     pub fn validate_price_data(open: f64, high: f64, low: f64, close: f64) -> Result<()> {
-        // 检查价格为正数
-        if open <= 0.0 || high <= 0.0 || low <= 0.0 || close <= 0.0 {
-            return Err(anyhow::anyhow!("价格必须为正数"));
-        }
-
-        // 检查高低价关系
-        if high < low {
-            return Err(anyhow::anyhow!("最高价不能低于最低价"));
-        }
-
-        // 检查开收盘价在高低价范围内
-        if open > high || open < low || close > high || close < low {
-            return Err(anyhow::anyhow!("开收盘价超出高低价范围"));
-        }
-
-        // 检查价格合理性
-        if open < 0.01 || high > 10000.0 || low < 0.01 || close > 10000.0 {
-            return Err(anyhow::anyhow!("价格超出合理范围"));
-        }
+        ValidationProfile::default()
+            .bounds_for("SH", AssetClass::Stock)
+            .check_prices(open, high, low, close)
+    }
 
-        Ok(())
+    /// 按校验配置验证某只股票的价格数据
+    pub fn validate_price_data_with(
+        profile: &ValidationProfile,
+        symbol: &str,
+        market: &str,
+        open: f64,
+        high: f64,
+        low: f64,
+        close: f64,
+    ) -> Result<()> {
+        profile.validate_prices(symbol, market, open, high, low, close)
     }
 
     /// 验证成交量数据
@@ -319,6 +361,8 @@ mod tests {
         assert!(ValidationUtils::validate_date("2024011").is_err()); // 长度错误
         assert!(ValidationUtils::validate_date("20241301").is_err()); // 月份无效
         assert!(ValidationUtils::validate_date("20240132").is_err()); // 日期无效
+        assert!(ValidationUtils::validate_date("2024年1").is_err()); // 非ASCII字符
+        assert!(ValidationUtils::validate_date("+2024011").is_err()); // 符号
     }
 
     #[test]
@@ -331,6 +375,14 @@ mod tests {
         assert!(ValidationUtils::validate_price_data(10.0, 8.0, 12.0, 11.0).is_err()); // 高低价关系错误
         assert!(ValidationUtils::validate_price_data(13.0, 12.0, 8.0, 11.0).is_err());
         // 开盘价超出范围
+
+        // 指数点位超出个股上限，按配置校验
+        let profile = ValidationProfile::default();
+        assert!(ValidationUtils::validate_price_data(3000.0, 10_500.0, 2990.0, 3020.0).is_err());
+        assert!(ValidationUtils::validate_price_data_with(
+            &profile, "000001", "SH", 3000.0, 10_500.0, 2990.0, 3020.0
+        )
+        .is_ok());
     }
 
     #[test]
@@ -343,4 +395,25 @@ mod tests {
         assert!(test_dir.exists());
         assert!(test_dir.is_dir());
     }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_archive_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        FileUtils::ensure_dir_exists(source.join("sh")).unwrap();
+        fs::write(source.join("sh").join("600000.day"), b"data").unwrap();
+
+        let zip_path = temp_dir.path().join("backup.zip");
+        CompressionUtils::compress_to_zip(&source, &zip_path).unwrap();
+        CompressionUtils::extract_zip(&zip_path, temp_dir.path().join("zip")).unwrap();
+        let extracted = temp_dir.path().join("zip").join("sh").join("600000.day");
+        assert_eq!(fs::read(extracted).unwrap(), b"data");
+
+        let tar_path = temp_dir.path().join("backup.tar.gz");
+        CompressionUtils::compress_to_tar_gz(&source, &tar_path).unwrap();
+        CompressionUtils::extract_tar_gz(&tar_path, temp_dir.path().join("tar")).unwrap();
+        let extracted = temp_dir.path().join("tar").join("sh").join("600000.day");
+        assert_eq!(fs::read(extracted).unwrap(), b"data");
+    }
 }
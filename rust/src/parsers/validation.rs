@@ -0,0 +1,359 @@
+//! 价格校验配置模块
+//!
+//! 不同市场、品种的合理价格区间差别很大：A股以元计价，B股以美元/港元计价，指数是点位。
+//! `ValidationProfile` 按（市场, 品种）配置价格上下限和单日最大涨跌幅，
+//! 注入解析器（`TDXDayParser::with_validation_profile`）和 `ValidationUtils`，替代写死的常量。
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::tdx_day::TDXDayRecord;
+
+/// 品种
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AssetClass {
+    /// A股
+    Stock,
+    /// B股（上海以美元、深圳以港元计价）
+    BShare,
+    /// 指数（点位）
+    Index,
+    /// 基金
+    Fund,
+    /// 其他（债券、无法识别的代码等）
+    Other,
+}
+
+impl AssetClass {
+    /// 根据股票代码和市场识别品种
+    pub fn classify(symbol: &str, market: &str) -> Self {
+        let prefix = symbol.get(0..3).unwrap_or("");
+        match market.to_uppercase().as_str() {
+            "SH" => match prefix {
+                "600" | "601" | "603" | "605" | "688" | "689" => AssetClass::Stock,
+                "900" => AssetClass::BShare,
+                "000" => AssetClass::Index,
+                "500" | "501" | "502" | "505" | "506" | "508" | "510" | "511" | "512" | "513"
+                | "515" | "516" | "517" | "518" | "560" | "561" | "562" | "563" | "588" => {
+                    AssetClass::Fund
+                }
+                _ => AssetClass::Other,
+            },
+            "SZ" => match prefix {
+                "000" | "001" | "002" | "003" | "300" | "301" => AssetClass::Stock,
+                "200" | "201" => AssetClass::BShare,
+                "399" => AssetClass::Index,
+                "150" | "159" | "160" | "161" | "162" | "163" | "164" | "165" | "166" | "167"
+                | "168" | "169" | "184" => AssetClass::Fund,
+                _ => AssetClass::Other,
+            },
+            "BJ" => match prefix {
+                "899" => AssetClass::Index,
+                _ => AssetClass::Stock,
+            },
+            _ => AssetClass::Other,
+        }
+    }
+}
+
+/// 价格校验边界
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceBounds {
+    /// 最低价格
+    pub min_price: f64,
+    /// 最高价格
+    pub max_price: f64,
+    /// 相对前收盘价的单日最大涨跌幅（%），None表示不检查
+    #[serde(default)]
+    pub max_daily_move_percent: Option<f64>,
+}
+
+impl PriceBounds {
+    /// 创建价格区间，不检查单日涨跌幅
+    pub fn new(min_price: f64, max_price: f64) -> Self {
+        Self {
+            min_price,
+            max_price,
+            max_daily_move_percent: None,
+        }
+    }
+
+    /// 设置单日最大涨跌幅（%）
+    pub fn with_max_daily_move_percent(mut self, percent: f64) -> Self {
+        self.max_daily_move_percent = Some(percent);
+        self
+    }
+
+    /// 校验单根K线的价格：为正、高低价关系正确、在价格区间内
+    pub fn check_prices(&self, open: f64, high: f64, low: f64, close: f64) -> Result<()> {
+        if open <= 0.0 || high <= 0.0 || low <= 0.0 || close <= 0.0 {
+            return Err(anyhow::anyhow!("价格必须为正数"));
+        }
+
+        if high < low {
+            return Err(anyhow::anyhow!("最高价不能低于最低价"));
+        }
+
+        if open > high || open < low || close > high || close < low {
+            return Err(anyhow::anyhow!("开收盘价超出高低价范围"));
+        }
+
+        if low < self.min_price || high > self.max_price {
+            return Err(anyhow::anyhow!(
+                "价格超出合理范围[{}, {}]: 最低{}，最高{}",
+                self.min_price,
+                self.max_price,
+                low,
+                high
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 校验相对前收盘价的涨跌幅
+    pub fn check_move(&self, prev_close: f64, close: f64) -> Result<()> {
+        let Some(limit) = self.max_daily_move_percent else {
+            return Ok(());
+        };
+        if prev_close <= 0.0 {
+            return Ok(());
+        }
+
+        let change = (close / prev_close - 1.0) * 100.0;
+        if change.abs() > limit {
+            return Err(anyhow::anyhow!(
+                "单日涨跌幅{:.2}%超过上限{}%",
+                change,
+                limit
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 一条配置规则，市场或品种为None时匹配任意值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileRule {
+    /// 市场
+    #[serde(default)]
+    pub market: Option<String>,
+    /// 品种
+    #[serde(default)]
+    pub asset_class: Option<AssetClass>,
+    /// 校验边界
+    pub bounds: PriceBounds,
+}
+
+impl ProfileRule {
+    /// 是否匹配，匹配时返回具体程度（同时指定市场和品种最具体）
+    fn specificity(&self, market: &str, asset_class: AssetClass) -> Option<u8> {
+        let market_match = match &self.market {
+            Some(m) if m.eq_ignore_ascii_case(market) => Some(2),
+            Some(_) => None,
+            None => Some(0),
+        }?;
+        let class_match = match self.asset_class {
+            Some(class) if class == asset_class => Some(1),
+            Some(_) => None,
+            None => Some(0),
+        }?;
+        Some(market_match + class_match)
+    }
+}
+
+/// 价格校验配置
+///
+/// 查找边界时取最具体的匹配规则（市场+品种 > 市场 > 品种），同样具体时后添加的优先，
+/// 都不匹配时使用 `fallback`。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationProfile {
+    /// 没有规则匹配时的边界
+    pub fallback: PriceBounds,
+    /// 配置规则
+    #[serde(default)]
+    pub rules: Vec<ProfileRule>,
+}
+
+impl Default for ValidationProfile {
+    fn default() -> Self {
+        Self::a_share()
+    }
+}
+
+impl ValidationProfile {
+    /// 创建只有兜底边界的配置
+    pub fn new(fallback: PriceBounds) -> Self {
+        Self {
+            fallback,
+            rules: Vec::new(),
+        }
+    }
+
+    /// 沪深京默认配置：A股0.01-10000元，B股0.001-1000美元/港元，指数0.01-100000点，
+    /// 基金0.001-1000元，其他0.01-100000；均不检查单日涨跌幅
+    pub fn a_share() -> Self {
+        Self::new(PriceBounds::new(0.01, 100_000.0))
+            .with_class(AssetClass::Stock, PriceBounds::new(0.01, 10_000.0))
+            .with_class(AssetClass::BShare, PriceBounds::new(0.001, 1_000.0))
+            .with_class(AssetClass::Index, PriceBounds::new(0.01, 100_000.0))
+            .with_class(AssetClass::Fund, PriceBounds::new(0.001, 1_000.0))
+    }
+
+    /// 设置某一品种的边界（任意市场）
+    pub fn with_class(self, asset_class: AssetClass, bounds: PriceBounds) -> Self {
+        self.with_rule(None, Some(asset_class), bounds)
+    }
+
+    /// 设置某一市场的边界（任意品种）
+    pub fn with_market(self, market: &str, bounds: PriceBounds) -> Self {
+        self.with_rule(Some(market), None, bounds)
+    }
+
+    /// 设置某一市场某一品种的边界
+    pub fn with_market_class(
+        self,
+        market: &str,
+        asset_class: AssetClass,
+        bounds: PriceBounds,
+    ) -> Self {
+        self.with_rule(Some(market), Some(asset_class), bounds)
+    }
+
+    fn with_rule(
+        mut self,
+        market: Option<&str>,
+        asset_class: Option<AssetClass>,
+        bounds: PriceBounds,
+    ) -> Self {
+        self.rules.push(ProfileRule {
+            market: market.map(str::to_uppercase),
+            asset_class,
+            bounds,
+        });
+        self
+    }
+
+    /// 股票适用的边界
+    pub fn bounds(&self, symbol: &str, market: &str) -> &PriceBounds {
+        self.bounds_for(market, AssetClass::classify(symbol, market))
+    }
+
+    /// 某一市场某一品种适用的边界
+    pub fn bounds_for(&self, market: &str, asset_class: AssetClass) -> &PriceBounds {
+        let mut best: Option<(u8, &PriceBounds)> = None;
+        for rule in &self.rules {
+            if let Some(score) = rule.specificity(market, asset_class) {
+                if best.is_none_or(|(best_score, _)| score >= best_score) {
+                    best = Some((score, &rule.bounds));
+                }
+            }
+        }
+        best.map_or(&self.fallback, |(_, bounds)| bounds)
+    }
+
+    /// 校验单根K线的价格
+    pub fn validate_prices(
+        &self,
+        symbol: &str,
+        market: &str,
+        open: f64,
+        high: f64,
+        low: f64,
+        close: f64,
+    ) -> Result<()> {
+        self.bounds(symbol, market)
+            .check_prices(open, high, low, close)
+    }
+
+    /// 校验同一只股票按日期排序的记录的单日涨跌幅
+    pub fn validate_moves(&self, records: &[TDXDayRecord]) -> Result<()> {
+        let Some(first) = records.first() else {
+            return Ok(());
+        };
+        let bounds = self.bounds(&first.symbol, &first.market);
+        if bounds.max_daily_move_percent.is_none() {
+            return Ok(());
+        }
+        for pair in records.windows(2) {
+            bounds
+                .check_move(pair[0].close, pair[1].close)
+                .map_err(|e| anyhow::anyhow!("{} {}: {}", pair[1].symbol, pair[1].date, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_class_and_bounds() {
+        assert_eq!(AssetClass::classify("600519", "SH"), AssetClass::Stock);
+        assert_eq!(AssetClass::classify("900901", "SH"), AssetClass::BShare);
+        assert_eq!(AssetClass::classify("000001", "SH"), AssetClass::Index);
+        assert_eq!(AssetClass::classify("000001", "SZ"), AssetClass::Stock);
+        assert_eq!(AssetClass::classify("399001", "sz"), AssetClass::Index);
+        assert_eq!(AssetClass::classify("510300", "SH"), AssetClass::Fund);
+
+        let profile = ValidationProfile::default();
+        // 茅台高价正常，上证指数点位不受个股上限约束
+        assert!(profile
+            .validate_prices("600519", "SH", 1800.0, 1850.0, 1790.0, 1820.0)
+            .is_ok());
+        assert!(profile
+            .validate_prices("000001", "SH", 3000.0, 3050.0, 2990.0, 3020.0)
+            .is_ok());
+        assert!(profile
+            .validate_prices("600000", "SH", 12000.0, 12000.0, 12000.0, 12000.0)
+            .is_err());
+        // B股价格低于1分
+        assert!(profile
+            .validate_prices("900901", "SH", 0.005, 0.005, 0.005, 0.005)
+            .is_ok());
+        assert!(profile
+            .validate_prices("600000", "SH", 10.0, 9.0, 9.5, 9.5)
+            .is_err());
+    }
+
+    #[test]
+    fn test_rule_specificity_and_moves() {
+        let profile = ValidationProfile::default()
+            .with_market("SZ", PriceBounds::new(0.01, 500.0))
+            .with_market_class(
+                "sz",
+                AssetClass::Stock,
+                PriceBounds::new(0.01, 1_000.0).with_max_daily_move_percent(21.0),
+            );
+        assert_eq!(profile.bounds("300750", "SZ").max_price, 1_000.0);
+        assert_eq!(profile.bounds("159915", "SZ").max_price, 500.0);
+        assert_eq!(profile.bounds("600000", "SH").max_price, 10_000.0);
+        assert_eq!(profile.bounds("123456", "HK").max_price, 100_000.0);
+
+        let day = |d: u32, close: f64| TDXDayRecord {
+            date: chrono::NaiveDate::from_ymd_opt(2024, 1, d).unwrap(),
+            symbol: "300750".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SZ".to_string(),
+        };
+        assert!(profile
+            .validate_moves(&[day(2, 10.0), day(3, 12.0)])
+            .is_ok());
+        let error = profile
+            .validate_moves(&[day(2, 10.0), day(3, 12.0), day(4, 15.0)])
+            .unwrap_err();
+        assert!(error.to_string().contains("2024-01-04"));
+        // 未配置涨跌幅上限的品种不检查
+        let mut index = vec![day(2, 10.0), day(3, 20.0)];
+        index
+            .iter_mut()
+            .for_each(|r| r.symbol = "399001".to_string());
+        assert!(profile.validate_moves(&index).is_ok());
+    }
+}
@@ -0,0 +1,627 @@
+//! 数据处理流水线
+//!
+//! 把通达信数据目录按批次依次完成“解析 → 清洗（可选）→ 指标计算 → 写入目标”，每批写入成功后
+//! 把已完成的文件和写入偏移持久化到检查点文件。全市场长时间运行中途崩溃时，
+//! `Pipeline::resume` 从最后一个成功批次之后继续，`Pipeline::run` 则忽略检查点完整重跑。
+//!
+//! 检查点在目标写入并刷新之后才更新，因此中断时最后一批可能被重复写入（至少一次语义）。
+//! 设置 `with_shutdown` 后，收到停止信号时完成当前批次的写入、刷新和检查点后返回，
+//! 报告中 `interrupted` 为真。
+//!
+//! 分红、拆股等公司行为生效后，`recompute::DependencyTracker` 推导出失效的股票和日期范围，
+//! `Pipeline::recompute` 只重算这些股票并写入失效范围内的行。
+//!
+//! `Pipeline::plan` 在执行前输出阶段依赖、输入规模和写入目标，不读写任何数据。
+
+pub mod plan;
+pub mod recompute;
+
+pub use plan::{PipelinePlan, PlanStage};
+
+pub use recompute::{
+    Artifact, CorporateAction, CorporateActionKind, DependencyTracker, PriceAdjustment,
+    RecomputePlan, RecomputeTask,
+};
+
+use crate::parsers::TDXDayParser;
+use crate::processors::calculator::{EnhancedDayRecord, IndicatorCalculator};
+use crate::processors::cleaner::DataCleaner;
+use crate::shutdown::Shutdown;
+use crate::source::DateRange;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// 流水线写入目标
+pub trait PipelineSink {
+    /// 写入一批记录
+    fn write(&mut self, batch: &[EnhancedDayRecord]) -> Result<()>;
+
+    /// 刷新缓冲，返回后已写入的数据应可持久读取
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 写入目标说明，用于执行计划展示，缺省为类型名
+    fn describe(&self) -> String {
+        std::any::type_name::<Self>().to_string()
+    }
+}
+
+impl<F> PipelineSink for F
+where
+    F: FnMut(&[EnhancedDayRecord]) -> Result<()>,
+{
+    fn write(&mut self, batch: &[EnhancedDayRecord]) -> Result<()> {
+        self(batch)
+    }
+}
+
+/// 流水线检查点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// 数据目录
+    pub source_dir: PathBuf,
+    /// 首次开始时间
+    pub started_at: DateTime<Utc>,
+    /// 最近更新时间
+    pub updated_at: DateTime<Utc>,
+    /// 已完成的文件（相对数据目录的路径）
+    pub completed: BTreeSet<String>,
+    /// 已写入的批次数
+    pub batches_written: usize,
+    /// 已写入的记录数
+    pub records_written: usize,
+}
+
+impl Checkpoint {
+    /// 创建空检查点
+    pub fn new<P: AsRef<Path>>(source_dir: P) -> Self {
+        let now = Utc::now();
+        Self {
+            source_dir: source_dir.as_ref().to_path_buf(),
+            started_at: now,
+            updated_at: now,
+            completed: BTreeSet::new(),
+            batches_written: 0,
+            records_written: 0,
+        }
+    }
+
+    /// 读取检查点，文件不存在时返回None
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("无法读取检查点: {}", path.display()))?;
+        let checkpoint = serde_json::from_str(&content)
+            .with_context(|| format!("检查点格式错误: {}", path.display()))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// 保存检查点（先写临时文件再改名，不会留下半截文件）
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("无法创建检查点目录: {}", parent.display()))?;
+        }
+        let staging = path.with_extension("tmp");
+        fs::write(&staging, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("无法写入检查点: {}", staging.display()))?;
+        fs::rename(&staging, path)
+            .with_context(|| format!("无法写入检查点: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// 流水线运行报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PipelineReport {
+    /// 数据目录中的文件总数
+    pub files_total: usize,
+    /// 因检查点跳过的文件数
+    pub files_skipped: usize,
+    /// 本次处理成功的文件数
+    pub files_processed: usize,
+    /// 处理失败的文件及原因（不计入检查点，下次恢复时重试）
+    pub files_failed: Vec<(String, String)>,
+    /// 本次写入的批次数
+    pub batches: usize,
+    /// 本次写入的记录数
+    pub records_written: usize,
+    /// 是否因停止信号提前结束
+    #[serde(default)]
+    pub interrupted: bool,
+}
+
+/// 数据处理流水线
+#[derive(Debug)]
+pub struct Pipeline {
+    /// 解析器
+    parser: TDXDayParser,
+    /// 数据目录
+    source_dir: PathBuf,
+    /// 数据清洗器
+    cleaner: Option<DataCleaner>,
+    /// 指标计算器
+    calculator: IndicatorCalculator,
+    /// 每批文件数
+    batch_size: usize,
+    /// 检查点文件
+    checkpoint_path: Option<PathBuf>,
+    /// 停止信号
+    shutdown: Option<Shutdown>,
+}
+
+impl Pipeline {
+    /// 创建处理指定数据目录的流水线
+    pub fn new<P: AsRef<Path>>(source_dir: P) -> Self {
+        let source_dir = source_dir.as_ref().to_path_buf();
+        Self {
+            parser: TDXDayParser::new(&source_dir),
+            source_dir,
+            cleaner: None,
+            calculator: IndicatorCalculator::new(),
+            batch_size: 100,
+            checkpoint_path: None,
+            shutdown: None,
+        }
+    }
+
+    /// 设置数据清洗器，解析后先清洗再计算指标
+    pub fn with_cleaner(mut self, cleaner: DataCleaner) -> Self {
+        self.cleaner = Some(cleaner);
+        self
+    }
+
+    /// 设置指标计算器
+    pub fn with_calculator(mut self, calculator: IndicatorCalculator) -> Self {
+        self.calculator = calculator;
+        self
+    }
+
+    /// 设置每批文件数
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// 设置检查点文件，每批写入后保存进度
+    pub fn with_checkpoint<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.checkpoint_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// 完整运行（忽略并覆盖已有检查点）
+    pub fn run<S: PipelineSink>(&self, sink: &mut S) -> Result<PipelineReport> {
+        self.execute(Checkpoint::new(&self.source_dir), sink)
+    }
+
+    /// 从检查点恢复运行，没有检查点时等同于完整运行
+    pub fn resume<S: PipelineSink>(&self, sink: &mut S) -> Result<PipelineReport> {
+        let checkpoint = match self.load_checkpoint()? {
+            Some(checkpoint) => {
+                info!(
+                    "从检查点恢复: 已完成{}个文件，{}条记录",
+                    checkpoint.completed.len(),
+                    checkpoint.records_written
+                );
+                checkpoint
+            }
+            None => Checkpoint::new(&self.source_dir),
+        };
+        self.execute(checkpoint, sink)
+    }
+
+    /// 设置停止信号，收到后不再开始新批次
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// 按重算计划重新处理受影响的股票，只写入存储行失效范围内的记录
+    ///
+    /// 指标需要完整历史预热，因此每只股票整体重算后再按日期范围筛选；不读写检查点。
+    /// 存储行未失效的任务跳过，数据目录中找不到对应文件的股票计入 `files_failed`。
+    pub fn recompute<S: PipelineSink>(
+        &self,
+        plan: &RecomputePlan,
+        sink: &mut S,
+    ) -> Result<PipelineReport> {
+        let mut by_symbol = HashMap::new();
+        for file in self.source_files()? {
+            if let Ok(key) = self
+                .parser
+                .extract_symbol_market(&self.source_dir.join(&file))
+            {
+                by_symbol.insert(key, file);
+            }
+        }
+
+        let tasks: Vec<(&RecomputeTask, DateRange)> = plan
+            .tasks
+            .iter()
+            .filter_map(|task| Some((task, task.rows_range()?)))
+            .collect();
+        let mut report = PipelineReport {
+            files_total: tasks.len(),
+            files_skipped: plan.tasks.len() - tasks.len(),
+            ..Default::default()
+        };
+
+        for batch in tasks.chunks(self.batch_size) {
+            if self.stop_requested(&mut report) {
+                break;
+            }
+            let results: Vec<(String, Result<Vec<EnhancedDayRecord>>)> = batch
+                .par_iter()
+                .map(|(task, range)| {
+                    let key = (task.symbol.clone(), task.market.clone());
+                    let result = match by_symbol.get(&key) {
+                        Some(file) => self.process_file(file).map(|mut records| {
+                            records.retain(|r| range.contains(r.base_record.date));
+                            records
+                        }),
+                        None => Err(anyhow::anyhow!("数据目录中没有该股票的文件")),
+                    };
+                    (format!("{}.{}", task.symbol, task.market), result)
+                })
+                .collect();
+
+            let mut records = Vec::new();
+            for (name, result) in results {
+                match result {
+                    Ok(mut task_records) => {
+                        records.append(&mut task_records);
+                        report.files_processed += 1;
+                    }
+                    Err(e) => {
+                        warn!("重算失败 {}: {}", name, e);
+                        report.files_failed.push((name, e.to_string()));
+                    }
+                }
+            }
+
+            sink.write(&records)?;
+            sink.flush()?;
+            report.batches += 1;
+            report.records_written += records.len();
+        }
+
+        info!(
+            "定向重算完成: 重算{}只股票，失败{}只，写入{}条记录",
+            report.files_processed,
+            report.files_failed.len(),
+            report.records_written
+        );
+        Ok(report)
+    }
+
+    /// 读取检查点并核对数据目录
+    fn load_checkpoint(&self) -> Result<Option<Checkpoint>> {
+        let checkpoint = match &self.checkpoint_path {
+            Some(path) => Checkpoint::load(path)?,
+            None => return Ok(None),
+        };
+        match checkpoint {
+            Some(checkpoint) if checkpoint.source_dir != self.source_dir => Err(anyhow::anyhow!(
+                "检查点的数据目录与当前配置不一致: {} != {}",
+                checkpoint.source_dir.display(),
+                self.source_dir.display()
+            )),
+            checkpoint => Ok(checkpoint),
+        }
+    }
+
+    /// 数据目录下的day文件（相对路径，按字典序）
+    fn source_files(&self) -> Result<Vec<String>> {
+        if !self.source_dir.exists() {
+            return Err(anyhow::anyhow!("目录不存在: {}", self.source_dir.display()));
+        }
+
+        let mut files: Vec<String> = WalkDir::new(&self.source_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("day"))
+            .filter_map(|e| {
+                e.path()
+                    .strip_prefix(&self.source_dir)
+                    .ok()
+                    .map(|p| p.to_string_lossy().into_owned())
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// 是否已收到停止信号，收到时标记报告
+    fn stop_requested(&self, report: &mut PipelineReport) -> bool {
+        let stop = self.shutdown.as_ref().is_some_and(Shutdown::is_triggered);
+        if stop {
+            warn!("收到停止信号，已写入{}批，剩余批次留待恢复", report.batches);
+            report.interrupted = true;
+        }
+        stop
+    }
+
+    /// 处理单个文件
+    fn process_file(&self, file: &str) -> Result<Vec<EnhancedDayRecord>> {
+        let mut records = self.parser.parse_file(self.source_dir.join(file))?;
+        if let Some(cleaner) = &self.cleaner {
+            records = cleaner.clean_with_data(records)?.0;
+        }
+        self.calculator.calculate_all_indicators(&records)
+    }
+
+    fn execute<S: PipelineSink>(
+        &self,
+        mut checkpoint: Checkpoint,
+        sink: &mut S,
+    ) -> Result<PipelineReport> {
+        let files = self.source_files()?;
+        let pending: Vec<&String> = files
+            .iter()
+            .filter(|file| !checkpoint.completed.contains(*file))
+            .collect();
+        let mut report = PipelineReport {
+            files_total: files.len(),
+            files_skipped: files.len() - pending.len(),
+            ..Default::default()
+        };
+
+        for batch in pending.chunks(self.batch_size) {
+            if self.stop_requested(&mut report) {
+                break;
+            }
+            let results: Vec<(&String, Result<Vec<EnhancedDayRecord>>)> = batch
+                .par_iter()
+                .map(|&file| (file, self.process_file(file)))
+                .collect();
+
+            let mut records = Vec::new();
+            let mut completed = Vec::new();
+            for (file, result) in results {
+                match result {
+                    Ok(mut file_records) => {
+                        records.append(&mut file_records);
+                        completed.push(file.clone());
+                    }
+                    Err(e) => {
+                        warn!("处理文件失败 {}: {}", file, e);
+                        report.files_failed.push((file.clone(), e.to_string()));
+                    }
+                }
+            }
+
+            sink.write(&records)?;
+            sink.flush()?;
+
+            report.files_processed += completed.len();
+            report.batches += 1;
+            report.records_written += records.len();
+            checkpoint.completed.extend(completed);
+            checkpoint.batches_written += 1;
+            checkpoint.records_written += records.len();
+            checkpoint.updated_at = Utc::now();
+            if let Some(path) = &self.checkpoint_path {
+                checkpoint.save(path)?;
+            }
+        }
+
+        info!(
+            "流水线完成: 处理{}个文件，跳过{}个，失败{}个，写入{}条记录",
+            report.files_processed,
+            report.files_skipped,
+            report.files_failed.len(),
+            report.records_written
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::cleaner::CleaningRule;
+    use tempfile::TempDir;
+
+    /// 构造通达信day文件内容
+    fn day_file(days: u32) -> Vec<u8> {
+        let mut content = Vec::new();
+        for i in 0..days {
+            for value in [20240102 + i, 1000, 1010, 990, 1000 + i] {
+                content.extend_from_slice(&value.to_le_bytes());
+            }
+            content.extend_from_slice(&100_000.0f32.to_le_bytes());
+            content.extend_from_slice(&100u32.to_le_bytes());
+            content.extend_from_slice(&0u32.to_le_bytes());
+        }
+        content
+    }
+
+    pub(super) fn create_data_dir(symbols: usize) -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path().join("sh").join("lday");
+        fs::create_dir_all(&day_dir).unwrap();
+        for i in 0..symbols {
+            fs::write(day_dir.join(format!("{:06}.day", 600000 + i)), day_file(3)).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_run_writes_batches_and_checkpoint() {
+        let data = create_data_dir(5);
+        let checkpoint_path = data.path().join("state").join("checkpoint.json");
+        let pipeline = Pipeline::new(data.path())
+            .with_batch_size(2)
+            .with_checkpoint(&checkpoint_path);
+
+        let mut batches = Vec::new();
+        let report = pipeline
+            .run(&mut |batch: &[EnhancedDayRecord]| {
+                batches.push(batch.len());
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(batches, vec![6, 6, 3]);
+        assert_eq!((report.files_total, report.files_processed), (5, 5));
+        assert_eq!(report.records_written, 15);
+
+        let checkpoint = Checkpoint::load(&checkpoint_path).unwrap().unwrap();
+        assert_eq!(checkpoint.completed.len(), 5);
+        assert_eq!(checkpoint.batches_written, 3);
+    }
+
+    #[test]
+    fn test_cleaner_stage() {
+        let data = create_data_dir(2);
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(CleaningRule::FilterExpr {
+            expr: "close > 10.005".to_string(),
+        });
+        let pipeline = Pipeline::new(data.path()).with_cleaner(cleaner);
+
+        let mut closes = Vec::new();
+        let report = pipeline
+            .run(&mut |batch: &[EnhancedDayRecord]| {
+                closes.extend(batch.iter().map(|r| r.base_record.close));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(report.records_written, 4);
+        assert!(closes.iter().all(|&close| close > 10.005));
+    }
+
+    #[test]
+    fn test_resume_after_crash() {
+        let data = create_data_dir(5);
+        let checkpoint_path = data.path().join("checkpoint.json");
+        let pipeline = Pipeline::new(data.path())
+            .with_batch_size(2)
+            .with_checkpoint(&checkpoint_path);
+
+        // 第二批写入时崩溃
+        let mut written = 0;
+        let crashed = pipeline.run(&mut |batch: &[EnhancedDayRecord]| {
+            if written > 0 {
+                return Err(anyhow::anyhow!("写入失败"));
+            }
+            written += batch.len();
+            Ok(())
+        });
+        assert!(crashed.is_err());
+
+        let mut resumed = 0;
+        let report = pipeline
+            .resume(&mut |batch: &[EnhancedDayRecord]| {
+                resumed += batch.len();
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(report.files_skipped, 2);
+        assert_eq!(report.files_processed, 3);
+        assert_eq!(written + resumed, 15);
+
+        // 已全部完成，再次恢复不写入；完整重跑忽略检查点
+        let mut count = 0;
+        let mut sink = |batch: &[EnhancedDayRecord]| {
+            count += batch.len();
+            Ok(())
+        };
+        assert_eq!(pipeline.resume(&mut sink).unwrap().files_skipped, 5);
+        assert_eq!(pipeline.run(&mut sink).unwrap().files_processed, 5);
+        assert_eq!(count, 15);
+    }
+
+    #[test]
+    fn test_shutdown_stops_after_batch() {
+        let data = create_data_dir(5);
+        let checkpoint_path = data.path().join("checkpoint.json");
+        let shutdown = Shutdown::new();
+        let pipeline = Pipeline::new(data.path())
+            .with_batch_size(2)
+            .with_checkpoint(&checkpoint_path)
+            .with_shutdown(shutdown.clone());
+
+        // 第一批写入期间收到停止信号，该批仍完整写入并保存检查点
+        let report = pipeline
+            .run(&mut |_: &[EnhancedDayRecord]| {
+                shutdown.trigger();
+                Ok(())
+            })
+            .unwrap();
+        assert!(report.interrupted);
+        assert_eq!((report.batches, report.files_processed), (1, 2));
+        let checkpoint = Checkpoint::load(&checkpoint_path).unwrap().unwrap();
+        assert_eq!(checkpoint.completed.len(), 2);
+
+        let report = Pipeline::new(data.path())
+            .with_batch_size(2)
+            .with_checkpoint(&checkpoint_path)
+            .resume(&mut |_: &[EnhancedDayRecord]| Ok(()))
+            .unwrap();
+        assert!(!report.interrupted);
+        assert_eq!((report.files_skipped, report.files_processed), (2, 3));
+    }
+
+    #[test]
+    fn test_failed_files_retried_on_resume() {
+        let data = create_data_dir(2);
+        let bad = data.path().join("sh").join("lday").join("600009.day");
+        fs::write(&bad, b"bad").unwrap();
+        let checkpoint_path = data.path().join("checkpoint.json");
+        let pipeline = Pipeline::new(data.path()).with_checkpoint(&checkpoint_path);
+
+        let mut sink = |_: &[EnhancedDayRecord]| Ok(());
+        let report = pipeline.run(&mut sink).unwrap();
+        assert_eq!(report.files_failed.len(), 1);
+        assert_eq!(report.files_processed, 2);
+
+        fs::write(&bad, day_file(1)).unwrap();
+        let report = pipeline.resume(&mut sink).unwrap();
+        assert_eq!((report.files_skipped, report.files_processed), (2, 1));
+        assert!(report.files_failed.is_empty());
+    }
+
+    #[test]
+    fn test_recompute_after_corporate_action() {
+        let data = create_data_dir(3);
+        let pipeline = Pipeline::new(data.path());
+        let mut tracker = DependencyTracker::new().with_adjustment(PriceAdjustment::Backward);
+        for symbol in ["600001", "600009"] {
+            tracker.record(&CorporateAction {
+                symbol: symbol.to_string(),
+                market: "SH".to_string(),
+                ex_date: chrono::NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+                kind: CorporateActionKind::Split { ratio: 2.0 },
+            });
+        }
+
+        let mut written = Vec::new();
+        let report = pipeline
+            .recompute(
+                &tracker.take_plan(),
+                &mut |batch: &[EnhancedDayRecord]| {
+                    written.extend(batch.iter().map(|r| r.base_record.clone()));
+                    Ok(())
+                },
+            )
+            .unwrap();
+        // 只写入600001除权日及之后的两天，600009没有数据文件
+        assert_eq!(report.records_written, 2);
+        assert!(written.iter().all(|r| r.symbol == "600001"));
+        assert_eq!(report.files_failed.len(), 1);
+        assert_eq!(report.files_failed[0].0, "600009.SH");
+    }
+}
@@ -0,0 +1,208 @@
+//! 流水线执行计划
+//!
+//! `Pipeline::plan` 只读取文件元数据和检查点，不解析也不写入数据，列出各阶段的依赖关系、
+//! 待处理文件的大小和估算记录数、已配置的清洗规则和指标参数，供修改配置后先行核对。
+//! 记录数按每条日线32字节估算。
+
+use super::{Checkpoint, Pipeline, PipelineSink};
+use crate::parsers::tdx_day::BinaryDayRecord;
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// 计划中的阶段
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanStage {
+    pub name: String,
+    /// 依赖的上游阶段
+    pub depends_on: Vec<String>,
+    /// 配置说明，每项一行
+    pub details: Vec<String>,
+}
+
+impl PlanStage {
+    fn new(name: &str, depends_on: Option<&str>, details: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            depends_on: depends_on.into_iter().map(str::to_string).collect(),
+            details,
+        }
+    }
+}
+
+/// 流水线执行计划
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelinePlan {
+    pub source_dir: PathBuf,
+    /// 数据目录中的文件总数
+    pub files_total: usize,
+    /// 检查点中已完成的文件数（`resume` 时跳过）
+    pub files_completed: usize,
+    /// 待处理文件的总字节数
+    pub input_bytes: u64,
+    /// 待处理文件的估算记录数
+    pub estimated_records: u64,
+    pub batch_size: usize,
+    /// 待处理的批次数
+    pub batches: usize,
+    pub checkpoint: Option<PathBuf>,
+    /// 按依赖顺序排列的阶段
+    pub stages: Vec<PlanStage>,
+}
+
+impl PipelinePlan {
+    /// 登记写入目标，可多次调用
+    pub fn with_sink<S: PipelineSink + ?Sized>(mut self, sink: &S) -> Self {
+        if let Some(stage) = self.stages.iter_mut().find(|stage| stage.name == "write") {
+            stage.details.push(sink.describe());
+        }
+        self
+    }
+
+    /// 待处理的文件数
+    pub fn files_pending(&self) -> usize {
+        self.files_total - self.files_completed
+    }
+}
+
+impl fmt::Display for PipelinePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "数据目录: {}", self.source_dir.display())?;
+        writeln!(
+            f,
+            "文件: 共{}个，已完成{}个，待处理{}个（{}字节，约{}条记录）",
+            self.files_total,
+            self.files_completed,
+            self.files_pending(),
+            self.input_bytes,
+            self.estimated_records
+        )?;
+        writeln!(
+            f,
+            "批次: 每批{}个文件，共{}批",
+            self.batch_size, self.batches
+        )?;
+        for (index, stage) in self.stages.iter().enumerate() {
+            write!(f, "{}. {}", index + 1, stage.name)?;
+            if !stage.depends_on.is_empty() {
+                write!(f, " <- {}", stage.depends_on.join(", "))?;
+            }
+            writeln!(f)?;
+            if stage.details.is_empty() {
+                writeln!(f, "   （未配置）")?;
+            }
+            for detail in &stage.details {
+                writeln!(f, "   - {}", detail)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Pipeline {
+    /// 生成执行计划（按 `resume` 的语义计入检查点），不读取文件内容也不写入任何数据
+    pub fn plan(&self) -> Result<PipelinePlan> {
+        let files = self.source_files()?;
+        let checkpoint = self
+            .load_checkpoint()?
+            .unwrap_or_else(|| Checkpoint::new(&self.source_dir));
+        let pending: Vec<&String> = files
+            .iter()
+            .filter(|file| !checkpoint.completed.contains(*file))
+            .collect();
+
+        let mut input_bytes = 0;
+        for file in &pending {
+            input_bytes += fs::metadata(self.source_dir.join(file))?.len();
+        }
+
+        let mut stages = vec![PlanStage::new(
+            "parse",
+            None,
+            vec![format!("通达信日线 {}", self.source_dir.display())],
+        )];
+        if let Some(cleaner) = &self.cleaner {
+            let rules = cleaner
+                .rules()
+                .iter()
+                .map(|rule| serde_json::to_string(rule).unwrap_or_else(|_| format!("{:?}", rule)))
+                .collect();
+            stages.push(PlanStage::new("clean", Some("parse"), rules));
+        }
+        let upstream = stages.last().map(|stage| stage.name.clone());
+        stages.push(PlanStage::new(
+            "indicators",
+            upstream.as_deref(),
+            self.calculator.describe(),
+        ));
+        stages.push(PlanStage::new("write", Some("indicators"), Vec::new()));
+        if let Some(path) = &self.checkpoint_path {
+            stages.push(PlanStage::new(
+                "checkpoint",
+                Some("write"),
+                vec![path.display().to_string()],
+            ));
+        }
+
+        Ok(PipelinePlan {
+            source_dir: self.source_dir.clone(),
+            files_total: files.len(),
+            files_completed: files.len() - pending.len(),
+            input_bytes,
+            estimated_records: input_bytes / BinaryDayRecord::SIZE as u64,
+            batch_size: self.batch_size,
+            batches: pending.len().div_ceil(self.batch_size),
+            checkpoint: self.checkpoint_path.clone(),
+            stages,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::create_data_dir;
+    use super::*;
+    use crate::processors::calculator::EnhancedDayRecord;
+    use crate::processors::cleaner::{CleaningRule, DataCleaner};
+
+    #[test]
+    fn test_plan_does_not_touch_data() {
+        let data = create_data_dir(5);
+        let checkpoint_path = data.path().join("checkpoint.json");
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(CleaningRule::ValidatePriceConsistency);
+        let pipeline = Pipeline::new(data.path())
+            .with_cleaner(cleaner)
+            .with_batch_size(2)
+            .with_checkpoint(&checkpoint_path);
+
+        let mut written = 0;
+        let mut sink = |batch: &[EnhancedDayRecord]| {
+            written += batch.len();
+            Ok(())
+        };
+        let plan = pipeline.plan().unwrap().with_sink(&sink);
+        assert_eq!((plan.files_total, plan.files_pending()), (5, 5));
+        assert_eq!((plan.input_bytes, plan.estimated_records), (480, 15));
+        assert_eq!(plan.batches, 3);
+        let names: Vec<&str> = plan.stages.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["parse", "clean", "indicators", "write", "checkpoint"]
+        );
+        assert_eq!(plan.stages[2].depends_on, vec!["clean"]);
+        assert_eq!(plan.stages[1].details, vec!["\"ValidatePriceConsistency\""]);
+        assert_eq!(plan.stages[3].details.len(), 1);
+        assert!(plan.to_string().contains("indicators <- clean"));
+        assert!(!checkpoint_path.exists());
+
+        // 计划按检查点扣除已完成的文件
+        pipeline.run(&mut sink).unwrap();
+        assert_eq!(written, 15);
+        let plan = pipeline.plan().unwrap();
+        assert_eq!((plan.files_completed, plan.batches), (5, 0));
+        assert_eq!(plan.estimated_records, 0);
+    }
+}
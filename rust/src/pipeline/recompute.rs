@@ -0,0 +1,325 @@
+//! 公司行为触发的定向重算
+//!
+//! 分红、拆股等公司行为生效后，复权价格会变化，依赖它的指标、因子和已写入的行随之失效。
+//! `DependencyTracker` 记录产物之间的依赖关系，把每个事件展开为（股票, 产物, 日期范围）的
+//! 失效集合，并按股票合并为 `RecomputePlan`，交给 `Pipeline::recompute` 只重算受影响的股票。
+//!
+//! 失效范围的推导：前复权时除权日之前的价格全部变化，后复权时除权日及之后的价格变化，
+//! 不复权时价格不变，但送转、拆股改变股本，股本相关的因子从除权日起失效。
+//! 指标和因子由滚动窗口或递推计算，上游某日变化会影响该日之后的所有值，
+//! 因此它们的失效范围延伸到最新日期。
+
+use crate::source::DateRange;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 公司行为类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CorporateActionKind {
+    /// 现金分红（每股派息，元）
+    CashDividend { per_share: f64 },
+    /// 送转或拆股（每股变为 `ratio` 股）
+    Split { ratio: f64 },
+}
+
+/// 公司行为事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorporateAction {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 除权除息日
+    pub ex_date: NaiveDate,
+    /// 类型
+    pub kind: CorporateActionKind,
+}
+
+/// 价格复权方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PriceAdjustment {
+    /// 前复权（最新价格不变，调整历史价格）
+    #[default]
+    Forward,
+    /// 后复权（最早价格不变，调整之后的价格）
+    Backward,
+    /// 不复权
+    None,
+}
+
+/// 可能失效的下游产物
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Artifact {
+    /// 复权价格
+    AdjustedPrices,
+    /// 技术指标
+    Indicators,
+    /// 因子
+    Factors,
+    /// 已写入存储的行
+    StoredRows,
+}
+
+impl Artifact {
+    /// 上游某日变化是否影响此后的所有值（滚动窗口或递推计算）
+    fn extends_forward(self) -> bool {
+        matches!(self, Artifact::Indicators | Artifact::Factors)
+    }
+}
+
+/// 一只股票的重算任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecomputeTask {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 失效的产物及其日期范围
+    pub artifacts: BTreeMap<Artifact, DateRange>,
+}
+
+impl RecomputeTask {
+    /// 需要重新写入存储的日期范围，已写入的行未失效时为None
+    pub fn rows_range(&self) -> Option<DateRange> {
+        self.artifacts.get(&Artifact::StoredRows).copied()
+    }
+}
+
+/// 定向重算计划，按（股票代码, 市场）排序
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecomputePlan {
+    /// 重算任务
+    pub tasks: Vec<RecomputeTask>,
+}
+
+impl RecomputePlan {
+    /// 是否没有需要重算的内容
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// 股票的重算任务
+    pub fn task(&self, symbol: &str, market: &str) -> Option<&RecomputeTask> {
+        self.tasks
+            .iter()
+            .find(|task| task.symbol == symbol && task.market == market)
+    }
+}
+
+/// 公司行为依赖跟踪器
+#[derive(Debug, Clone)]
+pub struct DependencyTracker {
+    /// 复权方式
+    adjustment: PriceAdjustment,
+    /// 依赖关系（上游, 下游）
+    edges: BTreeSet<(Artifact, Artifact)>,
+    /// 尚未重算的失效范围
+    stale: BTreeMap<(String, String), BTreeMap<Artifact, DateRange>>,
+}
+
+impl Default for DependencyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DependencyTracker {
+    /// 创建跟踪器：前复权；复权价格 → 指标、因子、存储行，指标 → 存储行
+    pub fn new() -> Self {
+        Self {
+            adjustment: PriceAdjustment::default(),
+            edges: BTreeSet::from([
+                (Artifact::AdjustedPrices, Artifact::Indicators),
+                (Artifact::AdjustedPrices, Artifact::Factors),
+                (Artifact::AdjustedPrices, Artifact::StoredRows),
+                (Artifact::Indicators, Artifact::StoredRows),
+            ]),
+            stale: BTreeMap::new(),
+        }
+    }
+
+    /// 设置复权方式
+    pub fn with_adjustment(mut self, adjustment: PriceAdjustment) -> Self {
+        self.adjustment = adjustment;
+        self
+    }
+
+    /// 增加依赖关系：`upstream` 变化时 `downstream` 失效
+    pub fn with_dependency(mut self, upstream: Artifact, downstream: Artifact) -> Self {
+        self.edges.insert((upstream, downstream));
+        self
+    }
+
+    /// 登记公司行为，把受影响的产物标记为失效
+    pub fn record(&mut self, action: &CorporateAction) {
+        let before_ex = action.ex_date.pred_opt().map(|end| DateRange {
+            start: None,
+            end: Some(end),
+        });
+        let (artifact, range) = match (self.adjustment, action.kind) {
+            (PriceAdjustment::Forward, _) => match before_ex {
+                Some(range) => (Artifact::AdjustedPrices, range),
+                None => return,
+            },
+            (PriceAdjustment::Backward, _) => {
+                (Artifact::AdjustedPrices, DateRange::since(action.ex_date))
+            }
+            (PriceAdjustment::None, CorporateActionKind::Split { .. }) => {
+                (Artifact::Factors, DateRange::since(action.ex_date))
+            }
+            (PriceAdjustment::None, CorporateActionKind::CashDividend { .. }) => return,
+        };
+
+        let stale = self
+            .stale
+            .entry((action.symbol.clone(), action.market.clone()))
+            .or_default();
+        // 沿依赖关系向下游传播
+        let mut pending = vec![(artifact, range)];
+        while let Some((artifact, range)) = pending.pop() {
+            let merged = match stale.get(&artifact) {
+                Some(existing) if covers(existing, &range) => continue,
+                Some(existing) => union(existing, &range),
+                None => range,
+            };
+            stale.insert(artifact, merged);
+            for &(_, downstream) in self.edges.iter().filter(|(up, _)| *up == artifact) {
+                let range = match downstream.extends_forward() {
+                    true => DateRange {
+                        start: merged.start,
+                        end: None,
+                    },
+                    false => merged,
+                };
+                pending.push((downstream, range));
+            }
+        }
+    }
+
+    /// 当前失效的股票数
+    pub fn stale_symbols(&self) -> usize {
+        self.stale.len()
+    }
+
+    /// 股票某一产物的失效范围
+    pub fn stale_range(&self, symbol: &str, market: &str, artifact: Artifact) -> Option<DateRange> {
+        self.stale
+            .get(&(symbol.to_string(), market.to_string()))?
+            .get(&artifact)
+            .copied()
+    }
+
+    /// 取出重算计划并清空失效记录
+    pub fn take_plan(&mut self) -> RecomputePlan {
+        let tasks = std::mem::take(&mut self.stale)
+            .into_iter()
+            .map(|((symbol, market), artifacts)| RecomputeTask {
+                symbol,
+                market,
+                artifacts,
+            })
+            .collect();
+        RecomputePlan { tasks }
+    }
+}
+
+/// `outer` 是否包含 `inner`
+fn covers(outer: &DateRange, inner: &DateRange) -> bool {
+    let start = match (outer.start, inner.start) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(a), Some(b)) => a <= b,
+    };
+    let end = match (outer.end, inner.end) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(a), Some(b)) => a >= b,
+    };
+    start && end
+}
+
+/// 覆盖两个范围的最小范围
+fn union(a: &DateRange, b: &DateRange) -> DateRange {
+    DateRange {
+        start: a.start.zip(b.start).map(|(x, y)| x.min(y)),
+        end: a.end.zip(b.end).map(|(x, y)| x.max(y)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn dividend(symbol: &str, day: u32) -> CorporateAction {
+        CorporateAction {
+            symbol: symbol.to_string(),
+            market: "SH".to_string(),
+            ex_date: date(day),
+            kind: CorporateActionKind::CashDividend { per_share: 0.5 },
+        }
+    }
+
+    #[test]
+    fn test_stale_ranges_by_adjustment() {
+        let mut forward = DependencyTracker::new();
+        forward.record(&dividend("600000", 10));
+        let prices = forward
+            .stale_range("600000", "SH", Artifact::AdjustedPrices)
+            .unwrap();
+        assert_eq!((prices.start, prices.end), (None, Some(date(9))));
+        // 前复权时指标全部失效，存储行只需改写除权日之前
+        assert_eq!(
+            forward.stale_range("600000", "SH", Artifact::Indicators),
+            Some(DateRange::all())
+        );
+        assert_eq!(
+            forward.stale_range("600000", "SH", Artifact::StoredRows),
+            Some(DateRange::all())
+        );
+
+        let mut backward = DependencyTracker::new().with_adjustment(PriceAdjustment::Backward);
+        backward.record(&dividend("600000", 10));
+        assert_eq!(
+            backward.stale_range("600000", "SH", Artifact::StoredRows),
+            Some(DateRange::since(date(10)))
+        );
+
+        let mut unadjusted = DependencyTracker::new().with_adjustment(PriceAdjustment::None);
+        unadjusted.record(&dividend("600000", 10));
+        assert_eq!(unadjusted.stale_symbols(), 0);
+        unadjusted.record(&CorporateAction {
+            kind: CorporateActionKind::Split { ratio: 2.0 },
+            ..dividend("600000", 10)
+        });
+        assert_eq!(
+            unadjusted.stale_range("600000", "SH", Artifact::Factors),
+            Some(DateRange::since(date(10)))
+        );
+        assert!(unadjusted
+            .stale_range("600000", "SH", Artifact::StoredRows)
+            .is_none());
+    }
+
+    #[test]
+    fn test_merge_and_take_plan() {
+        let mut tracker = DependencyTracker::new()
+            .with_adjustment(PriceAdjustment::Backward)
+            .with_dependency(Artifact::Factors, Artifact::StoredRows);
+        tracker.record(&dividend("600036", 20));
+        tracker.record(&dividend("600036", 5));
+        tracker.record(&dividend("600000", 15));
+
+        let plan = tracker.take_plan();
+        assert_eq!(tracker.stale_symbols(), 0);
+        assert_eq!(plan.tasks.len(), 2);
+        assert_eq!(plan.tasks[0].symbol, "600000");
+        let task = plan.task("600036", "SH").unwrap();
+        assert_eq!(task.rows_range(), Some(DateRange::since(date(5))));
+        assert_eq!(task.artifacts.len(), 4);
+    }
+}
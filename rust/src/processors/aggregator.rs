@@ -1,11 +1,25 @@
 //! 数据聚合模块
 
-use crate::parsers::tdx_day::TDXDayRecord;
-use anyhow::Result;
-use chrono::{DateTime, NaiveDate, Utc};
+use crate::parsers::Bar;
+use crate::processors::calculator::IndicatorCalculator;
+use crate::processors::columnar::ColumnarFrame;
+use crate::processors::expr::RecordExpr;
+use crate::processors::field::Field;
+use crate::reference::SecurityMaster;
+use crate::result_cache::{CacheKey, ResultCache};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 未配置行业映射的股票所属分组
+const UNCLASSIFIED_INDUSTRY: &str = "未分类";
 
 /// 聚合规则
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,44 +37,160 @@ pub enum AggregationRule {
         end_date: NaiveDate,
         function: AggregationFunction,
     },
+    /// 按分组键聚合（市场、行业、月份等）
+    GroupBy {
+        key: GroupKey,
+        function: AggregationFunction,
+    },
     /// 自定义聚合
     Custom {
         name: String,
-        rule: String, // 规则表达式或配置
+        rule: String, // 筛选表达式，如 "close > ma20 && volume > 2 * volume_ma5"，为空时不筛选
         function: AggregationFunction,
     },
 }
 
+/// 分组键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GroupKey {
+    /// 股票代码
+    Symbol,
+    /// 市场（SH/SZ）
+    Market,
+    /// 行业/板块（需通过 `DataAggregator::set_industry_mapping` 提供映射）
+    Industry,
+    /// 自然月（YYYY-MM）
+    Month,
+}
+
+impl AggregationRule {
+    /// 聚合函数
+    pub fn function(&self) -> &AggregationFunction {
+        match self {
+            AggregationRule::TimeWindow { function, .. }
+            | AggregationRule::GroupBySymbol { function }
+            | AggregationRule::DateRange { function, .. }
+            | AggregationRule::GroupBy { function, .. }
+            | AggregationRule::Custom { function, .. } => function,
+        }
+    }
+}
+
+impl GroupKey {
+    /// 分组键名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            GroupKey::Symbol => "symbol",
+            GroupKey::Market => "market",
+            GroupKey::Industry => "industry",
+            GroupKey::Month => "month",
+        }
+    }
+}
+
 /// 聚合函数
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AggregationFunction {
     /// 求和
-    Sum { field: String },
+    Sum { field: Field },
     /// 平均值
-    Mean { field: String },
+    Mean { field: Field },
     /// 最大值
-    Max { field: String },
+    Max { field: Field },
     /// 最小值
-    Min { field: String },
+    Min { field: Field },
     /// 中位数
-    Median { field: String },
+    Median { field: Field },
     /// 计数
     Count,
     /// 第一个值
-    First { field: String },
+    First { field: Field },
     /// 最后一个值
-    Last { field: String },
+    Last { field: Field },
     /// 标准差
-    StdDev { field: String },
+    StdDev { field: Field },
     /// 方差
-    Variance { field: String },
+    Variance { field: Field },
     /// 加权平均
     WeightedMean {
-        value_field: String,
-        weight_field: String,
+        value_field: Field,
+        weight_field: Field,
     },
+    /// 成交量加权均价（成交额合计/成交量合计）
+    Vwap,
     /// 自定义函数
-    Custom { name: String, fields: Vec<String> },
+    Custom { name: String, fields: Vec<Field> },
+    /// 多字段聚合（同一分组内一次计算多个函数，如OHLC汇总）
+    Multi(Vec<AggregationFunction>),
+}
+
+impl AggregationFunction {
+    /// 输出字段名（如 `mean_close`），用于多字段聚合结果中的命名
+    pub fn output_name(&self) -> String {
+        match self {
+            AggregationFunction::Sum { field } => format!("sum_{}", field),
+            AggregationFunction::Mean { field } => format!("mean_{}", field),
+            AggregationFunction::Max { field } => format!("max_{}", field),
+            AggregationFunction::Min { field } => format!("min_{}", field),
+            AggregationFunction::Median { field } => format!("median_{}", field),
+            AggregationFunction::Count => "count".to_string(),
+            AggregationFunction::First { field } => format!("first_{}", field),
+            AggregationFunction::Last { field } => format!("last_{}", field),
+            AggregationFunction::StdDev { field } => format!("stddev_{}", field),
+            AggregationFunction::Variance { field } => format!("variance_{}", field),
+            AggregationFunction::WeightedMean {
+                value_field,
+                weight_field,
+            } => format!("wmean_{}_{}", value_field, weight_field),
+            AggregationFunction::Vwap => "vwap".to_string(),
+            AggregationFunction::Custom { name, .. } => name.clone(),
+            AggregationFunction::Multi(functions) => functions
+                .iter()
+                .map(|f| f.output_name())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+
+    /// 引用的字段
+    pub fn fields(&self) -> Vec<Field> {
+        match self {
+            AggregationFunction::Sum { field }
+            | AggregationFunction::Mean { field }
+            | AggregationFunction::Max { field }
+            | AggregationFunction::Min { field }
+            | AggregationFunction::Median { field }
+            | AggregationFunction::First { field }
+            | AggregationFunction::Last { field }
+            | AggregationFunction::StdDev { field }
+            | AggregationFunction::Variance { field } => vec![field.clone()],
+            AggregationFunction::Count => Vec::new(),
+            AggregationFunction::WeightedMean {
+                value_field,
+                weight_field,
+            } => vec![value_field.clone(), weight_field.clone()],
+            AggregationFunction::Vwap => vec![Field::Amount, Field::Volume],
+            AggregationFunction::Custom { fields, .. } => fields.clone(),
+            AggregationFunction::Multi(functions) => {
+                functions.iter().flat_map(|f| f.fields()).collect()
+            }
+        }
+    }
+
+    /// 构造OHLC汇总聚合（首开、最高、最低、末收、成交量合计）
+    pub fn ohlc() -> Self {
+        AggregationFunction::Multi(vec![
+            AggregationFunction::First { field: Field::Open },
+            AggregationFunction::Max { field: Field::High },
+            AggregationFunction::Min { field: Field::Low },
+            AggregationFunction::Last {
+                field: Field::Close,
+            },
+            AggregationFunction::Sum {
+                field: Field::Volume,
+            },
+        ])
+    }
 }
 
 /// 聚合结果
@@ -85,21 +215,39 @@ pub struct AggregationResult {
 pub struct AggregatedValue {
     /// 聚合键（如股票代码、日期等）
     pub key: String,
-    /// 聚合值
+    /// 聚合值（多字段聚合时为第一个字段的值）
     pub value: f64,
+    /// 命名聚合值（字段名, 值），单函数聚合时仅包含一项
+    pub fields: Vec<(String, f64)>,
     /// 数量（如果是计数聚合）
     pub count: Option<usize>,
     /// 额外信息
     pub metadata: HashMap<String, String>,
 }
 
+impl AggregatedValue {
+    /// 按字段名获取聚合值
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| *value)
+    }
+}
+
 /// 高性能数据聚合器
 #[derive(Debug)]
 pub struct DataAggregator {
     /// 聚合规则列表
     rules: Vec<AggregationRule>,
-    /// 缓存聚合结果
-    cache: HashMap<String, AggregationResult>,
+    /// 共享结果缓存
+    cache: Option<Arc<ResultCache>>,
+    /// 证券主数据的指纹（参与缓存键）
+    master_fingerprint: u64,
+    /// 股票代码到行业的映射
+    industry_mapping: HashMap<String, String>,
+    /// 补齐指标列使用的计算器
+    calculator: IndicatorCalculator,
 }
 
 impl DataAggregator {
@@ -107,10 +255,63 @@ impl DataAggregator {
     pub fn new() -> Self {
         Self {
             rules: Vec::new(),
-            cache: HashMap::new(),
+            cache: None,
+            master_fingerprint: 0,
+            industry_mapping: HashMap::new(),
+            calculator: IndicatorCalculator::new(),
         }
     }
 
+    /// 设置股票代码到行业的映射
+    pub fn set_industry_mapping(&mut self, mapping: HashMap<String, String>) -> &mut Self {
+        self.industry_mapping = mapping;
+        self
+    }
+
+    /// 设置证券主数据，聚合函数可引用换手率（`turnover_rate`）和流通市值（`float_market_cap`）
+    pub fn set_security_master(&mut self, master: SecurityMaster) -> &mut Self {
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", master).hash(&mut hasher);
+        self.master_fingerprint = hasher.finish();
+        self.calculator = std::mem::take(&mut self.calculator).with_security_master(master);
+        self
+    }
+
+    /// 设置共享结果缓存，规则、行业映射、证券主数据和输入数据都相同的聚合直接返回缓存结果
+    pub fn set_result_cache(&mut self, cache: Arc<ResultCache>) -> &mut Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// 从CSV文件加载行业映射
+    ///
+    /// 文件需包含表头，前两列依次为股票代码和行业名称，例如：
+    /// `symbol,industry` / `600000,银行`
+    pub fn load_industry_mapping_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        let path = path.as_ref();
+        let mut reader = csv::Reader::from_path(path)
+            .with_context(|| format!("无法打开行业映射文件: {}", path.display()))?;
+
+        let mut mapping = HashMap::new();
+        for (line, row) in reader.records().enumerate() {
+            let row = row.with_context(|| format!("行业映射文件第{}行解析失败", line + 2))?;
+            match (row.get(0), row.get(1)) {
+                (Some(symbol), Some(industry)) if !symbol.trim().is_empty() => {
+                    mapping.insert(symbol.trim().to_string(), industry.trim().to_string());
+                }
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "行业映射文件第{}行格式错误，期望 股票代码,行业",
+                        line + 2
+                    ))
+                }
+            }
+        }
+
+        self.industry_mapping = mapping;
+        Ok(self)
+    }
+
     /// 添加聚合规则
     pub fn add_rule(&mut self, rule: AggregationRule) -> &mut Self {
         self.rules.push(rule);
@@ -124,11 +325,47 @@ impl DataAggregator {
     }
 
     /// 执行所有聚合规则
-    pub fn aggregate(&self, data: &[TDXDayRecord]) -> Result<Vec<AggregationResult>> {
+    pub fn aggregate<B: Bar>(&self, data: &[B]) -> Result<Vec<AggregationResult>> {
+        self.aggregate_columnar(&ColumnarFrame::from_records(data))
+    }
+
+    /// 基于列式数据执行所有聚合规则
+    ///
+    /// 聚合函数引用的指标列不存在时先在副本上计算。
+    pub fn aggregate_columnar(&self, frame: &ColumnarFrame) -> Result<Vec<AggregationResult>> {
+        match &self.cache {
+            Some(cache) => {
+                let key = CacheKey::new("aggregate", self.cache_params(), frame.fingerprint());
+                let results =
+                    cache.get_or_try_insert_with(key, || self.aggregate_uncached(frame))?;
+                Ok(results.as_ref().clone())
+            }
+            None => self.aggregate_uncached(frame),
+        }
+    }
+
+    /// 缓存键参数：规则与影响结果的配置
+    fn cache_params(&self) -> String {
+        let mut mapping: Vec<(&String, &String)> = self.industry_mapping.iter().collect();
+        mapping.sort();
+        let mut hasher = DefaultHasher::new();
+        mapping.hash(&mut hasher);
+        self.master_fingerprint.hash(&mut hasher);
+        format!("{:?}|{:016x}", self.rules, hasher.finish())
+    }
+
+    fn aggregate_uncached(&self, frame: &ColumnarFrame) -> Result<Vec<AggregationResult>> {
+        let fields: Vec<Field> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.function().fields())
+            .collect();
+        let frame = self.with_indicator_columns(frame, &fields)?;
+        let frame = frame.as_ref();
         let mut results = Vec::with_capacity(self.rules.len());
 
         for rule in &self.rules {
-            let result = self.apply_rule(data, rule)?;
+            let result = self.apply_rule_columnar(frame, rule)?;
             results.push(result);
         }
 
@@ -136,78 +373,88 @@ impl DataAggregator {
     }
 
     /// 应用单个聚合规则
-    fn apply_rule(
+    fn apply_rule<B: Bar>(&self, data: &[B], rule: &AggregationRule) -> Result<AggregationResult> {
+        let mut frame = ColumnarFrame::from_records(data);
+        frame.add_indicator_columns_with(&rule.function().fields(), &self.calculator)?;
+        self.apply_rule_columnar(&frame, rule)
+    }
+
+    /// 补齐聚合所需的指标列
+    fn with_indicator_columns<'a>(
+        &self,
+        frame: &'a ColumnarFrame,
+        fields: &[Field],
+    ) -> Result<Cow<'a, ColumnarFrame>> {
+        if fields.iter().all(|field| frame.has_column(field)) {
+            return Ok(Cow::Borrowed(frame));
+        }
+        let mut owned = frame.clone();
+        owned.add_indicator_columns_with(fields, &self.calculator)?;
+        Ok(Cow::Owned(owned))
+    }
+
+    /// 在列式数据上应用单个聚合规则
+    fn apply_rule_columnar(
         &self,
-        data: &[TDXDayRecord],
+        frame: &ColumnarFrame,
         rule: &AggregationRule,
     ) -> Result<AggregationResult> {
         match rule {
             AggregationRule::TimeWindow {
                 window_size,
                 function,
-            } => self.aggregate_time_window(data, *window_size, function),
-            AggregationRule::GroupBySymbol { function } => self.aggregate_by_symbol(data, function),
+            } => self.aggregate_time_window(frame, *window_size, function),
+            AggregationRule::GroupBySymbol { function } => {
+                self.aggregate_by_symbol(frame, function)
+            }
+            AggregationRule::GroupBy { key, function } => {
+                self.aggregate_by_key(frame, *key, function)
+            }
             AggregationRule::DateRange {
                 start_date,
                 end_date,
                 function,
-            } => self.aggregate_by_date_range(data, start_date, end_date, function),
+            } => self.aggregate_by_date_range(frame, start_date, end_date, function),
             AggregationRule::Custom {
                 name,
-                rule: _,
+                rule,
                 function,
-            } => {
-                // 简化实现：按名称调用对应的聚合方法
-                self.aggregate_custom(data, name, function)
-            }
+            } => self.aggregate_custom(frame, name, rule, function),
         }
     }
 
     /// 时间窗口聚合
     fn aggregate_time_window(
         &self,
-        data: &[TDXDayRecord],
+        frame: &ColumnarFrame,
         window_size: usize,
         function: &AggregationFunction,
     ) -> Result<AggregationResult> {
-        let original_count = data.len();
+        let original_count = frame.len();
         let mut aggregated_values = Vec::new();
+        let timestamps = frame.timestamps();
 
-        // 按股票分组后进行时间窗口聚合
-        let mut symbol_groups: HashMap<String, Vec<TDXDayRecord>> = HashMap::new();
-        for record in data {
-            symbol_groups
-                .entry(record.symbol.clone())
-                .or_insert_with(Vec::new)
-                .push(record.clone());
-        }
-
-        for (symbol, records) in symbol_groups {
-            // 按日期排序
-            let mut sorted_records: Vec<TDXDayRecord> = records.clone();
-            sorted_records.sort_by(|a, b| a.date.cmp(&b.date));
-
-            // 滑动窗口聚合
-            for window in sorted_records.chunks(window_size) {
-                if window.len() == window_size {
-                    let value = self.apply_aggregation_function(window, function)?;
-                    aggregated_values.push(AggregatedValue {
-                        key: format!("{}_{}", symbol, window[0].date),
-                        value,
-                        count: Some(window.len()),
-                        metadata: {
-                            let mut meta = HashMap::new();
-                            meta.insert("symbol".to_string(), symbol.clone());
-                            meta.insert("window_size".to_string(), window_size.to_string());
-                            meta.insert("start_date".to_string(), window[0].date.to_string());
-                            meta.insert(
-                                "end_date".to_string(),
-                                window[window.len() - 1].date.to_string(),
-                            );
-                            meta
-                        },
-                    });
-                }
+        // 按股票分组（组内已按时间排序）后按窗口分段聚合（不足一个窗口的尾部丢弃）
+        for (id, rows) in frame.symbol_groups() {
+            let symbol = frame.symbol(id);
+            for window in rows.chunks_exact(window_size) {
+                let start_date = time_label(timestamps[window[0]]);
+                let end_date = time_label(timestamps[window[window.len() - 1]]);
+                let fields = self.evaluate_fields(frame, window, function)?;
+                aggregated_values.push(AggregatedValue {
+                    key: format!("{}_{}", symbol, start_date),
+                    value: Self::primary_value(&fields),
+                    fields,
+                    count: Some(window.len()),
+                    metadata: {
+                        let mut meta = HashMap::new();
+                        meta.insert("symbol".to_string(), symbol.to_string());
+                        meta.insert("window_size".to_string(), window_size.to_string());
+                        meta.insert("start_date".to_string(), start_date);
+                        meta.insert("end_date".to_string(), end_date);
+                        meta
+                    },
+                });
             }
         }
 
@@ -224,32 +471,27 @@ impl DataAggregator {
     /// 按股票代码聚合
     fn aggregate_by_symbol(
         &self,
-        data: &[TDXDayRecord],
+        frame: &ColumnarFrame,
         function: &AggregationFunction,
     ) -> Result<AggregationResult> {
-        let original_count = data.len();
+        let original_count = frame.len();
         let mut aggregated_values = Vec::new();
 
-        // 按股票分组
-        let mut symbol_groups: HashMap<String, Vec<TDXDayRecord>> = HashMap::new();
-        for record in data {
-            symbol_groups
-                .entry(record.symbol.clone())
-                .or_insert_with(Vec::new)
-                .push(record.clone());
-        }
+        // 按股票代码分组（保持输入顺序）
+        let groups = Self::group_rows(frame.symbol_ids().iter().map(|&id| frame.symbol(id)));
 
         // 对每个股票组应用聚合函数
-        for (symbol, records) in symbol_groups {
-            let value = self.apply_aggregation_function(&records, function)?;
+        for (symbol, rows) in groups {
+            let fields = self.evaluate_fields(frame, &rows, function)?;
             aggregated_values.push(AggregatedValue {
-                key: symbol.clone(),
-                value,
-                count: Some(records.len()),
+                key: symbol.to_string(),
+                value: Self::primary_value(&fields),
+                fields,
+                count: Some(rows.len()),
                 metadata: {
                     let mut meta = HashMap::new();
-                    meta.insert("symbol".to_string(), symbol.clone());
-                    meta.insert("record_count".to_string(), records.len().to_string());
+                    meta.insert("symbol".to_string(), symbol.to_string());
+                    meta.insert("record_count".to_string(), rows.len().to_string());
                     meta
                 },
             });
@@ -265,38 +507,125 @@ impl DataAggregator {
         })
     }
 
+    /// 按分组键聚合
+    fn aggregate_by_key(
+        &self,
+        frame: &ColumnarFrame,
+        key: GroupKey,
+        function: &AggregationFunction,
+    ) -> Result<AggregationResult> {
+        let original_count = frame.len();
+        let mut aggregated_values = Vec::new();
+
+        // 按分组键分组
+        let keys = self.group_key_values(frame, key);
+        let groups = Self::group_rows(keys.iter().map(String::as_str));
+
+        for (group, rows) in groups {
+            let symbol_count = rows
+                .iter()
+                .map(|&row| frame.symbol_ids()[row])
+                .collect::<std::collections::HashSet<_>>()
+                .len();
+            let fields = self.evaluate_fields(frame, &rows, function)?;
+            aggregated_values.push(AggregatedValue {
+                key: group.to_string(),
+                value: Self::primary_value(&fields),
+                fields,
+                count: Some(rows.len()),
+                metadata: {
+                    let mut meta = HashMap::new();
+                    meta.insert(key.name().to_string(), group.to_string());
+                    meta.insert("record_count".to_string(), rows.len().to_string());
+                    meta.insert("symbol_count".to_string(), symbol_count.to_string());
+                    meta
+                },
+            });
+        }
+
+        Ok(AggregationResult {
+            aggregation_id: format!("group_by_{}", key.name()),
+            rule_name: "GroupBy".to_string(),
+            original_count,
+            aggregated_count: aggregated_values.len(),
+            values: aggregated_values,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// 计算每行的分组键值（股票相关的键按字典编码只计算一次）
+    fn group_key_values(&self, frame: &ColumnarFrame, key: GroupKey) -> Vec<String> {
+        let per_symbol = |value: &dyn Fn(u32) -> String| -> Vec<String> {
+            let dictionary: Vec<String> = (0..frame.symbol_count() as u32).map(value).collect();
+            frame
+                .symbol_ids()
+                .iter()
+                .map(|&id| dictionary[id as usize].clone())
+                .collect()
+        };
+
+        match key {
+            GroupKey::Symbol => per_symbol(&|id| frame.symbol(id).to_string()),
+            GroupKey::Market => per_symbol(&|id| frame.market(id).to_string()),
+            GroupKey::Industry => per_symbol(&|id| {
+                self.industry_mapping
+                    .get(frame.symbol(id))
+                    .cloned()
+                    .unwrap_or_else(|| UNCLASSIFIED_INDUSTRY.to_string())
+            }),
+            GroupKey::Month => frame
+                .timestamps()
+                .iter()
+                .map(|timestamp| timestamp.format("%Y-%m").to_string())
+                .collect(),
+        }
+    }
+
+    /// 按键分组行索引，分组按首次出现顺序排列
+    fn group_rows<'a>(keys: impl Iterator<Item = &'a str>) -> Vec<(&'a str, Vec<usize>)> {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+        for (row, key) in keys.enumerate() {
+            let slot = *index.entry(key).or_insert_with(|| {
+                groups.push((key, Vec::new()));
+                groups.len() - 1
+            });
+            groups[slot].1.push(row);
+        }
+        groups
+    }
+
     /// 按日期范围聚合
     fn aggregate_by_date_range(
         &self,
-        data: &[TDXDayRecord],
+        frame: &ColumnarFrame,
         start_date: &NaiveDate,
         end_date: &NaiveDate,
         function: &AggregationFunction,
     ) -> Result<AggregationResult> {
-        let original_count = data.len();
+        let original_count = frame.len();
         let mut aggregated_values = Vec::new();
 
         // 过滤日期范围内的记录
-        let filtered_records: Vec<TDXDayRecord> = data
-            .iter()
-            .filter(|record| &record.date >= start_date && &record.date <= end_date)
-            .cloned()
+        let rows: Vec<usize> = (0..frame.len())
+            .filter(|&row| {
+                let date = frame.date(row);
+                date >= *start_date && date <= *end_date
+            })
             .collect();
 
-        if !filtered_records.is_empty() {
-            let value = self.apply_aggregation_function(&filtered_records, function)?;
+        if !rows.is_empty() {
+            let fields = self.evaluate_fields(frame, &rows, function)?;
             aggregated_values.push(AggregatedValue {
                 key: format!("{}_{}", start_date, end_date),
-                value,
-                count: Some(filtered_records.len()),
+                value: Self::primary_value(&fields),
+                fields,
+                count: Some(rows.len()),
                 metadata: {
                     let mut meta = HashMap::new();
                     meta.insert("start_date".to_string(), start_date.to_string());
                     meta.insert("end_date".to_string(), end_date.to_string());
-                    meta.insert(
-                        "record_count".to_string(),
-                        filtered_records.len().to_string(),
-                    );
+                    meta.insert("record_count".to_string(), rows.len().to_string());
                     meta
                 },
             });
@@ -312,26 +641,40 @@ impl DataAggregator {
         })
     }
 
-    /// 自定义聚合
+    /// 自定义聚合：先按表达式筛选记录，再对筛选结果应用聚合函数
     fn aggregate_custom(
         &self,
-        data: &[TDXDayRecord],
+        frame: &ColumnarFrame,
         name: &str,
+        rule: &str,
         function: &AggregationFunction,
     ) -> Result<AggregationResult> {
-        let original_count = data.len();
+        let original_count = frame.len();
         let mut aggregated_values = Vec::new();
 
-        // 简化实现：对全部数据应用聚合函数
-        let value = self.apply_aggregation_function(data, function)?;
+        let rows: Vec<usize> = if rule.trim().is_empty() {
+            (0..frame.len()).collect()
+        } else {
+            RecordExpr::parse(rule)?
+                .mask_columnar(frame)?
+                .into_iter()
+                .enumerate()
+                .filter(|(_, keep)| *keep)
+                .map(|(row, _)| row)
+                .collect()
+        };
+
+        let fields = self.evaluate_fields(frame, &rows, function)?;
         aggregated_values.push(AggregatedValue {
             key: name.to_string(),
-            value,
-            count: Some(data.len()),
+            value: Self::primary_value(&fields),
+            fields,
+            count: Some(rows.len()),
             metadata: {
                 let mut meta = HashMap::new();
                 meta.insert("aggregation_type".to_string(), "custom".to_string());
-                meta.insert("record_count".to_string(), data.len().to_string());
+                meta.insert("rule".to_string(), rule.to_string());
+                meta.insert("record_count".to_string(), rows.len().to_string());
                 meta
             },
         });
@@ -346,109 +689,103 @@ impl DataAggregator {
         })
     }
 
-    /// 应用聚合函数
+    /// 计算命名聚合值，多字段聚合在同一分组上依次求值并展开
+    fn evaluate_fields(
+        &self,
+        frame: &ColumnarFrame,
+        rows: &[usize],
+        function: &AggregationFunction,
+    ) -> Result<Vec<(String, f64)>> {
+        match function {
+            AggregationFunction::Multi(functions) => {
+                if functions.is_empty() {
+                    return Err(anyhow::anyhow!("多字段聚合至少需要一个聚合函数"));
+                }
+                let mut fields = Vec::with_capacity(functions.len());
+                for function in functions {
+                    fields.extend(self.evaluate_fields(frame, rows, function)?);
+                }
+                Ok(fields)
+            }
+            _ => Ok(vec![(
+                function.output_name(),
+                self.apply_aggregation_function(frame, rows, function)?,
+            )]),
+        }
+    }
+
+    /// 主聚合值（第一个字段）
+    fn primary_value(fields: &[(String, f64)]) -> f64 {
+        fields.first().map(|(_, value)| *value).unwrap_or(0.0)
+    }
+
+    /// 应用单个聚合函数（`rows` 为参与聚合的行索引），多字段聚合需经 `evaluate_fields` 展开
     fn apply_aggregation_function(
         &self,
-        records: &[TDXDayRecord],
+        frame: &ColumnarFrame,
+        rows: &[usize],
         function: &AggregationFunction,
     ) -> Result<f64> {
-        if records.is_empty() {
+        if let AggregationFunction::Multi(_) = function {
+            return Err(anyhow::anyhow!(
+                "多字段聚合没有单一聚合值: {}",
+                function.output_name()
+            ));
+        }
+        if rows.is_empty() {
             return Ok(0.0);
         }
 
+        let values = |field: &Field| -> Result<Vec<f64>> { Ok(frame.column(field)?.gather(rows)) };
+
         match function {
             AggregationFunction::Sum { field } => {
-                let sum: f64 = records
-                    .iter()
-                    .map(|r| self.extract_field_value(r, field))
-                    .collect::<Result<Vec<f64>>>()?
-                    .iter()
-                    .sum();
-                Ok(sum)
+                let column = frame.column(field)?;
+                Ok(rows.iter().map(|&row| column.get(row)).sum())
             }
             AggregationFunction::Mean { field } => {
-                let values: Vec<f64> = records
-                    .iter()
-                    .map(|r| self.extract_field_value(r, &field))
-                    .collect::<Result<Vec<f64>>>()?;
-                let mean = values.iter().sum::<f64>() / values.len() as f64;
-                Ok(mean)
+                let column = frame.column(field)?;
+                let sum: f64 = rows.iter().map(|&row| column.get(row)).sum();
+                Ok(sum / rows.len() as f64)
             }
             AggregationFunction::Max { field } => {
-                let values: Vec<f64> = records
+                let column = frame.column(field)?;
+                Ok(rows
                     .iter()
-                    .map(|r| self.extract_field_value(r, &field))
-                    .collect::<Result<Vec<f64>>>()?;
-                let max = values.iter().fold(f64::MIN, |a, &b| a.max(b));
-                Ok(max)
+                    .map(|&row| column.get(row))
+                    .fold(f64::MIN, f64::max))
             }
             AggregationFunction::Min { field } => {
-                let values: Vec<f64> = records
+                let column = frame.column(field)?;
+                Ok(rows
                     .iter()
-                    .map(|r| self.extract_field_value(r, &field))
-                    .collect::<Result<Vec<f64>>>()?;
-                let min = values.iter().fold(f64::MAX, |a, &b| a.min(b));
-                Ok(min)
+                    .map(|&row| column.get(row))
+                    .fold(f64::MAX, f64::min))
             }
             AggregationFunction::Median { field } => {
-                let mut values: Vec<f64> = records
-                    .iter()
-                    .map(|r| self.extract_field_value(r, field))
-                    .collect::<Result<Vec<f64>>>()?;
+                let mut values = values(field)?;
                 values.sort_by(|a, b| a.partial_cmp(b).unwrap());
-                let median = if values.is_empty() {
-                    0.0
-                } else {
-                    values[values.len() / 2]
-                };
-                Ok(median)
-            }
-            AggregationFunction::Count => Ok(records.len() as f64),
-            AggregationFunction::First { field } => {
-                if let Some(record) = records.first() {
-                    self.extract_field_value(record, field)
-                } else {
-                    Ok(0.0)
-                }
+                Ok(values[values.len() / 2])
             }
+            AggregationFunction::Count => Ok(rows.len() as f64),
+            AggregationFunction::First { field } => Ok(frame.column(field)?.get(rows[0])),
             AggregationFunction::Last { field } => {
-                if let Some(record) = records.last() {
-                    self.extract_field_value(record, field)
-                } else {
-                    Ok(0.0)
-                }
-            }
-            AggregationFunction::StdDev { field } => {
-                let values: Vec<f64> = records
-                    .iter()
-                    .map(|r| self.extract_field_value(r, &field))
-                    .collect::<Result<Vec<f64>>>()?;
-                let mean = values.iter().sum::<f64>() / values.len() as f64;
-                let variance =
-                    values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64;
-                Ok(variance.sqrt())
-            }
-            AggregationFunction::Variance { field } => {
-                let values: Vec<f64> = records
-                    .iter()
-                    .map(|r| self.extract_field_value(r, &field))
-                    .collect::<Result<Vec<f64>>>()?;
-                let mean = values.iter().sum::<f64>() / values.len() as f64;
-                let variance =
-                    values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64;
-                Ok(variance)
+                Ok(frame.column(field)?.get(rows[rows.len() - 1]))
             }
+            AggregationFunction::StdDev { field } => Ok(Self::variance(&values(field)?).sqrt()),
+            AggregationFunction::Variance { field } => Ok(Self::variance(&values(field)?)),
             AggregationFunction::WeightedMean {
                 value_field,
                 weight_field,
             } => {
+                let value_column = frame.column(value_field)?;
+                let weight_column = frame.column(weight_field)?;
                 let mut weighted_sum = 0.0;
                 let mut weight_sum = 0.0;
 
-                for record in records {
-                    let value = self.extract_field_value(record, value_field)?;
-                    let weight = self.extract_field_value(record, weight_field)?;
-                    weighted_sum += value * weight;
+                for &row in rows {
+                    let weight = weight_column.get(row);
+                    weighted_sum += value_column.get(row) * weight;
                     weight_sum += weight;
                 }
 
@@ -458,30 +795,33 @@ impl DataAggregator {
                     0.0
                 })
             }
+            AggregationFunction::Vwap => {
+                let (amounts, volumes) = (values(&Field::Amount)?, values(&Field::Volume)?);
+                let volume: f64 = volumes.iter().sum();
+                Ok(if volume > 0.0 {
+                    amounts.iter().sum::<f64>() / volume
+                } else {
+                    f64::NAN
+                })
+            }
             AggregationFunction::Custom { name: _, fields: _ } => {
                 // 简化实现：返回记录数的对数
-                Ok((records.len() as f64).log2())
+                Ok((rows.len() as f64).log2())
             }
+            AggregationFunction::Multi(_) => unreachable!("多字段聚合已在入口处拒绝"),
         }
     }
 
-    /// 从记录中提取字段值
-    fn extract_field_value(&self, record: &TDXDayRecord, field: &str) -> Result<f64> {
-        match field {
-            "open" => Ok(record.open),
-            "high" => Ok(record.high),
-            "low" => Ok(record.low),
-            "close" => Ok(record.close),
-            "volume" => Ok(record.volume as f64),
-            "amount" => Ok(record.amount),
-            _ => Err(anyhow::anyhow!("未知字段: {}", field)),
-        }
+    /// 总体方差
+    fn variance(values: &[f64]) -> f64 {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64
     }
 
     /// 并行聚合多个数据集
-    pub fn aggregate_parallel(
+    pub fn aggregate_parallel<B: Bar + Sync>(
         &self,
-        datasets: &[&[TDXDayRecord]],
+        datasets: &[&[B]],
     ) -> Result<Vec<Vec<AggregationResult>>> {
         let results: Result<Vec<_>> = datasets
             .into_par_iter()
@@ -492,14 +832,15 @@ impl DataAggregator {
     }
 
     /// 流式聚合（适用于大数据集）
-    pub fn aggregate_stream<I>(
+    pub fn aggregate_stream<B, I>(
         &self,
         data_stream: I,
         batch_size: usize,
         rule: &AggregationRule,
     ) -> Result<Vec<AggregationResult>>
     where
-        I: Iterator<Item = TDXDayRecord>,
+        B: Bar,
+        I: Iterator<Item = B>,
     {
         let mut batch = Vec::with_capacity(batch_size);
         let mut results = Vec::new();
@@ -543,6 +884,15 @@ impl DataAggregator {
     }
 }
 
+/// 时间标签：日线为日期，分钟线为完整时间
+fn time_label(timestamp: NaiveDateTime) -> String {
+    if timestamp.time() == NaiveTime::MIN {
+        timestamp.date().to_string()
+    } else {
+        timestamp.to_string()
+    }
+}
+
 /// 聚合统计信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AggregationStats {
@@ -566,19 +916,19 @@ impl Default for DataAggregator {
         aggregator.add_rules(vec![
             AggregationRule::GroupBySymbol {
                 function: AggregationFunction::Mean {
-                    field: "close".to_string(),
+                    field: Field::Close,
                 },
             },
             AggregationRule::TimeWindow {
                 window_size: 5,
                 function: AggregationFunction::Mean {
-                    field: "close".to_string(),
+                    field: Field::Close,
                 },
             },
             AggregationRule::TimeWindow {
                 window_size: 20,
                 function: AggregationFunction::Mean {
-                    field: "volume".to_string(),
+                    field: Field::Volume,
                 },
             },
         ]);
@@ -590,6 +940,7 @@ impl Default for DataAggregator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parsers::TDXDayRecord;
     use chrono::NaiveDate;
 
     fn create_test_record(symbol: &str, date: &str) -> TDXDayRecord {
@@ -612,12 +963,42 @@ mod tests {
         assert!(aggregator.rules.is_empty());
     }
 
+    #[test]
+    fn test_shared_result_cache() {
+        let cache = Arc::new(ResultCache::new());
+        let mut aggregator = DataAggregator::new();
+        aggregator.set_result_cache(Arc::clone(&cache));
+        aggregator.add_rule(AggregationRule::GroupBySymbol {
+            function: AggregationFunction::Mean {
+                field: Field::Close,
+            },
+        });
+        let mut data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("000001", "2024-01-01"),
+        ];
+
+        let first = aggregator.aggregate(&data).unwrap();
+        let second = aggregator.aggregate(&data).unwrap();
+        assert_eq!(first[0].timestamp, second[0].timestamp);
+        assert_eq!(cache.stats().hits, 1);
+
+        // 数据或行业映射变化后不命中
+        data[0].close = 12.0;
+        aggregator.aggregate(&data).unwrap();
+        aggregator
+            .set_industry_mapping(HashMap::from([("600000".to_string(), "银行".to_string())]));
+        aggregator.aggregate(&data).unwrap();
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.len(), 3);
+    }
+
     #[test]
     fn test_add_rules() {
         let mut aggregator = DataAggregator::new();
         aggregator.add_rule(AggregationRule::GroupBySymbol {
             function: AggregationFunction::Mean {
-                field: "close".to_string(),
+                field: Field::Close,
             },
         });
         assert_eq!(aggregator.rules.len(), 1);
@@ -634,7 +1015,7 @@ mod tests {
 
         let rule = AggregationRule::GroupBySymbol {
             function: AggregationFunction::Mean {
-                field: "close".to_string(),
+                field: Field::Close,
             },
         };
 
@@ -656,22 +1037,124 @@ mod tests {
         let rule = AggregationRule::TimeWindow {
             window_size: 3,
             function: AggregationFunction::Mean {
-                field: "close".to_string(),
+                field: Field::Close,
             },
         };
 
         let result = aggregator.apply_rule(&data, &rule).unwrap();
-        assert_eq!(result.aggregated_count, 3); // 5个记录，3个窗口
+        assert_eq!(result.aggregated_count, 1); // 5个记录，1个完整窗口
+    }
+
+    #[test]
+    fn test_multi_field_aggregation() {
+        let aggregator = DataAggregator::new();
+        let mut data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600000", "2024-01-02"),
+        ];
+        data[1].open = 10.6;
+        data[1].high = 12.0;
+        data[1].close = 11.8;
+
+        let rule = AggregationRule::GroupBySymbol {
+            function: AggregationFunction::ohlc(),
+        };
+
+        let result = aggregator.apply_rule(&data, &rule).unwrap();
+        assert_eq!(result.aggregated_count, 1);
+
+        let row = &result.values[0];
+        assert_eq!(row.fields.len(), 5);
+        assert_eq!(row.get("first_open"), Some(10.0));
+        assert_eq!(row.get("max_high"), Some(12.0));
+        assert_eq!(row.get("min_low"), Some(9.0));
+        assert_eq!(row.get("last_close"), Some(11.8));
+        assert_eq!(row.get("sum_volume"), Some(2000000.0));
+        assert_eq!(row.value, 10.0);
+
+        // 空的多字段聚合直接报错，而不是返回0
+        let rule = AggregationRule::GroupBySymbol {
+            function: AggregationFunction::Multi(Vec::new()),
+        };
+        assert!(aggregator.apply_rule(&data, &rule).is_err());
+    }
+
+    #[test]
+    fn test_group_by_market_and_industry() {
+        let mut aggregator = DataAggregator::new();
+        let mut data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600036", "2024-01-01"),
+            create_test_record("000001", "2024-02-01"),
+            create_test_record("000002", "2024-02-01"),
+        ];
+        data[2].market = "SZ".to_string();
+        data[3].market = "SZ".to_string();
+
+        let rule = AggregationRule::GroupBy {
+            key: GroupKey::Market,
+            function: AggregationFunction::Count,
+        };
+        let result = aggregator.apply_rule(&data, &rule).unwrap();
+        assert_eq!(result.aggregated_count, 2);
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let csv_path = temp_dir.path().join("industry.csv");
+        std::fs::write(
+            &csv_path,
+            "symbol,industry\n600000,银行\n600036,银行\n000001,银行\n",
+        )
+        .unwrap();
+        aggregator.load_industry_mapping_csv(&csv_path).unwrap();
+
+        let rule = AggregationRule::GroupBy {
+            key: GroupKey::Industry,
+            function: AggregationFunction::Sum {
+                field: Field::Amount,
+            },
+        };
+        let result = aggregator.apply_rule(&data, &rule).unwrap();
+        let bank = result.values.iter().find(|v| v.key == "银行").unwrap();
+        assert_eq!(bank.count, Some(3));
+        assert!(result.values.iter().any(|v| v.key == UNCLASSIFIED_INDUSTRY));
+
+        let rule = AggregationRule::GroupBy {
+            key: GroupKey::Month,
+            function: AggregationFunction::Count,
+        };
+        let result = aggregator.apply_rule(&data, &rule).unwrap();
+        assert!(result.values.iter().any(|v| v.key == "2024-02"));
+    }
+
+    #[test]
+    fn test_custom_rule_expression() {
+        let aggregator = DataAggregator::new();
+        let mut data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600000", "2024-01-02"),
+            create_test_record("000001", "2024-01-01"),
+        ];
+        data[1].volume = 3000000;
+
+        let rule = AggregationRule::Custom {
+            name: "heavy_volume".to_string(),
+            rule: "volume > 2000000".to_string(),
+            function: AggregationFunction::Count,
+        };
+
+        let result = aggregator.apply_rule(&data, &rule).unwrap();
+        assert_eq!(result.original_count, 3);
+        assert_eq!(result.values[0].value, 1.0);
     }
 
     #[test]
     fn test_parallel_aggregation() {
         let aggregator = DataAggregator::new();
-        let dataset1 = vec![
+        let dataset1 = [
             create_test_record("600000", "2024-01-01"),
             create_test_record("600000", "2024-01-02"),
         ];
-        let dataset2 = vec![
+        let dataset2 = [
             create_test_record("000001", "2024-01-01"),
             create_test_record("000001", "2024-01-02"),
         ];
@@ -686,4 +1169,97 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_indicator_field_from_config() {
+        let rule: AggregationRule =
+            serde_json::from_str(r#"{"GroupBySymbol":{"function":{"Last":{"field":"ma5"}}}}"#)
+                .unwrap();
+        // 字段名拼写错误在解析配置时报错
+        assert!(serde_json::from_str::<AggregationRule>(
+            r#"{"GroupBySymbol":{"function":{"Last":{"field":"ma_5"}}}}"#
+        )
+        .is_err());
+
+        let data: Vec<TDXDayRecord> = (1..=6)
+            .map(|day| {
+                let mut record = create_test_record("600000", &format!("2024-01-0{}", day));
+                record.close = day as f64;
+                record
+            })
+            .collect();
+
+        let mut aggregator = DataAggregator::new();
+        aggregator.add_rule(rule);
+        let results = aggregator.aggregate(&data).unwrap();
+        // 最后5日收盘价2..=6的均值
+        assert_eq!(results[0].values[0].get("last_ma5"), Some(4.0));
+    }
+
+    #[test]
+    fn test_turnover_with_security_master() {
+        use crate::reference::{SecurityMaster, SecurityMeta, ShareCapital};
+
+        let mut meta = SecurityMeta::new("600000", "SH", "浦发银行");
+        meta.add_share_capital(ShareCapital {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            total_shares: 20_000_000.0,
+            float_shares: 10_000_000.0,
+        });
+        let mut master = SecurityMaster::new();
+        master.insert(meta);
+
+        let data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600000", "2024-01-02"),
+            create_test_record("000001", "2024-01-02"),
+        ];
+        let mut aggregator = DataAggregator::new();
+        aggregator.set_security_master(master).add_rules(vec![
+            AggregationRule::GroupBySymbol {
+                function: AggregationFunction::Mean {
+                    field: "turnover_rate".parse().unwrap(),
+                },
+            },
+            AggregationRule::GroupBySymbol {
+                function: AggregationFunction::Last {
+                    field: "float_market_cap".parse().unwrap(),
+                },
+            },
+        ]);
+
+        let results = aggregator.aggregate(&data).unwrap();
+        let value = |rule: usize, symbol: &str, name: &str| {
+            results[rule]
+                .values
+                .iter()
+                .find(|v| v.key == symbol)
+                .and_then(|v| v.get(name))
+        };
+        assert_eq!(value(0, "600000", "mean_turnover_rate"), Some(10.0));
+        assert_eq!(
+            value(1, "600000", "last_float_market_cap"),
+            Some(105_000_000.0)
+        );
+        // 未登记股本的股票没有换手率
+        assert!(value(0, "000001", "mean_turnover_rate").is_none_or(f64::is_nan));
+    }
+
+    #[test]
+    fn test_vwap_aggregation() {
+        let mut data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600000", "2024-01-02"),
+        ];
+        data[1].volume = 3_000_000;
+        data[1].amount = 33_000_000.0;
+
+        let mut aggregator = DataAggregator::new();
+        aggregator.add_rule(AggregationRule::GroupBySymbol {
+            function: AggregationFunction::Vwap,
+        });
+        let results = aggregator.aggregate(&data).unwrap();
+        // (1050万 + 3300万) / (100万 + 300万)
+        assert_eq!(results[0].values[0].get("vwap"), Some(10.875));
+    }
 }
@@ -0,0 +1,210 @@
+//! 时间序列对齐模块
+//!
+//! 将长格式（每行一只股票一天）的日线记录透视为按日期排列、跨股票对齐的宽格式，
+//! 缺失数据可按策略处理：删除该日、前向填充或保留为空（数值视图中为NaN）。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::field::Field;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 缺失数据处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MissingPolicy {
+    /// 删除任一股票缺失的日期
+    Drop,
+    /// 使用该股票最近一条记录填充（首次出现之前仍为空）
+    ForwardFill,
+    /// 保留缺失，数值视图中为NaN
+    NaN,
+}
+
+/// 对齐后的单日数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignedRow {
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 各股票的记录，顺序与 `AlignedFrame::symbols` 一致
+    pub records: Vec<Option<TDXDayRecord>>,
+}
+
+/// 按日期对齐的宽格式数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignedFrame {
+    /// 股票代码（列）
+    pub symbols: Vec<String>,
+    /// 按日期升序排列的行
+    pub rows: Vec<AlignedRow>,
+}
+
+impl AlignedFrame {
+    /// 日期序列
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        self.rows.iter().map(|row| row.date).collect()
+    }
+
+    /// 股票所在列
+    pub fn column_index(&self, symbol: &str) -> Option<usize> {
+        self.symbols.iter().position(|s| s == symbol)
+    }
+
+    /// 提取某个字段的数值矩阵（行为日期，列为股票），缺失为NaN
+    pub fn values(&self, field: &Field) -> Result<Vec<Vec<f64>>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.records
+                    .iter()
+                    .map(|record| value_or_nan(record.as_ref(), field))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// 提取单只股票某个字段的日期序列，缺失为NaN
+    pub fn column(&self, symbol: &str, field: &Field) -> Result<Vec<(NaiveDate, f64)>> {
+        let index = self
+            .column_index(symbol)
+            .ok_or_else(|| anyhow::anyhow!("对齐数据中不存在股票: {}", symbol))?;
+        self.rows
+            .iter()
+            .map(|row| Ok((row.date, value_or_nan(row.records[index].as_ref(), field)?)))
+            .collect()
+    }
+}
+
+/// 将长格式记录按日期对齐
+///
+/// `symbols` 为空时使用数据中出现的全部股票（按代码排序）。
+pub fn align_by_date(
+    records: &[TDXDayRecord],
+    symbols: &[String],
+    policy: MissingPolicy,
+) -> AlignedFrame {
+    let symbols: Vec<String> = if symbols.is_empty() {
+        let mut all: Vec<String> = records.iter().map(|r| r.symbol.clone()).collect();
+        all.sort();
+        all.dedup();
+        all
+    } else {
+        symbols.to_vec()
+    };
+
+    let columns: HashMap<&str, usize> = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, symbol)| (symbol.as_str(), i))
+        .collect();
+
+    // 透视为按日期的宽表
+    let mut by_date: BTreeMap<NaiveDate, Vec<Option<TDXDayRecord>>> = BTreeMap::new();
+    for record in records {
+        if let Some(&column) = columns.get(record.symbol.as_str()) {
+            by_date
+                .entry(record.date)
+                .or_insert_with(|| vec![None; symbols.len()])[column] = Some(record.clone());
+        }
+    }
+
+    let mut rows: Vec<AlignedRow> = by_date
+        .into_iter()
+        .map(|(date, records)| AlignedRow { date, records })
+        .collect();
+
+    match policy {
+        MissingPolicy::Drop => rows.retain(|row| row.records.iter().all(Option::is_some)),
+        MissingPolicy::ForwardFill => {
+            let mut last: Vec<Option<TDXDayRecord>> = vec![None; symbols.len()];
+            for row in &mut rows {
+                for (slot, previous) in row.records.iter_mut().zip(last.iter_mut()) {
+                    match slot {
+                        Some(record) => *previous = Some(record.clone()),
+                        None => *slot = previous.clone(),
+                    }
+                }
+            }
+        }
+        MissingPolicy::NaN => {}
+    }
+
+    AlignedFrame { symbols, rows }
+}
+
+/// 读取字段值，缺失记录为NaN
+fn value_or_nan(record: Option<&TDXDayRecord>, field: &Field) -> Result<f64> {
+    match record {
+        Some(record) => field.value(record),
+        None => Ok(f64::NAN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn sample_data() -> Vec<TDXDayRecord> {
+        vec![
+            create_test_record("600000", 2, 10.0),
+            create_test_record("600000", 3, 10.5),
+            create_test_record("600000", 4, 11.0),
+            create_test_record("600036", 2, 20.0),
+            // 600036 在3日停牌
+            create_test_record("600036", 4, 21.0),
+        ]
+    }
+
+    #[test]
+    fn test_align_with_nan() {
+        let frame = align_by_date(&sample_data(), &[], MissingPolicy::NaN);
+        assert_eq!(frame.symbols, vec!["600000", "600036"]);
+        assert_eq!(frame.rows.len(), 3);
+
+        let values = frame.values(&Field::Close).unwrap();
+        assert_eq!(values[0], vec![10.0, 20.0]);
+        assert_eq!(values[1][0], 10.5);
+        assert!(values[1][1].is_nan());
+        assert!(frame.values(&Field::Indicator("ma5".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_align_with_drop_and_forward_fill() {
+        let dropped = align_by_date(&sample_data(), &[], MissingPolicy::Drop);
+        assert_eq!(dropped.rows.len(), 2);
+        assert_eq!(
+            dropped.dates()[1],
+            NaiveDate::from_ymd_opt(2024, 1, 4).unwrap()
+        );
+
+        let filled = align_by_date(&sample_data(), &[], MissingPolicy::ForwardFill);
+        let column = filled.column("600036", &Field::Close).unwrap();
+        assert_eq!(column[1].1, 20.0);
+        assert_eq!(column[2].1, 21.0);
+    }
+
+    #[test]
+    fn test_align_selected_symbols() {
+        let symbols = vec!["600036".to_string(), "601318".to_string()];
+        let frame = align_by_date(&sample_data(), &symbols, MissingPolicy::NaN);
+
+        // 只有600036有数据的日期
+        assert_eq!(frame.rows.len(), 2);
+        assert_eq!(frame.column_index("600036"), Some(0));
+        assert!(frame.rows[0].records[1].is_none());
+    }
+}
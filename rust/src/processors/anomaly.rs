@@ -0,0 +1,299 @@
+//! 成交量/成交额异常检测模块
+//!
+//! 按股票分组，用此前K线的滚动均值和标准差（或指数加权均值和方差）计算当日
+//! 成交量、成交额的Z分数，超过阈值的放量K线输出为 `VolumeAnomaly` 事件，
+//! 并附带相对此前N日（默认20日）平均值的倍数。既可作为数据质量检查
+//! （单位错误、重复累加等），也可作为交易特征。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::kernels::{rolling_mean, rolling_std};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 检测的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyField {
+    /// 成交量
+    Volume,
+    /// 成交额
+    Amount,
+}
+
+impl AnomalyField {
+    /// 取出K线上的字段值
+    pub fn value(&self, record: &TDXDayRecord) -> f64 {
+        match self {
+            AnomalyField::Volume => record.volume as f64,
+            AnomalyField::Amount => record.amount,
+        }
+    }
+}
+
+/// 基准统计方法
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum AnomalyMethod {
+    /// 此前 `window` 根K线的均值和标准差
+    ZScore {
+        /// 窗口长度
+        window: usize,
+    },
+    /// 指数加权均值和方差（平滑系数 2/(span+1)），至少积累 `span` 根K线后才开始判断
+    Ewma {
+        /// 跨度
+        span: usize,
+    },
+}
+
+impl Default for AnomalyMethod {
+    fn default() -> Self {
+        AnomalyMethod::ZScore { window: 20 }
+    }
+}
+
+/// 成交量/成交额异常事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeAnomaly {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 异常字段
+    pub field: AnomalyField,
+    /// 当日值
+    pub value: f64,
+    /// 此前N日平均值
+    pub average: f64,
+    /// 当日值相对平均值的倍数
+    pub ratio: f64,
+    /// Z分数
+    pub zscore: f64,
+}
+
+/// 成交量/成交额异常检测器
+#[derive(Debug, Clone)]
+pub struct VolumeAnomalyDetector {
+    /// 基准统计方法
+    method: AnomalyMethod,
+    /// Z分数阈值
+    threshold: f64,
+    /// 计算平均倍数的窗口
+    average_window: usize,
+    /// 检测的字段
+    fields: Vec<AnomalyField>,
+}
+
+impl Default for VolumeAnomalyDetector {
+    fn default() -> Self {
+        Self {
+            method: AnomalyMethod::default(),
+            threshold: 3.0,
+            average_window: 20,
+            fields: vec![AnomalyField::Volume, AnomalyField::Amount],
+        }
+    }
+}
+
+impl VolumeAnomalyDetector {
+    /// 创建检测器：20日滚动Z分数、阈值3，检测成交量和成交额
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置基准统计方法
+    pub fn with_method(mut self, method: AnomalyMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// 设置Z分数阈值
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// 设置计算平均倍数的窗口
+    pub fn with_average_window(mut self, window: usize) -> Self {
+        self.average_window = window.max(1);
+        self
+    }
+
+    /// 设置检测的字段
+    pub fn with_fields(mut self, fields: Vec<AnomalyField>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// 单只股票（已按日期排序）某字段的Z分数序列，历史不足或波动为0的位置为NaN
+    pub fn zscores(&self, values: &[f64]) -> Vec<f64> {
+        let mut scores = vec![f64::NAN; values.len()];
+        match self.method {
+            AnomalyMethod::ZScore { window } => {
+                let means = rolling_mean(values, window);
+                let stds = rolling_std(values, window);
+                for i in 1..values.len() {
+                    // 基准只用此前的K线，当日的放量不稀释自身
+                    let (mean, std) = (means[i - 1], stds[i - 1]);
+                    if std > 0.0 {
+                        scores[i] = (values[i] - mean) / std;
+                    }
+                }
+            }
+            AnomalyMethod::Ewma { span } => {
+                let alpha = 2.0 / (span.max(1) as f64 + 1.0);
+                let Some(&first) = values.first() else {
+                    return scores;
+                };
+                let (mut mean, mut variance) = (first, 0.0f64);
+                for (i, &value) in values.iter().enumerate().skip(1) {
+                    if i >= span && variance > 0.0 {
+                        scores[i] = (value - mean) / variance.sqrt();
+                    }
+                    let diff = value - mean;
+                    let increment = alpha * diff;
+                    mean += increment;
+                    variance = (1.0 - alpha) * (variance + diff * increment);
+                }
+            }
+        }
+        scores
+    }
+
+    /// 检测放量异常，按（股票代码, 市场, 日期, 字段）排序
+    pub fn detect(&self, data: &[TDXDayRecord]) -> Vec<VolumeAnomaly> {
+        let mut groups: HashMap<(&str, &str), Vec<&TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        let mut anomalies = Vec::new();
+        for records in groups.values_mut() {
+            records.sort_by_key(|r| r.date);
+            for &field in &self.fields {
+                let values: Vec<f64> = records.iter().map(|r| field.value(r)).collect();
+                let averages = rolling_mean(&values, self.average_window);
+                let scores = self.zscores(&values);
+                for i in 1..records.len() {
+                    let (zscore, average) = (scores[i], averages[i - 1]);
+                    // 历史不足时为NaN，不满足比较条件而被跳过
+                    let flagged = zscore >= self.threshold && average > 0.0;
+                    if !flagged {
+                        continue;
+                    }
+                    anomalies.push(VolumeAnomaly {
+                        symbol: records[i].symbol.clone(),
+                        market: records[i].market.clone(),
+                        date: records[i].date,
+                        field,
+                        value: values[i],
+                        average,
+                        ratio: values[i] / average,
+                        zscore,
+                    });
+                }
+            }
+        }
+
+        anomalies.sort_by(|a, b| {
+            (&a.symbol, &a.market, a.date, a.field).cmp(&(&b.symbol, &b.market, b.date, b.field))
+        });
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: usize, volume: u64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(day as i64),
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            volume,
+            amount: volume as f64 * 10.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    /// 成交量在1000附近小幅波动，第25根放量到5倍
+    fn create_data(symbol: &str) -> Vec<TDXDayRecord> {
+        (0..30)
+            .map(|day| {
+                let volume = if day == 25 {
+                    5000
+                } else {
+                    1000 + (day % 3) as u64 * 50
+                };
+                create_test_record(symbol, day, volume)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_spike() {
+        let mut data = create_data("600000");
+        data.reverse();
+        let anomalies = VolumeAnomalyDetector::new().detect(&data);
+        assert_eq!(anomalies.len(), 2);
+        assert_eq!(anomalies[0].field, AnomalyField::Volume);
+        assert_eq!(anomalies[1].field, AnomalyField::Amount);
+
+        let spike = &anomalies[0];
+        assert_eq!(spike.date, NaiveDate::from_ymd_opt(2024, 1, 26).unwrap());
+        assert_eq!(spike.value, 5000.0);
+        assert!(spike.ratio > 4.5 && spike.ratio < 5.0);
+        assert!(spike.zscore > 3.0);
+
+        // 只检测成交量、阈值极高时没有事件
+        let volume_only = VolumeAnomalyDetector::new()
+            .with_fields(vec![AnomalyField::Volume])
+            .detect(&data);
+        assert_eq!(volume_only.len(), 1);
+        assert!(VolumeAnomalyDetector::new()
+            .with_threshold(1e6)
+            .detect(&data)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_ewma() {
+        let mut data = create_data("600000");
+        data.extend(create_data("000001").into_iter().map(|mut r| {
+            r.market = "SZ".to_string();
+            r
+        }));
+        let anomalies = VolumeAnomalyDetector::new()
+            .with_method(AnomalyMethod::Ewma { span: 10 })
+            .with_fields(vec![AnomalyField::Volume])
+            .detect(&data);
+        assert_eq!(anomalies.len(), 2);
+        assert_eq!(anomalies[0].symbol, "000001");
+        assert_eq!(anomalies[1].symbol, "600000");
+        assert!(anomalies.iter().all(|a| a.value == 5000.0));
+    }
+
+    #[test]
+    fn test_zscores_warmup_and_flat() {
+        let detector =
+            VolumeAnomalyDetector::new().with_method(AnomalyMethod::ZScore { window: 3 });
+        let scores = detector.zscores(&[1.0, 2.0, 3.0, 10.0]);
+        assert!(scores[..3].iter().all(|s| s.is_nan()));
+        assert!(scores[3] > 3.0);
+
+        // 历史无波动时不判断
+        let flat = detector.zscores(&[5.0, 5.0, 5.0, 50.0]);
+        assert!(flat.iter().all(|s| s.is_nan()));
+        assert!(detector.zscores(&[]).is_empty());
+    }
+}
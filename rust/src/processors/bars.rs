@@ -0,0 +1,264 @@
+//! K线合成模块
+//!
+//! 将1分钟线合成为5/15/30/60分钟线或日线，按A股交易时段
+//! （9:30–11:30、13:00–15:00）切分，K线以结束时间标记：
+//! 60分钟线为10:30、11:30、14:00、15:00。
+//! 开盘集合竞价（9:30及之前）并入第一根K线，午间休市和收盘后的记录
+//! （如盘后固定价格交易）分别并入11:30和15:00的K线。
+
+use crate::parsers::{TDXDayRecord, TDXMinuteRecord};
+use chrono::{NaiveDate, NaiveTime, Timelike};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 上午开盘（自零点起的分钟数）
+const MORNING_OPEN: u32 = 9 * 60 + 30;
+/// 上午收盘
+const MORNING_CLOSE: u32 = 11 * 60 + 30;
+/// 下午开盘
+const AFTERNOON_OPEN: u32 = 13 * 60;
+/// 下午收盘
+const AFTERNOON_CLOSE: u32 = 15 * 60;
+/// 上午交易分钟数
+const MORNING_MINUTES: u32 = MORNING_CLOSE - MORNING_OPEN;
+/// 全天交易分钟数
+const SESSION_MINUTES: u32 = MORNING_MINUTES + (AFTERNOON_CLOSE - AFTERNOON_OPEN);
+
+/// K线周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Timeframe {
+    /// 5分钟
+    Min5,
+    /// 15分钟
+    Min15,
+    /// 30分钟
+    Min30,
+    /// 60分钟
+    Min60,
+    /// 日线
+    Daily,
+}
+
+impl Timeframe {
+    /// 周期包含的交易分钟数
+    pub fn minutes(self) -> u32 {
+        match self {
+            Timeframe::Min5 => 5,
+            Timeframe::Min15 => 15,
+            Timeframe::Min30 => 30,
+            Timeframe::Min60 => 60,
+            Timeframe::Daily => SESSION_MINUTES,
+        }
+    }
+}
+
+/// K线合成器
+#[derive(Debug, Clone)]
+pub struct BarBuilder {
+    /// 目标周期
+    timeframe: Timeframe,
+}
+
+impl Default for BarBuilder {
+    fn default() -> Self {
+        Self::new(Timeframe::Min5)
+    }
+}
+
+impl BarBuilder {
+    /// 创建新的K线合成器
+    pub fn new(timeframe: Timeframe) -> Self {
+        Self { timeframe }
+    }
+
+    /// 目标周期
+    pub fn timeframe(&self) -> Timeframe {
+        self.timeframe
+    }
+
+    /// 合成K线，结果按（股票代码, 市场, 结束时间）排序
+    ///
+    /// 输入为分钟线（时间为分钟结束时间），可以是任意顺序、多只股票混合。
+    pub fn build(&self, minutes: &[TDXMinuteRecord]) -> Vec<TDXMinuteRecord> {
+        let mut groups: BTreeMap<(&str, &str), Vec<&TDXMinuteRecord>> = BTreeMap::new();
+        for record in minutes {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        let groups: Vec<Vec<&TDXMinuteRecord>> = groups.into_values().collect();
+        groups
+            .into_par_iter()
+            .flat_map_iter(|mut records| {
+                records.sort_by_key(|r| r.datetime);
+                self.build_symbol(&records)
+            })
+            .collect()
+    }
+
+    /// 直接合成日线记录
+    pub fn build_daily(minutes: &[TDXMinuteRecord]) -> Vec<TDXDayRecord> {
+        BarBuilder::new(Timeframe::Daily)
+            .build(minutes)
+            .into_iter()
+            .map(|bar| TDXDayRecord {
+                date: bar.datetime.date(),
+                symbol: bar.symbol,
+                open: bar.open,
+                high: bar.high,
+                low: bar.low,
+                close: bar.close,
+                volume: bar.volume,
+                amount: bar.amount,
+                market: bar.market,
+            })
+            .collect()
+    }
+
+    /// 合成单只股票（已按时间排序）
+    fn build_symbol(&self, records: &[&TDXMinuteRecord]) -> Vec<TDXMinuteRecord> {
+        let mut bars: Vec<TDXMinuteRecord> = Vec::new();
+        let mut current_key: Option<(NaiveDate, u32)> = None;
+
+        for record in records {
+            let date = record.datetime.date();
+            let end = self.bucket_end(record.datetime.time());
+            match bars.last_mut() {
+                Some(bar) if current_key == Some((date, end)) => {
+                    bar.high = bar.high.max(record.high);
+                    bar.low = bar.low.min(record.low);
+                    bar.close = record.close;
+                    bar.volume += record.volume;
+                    bar.amount += record.amount;
+                }
+                _ => {
+                    let mut bar = (*record).clone();
+                    bar.datetime = date.and_time(ordinal_to_time(end));
+                    bars.push(bar);
+                    current_key = Some((date, end));
+                }
+            }
+        }
+        bars
+    }
+
+    /// 所在K线的结束位置（交易分钟序号）
+    fn bucket_end(&self, time: NaiveTime) -> u32 {
+        let span = self.timeframe.minutes();
+        let ordinal = session_ordinal(time);
+        (ordinal.div_ceil(span) * span).min(SESSION_MINUTES)
+    }
+}
+
+/// 分钟结束时间对应的交易分钟序号（9:31为1，15:00为240）
+fn session_ordinal(time: NaiveTime) -> u32 {
+    let minute = time.hour() * 60 + time.minute();
+    if minute <= MORNING_OPEN {
+        // 开盘集合竞价
+        1
+    } else if minute <= MORNING_CLOSE {
+        minute - MORNING_OPEN
+    } else if minute <= AFTERNOON_OPEN {
+        // 午间休市
+        MORNING_MINUTES
+    } else if minute <= AFTERNOON_CLOSE {
+        MORNING_MINUTES + minute - AFTERNOON_OPEN
+    } else {
+        // 收盘后
+        SESSION_MINUTES
+    }
+}
+
+/// 交易分钟序号转换为时钟时间
+fn ordinal_to_time(ordinal: u32) -> NaiveTime {
+    let minute = if ordinal <= MORNING_MINUTES {
+        MORNING_OPEN + ordinal
+    } else {
+        AFTERNOON_OPEN + ordinal - MORNING_MINUTES
+    };
+    NaiveTime::from_hms_opt(minute / 60, minute % 60, 0).unwrap_or(NaiveTime::MIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minute(hour: u32, min: u32, close: f64) -> TDXMinuteRecord {
+        TDXMinuteRecord {
+            datetime: NaiveDate::from_ymd_opt(2024, 1, 2)
+                .unwrap()
+                .and_hms_opt(hour, min, 0)
+                .unwrap(),
+            symbol: "600000".to_string(),
+            open: close,
+            high: close + 0.05,
+            low: close - 0.05,
+            close,
+            volume: 100,
+            amount: close * 100.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_session_buckets() {
+        let hourly = BarBuilder::new(Timeframe::Min60);
+        assert_eq!(
+            ordinal_to_time(hourly.bucket_end(time(9, 31))),
+            time(10, 30)
+        );
+        assert_eq!(
+            ordinal_to_time(hourly.bucket_end(time(11, 30))),
+            time(11, 30)
+        );
+        assert_eq!(ordinal_to_time(hourly.bucket_end(time(13, 1))), time(14, 0));
+        assert_eq!(ordinal_to_time(hourly.bucket_end(time(15, 0))), time(15, 0));
+
+        let five = BarBuilder::new(Timeframe::Min5);
+        // 集合竞价并入第一根K线
+        assert_eq!(ordinal_to_time(five.bucket_end(time(9, 25))), time(9, 35));
+        assert_eq!(ordinal_to_time(five.bucket_end(time(13, 3))), time(13, 5));
+    }
+
+    #[test]
+    fn test_build_five_minute_bars() {
+        let minutes = vec![
+            minute(9, 32, 10.1),
+            minute(9, 25, 10.0),
+            minute(9, 31, 10.05),
+            minute(9, 36, 10.2),
+        ];
+        let bars = BarBuilder::new(Timeframe::Min5).build(&minutes);
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].datetime.time(), time(9, 35));
+        assert_eq!(bars[0].open, 10.0);
+        assert_eq!(bars[0].close, 10.1);
+        assert_eq!(bars[0].high, 10.15);
+        assert_eq!(bars[0].volume, 300);
+        assert_eq!(bars[1].datetime.time(), time(9, 40));
+    }
+
+    #[test]
+    fn test_build_daily() {
+        let minutes = vec![
+            minute(9, 31, 10.0),
+            minute(11, 30, 10.5),
+            minute(13, 1, 9.8),
+            minute(15, 0, 10.2),
+        ];
+        let daily = BarBuilder::build_daily(&minutes);
+        assert_eq!(daily.len(), 1);
+        assert_eq!(daily[0].open, 10.0);
+        assert_eq!(daily[0].close, 10.2);
+        assert_eq!(daily[0].high, 10.55);
+        assert_eq!(daily[0].low, 9.75);
+        assert_eq!(daily[0].volume, 400);
+    }
+}
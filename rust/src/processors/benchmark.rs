@@ -0,0 +1,126 @@
+//! 基准指数模块
+//!
+//! 将上证指数、深证成指等指数序列作为基准，按个股日期对齐（取当日或之前最近的收盘点位），
+//! 供指标计算器计算相对强弱线、滚动贝塔和超额收益等跨序列指标。
+
+use crate::parsers::{TDXDayParser, TDXDayRecord};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 上证指数
+pub const SSE_COMPOSITE: (&str, &str) = ("000001", "SH");
+/// 沪深300
+pub const CSI_300: (&str, &str) = ("000300", "SH");
+/// 深证成指
+pub const SZSE_COMPONENT: (&str, &str) = ("399001", "SZ");
+/// 创业板指
+pub const CHINEXT_INDEX: (&str, &str) = ("399006", "SZ");
+
+/// 基准指数序列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Benchmark {
+    /// 指数代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 日期到收盘点位
+    closes: BTreeMap<NaiveDate, f64>,
+}
+
+impl Benchmark {
+    /// 从记录集中提取指定指数
+    pub fn from_records(symbol: &str, market: &str, records: &[TDXDayRecord]) -> Result<Self> {
+        let closes: BTreeMap<NaiveDate, f64> = records
+            .iter()
+            .filter(|r| r.symbol == symbol && r.market.eq_ignore_ascii_case(market))
+            .map(|r| (r.date, r.close))
+            .collect();
+
+        if closes.is_empty() {
+            return Err(anyhow::anyhow!("基准指数无数据: {}.{}", symbol, market));
+        }
+
+        Ok(Self {
+            symbol: symbol.to_string(),
+            market: market.to_uppercase(),
+            closes,
+        })
+    }
+
+    /// 从通达信数据目录加载指数日线
+    pub fn load(parser: &TDXDayParser, symbol: &str, market: &str) -> Result<Self> {
+        let records = parser
+            .get_data_by_symbol(symbol, market)
+            .with_context(|| format!("无法加载基准指数: {}.{}", symbol, market))?;
+        Self::from_records(symbol, market, &records)
+    }
+
+    /// 数据天数
+    pub fn len(&self) -> usize {
+        self.closes.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.closes.is_empty()
+    }
+
+    /// 指定日期（或之前最近交易日）的收盘点位
+    pub fn close_on(&self, date: NaiveDate) -> Option<f64> {
+        self.closes
+            .range(..=date)
+            .next_back()
+            .map(|(_, &close)| close)
+    }
+
+    /// 按给定日期对齐收盘点位，早于基准首日的日期为NaN
+    pub fn align(&self, dates: &[NaiveDate]) -> Vec<f64> {
+        dates
+            .iter()
+            .map(|&date| self.close_on(date).unwrap_or(f64::NAN))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_align_as_of() {
+        let records = vec![
+            create_test_record("000001", 2, 2950.0),
+            create_test_record("000001", 4, 2960.0),
+            create_test_record("600000", 3, 10.0),
+        ];
+        let (symbol, market) = SSE_COMPOSITE;
+        let benchmark = Benchmark::from_records(symbol, market, &records).unwrap();
+        assert_eq!(benchmark.len(), 2);
+
+        let dates: Vec<NaiveDate> = [1, 2, 3, 4]
+            .iter()
+            .map(|&d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap())
+            .collect();
+        let aligned = benchmark.align(&dates);
+        assert!(aligned[0].is_nan());
+        assert_eq!(&aligned[1..], &[2950.0, 2950.0, 2960.0]);
+
+        assert!(Benchmark::from_records("399001", "SZ", &records).is_err());
+    }
+}
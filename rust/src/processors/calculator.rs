@@ -1,17 +1,50 @@
 //! 技术指标计算模块
 
-use crate::parsers::TDXDayRecord;
-use crate::processors::DataCleaner;
+use crate::parsers::{Bar, TDXDayRecord};
+use crate::processors::benchmark::Benchmark;
+use crate::processors::columnar::ColumnarFrame;
+use crate::processors::extremes;
+use crate::processors::field::Field;
+use crate::processors::kernels;
+use crate::processors::rolling::{RollingEngine, RollingWindow, WindowHandle};
+use crate::processors::suspension::{SuspensionIndex, SuspensionPeriod};
+use crate::processors::vwap;
+use crate::reference::SecurityMaster;
 use anyhow::Result;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
+
+/// 布林带周期
+const BOLLINGER_PERIOD: usize = 20;
+/// 52周约250个交易日
+const YEAR_WINDOW: usize = 250;
 
 /// 技术指标计算器
 #[derive(Debug)]
 pub struct IndicatorCalculator {
     /// 计算窗口大小
     window_sizes: Vec<usize>,
+    /// 停牌期间，复牌后指标重新预热
+    suspensions: SuspensionIndex,
+    /// 基准指数
+    benchmark: Option<Benchmark>,
+    /// 贝塔计算窗口
+    beta_window: usize,
+    /// 滚动回归贝塔/阿尔法窗口
+    regression_windows: Vec<usize>,
+    /// 证券主数据（提供股本）
+    security_master: Option<SecurityMaster>,
+    /// N日VWAP/TWAP窗口
+    average_price_windows: Vec<usize>,
+    /// 滚动最大回撤窗口
+    drawdown_windows: Vec<usize>,
+}
+
+impl Default for IndicatorCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl IndicatorCalculator {
@@ -19,6 +52,13 @@ impl IndicatorCalculator {
     pub fn new() -> Self {
         Self {
             window_sizes: vec![5, 10, 20, 60],
+            suspensions: SuspensionIndex::default(),
+            benchmark: None,
+            beta_window: 60,
+            regression_windows: vec![20, 60, 250],
+            security_master: None,
+            average_price_windows: vec![5, 20],
+            drawdown_windows: vec![20, 60, 250],
         }
     }
 
@@ -28,89 +68,345 @@ impl IndicatorCalculator {
         self
     }
 
-    /// 计算所有指标
-    pub fn calculate_all_indicators(
+    /// 设置基准指数，启用相对强弱线、滚动贝塔和超额收益
+    pub fn with_benchmark(mut self, benchmark: Benchmark) -> Self {
+        self.benchmark = Some(benchmark);
+        self
+    }
+
+    /// 设置贝塔计算窗口（收益率个数）
+    pub fn with_beta_window(mut self, window: usize) -> Self {
+        self.beta_window = window;
+        self
+    }
+
+    /// 设置滚动回归窗口（收益率个数），支持20、60、250
+    pub fn with_regression_windows(mut self, windows: Vec<usize>) -> Self {
+        self.regression_windows = windows;
+        self
+    }
+
+    /// 设置N日VWAP/TWAP窗口，支持5、20
+    pub fn with_average_price_windows(mut self, windows: Vec<usize>) -> Self {
+        self.average_price_windows = windows;
+        self
+    }
+
+    /// 设置滚动最大回撤窗口，支持20、60、250
+    pub fn with_drawdown_windows(mut self, windows: Vec<usize>) -> Self {
+        self.drawdown_windows = windows;
+        self
+    }
+
+    /// 设置证券主数据，按K线日期生效的股本计算换手率和流通市值
+    pub fn with_security_master(mut self, master: SecurityMaster) -> Self {
+        self.security_master = Some(master);
+        self
+    }
+
+    /// 设置停牌期间
+    ///
+    /// 序列在复牌处断开，移动平均等窗口不跨越停牌，复牌后重新预热。
+    pub fn with_suspensions(mut self, periods: Vec<SuspensionPeriod>) -> Self {
+        self.suspensions = SuspensionIndex::new(periods);
+        self
+    }
+
+    /// 配置摘要，每项一行，用于执行计划展示
+    pub fn describe(&self) -> Vec<String> {
+        let optional = |configured: bool| if configured { "已设置" } else { "未设置" };
+        vec![
+            format!("均线窗口 {:?}", self.window_sizes),
+            format!(
+                "基准指数 {}，贝塔窗口 {}，回归窗口 {:?}",
+                optional(self.benchmark.is_some()),
+                self.beta_window,
+                self.regression_windows
+            ),
+            format!("VWAP/TWAP窗口 {:?}", self.average_price_windows),
+            format!("回撤窗口 {:?}", self.drawdown_windows),
+            format!("证券主数据 {}", optional(self.security_master.is_some())),
+        ]
+    }
+
+    /// 计算所有指标（输出顺序与输入一致）
+    ///
+    /// 输入可以是日线或分钟线，移动平均等窗口按K线根数计算。
+    pub fn calculate_all_indicators<B: Bar + Clone>(
         &self,
-        data: &[TDXDayRecord],
-    ) -> Result<Vec<EnhancedDayRecord>> {
-        // 按股票分组
-        use std::collections::HashMap;
-        let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+        data: &[B],
+    ) -> Result<Vec<EnhancedDayRecord<B>>> {
+        let frame = ColumnarFrame::from_records(data);
+        let indicators = self.calculate_columnar(&frame)?;
+
+        Ok(data
+            .iter()
+            .zip(indicators)
+            .map(|(record, indicator_values)| {
+                EnhancedDayRecord::from_record(record, indicator_values)
+            })
+            .collect())
+    }
 
-        for (i, record) in data.iter().enumerate() {
-            groups
-                .entry(record.symbol.clone())
-                .or_insert_with(Vec::new)
-                .push(i);
+    /// 基于列式数据并行计算指标，返回值与 `frame` 的行一一对应
+    pub fn calculate_columnar(&self, frame: &ColumnarFrame) -> Result<Vec<IndicatorValues>> {
+        let closes = frame.column(&Field::Close)?;
+        let highs = frame.column(&Field::High)?;
+        let lows = frame.column(&Field::Low)?;
+        let volumes = frame.column(&Field::Volume)?;
+
+        // 按股票（及停牌分段）并行计算，组内行已按时间排序
+        let results: Result<Vec<(Vec<usize>, Vec<IndicatorValues>)>> = frame
+            .symbol_groups()
+            .into_iter()
+            .flat_map(|(id, rows)| {
+                self.split_at_suspensions(frame, id, rows)
+                    .into_iter()
+                    .map(move |rows| (id, rows))
+            })
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(|(id, rows)| {
+                let benchmark = self.benchmark.as_ref().map(|benchmark| {
+                    let dates: Vec<_> = rows.iter().map(|&row| frame.date(row)).collect();
+                    benchmark.align(&dates)
+                });
+                let mut indicators = self.calculate_symbol_indicators(
+                    &closes.gather(&rows),
+                    &highs.gather(&rows),
+                    &lows.gather(&rows),
+                    &volumes.gather(&rows),
+                    benchmark.as_deref(),
+                )?;
+                self.fill_average_prices(frame, &rows, &mut indicators)?;
+                self.fill_extremes(
+                    &closes.gather(&rows),
+                    &highs.gather(&rows),
+                    &lows.gather(&rows),
+                    &mut indicators,
+                );
+                if let Some(master) = &self.security_master {
+                    let (symbol, market) = (frame.symbol(id), frame.market(id));
+                    for (indicator_values, &row) in indicators.iter_mut().zip(&rows) {
+                        if let Some(capital) = master.shares_on(symbol, market, frame.date(row)) {
+                            indicator_values.turnover_rate =
+                                capital.turnover_rate(volumes.get(row));
+                            indicator_values.float_market_cap =
+                                Some(capital.float_market_cap(closes.get(row)));
+                        }
+                    }
+                }
+                Ok((rows, indicators))
+            })
+            .collect();
+
+        let mut output = vec![IndicatorValues::default(); frame.len()];
+        for (rows, indicators) in results? {
+            for (row, indicator_values) in rows.into_iter().zip(indicators) {
+                output[row] = indicator_values;
+            }
         }
 
-        // 为每只股票计算指标
-        let mut enhanced_records = Vec::with_capacity(data.len());
+        Ok(output)
+    }
 
-        for (symbol, indices) in groups {
-            // 按日期排序
-            let mut sorted_indices = indices.clone();
-            sorted_indices.sort_by(|&i, &j| data[i].date.cmp(&data[j].date));
+    /// 填充单根K线VWAP与N日VWAP/TWAP（`rows` 为同一股票按时间排序的行）
+    fn fill_average_prices(
+        &self,
+        frame: &ColumnarFrame,
+        rows: &[usize],
+        indicators: &mut [IndicatorValues],
+    ) -> Result<()> {
+        let column = |field: &Field| -> Result<Vec<f64>> { Ok(frame.column(field)?.gather(rows)) };
+        let (opens, highs, lows, closes) = (
+            column(&Field::Open)?,
+            column(&Field::High)?,
+            column(&Field::Low)?,
+            column(&Field::Close)?,
+        );
+        let (volumes, amounts) = (column(&Field::Volume)?, column(&Field::Amount)?);
+        let typical: Vec<f64> = (0..rows.len())
+            .map(|i| vwap::typical_price(opens[i], highs[i], lows[i], closes[i]))
+            .collect();
 
-            // 提取该股票的时间序列数据
-            let time_series: Vec<&TDXDayRecord> =
-                sorted_indices.iter().map(|&idx| &data[idx]).collect();
+        for (i, indicator_values) in indicators.iter_mut().enumerate() {
+            indicator_values.vwap = vwap::bar_vwap(amounts[i], volumes[i], lows[i], highs[i]);
+        }
+        for &window in &self.average_price_windows {
+            let vwaps = vwap::rolling_vwap(&amounts, &volumes, window);
+            let twaps = vwap::rolling_twap(&typical, window);
+            for (i, indicator_values) in indicators.iter_mut().enumerate() {
+                let at = |series: &[f64]| Some(series[i]).filter(|value| !value.is_nan());
+                let values = (at(&vwaps), at(&twaps));
+                match window {
+                    5 => (indicator_values.vwap_5, indicator_values.twap_5) = values,
+                    20 => (indicator_values.vwap_20, indicator_values.twap_20) = values,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
 
-            // 计算指标
-            let calculated_indicators = self.calculate_symbol_indicators(&time_series)?;
+    /// 填充52周高低点距离、距创新高天数与滚动最大回撤（单只股票按时间排序的序列）
+    fn fill_extremes(
+        &self,
+        closes: &[f64],
+        highs: &[f64],
+        lows: &[f64],
+        indicators: &mut [IndicatorValues],
+    ) {
+        let year_highs = extremes::rolling_max_index(highs, YEAR_WINDOW);
+        let year_lows = extremes::rolling_min_index(lows, YEAR_WINDOW);
+        for (i, indicator_values) in indicators.iter_mut().enumerate() {
+            if let Some(high) = year_highs[i] {
+                indicator_values.dist_high_52w =
+                    (highs[high] > 0.0).then(|| closes[i] / highs[high] - 1.0);
+                indicator_values.days_since_high_52w = Some((i - high) as f64);
+            }
+            if let Some(low) = year_lows[i] {
+                indicator_values.dist_low_52w =
+                    (lows[low] > 0.0).then(|| closes[i] / lows[low] - 1.0);
+            }
+        }
 
-            // 合并结果
-            for (i, record) in time_series.iter().enumerate() {
-                if let Some(Some(indicator_values)) = calculated_indicators.get(i).cloned() {
-                    let enhanced = EnhancedDayRecord::from_record(record, indicator_values);
-                    enhanced_records.push(enhanced);
+        for &window in &self.drawdown_windows {
+            let drawdowns = extremes::rolling_max_drawdown(closes, window);
+            for (indicator_values, &drawdown) in indicators.iter_mut().zip(&drawdowns) {
+                let value = Some(drawdown).filter(|value| !value.is_nan());
+                match window {
+                    20 => indicator_values.max_drawdown_20 = value,
+                    60 => indicator_values.max_drawdown_60 = value,
+                    250 => indicator_values.max_drawdown_250 = value,
+                    _ => {}
                 }
             }
         }
+    }
 
-        Ok(enhanced_records)
+    /// 在复牌处拆分单只股票的行
+    fn split_at_suspensions(
+        &self,
+        frame: &ColumnarFrame,
+        id: u32,
+        rows: Vec<usize>,
+    ) -> Vec<Vec<usize>> {
+        if self.suspensions.is_empty() {
+            return vec![rows];
+        }
+
+        let (symbol, market) = (frame.symbol(id), frame.market(id));
+        let mut segments: Vec<Vec<usize>> = Vec::new();
+        for row in rows {
+            let resumed = segments.last().and_then(|s| s.last()).is_some_and(|&prev| {
+                self.suspensions
+                    .separates(symbol, market, frame.date(prev), frame.date(row))
+            });
+            match segments.last_mut() {
+                Some(segment) if !resumed => segment.push(row),
+                _ => segments.push(vec![row]),
+            }
+        }
+        segments
     }
 
-    /// 计算单个股票的指标
+    /// 计算单个股票的指标（各序列已按时间排序）
     fn calculate_symbol_indicators(
         &self,
-        time_series: &[&TDXDayRecord],
-    ) -> Result<Vec<Option<IndicatorValues>>> {
-        let mut indicators = Vec::with_capacity(time_series.len());
-
-        // 预计算价格序列
-        let closes: Vec<f64> = time_series.iter().map(|r| r.close).collect();
-        let highs: Vec<f64> = time_series.iter().map(|r| r.high).collect();
-        let lows: Vec<f64> = time_series.iter().map(|r| r.low).collect();
-        let volumes: Vec<f64> = time_series.iter().map(|r| r.volume as f64).collect();
-        let amounts: Vec<f64> = time_series.iter().map(|r| r.amount).collect();
-
-        for i in 0..time_series.len() {
+        closes: &[f64],
+        highs: &[f64],
+        lows: &[f64],
+        volumes: &[f64],
+        benchmark: Option<&[f64]>,
+    ) -> Result<Vec<IndicatorValues>> {
+        let mut indicators = Vec::with_capacity(closes.len());
+
+        // 基准相关序列：个股与基准的日收益率、滚动贝塔
+        let returns = |series: &[f64]| -> Vec<f64> {
+            (0..series.len())
+                .map(|i| match i {
+                    0 => f64::NAN,
+                    _ => series[i] / series[i - 1] - 1.0,
+                })
+                .collect()
+        };
+        let relative = benchmark.map(|benchmark| {
+            let (asset_returns, benchmark_returns) = (returns(closes), returns(benchmark));
+            // 首个收益率为NaN，回归窗口从第二根K线开始
+            let regression = |window: usize| {
+                let mut betas = vec![f64::NAN; closes.len()];
+                let mut alphas = vec![f64::NAN; closes.len()];
+                if closes.len() > 1 {
+                    let (beta, alpha) = kernels::rolling_regression(
+                        &asset_returns[1..],
+                        &benchmark_returns[1..],
+                        window,
+                    );
+                    betas[1..].copy_from_slice(&beta);
+                    alphas[1..].copy_from_slice(&alpha);
+                }
+                (betas, alphas)
+            };
+            let betas = regression(self.beta_window).0;
+            let regressions: Vec<_> = self
+                .regression_windows
+                .iter()
+                .map(|&window| (window, regression(window)))
+                .collect();
+            (
+                benchmark,
+                asset_returns,
+                benchmark_returns,
+                betas,
+                regressions,
+            )
+        });
+
+        // 移动平均线、成交量均线与布林带订阅共享的滚动窗口（MA20与布林带共用同一窗口）
+        let mut engine = RollingEngine::new();
+        let ma_windows: Vec<(usize, WindowHandle)> = self
+            .window_sizes
+            .iter()
+            .map(|&window_size| (window_size, engine.subscribe(Field::Close, window_size)))
+            .collect();
+        let volume_window = self
+            .window_sizes
+            .contains(&5)
+            .then(|| engine.subscribe(Field::Volume, 5));
+        let bollinger_window = engine.subscribe(Field::Close, BOLLINGER_PERIOD);
+
+        // MACD：DIF（EMA12 - EMA26）与信号线（DIF的9日EMA）按整段序列各递推一次
+        let difs: Vec<f64> = kernels::ema(closes, 12)
+            .iter()
+            .zip(kernels::ema(closes, 26))
+            .map(|(fast, slow)| fast - slow)
+            .collect();
+        let signals = kernels::ema(&difs, 9);
+
+        for i in 0..closes.len() {
             let mut indicator_values = IndicatorValues::default();
+            let at = |series: &[f64]| Some(series[i]).filter(|value| !value.is_nan());
+            engine.push_with(|field| match field {
+                Field::Volume => volumes[i],
+                _ => closes[i],
+            });
 
             // 计算移动平均线
-            for &window_size in &self.window_sizes {
-                if i >= window_size - 1 {
-                    let ma = self.calculate_ma(&closes[i - window_size + 1..=i]);
-                    match window_size {
-                        5 => indicator_values.ma5 = Some(ma),
-                        10 => indicator_values.ma10 = Some(ma),
-                        20 => indicator_values.ma20 = Some(ma),
-                        60 => indicator_values.ma60 = Some(ma),
-                        _ => {}
-                    }
-                }
-
-                // 计算成交量移动平均
-                if i >= window_size - 1 {
-                    let vol_ma = self.calculate_ma(&volumes[i - window_size + 1..=i]);
-                    match window_size {
-                        5 => indicator_values.volume_ma5 = Some(vol_ma),
-                        _ => {}
-                    }
+            for &(window_size, handle) in &ma_windows {
+                let ma = engine.window(handle).mean();
+                match window_size {
+                    5 => indicator_values.ma5 = ma,
+                    10 => indicator_values.ma10 = ma,
+                    20 => indicator_values.ma20 = ma,
+                    60 => indicator_values.ma60 = ma,
+                    _ => {}
                 }
             }
 
+            // 计算成交量移动平均
+            indicator_values.volume_ma5 =
+                volume_window.and_then(|handle| engine.window(handle).mean());
+
             // 计算技术指标
             if i >= 1 {
                 indicator_values.change_percent =
@@ -123,27 +419,43 @@ impl IndicatorCalculator {
             }
 
             if i >= 25 {
-                indicator_values.macd = self.calculate_macd(&closes[i - 25..=i]);
+                indicator_values.macd = Some(MACD {
+                    dif: difs[i],
+                    signal: signals[i],
+                    histogram: difs[i] - signals[i],
+                });
             }
 
-            if i >= 19 {
-                indicator_values.bollinger = self.calculate_bollinger_bands(&closes[i - 19..=i]);
+            indicator_values.bollinger = Self::bollinger_bands(engine.window(bollinger_window));
+
+            if let Some((benchmark, asset_returns, benchmark_returns, betas, regressions)) =
+                &relative
+            {
+                indicator_values.rs_line = Some(closes[i] / benchmark[i])
+                    .filter(|value| value.is_finite() && benchmark[i] > 0.0);
+                indicator_values.excess_return =
+                    at(asset_returns).and_then(|r| Some((r - at(benchmark_returns)?) * 100.0));
+                indicator_values.beta = at(betas);
+
+                for (window, (betas, alphas)) in regressions {
+                    let (beta, alpha) = (at(betas), at(alphas));
+                    match window {
+                        20 => (indicator_values.beta_20, indicator_values.alpha_20) = (beta, alpha),
+                        60 => (indicator_values.beta_60, indicator_values.alpha_60) = (beta, alpha),
+                        250 => {
+                            (indicator_values.beta_250, indicator_values.alpha_250) = (beta, alpha)
+                        }
+                        _ => {}
+                    }
+                }
             }
 
-            indicators.push(Some(indicator_values));
+            indicators.push(indicator_values);
         }
 
         Ok(indicators)
     }
 
-    /// 计算移动平均
-    fn calculate_ma(&self, prices: &[f64]) -> f64 {
-        if prices.is_empty() {
-            return 0.0;
-        }
-        prices.iter().sum::<f64>() / prices.len() as f64
-    }
-
     /// 计算RSI相对强弱指标
     fn calculate_rsi(&self, closes: &[f64]) -> f64 {
         if closes.len() < 2 {
@@ -175,70 +487,9 @@ impl IndicatorCalculator {
         100.0 - (100.0 / (1.0 + rs))
     }
 
-    /// 计算MACD指标
-    fn calculate_macd(&self, closes: &[f64]) -> Option<MACD> {
-        if closes.len() < 26 {
-            return None;
-        }
-
-        let ema12 = self.calculate_ema(&closes, 12);
-        let ema26 = self.calculate_ema(&closes, 26);
-
-        let dif = ema12 - ema26;
-
-        // 计算信号线（9日EMA）
-        let mut dif_values = Vec::new();
-        for i in 0..closes.len() {
-            let current_closes = &closes[i..];
-            if current_closes.len() >= 12 {
-                let current_ema12 = self.calculate_ema(current_closes, 12);
-                if current_closes.len() >= 26 {
-                    let current_ema26 = self.calculate_ema(current_closes, 26);
-                    dif_values.push(current_ema12 - current_ema26);
-                }
-            }
-        }
-
-        let signal = if dif_values.len() >= 9 {
-            self.calculate_ema(&dif_values, 9)
-        } else {
-            0.0
-        };
-
-        Some(MACD {
-            dif,
-            signal,
-            histogram: dif - signal,
-        })
-    }
-
-    /// 计算指数移动平均
-    fn calculate_ema(&self, values: &[f64], period: usize) -> f64 {
-        if values.is_empty() {
-            return 0.0;
-        }
-
-        let multiplier = 2.0 / (period as f64 + 1.0);
-        let mut ema = values[0];
-
-        for &value in &values[1..] {
-            ema = value * multiplier + ema * (1.0 - multiplier);
-        }
-
-        ema
-    }
-
-    /// 计算布林带
-    fn calculate_bollinger_bands(&self, closes: &[f64]) -> Option<BollingerBands> {
-        if closes.len() < 20 {
-            return None;
-        }
-
-        let ma = self.calculate_ma(closes);
-        let variance: f64 =
-            closes.iter().map(|price| (price - ma).powi(2)).sum::<f64>() / closes.len() as f64;
-        let std_dev = variance.sqrt();
-
+    /// 由20日窗口计算布林带（中轨±2倍总体标准差）
+    fn bollinger_bands(window: &RollingWindow) -> Option<BollingerBands> {
+        let (ma, std_dev) = window.mean().zip(window.std())?;
         Some(BollingerBands {
             upper: ma + 2.0 * std_dev,
             middle: ma,
@@ -247,111 +498,146 @@ impl IndicatorCalculator {
         })
     }
 
-    /// 并行计算指标（多股票）
-    pub fn calculate_parallel(&self, data: &[TDXDayRecord]) -> Result<Vec<EnhancedDayRecord>> {
-        // 按股票分组进行并行处理
-        use std::collections::HashMap;
-        let mut symbol_groups: HashMap<String, Vec<TDXDayRecord>> = HashMap::new();
-
-        for record in data {
-            symbol_groups
-                .entry(record.symbol.clone())
-                .or_insert_with(Vec::new)
-                .push(record.clone());
-        }
-
-        let mut all_records = Vec::new();
-
-        // 并行处理每个股票的数据
-        let results: Result<Vec<_>> = symbol_groups
-            .into_par_iter()
-            .map(|(symbol, records)| {
-                // 按日期排序
-                let mut sorted_records = records;
-                sorted_records.sort_by(|a, b| a.date.cmp(&b.date));
-
-                // 计算指标
-                let time_series: Vec<&TDXDayRecord> = sorted_records.iter().collect();
-                let indicators = self.calculate_symbol_indicators(&time_series)?;
-
-                // 组合结果
-                let mut enhanced_records = Vec::with_capacity(sorted_records.len());
-                for (i, record) in sorted_records.into_iter().enumerate() {
-                    if let Some(Some(indicator_values)) = indicators.get(i).cloned() {
-                        let enhanced = EnhancedDayRecord::from_record(&record, indicator_values);
-                        enhanced_records.push(enhanced);
-                    }
-                }
-
-                Ok((symbol, enhanced_records))
-            })
-            .collect();
-
-        // 合并所有结果
-        for (_, records) in results? {
-            all_records.extend(records);
-        }
-
-        // 按日期和股票重新排序
-        all_records.sort_by(|a, b| a.date().cmp(&b.date()).then(a.symbol().cmp(b.symbol())));
+    /// 并行计算指标（多股票），结果按时间和股票排序
+    pub fn calculate_parallel<B: Bar + Clone>(
+        &self,
+        data: &[B],
+    ) -> Result<Vec<EnhancedDayRecord<B>>> {
+        let mut all_records = self.calculate_all_indicators(data)?;
+
+        // 按时间和股票重新排序
+        all_records.sort_by(|a, b| {
+            a.base_record
+                .timestamp()
+                .cmp(&b.base_record.timestamp())
+                .then(a.symbol().cmp(b.symbol()))
+        });
 
         Ok(all_records)
     }
 }
 
-/// 增强的日线记录（包含技术指标）
-#[derive(Debug, Clone)]
-pub struct EnhancedDayRecord {
+/// 增强的K线记录（包含技术指标），默认基于日线
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnhancedDayRecord<B = TDXDayRecord> {
     /// 基础数据
-    pub base_record: TDXDayRecord,
+    pub base_record: B,
     /// 技术指标值
     pub indicators: IndicatorValues,
 }
 
-impl EnhancedDayRecord {
+impl<B: Bar + Clone> EnhancedDayRecord<B> {
     /// 从基础记录创建增强记录
-    pub fn from_record(record: &TDXDayRecord, indicators: IndicatorValues) -> Self {
+    pub fn from_record(record: &B, indicators: IndicatorValues) -> Self {
         Self {
             base_record: record.clone(),
             indicators,
         }
     }
+}
 
+impl<B: Bar> EnhancedDayRecord<B> {
     /// 获取基础字段
     pub fn date(&self) -> chrono::NaiveDate {
-        self.base_record.date
+        self.base_record.date()
     }
 
     pub fn symbol(&self) -> &str {
-        &self.base_record.symbol
+        self.base_record.symbol()
     }
 
     pub fn open(&self) -> f64 {
-        self.base_record.open
+        self.base_record.open()
     }
 
     pub fn high(&self) -> f64 {
-        self.base_record.high
+        self.base_record.high()
     }
 
     pub fn low(&self) -> f64 {
-        self.base_record.low
+        self.base_record.low()
     }
 
     pub fn close(&self) -> f64 {
-        self.base_record.close
+        self.base_record.close()
     }
 
     pub fn volume(&self) -> u64 {
-        self.base_record.volume
+        self.base_record.volume()
     }
 
     pub fn amount(&self) -> f64 {
-        self.base_record.amount
+        self.base_record.amount()
     }
 
     pub fn market(&self) -> &str {
-        &self.base_record.market
+        self.base_record.market()
+    }
+}
+
+impl<B: Bar> Bar for EnhancedDayRecord<B> {
+    fn symbol(&self) -> &str {
+        self.base_record.symbol()
+    }
+
+    fn market(&self) -> &str {
+        self.base_record.market()
+    }
+
+    fn timestamp(&self) -> chrono::NaiveDateTime {
+        self.base_record.timestamp()
+    }
+
+    fn open(&self) -> f64 {
+        self.base_record.open()
+    }
+
+    fn high(&self) -> f64 {
+        self.base_record.high()
+    }
+
+    fn low(&self) -> f64 {
+        self.base_record.low()
+    }
+
+    fn close(&self) -> f64 {
+        self.base_record.close()
+    }
+
+    fn volume(&self) -> u64 {
+        self.base_record.volume()
+    }
+
+    fn amount(&self) -> f64 {
+        self.base_record.amount()
+    }
+
+    fn indicator(&self, name: &str) -> Option<f64> {
+        self.indicators.get(name)
+    }
+
+    fn set_open(&mut self, value: f64) {
+        self.base_record.set_open(value);
+    }
+
+    fn set_high(&mut self, value: f64) {
+        self.base_record.set_high(value);
+    }
+
+    fn set_low(&mut self, value: f64) {
+        self.base_record.set_low(value);
+    }
+
+    fn set_close(&mut self, value: f64) {
+        self.base_record.set_close(value);
+    }
+
+    fn set_volume(&mut self, value: u64) {
+        self.base_record.set_volume(value);
+    }
+
+    fn set_amount(&mut self, value: f64) {
+        self.base_record.set_amount(value);
     }
 }
 
@@ -382,7 +668,7 @@ pub enum TechnicalIndicator {
 }
 
 /// 技术指标值集合
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct IndicatorValues {
     /// 5日移动平均
     pub ma5: Option<f64>,
@@ -404,12 +690,123 @@ pub struct IndicatorValues {
     pub macd: Option<MACD>,
     /// 布林带
     pub bollinger: Option<BollingerBands>,
+    /// 相对强弱线（收盘价/基准点位）
+    #[serde(default)]
+    pub rs_line: Option<f64>,
+    /// 相对基准的滚动贝塔
+    #[serde(default)]
+    pub beta: Option<f64>,
+    /// 相对基准的超额收益（%）
+    #[serde(default)]
+    pub excess_return: Option<f64>,
+    /// 20日滚动回归贝塔
+    #[serde(default)]
+    pub beta_20: Option<f64>,
+    /// 60日滚动回归贝塔
+    #[serde(default)]
+    pub beta_60: Option<f64>,
+    /// 250日滚动回归贝塔
+    #[serde(default)]
+    pub beta_250: Option<f64>,
+    /// 20日滚动回归阿尔法（日收益率截距）
+    #[serde(default)]
+    pub alpha_20: Option<f64>,
+    /// 60日滚动回归阿尔法（日收益率截距）
+    #[serde(default)]
+    pub alpha_60: Option<f64>,
+    /// 250日滚动回归阿尔法（日收益率截距）
+    #[serde(default)]
+    pub alpha_250: Option<f64>,
+    /// 换手率（%，成交量/流通股本）
+    #[serde(default)]
+    pub turnover_rate: Option<f64>,
+    /// 流通市值（元）
+    #[serde(default)]
+    pub float_market_cap: Option<f64>,
+    /// 当根K线VWAP（成交额/成交量，超出最高/最低价时为空）
+    #[serde(default)]
+    pub vwap: Option<f64>,
+    /// 5日VWAP
+    #[serde(default)]
+    pub vwap_5: Option<f64>,
+    /// 20日VWAP
+    #[serde(default)]
+    pub vwap_20: Option<f64>,
+    /// 5日TWAP（典型价均值）
+    #[serde(default)]
+    pub twap_5: Option<f64>,
+    /// 20日TWAP（典型价均值）
+    #[serde(default)]
+    pub twap_20: Option<f64>,
+    /// 收盘价相对52周最高价的距离（收盘价/最高价-1，不大于0）
+    #[serde(default)]
+    pub dist_high_52w: Option<f64>,
+    /// 收盘价相对52周最低价的距离（收盘价/最低价-1，不小于0）
+    #[serde(default)]
+    pub dist_low_52w: Option<f64>,
+    /// 距52周最高价出现的K线数（当根创新高为0）
+    #[serde(default)]
+    pub days_since_high_52w: Option<f64>,
+    /// 20日最大回撤（比例）
+    #[serde(default)]
+    pub max_drawdown_20: Option<f64>,
+    /// 60日最大回撤（比例）
+    #[serde(default)]
+    pub max_drawdown_60: Option<f64>,
+    /// 250日最大回撤（比例）
+    #[serde(default)]
+    pub max_drawdown_250: Option<f64>,
     /// 技术指标列表
     pub indicators: Vec<TechnicalIndicator>,
 }
 
+impl IndicatorValues {
+    /// 按名称读取指标值（名称见 `field::INDICATOR_NAMES`）
+    pub fn get(&self, name: &str) -> Option<f64> {
+        match name {
+            "ma5" => self.ma5,
+            "ma10" => self.ma10,
+            "ma20" => self.ma20,
+            "ma60" => self.ma60,
+            "volume_ma5" => self.volume_ma5,
+            "change_percent" => self.change_percent,
+            "amplitude" => self.amplitude,
+            "rsi" => self.rsi,
+            "macd_dif" => self.macd.as_ref().map(|m| m.dif),
+            "macd_signal" => self.macd.as_ref().map(|m| m.signal),
+            "macd_histogram" => self.macd.as_ref().map(|m| m.histogram),
+            "boll_upper" => self.bollinger.as_ref().map(|b| b.upper),
+            "boll_middle" => self.bollinger.as_ref().map(|b| b.middle),
+            "boll_lower" => self.bollinger.as_ref().map(|b| b.lower),
+            "rs_line" => self.rs_line,
+            "beta" => self.beta,
+            "excess_return" => self.excess_return,
+            "beta_20" => self.beta_20,
+            "beta_60" => self.beta_60,
+            "beta_250" => self.beta_250,
+            "alpha_20" => self.alpha_20,
+            "alpha_60" => self.alpha_60,
+            "alpha_250" => self.alpha_250,
+            "turnover_rate" => self.turnover_rate,
+            "float_market_cap" => self.float_market_cap,
+            "vwap" => self.vwap,
+            "vwap_5" => self.vwap_5,
+            "vwap_20" => self.vwap_20,
+            "twap_5" => self.twap_5,
+            "twap_20" => self.twap_20,
+            "dist_high_52w" => self.dist_high_52w,
+            "dist_low_52w" => self.dist_low_52w,
+            "days_since_high_52w" => self.days_since_high_52w,
+            "max_drawdown_20" => self.max_drawdown_20,
+            "max_drawdown_60" => self.max_drawdown_60,
+            "max_drawdown_250" => self.max_drawdown_250,
+            _ => None,
+        }
+    }
+}
+
 /// MACD指标
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MACD {
     /// DIF线
     pub dif: f64,
@@ -420,7 +817,7 @@ pub struct MACD {
 }
 
 /// 布林带指标
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BollingerBands {
     /// 上轨
     pub upper: f64,
@@ -472,10 +869,12 @@ mod tests {
 
     #[test]
     fn test_ma_calculation() {
-        let calculator = IndicatorCalculator::new();
-        let prices = vec![10.0, 11.0, 12.0, 13.0, 14.0, 15.0];
-        let ma = calculator.calculate_ma(&prices[1..6]); // 5日均线
-        assert_eq!(ma, 13.0);
+        let prices = [10.0, 11.0, 12.0, 13.0, 14.0, 15.0];
+        let mut window = RollingWindow::new(5); // 5日均线
+        for price in prices {
+            window.push(price);
+        }
+        assert_eq!(window.mean(), Some(13.0));
     }
 
     #[test]
@@ -486,6 +885,39 @@ mod tests {
         assert!(rsi > 0.0 && rsi <= 100.0);
     }
 
+    #[test]
+    fn test_macd_over_full_series() {
+        let calculator = IndicatorCalculator::new();
+        let data: Vec<TDXDayRecord> = (0..40)
+            .map(|i| {
+                let close = 10.0 + (i as f64 * 0.7).sin() + i as f64 * 0.05;
+                TDXDayRecord {
+                    date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Days::new(i),
+                    close,
+                    ..create_test_data()[0].clone()
+                }
+            })
+            .collect();
+        let result = calculator.calculate_all_indicators(&data).unwrap();
+        assert!(result[24].indicators.macd.is_none());
+
+        // 与整段序列逐日递推的EMA一致
+        let (mut fast, mut slow, mut signal) = (data[0].close, data[0].close, 0.0);
+        for (i, record) in data.iter().enumerate() {
+            fast += (record.close - fast) * 2.0 / 13.0;
+            slow += (record.close - slow) * 2.0 / 27.0;
+            signal = if i == 0 {
+                fast - slow
+            } else {
+                signal + (fast - slow - signal) * 0.2
+            };
+        }
+        let macd = result[39].indicators.macd.as_ref().unwrap();
+        assert!((macd.dif - (fast - slow)).abs() < 1e-9);
+        assert!((macd.signal - signal).abs() < 1e-9);
+        assert!((macd.histogram - (macd.dif - macd.signal)).abs() < 1e-12);
+    }
+
     #[test]
     fn test_calculate_all_indicators() {
         let calculator = IndicatorCalculator::new();
@@ -511,13 +943,278 @@ mod tests {
         let mut data = create_test_data();
 
         // 添加第二只股票
-        let mut record2 = create_test_data()[0].clone();
-        record2.symbol = "000001".to_string();
-        record2.market = "SZ".to_string();
-        data.push(record2);
+        for mut record in create_test_data() {
+            record.symbol = "000001".to_string();
+            record.market = "SZ".to_string();
+            data.push(record);
+        }
 
         let result = calculator.calculate_parallel(&data).unwrap();
 
         assert_eq!(result.len(), 4); // 每只股票2条记录
     }
+
+    #[test]
+    fn test_minute_bars_share_stack() {
+        use crate::parsers::TDXMinuteRecord;
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(9, 31, 0)
+            .unwrap();
+        // 输入按时间倒序，指标仍按时间顺序计算
+        let minutes: Vec<TDXMinuteRecord> = (0..6)
+            .rev()
+            .map(|i| TDXMinuteRecord {
+                datetime: start + chrono::Duration::minutes(i),
+                symbol: "600000".to_string(),
+                open: 10.0,
+                high: 10.0 + i as f64,
+                low: 10.0,
+                close: 10.0 + i as f64,
+                volume: 100,
+                amount: 1000.0,
+                market: "SH".to_string(),
+            })
+            .collect();
+
+        let result = IndicatorCalculator::new()
+            .calculate_parallel(&minutes)
+            .unwrap();
+        assert_eq!(result.len(), 6);
+        assert_eq!(result[0].base_record.datetime, start);
+        assert_eq!(result[4].indicators.ma5, Some(12.0));
+        assert_eq!(result[5].indicators.ma5, Some(13.0));
+    }
+
+    #[test]
+    fn test_fixed_price_records() {
+        use crate::parsers::{Price, TDXDayRecord};
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let days: Vec<TDXDayRecord<Price>> = (0..5)
+            .map(|i| TDXDayRecord {
+                date: start + chrono::Duration::days(i),
+                symbol: "600000".to_string(),
+                open: Price::from_raw(10_000),
+                high: Price::from_raw(10_500),
+                low: Price::from_raw(9_900),
+                close: Price::from_raw(10_100 + i * 10),
+                volume: 1000,
+                amount: 10_000.0,
+                market: "SH".to_string(),
+            })
+            .collect();
+
+        let result = IndicatorCalculator::new()
+            .calculate_parallel(&days)
+            .unwrap();
+        assert_eq!(result[4].base_record.close, Price::from_raw(10_140));
+        assert!((result[4].indicators.ma5.unwrap() - 10.12).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_warm_up_restarts_after_suspension() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let days: Vec<TDXDayRecord> = (0..12)
+            .map(|i| TDXDayRecord {
+                // 第6根之后停牌10天
+                date: start + chrono::Duration::days(if i < 6 { i } else { i + 10 }),
+                symbol: "600000".to_string(),
+                open: 10.0,
+                high: 10.5,
+                low: 9.5,
+                close: 10.0 + i as f64,
+                volume: 1000,
+                amount: 10_000.0,
+                market: "SH".to_string(),
+            })
+            .collect();
+        let suspension = SuspensionPeriod {
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            start: start + chrono::Duration::days(6),
+            end: start + chrono::Duration::days(15),
+            trading_days: 8,
+        };
+
+        let bridged = IndicatorCalculator::new()
+            .calculate_all_indicators(&days)
+            .unwrap();
+        assert!(bridged[6].indicators.ma5.is_some());
+
+        let result = IndicatorCalculator::new()
+            .with_suspensions(vec![suspension])
+            .calculate_all_indicators(&days)
+            .unwrap();
+        assert_eq!(result[5].indicators.ma5, Some(13.0));
+        assert!(result[6].indicators.ma5.is_none());
+        assert!(result[6].indicators.change_percent.is_none());
+        assert_eq!(result[10].indicators.ma5, Some(18.0));
+    }
+
+    #[test]
+    fn test_benchmark_relative_strength() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let bench_returns = [0.0, 0.01, -0.005, 0.02, -0.01, 0.015, 0.005, -0.02];
+        let (mut bench, mut stock) = (3000.0, 10.0);
+        let mut index = Vec::new();
+        let mut days = Vec::new();
+        for (i, r) in bench_returns.iter().enumerate() {
+            // 个股收益率恰为基准的两倍
+            bench *= 1.0 + r;
+            stock *= 1.0 + 2.0 * r;
+            let date = start + chrono::Duration::days(i as i64);
+            for (symbol, close, records) in
+                [("000001", bench, &mut index), ("600000", stock, &mut days)]
+            {
+                records.push(TDXDayRecord {
+                    date,
+                    symbol: symbol.to_string(),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 1000,
+                    amount: close * 1000.0,
+                    market: "SH".to_string(),
+                });
+            }
+        }
+
+        let benchmark = Benchmark::from_records("000001", "SH", &index).unwrap();
+        let result = IndicatorCalculator::new()
+            .with_benchmark(benchmark)
+            .with_beta_window(5)
+            .calculate_all_indicators(&days)
+            .unwrap();
+
+        let last = &result[7].indicators;
+        assert!((last.rs_line.unwrap() - days[7].close / index[7].close).abs() < 1e-12);
+        assert!((last.excess_return.unwrap() - (-2.0)).abs() < 1e-9);
+        assert!((last.beta.unwrap() - 2.0).abs() < 1e-9);
+        assert!(result[0].indicators.excess_return.is_none());
+        assert!(result[4].indicators.beta.is_none());
+        assert!(result[5].indicators.beta.is_some());
+    }
+
+    #[test]
+    fn test_rolling_regression_columns() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let (mut bench, mut stock) = (3000.0, 10.0);
+        let mut index = Vec::new();
+        let mut days = Vec::new();
+        for i in 0..30 {
+            let r = if i == 0 {
+                0.0
+            } else {
+                (i as f64 * 0.7).sin() * 0.02
+            };
+            // 个股日收益 = 0.001 + 1.5 × 基准日收益
+            bench *= 1.0 + r;
+            stock *= if i == 0 { 1.0 } else { 1.001 + 1.5 * r };
+            let date = start + chrono::Duration::days(i);
+            for (symbol, close, records) in
+                [("000300", bench, &mut index), ("600000", stock, &mut days)]
+            {
+                records.push(TDXDayRecord {
+                    date,
+                    symbol: symbol.to_string(),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 1000,
+                    amount: close * 1000.0,
+                    market: "SH".to_string(),
+                });
+            }
+        }
+
+        let benchmark = Benchmark::from_records("000300", "SH", &index).unwrap();
+        let result = IndicatorCalculator::new()
+            .with_benchmark(benchmark)
+            .calculate_all_indicators(&days)
+            .unwrap();
+
+        assert!(result[19].indicators.beta_20.is_none());
+        let last = &result[29].indicators;
+        assert!((last.beta_20.unwrap() - 1.5).abs() < 1e-9);
+        assert!((last.alpha_20.unwrap() - 0.001).abs() < 1e-9);
+        assert_eq!(last.get("alpha_20"), last.alpha_20);
+        assert!(last.beta_60.is_none() && last.alpha_250.is_none());
+    }
+
+    #[test]
+    fn test_vwap_and_twap_columns() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let days: Vec<TDXDayRecord> = (0..6)
+            .map(|i| {
+                let close = 10.0 + i as f64;
+                TDXDayRecord {
+                    date: start + chrono::Duration::days(i),
+                    symbol: "600000".to_string(),
+                    open: close,
+                    high: close + 1.0,
+                    low: close - 1.0,
+                    close,
+                    volume: 1000 * (i as u64 + 1),
+                    amount: close * 1000.0 * (i + 1) as f64,
+                    market: "SH".to_string(),
+                }
+            })
+            .collect();
+
+        let result = IndicatorCalculator::new()
+            .calculate_all_indicators(&days)
+            .unwrap();
+        assert_eq!(result[0].indicators.vwap, Some(10.0));
+        assert!(result[3].indicators.vwap_5.is_none());
+
+        // 最后5日成交额合计/成交量合计：Σ(11..=15)×k / Σk，k=2..=6
+        let last = &result[5].indicators;
+        let expected = (2..=6).map(|k| (9 + k) as f64 * k as f64).sum::<f64>() / 20.0;
+        assert!((last.vwap_5.unwrap() - expected).abs() < 1e-9);
+        assert!((last.twap_5.unwrap() - 13.0).abs() < 1e-9);
+        assert_eq!(last.get("twap_5"), last.twap_5);
+        assert!(last.vwap_20.is_none());
+    }
+
+    #[test]
+    fn test_extreme_and_drawdown_columns() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        // 先涨到第100根，再跌回
+        let days: Vec<TDXDayRecord> = (0i64..260)
+            .map(|i| {
+                let close = 10.0 + (100 - (i - 100).abs()) as f64 * 0.1;
+                TDXDayRecord {
+                    date: start + chrono::Duration::days(i),
+                    symbol: "600000".to_string(),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 1000,
+                    amount: close * 1000.0,
+                    market: "SH".to_string(),
+                }
+            })
+            .collect();
+
+        let result = IndicatorCalculator::new()
+            .with_drawdown_windows(vec![20])
+            .calculate_all_indicators(&days)
+            .unwrap();
+        assert!(result[248].indicators.dist_high_52w.is_none());
+        assert!(result[18].indicators.max_drawdown_20.is_none());
+        assert_eq!(result[50].indicators.max_drawdown_20, Some(0.0));
+
+        let last = &result[259].indicators;
+        assert_eq!(last.days_since_high_52w, Some(159.0));
+        assert!((last.dist_high_52w.unwrap() - (4.1 / 20.0 - 1.0)).abs() < 1e-9);
+        assert_eq!(last.dist_low_52w, Some(0.0));
+        assert!((last.max_drawdown_20.unwrap() - (1.0 - 4.1 / 6.0)).abs() < 1e-9);
+        assert_eq!(last.get("days_since_high_52w"), last.days_since_high_52w);
+        assert!(last.max_drawdown_60.is_none());
+    }
 }
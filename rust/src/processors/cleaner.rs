@@ -1,35 +1,66 @@
 //! 数据清洗模块
-
-use crate::parsers::TDXDayRecord;
+//!
+//! 清洗器对任意实现 `Bar` 的记录（日线、分钟线）生效。
+//!
+//! 异常值默认按股票分组检测（可选合并检测、可选按收益率检测），检测到后可移除、
+//! 仅标记、缩尾到检测边界或置为NaN，标记结果以原始输入中的位置记录在
+//! `CleaningResult::outliers` 中。
+//!
+//! 每条规则的影响（移除、修改的记录数及抽样位置）记录在 `CleaningResult::rule_impacts` 中；
+//! `DataCleaner::dry_run` 只报告影响而不改动输入，便于入库前审查规则。
+
+use crate::parsers::{Bar, ValidationProfile};
+use crate::processors::expr::RecordExpr;
+use crate::processors::field::Field;
+use crate::processors::lineage::Lineage;
+use crate::processors::suspension::{SuspensionIndex, SuspensionPeriod};
 use anyhow::Result;
-use chrono::{Datelike, NaiveDate, Weekday};
-use rayon::prelude::*;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 
 /// 数据清洗规则
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CleaningRule {
     /// 移除异常值
     RemoveOutliers {
-        field: String,
+        field: Field,
         method: OutlierMethod,
         threshold: f64,
+        /// 检测到异常值后的处理方式，缺省为移除
+        #[serde(default)]
+        action: OutlierAction,
+        /// 所有股票合并检测（旧行为），缺省按股票分组
+        #[serde(default)]
+        pooled: bool,
+        /// 按相邻K线的收益率而不是字段原值检测
+        #[serde(default)]
+        on_returns: bool,
     },
     /// 填充缺失值
-    FillMissing { field: String, method: FillMethod },
-    /// 移除重复记录
-    RemoveDuplicates { keys: Vec<String> },
+    FillMissing { field: Field, method: FillMethod },
+    /// 移除重复记录（键为 symbol/market/date 或数值字段名，为空时按股票代码和K线时间）
+    RemoveDuplicates {
+        keys: Vec<String>,
+        /// 重复记录中保留哪一条，缺省保留首条
+        #[serde(default)]
+        keep: KeepPolicy,
+    },
     /// 价格一致性检查
     ValidatePriceConsistency,
     /// 数据范围验证
     ValidateRange {
-        field: String,
+        field: Field,
         min: Option<f64>,
         max: Option<f64>,
     },
+    /// 按校验配置验证价格：开高低收须在该股票品种适用的价格区间内
+    ValidateProfile { profile: ValidationProfile },
     /// 移除非交易日数据
     RemoveNonTradingDays,
+    /// 按表达式筛选，仅保留满足表达式的记录
+    FilterExpr { expr: String },
 }
 
 /// 异常值检测方法
@@ -40,14 +71,66 @@ pub enum OutlierMethod {
     MedianDeviation { threshold: f64 },
 }
 
+/// 异常值处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OutlierAction {
+    /// 移除整条记录
+    #[default]
+    Remove,
+    /// 仅标记，不修改数据
+    FlagOnly,
+    /// 缩尾到该股票的检测边界
+    Winsorize,
+    /// 字段值置为NaN（成交量无法表示NaN，置为0，可再由 `FillMissing` 填充）
+    ReplaceWithNaN,
+}
+
+/// 被检测为异常值的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlierFlag {
+    /// 记录在原始输入中的位置
+    pub index: usize,
+    /// 检测的字段
+    pub field: Field,
+    /// 检测时的字段值
+    pub value: f64,
+    /// 采取的处理方式
+    pub action: OutlierAction,
+}
+
+/// 重复记录保留策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KeepPolicy {
+    /// 保留首条
+    #[default]
+    First,
+    /// 保留末条
+    Last,
+    /// 保留成交量最大的一条（并列时保留首条）
+    HighestVolume,
+}
+
 /// 缺失值填充方法
+///
+/// 均按股票分组、按K线时间排序后填充；前向、后向填充和插值不跨越停牌，
+/// 找不到可用的相邻值时保持缺失。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FillMethod {
+    /// 沿用前一个有效值
     ForwardFill,
+    /// 使用后一个有效值
     BackwardFill,
+    /// 在前后有效值之间按K线位置线性插值
+    Interpolate,
+    /// 该股票有效值的均值
     Mean,
+    /// 该股票有效值的中位数
     Median,
+    /// 填0
     Zero,
+    /// 填指定值
+    Value(f64),
+    /// 移除缺失记录
     Drop,
 }
 
@@ -64,33 +147,74 @@ pub struct CleaningResult {
     pub applied_rules: Vec<String>,
     /// 清洗统计信息
     pub statistics: CleaningStatistics,
+    /// 检测到的异常值（无论采取何种处理方式）
+    #[serde(default)]
+    pub outliers: Vec<OutlierFlag>,
+    /// 各规则的影响，与 `applied_rules` 一一对应
+    #[serde(default)]
+    pub rule_impacts: Vec<RuleImpact>,
 }
 
-/// 清洗统计信息
+/// 单条清洗规则的影响
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleImpact {
+    /// 规则名称
+    pub rule: String,
+    /// 规则输入记录数
+    pub input_count: usize,
+    /// 移除的记录数
+    pub removed: usize,
+    /// 修改了价格、成交量或成交额的记录数
+    pub modified: usize,
+    /// 受影响记录在原始输入中的位置（按位置抽样，最多 `sample_size` 条）
+    pub samples: Vec<usize>,
+}
+
+/// 清洗预演结果
+#[derive(Debug, Clone)]
+pub struct DryRun<B> {
+    /// 若实际清洗将得到的结果
+    pub result: CleaningResult,
+    /// 各规则抽样的受影响原始记录，与 `result.rule_impacts` 一一对应
+    pub samples: Vec<Vec<B>>,
+}
+
+/// 清洗统计信息
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CleaningStatistics {
-    /// 异常值数量
+    /// 移除的异常值数量
     pub outliers_removed: usize,
+    /// 仅标记的异常值数量
+    #[serde(default)]
+    pub outliers_flagged: usize,
+    /// 缩尾或置为NaN的异常值数量
+    #[serde(default)]
+    pub outliers_replaced: usize,
     /// 缺失值数量
     pub missing_values_filled: usize,
+    /// 因缺失值移除的记录数量
+    #[serde(default)]
+    pub missing_values_dropped: usize,
     /// 重复记录数量
     pub duplicates_removed: usize,
     /// 价格不一致数量
     pub price_inconsistencies: usize,
     /// 范围异常数量
     pub range_violations: usize,
+    /// 表达式筛选移除数量
+    pub filtered_by_expr: usize,
 }
 
-impl Default for CleaningStatistics {
-    fn default() -> Self {
-        Self {
-            outliers_removed: 0,
-            missing_values_filled: 0,
-            duplicates_removed: 0,
-            price_inconsistencies: 0,
-            range_violations: 0,
-        }
-    }
+/// 一次清洗的完整结果
+struct CleaningRun<B> {
+    /// 清洗后的记录
+    data: Vec<B>,
+    /// 清洗结果
+    result: CleaningResult,
+    /// 清洗后每条记录在原始输入中的位置
+    origin: Vec<usize>,
+    /// 每条规则修改过的记录的原始位置，与 `result.applied_rules` 一一对应
+    modified: Vec<Vec<usize>>,
 }
 
 /// 高性能数据清洗器
@@ -100,6 +224,10 @@ pub struct DataCleaner {
     rules: Vec<CleaningRule>,
     /// 交易日集合
     trading_days: HashSet<NaiveDate>,
+    /// 停牌期间，前向填充不跨越停牌
+    suspensions: SuspensionIndex,
+    /// 每条规则抽样的受影响记录数
+    sample_size: usize,
 }
 
 impl DataCleaner {
@@ -108,6 +236,8 @@ impl DataCleaner {
         Self {
             rules: Vec::new(),
             trading_days: HashSet::new(),
+            suspensions: SuspensionIndex::default(),
+            sample_size: 10,
         }
     }
 
@@ -117,6 +247,11 @@ impl DataCleaner {
         self
     }
 
+    /// 已配置的清洗规则（按执行顺序）
+    pub fn rules(&self) -> &[CleaningRule] {
+        &self.rules
+    }
+
     /// 批量添加清洗规则
     pub fn add_rules(&mut self, rules: Vec<CleaningRule>) -> &mut Self {
         self.rules.extend(rules);
@@ -129,36 +264,133 @@ impl DataCleaner {
         self
     }
 
-    /// 清洗数据
-    pub fn clean(&self, data: Vec<TDXDayRecord>) -> Result<CleaningResult> {
+    /// 设置停牌期间
+    pub fn set_suspensions(&mut self, periods: Vec<SuspensionPeriod>) -> &mut Self {
+        self.suspensions = SuspensionIndex::new(periods);
+        self
+    }
+
+    /// 设置每条规则抽样的受影响记录数
+    pub fn set_sample_size(&mut self, sample_size: usize) -> &mut Self {
+        self.sample_size = sample_size;
+        self
+    }
+
+    /// 预演清洗：报告每条规则将移除、修改哪些记录，不改动输入
+    pub fn dry_run<B: Bar + Clone>(&self, data: &[B]) -> Result<DryRun<B>> {
+        let (_, result) = self.clean_with_data(data.to_vec())?;
+        let samples = result
+            .rule_impacts
+            .iter()
+            .map(|impact| impact.samples.iter().map(|&i| data[i].clone()).collect())
+            .collect();
+        Ok(DryRun { result, samples })
+    }
+
+    /// 清洗数据，仅返回统计信息
+    #[deprecated(note = "清洗后的记录会被丢弃，请使用 `clean_with_data`")]
+    pub fn clean<B: Bar + Clone>(&self, data: Vec<B>) -> Result<CleaningResult> {
+        self.clean_with_data(data).map(|(_, result)| result)
+    }
+
+    /// 清洗数据，返回（清洗后的记录, 清洗结果）
+    pub fn clean_with_data<B: Bar + Clone>(
+        &self,
+        data: Vec<B>,
+    ) -> Result<(Vec<B>, CleaningResult)> {
+        self.run(data).map(|run| (run.data, run.result))
+    }
+
+    /// 清洗数据并同步更新血缘，`lineage` 与 `data` 按位置一一对应
+    ///
+    /// 返回的血缘只保留清洗后留下的记录，每条规则登记为一个步骤，并标记到被它修改过的记录上。
+    pub fn clean_with_lineage<B: Bar + Clone>(
+        &self,
+        data: Vec<B>,
+        lineage: &Lineage,
+    ) -> Result<(Vec<B>, CleaningResult, Lineage)> {
+        lineage.check_len(data.len())?;
+        let run = self.run(data)?;
+
+        let mut cleaned = lineage.select(&run.origin)?;
+        // 原始位置到清洗后位置的映射
+        let position: HashMap<usize, usize> = run
+            .origin
+            .iter()
+            .enumerate()
+            .map(|(kept, &index)| (index, kept))
+            .collect();
+        for (rule, modified) in run.result.applied_rules.iter().zip(&run.modified) {
+            let step = cleaned.add_step(rule.as_str());
+            cleaned.tag(
+                step,
+                modified
+                    .iter()
+                    .filter_map(|index| position.get(index).copied()),
+            );
+        }
+        Ok((run.data, run.result, cleaned))
+    }
+
+    fn run<B: Bar + Clone>(&self, data: Vec<B>) -> Result<CleaningRun<B>> {
         let original_count = data.len();
         let mut current_data = data;
+        // 当前每条记录在原始输入中的位置
+        let mut origin: Vec<usize> = (0..original_count).collect();
         let mut applied_rules = Vec::new();
         let mut statistics = CleaningStatistics::default();
+        let mut outliers = Vec::new();
+        let mut rule_impacts = Vec::new();
+        let mut modified = Vec::new();
 
         // 应用所有清洗规则
         for rule in &self.rules {
+            let before_origin = origin.clone();
+            let before_values: Vec<[u64; 6]> = current_data.iter().map(bar_values).collect();
+
             match rule {
                 CleaningRule::RemoveOutliers {
                     field,
                     method,
                     threshold,
+                    action,
+                    pooled,
+                    on_returns,
                 } => {
-                    current_data =
-                        self.remove_outliers(current_data, field, method.clone(), *threshold)?;
+                    let spec = OutlierSpec {
+                        field,
+                        method,
+                        threshold: *threshold,
+                        action: *action,
+                        pooled: *pooled,
+                        on_returns: *on_returns,
+                    };
+                    let (cleaned_data, flags) =
+                        self.handle_outliers(current_data, &mut origin, &spec)?;
+                    current_data = cleaned_data;
+                    match action {
+                        OutlierAction::Remove => statistics.outliers_removed += flags.len(),
+                        OutlierAction::FlagOnly => statistics.outliers_flagged += flags.len(),
+                        OutlierAction::Winsorize | OutlierAction::ReplaceWithNaN => {
+                            statistics.outliers_replaced += flags.len()
+                        }
+                    }
+                    outliers.extend(flags);
                     applied_rules.push(format!("RemoveOutliers({})", field));
                 }
                 CleaningRule::FillMissing { field, method } => {
                     current_data = self.fill_missing_values(
                         current_data,
+                        &mut origin,
                         field,
-                        method.clone(),
+                        method,
                         &mut statistics,
-                    )?;
+                    );
                     applied_rules.push(format!("FillMissing({})", field));
                 }
-                CleaningRule::RemoveDuplicates { keys } => {
-                    let (cleaned_data, removed) = self.remove_duplicates(current_data, keys)?;
+                CleaningRule::RemoveDuplicates { keys, keep } => {
+                    let keep = self.duplicate_mask(&current_data, keys, *keep)?;
+                    let (cleaned_data, removed) = retain_by_mask(current_data, &mut origin, &keep);
                     current_data = cleaned_data;
                     statistics.duplicates_removed += removed;
                     applied_rules.push("RemoveDuplicates".to_string());
@@ -170,74 +402,236 @@ impl DataCleaner {
                     applied_rules.push("ValidatePriceConsistency".to_string());
                 }
                 CleaningRule::ValidateRange { field, min, max } => {
+                    let keep = self.range_mask(&current_data, field, *min, *max)?;
                     let (cleaned_data, violations) =
-                        self.validate_range(current_data, field, *min, *max)?;
+                        retain_by_mask(current_data, &mut origin, &keep);
                     current_data = cleaned_data;
                     statistics.range_violations += violations;
                     applied_rules.push(format!("ValidateRange({})", field));
                 }
+                CleaningRule::ValidateProfile { profile } => {
+                    let keep: Vec<bool> = current_data
+                        .iter()
+                        .map(|record| {
+                            profile
+                                .validate_prices(
+                                    record.symbol(),
+                                    record.market(),
+                                    record.open(),
+                                    record.high(),
+                                    record.low(),
+                                    record.close(),
+                                )
+                                .is_ok()
+                        })
+                        .collect();
+                    let (cleaned_data, violations) =
+                        retain_by_mask(current_data, &mut origin, &keep);
+                    current_data = cleaned_data;
+                    statistics.range_violations += violations;
+                    applied_rules.push("ValidateProfile".to_string());
+                }
                 CleaningRule::RemoveNonTradingDays => {
-                    let (cleaned_data, removed) = self.remove_non_trading_days(current_data)?;
+                    let keep = self.trading_day_mask(&current_data);
+                    let (cleaned_data, _removed) = retain_by_mask(current_data, &mut origin, &keep);
                     current_data = cleaned_data;
                     // 移除的数据计入移除总数
                     applied_rules.push("RemoveNonTradingDays".to_string());
                 }
+                CleaningRule::FilterExpr { expr } => {
+                    let keep = RecordExpr::parse(expr)?.mask(&current_data)?;
+                    let (cleaned_data, removed) = retain_by_mask(current_data, &mut origin, &keep);
+                    current_data = cleaned_data;
+                    statistics.filtered_by_expr += removed;
+                    applied_rules.push(format!("FilterExpr({})", expr));
+                }
             }
+
+            let name = applied_rules.last().cloned().unwrap_or_default();
+            let (impact, rule_modified) =
+                self.rule_impact(name, &before_origin, &before_values, &origin, &current_data);
+            rule_impacts.push(impact);
+            modified.push(rule_modified);
         }
 
         let cleaned_count = current_data.len();
         let removed_count = original_count - cleaned_count;
 
-        Ok(CleaningResult {
+        let result = CleaningResult {
             original_count,
             cleaned_count,
             removed_count,
             applied_rules,
             statistics,
+            outliers,
+            rule_impacts,
+        };
+        Ok(CleaningRun {
+            data: current_data,
+            result,
+            origin,
+            modified,
         })
     }
 
-    /// 移除异常值
-    fn remove_outliers(
+    /// 对比规则前后的记录，统计移除和修改情况，另返回被修改记录的原始位置
+    ///
+    /// 规则只会按顺序保留记录，`origin` 是 `before_origin` 的子序列。
+    fn rule_impact<B: Bar>(
         &self,
-        data: Vec<TDXDayRecord>,
-        field: &str,
-        method: OutlierMethod,
-        threshold: f64,
-    ) -> Result<Vec<TDXDayRecord>> {
+        rule: String,
+        before_origin: &[usize],
+        before_values: &[[u64; 6]],
+        origin: &[usize],
+        data: &[B],
+    ) -> (RuleImpact, Vec<usize>) {
+        let mut affected = Vec::new();
+        let mut modified = Vec::new();
+        let mut kept = 0;
+        for (position, &index) in before_origin.iter().enumerate() {
+            if origin.get(kept) == Some(&index) {
+                if bar_values(&data[kept]) != before_values[position] {
+                    modified.push(index);
+                    affected.push(index);
+                }
+                kept += 1;
+            } else {
+                affected.push(index);
+            }
+        }
+
+        affected.sort_unstable();
+        affected.truncate(self.sample_size);
+        let impact = RuleImpact {
+            rule,
+            input_count: before_origin.len(),
+            removed: before_origin.len() - origin.len(),
+            modified: modified.len(),
+            samples: affected,
+        };
+        (impact, modified)
+    }
+
+    /// 检测异常值并按 `action` 处理
+    ///
+    /// 默认每只股票单独计算检测边界，缩尾也缩到该股票自己的边界，不同价位的股票互不影响。
+    /// 按收益率检测时，收益率在同一股票内按时间顺序计算，缩尾后的字段值由前一根K线的值
+    /// 和缩尾后的收益率还原。
+    fn handle_outliers<B: Bar>(
+        &self,
+        mut data: Vec<B>,
+        origin: &mut Vec<usize>,
+        spec: &OutlierSpec,
+    ) -> Result<(Vec<B>, Vec<OutlierFlag>)> {
+        let OutlierSpec {
+            field,
+            method,
+            threshold,
+            action,
+            ..
+        } = *spec;
+
         // 提取字段值
         let values: Vec<f64> = data
             .iter()
-            .map(|record| self.extract_field_value(record, field))
+            .map(|record| field.value(record))
             .collect::<Result<Vec<f64>>>()?;
 
-        let (outlier_indices, _) = self.detect_outliers(&values, &method, threshold);
+        let mut groups: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+        for (i, record) in data.iter().enumerate() {
+            groups
+                .entry((record.symbol(), record.market()))
+                .or_default()
+                .push(i);
+        }
 
-        // 保留非异常值的数据
-        let cleaned_data: Vec<TDXDayRecord> = data
-            .into_iter()
-            .enumerate()
-            .filter(|(index, _)| !outlier_indices.contains(index))
-            .map(|(_, record)| record)
+        // 同一股票上一根K线的位置
+        let mut previous = vec![None; data.len()];
+        if spec.on_returns {
+            for indices in groups.values_mut() {
+                indices.sort_by_key(|&i| data[i].timestamp());
+                for pair in indices.windows(2) {
+                    previous[pair[1]] = Some(pair[0]);
+                }
+            }
+        }
+        let series: Vec<f64> = if spec.on_returns {
+            (0..data.len())
+                .map(|i| previous[i].map_or(f64::NAN, |p| values[i] / values[p] - 1.0))
+                .collect()
+        } else {
+            values.clone()
+        };
+
+        let sections: Vec<Vec<usize>> = if spec.pooled {
+            vec![(0..data.len()).collect()]
+        } else {
+            groups.into_values().collect()
+        };
+
+        // （位置, 替换值）
+        let mut detected: Vec<(usize, f64)> = Vec::new();
+        for indices in sections {
+            let section: Vec<f64> = indices.iter().map(|&i| series[i]).collect();
+            let (outlier_indices, bounds) = self.detect_outliers(&section, method, threshold);
+            for local in outlier_indices {
+                let index = indices[local];
+                let replacement = match (action, bounds.as_slice(), previous[index]) {
+                    (OutlierAction::Winsorize, &[lower, upper], Some(p)) if spec.on_returns => {
+                        values[p] * (1.0 + series[index].clamp(lower, upper))
+                    }
+                    (OutlierAction::Winsorize, &[lower, upper], _) => {
+                        series[index].clamp(lower, upper)
+                    }
+                    _ => f64::NAN,
+                };
+                detected.push((index, replacement));
+            }
+        }
+        detected.sort_unstable_by_key(|&(index, _)| index);
+
+        let flags = detected
+            .iter()
+            .map(|&(index, _)| OutlierFlag {
+                index: origin[index],
+                field: field.clone(),
+                value: values[index],
+                action,
+            })
             .collect();
 
-        Ok(cleaned_data)
+        match action {
+            OutlierAction::Remove => {
+                let mut keep = vec![true; data.len()];
+                for &(index, _) in &detected {
+                    keep[index] = false;
+                }
+                data = retain_by_mask(data, origin, &keep).0;
+            }
+            OutlierAction::FlagOnly => {}
+            OutlierAction::Winsorize | OutlierAction::ReplaceWithNaN => {
+                for &(index, replacement) in &detected {
+                    field.set(&mut data[index], replacement);
+                }
+            }
+        }
+
+        Ok((data, flags))
     }
 
-    /// 检测异常值
+    /// 检测异常值（非有限值不参与统计，也不会被判为异常）
     fn detect_outliers(
         &self,
         values: &[f64],
         method: &OutlierMethod,
-        threshold: f64,
+        _threshold: f64,
     ) -> (Vec<usize>, Vec<f64>) {
         let mut outlier_indices = Vec::new();
         let mut bounds = Vec::new();
 
         match method {
             OutlierMethod::IQR { multiplier } => {
-                let mut sorted_values = values.to_vec();
-                sorted_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let sorted_values = sorted_finite(values);
 
                 let q1_index = (sorted_values.len() as f64 * 0.25) as usize;
                 let q3_index = (sorted_values.len() as f64 * 0.75) as usize;
@@ -259,9 +653,10 @@ impl DataCleaner {
                 }
             }
             OutlierMethod::ZScore { threshold } => {
-                let mean = values.iter().sum::<f64>() / values.len() as f64;
+                let finite = sorted_finite(values);
+                let mean = finite.iter().sum::<f64>() / finite.len() as f64;
                 let variance =
-                    values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64;
+                    finite.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / finite.len() as f64;
                 let std = variance.sqrt();
 
                 if std > 0.0 {
@@ -276,8 +671,7 @@ impl DataCleaner {
                 }
             }
             OutlierMethod::MedianDeviation { threshold } => {
-                let mut sorted_values = values.to_vec();
-                sorted_values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let sorted_values = sorted_finite(values);
                 let median = if sorted_values.is_empty() {
                     0.0
                 } else {
@@ -307,172 +701,205 @@ impl DataCleaner {
     }
 
     /// 填充缺失值
-    fn fill_missing_values(
-        &self,
-        data: Vec<TDXDayRecord>,
-        field: &str,
-        method: FillMethod,
-        statistics: &mut CleaningStatistics,
-    ) -> Result<Vec<TDXDayRecord>> {
-        // 简化实现：主要处理价格数据的前向填充
-        if field == "volume" || field == "amount" {
-            // 成交量和成交额的填充逻辑
-            let mut filled_data = Vec::with_capacity(data.len());
-            let mut last_valid_volume = None;
-            let mut last_valid_amount = None;
-
-            for record in data {
-                let mut filled_record = record.clone();
-
-                if record.volume == 0 {
-                    if let Some(volume) = last_valid_volume {
-                        filled_record.volume = volume;
-                        statistics.missing_values_filled += 1;
-                    }
-                } else {
-                    last_valid_volume = Some(record.volume);
-                }
-
-                if record.amount == 0.0 {
-                    if let Some(amount) = last_valid_amount {
-                        filled_record.amount = amount;
-                        statistics.missing_values_filled += 1;
-                    }
-                } else {
-                    last_valid_amount = Some(record.amount);
-                }
-
-                filled_data.push(filled_record);
-            }
-
-            Ok(filled_data)
-        } else {
-            // 价格字段的填充
-            self.fill_price_values(data, field, method, statistics)
-        }
-    }
-
-    /// 填充价格值
-    fn fill_price_values(
+    fn fill_missing_values<B: Bar>(
         &self,
-        data: Vec<TDXDayRecord>,
-        field: &str,
-        method: FillMethod,
+        mut data: Vec<B>,
+        origin: &mut Vec<usize>,
+        field: &Field,
+        method: &FillMethod,
         statistics: &mut CleaningStatistics,
-    ) -> Result<Vec<TDXDayRecord>> {
-        let mut filled_data = data;
-
+    ) -> Vec<B> {
         // 根据股票代码分组处理
-        use std::collections::HashMap;
-        let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
-
-        for (i, record) in filled_data.iter().enumerate() {
+        let mut groups: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+        for (i, record) in data.iter().enumerate() {
             groups
-                .entry(record.symbol.clone())
-                .or_insert_with(Vec::new)
+                .entry((record.symbol(), record.market()))
+                .or_default()
                 .push(i);
         }
-
-        for (symbol, mut indices) in groups {
-            // 按日期排序索引
-            indices.sort_by(|&i, &j| filled_data[i].date.cmp(&filled_data[j].date));
-
-            // 先收集需要填充的索引
-            let indices_to_fill: Vec<usize> = indices
+        let groups: Vec<Vec<usize>> = groups.into_values().collect();
+
+        // （位置, 填充值）
+        let mut fills: Vec<(usize, f64)> = Vec::new();
+        let mut keep = vec![true; data.len()];
+        for mut indices in groups {
+            // 按时间排序索引
+            indices.sort_by_key(|&i| data[i].timestamp());
+            let missing: Vec<bool> = indices
                 .iter()
-                .copied()
-                .filter(|&idx| self.needs_filling(&filled_data[idx], field))
+                .map(|&i| self.needs_filling(&data[i], field))
                 .collect();
+            if !missing.contains(&true) {
+                continue;
+            }
 
-            for &idx in &indices_to_fill {
+            let value_at = |pos: usize| field.value(&data[indices[pos]]).unwrap_or(f64::NAN);
+            let mut valid: Vec<f64> = (0..indices.len())
+                .filter(|&pos| !missing[pos])
+                .map(value_at)
+                .collect();
+            let statistic = match method {
+                FillMethod::Mean if !valid.is_empty() => {
+                    Some(valid.iter().sum::<f64>() / valid.len() as f64)
+                }
+                FillMethod::Median if !valid.is_empty() => {
+                    valid.sort_unstable_by(f64::total_cmp);
+                    let mid = valid.len() / 2;
+                    Some(if valid.len().is_multiple_of(2) {
+                        (valid[mid - 1] + valid[mid]) / 2.0
+                    } else {
+                        valid[mid]
+                    })
+                }
+                _ => None,
+            };
+
+            // 每个位置之前 / 之后最近的有效位置
+            let mut previous = vec![None; indices.len()];
+            let mut last = None;
+            for pos in 0..indices.len() {
+                previous[pos] = last;
+                if !missing[pos] {
+                    last = Some(pos);
+                }
+            }
+            let mut next = vec![None; indices.len()];
+            last = None;
+            for pos in (0..indices.len()).rev() {
+                next[pos] = last;
+                if !missing[pos] {
+                    last = Some(pos);
+                }
+            }
+            // 不跨越停牌的相邻有效位置
+            let reachable = |from: Option<usize>, pos: usize| {
+                from.filter(|&other| {
+                    let (a, b) = (other.min(pos), other.max(pos));
+                    !self.crosses_suspension(&data[indices[a]], &data[indices[b]])
+                })
+            };
+
+            for pos in (0..indices.len()).filter(|&pos| missing[pos]) {
                 let fill_value = match method {
-                    FillMethod::ForwardFill => {
-                        // 前向填充
-                        self.get_previous_value(&filled_data, idx, &symbol, field)
-                    }
-                    FillMethod::Mean => {
-                        // 均值填充
-                        self.calculate_mean_value(&filled_data, symbol.clone(), field)
+                    FillMethod::ForwardFill => reachable(previous[pos], pos).map(value_at),
+                    FillMethod::BackwardFill => reachable(next[pos], pos).map(value_at),
+                    FillMethod::Interpolate => {
+                        match (reachable(previous[pos], pos), reachable(next[pos], pos)) {
+                            (Some(before), Some(after)) => {
+                                let weight = (pos - before) as f64 / (after - before) as f64;
+                                Some(
+                                    value_at(before)
+                                        + (value_at(after) - value_at(before)) * weight,
+                                )
+                            }
+                            _ => None,
+                        }
                     }
-                    _ => {
-                        // 默认值或移除
-                        0.0
+                    FillMethod::Mean | FillMethod::Median => statistic,
+                    FillMethod::Zero => Some(0.0),
+                    FillMethod::Value(value) => Some(*value),
+                    FillMethod::Drop => {
+                        keep[indices[pos]] = false;
+                        None
                     }
                 };
+                if let Some(value) = fill_value {
+                    fills.push((indices[pos], value));
+                }
+            }
+        }
 
-                self.set_field_value(&mut filled_data[idx], field, fill_value);
+        for (i, value) in fills {
+            if field.set(&mut data[i], value) {
                 statistics.missing_values_filled += 1;
             }
         }
-
-        Ok(filled_data)
+        if matches!(method, FillMethod::Drop) {
+            let (kept, dropped) = retain_by_mask(data, origin, &keep);
+            statistics.missing_values_dropped += dropped;
+            data = kept;
+        }
+        data
     }
 
-    /// 移除重复记录
-    fn remove_duplicates(
+    /// 去重掩码，每组重复记录按 `policy` 保留一条
+    fn duplicate_mask<B: Bar>(
         &self,
-        data: Vec<TDXDayRecord>,
+        data: &[B],
         keys: &[String],
-    ) -> Result<(Vec<TDXDayRecord>, usize)> {
-        if keys.is_empty() {
-            // 默认按股票代码和日期去重
-            let mut seen = std::collections::HashSet::new();
-            let mut unique_data = Vec::new();
-            let mut removed_count = 0;
-
-            for record in data {
-                let key = format!("{}_{}", record.symbol, record.date.format("%Y-%m-%d"));
-
-                if seen.insert(key) {
-                    unique_data.push(record);
-                } else {
-                    removed_count += 1;
+        policy: KeepPolicy,
+    ) -> Result<Vec<bool>> {
+        // 键 -> 保留记录的位置
+        let mut kept: HashMap<Vec<String>, usize> = HashMap::new();
+        for (i, record) in data.iter().enumerate() {
+            let key = if keys.is_empty() {
+                // 默认按股票代码和K线时间去重
+                vec![record.symbol().to_string(), record.timestamp().to_string()]
+            } else {
+                // 按指定字段去重
+                keys.iter()
+                    .map(|field| self.extract_key_value(record, field))
+                    .collect::<Result<Vec<String>>>()?
+            };
+
+            match kept.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(i);
+                }
+                Entry::Occupied(mut entry) => {
+                    let replace = match policy {
+                        KeepPolicy::First => false,
+                        KeepPolicy::Last => true,
+                        KeepPolicy::HighestVolume => record.volume() > data[*entry.get()].volume(),
+                    };
+                    if replace {
+                        entry.insert(i);
+                    }
                 }
             }
+        }
 
-            Ok((unique_data, removed_count))
-        } else {
-            // 按指定字段去重
-            Ok((data, 0)) // 简化实现
+        let mut mask = vec![false; data.len()];
+        for i in kept.into_values() {
+            mask[i] = true;
         }
+        Ok(mask)
     }
 
     /// 验证价格一致性
-    fn validate_price_consistency(
-        &self,
-        data: Vec<TDXDayRecord>,
-    ) -> Result<(Vec<TDXDayRecord>, usize)> {
+    fn validate_price_consistency<B: Bar>(&self, data: Vec<B>) -> Result<(Vec<B>, usize)> {
         let mut fixed_data = Vec::with_capacity(data.len());
         let mut fixed_count = 0;
 
-        for record in data {
-            let mut fixed_record = record.clone();
+        for mut fixed_record in data {
             let mut needs_fix = false;
 
             // 检查价格关系
-            if fixed_record.high < fixed_record.low {
+            if fixed_record.high() < fixed_record.low() {
                 // 修正高低价
-                std::mem::swap(&mut fixed_record.high, &mut fixed_record.low);
+                let (high, low) = (fixed_record.high(), fixed_record.low());
+                fixed_record.set_high(low);
+                fixed_record.set_low(high);
                 needs_fix = true;
             }
 
-            if fixed_record.open > fixed_record.high {
-                fixed_record.open = fixed_record.high;
+            if fixed_record.open() > fixed_record.high() {
+                fixed_record.set_open(fixed_record.high());
                 needs_fix = true;
             }
 
-            if fixed_record.open < fixed_record.low {
-                fixed_record.open = fixed_record.low;
+            if fixed_record.open() < fixed_record.low() {
+                fixed_record.set_open(fixed_record.low());
                 needs_fix = true;
             }
 
-            if fixed_record.close > fixed_record.high {
-                fixed_record.close = fixed_record.high;
+            if fixed_record.close() > fixed_record.high() {
+                fixed_record.set_close(fixed_record.high());
                 needs_fix = true;
             }
 
-            if fixed_record.close < fixed_record.low {
-                fixed_record.close = fixed_record.low;
+            if fixed_record.close() < fixed_record.low() {
+                fixed_record.set_close(fixed_record.low());
                 needs_fix = true;
             }
 
@@ -486,136 +913,112 @@ impl DataCleaner {
         Ok((fixed_data, fixed_count))
     }
 
-    /// 验证数值范围
-    fn validate_range(
+    /// 数值范围掩码
+    fn range_mask<B: Bar>(
         &self,
-        data: Vec<TDXDayRecord>,
-        field: &str,
+        data: &[B],
+        field: &Field,
         min: Option<f64>,
         max: Option<f64>,
-    ) -> Result<(Vec<TDXDayRecord>, usize)> {
-        let mut valid_data = Vec::with_capacity(data.len());
-        let mut violations = 0;
-
-        for record in data {
-            let value = self.extract_field_value(&record, field)?;
-            let mut is_valid = true;
-
-            if let Some(min_val) = min {
-                if value < min_val {
-                    is_valid = false;
-                }
-            }
-
-            if let Some(max_val) = max {
-                if value > max_val {
-                    is_valid = false;
-                }
-            }
-
-            if is_valid {
-                valid_data.push(record);
-            } else {
-                violations += 1;
-            }
-        }
-
-        Ok((valid_data, violations))
+    ) -> Result<Vec<bool>> {
+        data.iter()
+            .map(|record| {
+                let value = field.value(record)?;
+                Ok(min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max))
+            })
+            .collect()
     }
 
-    /// 移除非交易日数据
-    fn remove_non_trading_days(
-        &self,
-        data: Vec<TDXDayRecord>,
-    ) -> Result<(Vec<TDXDayRecord>, usize)> {
-        let mut trading_data = Vec::with_capacity(data.len());
-        let mut removed_count = 0;
-
-        for record in data {
-            if self.trading_days.contains(&record.date) {
-                trading_data.push(record);
-            } else {
-                removed_count += 1;
-            }
-        }
-
-        Ok((trading_data, removed_count))
+    /// 交易日掩码
+    fn trading_day_mask<B: Bar>(&self, data: &[B]) -> Vec<bool> {
+        data.iter()
+            .map(|record| self.trading_days.contains(&record.date()))
+            .collect()
     }
 
-    /// 辅助方法：从记录中提取字段值
-    fn extract_field_value(&self, record: &TDXDayRecord, field: &str) -> Result<f64> {
+    /// 辅助方法：提取去重键字段值
+    ///
+    /// `date` 键取K线时间，分钟线按分钟区分而不是按日期合并。
+    fn extract_key_value<B: Bar>(&self, record: &B, field: &str) -> Result<String> {
         match field {
-            "open" => Ok(record.open),
-            "high" => Ok(record.high),
-            "low" => Ok(record.low),
-            "close" => Ok(record.close),
-            "volume" => Ok(record.volume as f64),
-            "amount" => Ok(record.amount),
-            _ => Err(anyhow::anyhow!("未知字段: {}", field)),
+            "symbol" => Ok(record.symbol().to_string()),
+            "market" => Ok(record.market().to_string()),
+            "date" => Ok(record.timestamp().to_string()),
+            _ => field
+                .parse::<Field>()?
+                .value(record)
+                .map(|value| value.to_string()),
         }
     }
 
-    /// 辅助方法：检查是否需要填充
-    fn needs_filling(&self, record: &TDXDayRecord, field: &str) -> bool {
+    /// 辅助方法：检查是否需要填充（价格、成交额非正或非有限，成交量为0）
+    fn needs_filling<B: Bar>(&self, record: &B, field: &Field) -> bool {
         match field {
-            "open" | "high" | "low" | "close" => {
-                record.open <= 0.0 || record.high <= 0.0 || record.low <= 0.0 || record.close <= 0.0
-            }
-            "volume" => record.volume == 0,
-            "amount" => record.amount <= 0.0,
-            _ => false,
+            Field::Volume => record.volume() == 0,
+            Field::Indicator(_) => false,
+            _ => field
+                .value(record)
+                .is_ok_and(|value| !value.is_finite() || value <= 0.0),
         }
     }
 
-    /// 辅助方法：获取前一个有效值
-    fn get_previous_value(
-        &self,
-        data: &[TDXDayRecord],
-        idx: usize,
-        symbol: &str,
-        field: &str,
-    ) -> f64 {
-        for i in (0..idx).rev() {
-            if data[i].symbol == symbol && !self.needs_filling(&data[i], field) {
-                return self.extract_field_value(&data[i], field).unwrap_or(0.0);
-            }
-        }
-        0.0
+    /// 辅助方法：两条记录之间是否隔着停牌
+    fn crosses_suspension<B: Bar>(&self, previous: &B, current: &B) -> bool {
+        self.suspensions.separates(
+            current.symbol(),
+            current.market(),
+            previous.date(),
+            current.date(),
+        )
     }
+}
 
-    /// 辅助方法：计算均值
-    fn calculate_mean_value(&self, data: &[TDXDayRecord], symbol: String, field: &str) -> f64 {
-        let mut sum = 0.0;
-        let mut count = 0;
+/// 异常值规则参数
+struct OutlierSpec<'a> {
+    field: &'a Field,
+    method: &'a OutlierMethod,
+    threshold: f64,
+    action: OutlierAction,
+    pooled: bool,
+    on_returns: bool,
+}
 
-        for record in data {
-            if record.symbol == symbol && !self.needs_filling(record, field) {
-                if let Ok(value) = self.extract_field_value(record, field) {
-                    sum += value;
-                    count += 1;
-                }
-            }
-        }
+/// 记录的价格、成交量和成交额（按位比较，NaN也视为相等）
+fn bar_values<B: Bar>(record: &B) -> [u64; 6] {
+    [
+        record.open().to_bits(),
+        record.high().to_bits(),
+        record.low().to_bits(),
+        record.close().to_bits(),
+        record.volume(),
+        record.amount().to_bits(),
+    ]
+}
 
-        if count > 0 {
-            sum / count as f64
-        } else {
-            0.0
-        }
-    }
+/// 有限值（升序）
+fn sorted_finite(values: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    sorted.sort_unstable_by(f64::total_cmp);
+    sorted
+}
 
-    /// 辅助方法：设置字段值
-    fn set_field_value(&self, record: &mut TDXDayRecord, field: &str, value: f64) {
-        match field {
-            "open" => record.open = value,
-            "high" => record.high = value,
-            "low" => record.low = value,
-            "close" => record.close = value,
-            "volume" => record.volume = value as u64,
-            "amount" => record.amount = value,
-            _ => {} // 忽略未知字段
-        }
-    }
+/// 按掩码保留记录并同步原始位置，返回（保留的记录, 移除数）
+fn retain_by_mask<B>(data: Vec<B>, origin: &mut Vec<usize>, keep: &[bool]) -> (Vec<B>, usize) {
+    let original_count = data.len();
+    let mut kept_origin = Vec::with_capacity(original_count);
+    let kept: Vec<B> = data
+        .into_iter()
+        .zip(origin.iter())
+        .zip(keep)
+        .filter(|(_, keep)| **keep)
+        .map(|((record, &position), _)| {
+            kept_origin.push(position);
+            record
+        })
+        .collect();
+    *origin = kept_origin;
+    let removed = original_count - kept.len();
+    (kept, removed)
 }
 
 impl Default for DataCleaner {
@@ -627,16 +1030,10 @@ impl Default for DataCleaner {
             CleaningRule::ValidatePriceConsistency,
             CleaningRule::RemoveDuplicates {
                 keys: vec!["symbol".to_string(), "date".to_string()],
+                keep: KeepPolicy::First,
             },
-            CleaningRule::ValidateRange {
-                field: "open".to_string(),
-                min: Some(0.01),
-                max: Some(10000.0),
-            },
-            CleaningRule::ValidateRange {
-                field: "close".to_string(),
-                min: Some(0.01),
-                max: Some(10000.0),
+            CleaningRule::ValidateProfile {
+                profile: ValidationProfile::a_share(),
             },
         ]);
 
@@ -647,6 +1044,7 @@ impl Default for DataCleaner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parsers::{TDXDayRecord, TDXMinuteRecord};
     use chrono::NaiveDate;
 
     fn create_test_record(symbol: &str, date: &str) -> TDXDayRecord {
@@ -676,6 +1074,26 @@ mod tests {
         assert_eq!(cleaner.rules.len(), 1);
     }
 
+    #[test]
+    fn test_default_uses_a_share_profile() {
+        // 12000点的上证指数合法，12000元的A股超出区间
+        let scaled = |symbol: &str| TDXDayRecord {
+            open: 12_000.0,
+            high: 12_100.0,
+            low: 11_900.0,
+            close: 12_050.0,
+            ..create_test_record(symbol, "2024-01-02")
+        };
+        let data = vec![scaled("000001"), scaled("600000")];
+        let (cleaned, result) = DataCleaner::default().clean_with_data(data).unwrap();
+        assert_eq!(cleaned.len(), 1);
+        assert_eq!(cleaned[0].symbol, "000001");
+        assert_eq!(result.statistics.range_violations, 1);
+        assert!(result
+            .applied_rules
+            .contains(&"ValidateProfile".to_string()));
+    }
+
     #[test]
     fn test_price_consistency_validation() {
         let mut cleaner = DataCleaner::new();
@@ -691,11 +1109,12 @@ mod tests {
         data[1].high = 8.0; // 最高价低于最低价
         data[1].low = 12.0; // 最低价高于最高价
 
-        let result = cleaner.clean(data).unwrap();
+        let (cleaned, result) = cleaner.clean_with_data(data).unwrap();
 
         // 验证数据被修正
         assert_eq!(result.cleaned_count, 2);
         assert_eq!(result.statistics.price_inconsistencies, 1);
+        assert_eq!((cleaned[1].high, cleaned[1].low), (12.0, 8.0));
     }
 
     #[test]
@@ -703,6 +1122,7 @@ mod tests {
         let mut cleaner = DataCleaner::new();
         cleaner.add_rule(CleaningRule::RemoveDuplicates {
             keys: vec!["symbol".to_string(), "date".to_string()],
+            keep: KeepPolicy::First,
         });
 
         // 创建包含重复记录的测试数据
@@ -712,10 +1132,377 @@ mod tests {
             create_test_record("600000", "2024-01-02"),
         ];
 
-        let result = cleaner.clean(data).unwrap();
+        let (cleaned, result) = cleaner.clean_with_data(data).unwrap();
 
         // 验证重复记录被移除
         assert_eq!(result.cleaned_count, 2);
+        assert_eq!(cleaned.len(), 2);
+        assert_ne!(cleaned[0].date, cleaned[1].date);
         assert_eq!(result.statistics.duplicates_removed, 1);
     }
+
+    #[test]
+    fn test_duplicate_keep_policies() {
+        let mut data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600001", "2024-01-01"),
+        ];
+        data[1].volume = 3_000_000;
+        data[2].volume = 2_000_000;
+
+        let dedup = |keys: &[&str], keep| {
+            let mut cleaner = DataCleaner::new();
+            cleaner.add_rule(CleaningRule::RemoveDuplicates {
+                keys: keys.iter().map(|k| k.to_string()).collect(),
+                keep,
+            });
+            cleaner.clean_with_data(data.clone()).unwrap()
+        };
+
+        let keys = ["symbol", "market", "date"];
+        let (kept, result) = dedup(&keys, KeepPolicy::Last);
+        assert_eq!(result.statistics.duplicates_removed, 2);
+        assert_eq!(kept[0].volume, 2_000_000);
+        assert_eq!(kept[1].symbol, "600001");
+
+        let (kept, _) = dedup(&keys, KeepPolicy::HighestVolume);
+        assert_eq!(kept[0].volume, 3_000_000);
+
+        // 按价格字段去重：四条记录收盘价相同
+        let (kept, result) = dedup(&["close"], KeepPolicy::First);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(result.removed_count, 3);
+    }
+
+    #[test]
+    fn test_dry_run_reports_rule_impacts() {
+        let mut data: Vec<TDXDayRecord> = (1..=5)
+            .map(|day| create_test_record("600000", &format!("2024-01-{:02}", day)))
+            .collect();
+        data[1].high = 8.0;
+        data[3].close = 9.5;
+        data.push(data[4].clone());
+
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rules(vec![
+            CleaningRule::ValidatePriceConsistency,
+            CleaningRule::RemoveDuplicates {
+                keys: vec![],
+                keep: KeepPolicy::First,
+            },
+            CleaningRule::FilterExpr {
+                expr: "close >= 10".to_string(),
+            },
+        ]);
+        cleaner.set_sample_size(1);
+
+        let dry_run = cleaner.dry_run(&data).unwrap();
+        let impacts = &dry_run.result.rule_impacts;
+        assert_eq!(impacts.len(), 3);
+        assert_eq!(impacts[0].rule, "ValidatePriceConsistency");
+        assert_eq!((impacts[0].modified, impacts[0].removed), (1, 0));
+        assert_eq!((impacts[1].input_count, impacts[1].removed), (6, 1));
+        assert_eq!(impacts[1].samples, vec![5]);
+        // 第二条记录修正后收盘价被截到最高价9.0，与第四条一起被筛掉；抽样给出原始记录
+        assert_eq!(impacts[2].removed, 2);
+        assert_eq!(impacts[2].samples, vec![1]);
+        assert_eq!(dry_run.samples[2][0].high, 8.0);
+
+        // 输入未被改动，实际清洗结果与预演一致
+        assert_eq!(data.len(), 6);
+        let (cleaned, result) = cleaner.clean_with_data(data).unwrap();
+        assert_eq!(cleaned.len(), dry_run.result.cleaned_count);
+        assert_eq!(result.removed_count, 3);
+    }
+
+    #[test]
+    fn test_filter_expr() {
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(CleaningRule::FilterExpr {
+            expr: "close >= 10 && amount > 0".to_string(),
+        });
+
+        let mut data = vec![
+            create_test_record("600000", "2024-01-01"),
+            create_test_record("600000", "2024-01-02"),
+        ];
+        data[1].close = 9.5;
+
+        let (_, result) = cleaner.clean_with_data(data).unwrap();
+
+        assert_eq!(result.cleaned_count, 1);
+        assert_eq!(result.statistics.filtered_by_expr, 1);
+    }
+
+    #[test]
+    fn test_outlier_actions_keep_original_indices() {
+        let closes = [10.0, 10.2, 9.9, 10.1, 10.0, 50.0, 10.1];
+        let mut data: Vec<TDXDayRecord> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| {
+                let mut record = create_test_record("600000", &format!("2024-01-{:02}", i + 2));
+                record.close = close;
+                record
+            })
+            .collect();
+        // 首条重复记录先被去重规则移除，异常值位置仍按原始输入计
+        data.insert(0, data[0].clone());
+
+        let outlier_rule = |action| CleaningRule::RemoveOutliers {
+            field: Field::Close,
+            method: OutlierMethod::IQR { multiplier: 1.5 },
+            threshold: 0.0,
+            action,
+            pooled: false,
+            on_returns: false,
+        };
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(CleaningRule::RemoveDuplicates {
+            keys: vec![],
+            keep: KeepPolicy::First,
+        });
+        cleaner.add_rule(outlier_rule(OutlierAction::FlagOnly));
+        let (_, result) = cleaner.clean_with_data(data.clone()).unwrap();
+        assert_eq!(result.cleaned_count, 7);
+        assert_eq!(result.statistics.outliers_flagged, 1);
+        assert_eq!(result.outliers[0].index, 6);
+        assert_eq!(result.outliers[0].value, 50.0);
+
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(outlier_rule(OutlierAction::Remove));
+        let (_, result) = cleaner.clean_with_data(data).unwrap();
+        assert_eq!(result.cleaned_count, 7);
+        assert_eq!(result.statistics.outliers_removed, 1);
+    }
+
+    #[test]
+    fn test_winsorize_per_symbol() {
+        // 两只股票价位不同，各自按自己的边界缩尾
+        let mut data = Vec::new();
+        for (symbol, base) in [("600000", 10.0), ("600001", 100.0)] {
+            for (i, offset) in [0.0, 0.2, -0.1, 0.1, 0.0, 4.0].into_iter().enumerate() {
+                let mut record = create_test_record(symbol, &format!("2024-01-{:02}", i + 2));
+                record.close = base + offset * base / 10.0;
+                data.push(record);
+            }
+        }
+
+        let cleaner = DataCleaner::new();
+        let method = OutlierMethod::IQR { multiplier: 1.5 };
+        let mut spec = OutlierSpec {
+            field: &Field::Close,
+            method: &method,
+            threshold: 0.0,
+            action: OutlierAction::Winsorize,
+            pooled: false,
+            on_returns: false,
+        };
+        let mut origin: Vec<usize> = (0..data.len()).collect();
+        let (winsorized, flags) = cleaner.handle_outliers(data, &mut origin, &spec).unwrap();
+
+        assert_eq!(winsorized.len(), 12);
+        assert_eq!(
+            flags.iter().map(|f| f.index).collect::<Vec<_>>(),
+            vec![5, 11]
+        );
+        // 边界为 Q3 + 1.5 * IQR
+        assert!((winsorized[5].close - 10.5).abs() < 1e-9);
+        assert!((winsorized[11].close - 105.0).abs() < 1e-9);
+        assert_eq!(winsorized[4].close, 10.0);
+
+        let zscore = OutlierMethod::ZScore { threshold: 1.0 };
+        spec.method = &zscore;
+        spec.action = OutlierAction::ReplaceWithNaN;
+        let (replaced, _) = cleaner
+            .handle_outliers(winsorized, &mut origin, &spec)
+            .unwrap();
+        assert!(replaced.iter().any(|r| r.close.is_nan()));
+    }
+
+    #[test]
+    fn test_pooled_and_return_based_detection() {
+        // 低价股与高价股混在一起，合并检测会把正常的低价股K线判为异常
+        let mut data = Vec::new();
+        for (symbol, base) in [("600000", 5.0), ("600519", 1500.0)] {
+            for (i, change) in [0.0, 0.01, -0.01, 0.02, 0.0, 0.5, 0.01]
+                .into_iter()
+                .enumerate()
+            {
+                let mut record = create_test_record(symbol, &format!("2024-01-{:02}", i + 2));
+                record.close = base * (1.0 + change);
+                data.push(record);
+            }
+        }
+        let rule = |pooled, on_returns| CleaningRule::RemoveOutliers {
+            field: Field::Close,
+            method: OutlierMethod::ZScore { threshold: 0.9 },
+            threshold: 0.0,
+            action: OutlierAction::FlagOnly,
+            pooled,
+            on_returns,
+        };
+        let flagged = |pooled, on_returns| {
+            let mut cleaner = DataCleaner::new();
+            cleaner.add_rule(rule(pooled, on_returns));
+            let (_, result) = cleaner.clean_with_data(data.clone()).unwrap();
+            result.outliers.iter().map(|f| f.index).collect::<Vec<_>>()
+        };
+
+        assert!(flagged(true, false).starts_with(&[0, 1, 2]));
+        assert_eq!(flagged(false, false), vec![5, 12]);
+        // 按收益率检测：跳涨和次日回落都是异常收益
+        assert_eq!(flagged(false, true), vec![5, 6, 12, 13]);
+    }
+
+    #[test]
+    fn test_clean_minute_bars() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(9, 31, 0)
+            .unwrap();
+        let minute = |offset: i64| TDXMinuteRecord {
+            datetime: start + chrono::Duration::minutes(offset),
+            symbol: "600000".to_string(),
+            open: 10.0,
+            high: 9.9,
+            low: 10.1,
+            close: 10.0,
+            volume: 100,
+            amount: 1000.0,
+            market: "SH".to_string(),
+        };
+        let data = vec![minute(0), minute(1), minute(1)];
+
+        // 同一交易日的不同分钟不视为重复
+        let (_, result) = DataCleaner::default().clean_with_data(data).unwrap();
+        assert_eq!(result.cleaned_count, 2);
+        assert_eq!(result.statistics.duplicates_removed, 1);
+        assert_eq!(result.statistics.price_inconsistencies, 3);
+    }
+
+    #[test]
+    fn test_fill_methods() {
+        let closes = [10.0, 0.0, f64::NAN, 16.0, 0.0];
+        let mut data: Vec<TDXDayRecord> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| {
+                let mut record = create_test_record("600000", &format!("2024-01-{:02}", i + 2));
+                record.close = close;
+                record
+            })
+            .collect();
+        // 输入顺序打乱，填充仍按日期
+        data.reverse();
+
+        let fill = |method| {
+            let mut cleaner = DataCleaner::new();
+            cleaner.add_rule(CleaningRule::FillMissing {
+                field: Field::Close,
+                method,
+            });
+            let (mut filled, result) = cleaner.clean_with_data(data.clone()).unwrap();
+            filled.sort_by_key(|r| r.date);
+            (filled.iter().map(|r| r.close).collect::<Vec<_>>(), result)
+        };
+
+        let (closes, result) = fill(FillMethod::BackwardFill);
+        assert_eq!(&closes[..4], &[10.0, 16.0, 16.0, 16.0]);
+        assert_eq!(closes[4], 0.0);
+        assert_eq!(result.statistics.missing_values_filled, 2);
+
+        let (closes, _) = fill(FillMethod::Interpolate);
+        assert_eq!(&closes[..4], &[10.0, 12.0, 14.0, 16.0]);
+
+        let (closes, _) = fill(FillMethod::Median);
+        assert_eq!(closes, vec![10.0, 13.0, 13.0, 16.0, 13.0]);
+
+        let (closes, _) = fill(FillMethod::Value(1.0));
+        assert_eq!(closes, vec![10.0, 1.0, 1.0, 16.0, 1.0]);
+
+        let (closes, result) = fill(FillMethod::Drop);
+        assert_eq!(closes, vec![10.0, 16.0]);
+        assert_eq!(result.statistics.missing_values_dropped, 3);
+        assert_eq!(result.removed_count, 3);
+    }
+
+    #[test]
+    fn test_forward_fill_stops_at_suspension() {
+        let day = |d: u32, close: f64| TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, d).unwrap(),
+            symbol: "600000".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: 10_000.0,
+            market: "SH".to_string(),
+        };
+        let data = vec![day(2, 10.0), day(3, 0.0), day(8, 0.0)];
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(CleaningRule::FillMissing {
+            field: Field::Close,
+            method: FillMethod::ForwardFill,
+        });
+        cleaner.set_suspensions(vec![SuspensionPeriod {
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            start: NaiveDate::from_ymd_opt(2024, 1, 4).unwrap(),
+            end: NaiveDate::from_ymd_opt(2024, 1, 5).unwrap(),
+            trading_days: 2,
+        }]);
+
+        // 01-03 在停牌前，可沿用01-02的收盘价；01-08 为复牌日，不跨停牌填充
+        let (_, result) = cleaner.clean_with_data(data).unwrap();
+        assert_eq!(result.statistics.missing_values_filled, 1);
+    }
+
+    #[test]
+    fn test_clean_with_lineage() {
+        use crate::processors::lineage::LineageSource;
+
+        let mut data = vec![
+            create_test_record("600000", "2024-01-02"),
+            create_test_record("600000", "2024-01-02"),
+            create_test_record("600000", "2024-01-03"),
+            create_test_record("600000", "2024-01-04"),
+        ];
+        data[2].close = f64::NAN;
+        let mut lineage = Lineage::from_source(LineageSource::new("a.day"), 3);
+        lineage.extend(Lineage::from_source(LineageSource::new("b.day"), 1));
+
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(CleaningRule::RemoveDuplicates {
+            keys: Vec::new(),
+            keep: KeepPolicy::First,
+        });
+        cleaner.add_rule(CleaningRule::FillMissing {
+            field: Field::Close,
+            method: FillMethod::Value(1.0),
+        });
+        let (cleaned, result, lineage) =
+            cleaner.clean_with_lineage(data.clone(), &lineage).unwrap();
+
+        assert_eq!(cleaned.len(), 3);
+        assert_eq!(lineage.len(), 3);
+        assert_eq!(lineage.steps(), &result.applied_rules[..]);
+        let filled = cleaned.iter().position(|r| r.close == 1.0).unwrap();
+        assert_eq!(
+            lineage.trace(filled).unwrap().steps,
+            vec!["FillMissing(close)"]
+        );
+        let last = cleaned
+            .iter()
+            .position(|r| r.date.to_string() == "2024-01-04")
+            .unwrap();
+        let trace = lineage.trace(last).unwrap();
+        assert_eq!(trace.source.location, "b.day");
+        assert!(trace.steps.is_empty());
+
+        assert!(cleaner.clean_with_lineage(data, &Lineage::new()).is_err());
+    }
 }
@@ -0,0 +1,446 @@
+//! 列式数据模块
+//!
+//! `ColumnarFrame` 以结构体数组（struct-of-arrays）形式存放K线数据：
+//! 时间、开高低收等字段各自为连续的 `Vec`，股票代码做字典编码，
+//! 避免处理器反复克隆 `Vec<TDXDayRecord>` 和逐条记录持有 `String`。
+//! 任意实现 `Bar` 的记录（日线、分钟线）都可以转换为列式数据。
+
+use crate::parsers::{Bar, TDXDayRecord};
+use crate::processors::calculator::IndicatorCalculator;
+use crate::processors::field::Field;
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// 列引用，统一以f64读取
+#[derive(Debug, Clone, Copy)]
+pub enum ColumnRef<'a> {
+    /// 浮点列
+    Float(&'a [f64]),
+    /// 整数列（成交量）
+    Integer(&'a [u64]),
+}
+
+impl ColumnRef<'_> {
+    /// 读取指定行的值
+    #[inline]
+    pub fn get(&self, row: usize) -> f64 {
+        match self {
+            ColumnRef::Float(values) => values[row],
+            ColumnRef::Integer(values) => values[row] as f64,
+        }
+    }
+
+    /// 按行索引收集值
+    pub fn gather(&self, rows: &[usize]) -> Vec<f64> {
+        rows.iter().map(|&row| self.get(row)).collect()
+    }
+}
+
+/// 列式K线数据
+#[derive(Debug, Clone, Default)]
+pub struct ColumnarFrame {
+    /// 字典：编码 -> 股票代码
+    symbols: Vec<String>,
+    /// 字典：编码 -> 市场
+    markets: Vec<String>,
+    /// 字典索引：股票代码 -> 编码（同代码不同市场对应多个编码）
+    dictionary: HashMap<String, Vec<u32>>,
+    /// 每行的股票编码
+    symbol_ids: Vec<u32>,
+    /// K线时间（日线为当日零点）
+    timestamps: Vec<NaiveDateTime>,
+    /// 开盘价
+    opens: Vec<f64>,
+    /// 最高价
+    highs: Vec<f64>,
+    /// 最低价
+    lows: Vec<f64>,
+    /// 收盘价
+    closes: Vec<f64>,
+    /// 成交量
+    volumes: Vec<u64>,
+    /// 成交额
+    amounts: Vec<f64>,
+    /// 技术指标列（指标名 -> 值，未形成为NaN）
+    indicators: HashMap<String, Vec<f64>>,
+}
+
+impl ColumnarFrame {
+    /// 创建空的列式数据
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建预分配容量的列式数据
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            symbol_ids: Vec::with_capacity(capacity),
+            timestamps: Vec::with_capacity(capacity),
+            opens: Vec::with_capacity(capacity),
+            highs: Vec::with_capacity(capacity),
+            lows: Vec::with_capacity(capacity),
+            closes: Vec::with_capacity(capacity),
+            volumes: Vec::with_capacity(capacity),
+            amounts: Vec::with_capacity(capacity),
+            ..Self::default()
+        }
+    }
+
+    /// 从记录列表转换
+    pub fn from_records<B: Bar>(records: &[B]) -> Self {
+        let mut frame = Self::with_capacity(records.len());
+        for record in records {
+            frame.push(record);
+        }
+        frame
+    }
+
+    /// 追加一条记录
+    pub fn push<B: Bar>(&mut self, record: &B) {
+        let id = self.intern(record.symbol(), record.market());
+        self.symbol_ids.push(id);
+        self.timestamps.push(record.timestamp());
+        self.opens.push(record.open());
+        self.highs.push(record.high());
+        self.lows.push(record.low());
+        self.closes.push(record.close());
+        self.volumes.push(record.volume());
+        self.amounts.push(record.amount());
+    }
+
+    /// 转换回日线记录列表（分钟线只保留日期）
+    pub fn to_records(&self) -> Vec<TDXDayRecord> {
+        (0..self.len()).map(|row| self.record(row)).collect()
+    }
+
+    /// 还原单行日线记录
+    pub fn record(&self, row: usize) -> TDXDayRecord {
+        let id = self.symbol_ids[row] as usize;
+        TDXDayRecord {
+            date: self.date(row),
+            symbol: self.symbols[id].clone(),
+            open: self.opens[row],
+            high: self.highs[row],
+            low: self.lows[row],
+            close: self.closes[row],
+            volume: self.volumes[row],
+            amount: self.amounts[row],
+            market: self.markets[id].clone(),
+        }
+    }
+
+    /// 行数
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// 字典大小（不同股票数）
+    pub fn symbol_count(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// 根据编码获取股票代码
+    pub fn symbol(&self, id: u32) -> &str {
+        &self.symbols[id as usize]
+    }
+
+    /// 根据编码获取市场
+    pub fn market(&self, id: u32) -> &str {
+        &self.markets[id as usize]
+    }
+
+    /// 指定行的股票代码
+    pub fn symbol_at(&self, row: usize) -> &str {
+        self.symbol(self.symbol_ids[row])
+    }
+
+    /// 指定行的市场
+    pub fn market_at(&self, row: usize) -> &str {
+        self.market(self.symbol_ids[row])
+    }
+
+    /// 股票编码列
+    pub fn symbol_ids(&self) -> &[u32] {
+        &self.symbol_ids
+    }
+
+    /// 时间列
+    pub fn timestamps(&self) -> &[NaiveDateTime] {
+        &self.timestamps
+    }
+
+    /// 指定行的交易日期
+    pub fn date(&self, row: usize) -> NaiveDate {
+        self.timestamps[row].date()
+    }
+
+    /// 开盘价列
+    pub fn opens(&self) -> &[f64] {
+        &self.opens
+    }
+
+    /// 最高价列
+    pub fn highs(&self) -> &[f64] {
+        &self.highs
+    }
+
+    /// 最低价列
+    pub fn lows(&self) -> &[f64] {
+        &self.lows
+    }
+
+    /// 收盘价列
+    pub fn closes(&self) -> &[f64] {
+        &self.closes
+    }
+
+    /// 成交量列
+    pub fn volumes(&self) -> &[u64] {
+        &self.volumes
+    }
+
+    /// 成交额列
+    pub fn amounts(&self) -> &[f64] {
+        &self.amounts
+    }
+
+    /// 获取数值列，指标列需先通过 `add_indicator_columns` 计算
+    pub fn column(&self, field: &Field) -> Result<ColumnRef<'_>> {
+        Ok(match field {
+            Field::Open => ColumnRef::Float(&self.opens),
+            Field::High => ColumnRef::Float(&self.highs),
+            Field::Low => ColumnRef::Float(&self.lows),
+            Field::Close => ColumnRef::Float(&self.closes),
+            Field::Volume => ColumnRef::Integer(&self.volumes),
+            Field::Amount => ColumnRef::Float(&self.amounts),
+            Field::Indicator(name) => ColumnRef::Float(
+                self.indicators
+                    .get(name)
+                    .ok_or_else(|| anyhow::anyhow!("指标列尚未计算: {}", name))?,
+            ),
+        })
+    }
+
+    /// 是否已包含该列
+    pub fn has_column(&self, field: &Field) -> bool {
+        match field {
+            Field::Indicator(name) => self.indicators.contains_key(name),
+            _ => true,
+        }
+    }
+
+    /// 计算并追加指标列，已存在的列不重复计算
+    pub fn add_indicator_columns(&mut self, fields: &[Field]) -> Result<()> {
+        self.add_indicator_columns_with(fields, &IndicatorCalculator::new())
+    }
+
+    /// 用指定的指标计算器（如带基准或证券主数据）计算并追加指标列
+    pub fn add_indicator_columns_with(
+        &mut self,
+        fields: &[Field],
+        calculator: &IndicatorCalculator,
+    ) -> Result<()> {
+        let missing: Vec<&str> = fields
+            .iter()
+            .filter(|field| field.is_indicator() && !self.has_column(field))
+            .map(Field::name)
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let values = calculator.calculate_columnar(self)?;
+        for name in missing {
+            let column = values
+                .iter()
+                .map(|row| row.get(name).unwrap_or(f64::NAN))
+                .collect();
+            self.indicators.insert(name.to_string(), column);
+        }
+        Ok(())
+    }
+
+    /// 数据指纹（全部行和已计算的指标列），用作结果缓存键
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.len().hash(&mut hasher);
+        for &id in &self.symbol_ids {
+            self.symbols[id as usize].hash(&mut hasher);
+            self.markets[id as usize].hash(&mut hasher);
+        }
+        self.timestamps.hash(&mut hasher);
+        self.volumes.hash(&mut hasher);
+        for column in [
+            &self.opens,
+            &self.highs,
+            &self.lows,
+            &self.closes,
+            &self.amounts,
+        ] {
+            column.iter().for_each(|v| v.to_bits().hash(&mut hasher));
+        }
+        let mut names: Vec<&String> = self.indicators.keys().collect();
+        names.sort();
+        for name in names {
+            name.hash(&mut hasher);
+            self.indicators[name]
+                .iter()
+                .for_each(|v| v.to_bits().hash(&mut hasher));
+        }
+        hasher.finish()
+    }
+
+    /// 按股票分组的行索引，组内按时间升序，组间按编码顺序
+    pub fn symbol_groups(&self) -> Vec<(u32, Vec<usize>)> {
+        let mut groups: Vec<Vec<usize>> = vec![Vec::new(); self.symbols.len()];
+        for (row, &id) in self.symbol_ids.iter().enumerate() {
+            groups[id as usize].push(row);
+        }
+
+        groups
+            .into_iter()
+            .enumerate()
+            .filter(|(_, rows)| !rows.is_empty())
+            .map(|(id, mut rows)| {
+                rows.sort_by_key(|&row| self.timestamps[row]);
+                (id as u32, rows)
+            })
+            .collect()
+    }
+
+    /// 按（股票代码, 时间）重排，使每只股票的数据连续存放
+    pub fn sort_by_symbol_and_date(&mut self) {
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.symbols[self.symbol_ids[a] as usize]
+                .cmp(&self.symbols[self.symbol_ids[b] as usize])
+                .then(self.symbol_ids[a].cmp(&self.symbol_ids[b]))
+                .then(self.timestamps[a].cmp(&self.timestamps[b]))
+        });
+
+        fn permute<T: Copy>(values: &[T], order: &[usize]) -> Vec<T> {
+            order.iter().map(|&i| values[i]).collect()
+        }
+
+        self.symbol_ids = permute(&self.symbol_ids, &order);
+        self.timestamps = permute(&self.timestamps, &order);
+        self.opens = permute(&self.opens, &order);
+        self.highs = permute(&self.highs, &order);
+        self.lows = permute(&self.lows, &order);
+        self.closes = permute(&self.closes, &order);
+        self.volumes = permute(&self.volumes, &order);
+        self.amounts = permute(&self.amounts, &order);
+        for column in self.indicators.values_mut() {
+            *column = permute(column, &order);
+        }
+    }
+
+    /// 字典编码
+    fn intern(&mut self, symbol: &str, market: &str) -> u32 {
+        if let Some(ids) = self.dictionary.get(symbol) {
+            if let Some(&id) = ids.iter().find(|&&id| self.markets[id as usize] == market) {
+                return id;
+            }
+        }
+
+        let id = self.symbols.len() as u32;
+        self.symbols.push(symbol.to_string());
+        self.markets.push(market.to_string());
+        self.dictionary
+            .entry(symbol.to_string())
+            .or_default()
+            .push(id);
+        id
+    }
+}
+
+impl<B: Bar> From<&[B]> for ColumnarFrame {
+    fn from(records: &[B]) -> Self {
+        Self::from_records(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, market: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 1000 * day as u64,
+            amount: close * 1000.0,
+            market: market.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let records = vec![
+            create_test_record("600000", "SH", 2, 10.0),
+            create_test_record("000001", "SZ", 2, 12.0),
+            create_test_record("600000", "SH", 3, 10.5),
+        ];
+
+        let frame = ColumnarFrame::from_records(&records);
+        assert_eq!(frame.len(), 3);
+        assert_eq!(frame.symbol_count(), 2);
+        assert_eq!(frame.symbol_ids(), &[0, 1, 0]);
+
+        let restored = frame.to_records();
+        assert_eq!(restored[1].symbol, "000001");
+        assert_eq!(restored[1].market, "SZ");
+        assert_eq!(restored[2].close, 10.5);
+        assert_eq!(restored[2].volume, 3000);
+    }
+
+    #[test]
+    fn test_symbol_dictionary_distinguishes_market() {
+        // 同代码不同市场（如上证指数与平安银行）应编码为不同股票
+        let records = vec![
+            create_test_record("000001", "SH", 2, 3000.0),
+            create_test_record("000001", "SZ", 2, 12.0),
+        ];
+        let frame = ColumnarFrame::from_records(&records);
+        assert_eq!(frame.symbol_count(), 2);
+        assert_eq!(frame.market_at(1), "SZ");
+    }
+
+    #[test]
+    fn test_groups_and_sort() {
+        let records = vec![
+            create_test_record("600036", "SH", 3, 20.5),
+            create_test_record("600000", "SH", 3, 10.5),
+            create_test_record("600036", "SH", 2, 20.0),
+            create_test_record("600000", "SH", 2, 10.0),
+        ];
+
+        let mut frame = ColumnarFrame::from_records(&records);
+        let groups = frame.symbol_groups();
+        assert_eq!(groups[0], (0, vec![2, 0]));
+
+        frame.sort_by_symbol_and_date();
+        assert_eq!(frame.symbol_at(0), "600000");
+        assert_eq!(frame.closes(), &[10.0, 10.5, 20.0, 20.5]);
+        assert_eq!(frame.column(&Field::Volume).unwrap().get(1), 3000.0);
+
+        let ma5 = Field::Indicator("ma5".to_string());
+        assert!(frame.column(&ma5).is_err());
+        frame
+            .add_indicator_columns(std::slice::from_ref(&ma5))
+            .unwrap();
+        assert!(frame.column(&ma5).unwrap().get(0).is_nan());
+    }
+}
@@ -0,0 +1,383 @@
+//! 相关性矩阵模块
+//!
+//! 对N只股票的收益率序列两两计算相关系数和协方差（rayon按股票对并行），
+//! 缺失日期按股票对内连接对齐，结果为带标签的矩阵，可导出为CSV或Arrow批次（`storage` 特性）。
+
+use crate::export::{ExportManifest, RotatingCsvWriter, RotationPolicy};
+use crate::parsers::TDXDayRecord;
+use crate::processors::performance::{DateSeries, PerformanceAnalyzer};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 带标签的对称矩阵
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabeledMatrix {
+    /// 行列标签（股票代码，按字典序）
+    pub labels: Vec<String>,
+    /// 矩阵值（行优先），无法计算的位置为NaN
+    pub values: Vec<Vec<f64>>,
+}
+
+impl LabeledMatrix {
+    /// 按标签获取矩阵元素
+    pub fn get(&self, row: &str, col: &str) -> Option<f64> {
+        let i = self.labels.iter().position(|label| label == row)?;
+        let j = self.labels.iter().position(|label| label == col)?;
+        Some(self.values[i][j])
+    }
+
+    /// 导出为CSV（首行首列为标签）
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("无法创建CSV文件: {}", path.display()))?;
+        writer.write_record(self.csv_header())?;
+        for line in self.csv_rows() {
+            writer.write_record(&line)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 按行数或字节数分片导出为CSV，每个分片都带标签表头
+    pub fn to_csv_parts<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        name: &str,
+        policy: &RotationPolicy,
+    ) -> Result<ExportManifest> {
+        let mut writer =
+            RotatingCsvWriter::create(dir, name, policy)?.with_header(self.csv_header());
+        for line in self.csv_rows() {
+            writer.write_record(&line)?;
+        }
+        writer.finish()
+    }
+
+    /// 转为Arrow批次：`label` 列为行标签，其后每个标签一列 `Float64`，布局与CSV相同
+    #[cfg(feature = "storage")]
+    pub fn to_record_batch(&self) -> Result<arrow_array::RecordBatch> {
+        use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray};
+        use arrow_schema::{DataType, Field as ArrowField, Schema};
+        use std::sync::Arc;
+
+        let mut fields = vec![ArrowField::new("label", DataType::Utf8, false)];
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(StringArray::from(self.labels.clone()))];
+        for (j, label) in self.labels.iter().enumerate() {
+            fields.push(ArrowField::new(label, DataType::Float64, false));
+            let column: Float64Array = self.values.iter().map(|row| Some(row[j])).collect();
+            columns.push(Arc::new(column));
+        }
+        Ok(RecordBatch::try_new(
+            Arc::new(Schema::new(fields)),
+            columns,
+        )?)
+    }
+
+    fn csv_header(&self) -> Vec<String> {
+        let mut header = vec![String::new()];
+        header.extend(self.labels.iter().cloned());
+        header
+    }
+
+    fn csv_rows(&self) -> impl Iterator<Item = Vec<String>> + '_ {
+        self.labels.iter().zip(&self.values).map(|(label, row)| {
+            let mut line = vec![label.clone()];
+            line.extend(row.iter().map(|value| value.to_string()));
+            line
+        })
+    }
+}
+
+/// 相关性计算结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationResult {
+    /// 相关系数矩阵
+    pub correlation: LabeledMatrix,
+    /// 协方差矩阵
+    pub covariance: LabeledMatrix,
+    /// 每个股票对参与计算的共同样本数
+    pub observations: LabeledMatrix,
+}
+
+/// 相关性矩阵计算器
+#[derive(Debug, Clone)]
+pub struct CorrelationCalculator {
+    /// 股票对最少共同样本数，不足时结果为NaN
+    min_observations: usize,
+}
+
+impl Default for CorrelationCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorrelationCalculator {
+    /// 创建新的相关性计算器
+    pub fn new() -> Self {
+        Self {
+            min_observations: 20,
+        }
+    }
+
+    /// 设置最少共同样本数
+    pub fn with_min_observations(mut self, min_observations: usize) -> Self {
+        self.min_observations = min_observations.max(2);
+        self
+    }
+
+    /// 基于日线记录计算收盘价收益率的相关性矩阵，标签为 `代码.市场`
+    pub fn calculate_from_records(&self, data: &[TDXDayRecord]) -> Result<CorrelationResult> {
+        let returns: HashMap<String, DateSeries> = PerformanceAnalyzer::close_series(data)
+            .into_iter()
+            .map(|((symbol, market), closes)| {
+                (
+                    format!("{}.{}", symbol, market),
+                    PerformanceAnalyzer::returns(&closes),
+                )
+            })
+            .collect();
+        self.calculate(&returns)
+    }
+
+    /// 基于收益率序列计算相关性矩阵
+    pub fn calculate(&self, returns: &HashMap<String, DateSeries>) -> Result<CorrelationResult> {
+        if returns.is_empty() {
+            return Err(anyhow::anyhow!("收益率序列为空"));
+        }
+
+        let mut labels: Vec<String> = returns.keys().cloned().collect();
+        labels.sort();
+
+        let series: Vec<DateSeries> = labels
+            .iter()
+            .map(|label| {
+                let mut s = returns[label].clone();
+                s.sort_by_key(|(date, _)| *date);
+                s
+            })
+            .collect();
+
+        // 上三角（含对角线）股票对并行计算
+        let n = labels.len();
+        let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i..n).map(move |j| (i, j))).collect();
+        let results: Vec<(usize, usize, PairStats)> = pairs
+            .into_par_iter()
+            .map(|(i, j)| (i, j, self.pair_stats(&series[i], &series[j])))
+            .collect();
+
+        let mut correlation = vec![vec![f64::NAN; n]; n];
+        let mut covariance = vec![vec![f64::NAN; n]; n];
+        let mut observations = vec![vec![0.0; n]; n];
+        for (i, j, stats) in results {
+            for (a, b) in [(i, j), (j, i)] {
+                correlation[a][b] = stats.correlation;
+                covariance[a][b] = stats.covariance;
+                observations[a][b] = stats.observations as f64;
+            }
+        }
+
+        Ok(CorrelationResult {
+            correlation: LabeledMatrix {
+                labels: labels.clone(),
+                values: correlation,
+            },
+            covariance: LabeledMatrix {
+                labels: labels.clone(),
+                values: covariance,
+            },
+            observations: LabeledMatrix {
+                labels,
+                values: observations,
+            },
+        })
+    }
+
+    /// 计算单个股票对的统计量（两序列均已按日期排序）
+    fn pair_stats(&self, a: &[(NaiveDate, f64)], b: &[(NaiveDate, f64)]) -> PairStats {
+        let aligned = Self::inner_join(a, b);
+        let n = aligned.len();
+        if n < self.min_observations {
+            return PairStats {
+                correlation: f64::NAN,
+                covariance: f64::NAN,
+                observations: n,
+            };
+        }
+
+        let count = n as f64;
+        let mean_a = aligned.iter().map(|(x, _)| x).sum::<f64>() / count;
+        let mean_b = aligned.iter().map(|(_, y)| y).sum::<f64>() / count;
+
+        let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+        for (x, y) in &aligned {
+            let (dx, dy) = (x - mean_a, y - mean_b);
+            cov += dx * dy;
+            var_a += dx * dx;
+            var_b += dy * dy;
+        }
+
+        let denominator = (var_a * var_b).sqrt();
+        PairStats {
+            correlation: if denominator > 0.0 {
+                cov / denominator
+            } else {
+                f64::NAN
+            },
+            covariance: cov / (count - 1.0),
+            observations: n,
+        }
+    }
+
+    /// 按日期内连接两个有序序列
+    fn inner_join(a: &[(NaiveDate, f64)], b: &[(NaiveDate, f64)]) -> Vec<(f64, f64)> {
+        let mut aligned = Vec::with_capacity(a.len().min(b.len()));
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].0.cmp(&b[j].0) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    if a[i].1.is_finite() && b[j].1.is_finite() {
+                        aligned.push((a[i].1, b[j].1));
+                    }
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        aligned
+    }
+}
+
+/// 股票对统计量
+struct PairStats {
+    correlation: f64,
+    covariance: f64,
+    observations: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn series(offset: i64, values: &[f64]) -> DateSeries {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| (start + chrono::Duration::days(offset + i as i64), v))
+            .collect()
+    }
+
+    #[test]
+    fn test_correlation_matrix() {
+        let mut returns = HashMap::new();
+        returns.insert("A".to_string(), series(0, &[0.01, -0.02, 0.03, 0.00]));
+        returns.insert("B".to_string(), series(0, &[0.02, -0.04, 0.06, 0.00]));
+        returns.insert("C".to_string(), series(0, &[-0.01, 0.02, -0.03, 0.00]));
+
+        let result = CorrelationCalculator::new()
+            .with_min_observations(3)
+            .calculate(&returns)
+            .unwrap();
+
+        let corr = &result.correlation;
+        assert_eq!(corr.labels, vec!["A", "B", "C"]);
+        assert!((corr.get("A", "A").unwrap() - 1.0).abs() < 1e-12);
+        assert!((corr.get("A", "B").unwrap() - 1.0).abs() < 1e-12);
+        assert!((corr.get("C", "A").unwrap() + 1.0).abs() < 1e-12);
+
+        // 协方差对称，B的方差是A的4倍
+        let cov = &result.covariance;
+        assert_eq!(cov.get("A", "B"), cov.get("B", "A"));
+        assert!((cov.get("B", "B").unwrap() - 4.0 * cov.get("A", "A").unwrap()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_inner_join_alignment() {
+        let mut returns = HashMap::new();
+        returns.insert("A".to_string(), series(0, &[0.01, -0.02, 0.03, 0.01, 0.02]));
+        // B缺少前两天，只有3个共同日期
+        returns.insert("B".to_string(), series(2, &[0.03, 0.01, 0.02]));
+
+        let calculator = CorrelationCalculator::new().with_min_observations(3);
+        let result = calculator.calculate(&returns).unwrap();
+        assert_eq!(result.observations.get("A", "B"), Some(3.0));
+        assert!((result.correlation.get("A", "B").unwrap() - 1.0).abs() < 1e-12);
+
+        // 共同样本不足时为NaN
+        let strict = CorrelationCalculator::new().with_min_observations(4);
+        let result = strict.calculate(&returns).unwrap();
+        assert!(result.correlation.get("A", "B").unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let mut returns = HashMap::new();
+        returns.insert("A".to_string(), series(0, &[0.01, -0.02, 0.03]));
+        returns.insert("B".to_string(), series(0, &[0.02, -0.01, 0.01]));
+
+        let result = CorrelationCalculator::new()
+            .with_min_observations(2)
+            .calculate(&returns)
+            .unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("corr.csv");
+        result.correlation.to_csv(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], ",A,B");
+        assert!(lines[1].starts_with("A,1,"));
+
+        let policy = RotationPolicy::new().with_max_rows(1);
+        let manifest = result
+            .covariance
+            .to_csv_parts(temp_dir.path(), "cov", &policy)
+            .unwrap();
+        assert_eq!(manifest.parts.len(), 2);
+        let second = std::fs::read_to_string(temp_dir.path().join("cov-00001.csv")).unwrap();
+        assert!(second.starts_with(",A,B\nB,"));
+    }
+
+    #[cfg(feature = "storage")]
+    #[test]
+    fn test_record_batch() {
+        use arrow_array::{Array, Float64Array, StringArray};
+
+        let matrix = LabeledMatrix {
+            labels: vec!["A".to_string(), "B".to_string()],
+            values: vec![vec![1.0, 0.5], vec![0.5, f64::NAN]],
+        };
+        let batch = matrix.to_record_batch().unwrap();
+        assert_eq!((batch.num_rows(), batch.num_columns()), (2, 3));
+        assert_eq!(batch.schema().field(2).name(), "B");
+        let labels = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(labels.value(1), "B");
+        let column = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(column.value(1), 0.5);
+        let column = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert!(column.value(1).is_nan() && column.null_count() == 0);
+    }
+}
@@ -0,0 +1,217 @@
+//! 截面处理模块
+//!
+//! 对同一日期全部股票的数值做截面变换：排名、百分位、z分数、按分位数缩尾和按阈值截断。
+//! 输入为平行的日期列与数值列（任意顺序），按日期排序的行索引分组后并行计算，
+//! 不复制记录；NaN不参与计算并原样保留。因子框架和机器学习特征标准化共用这些函数。
+
+use crate::processors::columnar::ColumnarFrame;
+use crate::processors::field::Field;
+use anyhow::Result;
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 截面变换
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum CrossSectionOp {
+    /// 升序名次（从1开始，并列取平均名次）
+    Rank,
+    /// 百分位（0~1，并列取平均名次，截面仅一个有效值时为0.5）
+    Percentile,
+    /// z分数（样本标准差，标准差为0时为0）
+    ZScore,
+    /// 按分位数缩尾，`lower`/`upper` 为0~1的分位点
+    Winsorize { lower: f64, upper: f64 },
+    /// 按固定阈值截断
+    Clip { min: f64, max: f64 },
+}
+
+impl CrossSectionOp {
+    /// 对单个截面的值做变换
+    pub fn apply(&self, values: &[f64]) -> Vec<f64> {
+        match *self {
+            CrossSectionOp::Rank => rank(values),
+            CrossSectionOp::Percentile => percentile(values),
+            CrossSectionOp::ZScore => zscore(values),
+            CrossSectionOp::Winsorize { lower, upper } => winsorize(values, lower, upper),
+            CrossSectionOp::Clip { min, max } => clip(values, min, max),
+        }
+    }
+}
+
+/// 按日期分组做截面变换，返回值与输入一一对应
+pub fn transform_by_date(dates: &[NaiveDate], values: &[f64], op: CrossSectionOp) -> Vec<f64> {
+    let len = dates.len().min(values.len());
+    let mut order: Vec<usize> = (0..len).collect();
+    order.par_sort_unstable_by_key(|&row| dates[row]);
+
+    let sections: Vec<(&[usize], Vec<f64>)> = order
+        .par_chunk_by(|&a, &b| dates[a] == dates[b])
+        .map(|rows| {
+            let section: Vec<f64> = rows.iter().map(|&row| values[row]).collect();
+            (rows, op.apply(&section))
+        })
+        .collect();
+
+    let mut output = vec![f64::NAN; len];
+    for (rows, transformed) in sections {
+        for (&row, value) in rows.iter().zip(transformed) {
+            output[row] = value;
+        }
+    }
+    output
+}
+
+/// 对列式数据的某一列按日期做截面变换
+pub fn transform_column(
+    frame: &ColumnarFrame,
+    field: &Field,
+    op: CrossSectionOp,
+) -> Result<Vec<f64>> {
+    let column = frame.column(field)?;
+    let dates: Vec<NaiveDate> = frame.timestamps().iter().map(|ts| ts.date()).collect();
+    let values: Vec<f64> = (0..frame.len()).map(|row| column.get(row)).collect();
+    Ok(transform_by_date(&dates, &values, op))
+}
+
+/// 有效值（非NaN）按升序排列的索引
+fn sorted_valid(values: &[f64]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..values.len()).filter(|&i| !values[i].is_nan()).collect();
+    order.sort_unstable_by(|&a, &b| values[a].total_cmp(&values[b]));
+    order
+}
+
+/// 升序名次（从1开始，并列取平均名次），NaN保持为NaN
+pub fn rank(values: &[f64]) -> Vec<f64> {
+    let order = sorted_valid(values);
+    let mut ranks = vec![f64::NAN; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let average = (start + end + 1) as f64 / 2.0;
+        for &index in &order[start..end] {
+            ranks[index] = average;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// 百分位（0~1），截面仅一个有效值时为0.5
+pub fn percentile(values: &[f64]) -> Vec<f64> {
+    let valid = values.iter().filter(|v| !v.is_nan()).count();
+    rank(values)
+        .into_iter()
+        .map(|r| match valid {
+            _ if r.is_nan() => f64::NAN,
+            1 => 0.5,
+            n => (r - 1.0) / (n - 1) as f64,
+        })
+        .collect()
+}
+
+/// z分数（样本标准差），标准差为0时为0
+pub fn zscore(values: &[f64]) -> Vec<f64> {
+    let valid: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+    let n = valid.len() as f64;
+    let mean = valid.iter().sum::<f64>() / n;
+    let std = if valid.len() > 1 {
+        (valid.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+    } else {
+        0.0
+    };
+    values
+        .iter()
+        .map(|&v| match v {
+            _ if v.is_nan() => f64::NAN,
+            _ if std > 0.0 => (v - mean) / std,
+            _ => 0.0,
+        })
+        .collect()
+}
+
+/// 按分位数缩尾（线性插值），超出分位值的数值被拉回分位值
+pub fn winsorize(values: &[f64], lower: f64, upper: f64) -> Vec<f64> {
+    let order = sorted_valid(values);
+    if order.is_empty() {
+        return values.to_vec();
+    }
+    let quantile = |q: f64| {
+        let position = q.clamp(0.0, 1.0) * (order.len() - 1) as f64;
+        let (below, above) = (position.floor() as usize, position.ceil() as usize);
+        let (a, b) = (values[order[below]], values[order[above]]);
+        a + (b - a) * (position - below as f64)
+    };
+    clip(values, quantile(lower), quantile(upper))
+}
+
+/// 按固定阈值截断，NaN保持为NaN
+pub fn clip(values: &[f64], min: f64, max: f64) -> Vec<f64> {
+    values
+        .iter()
+        .map(|&v| if v.is_nan() { v } else { v.max(min).min(max) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_kernels() {
+        let values = [3.0, 1.0, f64::NAN, 3.0, 2.0];
+        let ranks = rank(&values);
+        assert_eq!(ranks[1], 1.0);
+        assert_eq!(ranks[4], 2.0);
+        assert_eq!((ranks[0], ranks[3]), (3.5, 3.5));
+        assert!(ranks[2].is_nan());
+
+        let pct = percentile(&values);
+        assert_eq!((pct[1], pct[4], pct[0]), (0.0, 1.0 / 3.0, 5.0 / 6.0));
+        assert_eq!(percentile(&[7.0, f64::NAN])[0], 0.5);
+
+        let z = zscore(&values);
+        let sum: f64 = z.iter().filter(|v| !v.is_nan()).sum();
+        assert!(sum.abs() < 1e-12);
+        assert_eq!(zscore(&[2.0, 2.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_winsorize_and_clip() {
+        let values: Vec<f64> = (0..=10).map(|v| v as f64).chain([f64::NAN]).collect();
+        let winsorized = winsorize(&values, 0.1, 0.9);
+        assert_eq!(winsorized[0], 1.0);
+        assert_eq!(winsorized[5], 5.0);
+        assert_eq!(winsorized[10], 9.0);
+        assert!(winsorized[11].is_nan());
+
+        let clipped = clip(&values, 2.0, 3.0);
+        assert_eq!(&clipped[..5], &[2.0, 2.0, 2.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn test_transform_by_date() {
+        let d1 = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let d2 = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        // 两个日期交错排列
+        let dates = [d1, d2, d1, d2, d1];
+        let values = [10.0, 1.0, 30.0, 2.0, 20.0];
+
+        let pct = transform_by_date(&dates, &values, CrossSectionOp::Percentile);
+        assert_eq!(pct, vec![0.0, 0.0, 1.0, 1.0, 0.5]);
+
+        let clipped = transform_by_date(
+            &dates,
+            &values,
+            CrossSectionOp::Clip {
+                min: 1.5,
+                max: 25.0,
+            },
+        );
+        assert_eq!(clipped, vec![10.0, 1.5, 25.0, 2.0, 20.0]);
+    }
+}
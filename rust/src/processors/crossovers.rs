@@ -0,0 +1,437 @@
+//! 指标交叉与阈值事件检测模块
+//!
+//! 内置常用的交叉检测：任意两条均线（SMA/EMA）的金叉/死叉、MACD的DIF穿越零轴和信号线、
+//! RSI进出超买/超卖区间。检测结果是按股票、日期排序的 `CrossoverEvent`，
+//! 无需再逐条比较相邻的 `EnhancedDayRecord`。
+//!
+//! 均线由收盘价直接计算（周期任意），MACD和RSI取记录中已计算的指标。
+//! “上穿”指前一日不高于参考值、当日高于参考值，“下穿”反之；任一取值缺失时不产生事件。
+
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::processors::kernels;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 穿越方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CrossDirection {
+    /// 上穿
+    Up,
+    /// 下穿
+    Down,
+}
+
+/// 计算 `series` 相对 `reference` 的逐日穿越方向（首日没有前值，恒为None）
+pub fn crossings(series: &[f64], reference: &[f64]) -> Vec<Option<CrossDirection>> {
+    let spread: Vec<f64> = series
+        .iter()
+        .zip(reference)
+        .map(|(value, reference)| value - reference)
+        .collect();
+    let mut directions = vec![None; spread.len()];
+    for i in 1..spread.len() {
+        let (prev, cur) = (spread[i - 1], spread[i]);
+        if prev.is_nan() || cur.is_nan() {
+            continue;
+        }
+        if prev <= 0.0 && cur > 0.0 {
+            directions[i] = Some(CrossDirection::Up);
+        } else if prev >= 0.0 && cur < 0.0 {
+            directions[i] = Some(CrossDirection::Down);
+        }
+    }
+    directions
+}
+
+/// 均线
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovingAverage {
+    /// 简单移动平均
+    Sma(usize),
+    /// 指数移动平均
+    Ema(usize),
+}
+
+impl MovingAverage {
+    /// 均线名称，如 `ma5`、`ema12`
+    pub fn name(&self) -> String {
+        match self {
+            MovingAverage::Sma(period) => format!("ma{}", period),
+            MovingAverage::Ema(period) => format!("ema{}", period),
+        }
+    }
+
+    /// 由收盘价计算均线，不足一个周期的位置为NaN
+    pub fn compute(&self, closes: &[f64]) -> Vec<f64> {
+        match *self {
+            MovingAverage::Sma(period) => kernels::rolling_mean(closes, period.max(1)),
+            MovingAverage::Ema(period) => {
+                // EMA以首个值为初值，前 `period - 1` 个位置尚未收敛，不参与交叉判断
+                let mut values = kernels::ema(closes, period.max(1));
+                for value in values.iter_mut().take(period.saturating_sub(1)) {
+                    *value = f64::NAN;
+                }
+                values
+            }
+        }
+    }
+}
+
+/// 交叉检测项
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CrossDetector {
+    /// 两条均线的金叉（快线上穿慢线）与死叉（快线下穿慢线）
+    MovingAverage {
+        /// 快线
+        fast: MovingAverage,
+        /// 慢线
+        slow: MovingAverage,
+    },
+    /// MACD的DIF穿越零轴
+    MacdZero,
+    /// MACD的DIF穿越信号线
+    MacdSignal,
+    /// RSI进出超买/超卖区间
+    RsiThreshold {
+        /// 超买阈值
+        overbought: f64,
+        /// 超卖阈值
+        oversold: f64,
+    },
+}
+
+impl CrossDetector {
+    /// 检测项名称，如 `ma5/ma20`、`macd_zero`、`rsi_70_30`
+    pub fn name(&self) -> String {
+        match self {
+            CrossDetector::MovingAverage { fast, slow } => {
+                format!("{}/{}", fast.name(), slow.name())
+            }
+            CrossDetector::MacdZero => "macd_zero".to_string(),
+            CrossDetector::MacdSignal => "macd_signal".to_string(),
+            CrossDetector::RsiThreshold {
+                overbought,
+                oversold,
+            } => format!("rsi_{}_{}", overbought, oversold),
+        }
+    }
+
+    /// 对单只股票按日期排序的序列检测，返回（序号, 事件类型, 取值, 参考值）
+    fn detect(&self, records: &[EnhancedDayRecord]) -> Vec<(usize, CrossoverKind, f64, f64)> {
+        let mut hits = Vec::new();
+        let mut collect =
+            |series: &[f64], reference: &[f64], up: CrossoverKind, down: CrossoverKind| {
+                for (i, direction) in crossings(series, reference).into_iter().enumerate() {
+                    let kind = match direction {
+                        Some(CrossDirection::Up) => up,
+                        Some(CrossDirection::Down) => down,
+                        None => continue,
+                    };
+                    hits.push((i, kind, series[i], reference[i]));
+                }
+            };
+
+        match self {
+            CrossDetector::MovingAverage { fast, slow } => {
+                let closes: Vec<f64> = records.iter().map(|r| r.base_record.close).collect();
+                collect(
+                    &fast.compute(&closes),
+                    &slow.compute(&closes),
+                    CrossoverKind::GoldenCross,
+                    CrossoverKind::DeathCross,
+                );
+            }
+            CrossDetector::MacdZero | CrossDetector::MacdSignal => {
+                let macd = |f: fn(&crate::processors::calculator::MACD) -> f64| -> Vec<f64> {
+                    records
+                        .iter()
+                        .map(|r| r.indicators.macd.as_ref().map_or(f64::NAN, f))
+                        .collect()
+                };
+                let dif = macd(|m| m.dif);
+                if *self == CrossDetector::MacdZero {
+                    collect(
+                        &dif,
+                        &vec![0.0; dif.len()],
+                        CrossoverKind::MacdAboveZero,
+                        CrossoverKind::MacdBelowZero,
+                    );
+                } else {
+                    collect(
+                        &dif,
+                        &macd(|m| m.signal),
+                        CrossoverKind::MacdBullish,
+                        CrossoverKind::MacdBearish,
+                    );
+                }
+            }
+            CrossDetector::RsiThreshold {
+                overbought,
+                oversold,
+            } => {
+                let rsi: Vec<f64> = records
+                    .iter()
+                    .map(|r| r.indicators.rsi.unwrap_or(f64::NAN))
+                    .collect();
+                collect(
+                    &rsi,
+                    &vec![*overbought; rsi.len()],
+                    CrossoverKind::RsiOverbought,
+                    CrossoverKind::RsiLeaveOverbought,
+                );
+                collect(
+                    &rsi,
+                    &vec![*oversold; rsi.len()],
+                    CrossoverKind::RsiLeaveOversold,
+                    CrossoverKind::RsiOversold,
+                );
+            }
+        }
+        hits
+    }
+}
+
+/// 交叉事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CrossoverKind {
+    /// 金叉：快线上穿慢线
+    GoldenCross,
+    /// 死叉：快线下穿慢线
+    DeathCross,
+    /// DIF上穿零轴
+    MacdAboveZero,
+    /// DIF下穿零轴
+    MacdBelowZero,
+    /// DIF上穿信号线
+    MacdBullish,
+    /// DIF下穿信号线
+    MacdBearish,
+    /// RSI上穿超买阈值
+    RsiOverbought,
+    /// RSI下穿超买阈值（离开超买区）
+    RsiLeaveOverbought,
+    /// RSI下穿超卖阈值
+    RsiOversold,
+    /// RSI上穿超卖阈值（离开超卖区）
+    RsiLeaveOversold,
+}
+
+/// 交叉事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrossoverEvent {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 日期
+    pub date: NaiveDate,
+    /// 事件类型
+    pub kind: CrossoverKind,
+    /// 产生事件的检测项名称
+    pub detector: String,
+    /// 当日取值（快线、DIF或RSI）
+    pub value: f64,
+    /// 当日参考值（慢线、信号线、零轴或阈值）
+    pub reference: f64,
+}
+
+/// 交叉事件检测器
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossoverDetector {
+    detectors: Vec<CrossDetector>,
+}
+
+/// 默认检测MA5/MA20金叉死叉、MACD零轴与信号线交叉、RSI 70/30阈值
+impl Default for CrossoverDetector {
+    fn default() -> Self {
+        Self {
+            detectors: vec![
+                CrossDetector::MovingAverage {
+                    fast: MovingAverage::Sma(5),
+                    slow: MovingAverage::Sma(20),
+                },
+                CrossDetector::MacdZero,
+                CrossDetector::MacdSignal,
+                CrossDetector::RsiThreshold {
+                    overbought: 70.0,
+                    oversold: 30.0,
+                },
+            ],
+        }
+    }
+}
+
+impl CrossoverDetector {
+    /// 创建带默认检测项的检测器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建没有检测项的检测器
+    pub fn empty() -> Self {
+        Self {
+            detectors: Vec::new(),
+        }
+    }
+
+    /// 添加检测项
+    pub fn with_detector(mut self, detector: CrossDetector) -> Self {
+        self.detectors.push(detector);
+        self
+    }
+
+    /// 已添加的检测项
+    pub fn detectors(&self) -> &[CrossDetector] {
+        &self.detectors
+    }
+
+    /// 检测数据集中的交叉事件（按股票、日期排序）
+    pub fn detect(&self, data: &[EnhancedDayRecord]) -> Result<Vec<CrossoverEvent>> {
+        let mut groups: HashMap<(&str, &str), Vec<&EnhancedDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((
+                    record.base_record.symbol.as_str(),
+                    record.base_record.market.as_str(),
+                ))
+                .or_default()
+                .push(record);
+        }
+
+        let mut events = Vec::new();
+        for records in groups.into_values() {
+            let mut records: Vec<EnhancedDayRecord> = records.into_iter().cloned().collect();
+            records.sort_by_key(|r| r.base_record.date);
+
+            for detector in &self.detectors {
+                let name = detector.name();
+                for (i, kind, value, reference) in detector.detect(&records) {
+                    let record = &records[i].base_record;
+                    events.push(CrossoverEvent {
+                        symbol: record.symbol.clone(),
+                        market: record.market.clone(),
+                        date: record.date,
+                        kind,
+                        detector: name.clone(),
+                        value,
+                        reference,
+                    });
+                }
+            }
+        }
+
+        events.sort_by(|a, b| {
+            a.symbol
+                .cmp(&b.symbol)
+                .then(a.market.cmp(&b.market))
+                .then(a.date.cmp(&b.date))
+        });
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use crate::processors::calculator::{IndicatorValues, MACD};
+
+    fn record(day: u32, close: f64, dif: f64, signal: f64, rsi: f64) -> EnhancedDayRecord {
+        EnhancedDayRecord {
+            base_record: TDXDayRecord {
+                date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+                symbol: "600000".to_string(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1000,
+                amount: close * 1000.0,
+                market: "SH".to_string(),
+            },
+            indicators: IndicatorValues {
+                rsi: Some(rsi),
+                macd: Some(MACD {
+                    dif,
+                    signal,
+                    histogram: dif - signal,
+                }),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_crossings() {
+        let series = [1.0, 2.0, 3.0, 2.0, f64::NAN, 0.0];
+        let reference = [2.0; 6];
+        assert_eq!(
+            crossings(&series, &reference),
+            vec![None, None, Some(CrossDirection::Up), None, None, None]
+        );
+        assert_eq!(
+            crossings(&[3.0, 1.0], &[2.0, 2.0]),
+            vec![None, Some(CrossDirection::Down)]
+        );
+
+        let ema = MovingAverage::Ema(3).compute(&[1.0, 2.0, 3.0, 4.0]);
+        assert!(ema[0].is_nan() && ema[1].is_nan());
+        assert!(ema[2].is_finite());
+        assert_eq!(MovingAverage::Ema(12).name(), "ema12");
+    }
+
+    #[test]
+    fn test_moving_average_cross() {
+        // 先跌后涨：MA2上穿MA3产生金叉，随后回落产生死叉
+        let closes = [10.0, 9.0, 8.0, 7.0, 9.0, 11.0, 12.0, 8.0, 6.0];
+        let records: Vec<EnhancedDayRecord> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| record(i as u32 + 2, close, 0.0, 0.0, 50.0))
+            .collect();
+        let detector = CrossoverDetector::empty().with_detector(CrossDetector::MovingAverage {
+            fast: MovingAverage::Sma(2),
+            slow: MovingAverage::Sma(3),
+        });
+        let events = detector.detect(&records).unwrap();
+        let kinds: Vec<CrossoverKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![CrossoverKind::GoldenCross, CrossoverKind::DeathCross]
+        );
+        assert_eq!(events[0].detector, "ma2/ma3");
+        assert_eq!(events[0].date, NaiveDate::from_ymd_opt(2024, 1, 7).unwrap());
+        assert!(events[0].value > events[0].reference);
+    }
+
+    #[test]
+    fn test_macd_and_rsi_events() {
+        // 乱序输入，检测时按日期排序
+        let records = vec![
+            record(4, 10.0, 0.5, 0.2, 75.0),
+            record(2, 10.0, -0.5, -0.2, 25.0),
+            record(3, 10.0, -0.1, -0.3, 40.0),
+            record(5, 10.0, 0.1, 0.3, 60.0),
+        ];
+        let events = CrossoverDetector::new().detect(&records).unwrap();
+        let kinds: Vec<(u32, CrossoverKind)> = events
+            .iter()
+            .map(|e| (chrono::Datelike::day(&e.date), e.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (3, CrossoverKind::MacdBullish),
+                (3, CrossoverKind::RsiLeaveOversold),
+                (4, CrossoverKind::MacdAboveZero),
+                (4, CrossoverKind::RsiOverbought),
+                (5, CrossoverKind::MacdBearish),
+                (5, CrossoverKind::RsiLeaveOverbought),
+            ]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! 数据集比对模块
+//!
+//! 比较两份数据（快照与快照、本地文件与ClickHouse查询结果等），
+//! 按股票列出新增、删除和变化的K线及变化字段，用于确认重新下载或迁移后历史数据未被悄悄改动。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::field::Field;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 字段变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// 字段
+    pub field: Field,
+    /// 旧值
+    pub old: f64,
+    /// 新值
+    pub new: f64,
+}
+
+/// 单根K线的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarChange {
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 发生变化的字段
+    pub fields: Vec<FieldChange>,
+}
+
+/// 单只股票的差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolDiff {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 新数据中新增的日期
+    pub added: Vec<NaiveDate>,
+    /// 新数据中缺失的日期
+    pub removed: Vec<NaiveDate>,
+    /// 发生变化的K线
+    pub changed: Vec<BarChange>,
+}
+
+/// 数据集差异
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatasetDiff {
+    /// 存在差异的股票，按（股票代码, 市场）排序
+    pub symbols: Vec<SymbolDiff>,
+    /// 新增K线数
+    pub added: usize,
+    /// 删除K线数
+    pub removed: usize,
+    /// 变化K线数
+    pub changed: usize,
+    /// 一致的K线数
+    pub unchanged: usize,
+}
+
+impl DatasetDiff {
+    /// 两份数据是否一致
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// 追加有差异的股票
+    fn push(&mut self, symbol: Option<SymbolDiff>) {
+        if let Some(symbol) =
+            symbol.filter(|s| !s.added.is_empty() || !s.removed.is_empty() || !s.changed.is_empty())
+        {
+            self.symbols.push(symbol);
+        }
+    }
+}
+
+/// 数据集比对器
+#[derive(Debug, Clone)]
+pub struct DatasetDiffer {
+    /// 价格容差（元）
+    price_tolerance: f64,
+    /// 成交额相对容差
+    amount_tolerance: f64,
+}
+
+impl Default for DatasetDiffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatasetDiffer {
+    /// 创建比对器，默认要求完全一致
+    pub fn new() -> Self {
+        Self {
+            price_tolerance: 0.0,
+            amount_tolerance: 0.0,
+        }
+    }
+
+    /// 设置价格容差（元）
+    pub fn with_price_tolerance(mut self, tolerance: f64) -> Self {
+        self.price_tolerance = tolerance;
+        self
+    }
+
+    /// 设置成交额相对容差
+    pub fn with_amount_tolerance(mut self, tolerance: f64) -> Self {
+        self.amount_tolerance = tolerance;
+        self
+    }
+
+    /// 比较旧数据与新数据
+    pub fn diff(&self, old: &[TDXDayRecord], new: &[TDXDayRecord]) -> Result<DatasetDiff> {
+        type Key<'a> = (&'a str, &'a str, NaiveDate);
+        let mut pairs: BTreeMap<Key, (Option<&TDXDayRecord>, Option<&TDXDayRecord>)> =
+            BTreeMap::new();
+        for record in old {
+            let slot = pairs
+                .entry((&record.symbol, &record.market, record.date))
+                .or_default();
+            if slot.0.replace(record).is_some() {
+                return Err(anyhow::anyhow!(
+                    "旧数据存在重复记录: {}.{} {}",
+                    record.symbol,
+                    record.market,
+                    record.date
+                ));
+            }
+        }
+        for record in new {
+            let slot = pairs
+                .entry((&record.symbol, &record.market, record.date))
+                .or_default();
+            if slot.1.replace(record).is_some() {
+                return Err(anyhow::anyhow!(
+                    "新数据存在重复记录: {}.{} {}",
+                    record.symbol,
+                    record.market,
+                    record.date
+                ));
+            }
+        }
+
+        let mut diff = DatasetDiff::default();
+        let mut current: Option<SymbolDiff> = None;
+        for ((symbol, market, date), pair) in pairs {
+            let same_symbol = current
+                .as_ref()
+                .is_some_and(|s| s.symbol == symbol && s.market == market);
+            if !same_symbol {
+                diff.push(current.take());
+                current = Some(SymbolDiff {
+                    symbol: symbol.to_string(),
+                    market: market.to_string(),
+                    added: Vec::new(),
+                    removed: Vec::new(),
+                    changed: Vec::new(),
+                });
+            }
+            let entry = current.as_mut().expect("当前股票已初始化");
+
+            match pair {
+                (None, Some(_)) => {
+                    entry.added.push(date);
+                    diff.added += 1;
+                }
+                (Some(_), None) => {
+                    entry.removed.push(date);
+                    diff.removed += 1;
+                }
+                (Some(a), Some(b)) => {
+                    let fields = self.changed_fields(a, b)?;
+                    if fields.is_empty() {
+                        diff.unchanged += 1;
+                    } else {
+                        entry.changed.push(BarChange { date, fields });
+                        diff.changed += 1;
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        diff.push(current);
+
+        Ok(diff)
+    }
+
+    /// 超出容差的字段
+    fn changed_fields(&self, a: &TDXDayRecord, b: &TDXDayRecord) -> Result<Vec<FieldChange>> {
+        let mut changes = Vec::new();
+        for field in Field::BASE {
+            let (old, new) = (field.value(a)?, field.value(b)?);
+            let changed = match field {
+                Field::Volume => a.volume != b.volume,
+                Field::Amount => {
+                    let scale = old.abs().max(new.abs()).max(1.0);
+                    (old - new).abs() / scale > self.amount_tolerance
+                }
+                _ => (old - new).abs() > self.price_tolerance,
+            };
+            if changed {
+                changes.push(FieldChange { field, old, new });
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff_added_removed_changed() {
+        let old = vec![
+            create_test_record("600000", 2, 10.0),
+            create_test_record("600000", 3, 10.2),
+            create_test_record("600001", 2, 5.0),
+        ];
+        let mut changed = create_test_record("600000", 3, 10.2);
+        changed.close = 10.3;
+        let new = vec![
+            create_test_record("600000", 2, 10.0),
+            changed,
+            create_test_record("600000", 4, 10.5),
+        ];
+
+        let diff = DatasetDiffer::new().diff(&old, &new).unwrap();
+        assert_eq!((diff.added, diff.removed, diff.changed), (1, 1, 1));
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.symbols.len(), 2);
+
+        let first = &diff.symbols[0];
+        assert_eq!(first.symbol, "600000");
+        assert_eq!(
+            first.added,
+            vec![NaiveDate::from_ymd_opt(2024, 1, 4).unwrap()]
+        );
+        assert_eq!(first.changed[0].fields.len(), 1);
+        assert_eq!(first.changed[0].fields[0].field, Field::Close);
+        assert_eq!(first.changed[0].fields[0].new, 10.3);
+        assert_eq!(diff.symbols[1].removed.len(), 1);
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let old = vec![create_test_record("600000", 2, 10.0)];
+        let mut new = old.clone();
+        new[0].amount += 0.5;
+
+        assert!(!DatasetDiffer::new().diff(&old, &new).unwrap().is_empty());
+        let diff = DatasetDiffer::new()
+            .with_amount_tolerance(0.001)
+            .diff(&old, &new)
+            .unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn test_duplicate_keys_rejected() {
+        let old = vec![
+            create_test_record("600000", 2, 10.0),
+            create_test_record("600000", 2, 10.1),
+        ];
+        assert!(DatasetDiffer::new().diff(&old, &[]).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! 表达式规则模块
+//!
+//! 基于evalexpr的记录级布尔表达式，用于自定义聚合和清洗规则，例如：
+//! `close > ma20 && volume > 2 * volume_ma5`
+
+use crate::parsers::Bar;
+use crate::processors::calculator::{IndicatorCalculator, IndicatorValues};
+use crate::processors::columnar::ColumnarFrame;
+use crate::processors::field::{Field, INDICATOR_NAMES};
+use anyhow::Result;
+use evalexpr::{
+    build_operator_tree, ContextWithMutableVariables, DefaultNumericTypes, HashMapContext, Node,
+    Value,
+};
+
+/// 记录表达式
+///
+/// 表达式在创建时解析并校验变量名，求值时按记录绑定变量。
+/// 指标尚未形成（窗口不足）时对应变量取NaN，与其比较的结果为false。
+#[derive(Debug, Clone)]
+pub struct RecordExpr {
+    /// 表达式原文
+    source: String,
+    /// 预编译的语法树
+    node: Node<DefaultNumericTypes>,
+    /// 是否引用了技术指标变量
+    needs_indicators: bool,
+}
+
+impl RecordExpr {
+    /// 解析表达式
+    pub fn parse(expr: &str) -> Result<Self> {
+        let node = build_operator_tree::<DefaultNumericTypes>(expr)
+            .map_err(|e| anyhow::anyhow!("表达式解析失败 `{}`: {}", expr, e))?;
+
+        let mut needs_indicators = false;
+        for identifier in node.iter_variable_identifiers() {
+            match identifier.parse::<Field>() {
+                Ok(field) => needs_indicators |= field.is_indicator(),
+                Err(_) => return Err(anyhow::anyhow!("表达式中存在未知变量: {}", identifier)),
+            }
+        }
+
+        Ok(Self {
+            source: expr.to_string(),
+            node,
+            needs_indicators,
+        })
+    }
+
+    /// 表达式原文
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 是否需要技术指标
+    pub fn needs_indicators(&self) -> bool {
+        self.needs_indicators
+    }
+
+    /// 对单条记录求值
+    pub fn evaluate<B: Bar>(
+        &self,
+        record: &B,
+        indicators: Option<&IndicatorValues>,
+    ) -> Result<bool> {
+        let mut context = HashMapContext::<DefaultNumericTypes>::new();
+        let base = [
+            record.open(),
+            record.high(),
+            record.low(),
+            record.close(),
+            record.volume() as f64,
+            record.amount(),
+        ];
+        self.evaluate_in(&mut context, base, indicators)
+    }
+
+    /// 计算数据集中每条记录是否满足表达式
+    pub fn mask<B: Bar>(&self, data: &[B]) -> Result<Vec<bool>> {
+        self.mask_columnar(&ColumnarFrame::from_records(data))
+    }
+
+    /// 计算列式数据中每行是否满足表达式
+    pub fn mask_columnar(&self, frame: &ColumnarFrame) -> Result<Vec<bool>> {
+        // 指标与行一一对应
+        let indicators = if self.needs_indicators {
+            Some(IndicatorCalculator::new().calculate_columnar(frame)?)
+        } else {
+            None
+        };
+
+        let mut context = HashMapContext::<DefaultNumericTypes>::new();
+        (0..frame.len())
+            .map(|row| {
+                let base = [
+                    frame.opens()[row],
+                    frame.highs()[row],
+                    frame.lows()[row],
+                    frame.closes()[row],
+                    frame.volumes()[row] as f64,
+                    frame.amounts()[row],
+                ];
+                let values = indicators.as_ref().map(|values| &values[row]);
+                self.evaluate_in(&mut context, base, values)
+            })
+            .collect()
+    }
+
+    /// 过滤出满足表达式的记录
+    pub fn filter<B: Bar + Clone>(&self, data: &[B]) -> Result<Vec<B>> {
+        let mask = self.mask(data)?;
+        Ok(data
+            .iter()
+            .zip(mask)
+            .filter(|(_, keep)| *keep)
+            .map(|(record, _)| record.clone())
+            .collect())
+    }
+
+    /// 在给定上下文中求值（复用上下文避免重复分配）
+    fn evaluate_in(
+        &self,
+        context: &mut HashMapContext<DefaultNumericTypes>,
+        base: [f64; 6],
+        indicators: Option<&IndicatorValues>,
+    ) -> Result<bool> {
+        for (field, value) in Field::BASE.iter().zip(base) {
+            context
+                .set_value(field.to_string(), Value::Float(value))
+                .map_err(|e| anyhow::anyhow!("设置表达式变量失败 {}: {}", field, e))?;
+        }
+
+        if self.needs_indicators {
+            for (name, value) in INDICATOR_NAMES
+                .iter()
+                .zip(Self::indicator_values(indicators))
+            {
+                context
+                    .set_value(name.to_string(), Value::Float(value))
+                    .map_err(|e| anyhow::anyhow!("设置表达式变量失败 {}: {}", name, e))?;
+            }
+        }
+
+        self.node
+            .eval_boolean_with_context(context)
+            .map_err(|e| anyhow::anyhow!("表达式求值失败 `{}`: {}", self.source, e))
+    }
+
+    /// 指标变量取值，顺序与 `INDICATOR_NAMES` 一致
+    fn indicator_values(indicators: Option<&IndicatorValues>) -> [f64; INDICATOR_NAMES.len()] {
+        match indicators {
+            Some(values) => INDICATOR_NAMES.map(|name| values.get(name).unwrap_or(f64::NAN)),
+            None => [f64::NAN; INDICATOR_NAMES.len()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use chrono::NaiveDate;
+
+    fn create_test_record(date: NaiveDate, close: f64, volume: u64) -> TDXDayRecord {
+        TDXDayRecord {
+            date,
+            symbol: "600000".to_string(),
+            open: close,
+            high: close + 0.5,
+            low: close - 0.5,
+            close,
+            volume,
+            amount: close * volume as f64,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_variable() {
+        assert!(RecordExpr::parse("close > foo").is_err());
+        assert!(RecordExpr::parse("(close > 1").is_err());
+    }
+
+    #[test]
+    fn test_base_field_expression() {
+        let expr = RecordExpr::parse("close > 10 && volume >= 1000").unwrap();
+        assert!(!expr.needs_indicators());
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        assert!(expr
+            .evaluate(&create_test_record(date, 10.5, 1000), None)
+            .unwrap());
+        assert!(!expr
+            .evaluate(&create_test_record(date, 9.5, 1000), None)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_indicator_expression_filter() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let data: Vec<TDXDayRecord> = (0..10)
+            .map(|i| create_test_record(start + chrono::Duration::days(i), 10.0 + i as f64, 1000))
+            .collect();
+
+        let expr = RecordExpr::parse("close > ma5").unwrap();
+        assert!(expr.needs_indicators());
+
+        // 前4条记录ma5尚未形成，比较结果为false；之后价格持续上涨
+        let filtered = expr.filter(&data).unwrap();
+        assert_eq!(filtered.len(), 6);
+        assert_eq!(filtered[0].date, start + chrono::Duration::days(4));
+    }
+}
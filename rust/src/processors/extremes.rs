@@ -0,0 +1,181 @@
+//! 滚动极值与最大回撤模块
+//!
+//! 滚动最高/最低价用单调双端队列维护候选位置，每个元素最多入队出队一次，整条序列O(n)。
+//! 相同的最高价取最近一次，便于计算“距创新高的天数”。
+//! 窗口内最大回撤用双栈队列维护区间摘要（最高、最低、区间内最大回撤），
+//! 摘要的合并满足结合律，滑动窗口同样摊还O(1)。
+
+use std::collections::VecDeque;
+
+/// 滚动最大值所在位置，窗口未满的位置为None（相同值取最近的位置）
+pub fn rolling_max_index(values: &[f64], window: usize) -> Vec<Option<usize>> {
+    rolling_extreme_index(values, window, |newer, older| newer >= older)
+}
+
+/// 滚动最小值所在位置，窗口未满的位置为None（相同值取最近的位置）
+pub fn rolling_min_index(values: &[f64], window: usize) -> Vec<Option<usize>> {
+    rolling_extreme_index(values, window, |newer, older| newer <= older)
+}
+
+/// 单调队列：`dominates(新值, 队尾值)` 为真时队尾出队
+fn rolling_extreme_index(
+    values: &[f64],
+    window: usize,
+    dominates: impl Fn(f64, f64) -> bool,
+) -> Vec<Option<usize>> {
+    let mut output = vec![None; values.len()];
+    if window == 0 {
+        return output;
+    }
+
+    let mut candidates: VecDeque<usize> = VecDeque::with_capacity(window);
+    for (i, &value) in values.iter().enumerate() {
+        while candidates
+            .back()
+            .is_some_and(|&back| dominates(value, values[back]))
+        {
+            candidates.pop_back();
+        }
+        candidates.push_back(i);
+        if candidates.front().is_some_and(|&front| front + window <= i) {
+            candidates.pop_front();
+        }
+        if i + 1 >= window {
+            output[i] = candidates.front().copied();
+        }
+    }
+    output
+}
+
+/// 区间摘要
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    max: f64,
+    min: f64,
+    /// 区间内最大回撤（先高后低）
+    drawdown: f64,
+}
+
+impl Span {
+    fn single(value: f64) -> Self {
+        Self {
+            max: value,
+            min: value,
+            drawdown: 0.0,
+        }
+    }
+
+    /// 合并相邻区间，`later` 紧接在 `self` 之后
+    fn then(self, later: Span) -> Span {
+        let across = if self.max > 0.0 {
+            1.0 - later.min / self.max
+        } else {
+            0.0
+        };
+        Span {
+            max: self.max.max(later.max),
+            min: self.min.min(later.min),
+            drawdown: self.drawdown.max(later.drawdown).max(across),
+        }
+    }
+}
+
+/// 滚动最大回撤（以比例表示，0.25即25%），窗口未满的位置为NaN
+///
+/// 回撤以窗口内的峰值为基准，不考虑窗口开始之前的高点。
+pub fn rolling_max_drawdown(closes: &[f64], window: usize) -> Vec<f64> {
+    let mut output = vec![f64::NAN; closes.len()];
+    if window == 0 {
+        return output;
+    }
+
+    // 前栈存放较早元素，栈顶为最早元素及其后所有前栈元素的摘要；后栈只保留值和整体摘要
+    let mut front: Vec<Span> = Vec::with_capacity(window);
+    let mut back: Vec<f64> = Vec::with_capacity(window);
+    let mut back_span: Option<Span> = None;
+
+    for (i, &close) in closes.iter().enumerate() {
+        back.push(close);
+        back_span =
+            Some(back_span.map_or(Span::single(close), |span| span.then(Span::single(close))));
+
+        if i >= window {
+            if front.is_empty() {
+                let mut later: Option<Span> = None;
+                for value in back.drain(..).rev() {
+                    let span =
+                        later.map_or(Span::single(value), |later| Span::single(value).then(later));
+                    front.push(span);
+                    later = Some(span);
+                }
+                back_span = None;
+            }
+            front.pop();
+        }
+
+        if i + 1 >= window {
+            let span = match (front.last(), back_span) {
+                (Some(&earlier), Some(later)) => earlier.then(later),
+                (Some(&span), None) | (None, Some(span)) => span,
+                (None, None) => continue,
+            };
+            output[i] = span.drawdown;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 逐窗口暴力计算最大回撤（对照）
+    fn naive_max_drawdown(closes: &[f64], window: usize) -> Vec<f64> {
+        (0..closes.len())
+            .map(|i| {
+                if i + 1 < window {
+                    return f64::NAN;
+                }
+                let mut peak = f64::MIN;
+                let mut worst: f64 = 0.0;
+                for &close in &closes[i + 1 - window..=i] {
+                    peak = peak.max(close);
+                    worst = worst.max(1.0 - close / peak);
+                }
+                worst
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rolling_extreme_index() {
+        let values = [3.0, 1.0, 3.0, 2.0, 5.0, 4.0];
+        let max = rolling_max_index(&values, 3);
+        assert_eq!(max[..2], [None, None]);
+        // 相同的最高价取最近一次
+        assert_eq!(max[2..], [Some(2), Some(2), Some(4), Some(4)]);
+        let min = rolling_min_index(&values, 3);
+        assert_eq!(min[2..], [Some(1), Some(1), Some(3), Some(3)]);
+        assert!(rolling_max_index(&values, 0).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_rolling_max_drawdown_matches_naive() {
+        let closes: Vec<f64> = (0..200)
+            .map(|i| 10.0 + ((i * 37) % 23) as f64 * 0.3 - (i % 7) as f64 * 0.2)
+            .collect();
+        for window in [1, 2, 5, 20, 60] {
+            let fast = rolling_max_drawdown(&closes, window);
+            let naive = naive_max_drawdown(&closes, window);
+            for (a, b) in fast.iter().zip(&naive) {
+                assert!((a.is_nan() && b.is_nan()) || (a - b).abs() < 1e-12);
+            }
+        }
+
+        let dd = rolling_max_drawdown(&[10.0, 8.0, 12.0, 9.0], 2);
+        assert!(dd[0].is_nan());
+        assert!((dd[1] - 0.2).abs() < 1e-12);
+        assert_eq!(dd[2], 0.0);
+        assert!((dd[3] - 0.25).abs() < 1e-12);
+    }
+}
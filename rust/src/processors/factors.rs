@@ -0,0 +1,575 @@
+//! 因子计算模块
+//!
+//! `Factor` 特征按股票对按日期排序的日线计算逐日因子值（动量、波动率、换手率、市值等），
+//! `FactorCalculator` 按股票并行计算所有因子，输出长格式因子表
+//! （日期, 股票代码, 市场, 因子名, 因子值），可按日期做截面排名和标准化，
+//! 并导出为CSV（ClickHouse `CSVWithNames`）或Parquet供因子研究使用，大表可按行数或字节数分片导出。
+
+use crate::export::{ExportManifest, RotatingCsvWriter, RotationPolicy};
+use crate::parsers::TDXDayRecord;
+use crate::processors::cross_section::CrossSectionOp;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 每年交易日数，用于波动率年化
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// 因子
+///
+/// `compute` 的输入为单只股票按日期升序的日线，输出与输入一一对应，无法计算的位置为NaN。
+pub trait Factor: Send + Sync {
+    /// 因子名（因子表中的列值）
+    fn name(&self) -> String;
+
+    /// 计算单只股票的因子序列
+    fn compute(&self, records: &[TDXDayRecord]) -> Vec<f64>;
+}
+
+/// 动量因子：`window` 日收益率
+#[derive(Debug, Clone)]
+pub struct Momentum {
+    /// 回看天数
+    pub window: usize,
+}
+
+impl Momentum {
+    /// 创建动量因子
+    pub fn new(window: usize) -> Self {
+        Self { window }
+    }
+}
+
+impl Factor for Momentum {
+    fn name(&self) -> String {
+        format!("momentum_{}", self.window)
+    }
+
+    fn compute(&self, records: &[TDXDayRecord]) -> Vec<f64> {
+        (0..records.len())
+            .map(|i| match i.checked_sub(self.window) {
+                Some(start) if self.window > 0 && records[start].close > 0.0 => {
+                    records[i].close / records[start].close - 1.0
+                }
+                _ => f64::NAN,
+            })
+            .collect()
+    }
+}
+
+/// 波动率因子：`window` 日对数收益率的年化标准差
+#[derive(Debug, Clone)]
+pub struct Volatility {
+    /// 收益率个数
+    pub window: usize,
+}
+
+impl Volatility {
+    /// 创建波动率因子
+    pub fn new(window: usize) -> Self {
+        Self { window }
+    }
+}
+
+impl Factor for Volatility {
+    fn name(&self) -> String {
+        format!("volatility_{}", self.window)
+    }
+
+    fn compute(&self, records: &[TDXDayRecord]) -> Vec<f64> {
+        let returns: Vec<f64> = records
+            .windows(2)
+            .map(|pair| (pair[1].close / pair[0].close).ln())
+            .collect();
+
+        let mut output = vec![f64::NAN; records.len()];
+        if self.window < 2 {
+            return output;
+        }
+        for end in self.window..=returns.len() {
+            let window = &returns[end - self.window..end];
+            let mean = window.iter().sum::<f64>() / self.window as f64;
+            let variance =
+                window.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (self.window - 1) as f64;
+            // 第end个收益率对应第end根K线
+            output[end] = (variance * TRADING_DAYS_PER_YEAR).sqrt();
+        }
+        output
+    }
+}
+
+/// 流通股本表：（股票代码, 市场）到股数
+pub type ShareCounts = HashMap<(String, String), f64>;
+
+/// 换手率因子：`window` 日平均换手率（成交量/流通股本）
+#[derive(Debug, Clone)]
+pub struct Turnover {
+    /// 平均天数
+    pub window: usize,
+    /// 流通股本，缺失的股票因子值为NaN
+    pub float_shares: ShareCounts,
+}
+
+impl Turnover {
+    /// 创建换手率因子
+    pub fn new(window: usize, float_shares: ShareCounts) -> Self {
+        Self {
+            window,
+            float_shares,
+        }
+    }
+}
+
+impl Factor for Turnover {
+    fn name(&self) -> String {
+        format!("turnover_{}", self.window)
+    }
+
+    fn compute(&self, records: &[TDXDayRecord]) -> Vec<f64> {
+        let shares = records
+            .first()
+            .and_then(|r| {
+                self.float_shares
+                    .get(&(r.symbol.clone(), r.market.clone()))
+                    .copied()
+            })
+            .filter(|&shares| shares > 0.0);
+        let Some(shares) = shares else {
+            return vec![f64::NAN; records.len()];
+        };
+
+        let ratios: Vec<f64> = records.iter().map(|r| r.volume as f64 / shares).collect();
+        (0..records.len())
+            .map(|i| match (i + 1).checked_sub(self.window) {
+                Some(start) if self.window > 0 => {
+                    ratios[start..=i].iter().sum::<f64>() / self.window as f64
+                }
+                _ => f64::NAN,
+            })
+            .collect()
+    }
+}
+
+/// 规模因子：对数流通市值（收盘价×流通股本）
+#[derive(Debug, Clone)]
+pub struct Size {
+    /// 流通股本，缺失的股票因子值为NaN
+    pub float_shares: ShareCounts,
+}
+
+impl Size {
+    /// 创建规模因子
+    pub fn new(float_shares: ShareCounts) -> Self {
+        Self { float_shares }
+    }
+}
+
+impl Factor for Size {
+    fn name(&self) -> String {
+        "size".to_string()
+    }
+
+    fn compute(&self, records: &[TDXDayRecord]) -> Vec<f64> {
+        let shares = records.first().and_then(|r| {
+            self.float_shares
+                .get(&(r.symbol.clone(), r.market.clone()))
+                .copied()
+        });
+        records
+            .iter()
+            .map(|r| match shares {
+                Some(shares) if shares > 0.0 && r.close > 0.0 => (r.close * shares).ln(),
+                _ => f64::NAN,
+            })
+            .collect()
+    }
+}
+
+/// 因子值（长格式因子表的一行）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactorValue {
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 因子名
+    pub factor: String,
+    /// 因子值
+    pub value: f64,
+}
+
+/// 长格式因子表，按（因子名, 日期, 股票代码, 市场）排序
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FactorTable {
+    /// 因子值
+    rows: Vec<FactorValue>,
+}
+
+impl FactorTable {
+    /// 由因子值构建因子表
+    pub fn new(mut rows: Vec<FactorValue>) -> Self {
+        rows.sort_by(|a, b| {
+            (&a.factor, a.date, &a.symbol, &a.market)
+                .cmp(&(&b.factor, b.date, &b.symbol, &b.market))
+        });
+        Self { rows }
+    }
+
+    /// 行数
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// 所有因子值
+    pub fn rows(&self) -> &[FactorValue] {
+        &self.rows
+    }
+
+    /// 指定因子的值
+    pub fn factor<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a FactorValue> + 'a {
+        self.rows.iter().filter(move |row| row.factor == name)
+    }
+
+    /// 指定股票、日期和因子的值
+    pub fn get(&self, date: NaiveDate, symbol: &str, factor: &str) -> Option<f64> {
+        self.rows
+            .iter()
+            .find(|row| row.date == date && row.symbol == symbol && row.factor == factor)
+            .map(|row| row.value)
+    }
+
+    /// 按日期截面排名，值替换为百分位（0~1，并列取平均名次，截面仅一只股票时为0.5）
+    pub fn rank_by_date(&self) -> FactorTable {
+        self.transform_by_date(CrossSectionOp::Percentile)
+    }
+
+    /// 按日期截面标准化，值替换为z分数（截面标准差为0时为0）
+    pub fn zscore_by_date(&self) -> FactorTable {
+        self.transform_by_date(CrossSectionOp::ZScore)
+    }
+
+    /// 对每个（因子名, 日期）截面的值做截面变换
+    pub fn transform_by_date(&self, op: CrossSectionOp) -> FactorTable {
+        let mut rows = self.rows.clone();
+        rows.par_chunk_by_mut(|a, b| a.factor == b.factor && a.date == b.date)
+            .for_each(|section| {
+                let values: Vec<f64> = section.iter().map(|row| row.value).collect();
+                for (row, value) in section.iter_mut().zip(op.apply(&values)) {
+                    row.value = value;
+                }
+            });
+        FactorTable { rows }
+    }
+
+    /// 导出为CSV（表头 `date,symbol,market,factor,value`，可直接以 `CSVWithNames` 导入ClickHouse）
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("无法创建CSV文件: {}", path.display()))?;
+        for row in &self.rows {
+            writer.serialize(row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 按行数或字节数分片导出为CSV，分片名以 `factors` 为导出名称
+    pub fn to_csv_parts<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        policy: &RotationPolicy,
+    ) -> Result<ExportManifest> {
+        let mut writer = RotatingCsvWriter::create(dir, "factors", policy)?;
+        for row in &self.rows {
+            writer.serialize(row)?;
+        }
+        writer.finish()
+    }
+
+    /// 导出为zstd压缩的Parquet文件
+    #[cfg(feature = "storage")]
+    pub fn to_parquet<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        use parquet::arrow::ArrowWriter;
+        use parquet::basic::{Compression, ZstdLevel};
+        use parquet::file::properties::WriterProperties;
+
+        let batch = self.arrow_batch()?;
+        let path = path.as_ref();
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("无法创建因子文件: {}", path.display()))?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// 按行数或字节数分片导出为zstd压缩的Parquet
+    #[cfg(feature = "storage")]
+    pub fn to_parquet_parts<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        policy: &RotationPolicy,
+    ) -> Result<ExportManifest> {
+        let batch = self.arrow_batch()?;
+        let mut writer =
+            crate::export::RotatingParquetWriter::create(dir, "factors", batch.schema(), policy)?;
+        writer.write(&batch)?;
+        writer.finish()
+    }
+
+    #[cfg(feature = "storage")]
+    fn arrow_batch(&self) -> Result<arrow_array::RecordBatch> {
+        use arrow_array::builder::{Date32Builder, Float64Builder, StringBuilder};
+        use arrow_array::{ArrayRef, RecordBatch};
+        use arrow_schema::{DataType, Field as ArrowField, Schema};
+        use std::sync::Arc;
+
+        let schema = Arc::new(Schema::new(vec![
+            ArrowField::new("date", DataType::Date32, false),
+            ArrowField::new("symbol", DataType::Utf8, false),
+            ArrowField::new("market", DataType::Utf8, false),
+            ArrowField::new("factor", DataType::Utf8, false),
+            ArrowField::new("value", DataType::Float64, false),
+        ]));
+
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+        let mut dates = Date32Builder::with_capacity(self.rows.len());
+        let mut symbols = StringBuilder::new();
+        let mut markets = StringBuilder::new();
+        let mut factors = StringBuilder::new();
+        let mut values = Float64Builder::with_capacity(self.rows.len());
+        for row in &self.rows {
+            dates.append_value((row.date - epoch).num_days() as i32);
+            symbols.append_value(&row.symbol);
+            markets.append_value(&row.market);
+            factors.append_value(&row.factor);
+            values.append_value(row.value);
+        }
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(dates.finish()),
+            Arc::new(symbols.finish()),
+            Arc::new(markets.finish()),
+            Arc::new(factors.finish()),
+            Arc::new(values.finish()),
+        ];
+        Ok(RecordBatch::try_new(schema, columns)?)
+    }
+}
+
+/// 因子计算器
+#[derive(Default)]
+pub struct FactorCalculator {
+    /// 待计算的因子
+    factors: Vec<Box<dyn Factor>>,
+}
+
+impl std::fmt::Debug for FactorCalculator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FactorCalculator")
+            .field("factors", &self.factor_names())
+            .finish()
+    }
+}
+
+impl FactorCalculator {
+    /// 创建空的因子计算器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加因子
+    pub fn with_factor(mut self, factor: impl Factor + 'static) -> Self {
+        self.factors.push(Box::new(factor));
+        self
+    }
+
+    /// 已添加的因子名
+    pub fn factor_names(&self) -> Vec<String> {
+        self.factors.iter().map(|factor| factor.name()).collect()
+    }
+
+    /// 按股票并行计算所有因子，NaN值不进入因子表
+    pub fn calculate(&self, data: &[TDXDayRecord]) -> Result<FactorTable> {
+        let names = self.factor_names();
+        if let Some(name) = names
+            .iter()
+            .enumerate()
+            .find(|(i, name)| names[..*i].contains(name))
+            .map(|(_, name)| name)
+        {
+            return Err(anyhow::anyhow!("因子名重复: {}", name));
+        }
+
+        let mut groups: HashMap<(&str, &str), Vec<TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((&record.symbol, &record.market))
+                .or_default()
+                .push(record.clone());
+        }
+
+        let rows: Vec<FactorValue> = groups
+            .into_par_iter()
+            .flat_map_iter(|(_, mut records)| {
+                records.sort_by_key(|r| r.date);
+                let mut rows = Vec::new();
+                for (factor, name) in self.factors.iter().zip(&names) {
+                    for (record, value) in records.iter().zip(factor.compute(&records)) {
+                        if value.is_finite() {
+                            rows.push(FactorValue {
+                                date: record.date,
+                                symbol: record.symbol.clone(),
+                                market: record.market.clone(),
+                                factor: name.clone(),
+                                value,
+                            });
+                        }
+                    }
+                }
+                rows
+            })
+            .collect();
+
+        Ok(FactorTable::new(rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000 * day as u64,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn create_test_data() -> Vec<TDXDayRecord> {
+        (1..=5)
+            .flat_map(|day| {
+                [
+                    create_test_record("600000", day, 10.0 + day as f64),
+                    create_test_record("600001", day, 20.0 - day as f64),
+                    create_test_record("600002", day, 5.0),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_builtin_factors() {
+        let records: Vec<TDXDayRecord> = create_test_data()
+            .into_iter()
+            .filter(|r| r.symbol == "600000")
+            .collect();
+
+        let momentum = Momentum::new(2).compute(&records);
+        assert!(momentum[1].is_nan());
+        assert!((momentum[4] - (15.0 / 13.0 - 1.0)).abs() < 1e-12);
+
+        let volatility = Volatility::new(3).compute(&records);
+        assert!(volatility[2].is_nan());
+        assert!(volatility[3] > 0.0);
+
+        let shares: ShareCounts = [(("600000".to_string(), "SH".to_string()), 10_000.0)]
+            .into_iter()
+            .collect();
+        let turnover = Turnover::new(2, shares.clone()).compute(&records);
+        assert!((turnover[1] - 0.15).abs() < 1e-12);
+        assert!((Size::new(shares).compute(&records)[0] - 110_000f64.ln()).abs() < 1e-12);
+        assert!(Size::new(ShareCounts::new()).compute(&records)[0].is_nan());
+    }
+
+    #[test]
+    fn test_calculate_long_table() {
+        let table = FactorCalculator::new()
+            .with_factor(Momentum::new(1))
+            .with_factor(Momentum::new(4))
+            .calculate(&create_test_data())
+            .unwrap();
+
+        // momentum_1 每只股票4天，momentum_4 每只股票1天
+        assert_eq!(table.len(), 3 * 4 + 3);
+        assert_eq!(table.factor("momentum_4").count(), 3);
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        assert_eq!(table.get(date, "600002", "momentum_4"), Some(0.0));
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let csv_path = temp_dir.path().join("factors.csv");
+        table.to_csv(&csv_path).unwrap();
+        let content = std::fs::read_to_string(&csv_path).unwrap();
+        assert!(content.starts_with("date,symbol,market,factor,value\n"));
+        assert_eq!(content.lines().count(), table.len() + 1);
+        #[cfg(feature = "storage")]
+        table
+            .to_parquet(temp_dir.path().join("factors.parquet"))
+            .unwrap();
+
+        let parts_dir = temp_dir.path().join("parts");
+        let policy = RotationPolicy::new().with_max_rows(4);
+        let manifest = table.to_csv_parts(&parts_dir, &policy).unwrap();
+        assert_eq!(manifest.parts.len(), 4);
+        assert_eq!(manifest.row_count, table.len());
+        #[cfg(feature = "storage")]
+        assert_eq!(
+            table
+                .to_parquet_parts(&parts_dir, &policy)
+                .unwrap()
+                .parts
+                .len(),
+            4
+        );
+
+        assert!(FactorCalculator::new()
+            .with_factor(Momentum::new(1))
+            .with_factor(Momentum::new(1))
+            .calculate(&create_test_data())
+            .is_err());
+    }
+
+    #[test]
+    fn test_cross_sectional_rank_and_zscore() {
+        let table = FactorCalculator::new()
+            .with_factor(Momentum::new(4))
+            .calculate(&create_test_data())
+            .unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        let ranked = table.rank_by_date();
+        assert_eq!(ranked.get(date, "600000", "momentum_4"), Some(1.0));
+        assert_eq!(ranked.get(date, "600002", "momentum_4"), Some(0.5));
+        assert_eq!(ranked.get(date, "600001", "momentum_4"), Some(0.0));
+
+        let clipped = table.transform_by_date(CrossSectionOp::Winsorize {
+            lower: 0.0,
+            upper: 0.5,
+        });
+        assert_eq!(clipped.get(date, "600000", "momentum_4"), Some(0.0));
+
+        let scored = table.zscore_by_date();
+        let sum: f64 = scored.factor("momentum_4").map(|row| row.value).sum();
+        assert!(sum.abs() < 1e-12);
+        assert!(scored.get(date, "600000", "momentum_4").unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,220 @@
+//! 字段定义模块
+//!
+//! `Field` 替代各处理器中的字符串字段名，字段名只在配置边界（JSON规则、
+//! 命令行参数等）解析一次，拼写错误在解析时即报错。序列化形式仍为字段名字符串。
+
+use crate::parsers::Bar;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 支持的技术指标名称
+pub const INDICATOR_NAMES: [&str; 36] = [
+    "ma5",
+    "ma10",
+    "ma20",
+    "ma60",
+    "volume_ma5",
+    "change_percent",
+    "amplitude",
+    "rsi",
+    "macd_dif",
+    "macd_signal",
+    "macd_histogram",
+    "boll_upper",
+    "boll_middle",
+    "boll_lower",
+    "rs_line",
+    "beta",
+    "excess_return",
+    "beta_20",
+    "beta_60",
+    "beta_250",
+    "alpha_20",
+    "alpha_60",
+    "alpha_250",
+    "turnover_rate",
+    "float_market_cap",
+    "vwap",
+    "vwap_5",
+    "vwap_20",
+    "twap_5",
+    "twap_20",
+    "dist_high_52w",
+    "dist_low_52w",
+    "days_since_high_52w",
+    "max_drawdown_20",
+    "max_drawdown_60",
+    "max_drawdown_250",
+];
+
+/// 记录数值字段
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Field {
+    /// 开盘价
+    Open,
+    /// 最高价
+    High,
+    /// 最低价
+    Low,
+    /// 收盘价
+    Close,
+    /// 成交量
+    Volume,
+    /// 成交额
+    Amount,
+    /// 技术指标（名称见 `INDICATOR_NAMES`）
+    Indicator(String),
+}
+
+impl Field {
+    /// 基础行情字段
+    pub const BASE: [Field; 6] = [
+        Field::Open,
+        Field::High,
+        Field::Low,
+        Field::Close,
+        Field::Volume,
+        Field::Amount,
+    ];
+
+    /// 字段名
+    pub fn name(&self) -> &str {
+        match self {
+            Field::Open => "open",
+            Field::High => "high",
+            Field::Low => "low",
+            Field::Close => "close",
+            Field::Volume => "volume",
+            Field::Amount => "amount",
+            Field::Indicator(name) => name,
+        }
+    }
+
+    /// 是否为价格字段
+    pub fn is_price(&self) -> bool {
+        matches!(self, Field::Open | Field::High | Field::Low | Field::Close)
+    }
+
+    /// 是否为技术指标字段
+    pub fn is_indicator(&self) -> bool {
+        matches!(self, Field::Indicator(_))
+    }
+
+    /// 读取记录中的字段值
+    ///
+    /// 指标字段只有带指标的记录（如 `EnhancedDayRecord`）才能读取。
+    pub fn value<B: Bar>(&self, record: &B) -> Result<f64> {
+        Ok(match self {
+            Field::Open => record.open(),
+            Field::High => record.high(),
+            Field::Low => record.low(),
+            Field::Close => record.close(),
+            Field::Volume => record.volume() as f64,
+            Field::Amount => record.amount(),
+            Field::Indicator(name) => record
+                .indicator(name)
+                .ok_or_else(|| anyhow::anyhow!("记录不包含指标: {}", name))?,
+        })
+    }
+
+    /// 写入记录中的字段值，指标字段不可写入，返回是否写入
+    pub fn set<B: Bar>(&self, record: &mut B, value: f64) -> bool {
+        match self {
+            Field::Open => record.set_open(value),
+            Field::High => record.set_high(value),
+            Field::Low => record.set_low(value),
+            Field::Close => record.set_close(value),
+            Field::Volume => record.set_volume(value.max(0.0) as u64),
+            Field::Amount => record.set_amount(value),
+            Field::Indicator(_) => return false,
+        }
+        true
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.trim();
+        Ok(match name {
+            "open" => Field::Open,
+            "high" => Field::High,
+            "low" => Field::Low,
+            "close" => Field::Close,
+            "volume" => Field::Volume,
+            "amount" => Field::Amount,
+            _ if INDICATOR_NAMES.contains(&name) => Field::Indicator(name.to_string()),
+            _ => return Err(anyhow::anyhow!("未知字段: {}", s)),
+        })
+    }
+}
+
+impl TryFrom<String> for Field {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Field> for String {
+    fn from(field: Field) -> Self {
+        field.name().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("close".parse::<Field>().unwrap(), Field::Close);
+        assert_eq!(
+            "ma20".parse::<Field>().unwrap(),
+            Field::Indicator("ma20".to_string())
+        );
+        assert!("clsoe".parse::<Field>().is_err());
+        assert_eq!(Field::Volume.to_string(), "volume");
+    }
+
+    #[test]
+    fn test_serde_as_string() {
+        let json = serde_json::to_string(&vec![Field::High, Field::Indicator("rsi".into())]);
+        assert_eq!(json.unwrap(), r#"["high","rsi"]"#);
+        assert!(serde_json::from_str::<Field>(r#""foo""#).is_err());
+    }
+
+    #[test]
+    fn test_value_and_set() {
+        let mut record = TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            symbol: "600000".to_string(),
+            open: 10.0,
+            high: 10.5,
+            low: 9.8,
+            close: 10.2,
+            volume: 1000,
+            amount: 10_200.0,
+            market: "SH".to_string(),
+        };
+        assert_eq!(Field::Volume.value(&record).unwrap(), 1000.0);
+        assert!(Field::Indicator("ma5".into()).value(&record).is_err());
+
+        assert!(Field::Close.set(&mut record, 10.4));
+        assert_eq!(record.close, 10.4);
+        assert!(!Field::Indicator("ma5".into()).set(&mut record, 1.0));
+    }
+}
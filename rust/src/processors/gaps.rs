@@ -0,0 +1,254 @@
+//! 缺口检测与修复模块
+//!
+//! 以交易日历为基准，找出每只股票在首末日期之间缺失的交易日，
+//! 可选择插入合成K线（沿用前收盘价、成交量为0并打标记），
+//! 或通过通达信行情服务器补取真实数据。
+
+use crate::calendar::TradingCalendar;
+#[cfg(feature = "net")]
+use crate::net::{TdxClient, TdxMarket};
+use crate::parsers::TDXDayRecord;
+#[cfg(feature = "net")]
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use log::info;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// 单只股票的缺口
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolGap {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 缺失的交易日（升序）
+    pub missing: Vec<NaiveDate>,
+}
+
+/// 修复后的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilledRecord {
+    /// 日线记录
+    pub record: TDXDayRecord,
+    /// 是否为合成K线
+    pub synthetic: bool,
+}
+
+/// 缺口修复器
+#[derive(Debug, Clone, Default)]
+pub struct GapFiller {
+    /// 交易日历
+    calendar: TradingCalendar,
+}
+
+impl GapFiller {
+    /// 创建新的缺口修复器
+    pub fn new(calendar: TradingCalendar) -> Self {
+        Self { calendar }
+    }
+
+    /// 交易日历
+    pub fn calendar(&self) -> &TradingCalendar {
+        &self.calendar
+    }
+
+    /// 检测每只股票首末日期之间缺失的交易日，只返回存在缺口的股票
+    pub fn find_gaps(&self, data: &[TDXDayRecord]) -> Vec<SymbolGap> {
+        let mut gaps: Vec<SymbolGap> = group_by_symbol(data)
+            .into_par_iter()
+            .filter_map(|((symbol, market), records)| {
+                let present: HashSet<NaiveDate> = records.iter().map(|r| r.date).collect();
+                let first = records.first()?.date;
+                let last = records.last()?.date;
+                let missing: Vec<NaiveDate> = self
+                    .calendar
+                    .trading_days(first, last)
+                    .into_iter()
+                    .filter(|date| !present.contains(date))
+                    .collect();
+                (!missing.is_empty()).then(|| SymbolGap {
+                    symbol: symbol.to_string(),
+                    market: market.to_string(),
+                    missing,
+                })
+            })
+            .collect();
+
+        gaps.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.market.cmp(&b.market)));
+        gaps
+    }
+
+    /// 插入合成K线填补缺口，返回按（股票代码, 市场, 日期）排序的全部记录
+    ///
+    /// 合成K线的开高低收均为前一交易日收盘价，成交量和成交额为0。
+    pub fn fill_synthetic(&self, data: &[TDXDayRecord]) -> Vec<FilledRecord> {
+        let groups: Vec<Vec<FilledRecord>> = group_by_symbol(data)
+            .into_par_iter()
+            .map(|(_, records)| {
+                let mut filled = Vec::with_capacity(records.len());
+                let mut previous: Option<&TDXDayRecord> = None;
+                for record in records {
+                    if let Some(prev) = previous {
+                        let start = prev.date.succ_opt().unwrap_or(prev.date);
+                        for date in self.calendar.trading_days(start, record.date) {
+                            if date < record.date {
+                                filled.push(FilledRecord {
+                                    record: synthetic_bar(prev, date),
+                                    synthetic: true,
+                                });
+                            }
+                        }
+                    }
+                    filled.push(FilledRecord {
+                        record: record.clone(),
+                        synthetic: false,
+                    });
+                    previous = Some(record);
+                }
+                filled
+            })
+            .collect();
+
+        let filled: Vec<FilledRecord> = groups.into_iter().flatten().collect();
+        info!(
+            "缺口填补完成: 原始{}条, 合成{}条",
+            data.len(),
+            filled.len() - data.len()
+        );
+        filled
+    }
+
+    /// 从行情服务器补取缺失交易日的K线，返回取到的记录
+    ///
+    /// 服务器上同样不存在的日期（如停牌）不会返回。
+    #[cfg(feature = "net")]
+    pub async fn fetch_missing(
+        &self,
+        client: &mut TdxClient,
+        data: &[TDXDayRecord],
+    ) -> Result<Vec<TDXDayRecord>> {
+        let mut fetched = Vec::new();
+        for gap in self.find_gaps(data) {
+            let market = TdxMarket::parse(&gap.market)?;
+            let since = gap.missing[0].pred_opt();
+            let remote = client
+                .daily_records(market, &gap.symbol, since)
+                .await
+                .with_context(|| format!("补取数据失败: {}.{}", gap.symbol, gap.market))?;
+
+            let missing: HashSet<NaiveDate> = gap.missing.iter().copied().collect();
+            let before = fetched.len();
+            fetched.extend(remote.into_iter().filter(|r| missing.contains(&r.date)));
+            let found = fetched.len() - before;
+            if found < gap.missing.len() {
+                log::warn!(
+                    "{}.{} 有{}个缺失交易日服务器上也无数据",
+                    gap.symbol,
+                    gap.market,
+                    gap.missing.len() - found
+                );
+            }
+        }
+        Ok(fetched)
+    }
+}
+
+/// 按（股票代码, 市场）分组，组内按日期升序
+fn group_by_symbol(data: &[TDXDayRecord]) -> BTreeMap<(&str, &str), Vec<&TDXDayRecord>> {
+    let mut groups: BTreeMap<(&str, &str), Vec<&TDXDayRecord>> = BTreeMap::new();
+    for record in data {
+        groups
+            .entry((record.symbol.as_str(), record.market.as_str()))
+            .or_default()
+            .push(record);
+    }
+    for records in groups.values_mut() {
+        records.sort_by_key(|r| r.date);
+    }
+    groups
+}
+
+/// 以前收盘价生成合成K线
+fn synthetic_bar(previous: &TDXDayRecord, date: NaiveDate) -> TDXDayRecord {
+    TDXDayRecord {
+        date,
+        symbol: previous.symbol.clone(),
+        open: previous.close,
+        high: previous.close,
+        low: previous.close,
+        close: previous.close,
+        volume: 0,
+        amount: 0.0,
+        market: previous.market.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_find_gaps() {
+        // 2024-01-02 ~ 01-05 为周二至周五，01-08 为周一
+        let data = vec![
+            create_test_record("600000", 2, 10.0),
+            create_test_record("600000", 5, 10.3),
+            create_test_record("600000", 8, 10.4),
+            create_test_record("600036", 2, 30.0),
+            create_test_record("600036", 3, 30.1),
+        ];
+        let gaps = GapFiller::default().find_gaps(&data);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].symbol, "600000");
+        assert_eq!(
+            gaps[0].missing,
+            vec![
+                NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 1, 4).unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calendar_holidays_are_not_gaps() {
+        let calendar =
+            TradingCalendar::new().with_holidays([NaiveDate::from_ymd_opt(2024, 1, 3).unwrap()]);
+        let data = vec![
+            create_test_record("600000", 2, 10.0),
+            create_test_record("600000", 4, 10.2),
+        ];
+        assert!(GapFiller::new(calendar).find_gaps(&data).is_empty());
+    }
+
+    #[test]
+    fn test_fill_synthetic() {
+        let data = vec![
+            create_test_record("600000", 8, 10.4),
+            create_test_record("600000", 4, 10.2),
+        ];
+        let filled = GapFiller::default().fill_synthetic(&data);
+        // 01-04(周四), 01-05(合成), 01-08
+        assert_eq!(filled.len(), 3);
+        assert!(!filled[0].synthetic);
+        assert!(filled[1].synthetic);
+        assert_eq!(filled[1].record.close, 10.2);
+        assert_eq!(filled[1].record.volume, 0);
+        assert_eq!(filled[2].record.close, 10.4);
+    }
+}
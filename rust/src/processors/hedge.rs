@@ -0,0 +1,217 @@
+//! 贝塔对冲收益模块
+//!
+//! 以滚动贝塔对冲基准：对冲收益 = 个股收益率 - 贝塔 × 基准收益率，剩余部分即个股特有收益，
+//! 可用于评估选股alpha，也可作为配对分析的输入（见 `CorrelationCalculator::calculate`）。
+//! 默认用截至前一日的窗口估计贝塔，避免当日收益同时参与估计和对冲；
+//! 事后归因可改用包含当日的窗口。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::benchmark::Benchmark;
+use crate::processors::kernels;
+use crate::processors::performance::DateSeries;
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 单日对冲收益
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HedgedReturn {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 日期
+    pub date: NaiveDate,
+    /// 个股收益率
+    pub asset_return: f64,
+    /// 基准收益率（基准缺失时为NaN）
+    pub benchmark_return: f64,
+    /// 对冲使用的贝塔，窗口未满时为None
+    pub beta: Option<f64>,
+    /// 对冲收益率，窗口未满时为None
+    pub hedged_return: Option<f64>,
+}
+
+/// 贝塔对冲计算器
+#[derive(Debug, Clone)]
+pub struct BetaHedger {
+    /// 贝塔估计窗口（收益率个数）
+    window: usize,
+    /// 是否用截至前一日的贝塔
+    lagged: bool,
+}
+
+impl Default for BetaHedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BetaHedger {
+    /// 创建计算器：60日窗口，用截至前一日的贝塔
+    pub fn new() -> Self {
+        Self {
+            window: 60,
+            lagged: true,
+        }
+    }
+
+    /// 设置贝塔估计窗口（收益率个数）
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(2);
+        self
+    }
+
+    /// 用包含当日的窗口估计贝塔（事后归因，回测中会引入未来信息）
+    pub fn with_in_sample(mut self) -> Self {
+        self.lagged = false;
+        self
+    }
+
+    /// 对齐的收益率序列上逐日计算（贝塔, 对冲收益），无法计算的位置为NaN
+    pub fn hedge_series(&self, asset: &[f64], benchmark: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let betas = kernels::rolling_beta(asset, benchmark, self.window);
+        let len = betas.len();
+        let mut used = vec![f64::NAN; len];
+        let mut hedged = vec![f64::NAN; len];
+        for i in 0..len {
+            let beta = match (self.lagged, i) {
+                (true, 0) => continue,
+                (true, _) => betas[i - 1],
+                (false, _) => betas[i],
+            };
+            used[i] = beta;
+            hedged[i] = asset[i] - beta * benchmark[i];
+        }
+        (used, hedged)
+    }
+
+    /// 按股票计算收盘价收益率的对冲收益，结果按（股票代码, 市场, 日期）排序
+    ///
+    /// 每只股票的首日没有收益率，不输出。
+    pub fn hedge(&self, data: &[TDXDayRecord], benchmark: &Benchmark) -> Vec<HedgedReturn> {
+        let mut groups: HashMap<(&str, &str), Vec<&TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        let mut rows: Vec<HedgedReturn> = groups
+            .into_par_iter()
+            .flat_map_iter(|(_, mut records)| {
+                records.sort_by_key(|r| r.date);
+                let dates: Vec<NaiveDate> = records.iter().map(|r| r.date).collect();
+                let closes: Vec<f64> = records.iter().map(|r| r.close).collect();
+                let benchmark_closes = benchmark.align(&dates);
+                let returns = |series: &[f64]| -> Vec<f64> {
+                    series.windows(2).map(|w| w[1] / w[0] - 1.0).collect()
+                };
+                let (asset, base) = (returns(&closes), returns(&benchmark_closes));
+                let (betas, hedged) = self.hedge_series(&asset, &base);
+
+                let valid = |value: f64| Some(value).filter(|value| value.is_finite());
+                (0..asset.len())
+                    .map(|i| HedgedReturn {
+                        symbol: records[i + 1].symbol.clone(),
+                        market: records[i + 1].market.clone(),
+                        date: records[i + 1].date,
+                        asset_return: asset[i],
+                        benchmark_return: base[i],
+                        beta: valid(betas[i]),
+                        hedged_return: valid(hedged[i]),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        rows.sort_by(|a, b| (&a.symbol, &a.market, a.date).cmp(&(&b.symbol, &b.market, b.date)));
+        rows
+    }
+
+    /// 把对冲收益整理为按股票代码分组的日期序列（跳过无法对冲的日期）
+    pub fn to_date_series(rows: &[HedgedReturn]) -> HashMap<String, DateSeries> {
+        let mut series: HashMap<String, DateSeries> = HashMap::new();
+        for row in rows {
+            if let Some(hedged) = row.hedged_return {
+                series
+                    .entry(row.symbol.clone())
+                    .or_default()
+                    .push((row.date, hedged));
+            }
+        }
+        series
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: i64, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(day),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    /// 基准收益率交替变化，个股收益 = 0.001 + 1.5 × 基准收益
+    fn create_data() -> (Vec<TDXDayRecord>, Vec<TDXDayRecord>) {
+        let (mut index, mut stock) = (3000.0, 10.0);
+        let (mut indices, mut stocks) = (Vec::new(), Vec::new());
+        for day in 0..30 {
+            indices.push(create_test_record("000001", day, index));
+            stocks.push(create_test_record("600000", day, stock));
+            let base = [0.01, -0.02, 0.015, 0.005][day as usize % 4];
+            index *= 1.0 + base;
+            stock *= 1.0 + 0.001 + 1.5 * base;
+        }
+        (indices, stocks)
+    }
+
+    #[test]
+    fn test_hedge_records() {
+        let (indices, mut stocks) = create_data();
+        let benchmark = Benchmark::from_records("000001", "SH", &indices).unwrap();
+        stocks.reverse();
+
+        let rows = BetaHedger::new().with_window(10).hedge(&stocks, &benchmark);
+        assert_eq!(rows.len(), 29);
+        assert_eq!(rows[0].date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        // 前一日窗口在第11个收益率处才可用
+        assert!(rows[9].hedged_return.is_none());
+        let last = rows.last().unwrap();
+        assert!((last.beta.unwrap() - 1.5).abs() < 1e-9);
+        assert!((last.hedged_return.unwrap() - 0.001).abs() < 1e-9);
+
+        let series = BetaHedger::to_date_series(&rows);
+        assert_eq!(series["600000"].len(), 19);
+    }
+
+    #[test]
+    fn test_hedge_series_lag() {
+        let benchmark = [0.01, -0.01, 0.02, 0.0];
+        let asset = [0.02, -0.02, 0.04, 0.01];
+        let (betas, hedged) = BetaHedger::new()
+            .with_window(3)
+            .hedge_series(&asset, &benchmark);
+        assert!(betas[2].is_nan());
+        assert!((betas[3] - 2.0).abs() < 1e-12);
+        assert!((hedged[3] - 0.01).abs() < 1e-12);
+
+        let (betas, hedged) = BetaHedger::new()
+            .with_window(3)
+            .with_in_sample()
+            .hedge_series(&asset, &benchmark);
+        assert!((betas[2] - 2.0).abs() < 1e-12);
+        assert!(hedged[2].abs() < 1e-12);
+    }
+}
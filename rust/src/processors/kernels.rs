@@ -0,0 +1,296 @@
+//! 数值计算内核
+//!
+//! 针对f64切片的滚动均值、滚动标准差和EMA。多路累加器分块展开，
+//! 消除循环携带依赖，使编译器能够生成SIMD指令（稳定版Rust无需 `std::simd`）。
+//! 适用于整条序列一次性计算；逐K线增量更新见 `rolling::RollingEngine`。`scalar` 子模块保留逐元素实现作为对照。
+
+/// 并行累加路数
+const LANES: usize = 8;
+
+/// 分块求和
+#[inline]
+pub fn sum(values: &[f64]) -> f64 {
+    let mut acc = [0.0; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+
+    for chunk in chunks {
+        for (lane, value) in acc.iter_mut().zip(chunk) {
+            *lane += value;
+        }
+    }
+
+    let mut total = 0.0;
+    for lane in acc {
+        total += lane;
+    }
+    total + remainder.iter().sum::<f64>()
+}
+
+/// 分块计算离差平方和
+#[inline]
+pub fn sum_squared_deviation(values: &[f64], mean: f64) -> f64 {
+    let mut acc = [0.0; LANES];
+    let chunks = values.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+
+    for chunk in chunks {
+        for (lane, value) in acc.iter_mut().zip(chunk) {
+            let deviation = value - mean;
+            *lane += deviation * deviation;
+        }
+    }
+
+    let mut total = 0.0;
+    for lane in acc {
+        total += lane;
+    }
+    total
+        + remainder
+            .iter()
+            .map(|value| (value - mean) * (value - mean))
+            .sum::<f64>()
+}
+
+/// 滚动均值，窗口未满的位置为NaN
+pub fn rolling_mean(values: &[f64], window: usize) -> Vec<f64> {
+    let mut output = vec![f64::NAN; values.len()];
+    if window == 0 || values.len() < window {
+        return output;
+    }
+
+    let divisor = window as f64;
+    let means = &mut output[window - 1..];
+    rolling_sum_into(values, window, means);
+    for mean in means.iter_mut() {
+        *mean /= divisor;
+    }
+    output
+}
+
+/// 滚动总体标准差，窗口未满的位置为NaN
+pub fn rolling_std(values: &[f64], window: usize) -> Vec<f64> {
+    let mut output = vec![f64::NAN; values.len()];
+    if window == 0 || values.len() < window {
+        return output;
+    }
+
+    let divisor = window as f64;
+    let mut means = vec![0.0; values.len() + 1 - window];
+    rolling_sum_into(values, window, &mut means);
+    for mean in means.iter_mut() {
+        *mean /= divisor;
+    }
+
+    let stds = &mut output[window - 1..];
+    let count = means.len();
+    let mut start = 0;
+    while start + LANES <= count {
+        let mut acc = [0.0; LANES];
+        let lane_means = &means[start..start + LANES];
+        for offset in 0..window {
+            let lane_values = &values[start + offset..start + offset + LANES];
+            for ((lane, value), mean) in acc.iter_mut().zip(lane_values).zip(lane_means) {
+                let deviation = value - mean;
+                *lane += deviation * deviation;
+            }
+        }
+        for (std, total) in stds[start..start + LANES].iter_mut().zip(acc) {
+            *std = (total / divisor).sqrt();
+        }
+        start += LANES;
+    }
+    for i in start..count {
+        let slice = &values[i..i + window];
+        let total: f64 = slice.iter().map(|v| (v - means[i]) * (v - means[i])).sum();
+        stds[i] = (total / divisor).sqrt();
+    }
+    output
+}
+
+/// 计算所有完整窗口的和，`output` 长度为 `values.len() - window + 1`
+///
+/// 每个累加器对应一个相邻的输出位置，窗口内按原顺序累加，
+/// 因此结果与逐窗口标量求和逐位一致。
+fn rolling_sum_into(values: &[f64], window: usize, output: &mut [f64]) {
+    let count = output.len();
+    let mut start = 0;
+    while start + LANES <= count {
+        let mut acc = [0.0; LANES];
+        for offset in 0..window {
+            let lane_values = &values[start + offset..start + offset + LANES];
+            for (lane, value) in acc.iter_mut().zip(lane_values) {
+                *lane += value;
+            }
+        }
+        output[start..start + LANES].copy_from_slice(&acc);
+        start += LANES;
+    }
+    for (i, slot) in output.iter_mut().enumerate().skip(start) {
+        *slot = values[i..i + window].iter().sum();
+    }
+}
+
+/// 指数移动平均（以首个值为初值）
+///
+/// EMA是一阶递推，时间维度上无法向量化，这里以单次线性扫描输出完整序列，
+/// 避免对每个位置重新从头计算。
+pub fn ema(values: &[f64], period: usize) -> Vec<f64> {
+    let mut output = Vec::with_capacity(values.len());
+    let Some(&first) = values.first() else {
+        return output;
+    };
+
+    let multiplier = 2.0 / (period as f64 + 1.0);
+    let mut current = first;
+    output.push(current);
+    for &value in &values[1..] {
+        current = value * multiplier + current * (1.0 - multiplier);
+        output.push(current);
+    }
+    output
+}
+
+/// 滚动贝塔：`asset` 对 `benchmark` 的协方差除以 `benchmark` 的方差
+///
+/// 窗口内含NaN或基准方差为0时输出NaN。
+pub fn rolling_beta(asset: &[f64], benchmark: &[f64], window: usize) -> Vec<f64> {
+    rolling_regression(asset, benchmark, window).0
+}
+
+/// 滚动OLS回归 `asset = alpha + beta * benchmark`，返回（贝塔, 阿尔法）序列
+///
+/// 阿尔法为每期截距（未年化）；窗口内含NaN或基准方差为0时输出NaN。
+pub fn rolling_regression(asset: &[f64], benchmark: &[f64], window: usize) -> (Vec<f64>, Vec<f64>) {
+    let len = asset.len().min(benchmark.len());
+    let mut betas = vec![f64::NAN; len];
+    let mut alphas = vec![f64::NAN; len];
+    if window < 2 || len < window {
+        return (betas, alphas);
+    }
+
+    let divisor = window as f64;
+    for end in window..=len {
+        let (a, b) = (&asset[end - window..end], &benchmark[end - window..end]);
+        let mean_a = sum(a) / divisor;
+        let mean_b = sum(b) / divisor;
+        let covariance: f64 = a
+            .iter()
+            .zip(b)
+            .map(|(x, y)| (x - mean_a) * (y - mean_b))
+            .sum();
+        let variance = sum_squared_deviation(b, mean_b);
+        if variance > 0.0 {
+            let beta = covariance / variance;
+            betas[end - 1] = beta;
+            alphas[end - 1] = mean_a - beta * mean_b;
+        }
+    }
+    (betas, alphas)
+}
+
+/// 逐元素标量实现（对照基准）
+pub mod scalar {
+    /// 滚动均值
+    pub fn rolling_mean(values: &[f64], window: usize) -> Vec<f64> {
+        (0..values.len())
+            .map(|i| {
+                if window == 0 || i + 1 < window {
+                    f64::NAN
+                } else {
+                    values[i + 1 - window..=i].iter().sum::<f64>() / window as f64
+                }
+            })
+            .collect()
+    }
+
+    /// 滚动总体标准差
+    pub fn rolling_std(values: &[f64], window: usize) -> Vec<f64> {
+        (0..values.len())
+            .map(|i| {
+                if window == 0 || i + 1 < window {
+                    return f64::NAN;
+                }
+                let slice = &values[i + 1 - window..=i];
+                let mean = slice.iter().sum::<f64>() / window as f64;
+                (slice.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / window as f64).sqrt()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|i| 10.0 + (i as f64 * 0.37).sin() * 3.0)
+            .collect()
+    }
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!(
+                (x.is_nan() && y.is_nan()) || (x - y).abs() < 1e-9,
+                "{} != {}",
+                x,
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn test_rolling_kernels_match_scalar() {
+        let values = sample(103);
+        for window in [1, 5, 20, 60] {
+            // 滚动和的累加顺序与标量实现相同，结果逐位一致
+            let kernel = rolling_mean(&values, window);
+            let reference = scalar::rolling_mean(&values, window);
+            assert!(kernel
+                .iter()
+                .zip(&reference)
+                .all(|(a, b)| a.to_bits() == b.to_bits()));
+            assert_close(
+                &rolling_std(&values, window),
+                &scalar::rolling_std(&values, window),
+            );
+        }
+    }
+
+    #[test]
+    fn test_short_input() {
+        let values = [1.0, 2.0, 3.0];
+        assert!(rolling_mean(&values, 5).iter().all(|v| v.is_nan()));
+        assert!(rolling_mean(&values, 0).iter().all(|v| v.is_nan()));
+        assert_eq!(rolling_mean(&values, 3)[2], 2.0);
+        assert_eq!(sum(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_ema() {
+        let values = [10.0, 11.0, 12.0];
+        let result = ema(&values, 3);
+        // 乘数为0.5
+        assert_eq!(result, vec![10.0, 10.5, 11.25]);
+        assert!(ema(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_rolling_regression() {
+        let benchmark = sample(30)
+            .iter()
+            .map(|v| v / 100.0 - 0.1)
+            .collect::<Vec<_>>();
+        let asset: Vec<f64> = benchmark.iter().map(|b| 0.002 + 1.5 * b).collect();
+
+        let (betas, alphas) = rolling_regression(&asset, &benchmark, 10);
+        assert!(betas[8].is_nan() && alphas[8].is_nan());
+        for i in 9..30 {
+            assert!((betas[i] - 1.5).abs() < 1e-9);
+            assert!((alphas[i] - 0.002).abs() < 1e-9);
+        }
+        assert_eq!(rolling_beta(&asset, &benchmark, 10)[29], betas[29]);
+    }
+}
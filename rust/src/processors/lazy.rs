@@ -0,0 +1,638 @@
+//! 惰性计算图模块
+//!
+//! 在 `ColumnarFrame` 上声明派生列（`with_column`）、选择输出（`select`）和按股票聚合
+//! （`group_by_symbol().agg`），调用 `collect` 时才计算：
+//! - 只计算输出列依赖的节点；
+//! - 结构相同的子表达式（如多个因子共用的 `rolling_mean(close, 20)`）只物化一次；
+//! - 逐元素运算（加减乘除、常数）融合为单次遍历，不为中间结果分配列。
+//!
+//! 滚动和滞后运算按股票分组（组内按时间排序）并行计算，窗口不跨股票。
+//! 指标列需先通过 `ColumnarFrame::add_indicator_columns` 计算。
+
+use crate::processors::columnar::{ColumnRef, ColumnarFrame};
+use crate::processors::extremes;
+use crate::processors::field::Field;
+use crate::processors::kernels;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// 二元运算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    /// 加
+    Add,
+    /// 减
+    Sub,
+    /// 乘
+    Mul,
+    /// 除
+    Div,
+}
+
+impl BinaryOp {
+    fn apply(self, left: f64, right: f64) -> f64 {
+        match self {
+            BinaryOp::Add => left + right,
+            BinaryOp::Sub => left - right,
+            BinaryOp::Mul => left * right,
+            BinaryOp::Div => left / right,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+        }
+    }
+}
+
+/// 滚动运算（窗口未满为NaN）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollingOp {
+    /// 均值
+    Mean,
+    /// 求和
+    Sum,
+    /// 总体标准差
+    Std,
+    /// 最小值
+    Min,
+    /// 最大值
+    Max,
+}
+
+impl RollingOp {
+    fn name(self) -> &'static str {
+        match self {
+            RollingOp::Mean => "rolling_mean",
+            RollingOp::Sum => "rolling_sum",
+            RollingOp::Std => "rolling_std",
+            RollingOp::Min => "rolling_min",
+            RollingOp::Max => "rolling_max",
+        }
+    }
+
+    fn apply(self, values: &[f64], window: usize) -> Vec<f64> {
+        let at = |indices: Vec<Option<usize>>| -> Vec<f64> {
+            indices
+                .into_iter()
+                .map(|index| index.map_or(f64::NAN, |i| values[i]))
+                .collect()
+        };
+        match self {
+            RollingOp::Mean => kernels::rolling_mean(values, window),
+            RollingOp::Sum => kernels::rolling_mean(values, window)
+                .into_iter()
+                .map(|mean| mean * window as f64)
+                .collect(),
+            RollingOp::Std => kernels::rolling_std(values, window),
+            RollingOp::Min => at(extremes::rolling_min_index(values, window)),
+            RollingOp::Max => at(extremes::rolling_max_index(values, window)),
+        }
+    }
+}
+
+/// 列表达式
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// 原始字段或已声明的派生列
+    Column(String),
+    /// 常数
+    Literal(f64),
+    /// 逐元素二元运算
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    /// 按股票滚动
+    Rolling {
+        /// 输入
+        input: Box<Expr>,
+        /// 运算
+        op: RollingOp,
+        /// 窗口
+        window: usize,
+    },
+    /// 按股票滞后N根K线（前N根为NaN）
+    Shift {
+        /// 输入
+        input: Box<Expr>,
+        /// 滞后K线数
+        periods: usize,
+    },
+}
+
+/// 引用字段或派生列
+pub fn col(name: &str) -> Expr {
+    Expr::Column(name.trim().to_string())
+}
+
+/// 常数
+pub fn lit(value: f64) -> Expr {
+    Expr::Literal(value)
+}
+
+impl Expr {
+    fn binary(self, op: BinaryOp, other: Expr) -> Expr {
+        Expr::Binary(Box::new(self), op, Box::new(other))
+    }
+
+    fn rolling(self, op: RollingOp, window: usize) -> Expr {
+        Expr::Rolling {
+            input: Box::new(self),
+            op,
+            window,
+        }
+    }
+
+    /// 滚动均值
+    pub fn rolling_mean(self, window: usize) -> Expr {
+        self.rolling(RollingOp::Mean, window)
+    }
+
+    /// 滚动求和
+    pub fn rolling_sum(self, window: usize) -> Expr {
+        self.rolling(RollingOp::Sum, window)
+    }
+
+    /// 滚动总体标准差
+    pub fn rolling_std(self, window: usize) -> Expr {
+        self.rolling(RollingOp::Std, window)
+    }
+
+    /// 滚动最小值
+    pub fn rolling_min(self, window: usize) -> Expr {
+        self.rolling(RollingOp::Min, window)
+    }
+
+    /// 滚动最大值
+    pub fn rolling_max(self, window: usize) -> Expr {
+        self.rolling(RollingOp::Max, window)
+    }
+
+    /// 滞后N根K线
+    pub fn shift(self, periods: usize) -> Expr {
+        Expr::Shift {
+            input: Box::new(self),
+            periods,
+        }
+    }
+
+    /// 相对N根K线前的变化率（当前值/N根前-1）
+    pub fn pct_change(self, periods: usize) -> Expr {
+        self.clone() / self.shift(periods) - lit(1.0)
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Column(name) => write!(f, "{}", name),
+            Expr::Literal(value) => write!(f, "{:?}", value),
+            Expr::Binary(left, op, right) => write!(f, "({} {} {})", left, op.symbol(), right),
+            Expr::Rolling { input, op, window } => {
+                write!(f, "{}({}, {})", op.name(), input, window)
+            }
+            Expr::Shift { input, periods } => write!(f, "shift({}, {})", input, periods),
+        }
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($trait:ident, $method:ident, $op:expr) => {
+        impl std::ops::$trait for Expr {
+            type Output = Expr;
+
+            fn $method(self, other: Expr) -> Expr {
+                self.binary($op, other)
+            }
+        }
+
+        impl std::ops::$trait<f64> for Expr {
+            type Output = Expr;
+
+            fn $method(self, other: f64) -> Expr {
+                self.binary($op, lit(other))
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, BinaryOp::Add);
+impl_binary_op!(Sub, sub, BinaryOp::Sub);
+impl_binary_op!(Mul, mul, BinaryOp::Mul);
+impl_binary_op!(Div, div, BinaryOp::Div);
+
+/// 按股票聚合方式（忽略NaN）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggOp {
+    /// 首个有效值
+    First,
+    /// 最后一个有效值
+    Last,
+    /// 均值
+    Mean,
+    /// 求和
+    Sum,
+    /// 最小值
+    Min,
+    /// 最大值
+    Max,
+    /// 有效值个数
+    Count,
+}
+
+impl AggOp {
+    fn apply(self, values: impl Iterator<Item = f64>) -> f64 {
+        let mut valid = values.filter(|value| !value.is_nan()).peekable();
+        if valid.peek().is_none() {
+            return match self {
+                AggOp::Count | AggOp::Sum => 0.0,
+                _ => f64::NAN,
+            };
+        }
+        match self {
+            AggOp::First => valid.next().unwrap_or(f64::NAN),
+            AggOp::Last => valid.last().unwrap_or(f64::NAN),
+            AggOp::Mean => {
+                let (sum, count) =
+                    valid.fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+                sum / count as f64
+            }
+            AggOp::Sum => valid.sum(),
+            AggOp::Min => valid.fold(f64::INFINITY, f64::min),
+            AggOp::Max => valid.fold(f64::NEG_INFINITY, f64::max),
+            AggOp::Count => valid.count() as f64,
+        }
+    }
+}
+
+/// 惰性列式计算
+#[derive(Debug, Clone)]
+pub struct LazyFrame<'a> {
+    /// 数据
+    frame: &'a ColumnarFrame,
+    /// 按声明顺序的派生列
+    columns: Vec<(String, Expr)>,
+    /// 输出列（缺省为全部派生列）
+    selection: Option<Vec<String>>,
+}
+
+impl<'a> LazyFrame<'a> {
+    /// 在列式数据上创建惰性计算
+    pub fn new(frame: &'a ColumnarFrame) -> Self {
+        Self {
+            frame,
+            columns: Vec::new(),
+            selection: None,
+        }
+    }
+
+    /// 声明派生列，同名列后声明的覆盖先声明的
+    pub fn with_column(mut self, name: &str, expr: Expr) -> Self {
+        self.columns.retain(|(existing, _)| existing != name);
+        self.columns.push((name.to_string(), expr));
+        self
+    }
+
+    /// 选择输出列（派生列或原始字段）
+    pub fn select(mut self, names: &[&str]) -> Self {
+        self.selection = Some(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// 按股票聚合
+    pub fn group_by_symbol(self) -> LazyGroupBy<'a> {
+        LazyGroupBy { lazy: self }
+    }
+
+    /// 计算输出列
+    pub fn collect(&self) -> Result<LazyResult> {
+        let names: Vec<String> = match &self.selection {
+            Some(names) => names.clone(),
+            None => self.columns.iter().map(|(name, _)| name.clone()).collect(),
+        };
+
+        let mut evaluator = Evaluator::new(self);
+        let mut columns = Vec::with_capacity(names.len());
+        for name in names {
+            let values = evaluator.materialize(&col(&name))?;
+            columns.push((name, values.as_ref().clone()));
+        }
+        Ok(LazyResult {
+            columns,
+            materialized: evaluator.cache.len(),
+        })
+    }
+}
+
+/// 计算结果（列与 `ColumnarFrame` 的行一一对应）
+#[derive(Debug, Clone)]
+pub struct LazyResult {
+    /// 输出列
+    columns: Vec<(String, Vec<f64>)>,
+    /// 物化的节点数
+    materialized: usize,
+}
+
+impl LazyResult {
+    /// 输出列名
+    pub fn names(&self) -> Vec<&str> {
+        self.columns.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// 按名称获取列
+    pub fn get(&self, name: &str) -> Option<&[f64]> {
+        self.columns
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    /// 计算过程中物化的节点数（共享的子表达式只计一次）
+    pub fn materialized_nodes(&self) -> usize {
+        self.materialized
+    }
+}
+
+/// 按股票聚合的惰性计算
+#[derive(Debug, Clone)]
+pub struct LazyGroupBy<'a> {
+    lazy: LazyFrame<'a>,
+}
+
+/// 单只股票的聚合结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupRow {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 聚合值，顺序与 `agg` 的参数一致
+    pub values: Vec<f64>,
+}
+
+impl LazyGroupBy<'_> {
+    /// 对每只股票计算（名称, 表达式, 聚合方式），结果按（股票代码, 市场）排序
+    pub fn agg(&self, aggs: &[(&str, Expr, AggOp)]) -> Result<Vec<GroupRow>> {
+        let frame = self.lazy.frame;
+        let mut evaluator = Evaluator::new(&self.lazy);
+        let columns: Vec<Rc<Vec<f64>>> = aggs
+            .iter()
+            .map(|(_, expr, _)| evaluator.materialize(expr))
+            .collect::<Result<_>>()?;
+
+        let mut rows: Vec<GroupRow> = frame
+            .symbol_groups()
+            .into_iter()
+            .map(|(id, rows)| GroupRow {
+                symbol: frame.symbol(id).to_string(),
+                market: frame.market(id).to_string(),
+                values: aggs
+                    .iter()
+                    .zip(&columns)
+                    .map(|((_, _, op), column)| op.apply(rows.iter().map(|&row| column[row])))
+                    .collect(),
+            })
+            .collect();
+        rows.sort_by(|a, b| (&a.symbol, &a.market).cmp(&(&b.symbol, &b.market)));
+        Ok(rows)
+    }
+}
+
+/// 融合后的逐元素计算树，叶子为已物化的列或原始列
+enum Fused<'a> {
+    Materialized(Rc<Vec<f64>>),
+    Source(ColumnRef<'a>),
+    Literal(f64),
+    Binary(Box<Fused<'a>>, BinaryOp, Box<Fused<'a>>),
+}
+
+impl Fused<'_> {
+    #[inline]
+    fn eval(&self, row: usize) -> f64 {
+        match self {
+            Fused::Materialized(values) => values[row],
+            Fused::Source(column) => column.get(row),
+            Fused::Literal(value) => *value,
+            Fused::Binary(left, op, right) => op.apply(left.eval(row), right.eval(row)),
+        }
+    }
+}
+
+/// 求值器：按表达式结构缓存物化结果
+struct Evaluator<'a> {
+    frame: &'a ColumnarFrame,
+    definitions: HashMap<&'a str, &'a Expr>,
+    /// 表达式文本 -> 物化结果
+    cache: HashMap<String, Rc<Vec<f64>>>,
+    /// 正在展开的派生列（检测循环引用）
+    resolving: Vec<String>,
+    /// 按股票分组的行（组内按时间排序）
+    groups: Option<Vec<Vec<usize>>>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(lazy: &'a LazyFrame<'a>) -> Self {
+        Self {
+            frame: lazy.frame,
+            definitions: lazy
+                .columns
+                .iter()
+                .map(|(name, expr)| (name.as_str(), expr))
+                .collect(),
+            cache: HashMap::new(),
+            resolving: Vec::new(),
+            groups: None,
+        }
+    }
+
+    /// 物化表达式为整列（派生列名直接解析为其定义，不重复缓存）
+    fn materialize(&mut self, expr: &Expr) -> Result<Rc<Vec<f64>>> {
+        if let Expr::Column(name) = expr {
+            if let Some(&definition) = self.definitions.get(name.as_str()) {
+                if self.resolving.contains(name) {
+                    return Err(anyhow::anyhow!("派生列存在循环引用: {}", name));
+                }
+                self.resolving.push(name.clone());
+                let values = self.materialize(definition);
+                self.resolving.pop();
+                return values;
+            }
+        }
+
+        let key = expr.to_string();
+        if let Some(values) = self.cache.get(&key) {
+            return Ok(values.clone());
+        }
+
+        let values = match expr {
+            Expr::Rolling { input, op, window } => {
+                let (op, window) = (*op, *window);
+                self.per_symbol(input, |values| op.apply(values, window))?
+            }
+            Expr::Shift { input, periods } => {
+                let periods = *periods;
+                self.per_symbol(input, |values| {
+                    (0..values.len())
+                        .map(|i| i.checked_sub(periods).map_or(f64::NAN, |j| values[j]))
+                        .collect()
+                })?
+            }
+            _ => {
+                let fused = self.fuse(expr)?;
+                (0..self.frame.len()).map(|row| fused.eval(row)).collect()
+            }
+        };
+        let values = Rc::new(values);
+        self.cache.insert(key, values.clone());
+        Ok(values)
+    }
+
+    /// 把逐元素子树编译为融合树，遇到滚动/滞后/派生列时物化
+    fn fuse(&mut self, expr: &Expr) -> Result<Fused<'a>> {
+        Ok(match expr {
+            Expr::Literal(value) => Fused::Literal(*value),
+            Expr::Binary(left, op, right) => {
+                Fused::Binary(Box::new(self.fuse(left)?), *op, Box::new(self.fuse(right)?))
+            }
+            Expr::Column(name) if !self.definitions.contains_key(name.as_str()) => {
+                let field: Field = name.parse()?;
+                Fused::Source(self.frame.column(&field)?)
+            }
+            _ => Fused::Materialized(self.materialize(expr)?),
+        })
+    }
+
+    /// 按股票分组计算，结果写回原行位置
+    ///
+    /// 输入的逐元素部分在收集各组数据时直接求值，不单独物化；各组计算并行执行。
+    fn per_symbol<F>(&mut self, input: &Expr, f: F) -> Result<Vec<f64>>
+    where
+        F: Fn(&[f64]) -> Vec<f64> + Sync,
+    {
+        let input = self.fuse(input)?;
+        let frame = self.frame;
+        let groups = self.groups.get_or_insert_with(|| {
+            frame
+                .symbol_groups()
+                .into_iter()
+                .map(|(_, rows)| rows)
+                .collect()
+        });
+
+        let inputs: Vec<Vec<f64>> = groups
+            .iter()
+            .map(|rows| rows.iter().map(|&row| input.eval(row)).collect())
+            .collect();
+        let results: Vec<Vec<f64>> = inputs.par_iter().map(|values| f(values)).collect();
+
+        let mut output = vec![f64::NAN; frame.len()];
+        for (rows, values) in groups.iter().zip(results) {
+            for (&row, value) in rows.iter().zip(values) {
+                output[row] = value;
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use chrono::NaiveDate;
+
+    fn create_frame() -> ColumnarFrame {
+        let mut data = Vec::new();
+        for day in (0..5).rev() {
+            for (symbol, base) in [("600000", 10.0), ("000001", 20.0)] {
+                let close = base + day as f64;
+                data.push(TDXDayRecord {
+                    date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+                        + chrono::Duration::days(day),
+                    symbol: symbol.to_string(),
+                    open: close,
+                    high: close + 1.0,
+                    low: close - 1.0,
+                    close,
+                    volume: 100 * (day as u64 + 1),
+                    amount: close * 100.0,
+                    market: if symbol == "600000" { "SH" } else { "SZ" }.to_string(),
+                });
+            }
+        }
+        ColumnarFrame::from_records(&data)
+    }
+
+    #[test]
+    fn test_collect_shares_subexpressions() {
+        let frame = create_frame();
+        let ma3 = col("close").rolling_mean(3);
+        let result = LazyFrame::new(&frame)
+            .with_column("ma3", ma3.clone())
+            .with_column("bias", (col("close") - ma3.clone()) / ma3.clone())
+            .with_column("range", col("high") - col("low"))
+            .with_column("ret", col("close").pct_change(1))
+            .select(&["bias", "ma3", "close"])
+            .collect()
+            .unwrap();
+
+        assert_eq!(result.names(), vec!["bias", "ma3", "close"]);
+        // 行0为600000第4天：MA3 = 13，bias = 1/13
+        let ma3_values = result.get("ma3").unwrap();
+        assert_eq!(ma3_values[0], 13.0);
+        assert_eq!(ma3_values[1], 23.0);
+        assert!((result.get("bias").unwrap()[0] - 1.0 / 13.0).abs() < 1e-12);
+        assert!(ma3_values[6].is_nan());
+        assert_eq!(result.get("close").unwrap()[1], 24.0);
+        // 未选择的range、ret不计算；MA3只物化一次，bias的逐元素部分融合为一列，另有输出的close
+        assert_eq!(result.materialized_nodes(), 3);
+        assert!(result.get("range").is_none());
+    }
+
+    #[test]
+    fn test_shift_and_errors() {
+        let frame = create_frame();
+        let result = LazyFrame::new(&frame)
+            .with_column("prev", col("close").shift(1))
+            .with_column("ret", col("close").pct_change(1))
+            .with_column("vol_max", col("volume").rolling_max(2) * 2.0)
+            .collect()
+            .unwrap();
+        assert_eq!(result.get("prev").unwrap()[0], 13.0);
+        assert!(result.get("prev").unwrap()[8].is_nan());
+        assert!((result.get("ret").unwrap()[0] - 1.0 / 13.0).abs() < 1e-12);
+        assert_eq!(result.get("vol_max").unwrap()[0], 1000.0);
+
+        let cyclic = LazyFrame::new(&frame)
+            .with_column("a", col("b") + 1.0)
+            .with_column("b", col("a") * 2.0);
+        assert!(cyclic.collect().is_err());
+        assert!(LazyFrame::new(&frame)
+            .with_column("x", col("unknown"))
+            .collect()
+            .is_err());
+    }
+
+    #[test]
+    fn test_group_by_symbol_agg() {
+        let frame = create_frame();
+        let rows = LazyFrame::new(&frame)
+            .with_column("ret", col("close").pct_change(1))
+            .group_by_symbol()
+            .agg(&[
+                ("last", col("close"), AggOp::Last),
+                ("count", col("ret"), AggOp::Count),
+                ("max_volume", col("volume"), AggOp::Max),
+            ])
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].symbol, "000001");
+        assert_eq!(rows[0].values, vec![24.0, 4.0, 500.0]);
+        assert_eq!(rows[1].values[0], 14.0);
+    }
+}
@@ -0,0 +1,295 @@
+//! 支撑/阻力位模块
+//!
+//! 把历史拐点价格（默认取之字转向已确认的拐点，也可传入其他来源的拐点）按价格容差
+//! 聚类为水平价位。每个价位记录触及次数，并按拐点距今的天数做指数衰减得到强度，
+//! 价位取拐点价格按同样权重的加权均值。低于参考价（最新收盘价）的为支撑位，其余为阻力位。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::zigzag::{Pivot, ZigZag};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 价位类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LevelKind {
+    /// 支撑位
+    Support,
+    /// 阻力位
+    Resistance,
+}
+
+/// 支撑/阻力价位
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 价位（拐点价格的加权均值）
+    pub price: f64,
+    /// 类型（相对参考价）
+    pub kind: LevelKind,
+    /// 触及次数（聚入的拐点数）
+    pub touches: usize,
+    /// 最早触及日期
+    pub first_date: NaiveDate,
+    /// 最近触及日期
+    pub last_date: NaiveDate,
+    /// 强度（按距今天数衰减的触及次数）
+    pub strength: f64,
+}
+
+/// 支撑/阻力位提取器
+#[derive(Debug, Clone)]
+pub struct LevelExtractor {
+    /// 拐点检测
+    zigzag: ZigZag,
+    /// 聚类价格容差（%，相对聚类均价）
+    tolerance_percent: f64,
+    /// 强度衰减半衰期（自然日）
+    half_life_days: f64,
+    /// 最少触及次数
+    min_touches: usize,
+}
+
+impl Default for LevelExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LevelExtractor {
+    /// 创建提取器：5%之字转向拐点，容差1.5%，半衰期180天，至少触及2次
+    pub fn new() -> Self {
+        Self {
+            zigzag: ZigZag::new(),
+            tolerance_percent: 1.5,
+            half_life_days: 180.0,
+            min_touches: 2,
+        }
+    }
+
+    /// 设置拐点检测
+    pub fn with_zigzag(mut self, zigzag: ZigZag) -> Self {
+        self.zigzag = zigzag;
+        self
+    }
+
+    /// 设置聚类价格容差（%）
+    pub fn with_tolerance_percent(mut self, percent: f64) -> Self {
+        self.tolerance_percent = percent.max(0.0);
+        self
+    }
+
+    /// 设置强度衰减半衰期（自然日）
+    pub fn with_half_life_days(mut self, days: f64) -> Self {
+        self.half_life_days = days.max(1.0);
+        self
+    }
+
+    /// 设置最少触及次数
+    pub fn with_min_touches(mut self, touches: usize) -> Self {
+        self.min_touches = touches.max(1);
+        self
+    }
+
+    /// 提取每只股票截至最后一根K线的价位，结果按（股票代码, 市场, 价位）排序
+    ///
+    /// 以最新收盘价为参考价区分支撑和阻力。
+    pub fn extract(&self, data: &[TDXDayRecord]) -> Vec<PriceLevel> {
+        let mut latest: HashMap<(&str, &str), &TDXDayRecord> = HashMap::new();
+        for record in data {
+            let entry = latest
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_insert(record);
+            if record.date > entry.date {
+                *entry = record;
+            }
+        }
+
+        let mut pivots: HashMap<(String, String), Vec<Pivot>> = HashMap::new();
+        for pivot in self.zigzag.detect(data) {
+            pivots
+                .entry((pivot.symbol.clone(), pivot.market.clone()))
+                .or_default()
+                .push(pivot);
+        }
+
+        let mut levels: Vec<PriceLevel> = pivots
+            .into_iter()
+            .flat_map(|((symbol, market), pivots)| {
+                let last = latest[&(symbol.as_str(), market.as_str())];
+                self.cluster(&pivots, last.date, last.close)
+            })
+            .collect();
+        levels.sort_by(|a, b| {
+            (&a.symbol, &a.market)
+                .cmp(&(&b.symbol, &b.market))
+                .then(a.price.total_cmp(&b.price))
+        });
+        levels
+    }
+
+    /// 把一只股票的拐点聚类为价位，按价位升序
+    ///
+    /// `as_of` 为计算强度的基准日期，只使用此前已确认的拐点；`reference_price` 用于区分支撑和阻力。
+    pub fn cluster(
+        &self,
+        pivots: &[Pivot],
+        as_of: NaiveDate,
+        reference_price: f64,
+    ) -> Vec<PriceLevel> {
+        let mut sorted: Vec<&Pivot> = pivots
+            .iter()
+            .filter(|pivot| pivot.confirmed_date.is_some_and(|date| date <= as_of))
+            .filter(|pivot| pivot.price > 0.0)
+            .collect();
+        sorted.sort_by(|a, b| a.price.total_cmp(&b.price));
+
+        // 价格升序扫描，与当前聚类均价相差不超过容差的并入
+        let tolerance = self.tolerance_percent / 100.0;
+        let mut clusters: Vec<Vec<&Pivot>> = Vec::new();
+        let mut mean = 0.0;
+        for pivot in sorted {
+            match clusters.last_mut() {
+                Some(cluster) if pivot.price <= mean * (1.0 + tolerance) => {
+                    cluster.push(pivot);
+                    mean += (pivot.price - mean) / cluster.len() as f64;
+                }
+                _ => {
+                    clusters.push(vec![pivot]);
+                    mean = pivot.price;
+                }
+            }
+        }
+
+        clusters
+            .into_iter()
+            .filter(|cluster| cluster.len() >= self.min_touches)
+            .map(|cluster| {
+                let weight = |pivot: &Pivot| {
+                    let age = (as_of - pivot.date).num_days() as f64;
+                    0.5f64.powf(age / self.half_life_days)
+                };
+                let strength: f64 = cluster.iter().map(|pivot| weight(pivot)).sum();
+                let price = cluster
+                    .iter()
+                    .map(|pivot| pivot.price * weight(pivot))
+                    .sum::<f64>()
+                    / strength;
+                PriceLevel {
+                    symbol: cluster[0].symbol.clone(),
+                    market: cluster[0].market.clone(),
+                    price,
+                    kind: if price < reference_price {
+                        LevelKind::Support
+                    } else {
+                        LevelKind::Resistance
+                    },
+                    touches: cluster.len(),
+                    first_date: cluster.iter().map(|pivot| pivot.date).min().unwrap(),
+                    last_date: cluster.iter().map(|pivot| pivot.date).max().unwrap(),
+                    strength,
+                }
+            })
+            .collect()
+    }
+
+    /// 参考价下方最近的支撑位（`levels` 为同一只股票的价位）
+    pub fn nearest_support(levels: &[PriceLevel], price: f64) -> Option<&PriceLevel> {
+        levels
+            .iter()
+            .filter(|level| level.price < price)
+            .max_by(|a, b| a.price.total_cmp(&b.price))
+    }
+
+    /// 参考价上方最近的阻力位（`levels` 为同一只股票的价位）
+    pub fn nearest_resistance(levels: &[PriceLevel], price: f64) -> Option<&PriceLevel> {
+        levels
+            .iter()
+            .filter(|level| level.price >= price)
+            .min_by(|a, b| a.price.total_cmp(&b.price))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::zigzag::SwingKind;
+
+    fn pivot(day: i64, price: f64, kind: SwingKind) -> Pivot {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(day);
+        Pivot {
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            date,
+            price,
+            kind,
+            confirmed_date: Some(date + chrono::Duration::days(1)),
+        }
+    }
+
+    #[test]
+    fn test_cluster_levels() {
+        let pivots = vec![
+            pivot(0, 10.0, SwingKind::Low),
+            pivot(10, 12.0, SwingKind::High),
+            pivot(20, 10.1, SwingKind::Low),
+            pivot(30, 12.1, SwingKind::High),
+            pivot(40, 11.0, SwingKind::Low),
+            pivot(50, 12.05, SwingKind::High),
+        ];
+        let as_of = NaiveDate::from_ymd_opt(2024, 2, 21).unwrap();
+        let levels = LevelExtractor::new()
+            .with_half_life_days(10.0)
+            .cluster(&pivots, as_of, 11.5);
+
+        // 11.0 只触及一次，被过滤
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].kind, LevelKind::Support);
+        assert_eq!(levels[0].touches, 2);
+        // 较近的10.1权重更大
+        assert!(levels[0].price > 10.05 && levels[0].price < 10.1);
+        assert_eq!(levels[1].kind, LevelKind::Resistance);
+        assert_eq!(levels[1].touches, 3);
+        assert_eq!(levels[1].last_date, pivots[5].date);
+        assert!(levels[1].strength > levels[0].strength);
+
+        assert_eq!(
+            LevelExtractor::nearest_support(&levels, 11.5).map(|l| l.touches),
+            Some(2)
+        );
+        assert!(LevelExtractor::nearest_resistance(&levels, 12.5).is_none());
+    }
+
+    #[test]
+    fn test_extract_from_records() {
+        let closes = [10.0, 11.0, 12.0, 11.0, 10.0, 11.0, 12.0, 11.0, 10.0, 11.0];
+        let data: Vec<TDXDayRecord> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| TDXDayRecord {
+                date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+                    + chrono::Duration::days(i as i64),
+                symbol: "600000".to_string(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1000,
+                amount: close * 1000.0,
+                market: "SH".to_string(),
+            })
+            .collect();
+
+        let levels = LevelExtractor::new().extract(&data);
+        assert_eq!(levels.len(), 2);
+        assert!((levels[0].price - 10.0).abs() < 1e-9);
+        assert_eq!(levels[0].kind, LevelKind::Support);
+        // 第8根的低点在第9根确认，计入支撑
+        assert_eq!(levels[0].touches, 3);
+        assert!((levels[1].price - 12.0).abs() < 1e-9);
+        assert_eq!(levels[1].kind, LevelKind::Resistance);
+    }
+}
@@ -0,0 +1,405 @@
+//! 涨跌停检测模块
+//!
+//! 按板块和证券状态确定涨跌幅限制（主板10%、科创板/创业板20%、主板ST 5%、
+//! 新股首日可配置），根据前收盘价计算涨跌停价并输出 `LimitEvent`。
+//! 新股上市首日没有前收盘价，提供发行价时以发行价为参考价。
+
+use crate::parsers::{AssetClass, TDXDayRecord};
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 价格比较容差（元），涨跌停价精确到分
+const PRICE_TOLERANCE: f64 = 0.005;
+
+/// 交易板块
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Board {
+    /// 沪深主板
+    Main,
+    /// 科创板（688/689）
+    Star,
+    /// 创业板（300/301）
+    ChiNext,
+    /// 北交所
+    Beijing,
+    /// 无法识别（指数、基金等）
+    Unknown,
+}
+
+impl Board {
+    /// 根据股票代码和市场识别板块
+    ///
+    /// 只有 `AssetClass::classify` 识别为A股的代码有板块，再按市场和代码前缀细分。
+    pub fn classify(symbol: &str, market: &str) -> Self {
+        if AssetClass::classify(symbol, market) != AssetClass::Stock {
+            return Board::Unknown;
+        }
+        match (
+            market.to_uppercase().as_str(),
+            symbol.get(0..3).unwrap_or(""),
+        ) {
+            ("BJ", _) => Board::Beijing,
+            (_, "688" | "689") => Board::Star,
+            (_, "300" | "301") => Board::ChiNext,
+            _ => Board::Main,
+        }
+    }
+}
+
+/// 涨跌幅限制规则（百分比）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitRules {
+    /// 主板
+    pub main_board_percent: f64,
+    /// 科创板/创业板
+    pub growth_board_percent: f64,
+    /// 北交所
+    pub beijing_percent: f64,
+    /// 主板ST/*ST股票（其他板块的ST股票与本板块一致）
+    pub st_percent: f64,
+    /// 新股上市首日，None表示首日不设涨跌幅限制
+    pub ipo_first_day_percent: Option<f64>,
+}
+
+impl Default for LimitRules {
+    fn default() -> Self {
+        Self {
+            main_board_percent: 10.0,
+            growth_board_percent: 20.0,
+            beijing_percent: 30.0,
+            st_percent: 5.0,
+            ipo_first_day_percent: Some(30.0),
+        }
+    }
+}
+
+/// 涨跌停事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimitKind {
+    /// 收盘涨停
+    LimitUp,
+    /// 收盘跌停
+    LimitDown,
+    /// 盘中触及涨停但未封板
+    TouchedUp,
+    /// 盘中触及跌停但未封板
+    TouchedDown,
+}
+
+/// 涨跌停事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitEvent {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 事件类型
+    pub kind: LimitKind,
+    /// 所属板块
+    pub board: Board,
+    /// 适用的涨跌幅限制（%）
+    pub limit_percent: f64,
+    /// 前收盘价
+    pub prev_close: f64,
+    /// 涨跌停价
+    pub limit_price: f64,
+    /// 收盘价
+    pub close: f64,
+}
+
+/// 涨跌停检测器
+#[derive(Debug, Clone, Default)]
+pub struct LimitDetector {
+    /// 涨跌幅规则
+    rules: LimitRules,
+    /// ST股票集合，键为（代码, 市场）
+    st_symbols: HashSet<(String, String)>,
+    /// 上市日期，键为（代码, 市场）
+    listing_dates: HashMap<(String, String), NaiveDate>,
+    /// 新股发行价，键为（代码, 市场）
+    issue_prices: HashMap<(String, String), f64>,
+}
+
+impl LimitDetector {
+    /// 创建新的涨跌停检测器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置涨跌幅规则
+    pub fn with_rules(mut self, rules: LimitRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// 设置ST股票集合（代码, 市场），沪深同代码的证券互不影响
+    pub fn with_st_symbols(mut self, st_symbols: HashSet<(String, String)>) -> Self {
+        self.st_symbols = st_symbols
+            .into_iter()
+            .map(|(symbol, market)| symbol_key(&symbol, &market))
+            .collect();
+        self
+    }
+
+    /// 设置上市日期（用于识别新股首日），键为（代码, 市场）
+    pub fn with_listing_dates(
+        mut self,
+        listing_dates: HashMap<(String, String), NaiveDate>,
+    ) -> Self {
+        self.listing_dates = listing_dates
+            .into_iter()
+            .map(|((symbol, market), date)| (symbol_key(&symbol, &market), date))
+            .collect();
+        self
+    }
+
+    /// 设置新股发行价（作为上市首日的参考价），键为（代码, 市场）
+    pub fn with_issue_prices(mut self, issue_prices: HashMap<(String, String), f64>) -> Self {
+        self.issue_prices = issue_prices
+            .into_iter()
+            .map(|((symbol, market), price)| (symbol_key(&symbol, &market), price))
+            .collect();
+        self
+    }
+
+    /// 获取某只股票在指定日期适用的涨跌幅限制（%），None表示无限制
+    pub fn limit_percent(&self, symbol: &str, market: &str, date: NaiveDate) -> Option<f64> {
+        let key = symbol_key(symbol, market);
+        if self.listing_dates.get(&key) == Some(&date) {
+            return self.rules.ipo_first_day_percent;
+        }
+
+        match Board::classify(symbol, market) {
+            Board::Main if self.st_symbols.contains(&key) => Some(self.rules.st_percent),
+            Board::Main => Some(self.rules.main_board_percent),
+            Board::Star | Board::ChiNext => Some(self.rules.growth_board_percent),
+            Board::Beijing => Some(self.rules.beijing_percent),
+            Board::Unknown => None,
+        }
+    }
+
+    /// 计算涨停价和跌停价（四舍五入到分）
+    pub fn limit_prices(prev_close: f64, limit_percent: f64) -> (f64, f64) {
+        let round_cent = |price: f64| (price * 100.0).round() / 100.0;
+        (
+            round_cent(prev_close * (1.0 + limit_percent / 100.0)),
+            round_cent(prev_close * (1.0 - limit_percent / 100.0)),
+        )
+    }
+
+    /// 判断单根K线相对前收盘价的涨跌停状态
+    pub fn classify_bar(
+        &self,
+        record: &TDXDayRecord,
+        prev_close: f64,
+    ) -> Option<(LimitKind, f64, f64)> {
+        if prev_close <= 0.0 {
+            return None;
+        }
+
+        let limit_percent = self.limit_percent(&record.symbol, &record.market, record.date)?;
+        let (up_price, down_price) = Self::limit_prices(prev_close, limit_percent);
+
+        let kind = if record.close >= up_price - PRICE_TOLERANCE {
+            LimitKind::LimitUp
+        } else if record.close <= down_price + PRICE_TOLERANCE {
+            LimitKind::LimitDown
+        } else if record.high >= up_price - PRICE_TOLERANCE {
+            LimitKind::TouchedUp
+        } else if record.low <= down_price + PRICE_TOLERANCE {
+            LimitKind::TouchedDown
+        } else {
+            return None;
+        };
+
+        let limit_price = match kind {
+            LimitKind::LimitUp | LimitKind::TouchedUp => up_price,
+            LimitKind::LimitDown | LimitKind::TouchedDown => down_price,
+        };
+
+        Some((kind, limit_percent, limit_price))
+    }
+
+    /// 检测数据集中的涨跌停事件（按股票、日期排序）
+    pub fn detect(&self, data: &[TDXDayRecord]) -> Result<Vec<LimitEvent>> {
+        let mut groups: HashMap<(&str, &str), Vec<&TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        let mut events = Vec::new();
+        for records in groups.values_mut() {
+            records.sort_by_key(|r| r.date);
+
+            // 首根K线为上市首日时以发行价为参考价，其余K线以前一日收盘价为参考价
+            let first = records
+                .first()
+                .and_then(|record| Some((self.issue_price(record)?, *record)));
+            let pairs = records.windows(2).map(|pair| (pair[0].close, pair[1]));
+            for (prev_close, record) in first.into_iter().chain(pairs) {
+                if let Some((kind, limit_percent, limit_price)) =
+                    self.classify_bar(record, prev_close)
+                {
+                    events.push(LimitEvent {
+                        symbol: record.symbol.clone(),
+                        market: record.market.clone(),
+                        date: record.date,
+                        kind,
+                        board: Board::classify(&record.symbol, &record.market),
+                        limit_percent,
+                        prev_close,
+                        limit_price,
+                        close: record.close,
+                    });
+                }
+            }
+        }
+
+        events.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.date.cmp(&b.date)));
+        Ok(events)
+    }
+
+    /// 上市首日K线的参考价（发行价），非首日或未提供发行价时为None
+    ///
+    /// 股票的首根K线没有前收盘价，`detect` 和市场统计都以此判断首根K线的涨跌停。
+    pub fn issue_price(&self, record: &TDXDayRecord) -> Option<f64> {
+        let key = symbol_key(&record.symbol, &record.market);
+        if self.listing_dates.get(&key) != Some(&record.date) {
+            return None;
+        }
+        self.issue_prices.get(&key).copied()
+    }
+}
+
+/// 查找键（代码, 大写市场）
+fn symbol_key(symbol: &str, market: &str) -> (String, String) {
+    (symbol.to_string(), market.to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, market: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: market.to_string(),
+        }
+    }
+
+    fn key_set(symbol: &str, market: &str) -> HashSet<(String, String)> {
+        [(symbol.to_string(), market.to_string())]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_board_classification() {
+        assert_eq!(Board::classify("600000", "SH"), Board::Main);
+        assert_eq!(Board::classify("688981", "SH"), Board::Star);
+        assert_eq!(Board::classify("300750", "SZ"), Board::ChiNext);
+        assert_eq!(Board::classify("000001", "SZ"), Board::Main);
+        assert_eq!(Board::classify("000001", "SH"), Board::Unknown);
+        assert_eq!(Board::classify("430047", "BJ"), Board::Beijing);
+        assert_eq!(Board::classify("899050", "BJ"), Board::Unknown);
+    }
+
+    #[test]
+    fn test_board_aware_limits() {
+        let detector = LimitDetector::new();
+        let data = vec![
+            // 主板涨停：10.00 -> 11.00
+            create_test_record("600000", "SH", 2, 10.0),
+            create_test_record("600000", "SH", 3, 11.0),
+            // 创业板涨11%不是涨停
+            create_test_record("300750", "SZ", 2, 10.0),
+            create_test_record("300750", "SZ", 3, 11.1),
+            // 创业板跌停：10.00 -> 8.00
+            create_test_record("300750", "SZ", 4, 8.88),
+        ];
+
+        let events = detector.detect(&data).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].symbol, "300750");
+        assert_eq!(events[0].kind, LimitKind::LimitDown);
+        assert_eq!(events[0].limit_price, 8.88);
+        assert_eq!(events[1].kind, LimitKind::LimitUp);
+        assert_eq!(events[1].board, Board::Main);
+    }
+
+    #[test]
+    fn test_ipo_first_day_against_issue_price() {
+        let listing = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let detector = LimitDetector::new()
+            .with_listing_dates(
+                [(("603000".to_string(), "SH".to_string()), listing)]
+                    .into_iter()
+                    .collect(),
+            )
+            .with_issue_prices(
+                [(("603000".to_string(), "SH".to_string()), 10.0)]
+                    .into_iter()
+                    .collect(),
+            );
+        let data = vec![
+            // 首日按30%限制：10.00 -> 13.00
+            create_test_record("603000", "SH", 2, 13.0),
+            // 次日恢复主板10%
+            create_test_record("603000", "SH", 3, 14.3),
+        ];
+
+        let events = detector.detect(&data).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].date, listing);
+        assert_eq!(events[0].kind, LimitKind::LimitUp);
+        assert_eq!(events[0].limit_percent, 30.0);
+        assert_eq!(events[0].prev_close, 10.0);
+        assert_eq!(events[1].limit_percent, 10.0);
+
+        // 没有发行价时首日无法判断
+        let detector = LimitDetector::new().with_listing_dates(
+            [(("603000".to_string(), "SH".to_string()), listing)]
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(detector.detect(&data).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_st_and_touched_limit() {
+        let detector = LimitDetector::new().with_st_symbols(key_set("600000", "sh"));
+
+        let mut record = create_test_record("600000", "SH", 3, 10.2);
+        record.high = 10.5;
+
+        let (kind, limit_percent, limit_price) = detector.classify_bar(&record, 10.0).unwrap();
+        assert_eq!(kind, LimitKind::TouchedUp);
+        assert_eq!(limit_percent, 5.0);
+        assert_eq!(limit_price, 10.5);
+
+        // 创业板ST股票仍为20%
+        let detector = LimitDetector::new().with_st_symbols(key_set("300001", "SZ"));
+        let date = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        assert_eq!(detector.limit_percent("300001", "SZ", date), Some(20.0));
+        let record = create_test_record("300001", "SZ", 3, 10.5);
+        assert!(detector.classify_bar(&record, 10.0).is_none());
+
+        // 深市平安银行不继承沪市同代码证券的ST状态
+        let detector = LimitDetector::new().with_st_symbols(key_set("000001", "SH"));
+        assert_eq!(detector.limit_percent("000001", "SZ", date), Some(10.0));
+    }
+}
@@ -0,0 +1,365 @@
+//! 记录级数据血缘模块
+//!
+//! `Lineage` 是与数据按位置一一对应的旁路结构：记录每条记录来自哪个数据源（文件路径或URL、
+//! 解析时间、管线版本），以及哪些处理步骤（清洗规则、转换）修改过它。
+//! 清洗和转换通过 `DataCleaner::clean_with_lineage`、`DataTransformer::transform_data_with_lineage`
+//! 同步更新血缘；写入快照时按（股票代码, 市场, 时间）保存为 `lineage.json`，读取时重新对齐，
+//! 审计时可从任一存储值追溯到原始来源。
+
+use crate::parsers::Bar;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// 数据源
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineageSource {
+    /// 文件路径或URL
+    pub location: String,
+    /// 解析时间
+    pub parsed_at: DateTime<Utc>,
+    /// 解析时的管线版本
+    pub pipeline_version: String,
+}
+
+impl LineageSource {
+    /// 创建数据源，解析时间取当前时间，管线版本取本库版本
+    pub fn new(location: impl Into<String>) -> Self {
+        Self {
+            location: location.into(),
+            parsed_at: Utc::now(),
+            pipeline_version: crate::VERSION.to_string(),
+        }
+    }
+
+    /// 设置解析时间
+    pub fn with_parsed_at(mut self, parsed_at: DateTime<Utc>) -> Self {
+        self.parsed_at = parsed_at;
+        self
+    }
+}
+
+/// 单条记录的血缘
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordLineage {
+    /// 数据源在 `Lineage::sources` 中的位置
+    pub source: usize,
+    /// 修改过该记录的步骤在 `Lineage::steps` 中的位置，按应用顺序
+    pub steps: Vec<usize>,
+}
+
+/// 单条记录的追溯结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordTrace<'a> {
+    /// 数据源
+    pub source: &'a LineageSource,
+    /// 修改过该记录的步骤名称
+    pub steps: Vec<&'a str>,
+}
+
+/// 数据集血缘，`records` 与数据按位置一一对应
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lineage {
+    /// 数据源
+    sources: Vec<LineageSource>,
+    /// 已应用的处理步骤（不重复）
+    steps: Vec<String>,
+    /// 每条记录的血缘
+    records: Vec<RecordLineage>,
+}
+
+impl Lineage {
+    /// 创建空血缘
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 同一数据源解析出的 `count` 条记录
+    pub fn from_source(source: LineageSource, count: usize) -> Self {
+        Self {
+            sources: vec![source],
+            steps: Vec::new(),
+            records: vec![
+                RecordLineage {
+                    source: 0,
+                    steps: Vec::new(),
+                };
+                count
+            ],
+        }
+    }
+
+    /// 追加另一份数据的血缘（对应数据拼接在末尾）
+    pub fn extend(&mut self, other: Lineage) {
+        let source_offset = self.sources.len();
+        let step_map: Vec<usize> = other
+            .steps
+            .into_iter()
+            .map(|step| self.add_step(step))
+            .collect();
+        self.sources.extend(other.sources);
+        self.records
+            .extend(other.records.into_iter().map(|record| RecordLineage {
+                source: record.source + source_offset,
+                steps: record.steps.into_iter().map(|s| step_map[s]).collect(),
+            }));
+    }
+
+    /// 记录数
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// 数据源
+    pub fn sources(&self) -> &[LineageSource] {
+        &self.sources
+    }
+
+    /// 已应用的处理步骤
+    pub fn steps(&self) -> &[String] {
+        &self.steps
+    }
+
+    /// 每条记录的血缘
+    pub fn records(&self) -> &[RecordLineage] {
+        &self.records
+    }
+
+    /// 追溯第 `index` 条记录
+    pub fn trace(&self, index: usize) -> Option<RecordTrace<'_>> {
+        let record = self.records.get(index)?;
+        Some(RecordTrace {
+            source: &self.sources[record.source],
+            steps: record
+                .steps
+                .iter()
+                .map(|&s| self.steps[s].as_str())
+                .collect(),
+        })
+    }
+
+    /// 登记处理步骤，返回其位置（同名步骤只登记一次）
+    pub fn add_step(&mut self, name: impl Into<String>) -> usize {
+        let name = name.into();
+        match self.steps.iter().position(|step| *step == name) {
+            Some(index) => index,
+            None => {
+                self.steps.push(name);
+                self.steps.len() - 1
+            }
+        }
+    }
+
+    /// 标记 `positions` 处的记录被步骤 `step` 修改
+    pub fn tag(&mut self, step: usize, positions: impl IntoIterator<Item = usize>) {
+        for position in positions {
+            if let Some(record) = self.records.get_mut(position) {
+                if record.steps.last() != Some(&step) {
+                    record.steps.push(step);
+                }
+            }
+        }
+    }
+
+    /// 按位置选取记录的血缘（用于数据被筛选、重排之后）
+    pub fn select(&self, positions: &[usize]) -> Result<Lineage> {
+        let records = positions
+            .iter()
+            .map(|&position| {
+                self.records
+                    .get(position)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("血缘位置{}超出记录数{}", position, self.len()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Lineage {
+            sources: self.sources.clone(),
+            steps: self.steps.clone(),
+            records,
+        })
+    }
+
+    /// 检查血缘与数据记录数一致
+    pub fn check_len(&self, count: usize) -> Result<()> {
+        if self.len() != count {
+            return Err(anyhow::anyhow!(
+                "血缘记录数{}与数据记录数{}不一致",
+                self.len(),
+                count
+            ));
+        }
+        Ok(())
+    }
+
+    /// 转为按（股票代码, 市场, 时间）索引的形式，便于与重新排序后的数据对齐
+    pub fn keyed<B: Bar>(&self, data: &[B]) -> Result<KeyedLineage> {
+        self.check_len(data.len())?;
+        let mut records: Vec<KeyedRecord> = data
+            .iter()
+            .zip(&self.records)
+            .map(|(bar, lineage)| KeyedRecord {
+                symbol: bar.symbol().to_string(),
+                market: bar.market().to_string(),
+                timestamp: bar.timestamp(),
+                source: lineage.source,
+                steps: lineage.steps.clone(),
+            })
+            .collect();
+        // 稳定排序，键相同的记录保持原有先后顺序
+        records.sort_by(|a, b| {
+            (&a.symbol, &a.market, a.timestamp).cmp(&(&b.symbol, &b.market, b.timestamp))
+        });
+        Ok(KeyedLineage {
+            sources: self.sources.clone(),
+            steps: self.steps.clone(),
+            records,
+        })
+    }
+}
+
+/// 按键索引的单条记录血缘
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyedRecord {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 时间
+    pub timestamp: NaiveDateTime,
+    /// 数据源位置
+    pub source: usize,
+    /// 步骤位置
+    pub steps: Vec<usize>,
+}
+
+/// 按键索引的血缘，快照旁路文件的内容
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyedLineage {
+    /// 数据源
+    pub sources: Vec<LineageSource>,
+    /// 处理步骤
+    pub steps: Vec<String>,
+    /// 记录血缘（按键排序）
+    pub records: Vec<KeyedRecord>,
+}
+
+impl KeyedLineage {
+    /// 与数据按位置重新对齐，键相同的记录按先后顺序对应
+    pub fn align<B: Bar>(self, data: &[B]) -> Result<Lineage> {
+        let mut by_key: HashMap<(String, String, NaiveDateTime), VecDeque<RecordLineage>> =
+            HashMap::new();
+        for record in self.records {
+            by_key
+                .entry((record.symbol, record.market, record.timestamp))
+                .or_default()
+                .push_back(RecordLineage {
+                    source: record.source,
+                    steps: record.steps,
+                });
+        }
+
+        let records = data
+            .iter()
+            .map(|bar| {
+                let key = (
+                    bar.symbol().to_string(),
+                    bar.market().to_string(),
+                    bar.timestamp(),
+                );
+                by_key
+                    .get_mut(&key)
+                    .and_then(VecDeque::pop_front)
+                    .ok_or_else(|| anyhow::anyhow!("缺少记录的血缘: {} {} {}", key.0, key.1, key.2))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let lineage = Lineage {
+            sources: self.sources,
+            steps: self.steps,
+            records,
+        };
+        let valid = lineage.records.iter().all(|record| {
+            record.source < lineage.sources.len()
+                && record.steps.iter().all(|&s| s < lineage.steps.len())
+        });
+        if !valid {
+            return Err(anyhow::anyhow!("血缘引用了不存在的数据源或步骤"));
+        }
+        Ok(lineage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use chrono::NaiveDate;
+
+    fn create_test_record(symbol: &str, day: i64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(day),
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            volume: 1000,
+            amount: 10000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_extend_tag_and_trace() {
+        let mut lineage = Lineage::from_source(LineageSource::new("sh600000.day"), 2);
+        let fill = lineage.add_step("FillMissing(close)");
+        lineage.tag(fill, [1]);
+
+        let mut other = Lineage::from_source(LineageSource::new("http://example.com/a"), 1);
+        let step = other.add_step("FillMissing(close)");
+        other.tag(step, [0]);
+        lineage.extend(other);
+
+        assert_eq!(lineage.len(), 3);
+        assert_eq!(lineage.steps().len(), 1);
+        let trace = lineage.trace(2).unwrap();
+        assert_eq!(trace.source.location, "http://example.com/a");
+        assert_eq!(trace.source.pipeline_version, crate::VERSION);
+        assert_eq!(trace.steps, vec!["FillMissing(close)"]);
+        assert!(lineage.trace(0).unwrap().steps.is_empty());
+
+        let selected = lineage.select(&[2, 0]).unwrap();
+        assert_eq!(selected.records()[0].source, 1);
+        assert!(lineage.select(&[3]).is_err());
+    }
+
+    #[test]
+    fn test_keyed_round_trip() {
+        let data = vec![
+            create_test_record("600036", 1),
+            create_test_record("600000", 0),
+        ];
+        let mut lineage = Lineage::from_source(LineageSource::new("a.day"), 1);
+        lineage.extend(Lineage::from_source(LineageSource::new("b.day"), 1));
+
+        let keyed = lineage.keyed(&data).unwrap();
+        assert_eq!(keyed.records[0].symbol, "600000");
+        let json = serde_json::to_string(&keyed).unwrap();
+
+        let mut reordered = data.clone();
+        reordered.reverse();
+        let aligned: KeyedLineage = serde_json::from_str(&json).unwrap();
+        let aligned = aligned.align(&reordered).unwrap();
+        assert_eq!(aligned.trace(0).unwrap().source.location, "b.day");
+        assert_eq!(aligned.trace(1).unwrap().source.location, "a.day");
+
+        let missing: KeyedLineage = serde_json::from_str(&json).unwrap();
+        assert!(missing.align(&[create_test_record("000001", 0)]).is_err());
+        assert!(lineage.keyed(&data[..1]).is_err());
+    }
+}
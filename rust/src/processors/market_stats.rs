@@ -0,0 +1,356 @@
+//! 全市场横截面统计模块
+//!
+//! 将按股票存储的日线数据转换为按交易日的视图，计算每日市场宽度指标：
+//! 涨跌家数、涨跌停家数、成交额、涨跌幅中位数、52周新高/新低家数等。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::limits::{LimitDetector, LimitKind};
+use anyhow::Result;
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// 每日市场统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyMarketStats {
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 当日有交易的股票数
+    pub total_symbols: usize,
+    /// 上涨家数
+    pub advancers: usize,
+    /// 下跌家数
+    pub decliners: usize,
+    /// 平盘家数
+    pub unchanged: usize,
+    /// 涨停家数
+    pub limit_up: usize,
+    /// 跌停家数
+    pub limit_down: usize,
+    /// 总成交额（元）
+    pub total_amount: f64,
+    /// 总成交量（股）
+    pub total_volume: u64,
+    /// 涨跌幅中位数（%）
+    pub median_change_percent: Option<f64>,
+    /// 创52周新高家数
+    pub new_highs: usize,
+    /// 创52周新低家数
+    pub new_lows: usize,
+}
+
+impl DailyMarketStats {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            total_symbols: 0,
+            advancers: 0,
+            decliners: 0,
+            unchanged: 0,
+            limit_up: 0,
+            limit_down: 0,
+            total_amount: 0.0,
+            total_volume: 0,
+            median_change_percent: None,
+            new_highs: 0,
+            new_lows: 0,
+        }
+    }
+
+    /// 涨跌比（上涨家数/下跌家数）
+    pub fn advance_decline_ratio(&self) -> Option<f64> {
+        if self.decliners == 0 {
+            None
+        } else {
+            Some(self.advancers as f64 / self.decliners as f64)
+        }
+    }
+}
+
+/// 单只股票单日的横截面特征
+#[derive(Debug, Clone)]
+struct BarSnapshot {
+    date: NaiveDate,
+    change_percent: Option<f64>,
+    amount: f64,
+    volume: u64,
+    limit_up: bool,
+    limit_down: bool,
+    new_high: bool,
+    new_low: bool,
+}
+
+/// 市场统计计算器
+#[derive(Debug, Clone)]
+pub struct MarketStatsCalculator {
+    /// 涨跌停检测器（按板块和ST状态判定）
+    limit_detector: LimitDetector,
+    /// 新高/新低回看窗口（交易日数，52周约250个交易日）
+    high_low_window: usize,
+}
+
+impl Default for MarketStatsCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketStatsCalculator {
+    /// 创建新的市场统计计算器
+    pub fn new() -> Self {
+        Self {
+            limit_detector: LimitDetector::new(),
+            high_low_window: 250,
+        }
+    }
+
+    /// 设置涨跌停检测器
+    pub fn with_limit_detector(mut self, limit_detector: LimitDetector) -> Self {
+        self.limit_detector = limit_detector;
+        self
+    }
+
+    /// 设置新高/新低回看窗口
+    pub fn with_high_low_window(mut self, window: usize) -> Self {
+        self.high_low_window = window.max(1);
+        self
+    }
+
+    /// 计算每日市场统计（按日期升序）
+    pub fn calculate(&self, data: &[TDXDayRecord]) -> Result<Vec<DailyMarketStats>> {
+        // 按股票分组
+        let mut groups: HashMap<(&str, &str), Vec<&TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        // 并行计算每只股票的逐日特征
+        let snapshots: Vec<Vec<BarSnapshot>> = groups
+            .into_par_iter()
+            .map(|(_, mut records)| {
+                records.sort_by_key(|r| r.date);
+                self.symbol_snapshots(&records)
+            })
+            .collect();
+
+        // 按日期透视
+        let mut by_date: BTreeMap<NaiveDate, (DailyMarketStats, Vec<f64>)> = BTreeMap::new();
+        for snapshot in snapshots.into_iter().flatten() {
+            let (stats, changes) = by_date
+                .entry(snapshot.date)
+                .or_insert_with(|| (DailyMarketStats::new(snapshot.date), Vec::new()));
+
+            stats.total_symbols += 1;
+            stats.total_amount += snapshot.amount;
+            stats.total_volume += snapshot.volume;
+
+            if let Some(change) = snapshot.change_percent {
+                if change > 0.0 {
+                    stats.advancers += 1;
+                } else if change < 0.0 {
+                    stats.decliners += 1;
+                } else {
+                    stats.unchanged += 1;
+                }
+                changes.push(change);
+            }
+
+            stats.limit_up += snapshot.limit_up as usize;
+            stats.limit_down += snapshot.limit_down as usize;
+            stats.new_highs += snapshot.new_high as usize;
+            stats.new_lows += snapshot.new_low as usize;
+        }
+
+        Ok(by_date
+            .into_values()
+            .map(|(mut stats, mut changes)| {
+                stats.median_change_percent = Self::median(&mut changes);
+                stats
+            })
+            .collect())
+    }
+
+    /// 计算单只股票的逐日特征（记录需已按日期排序）
+    fn symbol_snapshots(&self, records: &[&TDXDayRecord]) -> Vec<BarSnapshot> {
+        let mut snapshots = Vec::with_capacity(records.len());
+        // 单调队列维护回看窗口内的最高价/最低价索引
+        let mut max_window: VecDeque<usize> = VecDeque::new();
+        let mut min_window: VecDeque<usize> = VecDeque::new();
+
+        for (i, record) in records.iter().enumerate() {
+            let change_percent = if i > 0 && records[i - 1].close > 0.0 {
+                Some((record.close - records[i - 1].close) / records[i - 1].close * 100.0)
+            } else {
+                None
+            };
+            // 首根K线为上市首日时以发行价为参考价，与 `LimitDetector::detect` 一致
+            let prev_close = if i > 0 {
+                Some(records[i - 1].close)
+            } else {
+                self.limit_detector.issue_price(record)
+            };
+            let limit_kind = prev_close.and_then(|prev_close| {
+                self.limit_detector
+                    .classify_bar(record, prev_close)
+                    .map(|(kind, _, _)| kind)
+            });
+
+            // 仅当历史数据足够一个完整窗口时才判断新高/新低
+            let window_full = i >= self.high_low_window;
+            let new_high = window_full
+                && max_window
+                    .front()
+                    .is_some_and(|&j| record.high > records[j].high);
+            let new_low = window_full
+                && min_window
+                    .front()
+                    .is_some_and(|&j| record.low < records[j].low);
+
+            snapshots.push(BarSnapshot {
+                date: record.date,
+                change_percent,
+                amount: record.amount,
+                volume: record.volume,
+                limit_up: limit_kind == Some(LimitKind::LimitUp),
+                limit_down: limit_kind == Some(LimitKind::LimitDown),
+                new_high,
+                new_low,
+            });
+
+            // 将当前bar纳入窗口，并移出过期索引
+            while max_window
+                .back()
+                .is_some_and(|&j| records[j].high <= record.high)
+            {
+                max_window.pop_back();
+            }
+            max_window.push_back(i);
+            while min_window
+                .back()
+                .is_some_and(|&j| records[j].low >= record.low)
+            {
+                min_window.pop_back();
+            }
+            min_window.push_back(i);
+
+            let oldest = (i + 1).saturating_sub(self.high_low_window);
+            while max_window.front().is_some_and(|&j| j < oldest) {
+                max_window.pop_front();
+            }
+            while min_window.front().is_some_and(|&j| j < oldest) {
+                min_window.pop_front();
+            }
+        }
+
+        snapshots
+    }
+
+    /// 计算中位数
+    fn median(values: &mut [f64]) -> Option<f64> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        let mid = values.len() / 2;
+        Some(if values.len().is_multiple_of(2) {
+            (values[mid - 1] + values[mid]) / 2.0
+        } else {
+            values[mid]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_breadth_counts() {
+        let data = vec![
+            create_test_record("600000", 2, 10.0),
+            create_test_record("600000", 3, 11.0),
+            create_test_record("600036", 2, 10.0),
+            create_test_record("600036", 3, 9.5),
+            create_test_record("601318", 2, 10.0),
+            create_test_record("601318", 3, 10.0),
+        ];
+
+        let stats = MarketStatsCalculator::new().calculate(&data).unwrap();
+        assert_eq!(stats.len(), 2);
+
+        let day = &stats[1];
+        assert_eq!(day.total_symbols, 3);
+        assert_eq!(day.advancers, 1);
+        assert_eq!(day.decliners, 1);
+        assert_eq!(day.unchanged, 1);
+        assert_eq!(day.limit_up, 1);
+        assert_eq!(day.limit_down, 0);
+        assert_eq!(day.median_change_percent, Some(0.0));
+        assert_eq!(day.total_amount, 11000.0 + 9500.0 + 10000.0);
+    }
+
+    #[test]
+    fn test_ipo_first_day_limit_up() {
+        let key = ("603000".to_string(), "SH".to_string());
+        let listing = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let detector = LimitDetector::new()
+            .with_listing_dates([(key.clone(), listing)].into_iter().collect())
+            .with_issue_prices([(key, 10.0)].into_iter().collect());
+        // 发行价10.00，首日按30%限制收于13.00
+        let data = vec![create_test_record("603000", 2, 13.0)];
+
+        let stats = MarketStatsCalculator::new()
+            .with_limit_detector(detector.clone())
+            .calculate(&data)
+            .unwrap();
+        assert_eq!(stats[0].limit_up, 1);
+        assert_eq!(detector.detect(&data).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_new_highs_and_lows() {
+        let data = vec![
+            create_test_record("600000", 2, 10.0),
+            create_test_record("600000", 3, 10.5),
+            create_test_record("600000", 4, 11.0),
+            create_test_record("600000", 5, 9.0),
+        ];
+
+        let stats = MarketStatsCalculator::new()
+            .with_high_low_window(2)
+            .calculate(&data)
+            .unwrap();
+
+        // 前两天历史不足，不统计新高新低
+        assert_eq!(stats[0].new_highs + stats[1].new_highs, 0);
+        assert_eq!(stats[2].new_highs, 1);
+        assert_eq!(stats[3].new_lows, 1);
+    }
+
+    #[test]
+    fn test_advance_decline_ratio() {
+        let mut stats = DailyMarketStats::new(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert_eq!(stats.advance_decline_ratio(), None);
+        stats.advancers = 30;
+        stats.decliners = 10;
+        assert_eq!(stats.advance_decline_ratio(), Some(3.0));
+    }
+}
@@ -0,0 +1,279 @@
+//! 内存估算与限额模块
+//!
+//! `SizeOf` 给出数据的近似内存占用（栈上大小加堆分配），
+//! `MemoryTracker` 按估算值预留内存：超出限额时等待已有预留释放，并记录峰值用量。
+
+use crate::parsers::{CompactDayRecord, TDXDayRecord};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// 近似内存占用估算
+pub trait SizeOf {
+    /// 估算占用字节数（包含自身大小与其拥有的堆内存）
+    fn size_of(&self) -> usize;
+}
+
+macro_rules! impl_size_of_plain {
+    ($($ty:ty),*) => {
+        $(impl SizeOf for $ty {
+            fn size_of(&self) -> usize {
+                std::mem::size_of::<$ty>()
+            }
+        })*
+    };
+}
+
+impl_size_of_plain!(
+    bool,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    char,
+    NaiveDate,
+    CompactDayRecord
+);
+
+impl SizeOf for String {
+    fn size_of(&self) -> usize {
+        std::mem::size_of::<String>() + self.capacity()
+    }
+}
+
+impl<T: SizeOf> SizeOf for Vec<T> {
+    fn size_of(&self) -> usize {
+        std::mem::size_of::<Vec<T>>()
+            + self.iter().map(SizeOf::size_of).sum::<usize>()
+            + (self.capacity() - self.len()) * std::mem::size_of::<T>()
+    }
+}
+
+impl<T: SizeOf> SizeOf for Option<T> {
+    fn size_of(&self) -> usize {
+        match self {
+            Some(value) => {
+                std::mem::size_of::<Option<T>>() - std::mem::size_of::<T>() + value.size_of()
+            }
+            None => std::mem::size_of::<Option<T>>(),
+        }
+    }
+}
+
+impl<A: SizeOf, B: SizeOf> SizeOf for (A, B) {
+    fn size_of(&self) -> usize {
+        self.0.size_of() + self.1.size_of()
+    }
+}
+
+impl SizeOf for TDXDayRecord {
+    fn size_of(&self) -> usize {
+        std::mem::size_of::<TDXDayRecord>() + self.symbol.capacity() + self.market.capacity()
+    }
+}
+
+/// 内存用量统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// 内存限额（字节）
+    pub limit: usize,
+    /// 当前预留（字节）
+    pub current: usize,
+    /// 峰值预留（字节）
+    pub peak: usize,
+}
+
+/// 内存限额跟踪器
+#[derive(Debug)]
+pub struct MemoryTracker {
+    /// 限额（字节）
+    limit: usize,
+    /// 当前预留
+    current: AtomicUsize,
+    /// 峰值预留
+    peak: AtomicUsize,
+    /// 预留释放通知
+    released: Notify,
+}
+
+impl MemoryTracker {
+    /// 创建新的内存跟踪器
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            current: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            released: Notify::new(),
+        }
+    }
+
+    /// 尝试预留内存，超出限额时返回None
+    ///
+    /// 没有其他预留时总是允许，避免单个超大批次永远无法执行。
+    pub fn try_reserve(self: &Arc<Self>, bytes: usize) -> Option<MemoryReservation> {
+        let mut current = self.current.load(Ordering::Acquire);
+        loop {
+            if current != 0 && current + bytes > self.limit {
+                return None;
+            }
+            match self.current.compare_exchange_weak(
+                current,
+                current + bytes,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => current = actual,
+            }
+        }
+
+        self.peak.fetch_max(current + bytes, Ordering::AcqRel);
+        Some(MemoryReservation {
+            tracker: Arc::clone(self),
+            bytes,
+        })
+    }
+
+    /// 预留内存，超出限额时等待其他预留释放
+    pub async fn reserve(self: &Arc<Self>, bytes: usize) -> MemoryReservation {
+        loop {
+            // 先注册通知再检查，避免错过释放
+            let notified = self.released.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some(reservation) = self.try_reserve(bytes) {
+                return reservation;
+            }
+            log::debug!(
+                "内存预留等待: 需要 {} 字节，当前 {} / {} 字节",
+                bytes,
+                self.current(),
+                self.limit
+            );
+            notified.await;
+        }
+    }
+
+    /// 当前预留字节数
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Acquire)
+    }
+
+    /// 峰值预留字节数
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Acquire)
+    }
+
+    /// 内存用量统计
+    pub fn stats(&self) -> MemoryStats {
+        MemoryStats {
+            limit: self.limit,
+            current: self.current(),
+            peak: self.peak(),
+        }
+    }
+
+    fn release(&self, bytes: usize) {
+        self.current.fetch_sub(bytes, Ordering::AcqRel);
+        self.released.notify_waiters();
+    }
+}
+
+/// 内存预留凭证，析构时释放
+#[derive(Debug)]
+pub struct MemoryReservation {
+    tracker: Arc<MemoryTracker>,
+    bytes: usize,
+}
+
+impl MemoryReservation {
+    /// 预留字节数
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.tracker.release(self.bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_of_estimates() {
+        let record = TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            symbol: "600000".to_string(),
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            volume: 1000,
+            amount: 10000.0,
+            market: "SH".to_string(),
+        };
+        assert_eq!(
+            record.size_of(),
+            std::mem::size_of::<TDXDayRecord>() + 6 + 2
+        );
+
+        let records = vec![record.clone(), record];
+        assert!(records.size_of() >= 2 * (std::mem::size_of::<TDXDayRecord>() + 8));
+        assert_eq!(7u64.size_of(), 8);
+    }
+
+    #[test]
+    fn test_try_reserve_respects_limit() {
+        let tracker = Arc::new(MemoryTracker::new(100));
+
+        let first = tracker.try_reserve(60).unwrap();
+        assert!(tracker.try_reserve(50).is_none());
+        let second = tracker.try_reserve(40).unwrap();
+        assert_eq!(tracker.current(), 100);
+
+        drop(first);
+        drop(second);
+        assert_eq!(tracker.current(), 0);
+        assert_eq!(tracker.peak(), 100);
+
+        // 空闲时允许单个超限预留
+        let oversized = tracker.try_reserve(500).unwrap();
+        assert_eq!(oversized.bytes(), 500);
+        assert_eq!(tracker.stats().peak, 500);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_waits_for_release() {
+        let tracker = Arc::new(MemoryTracker::new(100));
+        let held = tracker.reserve(80).await;
+
+        let waiter = {
+            let tracker = Arc::clone(&tracker);
+            tokio::spawn(async move { tracker.reserve(50).await.bytes() })
+        };
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(held);
+        assert_eq!(waiter.await.unwrap(), 50);
+        assert_eq!(tracker.current(), 0);
+    }
+}
@@ -0,0 +1,350 @@
+//! 多数据源合并模块
+//!
+//! 本地文件、ClickHouse和网络数据源的记录可能重叠，且成交额等字段存在细微差异。
+//! 按（股票代码, 市场, 日期）合并多个来源，在容差内一致的重复记录直接去重，
+//! 超出容差的按冲突策略处理并记录冲突明细。
+
+use crate::parsers::TDXDayRecord;
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveDateTime};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 冲突处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    /// 优先本地数据源（无本地记录时取第一个来源）
+    PreferLocal,
+    /// 优先更新时间最新的数据源
+    PreferNewest,
+    /// 取各来源的平均值
+    Average,
+    /// 出现冲突时报错
+    Error,
+}
+
+/// 数据来源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeSource {
+    /// 来源名称
+    pub name: String,
+    /// 是否为本地数据源
+    pub local: bool,
+    /// 数据更新时间
+    pub updated_at: Option<NaiveDateTime>,
+    /// 记录
+    pub records: Vec<TDXDayRecord>,
+}
+
+impl MergeSource {
+    /// 创建本地数据源
+    pub fn local(name: &str, records: Vec<TDXDayRecord>) -> Self {
+        Self {
+            name: name.to_string(),
+            local: true,
+            updated_at: None,
+            records,
+        }
+    }
+
+    /// 创建远程数据源
+    pub fn remote(name: &str, records: Vec<TDXDayRecord>) -> Self {
+        Self {
+            name: name.to_string(),
+            local: false,
+            updated_at: None,
+            records,
+        }
+    }
+
+    /// 设置数据更新时间
+    pub fn with_updated_at(mut self, updated_at: NaiveDateTime) -> Self {
+        self.updated_at = Some(updated_at);
+        self
+    }
+}
+
+/// 合并冲突
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflict {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 各来源的记录（来源名称, 记录）
+    pub candidates: Vec<(String, TDXDayRecord)>,
+    /// 最终采用的记录
+    pub resolved: TDXDayRecord,
+}
+
+/// 合并结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeResult {
+    /// 合并后的记录，按（股票代码, 市场, 日期）排序
+    pub records: Vec<TDXDayRecord>,
+    /// 冲突明细
+    pub conflicts: Vec<MergeConflict>,
+    /// 去除的重复记录数（含冲突）
+    pub duplicates: usize,
+}
+
+/// 数据源合并器
+#[derive(Debug, Clone)]
+pub struct RecordMerger {
+    /// 冲突策略
+    policy: ConflictPolicy,
+    /// 价格容差（元）
+    price_tolerance: f64,
+    /// 成交额相对容差
+    amount_tolerance: f64,
+}
+
+impl Default for RecordMerger {
+    fn default() -> Self {
+        Self::new(ConflictPolicy::PreferLocal)
+    }
+}
+
+impl RecordMerger {
+    /// 创建新的合并器
+    pub fn new(policy: ConflictPolicy) -> Self {
+        Self {
+            policy,
+            price_tolerance: 0.001,
+            amount_tolerance: 0.001,
+        }
+    }
+
+    /// 设置价格容差（元）
+    pub fn with_price_tolerance(mut self, tolerance: f64) -> Self {
+        self.price_tolerance = tolerance;
+        self
+    }
+
+    /// 设置成交额相对容差
+    pub fn with_amount_tolerance(mut self, tolerance: f64) -> Self {
+        self.amount_tolerance = tolerance;
+        self
+    }
+
+    /// 合并多个数据源
+    pub fn merge(&self, sources: &[MergeSource]) -> Result<MergeResult> {
+        type Key<'a> = (&'a str, &'a str, NaiveDate);
+        let mut grouped: BTreeMap<Key, Vec<(usize, &TDXDayRecord)>> = BTreeMap::new();
+        for (index, source) in sources.iter().enumerate() {
+            for record in &source.records {
+                grouped
+                    .entry((&record.symbol, &record.market, record.date))
+                    .or_default()
+                    .push((index, record));
+            }
+        }
+
+        let mut records = Vec::with_capacity(grouped.len());
+        let mut conflicts = Vec::new();
+        let mut duplicates = 0;
+
+        for ((symbol, market, date), candidates) in grouped {
+            duplicates += candidates.len() - 1;
+            let (_, first) = candidates[0];
+            if candidates.iter().all(|(_, r)| self.consistent(first, r)) {
+                records.push(self.pick_preferred(sources, &candidates).clone());
+                continue;
+            }
+
+            let resolved = match self.policy {
+                ConflictPolicy::PreferLocal | ConflictPolicy::PreferNewest => {
+                    self.pick_preferred(sources, &candidates).clone()
+                }
+                ConflictPolicy::Average => average(&candidates),
+                ConflictPolicy::Error => {
+                    let names: Vec<&str> = candidates
+                        .iter()
+                        .map(|(index, _)| sources[*index].name.as_str())
+                        .collect();
+                    return Err(anyhow::anyhow!(
+                        "数据源冲突: {}.{} {} ({})",
+                        symbol,
+                        market,
+                        date,
+                        names.join(", ")
+                    ));
+                }
+            };
+
+            conflicts.push(MergeConflict {
+                symbol: symbol.to_string(),
+                market: market.to_string(),
+                date,
+                candidates: candidates
+                    .iter()
+                    .map(|(index, r)| (sources[*index].name.clone(), (*r).clone()))
+                    .collect(),
+                resolved: resolved.clone(),
+            });
+            records.push(resolved);
+        }
+
+        if !conflicts.is_empty() {
+            warn!("合并数据源时发现{}处冲突", conflicts.len());
+        }
+        info!(
+            "合并{}个数据源: 输出{}条, 去重{}条",
+            sources.len(),
+            records.len(),
+            duplicates
+        );
+
+        Ok(MergeResult {
+            records,
+            conflicts,
+            duplicates,
+        })
+    }
+
+    /// 两条记录是否在容差内一致
+    fn consistent(&self, a: &TDXDayRecord, b: &TDXDayRecord) -> bool {
+        let price_ok = [
+            (a.open, b.open),
+            (a.high, b.high),
+            (a.low, b.low),
+            (a.close, b.close),
+        ]
+        .iter()
+        .all(|(x, y)| (x - y).abs() <= self.price_tolerance);
+        let scale = a.amount.abs().max(b.amount.abs()).max(1.0);
+        price_ok
+            && a.volume == b.volume
+            && (a.amount - b.amount).abs() / scale <= self.amount_tolerance
+    }
+
+    /// 按策略选择优先来源的记录
+    fn pick_preferred<'a>(
+        &self,
+        sources: &[MergeSource],
+        candidates: &[(usize, &'a TDXDayRecord)],
+    ) -> &'a TDXDayRecord {
+        let chosen = match self.policy {
+            ConflictPolicy::PreferNewest => candidates
+                .iter()
+                // 时间相同时取后出现的来源
+                .max_by_key(|(index, _)| (sources[*index].updated_at, *index)),
+            _ => candidates
+                .iter()
+                .find(|(index, _)| sources[*index].local)
+                .or(candidates.first()),
+        };
+        chosen.map(|(_, record)| *record).unwrap_or(candidates[0].1)
+    }
+}
+
+/// 各来源记录的平均值
+fn average(candidates: &[(usize, &TDXDayRecord)]) -> TDXDayRecord {
+    let n = candidates.len() as f64;
+    let mean = |field: fn(&TDXDayRecord) -> f64| -> f64 {
+        candidates.iter().map(|(_, r)| field(r)).sum::<f64>() / n
+    };
+
+    let mut record = candidates[0].1.clone();
+    record.open = mean(|r| r.open);
+    record.high = mean(|r| r.high);
+    record.low = mean(|r| r.low);
+    record.close = mean(|r| r.close);
+    record.amount = mean(|r| r.amount);
+    record.volume = mean(|r| r.volume as f64).round() as u64;
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, day: u32, close: f64, amount: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn sources() -> Vec<MergeSource> {
+        vec![
+            MergeSource::remote(
+                "clickhouse",
+                vec![
+                    create_test_record("600000", 2, 10.0, 10_000.0),
+                    create_test_record("600000", 3, 10.2, 10_200.0),
+                ],
+            )
+            .with_updated_at(
+                NaiveDate::from_ymd_opt(2024, 1, 4)
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap(),
+            ),
+            MergeSource::local(
+                "vipdoc",
+                vec![
+                    // 成交额在容差内，视为重复
+                    create_test_record("600000", 2, 10.0, 10_000.5),
+                    // 价格不一致，视为冲突
+                    create_test_record("600000", 3, 10.4, 10_400.0),
+                    create_test_record("600000", 4, 10.5, 10_500.0),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_prefer_local() {
+        let result = RecordMerger::new(ConflictPolicy::PreferLocal)
+            .merge(&sources())
+            .unwrap();
+        assert_eq!(result.records.len(), 3);
+        assert_eq!(result.duplicates, 2);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.records[0].amount, 10_000.5);
+        assert_eq!(result.records[1].close, 10.4);
+        assert_eq!(result.conflicts[0].candidates[0].0, "clickhouse");
+    }
+
+    #[test]
+    fn test_prefer_newest_and_average() {
+        let newest = RecordMerger::new(ConflictPolicy::PreferNewest)
+            .merge(&sources())
+            .unwrap();
+        // 只有clickhouse带更新时间
+        assert_eq!(newest.records[1].close, 10.2);
+
+        let averaged = RecordMerger::new(ConflictPolicy::Average)
+            .merge(&sources())
+            .unwrap();
+        assert!((averaged.records[1].close - 10.3).abs() < 1e-9);
+        assert_eq!(averaged.conflicts[0].resolved.amount, 10_300.0);
+    }
+
+    #[test]
+    fn test_error_policy() {
+        let error = RecordMerger::new(ConflictPolicy::Error)
+            .merge(&sources())
+            .unwrap_err();
+        assert!(error.to_string().contains("600000"));
+
+        // 放宽容差后不再冲突
+        let result = RecordMerger::new(ConflictPolicy::Error)
+            .with_price_tolerance(0.5)
+            .with_amount_tolerance(0.05)
+            .merge(&sources())
+            .unwrap();
+        assert!(result.conflicts.is_empty());
+    }
+}
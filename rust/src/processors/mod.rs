@@ -1,20 +1,94 @@
 //! 数据处理模块
 
 pub mod aggregator;
+pub mod align;
+pub mod anomaly;
+pub mod bars;
+pub mod benchmark;
 pub mod calculator;
 pub mod cleaner;
+pub mod columnar;
+pub mod correlation;
+pub mod cross_section;
+pub mod crossovers;
+pub mod diff;
+pub mod expr;
+pub mod extremes;
+pub mod factors;
+pub mod field;
+pub mod gaps;
+pub mod hedge;
+pub mod kernels;
+pub mod lazy;
+pub mod levels;
+pub mod limits;
+pub mod lineage;
+pub mod market_stats;
+pub mod memory;
+pub mod merge;
+pub mod performance;
+pub mod price_gaps;
+pub mod rolling;
+pub mod seasonality;
+pub mod session;
+pub mod signals;
+pub mod suspension;
 pub mod transformer;
+pub mod vwap;
+pub mod zigzag;
 
-pub use aggregator::{AggregationRule, DataAggregator};
+pub use aggregator::{
+    AggregatedValue, AggregationFunction, AggregationRule, DataAggregator, GroupKey,
+};
+pub use align::{align_by_date, AlignedFrame, AlignedRow, MissingPolicy};
+pub use anomaly::{AnomalyField, AnomalyMethod, VolumeAnomaly, VolumeAnomalyDetector};
+pub use bars::{BarBuilder, Timeframe};
+pub use benchmark::Benchmark;
 pub use calculator::{IndicatorCalculator, TechnicalIndicator};
-pub use cleaner::{CleaningResult, CleaningRule, DataCleaner};
+pub use cleaner::{
+    CleaningResult, CleaningRule, DataCleaner, DryRun, KeepPolicy, OutlierAction, OutlierFlag,
+    RuleImpact,
+};
+pub use columnar::{ColumnRef, ColumnarFrame};
+pub use correlation::{CorrelationCalculator, CorrelationResult, LabeledMatrix};
+pub use cross_section::CrossSectionOp;
+pub use crossovers::{
+    CrossDetector, CrossDirection, CrossoverDetector, CrossoverEvent, CrossoverKind, MovingAverage,
+};
+pub use diff::{BarChange, DatasetDiff, DatasetDiffer, FieldChange, SymbolDiff};
+pub use expr::RecordExpr;
+pub use factors::{
+    Factor, FactorCalculator, FactorTable, FactorValue, Momentum, Size, Turnover, Volatility,
+};
+pub use field::Field;
+pub use gaps::{FilledRecord, GapFiller, SymbolGap};
+pub use hedge::{BetaHedger, HedgedReturn};
+pub use lazy::{AggOp, Expr, GroupRow, LazyFrame, LazyGroupBy, LazyResult};
+pub use levels::{LevelExtractor, LevelKind, PriceLevel};
+pub use limits::{Board, LimitDetector, LimitEvent, LimitKind, LimitRules};
+pub use lineage::{KeyedLineage, Lineage, LineageSource, RecordLineage, RecordTrace};
+pub use market_stats::{DailyMarketStats, MarketStatsCalculator};
+pub use memory::{MemoryReservation, MemoryStats, MemoryTracker, SizeOf};
+pub use merge::{ConflictPolicy, MergeConflict, MergeResult, MergeSource, RecordMerger};
+pub use performance::{Drawdown, PerformanceAnalyzer, PerformanceMetrics};
+pub use price_gaps::{GapDirection, GapFill, GapSummary, PriceGap, PriceGapDetector};
+pub use rolling::{RollingEngine, RollingWindow, WindowHandle};
+pub use seasonality::{SeasonalBucket, SeasonalityAnalyzer, SeasonalityRow, SeasonalityTable};
+pub use session::{SessionStats, SessionStatsCalculator};
+pub use signals::{Condition, Operand, Signal, SignalGenerator, SignalKind, SignalRule};
+pub use suspension::{SuspensionDetector, SuspensionIndex, SuspensionPeriod};
 pub use transformer::DataTransformer;
+pub use zigzag::{Pivot, SwingKind, SwingPoint, ZigZag};
 
 use anyhow::Result;
+use futures::stream::{self, Stream, StreamExt};
 use rayon::prelude::*;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
 
+/// 并行处理时每个工作线程分配的块数
+const PARALLEL_CHUNKS_PER_WORKER: usize = 4;
+
 /// 高性能数据处理器
 #[derive(Debug)]
 pub struct DataProcessor {
@@ -24,55 +98,87 @@ pub struct DataProcessor {
     memory_limit: usize,
     /// 信号量控制并发
     semaphore: Arc<Semaphore>,
+    /// 并行计算线程池（线程数等于并发限制）
+    pool: Arc<rayon::ThreadPool>,
+    /// 内存限额跟踪
+    memory: Arc<MemoryTracker>,
 }
 
 impl DataProcessor {
     /// 创建新的数据处理器
     pub fn new(concurrency_limit: usize, memory_limit: usize) -> Self {
+        let concurrency_limit = concurrency_limit.max(1);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(concurrency_limit)
+            .thread_name(|i| format!("data-processor-{}", i))
+            .build()
+            .expect("创建数据处理线程池失败");
+
         Self {
             concurrency_limit,
             memory_limit,
             semaphore: Arc::new(Semaphore::new(concurrency_limit)),
+            pool: Arc::new(pool),
+            memory: Arc::new(MemoryTracker::new(memory_limit)),
         }
     }
 
+    /// 获取并发限制
+    pub fn concurrency_limit(&self) -> usize {
+        self.concurrency_limit
+    }
+
+    /// 获取内存限制（字节）
+    pub fn memory_limit(&self) -> usize {
+        self.memory_limit
+    }
+
+    /// 获取估算内存用量（当前与峰值）
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.memory.stats()
+    }
+
+    /// 单批次的内存预算（字节），使并发执行的批次合计不超过内存限制
+    fn batch_memory_budget(&self) -> usize {
+        (self.memory_limit / self.concurrency_limit).max(1)
+    }
+
     /// 并行处理数据集
+    ///
+    /// 元素按值移动给处理函数（不克隆），在容量等于并发限制的专用线程池上执行，
+    /// 不阻塞异步运行时，输出顺序与输入一致。执行前按数据估算大小预留内存。
     pub async fn process_parallel<T, R, F>(&self, data: Vec<T>, processor: F) -> Result<Vec<R>>
     where
-        T: Send + Sync + Clone + 'static,
+        T: SizeOf + Send + 'static,
         R: Send + 'static,
         F: Fn(T) -> Result<R> + Send + Sync + 'static,
     {
-        // 分块处理以控制内存使用
-        let chunk_size = (data.len() + self.concurrency_limit - 1) / self.concurrency_limit;
-        let chunks: Vec<_> = data.chunks(chunk_size).collect();
-
-        let mut results = Vec::with_capacity(data.len());
-
-        for chunk in chunks {
-            // 获取信号量许可
-            let _permit = self.semaphore.acquire().await?;
-
-            // 并行处理当前块
-            let chunk_results: Result<Vec<_>> = chunk
-                .par_iter()
-                .map(|item| {
-                    let result = processor(item.to_owned())?;
-                    Ok(result)
-                })
-                .collect();
-
-            let chunk_results = chunk_results?;
-            results.extend(chunk_results);
-
-            // 释放许可
-            drop(_permit);
+        if data.is_empty() {
+            return Ok(Vec::new());
         }
 
-        Ok(results)
+        // 最小块大小，避免过细的任务切分
+        let min_chunk = data
+            .len()
+            .div_ceil(self.concurrency_limit * PARALLEL_CHUNKS_PER_WORKER)
+            .max(1);
+        let pool = Arc::clone(&self.pool);
+        let _reservation = self.memory.reserve(data.size_of()).await;
+
+        tokio::task::spawn_blocking(move || {
+            pool.install(|| {
+                data.into_par_iter()
+                    .with_min_len(min_chunk)
+                    .map(processor)
+                    .collect::<Result<Vec<R>>>()
+            })
+        })
+        .await?
     }
 
     /// 流式处理大数据集
+    ///
+    /// 批次在达到 `batch_size` 条或单批内存预算时提交，超出内存限制时等待。
     pub async fn process_stream<T, R, F>(
         &self,
         data_stream: impl Iterator<Item = T>,
@@ -80,34 +186,108 @@ impl DataProcessor {
         processor: F,
     ) -> Result<Vec<R>>
     where
-        T: Send + Sync + Clone + 'static,
+        T: SizeOf + Send + 'static,
         R: Send + 'static,
         F: Fn(Vec<T>) -> Result<Vec<R>> + Send + Sync + 'static,
     {
+        let budget = self.batch_memory_budget();
         let mut results = Vec::new();
         let mut batch = Vec::with_capacity(batch_size);
+        let mut batch_bytes = 0;
 
         for item in data_stream {
+            batch_bytes += item.size_of();
             batch.push(item);
 
-            if batch.len() >= batch_size {
+            if batch.len() >= batch_size || batch_bytes >= budget {
                 let _permit = self.semaphore.acquire().await?;
-                let batch_results = processor(batch.clone())?;
+                let _reservation = self.memory.reserve(batch_bytes).await;
+                let batch_results = processor(std::mem::take(&mut batch))?;
                 results.extend(batch_results);
-                batch.clear();
-                drop(_permit);
+                batch_bytes = 0;
             }
         }
 
         // 处理最后一批
         if !batch.is_empty() {
             let _permit = self.semaphore.acquire().await?;
+            let _reservation = self.memory.reserve(batch_bytes).await;
             let batch_results = processor(batch)?;
             results.extend(batch_results);
         }
 
         Ok(results)
     }
+
+    /// 异步流式处理
+    ///
+    /// 从输入流中攒批（达到 `batch_size` 条或单批内存预算即提交），每批预留内存后
+    /// 在线程池上执行处理函数，同时在途的批次数不超过并发限制，超出内存限制时暂停；
+    /// 输出流按输入顺序逐条产出结果，批处理失败时产出一条错误。
+    pub fn process_async_stream<S, T, R, F>(
+        &self,
+        stream: S,
+        batch_size: usize,
+        processor: F,
+    ) -> impl Stream<Item = Result<R>> + Send + 'static
+    where
+        S: Stream<Item = T> + Send + 'static,
+        T: SizeOf + Send + 'static,
+        R: Send + 'static,
+        F: Fn(Vec<T>) -> Result<Vec<R>> + Send + Sync + 'static,
+    {
+        let processor = Arc::new(processor);
+        let pool = Arc::clone(&self.pool);
+        let memory = Arc::clone(&self.memory);
+
+        batch_by_size(stream, batch_size.max(1), self.batch_memory_budget())
+            .map(move |(batch, bytes)| {
+                let processor = Arc::clone(&processor);
+                let pool = Arc::clone(&pool);
+                let memory = Arc::clone(&memory);
+                async move {
+                    let _reservation = memory.reserve(bytes).await;
+                    tokio::task::spawn_blocking(move || pool.install(|| processor(batch))).await
+                }
+            })
+            .buffered(self.concurrency_limit)
+            .flat_map(|joined| {
+                let results: Vec<Result<R>> = match joined {
+                    Ok(Ok(batch_results)) => batch_results.into_iter().map(Ok).collect(),
+                    Ok(Err(e)) => vec![Err(e)],
+                    Err(e) => vec![Err(e.into())],
+                };
+                stream::iter(results)
+            })
+    }
+}
+
+/// 按条数和估算字节数攒批，产出（批次, 估算字节数）
+fn batch_by_size<S, T>(
+    input: S,
+    max_items: usize,
+    max_bytes: usize,
+) -> impl Stream<Item = (Vec<T>, usize)> + Send + 'static
+where
+    S: Stream<Item = T> + Send + 'static,
+    T: SizeOf + Send + 'static,
+{
+    stream::unfold(Some(Box::pin(input)), move |state| async move {
+        let mut input = state?;
+        let mut batch = Vec::new();
+        let mut bytes = 0;
+
+        while let Some(item) = input.next().await {
+            bytes += item.size_of();
+            batch.push(item);
+            if batch.len() >= max_items || bytes >= max_bytes {
+                return Some(((batch, bytes), Some(input)));
+            }
+        }
+
+        // 输入结束，产出最后一批
+        (!batch.is_empty()).then_some(((batch, bytes), None))
+    })
 }
 
 impl Default for DataProcessor {
@@ -118,3 +298,117 @@ impl Default for DataProcessor {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_process_parallel_preserves_order() {
+        let processor = DataProcessor::new(4, 1024);
+        let data: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+
+        let results = processor
+            .process_parallel(data, |item: String| Ok(item.parse::<usize>()? * 2))
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1000);
+        assert!(results.iter().enumerate().all(|(i, &v)| v == i * 2));
+    }
+
+    #[tokio::test]
+    async fn test_process_parallel_bounds_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let processor = DataProcessor::new(2, 1024);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let (r, p) = (Arc::clone(&running), Arc::clone(&peak));
+        processor
+            .process_parallel((0..64).collect(), move |item: usize| {
+                let current = r.fetch_add(1, Ordering::SeqCst) + 1;
+                p.fetch_max(current, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(1));
+                r.fetch_sub(1, Ordering::SeqCst);
+                Ok(item)
+            })
+            .await
+            .unwrap();
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_process_parallel_propagates_error() {
+        let processor = DataProcessor::new(2, 1024);
+        let result = processor
+            .process_parallel((0..10).collect(), |item: usize| {
+                if item == 7 {
+                    Err(anyhow::anyhow!("处理失败: {}", item))
+                } else {
+                    Ok(item)
+                }
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert!(processor
+            .process_parallel(Vec::<usize>::new(), Ok)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_process_async_stream() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let processor = DataProcessor::new(2, 1024);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let (r, p) = (Arc::clone(&running), Arc::clone(&peak));
+        let output = processor.process_async_stream(
+            stream::iter(0..100usize),
+            10,
+            move |batch: Vec<usize>| {
+                let current = r.fetch_add(1, Ordering::SeqCst) + 1;
+                p.fetch_max(current, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(2));
+                r.fetch_sub(1, Ordering::SeqCst);
+                Ok(batch.into_iter().map(|x| x + 1).collect())
+            },
+        );
+
+        let results: Vec<usize> = output.map(|item| item.unwrap()).collect::<Vec<_>>().await;
+        assert_eq!(results, (1..=100).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+
+        // 批处理错误作为流元素产出
+        let failing = processor.process_async_stream(
+            stream::iter(0..5usize),
+            2,
+            |_: Vec<usize>| -> Result<Vec<usize>> { Err(anyhow::anyhow!("批处理失败")) },
+        );
+        let errors: Vec<Result<usize>> = failing.collect().await;
+        assert_eq!(errors.len(), 3);
+        assert!(errors.iter().all(|e| e.is_err()));
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit_shrinks_batches() {
+        // 单批预算为 2 * size_of::<u64>()，批次被压缩为2条
+        let processor = DataProcessor::new(1, 2 * std::mem::size_of::<u64>());
+        let results = processor
+            .process_stream(0..10u64, 100, |batch: Vec<u64>| Ok(vec![batch.len()]))
+            .await
+            .unwrap();
+
+        assert_eq!(results, vec![2; 5]);
+        let stats = processor.memory_stats();
+        assert_eq!(stats.current, 0);
+        assert_eq!(stats.peak, 2 * std::mem::size_of::<u64>());
+    }
+}
@@ -0,0 +1,357 @@
+//! 绩效与风险指标模块
+//!
+//! 基于净值曲线或价格序列计算收益类和风险类指标：年化收益、年化波动率、
+//! 夏普/索提诺比率、最大回撤（含起止日期）、卡玛比率以及相对基准的滚动贝塔。
+
+use crate::parsers::TDXDayRecord;
+use anyhow::Result;
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 日期-数值序列（净值、价格或收益率）
+pub type DateSeries = Vec<(NaiveDate, f64)>;
+
+/// 最大回撤信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Drawdown {
+    /// 最大回撤幅度（正数，0.2表示回撤20%）
+    pub max_drawdown: f64,
+    /// 回撤前高点日期
+    pub peak_date: NaiveDate,
+    /// 回撤最低点日期
+    pub trough_date: NaiveDate,
+    /// 收复前高的日期（尚未收复则为None）
+    pub recovery_date: Option<NaiveDate>,
+}
+
+/// 绩效指标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceMetrics {
+    /// 起始日期
+    pub start_date: NaiveDate,
+    /// 结束日期
+    pub end_date: NaiveDate,
+    /// 收益期数
+    pub periods: usize,
+    /// 累计收益率
+    pub total_return: f64,
+    /// 年化收益率
+    pub annualized_return: f64,
+    /// 年化波动率
+    pub annualized_volatility: f64,
+    /// 夏普比率
+    pub sharpe_ratio: Option<f64>,
+    /// 索提诺比率
+    pub sortino_ratio: Option<f64>,
+    /// 最大回撤
+    pub max_drawdown: Option<Drawdown>,
+    /// 卡玛比率（年化收益/最大回撤）
+    pub calmar_ratio: Option<f64>,
+}
+
+/// 绩效分析器
+#[derive(Debug, Clone)]
+pub struct PerformanceAnalyzer {
+    /// 年化无风险利率
+    risk_free_rate: f64,
+    /// 每年期数（日频为252）
+    periods_per_year: f64,
+}
+
+impl Default for PerformanceAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PerformanceAnalyzer {
+    /// 创建新的绩效分析器（日频，无风险利率为0）
+    pub fn new() -> Self {
+        Self {
+            risk_free_rate: 0.0,
+            periods_per_year: 252.0,
+        }
+    }
+
+    /// 设置年化无风险利率
+    pub fn with_risk_free_rate(mut self, risk_free_rate: f64) -> Self {
+        self.risk_free_rate = risk_free_rate;
+        self
+    }
+
+    /// 设置每年期数
+    pub fn with_periods_per_year(mut self, periods_per_year: f64) -> Self {
+        self.periods_per_year = periods_per_year;
+        self
+    }
+
+    /// 分析净值曲线或价格序列（需按日期升序）
+    pub fn analyze(&self, equity: &[(NaiveDate, f64)]) -> Result<PerformanceMetrics> {
+        if equity.len() < 2 {
+            return Err(anyhow::anyhow!("序列长度不足，至少需要2个数据点"));
+        }
+        if equity.iter().any(|(_, value)| *value <= 0.0) {
+            return Err(anyhow::anyhow!("净值序列必须为正数"));
+        }
+
+        let returns: Vec<f64> = Self::returns(equity).into_iter().map(|(_, r)| r).collect();
+        let periods = returns.len();
+
+        let first = equity[0].1;
+        let last = equity[equity.len() - 1].1;
+        let total_return = last / first - 1.0;
+        let annualized_return = (last / first).powf(self.periods_per_year / periods as f64) - 1.0;
+
+        let mean = returns.iter().sum::<f64>() / periods as f64;
+        let volatility = Self::std_dev(&returns, mean);
+        let annualized_volatility = volatility * self.periods_per_year.sqrt();
+
+        // 超额收益（按期拆分无风险利率）
+        let rf_per_period = self.risk_free_rate / self.periods_per_year;
+        let excess_mean = mean - rf_per_period;
+        let sharpe_ratio =
+            (volatility > 0.0).then(|| excess_mean / volatility * self.periods_per_year.sqrt());
+
+        let downside_deviation = (returns
+            .iter()
+            .map(|r| (r - rf_per_period).min(0.0).powi(2))
+            .sum::<f64>()
+            / periods as f64)
+            .sqrt();
+        let sortino_ratio = (downside_deviation > 0.0)
+            .then(|| excess_mean / downside_deviation * self.periods_per_year.sqrt());
+
+        let max_drawdown = Self::max_drawdown(equity);
+        let calmar_ratio = max_drawdown
+            .as_ref()
+            .filter(|dd| dd.max_drawdown > 0.0)
+            .map(|dd| annualized_return / dd.max_drawdown);
+
+        Ok(PerformanceMetrics {
+            start_date: equity[0].0,
+            end_date: equity[equity.len() - 1].0,
+            periods,
+            total_return,
+            annualized_return,
+            annualized_volatility,
+            sharpe_ratio,
+            sortino_ratio,
+            max_drawdown,
+            calmar_ratio,
+        })
+    }
+
+    /// 按股票收盘价序列并行计算绩效指标（键为代码和市场），数据不足的股票被跳过
+    pub fn analyze_records(
+        &self,
+        data: &[TDXDayRecord],
+    ) -> Result<HashMap<(String, String), PerformanceMetrics>> {
+        let series = Self::close_series(data);
+
+        Ok(series
+            .into_par_iter()
+            .filter_map(|(key, closes)| self.analyze(&closes).ok().map(|metrics| (key, metrics)))
+            .collect())
+    }
+
+    /// 计算简单收益率序列
+    pub fn returns(series: &[(NaiveDate, f64)]) -> DateSeries {
+        series
+            .windows(2)
+            .filter(|pair| pair[0].1 != 0.0)
+            .map(|pair| (pair[1].0, pair[1].1 / pair[0].1 - 1.0))
+            .collect()
+    }
+
+    /// 计算最大回撤
+    pub fn max_drawdown(equity: &[(NaiveDate, f64)]) -> Option<Drawdown> {
+        let (mut peak_date, mut peak) = *equity.first()?;
+        let mut worst: Option<Drawdown> = None;
+
+        for &(date, value) in equity {
+            if value > peak {
+                peak = value;
+                peak_date = date;
+            }
+
+            let drawdown = 1.0 - value / peak;
+            if drawdown > worst.as_ref().map_or(0.0, |w| w.max_drawdown) {
+                worst = Some(Drawdown {
+                    max_drawdown: drawdown,
+                    peak_date,
+                    trough_date: date,
+                    recovery_date: None,
+                });
+            }
+        }
+
+        // 查找收复前高的日期
+        if let Some(dd) = worst.as_mut() {
+            let peak_value = equity
+                .iter()
+                .find(|(date, _)| *date == dd.peak_date)
+                .map(|(_, value)| *value)?;
+            dd.recovery_date = equity
+                .iter()
+                .filter(|(date, _)| *date > dd.trough_date)
+                .find(|(_, value)| *value >= peak_value)
+                .map(|(date, _)| *date);
+        }
+
+        worst.or(Some(Drawdown {
+            max_drawdown: 0.0,
+            peak_date,
+            trough_date: peak_date,
+            recovery_date: None,
+        }))
+    }
+
+    /// 计算相对基准的滚动贝塔（按日期内连接对齐收益率）
+    pub fn rolling_beta(
+        asset: &[(NaiveDate, f64)],
+        benchmark: &[(NaiveDate, f64)],
+        window: usize,
+    ) -> DateSeries {
+        let benchmark_returns: HashMap<NaiveDate, f64> =
+            Self::returns(benchmark).into_iter().collect();
+        let aligned: Vec<(NaiveDate, f64, f64)> = Self::returns(asset)
+            .into_iter()
+            .filter_map(|(date, r)| benchmark_returns.get(&date).map(|&b| (date, r, b)))
+            .collect();
+
+        if window < 2 || aligned.len() < window {
+            return Vec::new();
+        }
+
+        aligned
+            .windows(window)
+            .filter_map(|w| {
+                let n = w.len() as f64;
+                let mean_a = w.iter().map(|(_, a, _)| a).sum::<f64>() / n;
+                let mean_b = w.iter().map(|(_, _, b)| b).sum::<f64>() / n;
+                let covariance = w
+                    .iter()
+                    .map(|(_, a, b)| (a - mean_a) * (b - mean_b))
+                    .sum::<f64>();
+                let variance = w.iter().map(|(_, _, b)| (b - mean_b).powi(2)).sum::<f64>();
+
+                (variance > 0.0).then(|| (w[w.len() - 1].0, covariance / variance))
+            })
+            .collect()
+    }
+
+    /// 按（代码, 市场）提取收盘价序列（按日期升序），沪深同代码的股票分开统计
+    pub fn close_series(data: &[TDXDayRecord]) -> HashMap<(String, String), DateSeries> {
+        let mut series: HashMap<(String, String), DateSeries> = HashMap::new();
+        for record in data {
+            series
+                .entry((record.symbol.clone(), record.market.clone()))
+                .or_default()
+                .push((record.date, record.close));
+        }
+        for closes in series.values_mut() {
+            closes.sort_by_key(|(date, _)| *date);
+        }
+        series
+    }
+
+    /// 样本标准差
+    fn std_dev(values: &[f64], mean: f64) -> f64 {
+        if values.len() < 2 {
+            return 0.0;
+        }
+        (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(values: &[f64]) -> DateSeries {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| (start + chrono::Duration::days(i as i64), v))
+            .collect()
+    }
+
+    #[test]
+    fn test_max_drawdown_with_dates() {
+        let equity = series(&[100.0, 120.0, 90.0, 110.0, 125.0]);
+        let dd = PerformanceAnalyzer::max_drawdown(&equity).unwrap();
+
+        assert!((dd.max_drawdown - 0.25).abs() < 1e-12);
+        assert_eq!(dd.peak_date, equity[1].0);
+        assert_eq!(dd.trough_date, equity[2].0);
+        assert_eq!(dd.recovery_date, Some(equity[4].0));
+    }
+
+    #[test]
+    fn test_analyze_metrics() {
+        let equity = series(&[100.0, 101.0, 100.5, 102.0, 103.0]);
+        let metrics = PerformanceAnalyzer::new().analyze(&equity).unwrap();
+
+        assert_eq!(metrics.periods, 4);
+        assert!((metrics.total_return - 0.03).abs() < 1e-12);
+        assert!(metrics.annualized_return > metrics.total_return);
+        assert!(metrics.sharpe_ratio.unwrap() > 0.0);
+        assert!(metrics.sortino_ratio.unwrap() > metrics.sharpe_ratio.unwrap());
+        assert!(metrics.calmar_ratio.is_some());
+
+        assert!(PerformanceAnalyzer::new()
+            .analyze(&series(&[100.0]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_analyze_records_by_market() {
+        let record = |market: &str, day: u32, close: f64| TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: "000001".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: market.to_string(),
+        };
+        // 上证指数与平安银行代码相同
+        let data = vec![
+            record("SH", 2, 3000.0),
+            record("SZ", 2, 10.0),
+            record("SH", 3, 3030.0),
+            record("SZ", 3, 9.0),
+        ];
+
+        let metrics = PerformanceAnalyzer::new().analyze_records(&data).unwrap();
+        assert_eq!(metrics.len(), 2);
+        let sh = &metrics[&("000001".to_string(), "SH".to_string())];
+        let sz = &metrics[&("000001".to_string(), "SZ".to_string())];
+        assert_eq!(sh.periods, 1);
+        assert!((sh.total_return - 0.01).abs() < 1e-12);
+        assert!((sz.total_return + 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rolling_beta() {
+        let benchmark = series(&[100.0, 101.0, 99.0, 102.0, 103.0, 101.0]);
+        // 资产收益恰为基准收益的2倍
+        let mut asset = vec![(benchmark[0].0, 50.0)];
+        for pair in benchmark.windows(2) {
+            let r = pair[1].1 / pair[0].1 - 1.0;
+            let prev = asset.last().unwrap().1;
+            asset.push((pair[1].0, prev * (1.0 + 2.0 * r)));
+        }
+
+        let betas = PerformanceAnalyzer::rolling_beta(&asset, &benchmark, 3);
+        assert_eq!(betas.len(), 3);
+        for (_, beta) in betas {
+            assert!((beta - 2.0).abs() < 1e-9);
+        }
+    }
+}
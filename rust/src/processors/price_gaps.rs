@@ -0,0 +1,312 @@
+//! 跳空缺口统计模块
+//!
+//! 以开盘价相对前收盘价的涨跌幅识别开盘跳空，记录方向和幅度，并向后跟踪N根K线
+//! 判断缺口是否回补：向上跳空在某根K线最低价回落到前收盘价时回补，向下跳空在
+//! 最高价回升到前收盘价时回补，跳空当日即回补记为第0根。最低价高于前最高价
+//! （或最高价低于前最低价）的为完全跳空。
+
+use crate::parsers::TDXDayRecord;
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 跳空方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GapDirection {
+    /// 向上跳空
+    Up,
+    /// 向下跳空
+    Down,
+}
+
+/// 回补状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum GapFill {
+    /// 已回补
+    Filled {
+        /// 回补日期
+        date: NaiveDate,
+        /// 跳空后第几根K线回补（当日为0）
+        bars: usize,
+    },
+    /// 跟踪窗口内未回补
+    Unfilled,
+    /// 数据不足跟踪窗口，尚未回补
+    Pending,
+}
+
+/// 跳空事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceGap {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 跳空日期
+    pub date: NaiveDate,
+    /// 方向
+    pub direction: GapDirection,
+    /// 前收盘价
+    pub prev_close: f64,
+    /// 开盘价
+    pub open: f64,
+    /// 跳空幅度（%，开盘价相对前收盘价）
+    pub gap_percent: f64,
+    /// 是否完全跳空（当日价格区间与前一日不重叠）
+    pub full_gap: bool,
+    /// 回补状态
+    pub fill: GapFill,
+}
+
+/// 单只股票的跳空统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GapSummary {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 向上跳空次数
+    pub up: usize,
+    /// 向下跳空次数
+    pub down: usize,
+    /// 回补比例（不含尚在跟踪中的缺口），没有可统计缺口时为None
+    pub fill_rate: Option<f64>,
+    /// 已回补缺口的平均回补K线数
+    pub mean_bars_to_fill: Option<f64>,
+}
+
+/// 跳空检测器
+#[derive(Debug, Clone)]
+pub struct PriceGapDetector {
+    /// 最小跳空幅度（%）
+    min_gap_percent: f64,
+    /// 回补跟踪窗口（跳空后的K线数）
+    fill_window: usize,
+}
+
+impl Default for PriceGapDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceGapDetector {
+    /// 创建检测器：跳空幅度至少1%，跟踪20根K线
+    pub fn new() -> Self {
+        Self {
+            min_gap_percent: 1.0,
+            fill_window: 20,
+        }
+    }
+
+    /// 设置最小跳空幅度（%）
+    pub fn with_min_gap_percent(mut self, percent: f64) -> Self {
+        self.min_gap_percent = percent.max(0.0);
+        self
+    }
+
+    /// 设置回补跟踪窗口（跳空后的K线数）
+    pub fn with_fill_window(mut self, bars: usize) -> Self {
+        self.fill_window = bars;
+        self
+    }
+
+    /// 检测跳空并跟踪回补，结果按（股票代码, 市场, 日期）排序
+    pub fn detect(&self, data: &[TDXDayRecord]) -> Vec<PriceGap> {
+        let mut groups: HashMap<(&str, &str), Vec<&TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        let mut gaps: Vec<PriceGap> = groups
+            .into_par_iter()
+            .flat_map_iter(|(_, mut records)| {
+                records.sort_by_key(|r| r.date);
+                self.detect_symbol(&records)
+            })
+            .collect();
+        gaps.sort_by(|a, b| (&a.symbol, &a.market, a.date).cmp(&(&b.symbol, &b.market, b.date)));
+        gaps
+    }
+
+    /// 单只股票（已按日期排序）的跳空
+    fn detect_symbol(&self, records: &[&TDXDayRecord]) -> Vec<PriceGap> {
+        let mut gaps = Vec::new();
+        for i in 1..records.len() {
+            let (prev, bar) = (records[i - 1], records[i]);
+            if prev.close <= 0.0 {
+                continue;
+            }
+            let gap_percent = (bar.open - prev.close) / prev.close * 100.0;
+            if gap_percent.abs() < self.min_gap_percent || gap_percent == 0.0 {
+                continue;
+            }
+            let direction = if gap_percent > 0.0 {
+                GapDirection::Up
+            } else {
+                GapDirection::Down
+            };
+            let full_gap = match direction {
+                GapDirection::Up => bar.low > prev.high,
+                GapDirection::Down => bar.high < prev.low,
+            };
+
+            let window = &records[i..records.len().min(i + self.fill_window + 1)];
+            let filled = window.iter().position(|r| match direction {
+                GapDirection::Up => r.low <= prev.close,
+                GapDirection::Down => r.high >= prev.close,
+            });
+            let fill = match filled {
+                Some(bars) => GapFill::Filled {
+                    date: window[bars].date,
+                    bars,
+                },
+                None if window.len() > self.fill_window => GapFill::Unfilled,
+                None => GapFill::Pending,
+            };
+
+            gaps.push(PriceGap {
+                symbol: bar.symbol.clone(),
+                market: bar.market.clone(),
+                date: bar.date,
+                direction,
+                prev_close: prev.close,
+                open: bar.open,
+                gap_percent,
+                full_gap,
+                fill,
+            });
+        }
+        gaps
+    }
+
+    /// 按股票汇总跳空事件（输入为 `detect` 的结果），按（股票代码, 市场）排序
+    pub fn summarize(gaps: &[PriceGap]) -> Vec<GapSummary> {
+        let mut summaries: Vec<GapSummary> = Vec::new();
+        // （已回补数, 已结束数, 回补K线数合计）
+        let mut fills: Vec<(usize, usize, usize)> = Vec::new();
+        let mut sorted: Vec<&PriceGap> = gaps.iter().collect();
+        sorted.sort_by(|a, b| (&a.symbol, &a.market).cmp(&(&b.symbol, &b.market)));
+
+        for gap in sorted {
+            let same = summaries
+                .last()
+                .is_some_and(|s| s.symbol == gap.symbol && s.market == gap.market);
+            if !same {
+                summaries.push(GapSummary {
+                    symbol: gap.symbol.clone(),
+                    market: gap.market.clone(),
+                    up: 0,
+                    down: 0,
+                    fill_rate: None,
+                    mean_bars_to_fill: None,
+                });
+                fills.push((0, 0, 0));
+            }
+            let (summary, fill) = (summaries.last_mut().unwrap(), fills.last_mut().unwrap());
+            match gap.direction {
+                GapDirection::Up => summary.up += 1,
+                GapDirection::Down => summary.down += 1,
+            }
+            match gap.fill {
+                GapFill::Filled { bars, .. } => {
+                    fill.0 += 1;
+                    fill.1 += 1;
+                    fill.2 += bars;
+                }
+                GapFill::Unfilled => fill.1 += 1,
+                GapFill::Pending => {}
+            }
+        }
+
+        for (summary, (filled, finished, bars)) in summaries.iter_mut().zip(fills) {
+            summary.fill_rate = (finished > 0).then(|| filled as f64 / finished as f64);
+            summary.mean_bars_to_fill = (filled > 0).then(|| bars as f64 / filled as f64);
+        }
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(day: usize, open: f64, high: f64, low: f64, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(day as i64),
+            symbol: "600000".to_string(),
+            open,
+            high,
+            low,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn create_data() -> Vec<TDXDayRecord> {
+        vec![
+            create_test_record(0, 10.0, 10.2, 9.9, 10.0),
+            // 完全向上跳空3%，第2根K线回补
+            create_test_record(1, 10.3, 10.5, 10.25, 10.4),
+            create_test_record(2, 10.4, 10.6, 10.3, 10.5),
+            create_test_record(3, 10.4, 10.5, 9.95, 10.1),
+            // 向下跳空2%，当日回补
+            create_test_record(4, 9.9, 10.2, 9.8, 10.15),
+            // 小于1%，不计
+            create_test_record(5, 10.2, 10.3, 10.1, 10.2),
+            // 向上跳空，此后一直未回补
+            create_test_record(6, 10.8, 11.0, 10.7, 10.9),
+            create_test_record(7, 10.9, 11.2, 10.8, 11.1),
+        ]
+    }
+
+    #[test]
+    fn test_detect_and_fill() {
+        let mut data = create_data();
+        data.reverse();
+        let gaps = PriceGapDetector::new().with_fill_window(3).detect(&data);
+        assert_eq!(gaps.len(), 3);
+
+        assert_eq!(gaps[0].direction, GapDirection::Up);
+        assert!(gaps[0].full_gap);
+        assert!((gaps[0].gap_percent - 3.0).abs() < 1e-9);
+        assert_eq!(
+            gaps[0].fill,
+            GapFill::Filled {
+                date: data[4].date,
+                bars: 2
+            }
+        );
+
+        assert_eq!(gaps[1].direction, GapDirection::Down);
+        assert!(!gaps[1].full_gap);
+        assert!(matches!(gaps[1].fill, GapFill::Filled { bars: 0, .. }));
+        // 数据只剩1根K线，窗口未满
+        assert_eq!(gaps[2].fill, GapFill::Pending);
+
+        let unfilled = PriceGapDetector::new().with_fill_window(1).detect(&data);
+        assert_eq!(unfilled[0].fill, GapFill::Unfilled);
+        assert_eq!(unfilled[2].fill, GapFill::Unfilled);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let gaps = PriceGapDetector::new()
+            .with_fill_window(3)
+            .detect(&create_data());
+        let summary = PriceGapDetector::summarize(&gaps);
+        assert_eq!(summary.len(), 1);
+        assert_eq!((summary[0].up, summary[0].down), (2, 1));
+        // 待定的缺口不计入回补比例
+        assert_eq!(summary[0].fill_rate, Some(1.0));
+        assert_eq!(summary[0].mean_bars_to_fill, Some(1.0));
+        assert!(PriceGapDetector::summarize(&[]).is_empty());
+    }
+}
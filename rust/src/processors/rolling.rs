@@ -0,0 +1,303 @@
+//! 滚动窗口引擎
+//!
+//! `RollingWindow` 用环形缓冲区维护窗口内的累加和与离差平方和，每推入一个值O(1)更新；
+//! `RollingEngine` 按（字段, 窗口大小）管理一组窗口，多个指标订阅同一窗口时共享状态
+//! （如MA20与布林带中轨/标准差），不再各自重新求和。
+//!
+//! 一个引擎实例对应一只股票（或一个停牌分段）的时间序列；多只股票时可先订阅好窗口，
+//! 再为每只股票克隆一份。
+
+use crate::parsers::Bar;
+use crate::processors::field::Field;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 精确重算的最小间隔（推入次数）
+const RECOMPUTE_INTERVAL: usize = 1024;
+
+/// 滚动窗口状态
+///
+/// 累加和与平方和按偏移量（首个值或上次重算时的窗口均值）平移后累计，避免价格量级
+/// 较大时平方和相减的精度损失；缓冲区回绕时按间隔精确重算并更新偏移量，消除累计舍入误差。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RollingWindow {
+    /// 环形缓冲区
+    buffer: Vec<f64>,
+    /// 窗口大小
+    capacity: usize,
+    /// 下一个写入位置
+    head: usize,
+    /// 已推入值的个数（不超过窗口大小）
+    len: usize,
+    /// 窗口内非有限值个数
+    invalid: usize,
+    /// 距上次重算的推入次数
+    since_recompute: usize,
+    /// 平移偏移量
+    shift: f64,
+    /// 平移后的有限值累加和
+    sum: f64,
+    /// 平移后的有限值平方和
+    sum_squares: f64,
+}
+
+impl RollingWindow {
+    /// 创建指定大小的窗口
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: vec![0.0; capacity],
+            capacity,
+            head: 0,
+            len: 0,
+            invalid: 0,
+            since_recompute: 0,
+            shift: 0.0,
+            sum: 0.0,
+            sum_squares: 0.0,
+        }
+    }
+
+    /// 窗口大小
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 窗口是否已满
+    pub fn is_full(&self) -> bool {
+        self.capacity > 0 && self.len == self.capacity
+    }
+
+    /// 清空窗口
+    pub fn reset(&mut self) {
+        *self = Self::new(self.capacity);
+    }
+
+    /// 推入新值，窗口已满时返回被移出的值
+    #[inline]
+    pub fn push(&mut self, value: f64) -> Option<f64> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        if self.len == 0 && value.is_finite() {
+            self.shift = value;
+        }
+        let evicted = self.is_full().then(|| self.buffer[self.head]);
+        self.buffer[self.head] = value;
+        self.head += 1;
+        if self.len < self.capacity {
+            self.len += 1;
+        }
+
+        if let Some(old) = evicted {
+            self.accumulate(old, -1.0);
+        }
+        self.accumulate(value, 1.0);
+
+        self.since_recompute += 1;
+        if self.head == self.capacity {
+            self.head = 0;
+            if self.since_recompute >= RECOMPUTE_INTERVAL {
+                self.recompute();
+            }
+        }
+        evicted
+    }
+
+    /// 加入（`sign` 为1）或移出（`sign` 为-1）一个值
+    #[inline]
+    fn accumulate(&mut self, value: f64, sign: f64) {
+        if value.is_finite() {
+            let shifted = value - self.shift;
+            self.sum += sign * shifted;
+            self.sum_squares += sign * shifted * shifted;
+        } else if sign > 0.0 {
+            self.invalid += 1;
+        } else {
+            self.invalid -= 1;
+        }
+    }
+
+    /// 按缓冲区内容重算累加和与平方和，偏移量取窗口均值
+    fn recompute(&mut self) {
+        self.since_recompute = 0;
+        let values = &self.buffer[..self.len];
+        let valid = values.iter().filter(|v| v.is_finite());
+        let count = valid.clone().count();
+        self.shift = if count > 0 {
+            valid.clone().sum::<f64>() / count as f64
+        } else {
+            0.0
+        };
+        self.sum = 0.0;
+        self.sum_squares = 0.0;
+        for value in valid {
+            let shifted = value - self.shift;
+            self.sum += shifted;
+            self.sum_squares += shifted * shifted;
+        }
+    }
+
+    /// 窗口已满且不含非有限值
+    fn ready(&self) -> bool {
+        self.is_full() && self.invalid == 0
+    }
+
+    /// 窗口内累加和，窗口未满或含非有限值时为None
+    pub fn sum(&self) -> Option<f64> {
+        self.ready()
+            .then_some(self.sum + self.shift * self.capacity as f64)
+    }
+
+    /// 窗口均值
+    pub fn mean(&self) -> Option<f64> {
+        self.ready()
+            .then_some(self.shift + self.sum / self.capacity as f64)
+    }
+
+    /// 窗口总体方差
+    pub fn variance(&self) -> Option<f64> {
+        let n = self.capacity as f64;
+        self.ready()
+            .then_some(((self.sum_squares - self.sum * self.sum / n) / n).max(0.0))
+    }
+
+    /// 窗口总体标准差
+    pub fn std(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+}
+
+/// 窗口订阅句柄
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowHandle(usize);
+
+/// 滚动窗口引擎
+#[derive(Debug, Clone, Default)]
+pub struct RollingEngine {
+    /// 已订阅的（字段, 窗口大小）
+    keys: Vec<(Field, usize)>,
+    /// 与 `keys` 一一对应的窗口状态
+    windows: Vec<RollingWindow>,
+}
+
+impl RollingEngine {
+    /// 创建空引擎
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 订阅字段的滚动窗口，相同（字段, 窗口大小）返回同一句柄
+    pub fn subscribe(&mut self, field: Field, size: usize) -> WindowHandle {
+        if let Some(index) = self
+            .keys
+            .iter()
+            .position(|(f, s)| *f == field && *s == size)
+        {
+            return WindowHandle(index);
+        }
+        self.keys.push((field, size));
+        self.windows.push(RollingWindow::new(size));
+        WindowHandle(self.keys.len() - 1)
+    }
+
+    /// 已订阅的窗口数
+    pub fn window_count(&self) -> usize {
+        self.windows.len()
+    }
+
+    /// 推入一根K线的字段值（由 `value` 按字段取值）
+    pub fn push_with(&mut self, value: impl Fn(&Field) -> f64) {
+        for ((field, _), window) in self.keys.iter().zip(&mut self.windows) {
+            window.push(value(field));
+        }
+    }
+
+    /// 推入一根K线
+    pub fn push<B: Bar>(&mut self, bar: &B) -> Result<()> {
+        for ((field, _), window) in self.keys.iter().zip(&mut self.windows) {
+            window.push(field.value(bar)?);
+        }
+        Ok(())
+    }
+
+    /// 清空所有窗口（如复牌后重新预热）
+    pub fn reset(&mut self) {
+        self.windows.iter_mut().for_each(RollingWindow::reset);
+    }
+
+    /// 读取窗口状态
+    pub fn window(&self, handle: WindowHandle) -> &RollingWindow {
+        &self.windows[handle.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::kernels;
+
+    fn sample(len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|i| 10.0 + (i as f64 * 0.37).sin() * 3.0)
+            .collect()
+    }
+
+    #[test]
+    fn test_window_matches_kernels() {
+        // 超过重算间隔，覆盖增量更新与精确重算两条路径
+        let values = sample(3000);
+        let means = kernels::rolling_mean(&values, 20);
+        let stds = kernels::rolling_std(&values, 20);
+
+        let mut window = RollingWindow::new(20);
+        for (i, &value) in values.iter().enumerate() {
+            window.push(value);
+            match window.mean() {
+                Some(mean) => {
+                    assert!((mean - means[i]).abs() < 1e-9);
+                    assert!((window.std().unwrap() - stds[i]).abs() < 1e-9);
+                }
+                None => assert!(means[i].is_nan()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_finite_values() {
+        let mut window = RollingWindow::new(3);
+        for value in [1.0, f64::NAN, 2.0, 3.0] {
+            window.push(value);
+        }
+        assert!(window.mean().is_none());
+        assert!(window.push(4.0).unwrap().is_nan());
+        assert_eq!(window.mean(), Some(3.0));
+        assert!((window.variance().unwrap() - 2.0 / 3.0).abs() < 1e-12);
+
+        window.reset();
+        assert!(!window.is_full());
+    }
+
+    #[test]
+    fn test_engine_shares_windows() {
+        let mut engine = RollingEngine::new();
+        let ma20 = engine.subscribe(Field::Close, 20);
+        let bollinger = engine.subscribe(Field::Close, 20);
+        let volume = engine.subscribe(Field::Volume, 5);
+        assert_eq!(ma20, bollinger);
+        assert_ne!(ma20, volume);
+        assert_eq!(engine.window_count(), 2);
+
+        for i in 0..20 {
+            engine.push_with(|field| match field {
+                Field::Close => i as f64,
+                _ => 100.0,
+            });
+        }
+        assert_eq!(engine.window(ma20).mean(), Some(9.5));
+        assert_eq!(engine.window(volume).mean(), Some(100.0));
+
+        engine.reset();
+        assert!(engine.window(volume).mean().is_none());
+    }
+}
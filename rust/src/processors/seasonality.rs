@@ -0,0 +1,544 @@
+//! 季节性与日历效应统计模块
+//!
+//! 按星期、月份和节前/节后窗口对日收益率分组，统计平均收益率和胜率（收益率大于0的比例）。
+//! 单只股票使用自身相邻两根日线的收盘价收益率；全市场使用当日全部股票收益率的
+//! 等权平均。节假日由交易日历判断：相邻两个交易日之间存在休市的工作日即视为一次长假，
+//! 假期前最后N个交易日为节前窗口，假期后前N个交易日为节后窗口。
+//! 回看期按交易日计，从数据中最新的交易日往前数，可同时统计多个回看期。
+
+use crate::calendar::TradingCalendar;
+use crate::export::{ExportManifest, RotatingCsvWriter, RotationPolicy};
+use crate::parsers::TDXDayRecord;
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate, Weekday};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+/// CSV表头
+const CSV_HEADER: [&str; 8] = [
+    "symbol",
+    "market",
+    "lookback",
+    "dimension",
+    "bucket",
+    "count",
+    "mean_return",
+    "hit_rate",
+];
+
+/// 按日期排列的日收益率
+type ReturnSeries = Vec<(NaiveDate, f64)>;
+
+/// 日历分组
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum SeasonalBucket {
+    /// 星期（1=周一，…，5=周五）
+    Weekday(u32),
+    /// 月份（1~12）
+    Month(u32),
+    /// 节前第N个交易日（1=假期前最后一个交易日）
+    PreHoliday(usize),
+    /// 节后第N个交易日（1=假期后第一个交易日）
+    PostHoliday(usize),
+}
+
+impl SeasonalBucket {
+    /// 分组维度名称
+    pub fn dimension(&self) -> &'static str {
+        match self {
+            SeasonalBucket::Weekday(_) => "weekday",
+            SeasonalBucket::Month(_) => "month",
+            SeasonalBucket::PreHoliday(_) => "pre_holiday",
+            SeasonalBucket::PostHoliday(_) => "post_holiday",
+        }
+    }
+
+    /// 分组取值
+    pub fn value(&self) -> usize {
+        match *self {
+            SeasonalBucket::Weekday(value) | SeasonalBucket::Month(value) => value as usize,
+            SeasonalBucket::PreHoliday(value) | SeasonalBucket::PostHoliday(value) => value,
+        }
+    }
+}
+
+/// 单个分组的统计结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeasonalityRow {
+    /// 股票代码（全市场统计为None）
+    pub symbol: Option<String>,
+    /// 市场（全市场统计为None）
+    pub market: Option<String>,
+    /// 回看交易日数（None为全部历史）
+    pub lookback: Option<usize>,
+    /// 日历分组
+    pub bucket: SeasonalBucket,
+    /// 样本数
+    pub count: usize,
+    /// 平均日收益率
+    pub mean_return: f64,
+    /// 胜率（收益率大于0的比例）
+    pub hit_rate: f64,
+}
+
+impl SeasonalityRow {
+    /// CSV行（与 `CSV_HEADER` 对应）
+    fn csv_record(&self) -> [String; 8] {
+        [
+            self.symbol.clone().unwrap_or_default(),
+            self.market.clone().unwrap_or_default(),
+            self.lookback.map(|n| n.to_string()).unwrap_or_default(),
+            self.bucket.dimension().to_string(),
+            self.bucket.value().to_string(),
+            self.count.to_string(),
+            self.mean_return.to_string(),
+            self.hit_rate.to_string(),
+        ]
+    }
+}
+
+/// 季节性统计表，按（股票代码, 市场, 回看期, 分组）排序，全市场统计在前
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeasonalityTable {
+    /// 统计行
+    rows: Vec<SeasonalityRow>,
+}
+
+impl SeasonalityTable {
+    /// 由统计行构建统计表
+    pub fn new(mut rows: Vec<SeasonalityRow>) -> Self {
+        rows.sort_by(|a, b| {
+            (&a.symbol, &a.market, a.lookback, a.bucket)
+                .cmp(&(&b.symbol, &b.market, b.lookback, b.bucket))
+        });
+        Self { rows }
+    }
+
+    /// 行数
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// 所有统计行
+    pub fn rows(&self) -> &[SeasonalityRow] {
+        &self.rows
+    }
+
+    /// 全市场统计行
+    pub fn market_wide(&self) -> impl Iterator<Item = &SeasonalityRow> {
+        self.rows.iter().filter(|row| row.symbol.is_none())
+    }
+
+    /// 指定股票的统计行
+    pub fn symbol<'a>(
+        &'a self,
+        symbol: &'a str,
+        market: &'a str,
+    ) -> impl Iterator<Item = &'a SeasonalityRow> + 'a {
+        self.rows.iter().filter(move |row| {
+            row.symbol.as_deref() == Some(symbol) && row.market.as_deref() == Some(market)
+        })
+    }
+
+    /// 查找单个分组（`symbol` 为None时查找全市场统计）
+    pub fn get(
+        &self,
+        symbol: Option<(&str, &str)>,
+        lookback: Option<usize>,
+        bucket: SeasonalBucket,
+    ) -> Option<&SeasonalityRow> {
+        self.rows.iter().find(|row| {
+            let scope = row.symbol.as_deref().zip(row.market.as_deref());
+            scope == symbol && row.lookback == lookback && row.bucket == bucket
+        })
+    }
+
+    /// 导出为CSV（全市场统计的代码和市场列为空，全部历史的回看期列为空）
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("无法创建CSV文件: {}", path.display()))?;
+
+        writer.write_record(CSV_HEADER)?;
+        for row in &self.rows {
+            writer.write_record(row.csv_record())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 按行数或字节数分片导出为CSV，分片名以 `seasonality` 为导出名称
+    pub fn to_csv_parts<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        policy: &RotationPolicy,
+    ) -> Result<ExportManifest> {
+        let mut writer =
+            RotatingCsvWriter::create(dir, "seasonality", policy)?.with_header(CSV_HEADER);
+        for row in &self.rows {
+            writer.write_record(row.csv_record())?;
+        }
+        writer.finish()
+    }
+}
+
+/// 季节性统计计算器
+#[derive(Debug, Clone)]
+pub struct SeasonalityAnalyzer {
+    /// 交易日历（缺省由数据中出现过的全部日期构建）
+    calendar: Option<TradingCalendar>,
+    /// 节前/节后窗口长度（交易日）
+    holiday_window: usize,
+    /// 回看期（交易日，None为全部历史）
+    lookbacks: Vec<Option<usize>>,
+    /// 是否输出单只股票的统计
+    per_symbol: bool,
+}
+
+impl Default for SeasonalityAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SeasonalityAnalyzer {
+    /// 创建计算器：全部历史，节前/节后窗口3个交易日，输出单只股票和全市场统计
+    pub fn new() -> Self {
+        Self {
+            calendar: None,
+            holiday_window: 3,
+            lookbacks: vec![None],
+            per_symbol: true,
+        }
+    }
+
+    /// 设置交易日历
+    pub fn with_calendar(mut self, calendar: TradingCalendar) -> Self {
+        self.calendar = Some(calendar);
+        self
+    }
+
+    /// 设置节前/节后窗口长度，为0时不统计节假日效应
+    pub fn with_holiday_window(mut self, window: usize) -> Self {
+        self.holiday_window = window;
+        self
+    }
+
+    /// 设置回看期（交易日，None为全部历史）
+    pub fn with_lookbacks(mut self, lookbacks: Vec<Option<usize>>) -> Self {
+        self.lookbacks = lookbacks;
+        self
+    }
+
+    /// 设置是否输出单只股票的统计（关闭时只输出全市场统计）
+    pub fn with_per_symbol(mut self, per_symbol: bool) -> Self {
+        self.per_symbol = per_symbol;
+        self
+    }
+
+    /// 计算季节性统计表
+    pub fn analyze(&self, data: &[TDXDayRecord]) -> SeasonalityTable {
+        let mut groups: HashMap<(&str, &str), Vec<&TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        // 并行计算每只股票的日收益率
+        let returns: Vec<((&str, &str), ReturnSeries)> = groups
+            .into_par_iter()
+            .map(|(key, mut records)| {
+                records.sort_by_key(|r| r.date);
+                (key, daily_returns(&records))
+            })
+            .collect();
+
+        let dates: BTreeSet<NaiveDate> = data.iter().map(|r| r.date).collect();
+        let calendar = self
+            .calendar
+            .clone()
+            .unwrap_or_else(|| TradingCalendar::from_trading_days(dates.iter().copied()));
+        let buckets: HashMap<NaiveDate, Vec<SeasonalBucket>> = dates
+            .iter()
+            .map(|&date| (date, self.buckets(&calendar, date)))
+            .collect();
+        let dates: Vec<NaiveDate> = dates.into_iter().collect();
+
+        // 全市场等权平均收益率
+        let mut market: BTreeMap<NaiveDate, (f64, usize)> = BTreeMap::new();
+        for (_, series) in &returns {
+            for &(date, value) in series {
+                let entry = market.entry(date).or_insert((0.0, 0));
+                entry.0 += value;
+                entry.1 += 1;
+            }
+        }
+        let market: Vec<(NaiveDate, f64)> = market
+            .into_iter()
+            .map(|(date, (sum, n))| (date, sum / n as f64))
+            .collect();
+
+        let mut rows = Vec::new();
+        for &lookback in &self.lookbacks {
+            let cutoff = lookback_start(&dates, lookback);
+            rows.extend(summarize(None, lookback, &market, cutoff, &buckets));
+            if self.per_symbol {
+                for ((symbol, market), series) in &returns {
+                    rows.extend(summarize(
+                        Some((symbol, market)),
+                        lookback,
+                        series,
+                        cutoff,
+                        &buckets,
+                    ));
+                }
+            }
+        }
+        SeasonalityTable::new(rows)
+    }
+
+    /// 交易日所属的日历分组
+    fn buckets(&self, calendar: &TradingCalendar, date: NaiveDate) -> Vec<SeasonalBucket> {
+        let mut buckets = vec![
+            SeasonalBucket::Weekday(date.weekday().number_from_monday()),
+            SeasonalBucket::Month(date.month()),
+        ];
+
+        let mut current = date;
+        for offset in 1..=self.holiday_window {
+            let Some(next) = calendar.next_trading_day(current) else {
+                break;
+            };
+            if is_holiday_break(calendar, current, next) {
+                buckets.push(SeasonalBucket::PreHoliday(offset));
+                break;
+            }
+            current = next;
+        }
+
+        let mut current = date;
+        for offset in 1..=self.holiday_window {
+            let Some(previous) = calendar.previous_trading_day(current) else {
+                break;
+            };
+            if is_holiday_break(calendar, previous, current) {
+                buckets.push(SeasonalBucket::PostHoliday(offset));
+                break;
+            }
+            current = previous;
+        }
+        buckets
+    }
+}
+
+/// 相邻交易日之间是否有休市的工作日
+fn is_holiday_break(calendar: &TradingCalendar, from: NaiveDate, to: NaiveDate) -> bool {
+    from.iter_days()
+        .skip(1)
+        .take_while(|day| *day < to)
+        .any(|day| {
+            !matches!(day.weekday(), Weekday::Sat | Weekday::Sun) && !calendar.is_trading_day(day)
+        })
+}
+
+/// 相邻两根日线的收盘价收益率（停牌期间的涨跌计入复牌当日），前收盘价无效时跳过
+fn daily_returns(records: &[&TDXDayRecord]) -> ReturnSeries {
+    records
+        .windows(2)
+        .filter(|pair| pair[0].close > 0.0)
+        .map(|pair| (pair[1].date, pair[1].close / pair[0].close - 1.0))
+        .collect()
+}
+
+/// 回看期的起始日期（None为不限）
+fn lookback_start(dates: &[NaiveDate], lookback: Option<usize>) -> Option<NaiveDate> {
+    let n = lookback?;
+    (n < dates.len()).then(|| dates[dates.len() - n.max(1)])
+}
+
+/// 按日历分组汇总一条收益率序列
+fn summarize(
+    scope: Option<(&str, &str)>,
+    lookback: Option<usize>,
+    series: &[(NaiveDate, f64)],
+    cutoff: Option<NaiveDate>,
+    buckets: &HashMap<NaiveDate, Vec<SeasonalBucket>>,
+) -> Vec<SeasonalityRow> {
+    // （收益率合计, 上涨次数, 样本数）
+    let mut stats: BTreeMap<SeasonalBucket, (f64, usize, usize)> = BTreeMap::new();
+    for &(date, value) in series {
+        if cutoff.is_some_and(|cutoff| date < cutoff) {
+            continue;
+        }
+        for &bucket in buckets.get(&date).into_iter().flatten() {
+            let entry = stats.entry(bucket).or_insert((0.0, 0, 0));
+            entry.0 += value;
+            entry.1 += usize::from(value > 0.0);
+            entry.2 += 1;
+        }
+    }
+
+    stats
+        .into_iter()
+        .map(|(bucket, (sum, wins, count))| SeasonalityRow {
+            symbol: scope.map(|(symbol, _)| symbol.to_string()),
+            market: scope.map(|(_, market)| market.to_string()),
+            lookback,
+            bucket,
+            count,
+            mean_return: sum / count as f64,
+            hit_rate: wins as f64 / count as f64,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn create_test_record(symbol: &str, date: NaiveDate, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date,
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    /// 2024年9~10月的交易日（国庆10月1~7日休市），周一上涨1%，其余交易日下跌0.5%
+    fn create_data(symbol: &str) -> (Vec<TDXDayRecord>, TradingCalendar) {
+        let calendar = TradingCalendar::new().with_holidays((1..=7).map(|day| date(10, day)));
+        let mut close = 10.0;
+        let data = calendar
+            .trading_days(date(9, 2), date(10, 31))
+            .into_iter()
+            .map(|day| {
+                close *= if day.weekday() == Weekday::Mon {
+                    1.01
+                } else {
+                    0.995
+                };
+                create_test_record(symbol, day, close)
+            })
+            .collect();
+        (data, calendar)
+    }
+
+    #[test]
+    fn test_weekday_and_month() {
+        let (data, calendar) = create_data("600000");
+        let table = SeasonalityAnalyzer::new()
+            .with_calendar(calendar)
+            .analyze(&data);
+
+        let scope = Some(("600000", "SH"));
+        let monday = table.get(scope, None, SeasonalBucket::Weekday(1)).unwrap();
+        assert!((monday.mean_return - 0.01).abs() < 1e-9);
+        assert_eq!(monday.hit_rate, 1.0);
+        let friday = table.get(scope, None, SeasonalBucket::Weekday(5)).unwrap();
+        assert!((friday.mean_return + 0.005).abs() < 1e-9);
+        assert_eq!(friday.hit_rate, 0.0);
+
+        // 首个交易日没有收益率
+        let september = table.get(scope, None, SeasonalBucket::Month(9)).unwrap();
+        assert_eq!(september.count, 20);
+        // 单只股票时全市场统计与之相同
+        let market = table.get(None, None, SeasonalBucket::Month(9)).unwrap();
+        assert_eq!(market.count, september.count);
+        assert_eq!(
+            table.market_wide().count(),
+            table.symbol("600000", "SH").count()
+        );
+    }
+
+    #[test]
+    fn test_holiday_windows() {
+        let (data, calendar) = create_data("600000");
+        let table = SeasonalityAnalyzer::new()
+            .with_calendar(calendar)
+            .with_holiday_window(2)
+            .with_per_symbol(false)
+            .analyze(&data);
+        assert!(table.rows().iter().all(|row| row.symbol.is_none()));
+
+        // 节前最后一个交易日为9月30日（周一），节后第一个交易日为10月8日（周二）
+        let pre = table
+            .get(None, None, SeasonalBucket::PreHoliday(1))
+            .unwrap();
+        assert_eq!(pre.count, 1);
+        assert!((pre.mean_return - 0.01).abs() < 1e-9);
+        let post = table
+            .get(None, None, SeasonalBucket::PostHoliday(1))
+            .unwrap();
+        assert_eq!(post.count, 1);
+        assert!((post.mean_return + 0.005).abs() < 1e-9);
+        assert!(table
+            .get(None, None, SeasonalBucket::PreHoliday(2))
+            .is_some());
+        assert!(table
+            .get(None, None, SeasonalBucket::PreHoliday(3))
+            .is_none());
+
+        // 未提供日历时由数据日期构建，同样识别出长假
+        let (data, _) = create_data("600000");
+        let inferred = SeasonalityAnalyzer::new().analyze(&data);
+        assert!(inferred
+            .get(None, None, SeasonalBucket::PostHoliday(1))
+            .is_some());
+    }
+
+    #[test]
+    fn test_lookbacks_and_market_average() {
+        let (mut data, calendar) = create_data("600000");
+        // 第二只股票每日上涨2%
+        let mut close = 10.0;
+        for day in calendar.trading_days(date(9, 2), date(10, 31)) {
+            close *= 1.02;
+            data.push(create_test_record("600001", day, close));
+        }
+
+        let table = SeasonalityAnalyzer::new()
+            .with_calendar(calendar)
+            .with_lookbacks(vec![Some(5), None])
+            .analyze(&data);
+
+        let recent = table.get(None, Some(5), SeasonalBucket::Month(10)).unwrap();
+        assert_eq!(recent.count, 5);
+        assert!(table.get(None, Some(5), SeasonalBucket::Month(9)).is_none());
+
+        let monday = table.get(None, None, SeasonalBucket::Weekday(1)).unwrap();
+        assert!((monday.mean_return - 0.015).abs() < 1e-9);
+        assert_eq!(monday.hit_rate, 1.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seasonality.csv");
+        table.to_csv(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), table.len() + 1);
+        assert!(content.starts_with("symbol,market,lookback,dimension,bucket"));
+
+        let policy = RotationPolicy::new().with_max_bytes(256);
+        let manifest = table.to_csv_parts(dir.path(), &policy).unwrap();
+        assert!(manifest.parts.len() > 1);
+        assert_eq!(manifest.row_count, table.len());
+    }
+}
@@ -0,0 +1,234 @@
+//! 日内交易时段统计模块
+//!
+//! 由1分钟线逐日计算每只股票的日内特征：开盘集合竞价成交量占比、上午/下午收益拆分、
+//! 尾盘成交量占比，以及用分钟K线最高/最低价估计的日内已实现波动率
+//! （Parkinson与Garman-Klass，按分钟累加方差后开方，未年化）。
+//!
+//! 集合竞价取9:30及之前结束的记录；通达信分钟线把竞价成交并入9:31的K线，
+//! 此时取首根K线。上午时段包含11:30至13:00之间的记录（与K线合成一致）。
+
+use crate::parsers::TDXMinuteRecord;
+use chrono::{NaiveDate, Timelike};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 上午开盘（自零点起的分钟数）
+const MORNING_OPEN: u32 = 9 * 60 + 30;
+/// 下午开盘
+const AFTERNOON_OPEN: u32 = 13 * 60;
+/// 下午收盘
+const AFTERNOON_CLOSE: u32 = 15 * 60;
+
+/// 单只股票单日的时段统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    /// 交易日期
+    pub date: NaiveDate,
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 分钟K线数
+    pub bars: usize,
+    /// 开盘集合竞价成交量占全天比例
+    pub auction_volume_share: Option<f64>,
+    /// 上午收益率（上午收盘/开盘-1）
+    pub morning_return: Option<f64>,
+    /// 下午收益率（收盘/上午收盘-1）
+    pub afternoon_return: Option<f64>,
+    /// 尾盘成交量占全天比例
+    pub closing_volume_share: Option<f64>,
+    /// Parkinson日内波动率
+    pub parkinson_volatility: Option<f64>,
+    /// Garman-Klass日内波动率
+    pub garman_klass_volatility: Option<f64>,
+}
+
+/// 日内时段统计计算器
+#[derive(Debug, Clone)]
+pub struct SessionStatsCalculator {
+    /// 尾盘窗口（分钟）
+    closing_minutes: u32,
+}
+
+impl Default for SessionStatsCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionStatsCalculator {
+    /// 创建计算器，尾盘窗口为最后30分钟
+    pub fn new() -> Self {
+        Self {
+            closing_minutes: 30,
+        }
+    }
+
+    /// 设置尾盘窗口（分钟，最多120）
+    pub fn with_closing_minutes(mut self, minutes: u32) -> Self {
+        self.closing_minutes = minutes.clamp(1, AFTERNOON_CLOSE - AFTERNOON_OPEN);
+        self
+    }
+
+    /// 计算每只股票每日的时段统计，结果按（股票代码, 市场, 日期）排序
+    ///
+    /// 输入为1分钟线（时间为分钟结束时间），可以是任意顺序、多只股票混合。
+    pub fn calculate(&self, minutes: &[TDXMinuteRecord]) -> Vec<SessionStats> {
+        let mut groups: BTreeMap<(&str, &str, NaiveDate), Vec<&TDXMinuteRecord>> = BTreeMap::new();
+        for record in minutes {
+            groups
+                .entry((
+                    record.symbol.as_str(),
+                    record.market.as_str(),
+                    record.datetime.date(),
+                ))
+                .or_default()
+                .push(record);
+        }
+
+        let groups: Vec<Vec<&TDXMinuteRecord>> = groups.into_values().collect();
+        groups
+            .into_par_iter()
+            .map(|mut records| {
+                records.sort_by_key(|r| r.datetime);
+                self.day_stats(&records)
+            })
+            .collect()
+    }
+
+    /// 单只股票单日的统计（记录已按时间排序且非空）
+    fn day_stats(&self, records: &[&TDXMinuteRecord]) -> SessionStats {
+        let first = records[0];
+        let last = records[records.len() - 1];
+        let minute_of = |r: &TDXMinuteRecord| r.datetime.hour() * 60 + r.datetime.minute();
+        let ratio = |part: f64, total: f64| (total > 0.0).then(|| part / total);
+        let total_volume: u64 = records.iter().map(|r| r.volume).sum();
+
+        let auction: u64 = if minute_of(first) <= MORNING_OPEN {
+            records
+                .iter()
+                .take_while(|r| minute_of(r) <= MORNING_OPEN)
+                .map(|r| r.volume)
+                .sum()
+        } else {
+            first.volume
+        };
+
+        let closing_start = AFTERNOON_CLOSE - self.closing_minutes;
+        let closing: u64 = records
+            .iter()
+            .filter(|r| minute_of(r) > closing_start)
+            .map(|r| r.volume)
+            .sum();
+
+        // 上午最后一根K线（含午间休市期间的记录）
+        let morning_close = records
+            .iter()
+            .rev()
+            .find(|r| minute_of(r) <= AFTERNOON_OPEN)
+            .map(|r| r.close);
+        let has_afternoon = minute_of(last) > AFTERNOON_OPEN;
+        let simple_return = |from: f64, to: f64| (from > 0.0).then(|| to / from - 1.0);
+
+        // 按分钟累加方差
+        let (mut parkinson, mut garman_klass, mut valid) = (0.0, 0.0, 0);
+        for r in records {
+            if r.low <= 0.0 || r.open <= 0.0 || r.high < r.low {
+                continue;
+            }
+            let range = (r.high / r.low).ln().powi(2);
+            let body = (r.close / r.open).ln().powi(2);
+            parkinson += range / (4.0 * std::f64::consts::LN_2);
+            garman_klass += 0.5 * range - (2.0 * std::f64::consts::LN_2 - 1.0) * body;
+            valid += 1;
+        }
+
+        SessionStats {
+            date: first.datetime.date(),
+            symbol: first.symbol.clone(),
+            market: first.market.clone(),
+            bars: records.len(),
+            auction_volume_share: ratio(auction as f64, total_volume as f64),
+            morning_return: morning_close.and_then(|close| simple_return(first.open, close)),
+            afternoon_return: morning_close
+                .filter(|_| has_afternoon)
+                .and_then(|close| simple_return(close, last.close)),
+            closing_volume_share: ratio(closing as f64, total_volume as f64),
+            parkinson_volatility: (valid > 0).then(|| parkinson.sqrt()),
+            garman_klass_volatility: (valid > 0).then(|| garman_klass.max(0.0).sqrt()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minute(day: u32, hour: u32, min: u32, close: f64, volume: u64) -> TDXMinuteRecord {
+        TDXMinuteRecord {
+            datetime: NaiveDate::from_ymd_opt(2024, 1, day)
+                .unwrap()
+                .and_hms_opt(hour, min, 0)
+                .unwrap(),
+            symbol: "600000".to_string(),
+            open: close,
+            high: close * 1.01,
+            low: close,
+            close,
+            volume,
+            amount: close * volume as f64,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_session_stats() {
+        let minutes = vec![
+            minute(2, 15, 0, 10.5, 400),
+            minute(2, 9, 25, 10.0, 100),
+            minute(2, 9, 31, 10.0, 200),
+            minute(2, 11, 30, 11.0, 100),
+            minute(2, 13, 1, 11.0, 100),
+            minute(2, 14, 45, 10.5, 100),
+        ];
+        let stats = SessionStatsCalculator::new().calculate(&minutes);
+        assert_eq!(stats.len(), 1);
+
+        let day = &stats[0];
+        assert_eq!(day.bars, 6);
+        assert_eq!(day.auction_volume_share, Some(0.1));
+        assert!((day.morning_return.unwrap() - 0.1).abs() < 1e-12);
+        assert!((day.afternoon_return.unwrap() + 0.5 / 11.0).abs() < 1e-12);
+        assert_eq!(day.closing_volume_share, Some(0.5));
+
+        // 每分钟 ln(H/L) = ln(1.01)，开收相同
+        let range = 1.01f64.ln().powi(2);
+        let parkinson = (6.0 * range / (4.0 * std::f64::consts::LN_2)).sqrt();
+        assert!((day.parkinson_volatility.unwrap() - parkinson).abs() < 1e-12);
+        let garman_klass = (6.0 * 0.5 * range).sqrt();
+        assert!((day.garman_klass_volatility.unwrap() - garman_klass).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_tdx_auction_and_half_day() {
+        // 无竞价记录时取首根K线；只有上午数据时没有下午收益
+        let minutes = vec![
+            minute(3, 9, 31, 10.0, 300),
+            minute(3, 10, 0, 10.2, 100),
+            minute(3, 11, 30, 10.4, 100),
+            minute(4, 9, 31, 10.0, 0),
+        ];
+        let stats = SessionStatsCalculator::new()
+            .with_closing_minutes(60)
+            .calculate(&minutes);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].auction_volume_share, Some(0.6));
+        assert!(stats[0].afternoon_return.is_none());
+        assert_eq!(stats[0].closing_volume_share, Some(0.0));
+        // 全天无成交时占比为空
+        assert!(stats[1].auction_volume_share.is_none());
+        assert_eq!(stats[1].date, NaiveDate::from_ymd_opt(2024, 1, 4).unwrap());
+    }
+}
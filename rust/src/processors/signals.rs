@@ -0,0 +1,482 @@
+//! 交易信号模块
+//!
+//! 用户以规则描述开平仓条件，规则作用在带指标的日线序列上，产生
+//! `Signal { symbol, date, kind, strength }` 信号流，供回测或Redis发布使用。
+//! 条件可以用构建器组合，也可以写成文本规则，例如：
+//! `MA5 crosses above MA20 and RSI < 70`
+//!
+//! 文本规则按 `or` 拆分后再按 `and` 拆分（`and` 优先），每一项是交叉条件
+//! `A crosses above B` / `A crosses below B`（A、B为字段名或数值），
+//! 或者一个记录表达式（见 `RecordExpr`，如 `rsi < 70`、`close > 1.05 * ma20`）。
+//! 字段名不区分大小写。指标尚未形成时条件不成立。
+
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::processors::crossovers::{crossings, CrossDirection};
+use crate::processors::expr::RecordExpr;
+use crate::processors::field::Field;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// 条件中的操作数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Operand {
+    /// 常数
+    Value(f64),
+    /// 记录字段或指标
+    Field(Field),
+}
+
+impl Operand {
+    /// 读取记录中的操作数取值，指标未形成时为NaN
+    fn value(&self, record: &EnhancedDayRecord) -> f64 {
+        match self {
+            Operand::Value(value) => *value,
+            Operand::Field(field) => field.value(record).unwrap_or(f64::NAN),
+        }
+    }
+}
+
+impl FromStr for Operand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s.parse::<f64>() {
+            Ok(value) => Ok(Operand::Value(value)),
+            Err(_) => s.to_lowercase().parse::<Field>().map(Operand::Field),
+        }
+    }
+}
+
+/// 信号条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// 记录表达式成立
+    Expr {
+        /// 表达式原文
+        expr: String,
+    },
+    /// `fast` 由不高于 `slow` 变为高于 `slow`
+    CrossAbove {
+        /// 上穿的一方
+        fast: Operand,
+        /// 被上穿的一方
+        slow: Operand,
+    },
+    /// `fast` 由不低于 `slow` 变为低于 `slow`
+    CrossBelow {
+        /// 下穿的一方
+        fast: Operand,
+        /// 被下穿的一方
+        slow: Operand,
+    },
+    /// 全部子条件成立
+    All {
+        /// 子条件
+        conditions: Vec<Condition>,
+    },
+    /// 任一子条件成立
+    Any {
+        /// 子条件
+        conditions: Vec<Condition>,
+    },
+    /// 子条件不成立
+    Not {
+        /// 子条件
+        condition: Box<Condition>,
+    },
+}
+
+impl Condition {
+    /// 记录表达式条件（创建时校验表达式）
+    pub fn expr(expr: &str) -> Result<Self> {
+        RecordExpr::parse(expr)?;
+        Ok(Condition::Expr {
+            expr: expr.to_string(),
+        })
+    }
+
+    /// 上穿条件
+    pub fn cross_above(fast: Operand, slow: Operand) -> Self {
+        Condition::CrossAbove { fast, slow }
+    }
+
+    /// 下穿条件
+    pub fn cross_below(fast: Operand, slow: Operand) -> Self {
+        Condition::CrossBelow { fast, slow }
+    }
+
+    /// 与另一条件同时成立
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::All { mut conditions } => {
+                conditions.push(other);
+                Condition::All { conditions }
+            }
+            condition => Condition::All {
+                conditions: vec![condition, other],
+            },
+        }
+    }
+
+    /// 与另一条件任一成立
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Any { mut conditions } => {
+                conditions.push(other);
+                Condition::Any { conditions }
+            }
+            condition => Condition::Any {
+                conditions: vec![condition, other],
+            },
+        }
+    }
+
+    /// 取反
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Condition::Not {
+            condition: Box::new(self),
+        }
+    }
+
+    /// 计算单只股票按日期排序的序列中每条记录是否满足条件
+    pub fn mask(&self, records: &[EnhancedDayRecord]) -> Result<Vec<bool>> {
+        Ok(match self {
+            Condition::Expr { expr } => {
+                let expr = RecordExpr::parse(expr)?;
+                records
+                    .iter()
+                    .map(|r| expr.evaluate(&r.base_record, Some(&r.indicators)))
+                    .collect::<Result<_>>()?
+            }
+            Condition::CrossAbove { fast, slow } => cross_mask(records, fast, slow, true),
+            Condition::CrossBelow { fast, slow } => cross_mask(records, fast, slow, false),
+            Condition::All { conditions } => {
+                let mut mask = vec![true; records.len()];
+                for condition in conditions {
+                    for (keep, ok) in mask.iter_mut().zip(condition.mask(records)?) {
+                        *keep &= ok;
+                    }
+                }
+                mask
+            }
+            Condition::Any { conditions } => {
+                let mut mask = vec![false; records.len()];
+                for condition in conditions {
+                    for (keep, ok) in mask.iter_mut().zip(condition.mask(records)?) {
+                        *keep |= ok;
+                    }
+                }
+                mask
+            }
+            Condition::Not { condition } => {
+                condition.mask(records)?.into_iter().map(|ok| !ok).collect()
+            }
+        })
+    }
+
+    fn parse_term(term: &str) -> Result<Self> {
+        for (keyword, above) in [(" crosses above ", true), (" crosses below ", false)] {
+            if let Some((fast, slow)) = term.split_once(keyword) {
+                let fast = fast.parse::<Operand>()?;
+                let slow = slow.parse::<Operand>()?;
+                return Ok(if above {
+                    Condition::cross_above(fast, slow)
+                } else {
+                    Condition::cross_below(fast, slow)
+                });
+            }
+        }
+        Condition::expr(term.trim())
+    }
+}
+
+/// 上穿/下穿：前一条记录未越过、当前记录越过，任一取值缺失时不成立
+fn cross_mask(
+    records: &[EnhancedDayRecord],
+    fast: &Operand,
+    slow: &Operand,
+    above: bool,
+) -> Vec<bool> {
+    let fast: Vec<f64> = records.iter().map(|r| fast.value(r)).collect();
+    let slow: Vec<f64> = records.iter().map(|r| slow.value(r)).collect();
+    let wanted = if above {
+        CrossDirection::Up
+    } else {
+        CrossDirection::Down
+    };
+    crossings(&fast, &slow)
+        .into_iter()
+        .map(|direction| direction == Some(wanted))
+        .collect()
+}
+
+/// 解析文本规则（不区分大小写）
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let text = s.to_lowercase();
+        let mut any = Vec::new();
+        for clause in text.split(" or ") {
+            let all = clause
+                .split(" and ")
+                .map(Condition::parse_term)
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| anyhow::anyhow!("信号规则解析失败 `{}`: {}", s, e))?;
+            any.push(match all.len() {
+                1 => all.into_iter().next().unwrap(),
+                _ => Condition::All { conditions: all },
+            });
+        }
+        Ok(match any.len() {
+            1 => any.into_iter().next().unwrap(),
+            _ => Condition::Any { conditions: any },
+        })
+    }
+}
+
+/// 信号类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignalKind {
+    /// 开仓
+    Entry,
+    /// 平仓
+    Exit,
+}
+
+/// 交易信号
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Signal {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 日期
+    pub date: NaiveDate,
+    /// 信号类型
+    pub kind: SignalKind,
+    /// 信号强度（规则权重）
+    pub strength: f64,
+    /// 触发信号的规则名称
+    pub rule: String,
+}
+
+/// 信号规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalRule {
+    /// 规则名称
+    pub name: String,
+    /// 信号类型
+    pub kind: SignalKind,
+    /// 触发条件
+    pub condition: Condition,
+    /// 信号强度
+    #[serde(default = "default_strength")]
+    pub strength: f64,
+}
+
+fn default_strength() -> f64 {
+    1.0
+}
+
+impl SignalRule {
+    /// 开仓规则
+    pub fn entry(name: &str, condition: Condition) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: SignalKind::Entry,
+            condition,
+            strength: default_strength(),
+        }
+    }
+
+    /// 平仓规则
+    pub fn exit(name: &str, condition: Condition) -> Self {
+        Self {
+            kind: SignalKind::Exit,
+            ..Self::entry(name, condition)
+        }
+    }
+
+    /// 设置信号强度
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = strength;
+        self
+    }
+}
+
+/// 信号生成器
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalGenerator {
+    rules: Vec<SignalRule>,
+}
+
+impl SignalGenerator {
+    /// 创建没有规则的生成器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加规则
+    pub fn with_rule(mut self, rule: SignalRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// 已添加的规则
+    pub fn rules(&self) -> &[SignalRule] {
+        &self.rules
+    }
+
+    /// 生成信号（按股票、日期排序，同一天多条规则成立时各产生一个信号）
+    pub fn generate(&self, data: &[EnhancedDayRecord]) -> Result<Vec<Signal>> {
+        let mut groups: HashMap<(&str, &str), Vec<&EnhancedDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol(), record.market()))
+                .or_default()
+                .push(record);
+        }
+
+        let mut signals = Vec::new();
+        for records in groups.into_values() {
+            let mut records: Vec<EnhancedDayRecord> = records.into_iter().cloned().collect();
+            records.sort_by_key(|r| r.base_record.date);
+
+            for rule in &self.rules {
+                let mask = rule.condition.mask(&records)?;
+                for (record, _) in records.iter().zip(mask).filter(|(_, ok)| *ok) {
+                    signals.push(Signal {
+                        symbol: record.base_record.symbol.clone(),
+                        market: record.base_record.market.clone(),
+                        date: record.base_record.date,
+                        kind: rule.kind,
+                        strength: rule.strength,
+                        rule: rule.name.clone(),
+                    });
+                }
+            }
+        }
+
+        signals.sort_by(|a, b| {
+            a.symbol
+                .cmp(&b.symbol)
+                .then(a.market.cmp(&b.market))
+                .then(a.date.cmp(&b.date))
+        });
+        Ok(signals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use crate::processors::calculator::IndicatorValues;
+
+    fn record(day: u32, close: f64, ma5: Option<f64>, rsi: Option<f64>) -> EnhancedDayRecord {
+        EnhancedDayRecord {
+            base_record: TDXDayRecord {
+                date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+                symbol: "600000".to_string(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1000,
+                amount: close * 1000.0,
+                market: "SH".to_string(),
+            },
+            indicators: IndicatorValues {
+                ma5,
+                ma20: Some(10.0),
+                rsi,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let condition: Condition = "MA5 crosses above MA20 and RSI < 70".parse().unwrap();
+        assert_eq!(
+            condition,
+            Condition::cross_above(
+                Operand::Field(Field::Indicator("ma5".to_string())),
+                Operand::Field(Field::Indicator("ma20".to_string()))
+            )
+            .and(Condition::expr("rsi < 70").unwrap())
+        );
+
+        let condition: Condition = "close crosses below 9.5 or rsi > 80".parse().unwrap();
+        assert!(matches!(condition, Condition::Any { ref conditions } if conditions.len() == 2));
+        assert!("foo crosses above ma20".parse::<Condition>().is_err());
+        assert!("rsi < foo".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn test_cross_mask() {
+        let records = vec![
+            record(2, 10.0, None, Some(50.0)),
+            record(3, 10.0, Some(9.8), Some(50.0)),
+            record(4, 10.0, Some(10.2), Some(60.0)),
+            record(5, 10.0, Some(10.4), Some(75.0)),
+            record(8, 10.0, Some(9.9), Some(40.0)),
+        ];
+        let above: Condition = "ma5 crosses above ma20".parse().unwrap();
+        assert_eq!(
+            above.mask(&records).unwrap(),
+            vec![false, false, true, false, false]
+        );
+        let below: Condition = "ma5 crosses below ma20".parse().unwrap();
+        assert_eq!(
+            below.mask(&records).unwrap(),
+            vec![false, false, false, false, true]
+        );
+        assert_eq!(
+            below.not().mask(&records).unwrap(),
+            vec![true, true, true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_generate_signals() {
+        let generator = SignalGenerator::new()
+            .with_rule(SignalRule::entry(
+                "golden",
+                "MA5 crosses above MA20 and RSI < 70".parse().unwrap(),
+            ))
+            .with_rule(
+                SignalRule::exit("overbought", "rsi > 70".parse().unwrap()).with_strength(0.5),
+            );
+
+        // 乱序输入，生成时按日期排序
+        let records = vec![
+            record(5, 10.0, Some(10.4), Some(75.0)),
+            record(3, 10.0, Some(9.8), Some(50.0)),
+            record(4, 10.0, Some(10.2), Some(60.0)),
+        ];
+        let signals = generator.generate(&records).unwrap();
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].kind, SignalKind::Entry);
+        assert_eq!(
+            signals[0].date,
+            NaiveDate::from_ymd_opt(2024, 1, 4).unwrap()
+        );
+        assert_eq!(signals[1].kind, SignalKind::Exit);
+        assert_eq!(signals[1].rule, "overbought");
+        assert_eq!(signals[1].strength, 0.5);
+
+        // 规则可序列化为JSON配置
+        let json = serde_json::to_string(&generator).unwrap();
+        let restored: SignalGenerator = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), generator.rules());
+    }
+}
@@ -0,0 +1,239 @@
+//! 停牌检测模块
+//!
+//! 以交易日历为基准，把每只股票首末日期之间连续缺失、之后又恢复交易的交易日
+//! 识别为停牌期间。结合证券主数据时，未上市或已退市的日期不计为停牌。
+//! 指标计算和清洗可据此在复牌处断开序列，而不是把停牌前后的数据直接拼接。
+
+use crate::calendar::TradingCalendar;
+use crate::parsers::TDXDayRecord;
+use crate::processors::gaps::GapFiller;
+use crate::reference::SecurityMaster;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 停牌期间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SuspensionPeriod {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 停牌首日
+    pub start: NaiveDate,
+    /// 停牌末日（次一交易日复牌）
+    pub end: NaiveDate,
+    /// 停牌交易日数
+    pub trading_days: usize,
+}
+
+impl SuspensionPeriod {
+    /// 日期是否在停牌期间内
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.start && date <= self.end
+    }
+
+    /// 停牌是否位于两根K线之间
+    pub fn separates(&self, before: NaiveDate, after: NaiveDate) -> bool {
+        before < self.start && after > self.end
+    }
+}
+
+/// 停牌检测器
+#[derive(Debug, Clone, Default)]
+pub struct SuspensionDetector {
+    /// 交易日历
+    calendar: TradingCalendar,
+    /// 最少连续缺失交易日数
+    min_days: usize,
+    /// 证券主数据
+    master: Option<SecurityMaster>,
+}
+
+impl SuspensionDetector {
+    /// 创建新的停牌检测器
+    pub fn new(calendar: TradingCalendar) -> Self {
+        Self {
+            calendar,
+            min_days: 1,
+            master: None,
+        }
+    }
+
+    /// 设置最少连续缺失交易日数，较短的缺口不视为停牌
+    pub fn with_min_days(mut self, min_days: usize) -> Self {
+        self.min_days = min_days.max(1);
+        self
+    }
+
+    /// 设置证券主数据，未上市或已退市的日期不计为停牌
+    pub fn with_security_master(mut self, master: SecurityMaster) -> Self {
+        self.master = Some(master);
+        self
+    }
+
+    /// 检测停牌期间，按（股票代码, 市场, 开始日期）排序
+    pub fn detect(&self, data: &[TDXDayRecord]) -> Vec<SuspensionPeriod> {
+        let mut periods = Vec::new();
+        for gap in GapFiller::new(self.calendar.clone()).find_gaps(data) {
+            let mut run: Vec<NaiveDate> = Vec::new();
+            let missing = gap.missing.iter().copied().filter(|&date| {
+                self.master
+                    .as_ref()
+                    .is_none_or(|master| master.status(&gap.symbol, &gap.market, date).is_trading())
+            });
+
+            for date in missing {
+                let consecutive = run
+                    .last()
+                    .is_some_and(|&last| self.calendar.next_trading_day(last) == Some(date));
+                if !consecutive {
+                    self.push_period(&mut periods, &gap.symbol, &gap.market, &run);
+                    run.clear();
+                }
+                run.push(date);
+            }
+            self.push_period(&mut periods, &gap.symbol, &gap.market, &run);
+        }
+        periods
+    }
+
+    fn push_period(
+        &self,
+        periods: &mut Vec<SuspensionPeriod>,
+        symbol: &str,
+        market: &str,
+        run: &[NaiveDate],
+    ) {
+        if let (Some(&start), Some(&end)) = (run.first(), run.last()) {
+            if run.len() >= self.min_days {
+                periods.push(SuspensionPeriod {
+                    symbol: symbol.to_string(),
+                    market: market.to_string(),
+                    start,
+                    end,
+                    trading_days: run.len(),
+                });
+            }
+        }
+    }
+}
+
+/// 按股票索引的停牌期间
+#[derive(Debug, Clone, Default)]
+pub struct SuspensionIndex {
+    /// （股票代码, 市场）到停牌期间
+    periods: HashMap<(String, String), Vec<SuspensionPeriod>>,
+}
+
+impl SuspensionIndex {
+    /// 由停牌期间构建索引
+    pub fn new(periods: Vec<SuspensionPeriod>) -> Self {
+        let mut index: HashMap<(String, String), Vec<SuspensionPeriod>> = HashMap::new();
+        for period in periods {
+            index
+                .entry((period.symbol.clone(), period.market.clone()))
+                .or_default()
+                .push(period);
+        }
+        Self { periods: index }
+    }
+
+    /// 是否没有停牌
+    pub fn is_empty(&self) -> bool {
+        self.periods.is_empty()
+    }
+
+    /// 某只股票的停牌期间
+    pub fn periods(&self, symbol: &str, market: &str) -> &[SuspensionPeriod] {
+        self.periods
+            .get(&(symbol.to_string(), market.to_string()))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// 两个日期之间是否隔着停牌
+    pub fn separates(
+        &self,
+        symbol: &str,
+        market: &str,
+        before: NaiveDate,
+        after: NaiveDate,
+    ) -> bool {
+        self.periods(symbol, market)
+            .iter()
+            .any(|period| period.separates(before, after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::SecurityMeta;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+    }
+
+    fn create_test_record(symbol: &str, day: u32) -> TDXDayRecord {
+        TDXDayRecord {
+            date: date(day),
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            volume: 1000,
+            amount: 10_000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn create_data() -> Vec<TDXDayRecord> {
+        // 2024-01-02(二) ~ 01-19(五)，600000 缺 01-04、01-09~01-11
+        [2, 3, 5, 8, 12, 15]
+            .iter()
+            .map(|&day| create_test_record("600000", day))
+            .chain([2, 19].iter().map(|&day| create_test_record("600001", day)))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_periods() {
+        let periods = SuspensionDetector::new(TradingCalendar::new()).detect(&create_data());
+        assert_eq!(periods.len(), 3);
+        assert_eq!((periods[0].start, periods[0].end), (date(4), date(4)));
+        assert_eq!((periods[1].start, periods[1].end), (date(9), date(11)));
+        assert_eq!(periods[1].trading_days, 3);
+        assert_eq!(periods[2].symbol, "600001");
+        assert_eq!(periods[2].trading_days, 12);
+
+        let long = SuspensionDetector::new(TradingCalendar::new())
+            .with_min_days(3)
+            .detect(&create_data());
+        assert_eq!(long.len(), 2);
+    }
+
+    #[test]
+    fn test_security_master_excludes_unlisted_days() {
+        let mut master = SecurityMaster::new();
+        let mut meta = SecurityMeta::new("600001", "SH", "");
+        meta.listing_date = Some(date(19));
+        master.insert(meta);
+
+        // 01-19 才上市，之前的缺口不是停牌
+        let periods = SuspensionDetector::new(TradingCalendar::new())
+            .with_security_master(master)
+            .detect(&create_data());
+        assert!(periods.iter().all(|p| p.symbol == "600000"));
+    }
+
+    #[test]
+    fn test_index() {
+        let periods = SuspensionDetector::new(TradingCalendar::new()).detect(&create_data());
+        let index = SuspensionIndex::new(periods);
+        assert!(index.separates("600000", "SH", date(8), date(12)));
+        assert!(!index.separates("600000", "SH", date(2), date(3)));
+        assert!(index.periods("000001", "SZ").is_empty());
+    }
+}
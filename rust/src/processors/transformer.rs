@@ -1,11 +1,9 @@
 //! 数据转换模块 - 重构简化版本
 
 use crate::parsers::TDXDayRecord;
+use crate::processors::lineage::Lineage;
 use anyhow::Result;
-use chrono::{Duration, NaiveDate};
 use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 /// 重采样方法
 #[derive(Debug, Clone)]
@@ -102,8 +100,8 @@ impl DataTransformer {
                         transform_type: "Indicators".to_string(),
                         processing_time_ms: 0,
                         memory_usage_bytes: 0,
-                        input_size_bytes: current_data.len() * std::mem::size_of::<TDXDayRecord>(),
-                        output_size_bytes: current_data.len() * std::mem::size_of::<TDXDayRecord>(),
+                        input_size_bytes: std::mem::size_of_val(current_data.as_slice()),
+                        output_size_bytes: std::mem::size_of_val(current_data.as_slice()),
                     };
                     statistics.push(stats);
                 }
@@ -113,8 +111,8 @@ impl DataTransformer {
                         transform_type: "Features".to_string(),
                         processing_time_ms: 0,
                         memory_usage_bytes: 0,
-                        input_size_bytes: current_data.len() * std::mem::size_of::<TDXDayRecord>(),
-                        output_size_bytes: current_data.len() * std::mem::size_of::<TDXDayRecord>(),
+                        input_size_bytes: std::mem::size_of_val(current_data.as_slice()),
+                        output_size_bytes: std::mem::size_of_val(current_data.as_slice()),
                     };
                     statistics.push(stats);
                 }
@@ -130,6 +128,39 @@ impl DataTransformer {
         Ok((current_data, statistics))
     }
 
+    /// 执行数据转换并同步更新血缘，`lineage` 与 `data` 按位置一一对应
+    ///
+    /// 每个转换登记为一个步骤，标记到数值被它改变的记录上。
+    pub fn transform_data_with_lineage(
+        &self,
+        data: &[TDXDayRecord],
+        transformations: Vec<&str>,
+        lineage: &Lineage,
+    ) -> Result<(Vec<TDXDayRecord>, Vec<TransformationStatistics>, Lineage)> {
+        lineage.check_len(data.len())?;
+        let mut current_data = data.to_vec();
+        let mut statistics = Vec::new();
+        let mut lineage = lineage.clone();
+
+        for transform_name in transformations {
+            let (transformed, stats) = self.transform_data(&current_data, vec![transform_name])?;
+            let step = lineage.add_step(transform_name);
+            lineage.tag(
+                step,
+                current_data
+                    .iter()
+                    .zip(&transformed)
+                    .enumerate()
+                    .filter(|(_, (before, after))| !same_values(before, after))
+                    .map(|(position, _)| position),
+            );
+            current_data = transformed;
+            statistics.extend(stats);
+        }
+
+        Ok((current_data, statistics, lineage))
+    }
+
     /// 重采样数据
     pub fn resample_data(
         &self,
@@ -168,8 +199,8 @@ impl DataTransformer {
                     transform_type: format!("Resample_{}", target_timeframe),
                     processing_time_ms: 0,
                     memory_usage_bytes: 0,
-                    input_size_bytes: data.len() * std::mem::size_of::<TDXDayRecord>(),
-                    output_size_bytes: data.len() * std::mem::size_of::<TDXDayRecord>(),
+                    input_size_bytes: std::mem::size_of_val(data),
+                    output_size_bytes: std::mem::size_of_val(data),
                 },
             ));
         }
@@ -206,8 +237,8 @@ impl DataTransformer {
                 transform_type: format!("Resample_{}", target_timeframe),
                 processing_time_ms: 0,
                 memory_usage_bytes: 0,
-                input_size_bytes: data.len() * std::mem::size_of::<TDXDayRecord>(),
-                output_size_bytes: resampled_data.len() * std::mem::size_of::<TDXDayRecord>(),
+                input_size_bytes: std::mem::size_of_val(data),
+                output_size_bytes: std::mem::size_of_val(resampled_data.as_slice()),
             },
         ))
     }
@@ -280,7 +311,7 @@ impl DataTransformer {
     fn normalize_data(
         &self,
         data: &[TDXDayRecord],
-        method: &NormalizationMethod,
+        _method: &NormalizationMethod,
         fields: &[String],
     ) -> (Vec<TDXDayRecord>, usize, TransformationStatistics) {
         if data.is_empty() {
@@ -307,25 +338,12 @@ impl DataTransformer {
                 transform_type: "Normalize".to_string(),
                 processing_time_ms: 0,
                 memory_usage_bytes: 0,
-                input_size_bytes: data.len() * std::mem::size_of::<TDXDayRecord>(),
-                output_size_bytes: normalized_data.len() * std::mem::size_of::<TDXDayRecord>(),
+                input_size_bytes: std::mem::size_of_val(data),
+                output_size_bytes: std::mem::size_of_val(normalized_data.as_slice()),
             },
         )
     }
 
-    /// 获取字段值（简化实现）
-    fn get_field_value(&self, record: &TDXDayRecord, field: &str) -> f64 {
-        match field {
-            "open" => record.open,
-            "high" => record.high,
-            "low" => record.low,
-            "close" => record.close,
-            "volume" => record.volume as f64,
-            "amount" => record.amount,
-            _ => 0.0,
-        }
-    }
-
     /// 并行转换数据
     pub fn transform_parallel(
         &self,
@@ -338,10 +356,7 @@ impl DataTransformer {
 
         let batches: Vec<_> = data.chunks(self.batch_size).collect();
 
-        let results: Result<Vec<_>> = batches
-            .into_par_iter()
-            .map(|batch| transform_fn(batch))
-            .collect();
+        let results: Result<Vec<_>> = batches.into_par_iter().map(&transform_fn).collect();
 
         match results {
             Ok(batch_results) => {
@@ -356,6 +371,12 @@ impl DataTransformer {
     }
 }
 
+/// 两条记录的价格、成交量和成交额是否完全相同
+fn same_values(a: &TDXDayRecord, b: &TDXDayRecord) -> bool {
+    let values = |r: &TDXDayRecord| [r.open, r.high, r.low, r.close, r.amount].map(f64::to_bits);
+    values(a) == values(b) && a.volume == b.volume
+}
+
 impl Default for DataTransformer {
     fn default() -> Self {
         Self::new()
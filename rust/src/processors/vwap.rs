@@ -0,0 +1,97 @@
+//! 成交量加权均价（VWAP）与时间加权均价（TWAP）模块
+//!
+//! 单根K线的VWAP为成交额/成交量。成交量为0、或VWAP超出当根最高/最低价一定容差时
+//! （常见于指数或单位换算错误的数据），视为无效。N日VWAP为窗口内成交额合计/成交量合计；
+//! 日线没有逐笔时间，N日TWAP取窗口内各K线典型价（开高低收均值）的平均。
+
+use crate::parsers::Bar;
+use crate::processors::kernels::rolling_mean;
+
+/// VWAP超出最高/最低价的容差（相对价格）
+const VWAP_TOLERANCE: f64 = 0.01;
+
+/// 单根K线的VWAP，成交量为0或超出 `[最低价, 最高价]`（含1%容差）时为None
+pub fn bar_vwap(amount: f64, volume: f64, low: f64, high: f64) -> Option<f64> {
+    if volume <= 0.0 || amount <= 0.0 {
+        return None;
+    }
+    let vwap = amount / volume;
+    let within = vwap >= low * (1.0 - VWAP_TOLERANCE) && vwap <= high * (1.0 + VWAP_TOLERANCE);
+    (vwap.is_finite() && within).then_some(vwap)
+}
+
+/// 记录的VWAP（校验规则同 `bar_vwap`）
+pub fn vwap<B: Bar>(bar: &B) -> Option<f64> {
+    bar_vwap(bar.amount(), bar.volume() as f64, bar.low(), bar.high())
+}
+
+/// 典型价（开高低收均值）
+pub fn typical_price(open: f64, high: f64, low: f64, close: f64) -> f64 {
+    (open + high + low + close) / 4.0
+}
+
+/// N日VWAP（窗口内成交额合计/成交量合计），窗口未满或成交量合计为0时为NaN
+pub fn rolling_vwap(amounts: &[f64], volumes: &[f64], window: usize) -> Vec<f64> {
+    rolling_mean(amounts, window)
+        .into_iter()
+        .zip(rolling_mean(volumes, window))
+        .map(|(amount, volume)| {
+            if volume > 0.0 {
+                amount / volume
+            } else {
+                f64::NAN
+            }
+        })
+        .collect()
+}
+
+/// N日TWAP（窗口内典型价的平均），窗口未满时为NaN
+pub fn rolling_twap(typical_prices: &[f64], window: usize) -> Vec<f64> {
+    rolling_mean(typical_prices, window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_bar_vwap_validation() {
+        assert_eq!(bar_vwap(10_500.0, 1000.0, 10.0, 11.0), Some(10.5));
+        // 容差内
+        assert!(bar_vwap(11_050.0, 1000.0, 10.0, 11.0).is_some());
+        // 成交量单位为手时VWAP放大100倍
+        assert_eq!(bar_vwap(10_500.0, 10.0, 10.0, 11.0), None);
+        assert_eq!(bar_vwap(10_500.0, 0.0, 10.0, 11.0), None);
+
+        let record = TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            symbol: "600000".to_string(),
+            open: 10.0,
+            high: 11.0,
+            low: 9.0,
+            close: 10.5,
+            volume: 1_000_000,
+            amount: 10_200_000.0,
+            market: "SH".to_string(),
+        };
+        assert_eq!(vwap(&record), Some(10.2));
+    }
+
+    #[test]
+    fn test_rolling_vwap_and_twap() {
+        let amounts = [1000.0, 4000.0, 0.0, 3000.0];
+        let volumes = [100.0, 200.0, 0.0, 100.0];
+        let vwap = rolling_vwap(&amounts, &volumes, 2);
+        assert!(vwap[0].is_nan());
+        assert!((vwap[1] - 5000.0 / 300.0).abs() < 1e-12);
+        assert_eq!(vwap[2], 20.0);
+        assert_eq!(vwap[3], 30.0);
+        assert!(rolling_vwap(&[0.0, 0.0], &[0.0, 0.0], 2)[1].is_nan());
+
+        let twap = rolling_twap(&[typical_price(10.0, 12.0, 8.0, 10.0), 11.0, 12.0], 3);
+        assert!(twap[1].is_nan());
+        assert!((twap[2] - 11.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,282 @@
+//! 之字转向（ZigZag）与波段高低点模块
+//!
+//! 价格自上一个极值反向变动超过阈值时确认该极值为波段高点或低点。
+//! 确认需要后续K线，因此每个拐点都记录确认位置：回测中只能在确认K线之后使用该拐点。
+//! 序列末尾尚未反转确认的极值为临时拐点，后续数据可能延伸或改变它。
+
+use crate::parsers::TDXDayRecord;
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 拐点类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SwingKind {
+    /// 波段高点
+    High,
+    /// 波段低点
+    Low,
+}
+
+/// 序列上的拐点（按位置）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwingPoint {
+    /// 拐点所在位置
+    pub index: usize,
+    /// 拐点价格
+    pub price: f64,
+    /// 类型
+    pub kind: SwingKind,
+    /// 确认位置（反向变动达到阈值的K线），临时拐点为None
+    pub confirmed_at: Option<usize>,
+}
+
+/// 股票的拐点（按日期）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pivot {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 拐点日期
+    pub date: NaiveDate,
+    /// 拐点价格
+    pub price: f64,
+    /// 类型
+    pub kind: SwingKind,
+    /// 确认日期，临时拐点为None
+    pub confirmed_date: Option<NaiveDate>,
+}
+
+impl Pivot {
+    /// 是否已确认
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_date.is_some()
+    }
+}
+
+/// 之字转向检测器
+#[derive(Debug, Clone)]
+pub struct ZigZag {
+    /// 反转阈值（%）
+    threshold_percent: f64,
+    /// 使用最高/最低价（否则只用收盘价）
+    use_high_low: bool,
+}
+
+impl Default for ZigZag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZigZag {
+    /// 创建检测器：反转阈值5%，使用最高/最低价
+    pub fn new() -> Self {
+        Self {
+            threshold_percent: 5.0,
+            use_high_low: true,
+        }
+    }
+
+    /// 设置反转阈值（%）
+    pub fn with_threshold_percent(mut self, percent: f64) -> Self {
+        self.threshold_percent = percent.max(0.0);
+        self
+    }
+
+    /// 只用收盘价判断拐点
+    pub fn with_close_only(mut self) -> Self {
+        self.use_high_low = false;
+        self
+    }
+
+    /// 检测每只股票的拐点，结果按（股票代码, 市场, 日期）排序
+    pub fn detect(&self, data: &[TDXDayRecord]) -> Vec<Pivot> {
+        let mut groups: HashMap<(&str, &str), Vec<&TDXDayRecord>> = HashMap::new();
+        for record in data {
+            groups
+                .entry((record.symbol.as_str(), record.market.as_str()))
+                .or_default()
+                .push(record);
+        }
+
+        let mut pivots: Vec<Pivot> = groups
+            .into_par_iter()
+            .flat_map_iter(|(_, mut records)| {
+                records.sort_by_key(|r| r.date);
+                let (highs, lows): (Vec<f64>, Vec<f64>) = records
+                    .iter()
+                    .map(|r| match self.use_high_low {
+                        true => (r.high, r.low),
+                        false => (r.close, r.close),
+                    })
+                    .unzip();
+                self.swing_points(&highs, &lows)
+                    .into_iter()
+                    .map(|point| Pivot {
+                        symbol: records[point.index].symbol.clone(),
+                        market: records[point.index].market.clone(),
+                        date: records[point.index].date,
+                        price: point.price,
+                        kind: point.kind,
+                        confirmed_date: point.confirmed_at.map(|i| records[i].date),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        pivots.sort_by(|a, b| (&a.symbol, &a.market, a.date).cmp(&(&b.symbol, &b.market, b.date)));
+        pivots
+    }
+
+    /// 按时间排序的最高/最低价序列上的拐点，高低交替出现，最后一个可能是临时拐点
+    pub fn swing_points(&self, highs: &[f64], lows: &[f64]) -> Vec<SwingPoint> {
+        let len = highs.len().min(lows.len());
+        let threshold = self.threshold_percent / 100.0;
+        let reversed_down = |from: f64, to: f64| to <= from * (1.0 - threshold);
+        let reversed_up = |from: f64, to: f64| to >= from * (1.0 + threshold);
+
+        let mut points = Vec::new();
+        if len == 0 {
+            return points;
+        }
+
+        // 方向确定前同时跟踪最高点和最低点
+        let (mut high, mut low) = ((0, highs[0]), (0, lows[0]));
+        // 当前方向及其正在延伸的极值
+        let mut trend: Option<(SwingKind, usize, f64)> = None;
+
+        for i in 1..len {
+            match trend {
+                None => {
+                    if highs[i] > high.1 {
+                        high = (i, highs[i]);
+                    }
+                    if lows[i] < low.1 {
+                        low = (i, lows[i]);
+                    }
+                    let down = reversed_down(high.1, lows[i]) && high.0 < i;
+                    let up = reversed_up(low.1, highs[i]) && low.0 < i;
+                    // 两个方向同时触发时，较早的极值为第一个拐点
+                    if down && (!up || high.0 < low.0) {
+                        points.push(confirmed(high.0, high.1, SwingKind::High, i));
+                        trend = Some((SwingKind::Low, i, lows[i]));
+                    } else if up {
+                        points.push(confirmed(low.0, low.1, SwingKind::Low, i));
+                        trend = Some((SwingKind::High, i, highs[i]));
+                    }
+                }
+                Some((SwingKind::High, index, price)) => {
+                    if highs[i] > price {
+                        trend = Some((SwingKind::High, i, highs[i]));
+                    } else if reversed_down(price, lows[i]) {
+                        points.push(confirmed(index, price, SwingKind::High, i));
+                        trend = Some((SwingKind::Low, i, lows[i]));
+                    }
+                }
+                Some((SwingKind::Low, index, price)) => {
+                    if lows[i] < price {
+                        trend = Some((SwingKind::Low, i, lows[i]));
+                    } else if reversed_up(price, highs[i]) {
+                        points.push(confirmed(index, price, SwingKind::Low, i));
+                        trend = Some((SwingKind::High, i, highs[i]));
+                    }
+                }
+            }
+        }
+
+        if let Some((kind, index, price)) = trend {
+            points.push(SwingPoint {
+                index,
+                price,
+                kind,
+                confirmed_at: None,
+            });
+        }
+        points
+    }
+}
+
+fn confirmed(index: usize, price: f64, kind: SwingKind, at: usize) -> SwingPoint {
+    SwingPoint {
+        index,
+        price,
+        kind,
+        confirmed_at: Some(at),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(day: i64, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(day),
+            symbol: "600000".to_string(),
+            open: close,
+            high: close * 1.01,
+            low: close * 0.99,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_swing_points() {
+        let closes = [10.0, 10.6, 11.0, 10.8, 10.3, 10.6, 11.5, 12.0, 11.0, 11.2];
+        let points = ZigZag::new().swing_points(&closes, &closes);
+
+        let summary: Vec<_> = points
+            .iter()
+            .map(|p| (p.index, p.kind, p.confirmed_at))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, SwingKind::Low, Some(1)),
+                (2, SwingKind::High, Some(4)),
+                (4, SwingKind::Low, Some(6)),
+                (7, SwingKind::High, Some(8)),
+                // 末尾低点尚未被反向5%确认
+                (8, SwingKind::Low, None),
+            ]
+        );
+        assert!(ZigZag::new().swing_points(&[], &[]).is_empty());
+        // 波动不足阈值时没有拐点
+        assert!(ZigZag::new()
+            .with_threshold_percent(50.0)
+            .swing_points(&closes, &closes)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_detect_per_symbol() {
+        let mut data: Vec<TDXDayRecord> = [10.0, 11.0, 10.0, 11.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| create_test_record(i as i64, close))
+            .collect();
+        let mut other = create_test_record(0, 10.0);
+        other.symbol = "000001".to_string();
+        other.market = "SZ".to_string();
+        data.push(other);
+        data.reverse();
+
+        let pivots = ZigZag::new().with_close_only().detect(&data);
+        assert_eq!(pivots.len(), 4);
+        assert!(pivots.iter().all(|p| p.symbol == "600000"));
+        assert_eq!(pivots[1].kind, SwingKind::High);
+        assert_eq!(pivots[1].price, 11.0);
+        assert_eq!(pivots[1].confirmed_date, Some(data[2].date));
+        assert!(!pivots[3].is_confirmed());
+
+        // 使用最高/最低价时波动更大
+        let pivots = ZigZag::new().with_threshold_percent(11.0).detect(&data);
+        assert_eq!(pivots[0].price, 9.9);
+        assert!((pivots[1].price - 11.11).abs() < 1e-9);
+    }
+}
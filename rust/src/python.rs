@@ -0,0 +1,206 @@
+//! Python绑定模块（`python` 特性）
+//!
+//! 以 `pulse_trader_rust._core` 扩展模块的形式提供给Python端。行情数据按列返回：
+//! 数值列为NumPy数组（日期为 `datetime64[D]`），代码和市场列为字符串列表，
+//! 不再把数百万条记录逐条转换为字典。
+//!
+//! 解析和指标计算期间释放GIL，其他Python线程（包括在线程池中等待结果的asyncio事件循环）
+//! 可以继续运行；Python包中的 `*_async` 协程即基于此在线程池中执行。
+
+use crate::parsers::{TDXDayParser, TDXDayRecord};
+use crate::processors::calculator::{EnhancedDayRecord, IndicatorCalculator};
+use crate::processors::field::INDICATOR_NAMES;
+use chrono::NaiveDate;
+use numpy::datetime::{units, Datetime};
+use numpy::IntoPyArray;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// 把错误转换为Python异常
+fn to_py_err(error: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{:#}", error))
+}
+
+/// 日线记录转换为按列的字典
+///
+/// 键为 `date`、`symbol`、`market`、`open`、`high`、`low`、`close`、`volume`、`amount`，
+/// 可直接传给 `pandas.DataFrame`。
+pub fn records_to_columns<'py>(
+    py: Python<'py>,
+    records: &[TDXDayRecord],
+) -> PyResult<Bound<'py, PyDict>> {
+    base_columns(py, records, |record| record)
+}
+
+/// 带指标的记录转换为按列的字典，指标列名见 `INDICATOR_NAMES`
+pub fn enhanced_to_columns<'py>(
+    py: Python<'py>,
+    records: &[EnhancedDayRecord],
+) -> PyResult<Bound<'py, PyDict>> {
+    let columns = base_columns(py, records, |record| &record.base_record)?;
+    for name in INDICATOR_NAMES {
+        let values: Vec<f64> = records
+            .iter()
+            .map(|r| r.indicators.get(name).unwrap_or(f64::NAN))
+            .collect();
+        columns.set_item(name, values.into_pyarray(py))?;
+    }
+    Ok(columns)
+}
+
+/// 按 `base` 取出每行的日线记录，生成基础行情列
+fn base_columns<'py, T>(
+    py: Python<'py>,
+    rows: &[T],
+    base: impl Fn(&T) -> &TDXDayRecord,
+) -> PyResult<Bound<'py, PyDict>> {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let records: Vec<&TDXDayRecord> = rows.iter().map(base).collect();
+    let float_column = |value: fn(&TDXDayRecord) -> f64| {
+        records
+            .iter()
+            .map(|r| value(r))
+            .collect::<Vec<f64>>()
+            .into_pyarray(py)
+    };
+
+    let columns = PyDict::new(py);
+    let dates: Vec<Datetime<units::Days>> = records
+        .iter()
+        .map(|r| Datetime::from(r.date.signed_duration_since(epoch).num_days()))
+        .collect();
+    columns.set_item("date", dates.into_pyarray(py))?;
+    columns.set_item(
+        "symbol",
+        records
+            .iter()
+            .map(|r| r.symbol.as_str())
+            .collect::<Vec<_>>(),
+    )?;
+    columns.set_item(
+        "market",
+        records
+            .iter()
+            .map(|r| r.market.as_str())
+            .collect::<Vec<_>>(),
+    )?;
+    columns.set_item("open", float_column(|r| r.open))?;
+    columns.set_item("high", float_column(|r| r.high))?;
+    columns.set_item("low", float_column(|r| r.low))?;
+    columns.set_item("close", float_column(|r| r.close))?;
+    columns.set_item(
+        "volume",
+        records
+            .iter()
+            .map(|r| r.volume)
+            .collect::<Vec<u64>>()
+            .into_pyarray(py),
+    )?;
+    columns.set_item("amount", float_column(|r| r.amount))?;
+    Ok(columns)
+}
+
+/// 通达信日线解析器
+#[pyclass(name = "TDXDayParser")]
+pub struct PyTDXDayParser {
+    inner: TDXDayParser,
+}
+
+#[pymethods]
+impl PyTDXDayParser {
+    /// 创建解析器，`data_root` 为通达信数据根目录
+    #[new]
+    fn new(data_root: &str) -> Self {
+        Self {
+            inner: TDXDayParser::new(data_root),
+        }
+    }
+
+    /// 解析单个day文件，按列返回NumPy数组
+    fn parse_file_arrays<'py>(&self, py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyDict>> {
+        let records = py
+            .detach(|| self.inner.parse_file(path))
+            .map_err(to_py_err)?;
+        records_to_columns(py, &records)
+    }
+
+    /// 解析目录下的所有day文件，按列返回NumPy数组
+    fn parse_directory_arrays<'py>(
+        &self,
+        py: Python<'py>,
+        path: &str,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let records = py
+            .detach(|| self.inner.parse_directory(path))
+            .map_err(to_py_err)?;
+        records_to_columns(py, &records)
+    }
+
+    /// 解析目录并计算技术指标，按列返回NumPy数组（指标缺失值为NaN）
+    #[pyo3(signature = (path, window_sizes = None))]
+    fn parse_directory_indicators<'py>(
+        &self,
+        py: Python<'py>,
+        path: &str,
+        window_sizes: Option<Vec<usize>>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let mut calculator = IndicatorCalculator::new();
+        if let Some(window_sizes) = window_sizes {
+            calculator = calculator.with_window_sizes(window_sizes);
+        }
+        let enhanced = py
+            .detach(|| {
+                let records = self.inner.parse_directory(path)?;
+                calculator.calculate_parallel(&records)
+            })
+            .map_err(to_py_err)?;
+        enhanced_to_columns(py, &enhanced)
+    }
+}
+
+/// 解析day文件并把指定日期范围（`YYYY-MM-DD`，含端点）绘制为K线图，按扩展名输出PNG或SVG
+#[cfg(feature = "viz")]
+#[pyfunction]
+#[pyo3(signature = (path, output, start = None, end = None, width = 1200, height = 800))]
+fn render_kline(
+    py: Python<'_>,
+    path: &str,
+    output: &str,
+    start: Option<&str>,
+    end: Option<&str>,
+    width: u32,
+    height: u32,
+) -> PyResult<()> {
+    let parse_date = |text: Option<&str>| -> PyResult<Option<NaiveDate>> {
+        text.map(|text| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map_err(|e| PyRuntimeError::new_err(format!("日期格式错误: {}: {}", text, e)))
+        })
+        .transpose()
+    };
+    let range = crate::source::DateRange {
+        start: parse_date(start)?,
+        end: parse_date(end)?,
+    };
+    py.detach(|| {
+        let records = TDXDayParser::new(".").parse_file(path)?;
+        let bars: Vec<&TDXDayRecord> = records.iter().filter(|r| range.contains(r.date)).collect();
+        let title = bars.first().map(|r| r.symbol.clone()).unwrap_or_default();
+        crate::viz::KlineChart::new()
+            .with_size(width, height)
+            .with_title(&title)
+            .render(&bars, output)
+    })
+    .map_err(to_py_err)
+}
+
+/// 扩展模块入口
+#[pymodule]
+fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", crate::VERSION)?;
+    m.add_class::<PyTDXDayParser>()?;
+    #[cfg(feature = "viz")]
+    m.add_function(wrap_pyfunction!(render_kline, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,342 @@
+//! 实时K线聚合模块
+//!
+//! 消费逐笔/快照行情（来自通达信行情客户端或用户自定义数据源），
+//! 为每只股票维护正在形成的K线，在周期边界输出已完成的K线（以结束时间标记，
+//! 与 `BarBuilder` 一致）。K线在结束后保留一个迟到容忍窗口，
+//! 窗口内到达的迟到行情仍计入该K线，窗口之后到达的被丢弃并计数。
+
+use crate::net::Quote;
+use crate::parsers::TDXMinuteRecord;
+use anyhow::Result;
+use chrono::{Duration, NaiveDateTime, Timelike};
+use futures::{Stream, StreamExt};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::mpsc;
+
+/// 行情快照（成交量与成交额为本笔增量）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tick {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 行情时间
+    pub timestamp: NaiveDateTime,
+    /// 成交价
+    pub price: f64,
+    /// 成交量增量（股）
+    pub volume: u64,
+    /// 成交额增量（元）
+    pub amount: f64,
+}
+
+/// 单只股票的聚合状态
+#[derive(Debug, Default)]
+struct SymbolState {
+    /// 未输出的K线（结束时间 -> K线）
+    open_bars: BTreeMap<NaiveDateTime, TDXMinuteRecord>,
+    /// 已输出K线的最晚结束时间
+    emitted_until: Option<NaiveDateTime>,
+    /// 最新行情时间
+    watermark: Option<NaiveDateTime>,
+    /// 上一次快照的累计成交量与成交额
+    cumulative: Option<(u64, f64)>,
+}
+
+/// 实时K线聚合器
+#[derive(Debug)]
+pub struct LiveBarAggregator {
+    /// K线周期
+    interval: Duration,
+    /// 迟到容忍窗口
+    late_tolerance: Duration,
+    /// 各股票状态
+    states: HashMap<(String, String), SymbolState>,
+    /// 被丢弃的迟到行情数
+    late_ticks: u64,
+}
+
+impl Default for LiveBarAggregator {
+    fn default() -> Self {
+        Self::new(Duration::minutes(1))
+    }
+}
+
+impl LiveBarAggregator {
+    /// 创建新的聚合器
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval: interval.max(Duration::seconds(1)),
+            late_tolerance: Duration::seconds(2),
+            states: HashMap::new(),
+            late_ticks: 0,
+        }
+    }
+
+    /// 设置迟到容忍窗口
+    pub fn with_late_tolerance(mut self, tolerance: Duration) -> Self {
+        self.late_tolerance = tolerance.max(Duration::zero());
+        self
+    }
+
+    /// 被丢弃的迟到行情数
+    pub fn late_ticks(&self) -> u64 {
+        self.late_ticks
+    }
+
+    /// 处理一笔行情，返回因此完成的K线
+    pub fn push(&mut self, tick: Tick) -> Vec<TDXMinuteRecord> {
+        let end = self.bar_end(tick.timestamp);
+        let key = (tick.symbol.clone(), tick.market.clone());
+        let state = self.states.entry(key).or_default();
+
+        if state.emitted_until.is_some_and(|emitted| end <= emitted) {
+            self.late_ticks += 1;
+            debug!(
+                "丢弃迟到行情: {}.{} {}",
+                tick.symbol, tick.market, tick.timestamp
+            );
+            return Vec::new();
+        }
+
+        match state.open_bars.get_mut(&end) {
+            Some(bar) => {
+                bar.high = bar.high.max(tick.price);
+                bar.low = bar.low.min(tick.price);
+                bar.volume += tick.volume;
+                bar.amount += tick.amount;
+                // 迟到行情不改变收盘价
+                if state.watermark.is_none_or(|w| tick.timestamp >= w) {
+                    bar.close = tick.price;
+                }
+            }
+            None => {
+                state.open_bars.insert(
+                    end,
+                    TDXMinuteRecord {
+                        datetime: end,
+                        symbol: tick.symbol,
+                        open: tick.price,
+                        high: tick.price,
+                        low: tick.price,
+                        close: tick.price,
+                        volume: tick.volume,
+                        amount: tick.amount,
+                        market: tick.market,
+                    },
+                );
+            }
+        }
+
+        let watermark = state
+            .watermark
+            .map_or(tick.timestamp, |w| w.max(tick.timestamp));
+        state.watermark = Some(watermark);
+        Self::drain(state, watermark - self.late_tolerance)
+    }
+
+    /// 处理行情快照，成交量与成交额由累计值差分得到
+    ///
+    /// 快照中的成交量单位为手，按每手100股换算。
+    pub fn push_quote(&mut self, quote: &Quote, timestamp: NaiveDateTime) -> Vec<TDXMinuteRecord> {
+        let key = (quote.code.clone(), quote.market.as_str().to_string());
+        let cumulative_volume = quote.volume.max(0) as u64 * 100;
+        let state = self.states.entry(key).or_default();
+        let (volume, amount) = match state.cumulative {
+            // 累计值回退（如新交易日）时视为重新开始
+            Some((v, a)) if cumulative_volume >= v => (cumulative_volume - v, quote.amount - a),
+            _ => (cumulative_volume, quote.amount),
+        };
+        state.cumulative = Some((cumulative_volume, quote.amount));
+
+        self.push(Tick {
+            symbol: quote.code.clone(),
+            market: quote.market.as_str().to_string(),
+            timestamp,
+            price: quote.price,
+            volume,
+            amount: amount.max(0.0),
+        })
+    }
+
+    /// 按时钟推进，输出所有在 `now` 之前已结束且超过容忍窗口的K线
+    ///
+    /// 用于成交稀疏的股票：没有新行情时K线也能按时输出。
+    pub fn advance(&mut self, now: NaiveDateTime) -> Vec<TDXMinuteRecord> {
+        let cutoff = now - self.late_tolerance;
+        let mut bars: Vec<TDXMinuteRecord> = self
+            .states
+            .values_mut()
+            .flat_map(|state| Self::drain(state, cutoff))
+            .collect();
+        bars.sort_by(|a, b| a.datetime.cmp(&b.datetime).then(a.symbol.cmp(&b.symbol)));
+        bars
+    }
+
+    /// 输出全部未完成的K线
+    pub fn flush(&mut self) -> Vec<TDXMinuteRecord> {
+        self.advance(NaiveDateTime::MAX)
+    }
+
+    /// 消费行情流，将完成的K线发送到 `sender`，行情流结束时输出剩余K线
+    ///
+    /// 每个周期按本地时钟推进一次，保证无成交的股票也能及时输出。
+    pub async fn run<S>(mut self, ticks: S, sender: mpsc::Sender<TDXMinuteRecord>) -> Result<()>
+    where
+        S: Stream<Item = Tick>,
+    {
+        let period = self
+            .interval
+            .to_std()
+            .unwrap_or(std::time::Duration::from_secs(60));
+        let mut ticker = tokio::time::interval(period);
+        futures::pin_mut!(ticks);
+
+        loop {
+            let bars = tokio::select! {
+                tick = ticks.next() => match tick {
+                    Some(tick) => self.push(tick),
+                    None => break,
+                },
+                _ = ticker.tick() => self.advance(chrono::Local::now().naive_local()),
+            };
+            for bar in bars {
+                sender
+                    .send(bar)
+                    .await
+                    .map_err(|_| anyhow::anyhow!("K线接收端已关闭"))?;
+            }
+        }
+
+        for bar in self.flush() {
+            sender
+                .send(bar)
+                .await
+                .map_err(|_| anyhow::anyhow!("K线接收端已关闭"))?;
+        }
+        Ok(())
+    }
+
+    /// 行情所属K线的结束时间（自零点起按周期切分）
+    fn bar_end(&self, timestamp: NaiveDateTime) -> NaiveDateTime {
+        let seconds = self.interval.num_seconds();
+        let elapsed = timestamp.num_seconds_from_midnight() as i64;
+        let start = timestamp.date().and_time(chrono::NaiveTime::MIN)
+            + Duration::seconds(elapsed - elapsed % seconds);
+        start + self.interval
+    }
+
+    /// 输出结束时间不晚于 `cutoff` 的K线
+    fn drain(state: &mut SymbolState, cutoff: NaiveDateTime) -> Vec<TDXMinuteRecord> {
+        let pending = match cutoff.checked_add_signed(Duration::nanoseconds(1)) {
+            Some(bound) => state.open_bars.split_off(&bound),
+            None => BTreeMap::new(),
+        };
+        let ready = std::mem::replace(&mut state.open_bars, pending);
+        if let Some(&end) = ready.keys().next_back() {
+            state.emitted_until = Some(end);
+        }
+        ready.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::TdxMarket;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(hour, min, sec)
+            .unwrap()
+    }
+
+    fn tick(timestamp: NaiveDateTime, price: f64) -> Tick {
+        Tick {
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            timestamp,
+            price,
+            volume: 100,
+            amount: price * 100.0,
+        }
+    }
+
+    #[test]
+    fn test_emits_on_boundary() {
+        let mut aggregator = LiveBarAggregator::default().with_late_tolerance(Duration::zero());
+        assert!(aggregator.push(tick(at(9, 30, 5), 10.0)).is_empty());
+        assert!(aggregator.push(tick(at(9, 30, 40), 10.2)).is_empty());
+
+        let bars = aggregator.push(tick(at(9, 31, 1), 10.1));
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].datetime, at(9, 31, 0));
+        assert_eq!(bars[0].open, 10.0);
+        assert_eq!(bars[0].high, 10.2);
+        assert_eq!(bars[0].close, 10.2);
+        assert_eq!(bars[0].volume, 200);
+
+        let rest = aggregator.flush();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].datetime, at(9, 32, 0));
+    }
+
+    #[test]
+    fn test_late_tick_tolerance() {
+        let mut aggregator = LiveBarAggregator::default().with_late_tolerance(Duration::seconds(3));
+        aggregator.push(tick(at(9, 30, 50), 10.0));
+        // 新周期的行情到达，但仍在容忍窗口内
+        assert!(aggregator.push(tick(at(9, 31, 1), 10.1)).is_empty());
+        // 迟到行情计入上一根K线，但不改变收盘价
+        assert!(aggregator.push(tick(at(9, 30, 59), 9.8)).is_empty());
+
+        let bars = aggregator.push(tick(at(9, 31, 5), 10.2));
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].low, 9.8);
+        assert_eq!(bars[0].close, 10.0);
+        assert_eq!(bars[0].volume, 200);
+
+        // 超过窗口的迟到行情被丢弃
+        assert!(aggregator.push(tick(at(9, 30, 58), 9.5)).is_empty());
+        assert_eq!(aggregator.late_ticks(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_quotes_and_stream() {
+        let mut aggregator = LiveBarAggregator::default();
+        let quote = |volume: i64, amount: f64| Quote {
+            code: "600000".to_string(),
+            market: TdxMarket::SH,
+            price: 10.0,
+            last_close: 9.9,
+            open: 9.95,
+            high: 10.0,
+            low: 9.9,
+            volume,
+            current_volume: 0,
+            amount,
+            bids: Vec::new(),
+            asks: Vec::new(),
+        };
+        aggregator.push_quote(&quote(10, 10_000.0), at(9, 30, 3));
+        aggregator.push_quote(&quote(15, 15_000.0), at(9, 30, 6));
+        let bars = aggregator.flush();
+        // 首个快照的累计值全部计入
+        assert_eq!(bars[0].volume, 1500);
+        assert_eq!(bars[0].amount, 15_000.0);
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let ticks =
+            futures::stream::iter(vec![tick(at(9, 30, 1), 10.0), tick(at(9, 31, 30), 10.1)]);
+        LiveBarAggregator::default().run(ticks, tx).await.unwrap();
+        let mut received = Vec::new();
+        while let Some(bar) = rx.recv().await {
+            received.push(bar.datetime);
+        }
+        assert_eq!(received, vec![at(9, 31, 0), at(9, 32, 0)]);
+    }
+}
@@ -0,0 +1,9 @@
+//! 参考数据模块
+//!
+//! 证券主数据（名称、上市状态、ST期间、板块、行业、股本等）。
+
+pub mod security_master;
+pub mod share_capital;
+
+pub use security_master::{ListingStatus, SecurityMaster, SecurityMeta, StPeriod};
+pub use share_capital::ShareCapital;
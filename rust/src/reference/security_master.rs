@@ -0,0 +1,505 @@
+//! 证券主数据模块
+//!
+//! 保存证券名称、上市/退市日期、ST期间、板块、行业、曾用代码和股本变动，
+//! 可从CSV加载，也可由日线数据推断上市日期。按上市状态过滤记录集，
+//! 例如剔除退市后或上市前的K线。
+
+use crate::parsers::{Bar, TDXDayRecord};
+use crate::processors::limits::Board;
+use crate::reference::share_capital::{self, ShareCapital};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// ST期间（含起止日期）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StPeriod {
+    /// 开始日期
+    pub start: NaiveDate,
+    /// 结束日期，None表示仍为ST
+    pub end: Option<NaiveDate>,
+}
+
+impl StPeriod {
+    /// 日期是否在ST期间内
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.start && self.end.is_none_or(|end| date <= end)
+    }
+}
+
+/// 上市状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ListingStatus {
+    /// 尚未上市
+    NotListed,
+    /// 正常上市
+    Listed,
+    /// ST/*ST
+    SpecialTreatment,
+    /// 已退市
+    Delisted,
+}
+
+impl ListingStatus {
+    /// 是否处于上市交易期间（含ST）
+    pub fn is_trading(self) -> bool {
+        matches!(
+            self,
+            ListingStatus::Listed | ListingStatus::SpecialTreatment
+        )
+    }
+}
+
+/// 证券元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityMeta {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场（SH/SZ/BJ）
+    pub market: String,
+    /// 证券名称
+    pub name: String,
+    /// 上市日期
+    pub listing_date: Option<NaiveDate>,
+    /// 退市日期（最后交易日的次日起视为退市）
+    pub delisting_date: Option<NaiveDate>,
+    /// ST期间
+    pub st_periods: Vec<StPeriod>,
+    /// 所属板块
+    pub board: Board,
+    /// 所属行业
+    pub industry: Option<String>,
+    /// 曾用代码（代码变更前的代码）
+    pub former_symbols: Vec<String>,
+    /// 股本变动（按生效日期升序）
+    #[serde(default)]
+    pub share_capital: Vec<ShareCapital>,
+}
+
+impl SecurityMeta {
+    /// 创建证券元数据，板块按代码识别
+    pub fn new(symbol: &str, market: &str, name: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            market: market.to_uppercase(),
+            name: name.to_string(),
+            listing_date: None,
+            delisting_date: None,
+            st_periods: Vec::new(),
+            board: Board::classify(symbol, market),
+            industry: None,
+            former_symbols: Vec::new(),
+            share_capital: Vec::new(),
+        }
+    }
+
+    /// 登记股本变动，同一生效日期的记录被替换
+    pub fn add_share_capital(&mut self, capital: ShareCapital) -> &mut Self {
+        match self
+            .share_capital
+            .binary_search_by_key(&capital.date, |c| c.date)
+        {
+            Ok(index) => self.share_capital[index] = capital,
+            Err(index) => self.share_capital.insert(index, capital),
+        }
+        self
+    }
+
+    /// 指定日期生效的股本，早于首次登记的日期沿用首条记录（通达信快照只有当前股本）
+    pub fn shares_on(&self, date: NaiveDate) -> Option<&ShareCapital> {
+        let index = self.share_capital.partition_point(|c| c.date <= date);
+        self.share_capital.get(index.saturating_sub(1))
+    }
+
+    /// 指定日期的上市状态
+    pub fn status(&self, date: NaiveDate) -> ListingStatus {
+        if self.listing_date.is_some_and(|listing| date < listing) {
+            ListingStatus::NotListed
+        } else if self
+            .delisting_date
+            .is_some_and(|delisting| date >= delisting)
+        {
+            ListingStatus::Delisted
+        } else if self.st_periods.iter().any(|period| period.contains(date)) {
+            ListingStatus::SpecialTreatment
+        } else {
+            ListingStatus::Listed
+        }
+    }
+}
+
+/// CSV行
+#[derive(Debug, Deserialize)]
+struct SecurityRow {
+    symbol: String,
+    market: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    listing_date: Option<String>,
+    #[serde(default)]
+    delisting_date: Option<String>,
+    #[serde(default)]
+    st_periods: Option<String>,
+    #[serde(default)]
+    industry: Option<String>,
+    #[serde(default)]
+    former_symbols: Option<String>,
+}
+
+/// 证券主数据
+#[derive(Debug, Clone, Default)]
+pub struct SecurityMaster {
+    /// （股票代码, 市场）到元数据
+    securities: HashMap<(String, String), SecurityMeta>,
+    /// 曾用代码到（当前代码, 市场）
+    former_symbols: HashMap<(String, String), String>,
+}
+
+impl SecurityMaster {
+    /// 创建空的证券主数据
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加或替换证券
+    pub fn insert(&mut self, meta: SecurityMeta) -> &mut Self {
+        for former in &meta.former_symbols {
+            self.former_symbols
+                .insert((former.clone(), meta.market.clone()), meta.symbol.clone());
+        }
+        self.securities
+            .insert((meta.symbol.clone(), meta.market.clone()), meta);
+        self
+    }
+
+    /// 从CSV文件加载
+    ///
+    /// 表头：`symbol,market,name,listing_date,delisting_date,st_periods,industry,former_symbols`，
+    /// 除代码和市场外均可为空。日期为 `YYYY-MM-DD` 或 `YYYYMMDD`；
+    /// ST期间以 `;` 分隔，每段为 `YYYYMMDD-YYYYMMDD` 或 `YYYYMMDD-`（仍为ST），
+    /// 例如 `20200501-20210430;20230501-`；曾用代码以 `;` 分隔。
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = csv::Reader::from_path(path)
+            .with_context(|| format!("无法打开证券主数据文件: {}", path.display()))?;
+
+        let mut master = Self::new();
+        for (line, row) in reader.deserialize::<SecurityRow>().enumerate() {
+            let row = row.with_context(|| format!("证券主数据文件第{}行解析失败", line + 2))?;
+            let meta = Self::parse_row(row)
+                .with_context(|| format!("证券主数据文件第{}行格式错误", line + 2))?;
+            master.insert(meta);
+        }
+
+        Ok(master)
+    }
+
+    /// 从CSV加载股本变动，未登记的证券以空名称新建
+    ///
+    /// 表头：`symbol,market,date,total_shares,float_shares`，股本单位为股，
+    /// 同一股票可有多行，每行自 `date` 起生效。
+    pub fn load_share_capital_csv<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        let capitals = share_capital::read_csv(path.as_ref())?;
+        Ok(self.extend_share_capital(capitals))
+    }
+
+    /// 从通达信 `T0002/hq_cache/base.dbf` 加载当前股本，未登记的证券以空名称新建
+    pub fn load_tdx_base<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        let capitals = share_capital::read_tdx_base(path.as_ref())?;
+        Ok(self.extend_share_capital(capitals))
+    }
+
+    fn extend_share_capital(&mut self, capitals: Vec<(String, String, ShareCapital)>) -> &mut Self {
+        for (symbol, market, capital) in capitals {
+            let symbol = self.resolve(&symbol, &market).to_string();
+            self.securities
+                .entry((symbol.clone(), market.clone()))
+                .or_insert_with(|| SecurityMeta::new(&symbol, &market, ""))
+                .add_share_capital(capital);
+        }
+        self
+    }
+
+    /// 由日线数据推断上市日期（每只股票的首个交易日）
+    ///
+    /// 适用于只有本地通达信数据、没有主数据文件的场景；名称为空，不推断退市和ST。
+    pub fn from_records(records: &[TDXDayRecord]) -> Self {
+        let mut first_dates: HashMap<(&str, &str), NaiveDate> = HashMap::new();
+        for record in records {
+            first_dates
+                .entry((&record.symbol, &record.market))
+                .and_modify(|date| *date = (*date).min(record.date))
+                .or_insert(record.date);
+        }
+
+        let mut master = Self::new();
+        for ((symbol, market), date) in first_dates {
+            let mut meta = SecurityMeta::new(symbol, market, "");
+            meta.listing_date = Some(date);
+            master.insert(meta);
+        }
+        master
+    }
+
+    fn parse_row(row: SecurityRow) -> Result<SecurityMeta> {
+        let optional = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+        let split = |value: Option<String>| -> Vec<String> {
+            optional(value)
+                .map(|v| {
+                    v.split(';')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut meta = SecurityMeta::new(row.symbol.trim(), row.market.trim(), row.name.trim());
+        meta.listing_date = optional(row.listing_date)
+            .map(|v| parse_date(&v))
+            .transpose()?;
+        meta.delisting_date = optional(row.delisting_date)
+            .map(|v| parse_date(&v))
+            .transpose()?;
+        meta.st_periods = split(row.st_periods)
+            .iter()
+            .map(|period| {
+                let (start, end) = period
+                    .split_once('-')
+                    .ok_or_else(|| anyhow::anyhow!("无效的ST期间: {}", period))?;
+                Ok(StPeriod {
+                    start: parse_date(start)?,
+                    end: (!end.trim().is_empty())
+                        .then(|| parse_date(end))
+                        .transpose()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        meta.industry = optional(row.industry).map(|v| v.trim().to_string());
+        meta.former_symbols = split(row.former_symbols);
+        Ok(meta)
+    }
+
+    /// 证券数量
+    pub fn len(&self) -> usize {
+        self.securities.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.securities.is_empty()
+    }
+
+    /// 查询证券元数据，曾用代码解析为当前代码
+    pub fn get(&self, symbol: &str, market: &str) -> Option<&SecurityMeta> {
+        let market = market.to_uppercase();
+        let symbol = self.resolve(symbol, &market);
+        self.securities.get(&(symbol.to_string(), market))
+    }
+
+    /// 将曾用代码解析为当前代码，未知代码原样返回
+    pub fn resolve<'a>(&'a self, symbol: &'a str, market: &str) -> &'a str {
+        self.former_symbols
+            .get(&(symbol.to_string(), market.to_uppercase()))
+            .map(String::as_str)
+            .unwrap_or(symbol)
+    }
+
+    /// 遍历所有证券
+    pub fn iter(&self) -> impl Iterator<Item = &SecurityMeta> {
+        self.securities.values()
+    }
+
+    /// 指定日期的上市状态，未登记的证券视为正常上市
+    pub fn status(&self, symbol: &str, market: &str, date: NaiveDate) -> ListingStatus {
+        self.get(symbol, market)
+            .map(|meta| meta.status(date))
+            .unwrap_or(ListingStatus::Listed)
+    }
+
+    /// 指定日期生效的股本，未登记股本时为None
+    pub fn shares_on(&self, symbol: &str, market: &str, date: NaiveDate) -> Option<&ShareCapital> {
+        self.get(symbol, market)?.shares_on(date)
+    }
+
+    /// 指定日期是否为ST
+    pub fn is_st(&self, symbol: &str, market: &str, date: NaiveDate) -> bool {
+        self.status(symbol, market, date) == ListingStatus::SpecialTreatment
+    }
+
+    /// 指定日期处于ST状态的（代码, 市场）（可用于 `LimitDetector::with_st_symbols`）
+    pub fn st_symbols_on(&self, date: NaiveDate) -> HashSet<(String, String)> {
+        self.iter()
+            .filter(|meta| meta.status(date) == ListingStatus::SpecialTreatment)
+            .map(|meta| (meta.symbol.clone(), meta.market.clone()))
+            .collect()
+    }
+
+    /// 按（代码, 市场）的上市日期（可用于 `LimitDetector::with_listing_dates`）
+    pub fn listing_dates(&self) -> HashMap<(String, String), NaiveDate> {
+        self.iter()
+            .filter_map(|meta| {
+                Some((
+                    (meta.symbol.clone(), meta.market.clone()),
+                    meta.listing_date?,
+                ))
+            })
+            .collect()
+    }
+
+    /// 只保留上市状态在 `allowed` 中的记录
+    pub fn filter_by_status<B: Bar>(&self, records: Vec<B>, allowed: &[ListingStatus]) -> Vec<B> {
+        records
+            .into_iter()
+            .filter(|record| {
+                allowed.contains(&self.status(record.symbol(), record.market(), record.date()))
+            })
+            .collect()
+    }
+
+    /// 剔除上市前和退市后的记录（保留ST期间）
+    pub fn filter_listed<B: Bar>(&self, records: Vec<B>) -> Vec<B> {
+        self.filter_by_status(
+            records,
+            &[ListingStatus::Listed, ListingStatus::SpecialTreatment],
+        )
+    }
+}
+
+/// 解析 `YYYY-MM-DD` 或 `YYYYMMDD` 日期
+pub(super) fn parse_date(value: &str) -> Result<NaiveDate> {
+    let value = value.trim();
+    let format = if value.contains('-') {
+        "%Y-%m-%d"
+    } else {
+        "%Y%m%d"
+    };
+    NaiveDate::parse_from_str(value, format).with_context(|| format!("无效的日期: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn create_test_record(symbol: &str, day: NaiveDate) -> TDXDayRecord {
+        TDXDayRecord {
+            date: day,
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            volume: 1000,
+            amount: 10_000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn create_master() -> SecurityMaster {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("securities.csv");
+        std::fs::write(
+            &path,
+            "symbol,market,name,listing_date,delisting_date,st_periods,industry,former_symbols\n\
+             600001,SH,邯郸钢铁,1998-01-22,20091229,,钢铁,\n\
+             600002,SH,*ST示例,2010-01-04,,20200501-20210430;20230501-,,600999\n",
+        )
+        .unwrap();
+        SecurityMaster::from_csv(&path).unwrap()
+    }
+
+    #[test]
+    fn test_load_csv_and_status() {
+        let master = create_master();
+        assert_eq!(master.len(), 2);
+
+        let meta = master.get("600001", "sh").unwrap();
+        assert_eq!(meta.industry.as_deref(), Some("钢铁"));
+        assert_eq!(meta.board, Board::Main);
+        assert_eq!(meta.status(date(1998, 1, 21)), ListingStatus::NotListed);
+        assert_eq!(meta.status(date(2009, 12, 28)), ListingStatus::Listed);
+        assert_eq!(meta.status(date(2009, 12, 29)), ListingStatus::Delisted);
+
+        assert!(master.is_st("600002", "SH", date(2021, 4, 30)));
+        assert!(!master.is_st("600002", "SH", date(2021, 5, 1)));
+        assert!(master.is_st("600002", "SH", date(2024, 1, 2)));
+        assert_eq!(master.st_symbols_on(date(2024, 1, 2)).len(), 1);
+
+        // 曾用代码解析为当前代码
+        assert_eq!(master.get("600999", "SH").unwrap().symbol, "600002");
+        // 未登记的证券视为正常上市
+        assert_eq!(
+            master.status("600000", "SH", date(2024, 1, 2)),
+            ListingStatus::Listed
+        );
+    }
+
+    #[test]
+    fn test_filter_listed() {
+        let master = create_master();
+        let records = vec![
+            create_test_record("600001", date(2009, 12, 28)),
+            create_test_record("600001", date(2009, 12, 30)),
+            create_test_record("600002", date(2009, 12, 30)),
+            create_test_record("600002", date(2020, 6, 1)),
+        ];
+
+        let listed = master.filter_listed(records.clone());
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1].date, date(2020, 6, 1));
+
+        let normal = master.filter_by_status(records, &[ListingStatus::Listed]);
+        assert_eq!(normal.len(), 1);
+    }
+
+    #[test]
+    fn test_share_capital() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("shares.csv");
+        std::fs::write(
+            &path,
+            "symbol,market,date,total_shares,float_shares\n\
+             600002,SH,2024-01-02,2000000,1000000\n\
+             600999,SH,20240601,3000000,1500000\n\
+             600003,SH,2024-01-02,500000,400000\n",
+        )
+        .unwrap();
+
+        let mut master = create_master();
+        master.load_share_capital_csv(&path).unwrap();
+        // 曾用代码的股本登记到当前代码，未登记的证券新建
+        assert_eq!(master.get("600002", "SH").unwrap().share_capital.len(), 2);
+        assert_eq!(master.len(), 3);
+
+        let shares = |day| master.shares_on("600002", "SH", day).unwrap().float_shares;
+        assert_eq!(shares(date(2023, 6, 1)), 1_000_000.0);
+        assert_eq!(shares(date(2024, 5, 31)), 1_000_000.0);
+        assert_eq!(shares(date(2024, 6, 1)), 1_500_000.0);
+        assert!(master.shares_on("600001", "SH", date(2024, 6, 1)).is_none());
+        assert!(master
+            .load_share_capital_csv(temp_dir.path().join("missing.csv"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_infer_from_records() {
+        let records = vec![
+            create_test_record("600000", date(2024, 1, 3)),
+            create_test_record("600000", date(2024, 1, 2)),
+        ];
+        let master = SecurityMaster::from_records(&records);
+        let key = ("600000".to_string(), "SH".to_string());
+        assert_eq!(master.listing_dates()[&key], date(2024, 1, 2));
+        assert!(SecurityMeta::new("600000", "SH", "").st_periods.is_empty());
+        assert!(parse_date("2024-13-01").is_err());
+    }
+}
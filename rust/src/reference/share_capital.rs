@@ -0,0 +1,290 @@
+//! 股本数据模块
+//!
+//! 记录每只股票的总股本和流通股本变动，可从CSV（股本变动历史）或通达信
+//! `T0002/hq_cache/base.dbf`（当前股本快照，单位万股）加载。
+//! 换手率和流通市值按K线日期取当时生效的股本计算。
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 股本（自生效日期起）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ShareCapital {
+    /// 生效日期
+    pub date: NaiveDate,
+    /// 总股本（股）
+    pub total_shares: f64,
+    /// 流通股本（股）
+    pub float_shares: f64,
+}
+
+impl ShareCapital {
+    /// 换手率（%），流通股本无效时为None
+    pub fn turnover_rate(&self, volume: f64) -> Option<f64> {
+        (self.float_shares > 0.0).then(|| volume / self.float_shares * 100.0)
+    }
+
+    /// 流通市值（元）
+    pub fn float_market_cap(&self, close: f64) -> f64 {
+        close * self.float_shares
+    }
+
+    /// 总市值（元）
+    pub fn total_market_cap(&self, close: f64) -> f64 {
+        close * self.total_shares
+    }
+}
+
+/// 股本变动CSV行
+#[derive(Debug, Deserialize)]
+struct ShareCapitalRow {
+    symbol: String,
+    market: String,
+    date: String,
+    total_shares: f64,
+    float_shares: f64,
+}
+
+/// 从CSV读取股本变动，返回（股票代码, 市场, 股本）
+///
+/// 表头：`symbol,market,date,total_shares,float_shares`，股本单位为股，
+/// 同一股票可有多行，每行自 `date` 起生效。
+pub(crate) fn read_csv(path: &Path) -> Result<Vec<(String, String, ShareCapital)>> {
+    let mut reader = csv::Reader::from_path(path)
+        .with_context(|| format!("无法打开股本文件: {}", path.display()))?;
+
+    reader
+        .deserialize::<ShareCapitalRow>()
+        .enumerate()
+        .map(|(line, row)| {
+            let row = row.with_context(|| format!("股本文件第{}行解析失败", line + 2))?;
+            let date = super::security_master::parse_date(&row.date)
+                .with_context(|| format!("股本文件第{}行格式错误", line + 2))?;
+            Ok((
+                row.symbol.trim().to_string(),
+                row.market.trim().to_uppercase(),
+                ShareCapital {
+                    date,
+                    total_shares: row.total_shares,
+                    float_shares: row.float_shares,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// 读取通达信 `base.dbf`，返回（股票代码, 市场, 股本）
+///
+/// 取 `SC`（市场，0深圳/1上海/2北京）、`GPDM`（代码）、`GXRQ`（更新日期）、
+/// `ZGB`（总股本，万股）和 `LTAG`（流通A股，万股）字段，跳过已删除和股本为空的行。
+pub(crate) fn read_tdx_base(path: &Path) -> Result<Vec<(String, String, ShareCapital)>> {
+    let data =
+        std::fs::read(path).with_context(|| format!("无法读取股本文件: {}", path.display()))?;
+    let table =
+        DbfTable::parse(&data).with_context(|| format!("无效的DBF文件: {}", path.display()))?;
+
+    let mut capitals = Vec::new();
+    for record in table.records() {
+        let market = match record.get("SC")? {
+            "0" => "SZ",
+            "1" => "SH",
+            "2" => "BJ",
+            _ => continue,
+        };
+        let symbol = record.get("GPDM")?;
+        let total: f64 = record.get("ZGB")?.parse().unwrap_or(0.0);
+        let float: f64 = record.get("LTAG")?.parse().unwrap_or(0.0);
+        let Ok(date) = NaiveDate::parse_from_str(record.get("GXRQ")?, "%Y%m%d") else {
+            continue;
+        };
+        if symbol.is_empty() || total <= 0.0 {
+            continue;
+        }
+        capitals.push((
+            symbol.to_string(),
+            market.to_string(),
+            ShareCapital {
+                date,
+                total_shares: total * 10_000.0,
+                float_shares: float * 10_000.0,
+            },
+        ));
+    }
+    Ok(capitals)
+}
+
+/// dBase III表（只读取字符和数值字段的文本）
+struct DbfTable<'a> {
+    /// 字段（名称, 记录内偏移, 长度）
+    fields: Vec<(String, usize, usize)>,
+    /// 记录区
+    records: &'a [u8],
+    /// 记录数
+    count: usize,
+    /// 单条记录长度（含删除标记）
+    record_len: usize,
+}
+
+impl<'a> DbfTable<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        if data.len() < 32 {
+            anyhow::bail!("文件头不完整");
+        }
+        let count = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let header_len = u16::from_le_bytes([data[8], data[9]]) as usize;
+        let record_len = u16::from_le_bytes([data[10], data[11]]) as usize;
+        if header_len < 32 || record_len == 0 {
+            anyhow::bail!("文件头不完整");
+        }
+
+        // 字段描述每项32字节，以0x0D结束；记录首字节为删除标记
+        let mut fields = Vec::new();
+        let mut offset = 1;
+        for descriptor in data[32..header_len.min(data.len())].chunks_exact(32) {
+            if descriptor[0] == 0x0D {
+                break;
+            }
+            let name_len = descriptor[..11].iter().position(|&b| b == 0).unwrap_or(11);
+            let name = String::from_utf8_lossy(&descriptor[..name_len]).to_string();
+            let len = descriptor[16] as usize;
+            fields.push((name, offset, len));
+            offset += len;
+        }
+        if offset > record_len || data.len() < header_len + count * record_len {
+            anyhow::bail!("记录长度与字段定义不符");
+        }
+
+        Ok(Self {
+            fields,
+            records: &data[header_len..header_len + count * record_len],
+            count,
+            record_len,
+        })
+    }
+
+    /// 未删除的记录
+    fn records(&self) -> impl Iterator<Item = DbfRecord<'_>> {
+        self.records
+            .chunks_exact(self.record_len)
+            .take(self.count)
+            .filter(|bytes| bytes[0] != b'*')
+            .map(move |bytes| DbfRecord {
+                fields: &self.fields,
+                bytes,
+            })
+    }
+}
+
+/// dBase III记录
+struct DbfRecord<'a> {
+    fields: &'a [(String, usize, usize)],
+    bytes: &'a [u8],
+}
+
+impl DbfRecord<'_> {
+    /// 字段文本（去除首尾空白）
+    fn get(&self, name: &str) -> Result<&str> {
+        let &(_, offset, len) = self
+            .fields
+            .iter()
+            .find(|(field, _, _)| field == name)
+            .ok_or_else(|| anyhow::anyhow!("DBF缺少字段: {}", name))?;
+        Ok(std::str::from_utf8(&self.bytes[offset..offset + len])
+            .unwrap_or("")
+            .trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造只含 `SC,GPDM,GXRQ,ZGB,LTAG` 字段的base.dbf
+    fn create_tdx_base(rows: &[(&str, &str, &str, &str, &str)]) -> Vec<u8> {
+        let fields: [(&str, u8, u8); 5] = [
+            ("SC", b'C', 1),
+            ("GPDM", b'C', 6),
+            ("GXRQ", b'N', 8),
+            ("ZGB", b'N', 12),
+            ("LTAG", b'N', 12),
+        ];
+        let record_len: usize = 1 + fields.iter().map(|f| f.2 as usize).sum::<usize>();
+        let header_len = 32 + 32 * fields.len() + 1;
+
+        let mut data = vec![0u8; 32];
+        data[0] = 0x03;
+        data[4..8].copy_from_slice(&(rows.len() as u32).to_le_bytes());
+        data[8..10].copy_from_slice(&(header_len as u16).to_le_bytes());
+        data[10..12].copy_from_slice(&(record_len as u16).to_le_bytes());
+        for (name, kind, len) in fields {
+            let mut descriptor = [0u8; 32];
+            descriptor[..name.len()].copy_from_slice(name.as_bytes());
+            descriptor[11] = kind;
+            descriptor[16] = len;
+            data.extend_from_slice(&descriptor);
+        }
+        data.push(0x0D);
+        for row in rows {
+            data.push(b' ');
+            let values = [row.0, row.1, row.2, row.3, row.4];
+            for ((_, _, len), value) in fields.iter().zip(values) {
+                data.extend_from_slice(
+                    format!("{:>width$}", value, width = *len as usize).as_bytes(),
+                );
+            }
+        }
+        data.push(0x1A);
+        data
+    }
+
+    #[test]
+    fn test_read_tdx_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("base.dbf");
+        std::fs::write(
+            &path,
+            create_tdx_base(&[
+                ("1", "600000", "20240105", "2935208.04", "2935208.04"),
+                ("0", "000001", "20240105", "1940591.82", "1940575.50"),
+                ("0", "399001", "20240105", "0", "0"),
+            ]),
+        )
+        .unwrap();
+
+        let capitals = read_tdx_base(&path).unwrap();
+        assert_eq!(capitals.len(), 2);
+        assert_eq!(
+            (capitals[0].0.as_str(), capitals[0].1.as_str()),
+            ("600000", "SH")
+        );
+        assert_eq!(capitals[1].1, "SZ");
+        assert!((capitals[1].2.float_shares - 19_405_755_000.0).abs() < 1.0);
+        assert_eq!(
+            capitals[0].2.date,
+            NaiveDate::from_ymd_opt(2024, 1, 5).unwrap()
+        );
+
+        assert!(read_tdx_base(&dir.path().join("missing.dbf")).is_err());
+        std::fs::write(&path, b"short").unwrap();
+        assert!(read_tdx_base(&path).is_err());
+    }
+
+    #[test]
+    fn test_derived_values() {
+        let capital = ShareCapital {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            total_shares: 2_000_000.0,
+            float_shares: 1_000_000.0,
+        };
+        assert_eq!(capital.turnover_rate(50_000.0), Some(5.0));
+        assert_eq!(capital.float_market_cap(10.0), 10_000_000.0);
+        assert_eq!(capital.total_market_cap(10.0), 20_000_000.0);
+        let empty = ShareCapital {
+            float_shares: 0.0,
+            ..capital
+        };
+        assert_eq!(empty.turnover_rate(50_000.0), None);
+    }
+}
@@ -0,0 +1,413 @@
+//! HTML报告
+//!
+//! 把回测报告（`BacktestReport`）和数据清洗结果（`CleaningResult`）渲染为单个自包含的
+//! HTML文件：图表为内联SVG，不引用任何外部脚本或样式，可直接发送或归档。
+//!
+//! - 回测：绩效指标表、净值曲线、回撤曲线、月度收益热力表、平仓盈亏分布
+//! - 数据质量：记录数与各类问题统计、各清洗规则移除/修改的记录数
+//!
+//! 颜色按A股习惯：红色为上涨/盈利，绿色为下跌/亏损。
+
+pub mod svg;
+
+use crate::backtest::bootstrap::realized_pnl;
+use crate::backtest::BacktestReport;
+use crate::processors::cleaner::CleaningResult;
+use crate::processors::performance::DateSeries;
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use svg::escape;
+
+/// 回撤序列（相对前高的跌幅，取值≤0）
+pub fn drawdown_series(equity: &[(NaiveDate, f64)]) -> DateSeries {
+    let mut peak = f64::NEG_INFINITY;
+    equity
+        .iter()
+        .map(|&(date, value)| {
+            peak = peak.max(value);
+            (date, if peak > 0.0 { value / peak - 1.0 } else { 0.0 })
+        })
+        .collect()
+}
+
+/// 月度收益率（(年, 月) -> 收益率），以上月末净值为基准，首月以首日净值为基准
+pub fn monthly_returns(equity: &[(NaiveDate, f64)]) -> BTreeMap<(i32, u32), f64> {
+    let mut month_end: BTreeMap<(i32, u32), f64> = BTreeMap::new();
+    for &(date, value) in equity {
+        month_end.insert((date.year(), date.month()), value);
+    }
+    let mut base = equity.first().map_or(0.0, |(_, value)| *value);
+    month_end
+        .into_iter()
+        .map(|(month, value)| {
+            let ret = if base > 0.0 {
+                value / base - 1.0
+            } else {
+                f64::NAN
+            };
+            base = value;
+            (month, ret)
+        })
+        .collect()
+}
+
+/// 平仓盈亏分布：把盈亏等分为 `bins` 个区间，返回（区间下限, 笔数）
+pub fn pnl_histogram(pnl: &[f64], bins: usize) -> Vec<(f64, usize)> {
+    let values: Vec<f64> = pnl.iter().copied().filter(|v| v.is_finite()).collect();
+    let (Some(min), Some(max)) = (
+        values.iter().copied().reduce(f64::min),
+        values.iter().copied().reduce(f64::max),
+    ) else {
+        return Vec::new();
+    };
+    let bins = bins.max(1);
+    if min == max {
+        return vec![(min, values.len())];
+    }
+    let width = (max - min) / bins as f64;
+    let mut counts = vec![0; bins];
+    for value in values {
+        let bin = (((value - min) / width) as usize).min(bins - 1);
+        counts[bin] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (min + width * i as f64, count))
+        .collect()
+}
+
+fn percent(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:.2}%", value * 100.0)
+    } else {
+        "-".to_string()
+    }
+}
+
+fn ratio(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{:.2}", v))
+}
+
+/// 热力表单元格背景色：盈利为红、亏损为绿，绝对值10%时颜色最深
+fn heat_color(value: f64) -> String {
+    if !value.is_finite() {
+        return "#f5f5f5".to_string();
+    }
+    let strength = (value.abs() / 0.1).min(1.0);
+    let fade = (255.0 - 150.0 * strength) as u8;
+    if value >= 0.0 {
+        format!("#ff{:02x}{:02x}", fade, fade)
+    } else {
+        format!("#{:02x}ff{:02x}", fade, fade)
+    }
+}
+
+fn table(rows: &[(&str, String)]) -> String {
+    let mut html = String::from("<table class=\"kv\">");
+    for (name, value) in rows {
+        let _ = write!(
+            html,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            escape(name),
+            escape(value)
+        );
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// HTML报告
+#[derive(Debug, Clone)]
+pub struct HtmlReport {
+    /// 标题
+    title: String,
+    /// 各章节（标题, HTML片段）
+    sections: Vec<(String, String)>,
+}
+
+impl HtmlReport {
+    /// 创建空报告
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// 添加回测章节
+    pub fn with_backtest(mut self, name: &str, report: &BacktestReport) -> Self {
+        let mut html = String::new();
+
+        let fees: f64 = report.fills.iter().map(|fill| fill.fees.total()).sum();
+        let mut rows = vec![
+            (
+                "区间",
+                format!("{} ~ {}", report.start_date, report.end_date),
+            ),
+            ("初始资金", format!("{:.2}", report.initial_cash)),
+            ("期末权益", format!("{:.2}", report.final_equity)),
+            ("累计收益率", percent(report.total_return())),
+        ];
+        if let Some(metrics) = &report.metrics {
+            rows.extend([
+                ("年化收益率", percent(metrics.annualized_return)),
+                ("年化波动率", percent(metrics.annualized_volatility)),
+                ("夏普比率", ratio(metrics.sharpe_ratio)),
+                ("索提诺比率", ratio(metrics.sortino_ratio)),
+                (
+                    "最大回撤",
+                    metrics
+                        .max_drawdown
+                        .as_ref()
+                        .map_or_else(|| "-".to_string(), |d| percent(d.max_drawdown)),
+                ),
+                ("卡玛比率", ratio(metrics.calmar_ratio)),
+            ]);
+        }
+        rows.extend([
+            ("成交笔数", report.fills.len().to_string()),
+            ("交易费用", format!("{:.2}", fees)),
+            ("T+1限制事件", report.events.len().to_string()),
+        ]);
+        html.push_str(&table(&rows));
+
+        html.push_str("<h3>净值曲线</h3>");
+        html.push_str(&svg::line_chart(&report.equity, "#1f77b4", false, &|v| {
+            format!("{:.0}", v)
+        }));
+        html.push_str("<h3>回撤</h3>");
+        html.push_str(&svg::line_chart(
+            &drawdown_series(&report.equity),
+            "#2ca02c",
+            true,
+            &percent,
+        ));
+
+        html.push_str("<h3>月度收益</h3><table class=\"heat\"><tr><th>年份</th>");
+        for month in 1..=12 {
+            let _ = write!(html, "<th>{}月</th>", month);
+        }
+        html.push_str("<th>全年</th></tr>");
+        let monthly = monthly_returns(&report.equity);
+        let mut years: BTreeMap<i32, Vec<(u32, f64)>> = BTreeMap::new();
+        for (&(year, month), &ret) in &monthly {
+            years.entry(year).or_default().push((month, ret));
+        }
+        for (year, months) in &years {
+            let _ = write!(html, "<tr><th>{}</th>", year);
+            for month in 1..=12 {
+                match months.iter().find(|(m, _)| *m == month) {
+                    Some(&(_, ret)) => {
+                        let _ = write!(
+                            html,
+                            "<td style=\"background:{}\">{}</td>",
+                            heat_color(ret),
+                            percent(ret)
+                        );
+                    }
+                    None => html.push_str("<td></td>"),
+                }
+            }
+            let annual = months.iter().map(|(_, r)| 1.0 + r).product::<f64>() - 1.0;
+            let _ = write!(
+                html,
+                "<td style=\"background:{}\"><b>{}</b></td></tr>",
+                heat_color(annual),
+                percent(annual)
+            );
+        }
+        html.push_str("</table>");
+
+        let pnl = realized_pnl(&report.fills);
+        let _ = write!(html, "<h3>平仓盈亏分布（{}笔）</h3>", pnl.len());
+        let histogram: Vec<(String, f64)> = pnl_histogram(&pnl, 20)
+            .into_iter()
+            .map(|(lower, count)| (format!("{:.0}", lower), count as f64))
+            .collect();
+        html.push_str(&svg::bar_chart(&histogram, &|v| format!("{:.0}", v)));
+
+        self.sections.push((name.to_string(), html));
+        self
+    }
+
+    /// 添加数据质量章节
+    pub fn with_cleaning(mut self, name: &str, result: &CleaningResult) -> Self {
+        let stats = &result.statistics;
+        let mut html = table(&[
+            ("原始记录数", result.original_count.to_string()),
+            ("清洗后记录数", result.cleaned_count.to_string()),
+            ("移除记录数", result.removed_count.to_string()),
+            ("重复记录", stats.duplicates_removed.to_string()),
+            ("价格不一致", stats.price_inconsistencies.to_string()),
+            ("范围异常", stats.range_violations.to_string()),
+            ("移除的异常值", stats.outliers_removed.to_string()),
+            ("标记的异常值", stats.outliers_flagged.to_string()),
+            ("替换的异常值", stats.outliers_replaced.to_string()),
+            ("填充的缺失值", stats.missing_values_filled.to_string()),
+            ("因缺失移除", stats.missing_values_dropped.to_string()),
+            ("表达式筛选移除", stats.filtered_by_expr.to_string()),
+        ]);
+
+        if !result.rule_impacts.is_empty() {
+            html.push_str("<h3>各规则影响（移除+修改）</h3>");
+            let bars: Vec<(String, f64)> = result
+                .rule_impacts
+                .iter()
+                .map(|impact| {
+                    (
+                        impact.rule.clone(),
+                        (impact.removed + impact.modified) as f64,
+                    )
+                })
+                .collect();
+            html.push_str(&svg::bar_chart(&bars, &|v| format!("{:.0}", v)));
+        }
+
+        self.sections.push((name.to_string(), html));
+        self
+    }
+
+    /// 渲染为HTML文本
+    pub fn render(&self) -> String {
+        let title = escape(&self.title);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html lang=\"zh-CN\"><head><meta charset=\"utf-8\"><title>{}</title>\
+             <style>body{{font-family:sans-serif;max-width:900px;margin:24px auto;color:#222}}\
+             table{{border-collapse:collapse;margin:8px 0}}th,td{{border:1px solid #ddd;padding:4px 8px}}\
+             table.kv th{{text-align:left;background:#fafafa}}table.heat td{{text-align:right;font-size:12px}}\
+             </style></head><body><h1>{}</h1>",
+            title, title
+        );
+        for (name, section) in &self.sections {
+            let _ = write!(
+                html,
+                "<section><h2>{}</h2>{}</section>",
+                escape(name),
+                section
+            );
+        }
+        html.push_str("</body></html>\n");
+        html
+    }
+
+    /// 写入HTML文件
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.render())
+            .with_context(|| format!("无法写入报告: {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{Backtester, TargetWeights};
+    use crate::parsers::TDXDayRecord;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_drawdown_and_monthly_returns() {
+        let equity = vec![
+            (date(1, 2), 100.0),
+            (date(1, 31), 110.0),
+            (date(2, 1), 99.0),
+            (date(2, 29), 121.0),
+        ];
+        let drawdown = drawdown_series(&equity);
+        assert_eq!(drawdown[1].1, 0.0);
+        assert!((drawdown[2].1 + 0.1).abs() < 1e-12);
+        assert_eq!(drawdown[3].1, 0.0);
+
+        let monthly = monthly_returns(&equity);
+        assert!((monthly[&(2024, 1)] - 0.1).abs() < 1e-12);
+        assert!((monthly[&(2024, 2)] - 0.1).abs() < 1e-12);
+
+        let histogram = pnl_histogram(&[-10.0, -5.0, 0.0, 10.0], 2);
+        assert_eq!(histogram, vec![(-10.0, 2), (0.0, 2)]);
+        assert!(pnl_histogram(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_render_backtest() {
+        let bars: Vec<TDXDayRecord> = (1..=40)
+            .map(|i| {
+                let price = 10.0 + (i as f64 * 0.7).sin();
+                TDXDayRecord {
+                    date: date(1, 1) + chrono::Duration::days(i),
+                    symbol: "A".to_string(),
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume: 100_000,
+                    amount: price * 100_000.0,
+                    market: "SZ".to_string(),
+                }
+            })
+            .collect();
+        // 隔天满仓、空仓交替
+        let targets = bars
+            .iter()
+            .enumerate()
+            .map(|(i, bar)| {
+                let weight = if i % 4 < 2 { 1.0 } else { 0.0 };
+                (
+                    bar.date,
+                    TargetWeights::from([(("A".to_string(), "SZ".to_string()), weight)]),
+                )
+            })
+            .collect();
+        let report = Backtester::new(100_000.0).run(&bars, &targets).unwrap();
+
+        let html = HtmlReport::new("均线<策略>")
+            .with_backtest("回测", &report)
+            .render();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>均线&lt;策略&gt;</title>"));
+        assert!(html.contains("<h3>月度收益</h3>"));
+        assert!(html.contains("<th>2月</th>"));
+        assert!(html.contains("<polyline"));
+        assert!(html.contains("<rect"));
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn test_write_cleaning_report() {
+        use crate::processors::cleaner::{CleaningRule, DataCleaner, KeepPolicy};
+
+        let mut cleaner = DataCleaner::new();
+        cleaner.add_rule(CleaningRule::RemoveDuplicates {
+            keys: vec!["symbol".to_string(), "date".to_string()],
+            keep: KeepPolicy::First,
+        });
+        let bar = TDXDayRecord {
+            date: date(1, 2),
+            symbol: "A".to_string(),
+            open: 10.0,
+            high: 10.0,
+            low: 10.0,
+            close: 10.0,
+            volume: 100,
+            amount: 1000.0,
+            market: "SZ".to_string(),
+        };
+        let (_, result) = cleaner.clean_with_data(vec![bar.clone(), bar]).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quality.html");
+        HtmlReport::new("数据质量")
+            .with_cleaning("日线", &result)
+            .write(&path)
+            .unwrap();
+        let html = std::fs::read_to_string(&path).unwrap();
+        assert!(html.contains("<th>重复记录</th><td>1</td>"));
+        assert!(html.contains("各规则影响"));
+    }
+}
@@ -0,0 +1,201 @@
+//! 内联SVG图表
+//!
+//! 生成不依赖外部脚本的折线图、面积图和柱状图，嵌入HTML报告。
+
+use chrono::NaiveDate;
+use std::fmt::Write;
+
+/// 图表宽度
+const WIDTH: f64 = 860.0;
+/// 图表高度
+const HEIGHT: f64 = 260.0;
+/// 左侧留白（纵轴刻度）
+const LEFT: f64 = 64.0;
+/// 右侧留白
+const RIGHT: f64 = 16.0;
+/// 上方留白
+const TOP: f64 = 12.0;
+/// 下方留白（横轴刻度）
+const BOTTOM: f64 = 28.0;
+
+/// 转义HTML/SVG文本
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 纵轴范围，上下限相同时上下各扩展一点
+fn value_range(values: impl Iterator<Item = f64>) -> Option<(f64, f64)> {
+    let (min, max) = values
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if !min.is_finite() {
+        return None;
+    }
+    if min == max {
+        let pad = if min == 0.0 { 1.0 } else { min.abs() * 0.05 };
+        Some((min - pad, max + pad))
+    } else {
+        Some((min, max))
+    }
+}
+
+fn open_svg(svg: &mut String) {
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="100%" font-family="sans-serif" font-size="11">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+}
+
+/// 纵轴刻度和网格线
+fn y_axis(svg: &mut String, lo: f64, hi: f64, label: &dyn Fn(f64) -> String) {
+    let plot_height = HEIGHT - TOP - BOTTOM;
+    for i in 0..=4 {
+        let value = lo + (hi - lo) * i as f64 / 4.0;
+        let y = TOP + plot_height * (1.0 - i as f64 / 4.0);
+        let _ = write!(
+            svg,
+            r##"<line x1="{x1}" y1="{y:.1}" x2="{x2}" y2="{y:.1}" stroke="#e5e5e5"/><text x="{tx}" y="{ty:.1}" text-anchor="end" fill="#555">{text}</text>"##,
+            x1 = LEFT,
+            x2 = WIDTH - RIGHT,
+            tx = LEFT - 6.0,
+            ty = y + 4.0,
+            text = escape(&label(value)),
+        );
+    }
+}
+
+/// 日期序列折线图，`fill` 为真时填充到零线（用于回撤）
+pub fn line_chart(
+    series: &[(NaiveDate, f64)],
+    color: &str,
+    fill: bool,
+    label: &dyn Fn(f64) -> String,
+) -> String {
+    let mut svg = String::new();
+    open_svg(&mut svg);
+    let range = value_range(series.iter().map(|(_, v)| *v).chain(fill.then_some(0.0)));
+    let (Some((lo, hi)), true) = (range, series.len() >= 2) else {
+        svg.push_str(
+            r##"<text x="50%" y="50%" text-anchor="middle" fill="#999">无数据</text></svg>"##,
+        );
+        return svg;
+    };
+    y_axis(&mut svg, lo, hi, label);
+
+    let plot_width = WIDTH - LEFT - RIGHT;
+    let plot_height = HEIGHT - TOP - BOTTOM;
+    let x = |i: usize| LEFT + plot_width * i as f64 / (series.len() - 1) as f64;
+    let y = |v: f64| TOP + plot_height * (1.0 - (v - lo) / (hi - lo));
+
+    let mut points = String::new();
+    for (i, (_, value)) in series.iter().enumerate() {
+        if value.is_finite() {
+            let _ = write!(points, "{:.1},{:.1} ", x(i), y(*value));
+        }
+    }
+    if fill {
+        let zero = y(0.0);
+        let _ = write!(
+            svg,
+            r#"<polygon points="{:.1},{:.1} {}{:.1},{:.1}" fill="{}" fill-opacity="0.25" stroke="none"/>"#,
+            x(0),
+            zero,
+            points,
+            x(series.len() - 1),
+            zero,
+            color
+        );
+    }
+    let _ = write!(
+        svg,
+        r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+        points.trim_end(),
+        color
+    );
+
+    // 横轴标注首、中、尾日期
+    for i in [0, (series.len() - 1) / 2, series.len() - 1] {
+        let anchor = match i {
+            0 => "start",
+            i if i == series.len() - 1 => "end",
+            _ => "middle",
+        };
+        let _ = write!(
+            svg,
+            r##"<text x="{:.1}" y="{:.1}" text-anchor="{}" fill="#555">{}</text>"##,
+            x(i),
+            HEIGHT - 8.0,
+            anchor,
+            series[i].0
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// 柱状图，正值与负值使用不同颜色
+pub fn bar_chart(bars: &[(String, f64)], label: &dyn Fn(f64) -> String) -> String {
+    let mut svg = String::new();
+    open_svg(&mut svg);
+    let Some((lo, hi)) = value_range(bars.iter().map(|(_, v)| *v).chain([0.0])) else {
+        svg.push_str(
+            r##"<text x="50%" y="50%" text-anchor="middle" fill="#999">无数据</text></svg>"##,
+        );
+        return svg;
+    };
+    y_axis(&mut svg, lo, hi, label);
+
+    let plot_width = WIDTH - LEFT - RIGHT;
+    let plot_height = HEIGHT - TOP - BOTTOM;
+    let y = |v: f64| TOP + plot_height * (1.0 - (v - lo) / (hi - lo));
+    let slot = plot_width / bars.len().max(1) as f64;
+    let zero = y(0.0);
+    for (i, (name, value)) in bars.iter().enumerate() {
+        let top = y(*value).min(zero);
+        let height = (y(*value) - zero).abs();
+        let color = if *value >= 0.0 { "#d62728" } else { "#2ca02c" };
+        let _ = write!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"><title>{}: {}</title></rect>"#,
+            LEFT + slot * i as f64 + slot * 0.1,
+            top,
+            slot * 0.8,
+            height,
+            color,
+            escape(name),
+            escape(&label(*value))
+        );
+    }
+    // 柱子较少时逐个标注，较多时只标首尾
+    let labelled: Vec<usize> = if bars.len() <= 12 {
+        (0..bars.len()).collect()
+    } else {
+        vec![0, bars.len() - 1]
+    };
+    for i in labelled {
+        let _ = write!(
+            svg,
+            r##"<text x="{:.1}" y="{:.1}" text-anchor="middle" fill="#555">{}</text>"##,
+            LEFT + slot * (i as f64 + 0.5),
+            HEIGHT - 8.0,
+            escape(&bars[i].0)
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
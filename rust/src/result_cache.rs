@@ -0,0 +1,314 @@
+//! 共享结果缓存模块
+//!
+//! `ResultCache` 是可在线程间共享的内存缓存，键为（操作, 参数, 数据指纹），
+//! 供聚合器（`DataAggregator::set_result_cache`）、本地日线查询（`DayQuery::execute_cached`）
+//! 和HTTP服务（`HttpServer::with_result_cache`）共用：仪表盘重复发起相同查询时直接返回结果，
+//! 不再重复扫描全市场。数据变化后指纹随之改变，旧结果不会被命中，到期或超出容量后被淘汰。
+//!
+//! 值以 `Arc` 保存，同一缓存可存放不同类型的结果，读取时按类型取出。
+
+use crate::parsers::Bar;
+use dashmap::DashMap;
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 缓存键
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// 操作名称
+    pub operation: String,
+    /// 参数（调用方序列化后的文本）
+    pub params: String,
+    /// 输入数据指纹
+    pub fingerprint: u64,
+}
+
+impl CacheKey {
+    /// 创建缓存键
+    pub fn new(operation: impl Into<String>, params: impl Into<String>, fingerprint: u64) -> Self {
+        Self {
+            operation: operation.into(),
+            params: params.into(),
+            fingerprint,
+        }
+    }
+}
+
+/// 缓存统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// 命中次数
+    pub hits: u64,
+    /// 未命中次数（含已过期）
+    pub misses: u64,
+    /// 当前条目数
+    pub entries: usize,
+    /// 因过期或容量淘汰的条目数
+    pub evictions: u64,
+}
+
+/// 缓存条目
+struct Entry {
+    value: Arc<dyn Any + Send + Sync>,
+    inserted_at: Instant,
+}
+
+/// 并发安全的共享结果缓存
+pub struct ResultCache {
+    /// 条目
+    entries: DashMap<CacheKey, Entry>,
+    /// 条目有效期
+    ttl: Duration,
+    /// 最大条目数
+    max_entries: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl std::fmt::Debug for ResultCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResultCache")
+            .field("entries", &self.entries.len())
+            .field("ttl", &self.ttl)
+            .field("max_entries", &self.max_entries)
+            .finish()
+    }
+}
+
+impl Default for ResultCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResultCache {
+    /// 创建缓存：有效期5分钟，最多1024个条目
+    pub fn new() -> Self {
+        Self {
+            entries: DashMap::new(),
+            ttl: Duration::from_secs(300),
+            max_entries: 1024,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// 设置条目有效期
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// 设置最大条目数
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    /// 读取结果，不存在、已过期或类型不符时返回None
+    pub fn get<T: Any + Send + Sync>(&self, key: &CacheKey) -> Option<Arc<T>> {
+        let expired = match self.entries.get(key) {
+            Some(entry) if entry.inserted_at.elapsed() <= self.ttl => {
+                if let Ok(value) = Arc::clone(&entry.value).downcast::<T>() {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(value);
+                }
+                false
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired && self.entries.remove(key).is_some() {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// 写入结果，超出容量时先淘汰过期条目，再淘汰最早写入的条目
+    pub fn insert<T: Any + Send + Sync>(&self, key: CacheKey, value: T) -> Arc<T> {
+        let value = Arc::new(value);
+        self.entries.insert(
+            key,
+            Entry {
+                value: Arc::clone(&value) as Arc<dyn Any + Send + Sync>,
+                inserted_at: Instant::now(),
+            },
+        );
+        if self.entries.len() > self.max_entries {
+            self.evict();
+        }
+        value
+    }
+
+    /// 读取结果，未命中时计算并写入；计算失败时不写入
+    ///
+    /// 并发的相同请求可能各自计算一次，以最后写入的结果为准。
+    pub fn get_or_try_insert_with<T, F>(&self, key: CacheKey, compute: F) -> anyhow::Result<Arc<T>>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> anyhow::Result<T>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        Ok(self.insert(key, compute()?))
+    }
+
+    /// 移除某一操作的全部结果
+    pub fn invalidate_operation(&self, operation: &str) {
+        self.entries.retain(|key, _| key.operation != operation);
+    }
+
+    /// 清空缓存
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+
+    /// 当前条目数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 是否为空
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 统计信息
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.len(),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 淘汰过期条目；仍超出容量时按写入时间淘汰最早的条目，腾出约1/8的空间，
+    /// 避免每次写入都扫描全部条目
+    fn evict(&self) {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, entry| entry.inserted_at.elapsed() <= self.ttl);
+
+        let target = self.max_entries - self.max_entries / 8;
+        if self.entries.len() > target {
+            let mut ages: Vec<(Instant, CacheKey)> = self
+                .entries
+                .iter()
+                .map(|entry| (entry.inserted_at, entry.key().clone()))
+                .collect();
+            ages.sort_by_key(|(inserted_at, _)| *inserted_at);
+            let excess = self.entries.len() - target;
+            for (_, key) in ages.into_iter().take(excess) {
+                self.entries.remove(&key);
+            }
+        }
+        let evicted = before.saturating_sub(self.entries.len());
+        self.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+    }
+}
+
+/// K线记录的指纹（代码、市场、时间和全部价量字段），用于缓存键
+pub fn fingerprint_records<B: Bar>(data: &[B]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.len().hash(&mut hasher);
+    for bar in data {
+        bar.symbol().hash(&mut hasher);
+        bar.market().hash(&mut hasher);
+        bar.timestamp().hash(&mut hasher);
+        for value in [bar.open(), bar.high(), bar.low(), bar.close(), bar.amount()] {
+            value.to_bits().hash(&mut hasher);
+        }
+        bar.volume().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::TDXDayRecord;
+    use chrono::NaiveDate;
+
+    fn create_test_record(close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            symbol: "600000".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_get_insert_and_ttl() {
+        let cache = ResultCache::new();
+        let key = CacheKey::new("scan", "ma5>ma10", 1);
+        assert!(cache.get::<Vec<String>>(&key).is_none());
+
+        let mut calls = 0;
+        for _ in 0..2 {
+            let value = cache
+                .get_or_try_insert_with(key.clone(), || {
+                    calls += 1;
+                    Ok(vec!["600000".to_string()])
+                })
+                .unwrap();
+            assert_eq!(value.len(), 1);
+        }
+        assert_eq!(calls, 1);
+        // 类型不符视为未命中
+        assert!(cache.get::<String>(&key).is_none());
+        assert!(cache
+            .get_or_try_insert_with::<u32, _>(CacheKey::new("scan", "x", 1), || {
+                Err(anyhow::anyhow!("失败"))
+            })
+            .is_err());
+        assert_eq!(cache.len(), 1);
+
+        let expiring = ResultCache::new().with_ttl(Duration::ZERO);
+        expiring.insert(key.clone(), 1u32);
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(expiring.get::<u32>(&key).is_none());
+        assert_eq!(expiring.stats().evictions, 1);
+        assert!(expiring.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_and_invalidation() {
+        let cache = Arc::new(ResultCache::new().with_max_entries(8));
+        std::thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let cache = Arc::clone(&cache);
+                scope.spawn(move || {
+                    for i in 0..10u64 {
+                        cache.insert(CacheKey::new("agg", "", thread * 100 + i), i);
+                    }
+                });
+            }
+        });
+        assert!(cache.len() <= 8);
+        assert!(cache.stats().evictions >= 32);
+
+        cache.insert(CacheKey::new("query", "", 0), 0u64);
+        cache.invalidate_operation("agg");
+        assert_eq!(cache.len(), 1);
+
+        let a = [create_test_record(10.0)];
+        let b = [create_test_record(10.01)];
+        assert_eq!(fingerprint_records(&a), fingerprint_records(&a.clone()));
+        assert_ne!(fingerprint_records(&a), fingerprint_records(&b));
+    }
+}
@@ -0,0 +1,443 @@
+//! gRPC数据处理服务
+//!
+//! 接口定义见 `proto/pulse_trader.proto`，提供 ParseDirectory、CleanData、
+//! ComputeIndicators 和 Aggregate 四个流式返回的RPC，计算在rayon线程池中执行，
+//! 结果分批推送，非Rust服务可以通过网络调用本模块完成繁重计算。
+
+use crate::parsers::{TDXDayParser, TDXDayRecord};
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::processors::{
+    AggregationRule, CleaningRule, DataAggregator, DataCleaner, IndicatorCalculator, KeepPolicy,
+};
+use crate::shutdown::Shutdown;
+use anyhow::Context;
+use chrono::NaiveDate;
+use futures::stream::{self, Stream};
+use log::info;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use tonic::{Request, Response, Status};
+
+/// 由proto生成的消息与服务定义
+pub mod proto {
+    tonic::include_proto!("pulse_trader.v1");
+}
+
+use proto::processing_server::{Processing, ProcessingServer};
+
+/// 默认每批记录数
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// 流式响应类型
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// gRPC数据处理服务
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingService;
+
+impl ProcessingService {
+    /// 创建服务
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 包装为tonic服务，可与其他服务一起挂载
+    pub fn into_server(self) -> ProcessingServer<Self> {
+        ProcessingServer::new(self)
+    }
+
+    /// 在指定地址启动服务
+    pub async fn serve(self, addr: SocketAddr) -> anyhow::Result<()> {
+        self.serve_with_shutdown(addr, Shutdown::new()).await
+    }
+
+    /// 启动服务，收到停止信号后不再接受新请求，等待进行中的请求完成后返回
+    pub async fn serve_with_shutdown(
+        self,
+        addr: SocketAddr,
+        shutdown: Shutdown,
+    ) -> anyhow::Result<()> {
+        info!("gRPC服务已启动: {}", addr);
+        tonic::transport::Server::builder()
+            .add_service(self.into_server())
+            .serve_with_shutdown(addr, async move { shutdown.wait().await })
+            .await
+            .context("gRPC服务异常退出")?;
+        info!("gRPC服务已停止");
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl Processing for ProcessingService {
+    type ParseDirectoryStream = ResponseStream<proto::RecordBatch>;
+    type CleanDataStream = ResponseStream<proto::CleanDataResponse>;
+    type ComputeIndicatorsStream = ResponseStream<proto::IndicatorBatch>;
+    type AggregateStream = ResponseStream<proto::AggregationResult>;
+
+    async fn parse_directory(
+        &self,
+        request: Request<proto::ParseDirectoryRequest>,
+    ) -> Result<Response<Self::ParseDirectoryStream>, Status> {
+        let request = request.into_inner();
+        let batch_size = batch_size(request.batch_size);
+        let records = blocking(move || parse_directory(&request.directory)).await?;
+
+        Ok(stream_batches(records, batch_size, |records| {
+            proto::RecordBatch {
+                records: records.iter().map(to_proto_record).collect(),
+            }
+        }))
+    }
+
+    async fn clean_data(
+        &self,
+        request: Request<proto::CleanDataRequest>,
+    ) -> Result<Response<Self::CleanDataStream>, Status> {
+        let request = request.into_inner();
+        let rules: Vec<CleaningRule> = if request.rules_json.trim().is_empty() {
+            vec![
+                CleaningRule::ValidatePriceConsistency,
+                CleaningRule::RemoveDuplicates {
+                    keys: vec!["symbol".to_string(), "date".to_string()],
+                    keep: KeepPolicy::First,
+                },
+            ]
+        } else {
+            parse_rules(&request.rules_json)?
+        };
+
+        let (records, result) = blocking(move || {
+            let records = load_source(request.source)?;
+            let mut cleaner = DataCleaner::new();
+            cleaner.add_rules(rules);
+            cleaner.clean_with_data(records)
+        })
+        .await?;
+
+        // 先分批返回清洗后的记录，最后返回汇总
+        let mut events: Vec<Result<proto::CleanDataResponse, Status>> = records
+            .chunks(DEFAULT_BATCH_SIZE)
+            .map(|chunk| {
+                Ok(proto::CleanDataResponse {
+                    event: Some(proto::clean_data_response::Event::Records(
+                        proto::RecordBatch {
+                            records: chunk.iter().map(to_proto_record).collect(),
+                        },
+                    )),
+                })
+            })
+            .collect();
+        let summary = proto::CleanDataResponse {
+            event: Some(proto::clean_data_response::Event::Summary(
+                proto::CleaningSummary {
+                    original_count: result.original_count as u64,
+                    cleaned_count: result.cleaned_count as u64,
+                    removed_count: result.removed_count as u64,
+                    applied_rules: result.applied_rules,
+                    outliers_removed: result.statistics.outliers_removed as u64,
+                    missing_values_filled: result.statistics.missing_values_filled as u64,
+                    duplicates_removed: result.statistics.duplicates_removed as u64,
+                    price_inconsistencies: result.statistics.price_inconsistencies as u64,
+                    range_violations: result.statistics.range_violations as u64,
+                    filtered_by_expr: result.statistics.filtered_by_expr as u64,
+                },
+            )),
+        };
+        events.push(Ok(summary));
+        Ok(Response::new(Box::pin(stream::iter(events))))
+    }
+
+    async fn compute_indicators(
+        &self,
+        request: Request<proto::ComputeIndicatorsRequest>,
+    ) -> Result<Response<Self::ComputeIndicatorsStream>, Status> {
+        let request = request.into_inner();
+        let batch_size = batch_size(request.batch_size);
+        let mut calculator = IndicatorCalculator::new();
+        if !request.window_sizes.is_empty() {
+            calculator = calculator
+                .with_window_sizes(request.window_sizes.iter().map(|&w| w as usize).collect());
+        }
+
+        let enhanced = blocking(move || {
+            let records = load_source(request.source)?;
+            calculator.calculate_parallel(&records)
+        })
+        .await?;
+
+        Ok(stream_batches(enhanced, batch_size, |records| {
+            proto::IndicatorBatch {
+                records: records.iter().map(to_proto_indicator).collect(),
+            }
+        }))
+    }
+
+    async fn aggregate(
+        &self,
+        request: Request<proto::AggregateRequest>,
+    ) -> Result<Response<Self::AggregateStream>, Status> {
+        let request = request.into_inner();
+        let rules: Vec<AggregationRule> = parse_rules(&request.rules_json)?;
+
+        let results = blocking(move || {
+            let records = load_source(request.source)?;
+            let mut aggregator = DataAggregator::new();
+            aggregator.add_rules(rules);
+            aggregator.aggregate(&records)
+        })
+        .await?;
+
+        let messages: Vec<Result<proto::AggregationResult, Status>> = results
+            .into_iter()
+            .map(|result| {
+                Ok(proto::AggregationResult {
+                    rule_name: result.rule_name,
+                    original_count: result.original_count as u64,
+                    aggregated_count: result.aggregated_count as u64,
+                    values: result
+                        .values
+                        .into_iter()
+                        .map(|value| proto::AggregatedValue {
+                            key: value.key,
+                            value: value.value,
+                            fields: value
+                                .fields
+                                .into_iter()
+                                .map(|(name, value)| proto::NamedValue { name, value })
+                                .collect(),
+                            count: value.count.map(|c| c as u64),
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+        Ok(Response::new(Box::pin(stream::iter(messages))))
+    }
+}
+
+/// 在阻塞线程中执行计算，错误转换为gRPC状态
+async fn blocking<T, F>(f: F) -> Result<T, Status>
+where
+    T: Send + 'static,
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Status::internal(format!("计算任务失败: {}", e)))?
+        .map_err(|e| Status::invalid_argument(format!("{:#}", e)))
+}
+
+/// 将结果分批转换为流式响应
+fn stream_batches<T, M, F>(
+    items: Vec<T>,
+    batch_size: usize,
+    convert: F,
+) -> Response<ResponseStream<M>>
+where
+    T: Send + 'static,
+    M: Send + 'static,
+    F: Fn(&[T]) -> M,
+{
+    let batches: Vec<Result<M, Status>> = items
+        .chunks(batch_size)
+        .map(|chunk| Ok(convert(chunk)))
+        .collect();
+    Response::new(Box::pin(stream::iter(batches)))
+}
+
+fn batch_size(requested: u32) -> usize {
+    if requested == 0 {
+        DEFAULT_BATCH_SIZE
+    } else {
+        requested as usize
+    }
+}
+
+fn parse_rules<T: serde::de::DeserializeOwned>(json: &str) -> Result<Vec<T>, Status> {
+    serde_json::from_str(json).map_err(|e| Status::invalid_argument(format!("规则格式错误: {}", e)))
+}
+
+fn parse_directory(directory: &str) -> anyhow::Result<Vec<TDXDayRecord>> {
+    if directory.is_empty() {
+        return Err(anyhow::anyhow!("目录不能为空"));
+    }
+    TDXDayParser::new(directory).parse_directory(directory)
+}
+
+/// 读取数据来源
+fn load_source(source: Option<proto::DataSource>) -> anyhow::Result<Vec<TDXDayRecord>> {
+    match source.and_then(|s| s.source) {
+        Some(proto::data_source::Source::Directory(directory)) => parse_directory(&directory),
+        Some(proto::data_source::Source::Records(batch)) => {
+            batch.records.iter().map(from_proto_record).collect()
+        }
+        None => Err(anyhow::anyhow!("缺少数据来源")),
+    }
+}
+
+/// 转换为proto记录
+pub fn to_proto_record(record: &TDXDayRecord) -> proto::DayRecord {
+    proto::DayRecord {
+        symbol: record.symbol.clone(),
+        market: record.market.clone(),
+        date: record.date.format("%Y-%m-%d").to_string(),
+        open: record.open,
+        high: record.high,
+        low: record.low,
+        close: record.close,
+        volume: record.volume,
+        amount: record.amount,
+    }
+}
+
+/// 从proto记录转换
+pub fn from_proto_record(record: &proto::DayRecord) -> anyhow::Result<TDXDayRecord> {
+    Ok(TDXDayRecord {
+        date: NaiveDate::parse_from_str(&record.date, "%Y-%m-%d")
+            .with_context(|| format!("无效的日期: {}", record.date))?,
+        symbol: record.symbol.clone(),
+        open: record.open,
+        high: record.high,
+        low: record.low,
+        close: record.close,
+        volume: record.volume,
+        amount: record.amount,
+        market: record.market.clone(),
+    })
+}
+
+fn to_proto_indicator(record: &EnhancedDayRecord) -> proto::IndicatorRecord {
+    let values = &record.indicators;
+    proto::IndicatorRecord {
+        record: Some(to_proto_record(&record.base_record)),
+        ma5: values.ma5,
+        ma10: values.ma10,
+        ma20: values.ma20,
+        ma60: values.ma60,
+        volume_ma5: values.volume_ma5,
+        change_percent: values.change_percent,
+        amplitude: values.amplitude,
+        rsi: values.rsi,
+        macd_dif: values.macd.as_ref().map(|m| m.dif),
+        macd_signal: values.macd.as_ref().map(|m| m.signal),
+        macd_histogram: values.macd.as_ref().map(|m| m.histogram),
+        boll_upper: values.bollinger.as_ref().map(|b| b.upper),
+        boll_middle: values.bollinger.as_ref().map(|b| b.middle),
+        boll_lower: values.bollinger.as_ref().map(|b| b.lower),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::processing_client::ProcessingClient;
+
+    fn records() -> Vec<TDXDayRecord> {
+        (1..=30)
+            .map(|day| TDXDayRecord {
+                date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+                symbol: "600000".to_string(),
+                open: 10.0 + day as f64 * 0.1,
+                high: 10.5 + day as f64 * 0.1,
+                low: 9.5 + day as f64 * 0.1,
+                close: 10.2 + day as f64 * 0.1,
+                volume: 1000 * day as u64,
+                amount: 10_000.0 * day as f64,
+                market: "SH".to_string(),
+            })
+            .collect()
+    }
+
+    fn source() -> Option<proto::DataSource> {
+        Some(proto::DataSource {
+            source: Some(proto::data_source::Source::Records(proto::RecordBatch {
+                records: records().iter().map(to_proto_record).collect(),
+            })),
+        })
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = &records()[4];
+        let restored = from_proto_record(&to_proto_record(record)).unwrap();
+        assert_eq!(restored.date, record.date);
+        assert_eq!(restored.close, record.close);
+
+        let mut invalid = to_proto_record(record);
+        invalid.date = "2024/01/05".to_string();
+        assert!(from_proto_record(&invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streaming_rpcs() {
+        let service = ProcessingService::new();
+        let response = service
+            .compute_indicators(Request::new(proto::ComputeIndicatorsRequest {
+                source: source(),
+                window_sizes: vec![5],
+                batch_size: 8,
+            }))
+            .await
+            .unwrap();
+        let batches: Vec<proto::IndicatorBatch> =
+            futures::StreamExt::collect::<Vec<_>>(response.into_inner())
+                .await
+                .into_iter()
+                .collect::<Result<_, _>>()
+                .unwrap();
+        assert_eq!(batches.len(), 4);
+        assert!(batches[3].records.last().unwrap().ma5.is_some());
+
+        let error = service
+            .aggregate(Request::new(proto::AggregateRequest {
+                source: source(),
+                rules_json: "not json".to_string(),
+            }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_over_network() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(ProcessingService::new().into_server())
+                .serve_with_incoming(tonic::transport::server::TcpIncoming::from(listener))
+                .await
+        });
+
+        let mut client = ProcessingClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap();
+        let mut stream = client
+            .clean_data(proto::CleanDataRequest {
+                source: source(),
+                rules_json: String::new(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let mut cleaned = 0;
+        loop {
+            let response = stream.message().await.unwrap().unwrap();
+            match response.event {
+                Some(proto::clean_data_response::Event::Records(batch)) => {
+                    cleaned += batch.records.len();
+                }
+                Some(proto::clean_data_response::Event::Summary(summary)) => {
+                    assert_eq!(summary.original_count, 30);
+                    assert_eq!(summary.cleaned_count, 30);
+                    break;
+                }
+                other => panic!("意外的响应: {:?}", other),
+            }
+        }
+        assert_eq!(cleaned, 30);
+        assert!(stream.message().await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,580 @@
+//! HTTP查询接口
+//!
+//! 以REST接口提供本地数据查询，仪表盘和其他服务可以直接访问Rust数据仓库：
+//!
+//! - `GET /symbols`：股票列表及数据范围
+//! - `GET /bars/{symbol}?start=&end=&tf=&market=`：K线，`tf` 支持
+//!   `1d`（默认）、`1w`、`1mo`（由日线合成）以及 `1m`、`5m`、`15m`、`30m`、`60m`（由分钟线合成）
+//! - `GET /indicators/{symbol}?start=&end=&market=`：技术指标
+//! - `GET /stats`：数据概况
+//!
+//! 通过 `with_result_cache` 设置共享的 `ResultCache` 后，指标接口按股票历史数据的指纹缓存
+//! 完整历史的计算结果，仪表盘反复查询同一只股票时无需重新计算。
+
+use crate::parsers::{TDXDayParser, TDXDayRecord, TDXMinuteRecord};
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::processors::{BarBuilder, IndicatorCalculator, Timeframe};
+use crate::result_cache::{fingerprint_records, CacheKey, ResultCache};
+use crate::shutdown::Shutdown;
+use anyhow::{Context, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{Datelike, NaiveDate};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+/// 股票键（股票代码, 市场）
+type SymbolKey = (String, String);
+
+/// 内存数据仓库，按股票保存按日期/时间排序的数据
+#[derive(Debug, Clone, Default)]
+pub struct MarketDataStore {
+    /// 日线
+    daily: BTreeMap<SymbolKey, Vec<TDXDayRecord>>,
+    /// 分钟线
+    minutes: BTreeMap<SymbolKey, Vec<TDXMinuteRecord>>,
+}
+
+impl MarketDataStore {
+    /// 创建空仓库
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从日线记录创建
+    pub fn from_records(records: Vec<TDXDayRecord>) -> Self {
+        let mut store = Self::new();
+        store.insert_daily(records);
+        store
+    }
+
+    /// 从vipdoc目录加载日线
+    pub fn load_vipdoc<P: AsRef<std::path::Path>>(root: P) -> Result<Self> {
+        let root = root.as_ref();
+        let records = TDXDayParser::new(root)
+            .parse_directory(root)
+            .with_context(|| format!("加载数据失败: {}", root.display()))?;
+        Ok(Self::from_records(records))
+    }
+
+    /// 插入日线，同一日期的记录被替换
+    pub fn insert_daily(&mut self, records: Vec<TDXDayRecord>) {
+        for record in records {
+            let series = self
+                .daily
+                .entry((record.symbol.clone(), record.market.clone()))
+                .or_default();
+            match series.binary_search_by_key(&record.date, |r| r.date) {
+                Ok(index) => series[index] = record,
+                Err(index) => series.insert(index, record),
+            }
+        }
+    }
+
+    /// 插入分钟线，同一时间的记录被替换
+    pub fn insert_minutes(&mut self, records: Vec<TDXMinuteRecord>) {
+        for record in records {
+            let series = self
+                .minutes
+                .entry((record.symbol.clone(), record.market.clone()))
+                .or_default();
+            match series.binary_search_by_key(&record.datetime, |r| r.datetime) {
+                Ok(index) => series[index] = record,
+                Err(index) => series.insert(index, record),
+            }
+        }
+    }
+
+    /// 查找股票，`market` 为空且代码对应多个市场时报错
+    fn resolve<'a, T>(
+        series: &'a BTreeMap<SymbolKey, Vec<T>>,
+        symbol: &str,
+        market: Option<&str>,
+    ) -> std::result::Result<&'a Vec<T>, ApiError> {
+        let matches: Vec<(&SymbolKey, &Vec<T>)> = series
+            .iter()
+            .filter(|((s, m), _)| {
+                s == symbol && market.is_none_or(|market| m.eq_ignore_ascii_case(market))
+            })
+            .collect();
+        match matches.as_slice() {
+            [(_, data)] => Ok(data),
+            [] => Err(ApiError::not_found(format!("股票不存在: {}", symbol))),
+            _ => Err(ApiError::bad_request(format!(
+                "股票代码{}对应多个市场，请指定market参数",
+                symbol
+            ))),
+        }
+    }
+}
+
+/// 股票摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolSummary {
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 日线条数
+    pub records: usize,
+    /// 最早日期
+    pub first_date: Option<NaiveDate>,
+    /// 最新日期
+    pub last_date: Option<NaiveDate>,
+}
+
+/// 数据概况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreStats {
+    /// 股票数
+    pub symbols: usize,
+    /// 日线条数
+    pub daily_records: usize,
+    /// 分钟线条数
+    pub minute_records: usize,
+    /// 最早日期
+    pub earliest_date: Option<NaiveDate>,
+    /// 最新日期
+    pub latest_date: Option<NaiveDate>,
+}
+
+/// 查询参数
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RangeQuery {
+    /// 起始日期（含）
+    pub start: Option<NaiveDate>,
+    /// 结束日期（含）
+    pub end: Option<NaiveDate>,
+    /// K线周期
+    pub tf: Option<String>,
+    /// 市场
+    pub market: Option<String>,
+}
+
+impl RangeQuery {
+    fn contains(&self, date: NaiveDate) -> bool {
+        self.start.is_none_or(|start| date >= start) && self.end.is_none_or(|end| date <= end)
+    }
+}
+
+/// 接口错误
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: String) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message,
+        }
+    }
+
+    fn not_found(message: String) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message,
+        }
+    }
+
+    fn internal(error: anyhow::Error) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(serde_json::json!({ "error": self.message })),
+        )
+            .into_response()
+    }
+}
+
+/// HTTP查询服务
+#[derive(Debug, Clone)]
+pub struct HttpServer {
+    /// 数据仓库（可由目录监控等任务并发更新）
+    store: Arc<RwLock<MarketDataStore>>,
+    /// 指标计算器
+    calculator: Arc<IndicatorCalculator>,
+    /// 共享结果缓存
+    cache: Option<Arc<ResultCache>>,
+}
+
+impl HttpServer {
+    /// 创建HTTP服务
+    pub fn new(store: MarketDataStore) -> Self {
+        Self::from_shared(Arc::new(RwLock::new(store)))
+    }
+
+    /// 使用共享的数据仓库创建HTTP服务
+    pub fn from_shared(store: Arc<RwLock<MarketDataStore>>) -> Self {
+        Self {
+            store,
+            calculator: Arc::new(IndicatorCalculator::new()),
+            cache: None,
+        }
+    }
+
+    /// 设置共享结果缓存
+    pub fn with_result_cache(mut self, cache: Arc<ResultCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// 共享的数据仓库
+    pub fn store(&self) -> Arc<RwLock<MarketDataStore>> {
+        Arc::clone(&self.store)
+    }
+
+    /// 路由，可合并到其他axum应用中
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/symbols", get(symbols))
+            .route("/bars/{symbol}", get(bars))
+            .route("/indicators/{symbol}", get(indicators))
+            .route("/stats", get(stats))
+            .with_state(self.clone())
+    }
+
+    /// 在指定地址启动服务
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        self.serve_with_shutdown(addr, Shutdown::new()).await
+    }
+
+    /// 启动服务，收到停止信号后不再接受新连接，等待已有连接结束后返回
+    pub async fn serve_with_shutdown(self, addr: SocketAddr, shutdown: Shutdown) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("无法监听地址: {}", addr))?;
+        info!("HTTP服务已启动: http://{}", addr);
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(async move { shutdown.wait().await })
+            .await
+            .context("HTTP服务异常退出")?;
+        info!("HTTP服务已停止");
+        Ok(())
+    }
+
+    fn read(
+        &self,
+    ) -> std::result::Result<std::sync::RwLockReadGuard<'_, MarketDataStore>, ApiError> {
+        self.store
+            .read()
+            .map_err(|_| ApiError::internal(anyhow::anyhow!("数据仓库锁已损坏")))
+    }
+}
+
+/// `GET /symbols`
+async fn symbols(
+    State(server): State<HttpServer>,
+) -> std::result::Result<Json<Vec<SymbolSummary>>, ApiError> {
+    let store = server.read()?;
+    Ok(Json(
+        store
+            .daily
+            .iter()
+            .map(|((symbol, market), records)| SymbolSummary {
+                symbol: symbol.clone(),
+                market: market.clone(),
+                records: records.len(),
+                first_date: records.first().map(|r| r.date),
+                last_date: records.last().map(|r| r.date),
+            })
+            .collect(),
+    ))
+}
+
+/// `GET /bars/{symbol}`
+async fn bars(
+    State(server): State<HttpServer>,
+    Path(symbol): Path<String>,
+    Query(query): Query<RangeQuery>,
+) -> std::result::Result<Response, ApiError> {
+    let store = server.read()?;
+    let market = query.market.as_deref();
+    let tf = query.tf.as_deref().unwrap_or("1d");
+
+    let intraday = match tf {
+        "1d" | "1w" | "1mo" => None,
+        "1m" => Some(None),
+        "5m" => Some(Some(Timeframe::Min5)),
+        "15m" => Some(Some(Timeframe::Min15)),
+        "30m" => Some(Some(Timeframe::Min30)),
+        "60m" => Some(Some(Timeframe::Min60)),
+        other => return Err(ApiError::bad_request(format!("不支持的周期: {}", other))),
+    };
+
+    if let Some(timeframe) = intraday {
+        let minutes: Vec<TDXMinuteRecord> =
+            MarketDataStore::resolve(&store.minutes, &symbol, market)?
+                .iter()
+                .filter(|r| query.contains(r.datetime.date()))
+                .cloned()
+                .collect();
+        let bars = match timeframe {
+            Some(timeframe) => BarBuilder::new(timeframe).build(&minutes),
+            None => minutes,
+        };
+        return Ok(Json(bars).into_response());
+    }
+
+    let daily: Vec<TDXDayRecord> = MarketDataStore::resolve(&store.daily, &symbol, market)?
+        .iter()
+        .filter(|r| query.contains(r.date))
+        .cloned()
+        .collect();
+    let bars = match tf {
+        "1w" => resample(&daily, |d| (d.iso_week().year(), d.iso_week().week())),
+        "1mo" => resample(&daily, |d| (d.year(), d.month())),
+        _ => daily,
+    };
+    Ok(Json(bars).into_response())
+}
+
+/// `GET /indicators/{symbol}`
+async fn indicators(
+    State(server): State<HttpServer>,
+    Path(symbol): Path<String>,
+    Query(query): Query<RangeQuery>,
+) -> std::result::Result<Json<Vec<EnhancedDayRecord>>, ApiError> {
+    // 指标在完整历史上计算，避免区间起点处的均线缺失
+    let history = {
+        let store = server.read()?;
+        MarketDataStore::resolve(&store.daily, &symbol, query.market.as_deref())?.clone()
+    };
+    let key = server
+        .cache
+        .as_ref()
+        .map(|_| CacheKey::new("http.indicators", "", fingerprint_records(&history)));
+    let cached = server
+        .cache
+        .as_ref()
+        .zip(key.as_ref())
+        .and_then(|(cache, key)| cache.get::<Vec<EnhancedDayRecord>>(key));
+
+    let enhanced = match cached {
+        Some(enhanced) => enhanced,
+        None => {
+            let calculator = Arc::clone(&server.calculator);
+            let enhanced =
+                tokio::task::spawn_blocking(move || calculator.calculate_all_indicators(&history))
+                    .await
+                    .map_err(|e| ApiError::internal(anyhow::anyhow!("指标计算任务失败: {}", e)))?
+                    .map_err(ApiError::internal)?;
+            match server.cache.as_ref().zip(key) {
+                Some((cache, key)) => cache.insert(key, enhanced),
+                None => Arc::new(enhanced),
+            }
+        }
+    };
+
+    Ok(Json(
+        enhanced
+            .iter()
+            .filter(|r| query.contains(r.date()))
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// `GET /stats`
+async fn stats(
+    State(server): State<HttpServer>,
+) -> std::result::Result<Json<StoreStats>, ApiError> {
+    let store = server.read()?;
+    Ok(Json(StoreStats {
+        symbols: store.daily.len(),
+        daily_records: store.daily.values().map(Vec::len).sum(),
+        minute_records: store.minutes.values().map(Vec::len).sum(),
+        earliest_date: store
+            .daily
+            .values()
+            .filter_map(|s| s.first())
+            .map(|r| r.date)
+            .min(),
+        latest_date: store
+            .daily
+            .values()
+            .filter_map(|s| s.last())
+            .map(|r| r.date)
+            .max(),
+    }))
+}
+
+/// 将日线合成为周线/月线，日期取周期内最后一个交易日
+fn resample<K: PartialEq>(
+    daily: &[TDXDayRecord],
+    period: impl Fn(NaiveDate) -> K,
+) -> Vec<TDXDayRecord> {
+    let mut bars: Vec<TDXDayRecord> = Vec::new();
+    let mut current: Option<K> = None;
+    for record in daily {
+        let key = period(record.date);
+        match bars.last_mut() {
+            Some(bar) if current.as_ref() == Some(&key) => {
+                bar.date = record.date;
+                bar.high = bar.high.max(record.high);
+                bar.low = bar.low.min(record.low);
+                bar.close = record.close;
+                bar.volume += record.volume;
+                bar.amount += record.amount;
+            }
+            _ => {
+                bars.push(record.clone());
+                current = Some(key);
+            }
+        }
+    }
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_record(symbol: &str, market: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close + 0.5,
+            low: close - 0.5,
+            close,
+            volume: 1000,
+            amount: close * 1000.0,
+            market: market.to_string(),
+        }
+    }
+
+    fn store() -> MarketDataStore {
+        let mut records: Vec<TDXDayRecord> = (2..=12)
+            .map(|day| create_test_record("600000", "SH", day, 10.0 + day as f64 * 0.1))
+            .collect();
+        records.push(create_test_record("000001", "SH", 2, 3000.0));
+        records.push(create_test_record("000001", "SZ", 2, 12.0));
+        MarketDataStore::from_records(records)
+    }
+
+    #[test]
+    fn test_insert_replaces_same_date() {
+        let mut store = store();
+        store.insert_daily(vec![create_test_record("600000", "SH", 5, 99.0)]);
+        let series = MarketDataStore::resolve(&store.daily, "600000", None).unwrap();
+        assert_eq!(series.len(), 11);
+        assert_eq!(series[3].close, 99.0);
+        // 代码对应多个市场时需要指定market
+        assert!(MarketDataStore::resolve(&store.daily, "000001", None).is_err());
+        assert!(MarketDataStore::resolve(&store.daily, "000001", Some("sz")).is_ok());
+    }
+
+    #[test]
+    fn test_resample_weekly() {
+        let daily: Vec<TDXDayRecord> = (2..=12)
+            .map(|day| create_test_record("600000", "SH", day, day as f64))
+            .collect();
+        // 2024-01-02 ~ 01-07 为第1周，01-08 ~ 01-12 为第2周
+        let weekly = resample(&daily, |d| (d.iso_week().year(), d.iso_week().week()));
+        assert_eq!(weekly.len(), 2);
+        assert_eq!(weekly[0].open, 2.0);
+        assert_eq!(weekly[0].close, 7.0);
+        assert_eq!(weekly[0].date, NaiveDate::from_ymd_opt(2024, 1, 7).unwrap());
+        assert_eq!(weekly[1].volume, 5000);
+    }
+
+    #[tokio::test]
+    async fn test_serve_with_shutdown() {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let shutdown = Shutdown::new();
+        let server =
+            tokio::spawn(HttpServer::new(store()).serve_with_shutdown(addr, shutdown.clone()));
+
+        let base = format!("http://{}", addr);
+        let mut ready = false;
+        for _ in 0..50 {
+            if reqwest::get(format!("{}/stats", base)).await.is_ok() {
+                ready = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(ready);
+
+        shutdown.trigger();
+        tokio::time::timeout(std::time::Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_http_endpoints() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let cache = Arc::new(ResultCache::new());
+        let router = HttpServer::new(store())
+            .with_result_cache(Arc::clone(&cache))
+            .router();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let base = format!("http://{}", addr);
+        let stats: StoreStats = reqwest::get(format!("{}/stats", base))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(stats.symbols, 3);
+        assert_eq!(stats.daily_records, 13);
+
+        let bars: Vec<TDXDayRecord> =
+            reqwest::get(format!("{}/bars/600000?start=2024-01-08&tf=1d", base))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        assert_eq!(bars.len(), 5);
+
+        let response = reqwest::get(format!("{}/bars/000001", base)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let indicators: Vec<EnhancedDayRecord> =
+            reqwest::get(format!("{}/indicators/600000?start=2024-01-12", base))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        assert_eq!(indicators.len(), 1);
+        assert!(indicators[0].indicators.ma5.is_some());
+
+        // 第二次查询命中缓存
+        let cached: Vec<EnhancedDayRecord> =
+            reqwest::get(format!("{}/indicators/600000?start=2024-01-12", base))
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cache.stats().hits, 1);
+    }
+}
@@ -0,0 +1,11 @@
+//! 服务接口模块（需启用 `serve` 特性）
+//!
+//! 通过网络对外提供解析和处理结果，前端与其他服务无需轮询数据文件。
+
+pub mod grpc;
+pub mod http;
+pub mod ws;
+
+pub use grpc::ProcessingService;
+pub use http::{HttpServer, MarketDataStore, RangeQuery, StoreStats, SymbolSummary};
+pub use ws::{ClientMessage, StreamMessage, Subscription, WsBroadcaster};
@@ -0,0 +1,304 @@
+//! WebSocket推送服务
+//!
+//! 将新解析的K线、计算出的技术指标和涨跌停事件以JSON消息广播给客户端。
+//! 客户端通过订阅消息选择关注的股票：
+//!
+//! ```json
+//! {"action": "subscribe", "symbols": ["600000", "000001"]}
+//! {"action": "unsubscribe", "symbols": ["000001"]}
+//! {"action": "subscribe_all"}
+//! ```
+
+use crate::parsers::{TDXDayRecord, TDXMinuteRecord};
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::processors::LimitEvent;
+use crate::shutdown::Shutdown;
+use crate::watcher::FileUpdate;
+use anyhow::{Context, Result};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+
+/// 推送给客户端的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum StreamMessage {
+    /// 日线
+    Bar(TDXDayRecord),
+    /// 分钟线
+    MinuteBar(TDXMinuteRecord),
+    /// 带技术指标的日线
+    Indicator(Box<EnhancedDayRecord>),
+    /// 涨跌停事件
+    LimitEvent(LimitEvent),
+}
+
+impl StreamMessage {
+    /// 消息所属股票代码
+    pub fn symbol(&self) -> &str {
+        match self {
+            StreamMessage::Bar(record) => &record.symbol,
+            StreamMessage::MinuteBar(record) => &record.symbol,
+            StreamMessage::Indicator(record) => record.symbol(),
+            StreamMessage::LimitEvent(event) => &event.symbol,
+        }
+    }
+}
+
+/// 客户端订阅消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// 订阅股票
+    Subscribe { symbols: Vec<String> },
+    /// 取消订阅
+    Unsubscribe { symbols: Vec<String> },
+    /// 订阅全部股票
+    SubscribeAll,
+    /// 取消全部订阅
+    UnsubscribeAll,
+}
+
+/// 单个客户端的订阅状态
+#[derive(Debug, Clone, Default)]
+pub struct Subscription {
+    /// 是否订阅全部
+    all: bool,
+    /// 订阅的股票
+    symbols: HashSet<String>,
+}
+
+impl Subscription {
+    /// 应用订阅消息
+    pub fn apply(&mut self, message: ClientMessage) {
+        match message {
+            ClientMessage::Subscribe { symbols } => self.symbols.extend(symbols),
+            ClientMessage::Unsubscribe { symbols } => {
+                for symbol in symbols {
+                    self.symbols.remove(&symbol);
+                }
+            }
+            ClientMessage::SubscribeAll => self.all = true,
+            ClientMessage::UnsubscribeAll => {
+                self.all = false;
+                self.symbols.clear();
+            }
+        }
+    }
+
+    /// 是否订阅了该股票
+    pub fn matches(&self, symbol: &str) -> bool {
+        self.all || self.symbols.contains(symbol)
+    }
+}
+
+/// WebSocket广播器
+#[derive(Debug, Clone)]
+pub struct WsBroadcaster {
+    /// 广播通道
+    sender: broadcast::Sender<Arc<StreamMessage>>,
+}
+
+impl Default for WsBroadcaster {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl WsBroadcaster {
+    /// 创建广播器，`capacity` 为每个客户端可积压的消息数
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// 广播消息，返回当前连接的客户端数
+    pub fn publish(&self, message: StreamMessage) -> usize {
+        self.sender.send(Arc::new(message)).unwrap_or(0)
+    }
+
+    /// 当前连接的客户端数
+    pub fn client_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// 将目录监控的增量更新转发为日线消息
+    pub fn forward_updates(&self, mut updates: mpsc::Receiver<FileUpdate>) -> JoinHandle<()> {
+        let broadcaster = self.clone();
+        tokio::spawn(async move {
+            while let Some(update) = updates.recv().await {
+                for record in update.records {
+                    broadcaster.publish(StreamMessage::Bar(record));
+                }
+            }
+        })
+    }
+
+    /// 路由（`/ws`），可合并到其他axum应用中
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/ws", get(upgrade))
+            .with_state(self.clone())
+    }
+
+    /// 在指定地址启动服务
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        self.serve_with_shutdown(addr, Shutdown::new()).await
+    }
+
+    /// 启动服务，收到停止信号后不再接受新连接，等待已有连接结束后返回
+    pub async fn serve_with_shutdown(self, addr: SocketAddr, shutdown: Shutdown) -> Result<()> {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("无法监听地址: {}", addr))?;
+        info!("WebSocket服务已启动: ws://{}/ws", addr);
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(async move { shutdown.wait().await })
+            .await
+            .context("WebSocket服务异常退出")?;
+        info!("WebSocket服务已停止");
+        Ok(())
+    }
+}
+
+/// 升级为WebSocket连接
+async fn upgrade(
+    ws: WebSocketUpgrade,
+    State(broadcaster): State<WsBroadcaster>,
+) -> impl IntoResponse {
+    let receiver = broadcaster.sender.subscribe();
+    ws.on_upgrade(move |socket| handle_socket(socket, receiver))
+}
+
+/// 处理单个客户端连接
+async fn handle_socket(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<Arc<StreamMessage>>,
+) {
+    let mut subscription = Subscription::default();
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(message) => subscription.apply(message),
+                        Err(e) => {
+                            let reply = serde_json::json!({ "error": format!("无效的订阅消息: {}", e) });
+                            if socket.send(Message::Text(reply.to_string().into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    debug!("WebSocket接收失败: {}", e);
+                    break;
+                }
+            },
+            message = receiver.recv() => match message {
+                Ok(message) => {
+                    if !subscription.matches(message.symbol()) {
+                        continue;
+                    }
+                    let text = match serde_json::to_string(message.as_ref()) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            warn!("消息序列化失败: {}", e);
+                            continue;
+                        }
+                    };
+                    if socket.send(Message::Text(text.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("客户端处理过慢，丢弃{}条消息", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn record(symbol: &str) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 10.5,
+            low: 9.8,
+            close: 10.2,
+            volume: 1000,
+            amount: 10_200.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_subscription() {
+        let mut subscription = Subscription::default();
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"action":"subscribe","symbols":["600000","600036"]}"#)
+                .unwrap();
+        subscription.apply(message);
+        assert!(subscription.matches("600000"));
+        assert!(!subscription.matches("000001"));
+
+        subscription.apply(ClientMessage::Unsubscribe {
+            symbols: vec!["600000".to_string()],
+        });
+        assert!(!subscription.matches("600000"));
+
+        subscription.apply(ClientMessage::SubscribeAll);
+        assert!(subscription.matches("000001"));
+    }
+
+    #[test]
+    fn test_message_format() {
+        let json = serde_json::to_value(StreamMessage::Bar(record("600000"))).unwrap();
+        assert_eq!(json["type"], "bar");
+        assert_eq!(json["data"]["symbol"], "600000");
+        assert_eq!(StreamMessage::Bar(record("600036")).symbol(), "600036");
+    }
+
+    #[tokio::test]
+    async fn test_forward_updates() {
+        let broadcaster = WsBroadcaster::default();
+        let mut receiver = broadcaster.sender.subscribe();
+        assert_eq!(broadcaster.client_count(), 1);
+
+        let (tx, rx) = mpsc::channel(4);
+        let task = broadcaster.forward_updates(rx);
+        tx.send(FileUpdate {
+            path: "sh/lday/600000.day".into(),
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            rewritten: false,
+            records: vec![record("600000")],
+        })
+        .await
+        .unwrap();
+        drop(tx);
+        task.await.unwrap();
+
+        let message = receiver.recv().await.unwrap();
+        assert_eq!(message.symbol(), "600000");
+    }
+}
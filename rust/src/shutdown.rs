@@ -0,0 +1,102 @@
+//! 优雅停止
+//!
+//! `Shutdown` 是可克隆的停止信号，`listen` 在收到SIGINT/SIGTERM（Windows下为Ctrl-C）时触发。
+//! 流水线在每批写入、刷新并保存检查点之后检查信号，收到后不再开始新批次，
+//! 服务接口的 `serve_with_shutdown` 停止接受新连接并等待已有请求完成。
+//! 已触发后再次收到信号时立即以退出码130结束进程，避免卡住的任务无法终止。
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+#[derive(Debug, Default)]
+struct Inner {
+    triggered: AtomicBool,
+    notify: Notify,
+}
+
+/// 停止信号，克隆后共享同一状态
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+impl Shutdown {
+    /// 创建只能手动触发的停止信号
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建停止信号并注册SIGINT/SIGTERM处理，每个进程只能注册一次
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn listen() -> Result<Self> {
+        let shutdown = Self::new();
+        let handler = shutdown.clone();
+        ctrlc::set_handler(move || {
+            if handler.is_triggered() {
+                log::warn!("再次收到停止信号，立即退出");
+                std::process::exit(130);
+            }
+            log::warn!("收到停止信号，完成当前批次后退出");
+            handler.trigger();
+        })
+        .map_err(|e| anyhow::anyhow!("注册信号处理失败: {}", e))?;
+        Ok(shutdown)
+    }
+
+    /// 触发停止
+    pub fn trigger(&self) {
+        self.inner.triggered.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// 是否已触发
+    pub fn is_triggered(&self) -> bool {
+        self.inner.triggered.load(Ordering::SeqCst)
+    }
+
+    /// 已触发时返回错误，便于在循环中用 `?` 提前结束
+    pub fn check(&self) -> Result<()> {
+        if self.is_triggered() {
+            return Err(anyhow::anyhow!("已收到停止信号"));
+        }
+        Ok(())
+    }
+
+    /// 等待触发，可直接传给 `axum::serve(..).with_graceful_shutdown`
+    pub async fn wait(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // 先登记再检查，避免检查与等待之间的触发被漏掉
+        notified.as_mut().enable();
+        if self.is_triggered() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trigger_wakes_waiters() {
+        let shutdown = Shutdown::new();
+        assert!(shutdown.check().is_ok());
+
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.wait().await }
+        });
+        tokio::task::yield_now().await;
+        shutdown.trigger();
+        waiter.await.unwrap();
+
+        assert!(shutdown.is_triggered());
+        assert!(shutdown.check().is_err());
+        // 触发后再等待立即返回
+        shutdown.wait().await;
+    }
+}
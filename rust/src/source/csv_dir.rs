@@ -0,0 +1,157 @@
+//! CSV目录数据源
+//!
+//! 目录结构为 `{root}/{sh,sz}/{代码}.csv`，每个文件包含表头
+//! `date,open,high,low,close,volume,amount`，日期格式为 `YYYY-MM-DD`。
+
+use super::{DataSource, DateRange};
+use crate::parsers::TDXDayRecord;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// CSV文件中的一行
+#[derive(Debug, Deserialize)]
+struct CsvBar {
+    date: NaiveDate,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: u64,
+    amount: f64,
+}
+
+/// CSV目录数据源
+#[derive(Debug, Clone)]
+pub struct CsvSource {
+    root: PathBuf,
+}
+
+impl CsvSource {
+    /// 创建数据源，`root` 下按市场分子目录存放CSV文件
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn file_path(&self, symbol: &str, market: &str) -> PathBuf {
+        self.root
+            .join(market.to_lowercase())
+            .join(format!("{}.csv", symbol))
+    }
+}
+
+impl DataSource for CsvSource {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        let mut stocks = Vec::new();
+        for market in ["sh", "sz"] {
+            let market_dir = self.root.join(market);
+            if !market_dir.is_dir() {
+                continue;
+            }
+
+            let entries = std::fs::read_dir(&market_dir)
+                .with_context(|| format!("无法读取目录: {}", market_dir.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("csv") {
+                    continue;
+                }
+                if let Some(symbol) = path.file_stem().and_then(|s| s.to_str()) {
+                    stocks.push((symbol.to_string(), market.to_uppercase()));
+                }
+            }
+        }
+
+        stocks.sort();
+        Ok(stocks)
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let path = self.file_path(symbol, market);
+        if !path.is_file() {
+            return Ok(Vec::new());
+        }
+
+        let mut reader = csv::Reader::from_path(&path)
+            .with_context(|| format!("无法打开CSV文件: {}", path.display()))?;
+        let mut bars = Vec::new();
+        for (line, row) in reader.deserialize::<CsvBar>().enumerate() {
+            let row = row.with_context(|| format!("{}第{}行解析失败", path.display(), line + 2))?;
+            if !range.contains(row.date) {
+                continue;
+            }
+            bars.push(TDXDayRecord {
+                date: row.date,
+                symbol: symbol.to_string(),
+                open: row.open,
+                high: row.high,
+                low: row.low,
+                close: row.close,
+                volume: row.volume,
+                amount: row.amount,
+                market: market.to_uppercase(),
+            });
+        }
+
+        bars.sort_by_key(|bar| bar.date);
+        Ok(bars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_csv_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let sh_dir = temp_dir.path().join("sh");
+        std::fs::create_dir_all(&sh_dir).unwrap();
+        std::fs::write(
+            sh_dir.join("600000.csv"),
+            "date,open,high,low,close,volume,amount\n\
+             2024-01-03,10.1,10.3,10.0,10.2,2000,20400\n\
+             2024-01-02,10.0,10.2,9.9,10.1,1000,10100\n",
+        )
+        .unwrap();
+        std::fs::write(sh_dir.join("notes.txt"), "").unwrap();
+
+        let source = CsvSource::new(temp_dir.path());
+        assert_eq!(
+            source.list_symbols().unwrap(),
+            vec![("600000".to_string(), "SH".to_string())]
+        );
+
+        let bars = source
+            .fetch_bars("600000", "SH", &DateRange::all())
+            .unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert_eq!(bars[1].volume, 2000);
+
+        let since = DateRange::since(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap());
+        assert_eq!(source.fetch_bars("600000", "SH", &since).unwrap().len(), 1);
+        assert!(source
+            .fetch_bars("600001", "SH", &DateRange::all())
+            .unwrap()
+            .is_empty());
+
+        std::fs::write(sh_dir.join("600001.csv"), "date,open\nbad,1\n").unwrap();
+        assert!(source
+            .fetch_bars("600001", "SH", &DateRange::all())
+            .is_err());
+    }
+}
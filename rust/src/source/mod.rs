@@ -0,0 +1,308 @@
+//! 多数据源模块
+//!
+//! `DataSource` 统一“列出股票”和“按日期范围获取日线”两种访问，
+//! 处理流水线与回测可以指向任意数据源：本地通达信文件（`TdxSource`）、
+//! CSV目录（`CsvSource`）、ClickHouse（`ClickHouseSource`，`clickhouse` 特性）
+//! 或SQLite（`SqliteSource`，`sqlite` 特性）。
+//! `FallbackSource` 按顺序组合多个数据源，前一个失败或没有数据时尝试下一个。
+
+pub mod csv_dir;
+pub mod tdx;
+
+#[cfg(feature = "clickhouse")]
+pub mod clickhouse;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+#[cfg(feature = "clickhouse")]
+pub use clickhouse::ClickHouseSource;
+pub use csv_dir::CsvSource;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSource;
+pub use tdx::TdxSource;
+
+use crate::parsers::TDXDayRecord;
+use anyhow::Result;
+use chrono::NaiveDate;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// 日期范围（两端均含，None表示不限）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateRange {
+    /// 起始日期
+    pub start: Option<NaiveDate>,
+    /// 结束日期
+    pub end: Option<NaiveDate>,
+}
+
+impl DateRange {
+    /// 不限日期
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// 指定起止日期
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Self {
+        Self {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
+
+    /// 从指定日期开始
+    pub fn since(start: NaiveDate) -> Self {
+        Self {
+            start: Some(start),
+            end: None,
+        }
+    }
+
+    /// 是否包含指定日期
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start.is_none_or(|start| date >= start) && self.end.is_none_or(|end| date <= end)
+    }
+}
+
+/// 日线数据源
+pub trait DataSource: Send + Sync {
+    /// 数据源名称（用于日志和错误信息）
+    fn name(&self) -> &str;
+
+    /// 可用的股票（代码, 市场）列表，按代码排序
+    fn list_symbols(&self) -> Result<Vec<(String, String)>>;
+
+    /// 获取股票在日期范围内的日线，按日期排序；数据源中没有该股票时返回空列表
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>>;
+}
+
+/// 按顺序组合多个数据源
+///
+/// `fetch_bars` 依次尝试各数据源，返回第一个非空结果；出错或没有数据时尝试下一个，
+/// 全部出错时返回最后一个错误。`list_symbols` 合并所有可用数据源的股票列表。
+#[derive(Clone, Default)]
+pub struct FallbackSource {
+    sources: Vec<Arc<dyn DataSource>>,
+}
+
+impl FallbackSource {
+    /// 创建空的组合数据源
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加数据源（优先级低于已添加的数据源）
+    pub fn with_source<S: DataSource + 'static>(mut self, source: S) -> Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    /// 追加共享的数据源
+    pub fn with_shared_source(mut self, source: Arc<dyn DataSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// 数据源数量
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// 是否没有数据源
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+}
+
+impl DataSource for FallbackSource {
+    fn name(&self) -> &str {
+        "fallback"
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        let mut symbols = BTreeSet::new();
+        let mut last_error = None;
+        let mut succeeded = false;
+
+        for source in &self.sources {
+            match source.list_symbols() {
+                Ok(list) => {
+                    succeeded = true;
+                    symbols.extend(list);
+                }
+                Err(e) => {
+                    warn!("数据源{}列出股票失败: {:#}", source.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !succeeded => Err(e.context("所有数据源列出股票均失败")),
+            _ => Ok(symbols.into_iter().collect()),
+        }
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let mut last_error = None;
+        let mut succeeded = false;
+
+        for source in &self.sources {
+            match source.fetch_bars(symbol, market, range) {
+                Ok(bars) if !bars.is_empty() => return Ok(bars),
+                Ok(_) => succeeded = true,
+                Err(e) => {
+                    warn!(
+                        "数据源{}获取{}.{}失败: {:#}",
+                        source.name(),
+                        symbol,
+                        market,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !succeeded => {
+                Err(e.context(format!("所有数据源获取{}.{}均失败", symbol, market)))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试用内存数据源
+    struct MemorySource {
+        name: &'static str,
+        bars: Vec<TDXDayRecord>,
+        fail: bool,
+    }
+
+    impl DataSource for MemorySource {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+            if self.fail {
+                return Err(anyhow::anyhow!("连接失败"));
+            }
+            let symbols: BTreeSet<_> = self
+                .bars
+                .iter()
+                .map(|bar| (bar.symbol.clone(), bar.market.clone()))
+                .collect();
+            Ok(symbols.into_iter().collect())
+        }
+
+        fn fetch_bars(
+            &self,
+            symbol: &str,
+            market: &str,
+            range: &DateRange,
+        ) -> Result<Vec<TDXDayRecord>> {
+            if self.fail {
+                return Err(anyhow::anyhow!("连接失败"));
+            }
+            Ok(self
+                .bars
+                .iter()
+                .filter(|bar| bar.symbol == symbol && bar.market == market)
+                .filter(|bar| range.contains(bar.date))
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn bar(symbol: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100,
+            amount: close * 100.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    fn source(name: &'static str, bars: Vec<TDXDayRecord>, fail: bool) -> MemorySource {
+        MemorySource { name, bars, fail }
+    }
+
+    #[test]
+    fn test_date_range() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        assert!(DateRange::all().contains(day(1)));
+        assert!(DateRange::new(day(2), day(3)).contains(day(3)));
+        assert!(!DateRange::new(day(2), day(3)).contains(day(4)));
+        assert!(!DateRange::since(day(2)).contains(day(1)));
+    }
+
+    #[test]
+    fn test_fallback_order() {
+        let fallback = FallbackSource::new()
+            .with_source(source("down", Vec::new(), true))
+            .with_source(source("primary", vec![bar("600000", 2, 10.0)], false))
+            .with_source(source(
+                "backup",
+                vec![bar("600000", 2, 11.0), bar("600001", 2, 5.0)],
+                false,
+            ));
+
+        // 故障数据源被跳过，取第一个有数据的
+        let bars = fallback
+            .fetch_bars("600000", "SH", &DateRange::all())
+            .unwrap();
+        assert_eq!(bars[0].close, 10.0);
+        // 优先数据源没有该股票时继续尝试
+        let bars = fallback
+            .fetch_bars("600001", "SH", &DateRange::all())
+            .unwrap();
+        assert_eq!(bars[0].close, 5.0);
+        assert!(fallback
+            .fetch_bars("600002", "SH", &DateRange::all())
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            fallback.list_symbols().unwrap(),
+            vec![
+                ("600000".to_string(), "SH".to_string()),
+                ("600001".to_string(), "SH".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fallback_all_failed() {
+        let fallback = FallbackSource::new()
+            .with_source(source("a", Vec::new(), true))
+            .with_source(source("b", Vec::new(), true));
+        assert!(fallback
+            .fetch_bars("600000", "SH", &DateRange::all())
+            .is_err());
+        assert!(fallback.list_symbols().is_err());
+        assert!(FallbackSource::new().list_symbols().unwrap().is_empty());
+    }
+}
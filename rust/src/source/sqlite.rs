@@ -0,0 +1,415 @@
+//! SQLite数据源（`sqlite` 特性）
+//!
+//! 面向单机部署：日线表（`create_table` 创建）以（代码, 市场, 日期）为主键，另有日期索引，
+//! 同一股票同一日期重复写入时以新写入的为准。数据库文件使用WAL日志模式，写入时读取不受阻塞；
+//! 批量写入在单个事务内复用预编译语句。`replace_symbol` 在一个事务中删除并写入整段历史，
+//! 读取方不会看到替换了一半的股票。
+
+use super::{DataSource, DateRange};
+use crate::parsers::TDXDayRecord;
+use crate::pipeline::PipelineSink;
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::storage::Backend;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, Row};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// SQLite日线数据源
+pub struct SqliteSource {
+    conn: Mutex<Connection>,
+    table: String,
+}
+
+impl std::fmt::Debug for SqliteSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteSource")
+            .field("table", &self.table)
+            .finish()
+    }
+}
+
+impl SqliteSource {
+    /// 打开（或创建）数据库文件并启用WAL日志模式
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .with_context(|| format!("无法打开SQLite数据库: {}", path.display()))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .context("无法启用WAL模式")?;
+        // WAL模式下NORMAL只在检查点时同步磁盘，掉电最多丢失最近的事务，不会损坏数据库
+        conn.pragma_update(None, "synchronous", "NORMAL")
+            .context("无法设置同步模式")?;
+        Ok(Self::from_connection(conn))
+    }
+
+    /// 创建内存数据库
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("无法创建SQLite内存数据库")?;
+        Ok(Self::from_connection(conn))
+    }
+
+    fn from_connection(conn: Connection) -> Self {
+        Self {
+            conn: Mutex::new(conn),
+            table: "tdx_day".to_string(),
+        }
+    }
+
+    /// 设置日线表名（默认 `tdx_day`）
+    pub fn with_table(mut self, table: &str) -> Self {
+        self.table = table.to_string();
+        self
+    }
+
+    /// 创建日线表和日期索引（已存在时不做改动）
+    pub fn create_table(&self) -> Result<()> {
+        check_table(&self.table)?;
+        self.lock()
+            .execute_batch(&format!(
+                "CREATE TABLE IF NOT EXISTS {table} (\
+                 symbol TEXT NOT NULL, market TEXT NOT NULL, date TEXT NOT NULL, \
+                 open REAL NOT NULL, high REAL NOT NULL, low REAL NOT NULL, close REAL NOT NULL, \
+                 volume INTEGER NOT NULL, amount REAL NOT NULL, \
+                 PRIMARY KEY (symbol, market, date)) WITHOUT ROWID;\
+                 CREATE INDEX IF NOT EXISTS {table}_date ON {table} (date);",
+                table = self.table
+            ))
+            .with_context(|| format!("无法创建日线表: {}", self.table))
+    }
+
+    /// 写入记录，与已有的同日记录以新写入的为准；返回写入行数
+    pub fn upsert(&self, records: &[TDXDayRecord]) -> Result<usize> {
+        let mut conn = self.lock();
+        let tx = conn.transaction()?;
+        insert_records(&tx, &self.table, records)?;
+        tx.commit().context("SQLite提交失败")?;
+        Ok(records.len())
+    }
+
+    /// 以 `records` 替换一只股票的全部历史，删除与写入在同一事务中完成；返回写入行数
+    pub fn replace_symbol(
+        &self,
+        symbol: &str,
+        market: &str,
+        records: &[TDXDayRecord],
+    ) -> Result<usize> {
+        let market = market.to_uppercase();
+        if let Some(other) = records
+            .iter()
+            .find(|r| r.symbol != symbol || r.market.to_uppercase() != market)
+        {
+            return Err(anyhow::anyhow!(
+                "替换{}.{}时包含其他股票的记录: {}.{}",
+                symbol,
+                market,
+                other.symbol,
+                other.market
+            ));
+        }
+
+        let mut conn = self.lock();
+        let tx = conn.transaction()?;
+        tx.execute(
+            &format!(
+                "DELETE FROM {} WHERE symbol = ?1 AND market = ?2",
+                self.table
+            ),
+            params![symbol, market],
+        )?;
+        insert_records(&tx, &self.table, records)?;
+        tx.commit().context("SQLite提交失败")?;
+        Ok(records.len())
+    }
+
+    /// 读取日期早于 `cutoff` 的全部记录（按日期、代码排序），用于冷热分层滚动
+    pub fn records_before(&self, cutoff: NaiveDate) -> Result<Vec<TDXDayRecord>> {
+        let conn = self.lock();
+        let mut statement = conn.prepare(&format!(
+            "{} WHERE date < ?1 ORDER BY date, symbol, market",
+            self.select_sql()
+        ))?;
+        let bars = statement
+            .query_map(params![cutoff], read_bar)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(bars)
+    }
+
+    /// 删除日期早于 `cutoff` 的记录，返回删除的行数
+    pub fn delete_before(&self, cutoff: NaiveDate) -> Result<usize> {
+        let deleted = self.lock().execute(
+            &format!("DELETE FROM {} WHERE date < ?1", self.table),
+            params![cutoff],
+        )?;
+        Ok(deleted)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn select_sql(&self) -> String {
+        format!(
+            "SELECT symbol, market, date, open, high, low, close, volume, amount FROM {}",
+            self.table
+        )
+    }
+}
+
+/// 在事务内用预编译语句逐行写入
+fn insert_records(
+    tx: &rusqlite::Transaction<'_>,
+    table: &str,
+    records: &[TDXDayRecord],
+) -> Result<()> {
+    let mut statement = tx.prepare_cached(&format!(
+        "INSERT OR REPLACE INTO {} \
+         (symbol, market, date, open, high, low, close, volume, amount) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        table
+    ))?;
+    for record in records {
+        let volume = i64::try_from(record.volume)
+            .map_err(|_| anyhow::anyhow!("成交量超出范围: {}", record.volume))?;
+        statement.execute(params![
+            record.symbol,
+            record.market.to_uppercase(),
+            record.date,
+            record.open,
+            record.high,
+            record.low,
+            record.close,
+            volume,
+            record.amount
+        ])?;
+    }
+    Ok(())
+}
+
+fn read_bar(row: &Row<'_>) -> rusqlite::Result<TDXDayRecord> {
+    Ok(TDXDayRecord {
+        symbol: row.get("symbol")?,
+        market: row.get("market")?,
+        date: row.get("date")?,
+        open: row.get("open")?,
+        high: row.get("high")?,
+        low: row.get("low")?,
+        close: row.get("close")?,
+        volume: row.get::<_, i64>("volume")? as u64,
+        amount: row.get("amount")?,
+    })
+}
+
+/// 表名直接拼入SQL，只接受字母、数字和下划线
+fn check_table(table: &str) -> Result<()> {
+    let valid = table
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!("无效的表名: {}", table));
+    }
+    Ok(())
+}
+
+impl DataSource for SqliteSource {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.lock();
+        let mut statement = conn.prepare(&format!(
+            "SELECT DISTINCT symbol, market FROM {} ORDER BY symbol, market",
+            self.table
+        ))?;
+        let symbols = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(symbols)
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        // 日期以 `YYYY-MM-DD` 文本保存，按字符串比较即按日期比较，不限时取四位年份的两端
+        let start = range
+            .start
+            .unwrap_or_else(|| NaiveDate::from_ymd_opt(1, 1, 1).unwrap());
+        let end = range
+            .end
+            .unwrap_or_else(|| NaiveDate::from_ymd_opt(9999, 12, 31).unwrap());
+        let conn = self.lock();
+        let mut statement = conn.prepare_cached(&format!(
+            "{} WHERE symbol = ?1 AND market = ?2 AND date >= ?3 AND date <= ?4 ORDER BY date",
+            self.select_sql()
+        ))?;
+        let bars = statement
+            .query_map(params![symbol, market.to_uppercase(), start, end], read_bar)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(bars)
+    }
+}
+
+/// 流水线写入：每个批次在一个事务中upsert
+impl PipelineSink for SqliteSource {
+    fn write(&mut self, batch: &[EnhancedDayRecord]) -> Result<()> {
+        let records: Vec<TDXDayRecord> = batch.iter().map(|r| r.base_record.clone()).collect();
+        self.upsert(&records).map(|_| ())
+    }
+}
+
+/// 作为存储后端：每批记录在一个事务中upsert，支持原子替换
+impl Backend<TDXDayRecord> for SqliteSource {
+    fn write_batch(&mut self, records: &[TDXDayRecord]) -> Result<()> {
+        self.upsert(records).map(|_| ())
+    }
+
+    fn replace_symbol(
+        &mut self,
+        symbol: &str,
+        market: &str,
+        records: &[TDXDayRecord],
+    ) -> Result<()> {
+        SqliteSource::replace_symbol(self, symbol, market, records).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn bar(symbol: &str, market: &str, day: u32, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            symbol: symbol.to_string(),
+            open: 10.0,
+            high: 11.0,
+            low: 9.0,
+            close,
+            volume: 1000 + day as u64,
+            amount: 10_000.0,
+            market: market.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_upsert_and_fetch() {
+        let source = SqliteSource::open_in_memory().unwrap();
+        source.create_table().unwrap();
+
+        let records: Vec<_> = (1..=10)
+            .flat_map(|day| {
+                [
+                    bar("600000", "SH", day, 10.0),
+                    bar("000001", "sz", day, 20.0),
+                ]
+            })
+            .collect();
+        assert_eq!(source.upsert(&records).unwrap(), 20);
+        // 重复写入同一日期以新写入的为准
+        source.upsert(&[bar("600000", "SH", 3, 12.5)]).unwrap();
+
+        assert_eq!(
+            source.list_symbols().unwrap(),
+            vec![
+                ("000001".to_string(), "SZ".to_string()),
+                ("600000".to_string(), "SH".to_string())
+            ]
+        );
+        let range = DateRange::new(
+            NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 4).unwrap(),
+        );
+        let bars = source.fetch_bars("600000", "sh", &range).unwrap();
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[1].close, 12.5);
+        assert_eq!(bars[1].volume, 1003);
+        assert_eq!(
+            source
+                .fetch_bars("000001", "SZ", &DateRange::all())
+                .unwrap()
+                .len(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_replace_symbol_and_rollover() {
+        let mut source = SqliteSource::open_in_memory().unwrap().with_table("bars");
+        source.create_table().unwrap();
+        source
+            .write_batch(
+                &(1..=5)
+                    .map(|day| bar("600000", "SH", day, 10.0))
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+        source.upsert(&[bar("000001", "SZ", 1, 20.0)]).unwrap();
+
+        // 新历史少了一天，旧日期一并删除
+        let adjusted: Vec<_> = (2..=5).map(|day| bar("600000", "SH", day, 5.0)).collect();
+        Backend::replace_symbol(&mut source, "600000", "SH", &adjusted).unwrap();
+        let bars = source
+            .fetch_bars("600000", "SH", &DateRange::all())
+            .unwrap();
+        assert_eq!(bars.len(), 4);
+        assert!(bars.iter().all(|b| b.close == 5.0));
+        assert!(source
+            .replace_symbol("600000", "SH", &[bar("000001", "SZ", 1, 1.0)])
+            .is_err());
+
+        let cutoff = NaiveDate::from_ymd_opt(2024, 1, 3).unwrap();
+        let old = source.records_before(cutoff).unwrap();
+        assert_eq!(
+            old.iter().map(|b| b.symbol.as_str()).collect::<Vec<_>>(),
+            vec!["000001", "600000"]
+        );
+        assert_eq!(source.delete_before(cutoff).unwrap(), 2);
+        assert_eq!(
+            source
+                .fetch_bars("000001", "SZ", &DateRange::all())
+                .unwrap()
+                .len(),
+            0
+        );
+
+        assert!(SqliteSource::open_in_memory()
+            .unwrap()
+            .with_table("bars; DROP")
+            .create_table()
+            .is_err());
+    }
+
+    #[test]
+    fn test_file_database_uses_wal() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("bars.db");
+        {
+            let source = SqliteSource::open(&path).unwrap();
+            source.create_table().unwrap();
+            let mode: String = source
+                .lock()
+                .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(mode, "wal");
+            source.upsert(&[bar("600000", "SH", 2, 10.0)]).unwrap();
+        }
+
+        let reopened = SqliteSource::open(&path).unwrap();
+        reopened.create_table().unwrap();
+        assert_eq!(
+            reopened
+                .fetch_bars("600000", "SH", &DateRange::all())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
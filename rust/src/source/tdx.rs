@@ -0,0 +1,94 @@
+//! 本地通达信文件数据源
+
+use super::{DataSource, DateRange};
+use crate::parsers::{TDXDayParser, TDXDayRecord};
+use anyhow::Result;
+use std::path::Path;
+
+/// 本地通达信数据目录（`vipdoc/{sh,sz}/day`）
+///
+/// 日期范围下推到文件读取，只解析范围内的记录。
+#[derive(Debug)]
+pub struct TdxSource {
+    parser: TDXDayParser,
+}
+
+impl TdxSource {
+    /// 创建数据源，`data_root` 为通达信数据根目录
+    pub fn new<P: AsRef<Path>>(data_root: P) -> Self {
+        Self {
+            parser: TDXDayParser::new(data_root),
+        }
+    }
+}
+
+impl DataSource for TdxSource {
+    fn name(&self) -> &str {
+        "tdx"
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        self.parser.get_stock_list()
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let mut query = self
+            .parser
+            .query()
+            .with_symbols([symbol])
+            .with_market(market);
+        if let Some(start) = range.start {
+            query = query.with_start_date(start);
+        }
+        if let Some(end) = range.end {
+            query = query.with_end_date(end);
+        }
+        query.execute()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tdx_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let day_dir = temp_dir.path().join("vipdoc/sz/day");
+        std::fs::create_dir_all(&day_dir).unwrap();
+        let mut buffer = Vec::new();
+        for date in 20240102u32..=20240105 {
+            for value in [date, 1000, 1010, 990, 1005] {
+                buffer.extend_from_slice(&value.to_le_bytes());
+            }
+            buffer.extend_from_slice(&10_050.0f32.to_le_bytes());
+            buffer.extend_from_slice(&1000u32.to_le_bytes());
+            buffer.extend_from_slice(&0u32.to_le_bytes());
+        }
+        std::fs::write(day_dir.join("000001.day"), buffer).unwrap();
+
+        let source = TdxSource::new(temp_dir.path());
+        assert_eq!(
+            source.list_symbols().unwrap(),
+            vec![("000001".to_string(), "SZ".to_string())]
+        );
+        let range = DateRange::new(
+            NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 4).unwrap(),
+        );
+        let bars = source.fetch_bars("000001", "SZ", &range).unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].close, 10.05);
+        assert!(source
+            .fetch_bars("600000", "SH", &range)
+            .unwrap()
+            .is_empty());
+    }
+}
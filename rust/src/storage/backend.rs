@@ -0,0 +1,37 @@
+//! 存储后端接口
+
+use anyhow::Result;
+
+/// 存储后端：批量写入记录
+///
+/// `FnMut(&[T]) -> Result<()>` 闭包可直接作为后端使用。
+pub trait Backend<T>: Send {
+    /// 写入一批记录
+    fn write_batch(&mut self, records: &[T]) -> Result<()>;
+
+    /// 刷新后端自身的缓冲，返回后已写入的数据应可持久读取
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// 以 `records` 原子替换一只股票的全部历史（如重新复权），读取方不会看到替换了一半的数据
+    ///
+    /// 默认不支持，返回错误。
+    fn replace_symbol(&mut self, symbol: &str, market: &str, records: &[T]) -> Result<()> {
+        let _ = records;
+        Err(anyhow::anyhow!(
+            "存储后端不支持原子替换: {}.{}",
+            symbol,
+            market
+        ))
+    }
+}
+
+impl<T, F> Backend<T> for F
+where
+    F: FnMut(&[T]) -> Result<()> + Send,
+{
+    fn write_batch(&mut self, records: &[T]) -> Result<()> {
+        self(records)
+    }
+}
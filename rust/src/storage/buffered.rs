@@ -0,0 +1,394 @@
+//! 批量写入缓冲模块
+//!
+//! 流式“解析 → 存储”时每个文件只有几千条记录，逐文件写入会产生大量小批次的INSERT。
+//! `BufferedSink` 包装任意 `Backend`，累积记录直到达到最大行数或最早一条记录的等待时间
+//! 超过上限才写入一次：行数触发时在调用 `push` 的线程上同步写入（形成背压），
+//! 时间触发由后台线程完成。`close` 停止后台线程并写完剩余记录。
+//!
+//! 写入失败时记录保留在缓冲中，下次刷新时重试；后台写入的失败原因由下一次 `push` 返回。
+
+use super::backend::Backend;
+use crate::parsers::TDXDayRecord;
+use crate::pipeline::PipelineSink;
+use crate::processors::calculator::EnhancedDayRecord;
+use anyhow::Result;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// 缓冲写入统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferedSinkStats {
+    /// 成功写入的批次数
+    pub flushes: u64,
+    /// 成功写入的记录数
+    pub rows_written: u64,
+    /// 失败的写入次数
+    pub failed_flushes: u64,
+}
+
+struct State<T> {
+    buffer: Vec<T>,
+    /// 缓冲中最早一条记录的加入时间
+    oldest: Option<Instant>,
+    /// 后台写入失败的原因，由下一次 `push` 返回
+    error: Option<anyhow::Error>,
+    shutdown: bool,
+    stats: BufferedSinkStats,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    /// 通知后台线程：有新记录或需要停止
+    wakeup: Condvar,
+    /// 持有后端锁的线程负责写入，保证批次按顺序写入
+    backend: Mutex<Box<dyn Backend<T>>>,
+    max_rows: usize,
+    max_age: Duration,
+}
+
+/// 带缓冲的存储写入
+pub struct BufferedSink<T: Send + 'static> {
+    shared: Arc<Shared<T>>,
+    flusher: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> std::fmt::Debug for BufferedSink<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferedSink")
+            .field("max_rows", &self.shared.max_rows)
+            .field("max_age", &self.shared.max_age)
+            .field("buffered", &self.buffered())
+            .finish()
+    }
+}
+
+impl<T: Send + 'static> BufferedSink<T> {
+    /// 包装后端，缓冲达到 `max_rows` 行或最早一条记录等待超过 `max_age` 时写入
+    pub fn new<B: Backend<T> + 'static>(backend: B, max_rows: usize, max_age: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                buffer: Vec::new(),
+                oldest: None,
+                error: None,
+                shutdown: false,
+                stats: BufferedSinkStats::default(),
+            }),
+            wakeup: Condvar::new(),
+            backend: Mutex::new(Box::new(backend)),
+            max_rows: max_rows.max(1),
+            max_age,
+        });
+
+        let background = Arc::clone(&shared);
+        let flusher = std::thread::spawn(move || background.run_flusher());
+        Self {
+            shared,
+            flusher: Some(flusher),
+        }
+    }
+
+    /// 加入记录，缓冲达到最大行数时在当前线程写入
+    ///
+    /// 返回错误时本次记录同样已进入缓冲，会在下次刷新时重试。
+    pub fn push<I: IntoIterator<Item = T>>(&self, records: I) -> Result<()> {
+        let (full, error) = {
+            let mut state = self.shared.lock_state();
+            let before = state.buffer.len();
+            state.buffer.extend(records);
+            if before == 0 && !state.buffer.is_empty() {
+                state.oldest = Some(Instant::now());
+                self.shared.wakeup.notify_one();
+            }
+            (
+                state.buffer.len() >= self.shared.max_rows,
+                state.error.take(),
+            )
+        };
+
+        if let Some(error) = error {
+            return Err(error.context("缓冲定时写入失败，记录已保留待重试"));
+        }
+        if full {
+            self.shared.flush_buffer()?;
+        }
+        Ok(())
+    }
+
+    /// 立即写入缓冲中的全部记录并刷新后端
+    pub fn flush(&self) -> Result<()> {
+        if let Some(error) = self.shared.lock_state().error.take() {
+            warn!("上一次缓冲写入失败，重试: {:#}", error);
+        }
+        self.shared.flush_buffer()?;
+        self.shared.lock_backend().flush()
+    }
+
+    /// 原子替换一只股票的全部历史
+    ///
+    /// 先写完缓冲中的记录，避免其中的旧数据在替换后再次写入覆盖新历史。
+    pub fn replace_symbol(&self, symbol: &str, market: &str, records: &[T]) -> Result<()> {
+        self.shared.flush_buffer()?;
+        let mut backend = self.shared.lock_backend();
+        backend.replace_symbol(symbol, market, records)?;
+        let mut state = self.shared.lock_state();
+        state.stats.flushes += 1;
+        state.stats.rows_written += records.len() as u64;
+        Ok(())
+    }
+
+    /// 缓冲中的记录数
+    pub fn buffered(&self) -> usize {
+        self.shared.lock_state().buffer.len()
+    }
+
+    /// 写入统计
+    pub fn stats(&self) -> BufferedSinkStats {
+        self.shared.lock_state().stats
+    }
+
+    /// 停止后台线程并写完剩余记录
+    pub fn close(mut self) -> Result<BufferedSinkStats> {
+        self.stop_flusher();
+        self.flush()?;
+        Ok(self.stats())
+    }
+
+    fn stop_flusher(&mut self) {
+        self.shared.lock_state().shutdown = true;
+        self.shared.wakeup.notify_all();
+        if let Some(flusher) = self.flusher.take() {
+            if flusher.join().is_err() {
+                warn!("缓冲写入后台线程异常退出");
+            }
+        }
+    }
+}
+
+impl<T: Send + 'static> Drop for BufferedSink<T> {
+    fn drop(&mut self) {
+        if self.flusher.is_none() {
+            return;
+        }
+        // 未调用close时尽力写完剩余记录
+        self.stop_flusher();
+        if let Err(e) = self.flush() {
+            warn!("释放缓冲写入时写入剩余记录失败: {:#}", e);
+        }
+    }
+}
+
+impl<T: Send + 'static> Shared<T> {
+    fn lock_state(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_backend(&self) -> MutexGuard<'_, Box<dyn Backend<T>>> {
+        self.backend.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 写入缓冲中的全部记录，失败时放回缓冲头部
+    fn flush_buffer(&self) -> Result<()> {
+        let mut backend = self.lock_backend();
+        let records = {
+            let mut state = self.lock_state();
+            state.oldest = None;
+            std::mem::take(&mut state.buffer)
+        };
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let result = backend.write_batch(&records);
+        let mut state = self.lock_state();
+        match result {
+            Ok(()) => {
+                state.stats.flushes += 1;
+                state.stats.rows_written += records.len() as u64;
+                Ok(())
+            }
+            Err(e) => {
+                state.stats.failed_flushes += 1;
+                let newer = std::mem::replace(&mut state.buffer, records);
+                state.buffer.extend(newer);
+                state.oldest = Some(Instant::now());
+                Err(e)
+            }
+        }
+    }
+
+    /// 后台线程：最早一条记录等待超过 `max_age` 时写入
+    fn run_flusher(&self) {
+        let mut state = self.lock_state();
+        loop {
+            if state.shutdown {
+                return;
+            }
+            let due = state.oldest.map(|oldest| oldest + self.max_age);
+            let now = Instant::now();
+            match due {
+                Some(due) if due <= now => {
+                    drop(state);
+                    if let Err(e) = self.flush_buffer() {
+                        warn!("缓冲定时写入失败，稍后重试: {:#}", e);
+                        self.lock_state().error = Some(e);
+                    }
+                    state = self.lock_state();
+                }
+                Some(due) => {
+                    state = self
+                        .wakeup
+                        .wait_timeout(state, due - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0;
+                }
+                None => {
+                    state = self.wakeup.wait(state).unwrap_or_else(|e| e.into_inner());
+                }
+            }
+        }
+    }
+}
+
+/// 流水线写入：批次进入缓冲，流水线刷新（保存检查点前）时写入全部缓冲
+impl PipelineSink for BufferedSink<TDXDayRecord> {
+    fn write(&mut self, batch: &[EnhancedDayRecord]) -> Result<()> {
+        self.push(batch.iter().map(|r| r.base_record.clone()))
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        BufferedSink::flush(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Written = Arc<Mutex<Vec<Vec<u32>>>>;
+
+    fn backend(written: &Written) -> impl Backend<u32> + 'static {
+        let written = Arc::clone(written);
+        move |records: &[u32]| -> Result<()> {
+            written.lock().unwrap().push(records.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_flush_on_size() {
+        let written = Written::default();
+        let sink = BufferedSink::new(backend(&written), 5, Duration::from_secs(3600));
+
+        sink.push(0..3).unwrap();
+        assert!(written.lock().unwrap().is_empty());
+        assert_eq!(sink.buffered(), 3);
+        sink.push(3..6).unwrap();
+        assert_eq!(*written.lock().unwrap(), vec![(0..6).collect::<Vec<_>>()]);
+        assert_eq!(sink.buffered(), 0);
+
+        // 关闭时写完剩余记录
+        sink.push([6]).unwrap();
+        let stats = sink.close().unwrap();
+        assert_eq!(stats.flushes, 2);
+        assert_eq!(stats.rows_written, 7);
+        assert_eq!(written.lock().unwrap()[1], vec![6]);
+    }
+
+    #[test]
+    fn test_flush_on_age() {
+        let written = Written::default();
+        let sink = BufferedSink::new(backend(&written), 1000, Duration::from_millis(20));
+
+        sink.push([1, 2]).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while written.lock().unwrap().is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(*written.lock().unwrap(), vec![vec![1, 2]]);
+        assert_eq!(sink.buffered(), 0);
+
+        // 释放时同样写完剩余记录
+        sink.push([3]).unwrap();
+        drop(sink);
+        assert_eq!(written.lock().unwrap().concat(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_failed_flush_keeps_records() {
+        let attempts = Arc::new(Mutex::new(0));
+        let written = Written::default();
+        let failing = {
+            let attempts = Arc::clone(&attempts);
+            let written = Arc::clone(&written);
+            move |records: &[u32]| -> Result<()> {
+                let mut attempts = attempts.lock().unwrap();
+                *attempts += 1;
+                if *attempts == 1 {
+                    return Err(anyhow::anyhow!("连接断开"));
+                }
+                written.lock().unwrap().push(records.to_vec());
+                Ok(())
+            }
+        };
+        let sink = BufferedSink::new(failing, 2, Duration::from_secs(3600));
+
+        assert!(sink.push([1, 2]).is_err());
+        assert_eq!(sink.buffered(), 2);
+        sink.push([3]).unwrap();
+        assert_eq!(*written.lock().unwrap(), vec![vec![1, 2, 3]]);
+
+        let stats = sink.close().unwrap();
+        assert_eq!(stats.failed_flushes, 1);
+        assert_eq!(stats.rows_written, 3);
+    }
+
+    /// 按股票保存全部记录的后端
+    #[derive(Default)]
+    struct SymbolStore {
+        rows: Arc<Mutex<Vec<(String, u32)>>>,
+    }
+
+    impl Backend<(String, u32)> for SymbolStore {
+        fn write_batch(&mut self, records: &[(String, u32)]) -> Result<()> {
+            self.rows.lock().unwrap().extend_from_slice(records);
+            Ok(())
+        }
+
+        fn replace_symbol(
+            &mut self,
+            symbol: &str,
+            _market: &str,
+            records: &[(String, u32)],
+        ) -> Result<()> {
+            let mut rows = self.rows.lock().unwrap();
+            rows.retain(|(s, _)| s != symbol);
+            rows.extend_from_slice(records);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_replace_symbol_flushes_first() {
+        let store = SymbolStore::default();
+        let rows = Arc::clone(&store.rows);
+        let sink = BufferedSink::new(store, 100, Duration::from_secs(3600));
+
+        let row = |s: &str, v| (s.to_string(), v);
+        sink.push([row("600000", 1), row("000001", 2)]).unwrap();
+        sink.replace_symbol("600000", "SH", &[row("600000", 10), row("600000", 11)])
+            .unwrap();
+        assert_eq!(sink.buffered(), 0);
+        assert_eq!(
+            *rows.lock().unwrap(),
+            vec![row("000001", 2), row("600000", 10), row("600000", 11)]
+        );
+
+        // 闭包后端不支持原子替换
+        let written = Written::default();
+        let plain = BufferedSink::new(backend(&written), 100, Duration::from_secs(3600));
+        assert!(plain.replace_symbol("600000", "SH", &[1]).is_err());
+    }
+}
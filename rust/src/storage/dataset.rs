@@ -0,0 +1,690 @@
+//! 分区Parquet数据集模块
+//!
+//! 以Hive风格的目录导出数据集：`market=SH/year=2024/part-00000.parquet`，根目录的
+//! `_manifest.json` 记录结构版本以及各分区的文件、记录数、日期范围和写入参数。
+//! 分区列（市场、年份）只体现在目录名中，数据文件不再保存 `market` 列，
+//! Spark和DuckDB（`hive_partitioning`）可直接按目录裁剪分区。
+//!
+//! 分区内按代码、日期排序，行组的统计信息可用于按代码过滤。追加模式只写入清单中
+//! 尚不存在的分区，已有分区保持不变；覆盖模式先删除原有的全部分区。
+
+use crate::export::{RotatingParquetWriter, RotationPolicy};
+use crate::parsers::TDXDayRecord;
+use crate::processors::calculator::{EnhancedDayRecord, IndicatorValues};
+use crate::storage::schema::{self, RecordSchema, CURRENT_SCHEMA_VERSION};
+use crate::storage::snapshot::{build_batch, read_batch};
+use anyhow::{Context, Result};
+use arrow_array::{ArrayRef, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 数据集清单文件名
+pub const DATASET_MANIFEST_FILE: &str = "_manifest.json";
+
+/// Parquet压缩方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParquetCompression {
+    /// 不压缩
+    Uncompressed,
+    /// Snappy（Spark默认）
+    Snappy,
+    /// zstd，参数为压缩级别（1-22）
+    Zstd(i32),
+}
+
+impl Default for ParquetCompression {
+    fn default() -> Self {
+        Self::Zstd(3)
+    }
+}
+
+impl ParquetCompression {
+    fn to_parquet(self) -> Result<Compression> {
+        Ok(match self {
+            Self::Uncompressed => Compression::UNCOMPRESSED,
+            Self::Snappy => Compression::SNAPPY,
+            Self::Zstd(level) => Compression::ZSTD(
+                ZstdLevel::try_new(level)
+                    .map_err(|e| anyhow::anyhow!("无效的压缩级别 {}: {}", level, e))?,
+            ),
+        })
+    }
+}
+
+/// 写入模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteMode {
+    /// 删除原有分区后重新写入
+    #[default]
+    Overwrite,
+    /// 只写入清单中尚不存在的分区
+    Append,
+}
+
+/// 数据集中的一个分区
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetPartition {
+    /// 市场
+    pub market: String,
+    /// 年份
+    pub year: i32,
+    /// 相对数据集根目录的路径，如 `market=SH/year=2024`
+    pub path: String,
+    /// 数据文件名
+    pub files: Vec<String>,
+    /// 记录数
+    pub record_count: usize,
+    /// 股票数
+    pub symbol_count: usize,
+    /// 最早日期
+    pub start_date: NaiveDate,
+    /// 最晚日期
+    pub end_date: NaiveDate,
+    /// 压缩方式
+    pub compression: ParquetCompression,
+    /// 行组行数
+    pub row_group_size: usize,
+    /// 写入时间
+    pub written_at: DateTime<Utc>,
+}
+
+/// 数据集清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetManifest {
+    /// 结构版本
+    pub schema_version: u32,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 最后写入时间
+    pub updated_at: DateTime<Utc>,
+    /// 是否包含技术指标列
+    pub include_indicators: bool,
+    /// 记录总数
+    pub record_count: usize,
+    /// 分区（按路径排序）
+    pub partitions: Vec<DatasetPartition>,
+}
+
+impl DatasetManifest {
+    /// 读取数据集清单
+    pub fn read<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let path = dir.as_ref().join(DATASET_MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("无法读取数据集清单: {}", path.display()))?;
+        let manifest: Self = serde_json::from_str(&content)
+            .with_context(|| format!("数据集清单格式错误: {}", path.display()))?;
+
+        if manifest.schema_version > CURRENT_SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "数据集结构版本{}高于当前支持的版本{}",
+                manifest.schema_version,
+                CURRENT_SCHEMA_VERSION
+            ));
+        }
+        Ok(manifest)
+    }
+
+    /// 查找分区
+    pub fn partition(&self, market: &str, year: i32) -> Option<&DatasetPartition> {
+        self.partitions
+            .iter()
+            .find(|p| p.market == market && p.year == year)
+    }
+}
+
+/// 一次写入的结果
+#[derive(Debug, Clone)]
+pub struct DatasetWriteReport {
+    /// 本次写入的分区路径
+    pub written: Vec<String>,
+    /// 追加模式下因已存在而跳过的分区路径
+    pub skipped: Vec<String>,
+    /// 写入后的清单
+    pub manifest: DatasetManifest,
+}
+
+type Row<'a> = (&'a TDXDayRecord, Option<&'a IndicatorValues>);
+
+/// 分区数据集写入器
+#[derive(Debug, Clone)]
+pub struct DatasetWriter {
+    compression: ParquetCompression,
+    /// 每个行组的行数
+    row_group_size: usize,
+    /// 单个数据文件的最大行数，超过时拆分为多个 `part-*` 文件
+    max_rows_per_file: usize,
+    /// 单个数据文件的最大字节数（按已写入和缓冲中的估算大小）
+    max_bytes_per_file: Option<u64>,
+    mode: WriteMode,
+}
+
+impl Default for DatasetWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatasetWriter {
+    /// 创建写入器：zstd(3)压缩，行组131072行，单文件最多100万行，覆盖模式
+    pub fn new() -> Self {
+        Self {
+            compression: ParquetCompression::default(),
+            row_group_size: 131_072,
+            max_rows_per_file: 1_000_000,
+            max_bytes_per_file: None,
+            mode: WriteMode::Overwrite,
+        }
+    }
+
+    /// 设置压缩方式
+    pub fn with_compression(mut self, compression: ParquetCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// 设置行组行数
+    pub fn with_row_group_size(mut self, row_group_size: usize) -> Self {
+        self.row_group_size = row_group_size.max(1);
+        self
+    }
+
+    /// 设置单个数据文件的最大行数
+    pub fn with_max_rows_per_file(mut self, max_rows_per_file: usize) -> Self {
+        self.max_rows_per_file = max_rows_per_file.max(1);
+        self
+    }
+
+    /// 设置单个数据文件的最大字节数，与行数上限同时生效
+    pub fn with_max_bytes_per_file(mut self, max_bytes_per_file: u64) -> Self {
+        self.max_bytes_per_file = Some(max_bytes_per_file.max(1));
+        self
+    }
+
+    /// 设置写入模式
+    pub fn with_mode(mut self, mode: WriteMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 写入不含指标的数据集
+    pub fn write<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        records: &[TDXDayRecord],
+    ) -> Result<DatasetWriteReport> {
+        let rows = records.iter().map(|record| (record, None)).collect();
+        self.write_rows(dir.as_ref(), rows, false)
+    }
+
+    /// 写入包含技术指标的数据集
+    pub fn write_enhanced<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        records: &[EnhancedDayRecord],
+    ) -> Result<DatasetWriteReport> {
+        let rows = records
+            .iter()
+            .map(|record| (&record.base_record, Some(&record.indicators)))
+            .collect();
+        self.write_rows(dir.as_ref(), rows, true)
+    }
+
+    fn write_rows(
+        &self,
+        dir: &Path,
+        rows: Vec<Row<'_>>,
+        include_indicators: bool,
+    ) -> Result<DatasetWriteReport> {
+        // 覆盖模式会先删除原有分区，参数错误须在此之前发现
+        self.compression.to_parquet()?;
+        fs::create_dir_all(dir)
+            .with_context(|| format!("无法创建数据集目录: {}", dir.display()))?;
+
+        let now = Utc::now();
+        let existing = match self.mode {
+            WriteMode::Append if dir.join(DATASET_MANIFEST_FILE).exists() => {
+                let manifest = DatasetManifest::read(dir)?;
+                if manifest.include_indicators != include_indicators
+                    || manifest.schema_version != CURRENT_SCHEMA_VERSION
+                {
+                    return Err(anyhow::anyhow!(
+                        "追加的数据与已有数据集结构不一致: {}",
+                        dir.display()
+                    ));
+                }
+                Some(manifest)
+            }
+            WriteMode::Append => None,
+            WriteMode::Overwrite => {
+                remove_partitions(dir)?;
+                None
+            }
+        };
+        let mut manifest = existing.unwrap_or_else(|| DatasetManifest {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            created_at: now,
+            updated_at: now,
+            include_indicators,
+            record_count: 0,
+            partitions: Vec::new(),
+        });
+
+        let mut groups: BTreeMap<(String, i32), Vec<Row<'_>>> = BTreeMap::new();
+        for row in rows {
+            let market = row.0.market.to_uppercase();
+            if market.is_empty() || !market.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(anyhow::anyhow!("无效的市场: {}", row.0.market));
+            }
+            groups
+                .entry((market, row.0.date.year()))
+                .or_default()
+                .push(row);
+        }
+
+        let mut written = Vec::new();
+        let mut skipped = Vec::new();
+        for ((market, year), rows) in groups {
+            let path = partition_path(&market, year);
+            if manifest.partition(&market, year).is_some() {
+                skipped.push(path);
+                continue;
+            }
+            let partition = self.write_partition(dir, &market, year, rows, include_indicators)?;
+            manifest.partitions.push(partition);
+            written.push(path);
+        }
+
+        manifest.partitions.sort_by(|a, b| a.path.cmp(&b.path));
+        manifest.record_count = manifest.partitions.iter().map(|p| p.record_count).sum();
+        manifest.updated_at = now;
+
+        // 清单最后写入并整体替换，中途失败时清单中不会出现写了一半的分区
+        let manifest_path = dir.join(DATASET_MANIFEST_FILE);
+        let temp_path = dir.join(format!("{}.tmp", DATASET_MANIFEST_FILE));
+        fs::write(&temp_path, serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| format!("无法写入数据集清单: {}", temp_path.display()))?;
+        fs::rename(&temp_path, &manifest_path)
+            .with_context(|| format!("无法写入数据集清单: {}", manifest_path.display()))?;
+
+        Ok(DatasetWriteReport {
+            written,
+            skipped,
+            manifest,
+        })
+    }
+
+    fn write_partition(
+        &self,
+        dir: &Path,
+        market: &str,
+        year: i32,
+        mut rows: Vec<Row<'_>>,
+        include_indicators: bool,
+    ) -> Result<DatasetPartition> {
+        let path = partition_path(market, year);
+        let partition_dir = dir.join(&path);
+        // 清单中没有该分区，目录中的文件只可能是上次中断留下的
+        if partition_dir.exists() {
+            fs::remove_dir_all(&partition_dir)
+                .with_context(|| format!("无法清理分区目录: {}", partition_dir.display()))?;
+        }
+        fs::create_dir_all(&partition_dir)
+            .with_context(|| format!("无法创建分区目录: {}", partition_dir.display()))?;
+
+        rows.sort_by(|(a, _), (b, _)| a.symbol.cmp(&b.symbol).then(a.date.cmp(&b.date)));
+
+        let schema = RecordSchema::current(include_indicators).arrow_schema();
+        let market_index = schema.index_of("market")?;
+        let projection: Vec<usize> = (0..schema.fields().len())
+            .filter(|&i| i != market_index)
+            .collect();
+        let properties = WriterProperties::builder()
+            .set_compression(self.compression.to_parquet()?)
+            .set_max_row_group_size(self.row_group_size)
+            .build();
+
+        let mut policy = RotationPolicy::new()
+            .with_max_rows(self.max_rows_per_file)
+            .with_pattern("part-{seq}");
+        if let Some(max_bytes) = self.max_bytes_per_file {
+            policy = policy.with_max_bytes(max_bytes);
+        }
+        let file_schema = Arc::new(schema.project(&projection)?);
+        let mut writer =
+            RotatingParquetWriter::create(&partition_dir, "part", file_schema, &policy)?
+                .with_properties(properties);
+        for chunk in rows.chunks(self.row_group_size) {
+            let batch = build_batch(&schema, chunk, include_indicators)?;
+            writer.write(&batch.project(&projection)?)?;
+        }
+        // 分片记录在数据集清单中，不另写分片清单
+        let files = writer.close()?.into_iter().map(|part| part.file).collect();
+
+        let symbols: HashSet<&str> = rows.iter().map(|(r, _)| r.symbol.as_str()).collect();
+        let dates = rows.iter().map(|(r, _)| r.date);
+        Ok(DatasetPartition {
+            market: market.to_string(),
+            year,
+            path,
+            files,
+            record_count: rows.len(),
+            symbol_count: symbols.len(),
+            start_date: dates.clone().min().context("分区为空")?,
+            end_date: dates.max().context("分区为空")?,
+            compression: self.compression,
+            row_group_size: self.row_group_size,
+            written_at: Utc::now(),
+        })
+    }
+}
+
+/// 已导出的分区数据集
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    dir: PathBuf,
+    manifest: DatasetManifest,
+}
+
+impl Dataset {
+    /// 打开数据集
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest = DatasetManifest::read(&dir)?;
+        Ok(Self { dir, manifest })
+    }
+
+    /// 数据集根目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 数据集清单
+    pub fn manifest(&self) -> &DatasetManifest {
+        &self.manifest
+    }
+
+    /// 读取全部分区的日线记录
+    pub fn records(&self) -> Result<Vec<TDXDayRecord>> {
+        let mut records = Vec::with_capacity(self.manifest.record_count);
+        for partition in &self.manifest.partitions {
+            self.read_partition(partition, &mut records)?;
+        }
+        Ok(records)
+    }
+
+    /// 读取一个分区的日线记录（按代码、日期排序），分区不存在时返回空
+    pub fn partition_records(&self, market: &str, year: i32) -> Result<Vec<TDXDayRecord>> {
+        let mut records = Vec::new();
+        if let Some(partition) = self.manifest.partition(&market.to_uppercase(), year) {
+            self.read_partition(partition, &mut records)?;
+        }
+        Ok(records)
+    }
+
+    fn read_partition(
+        &self,
+        partition: &DatasetPartition,
+        records: &mut Vec<TDXDayRecord>,
+    ) -> Result<()> {
+        let stored = RecordSchema::new(
+            self.manifest.schema_version,
+            self.manifest.include_indicators,
+        )?;
+        let start = records.len();
+        for name in &partition.files {
+            let path = self.dir.join(&partition.path).join(name);
+            let file = File::open(&path)
+                .with_context(|| format!("无法打开数据文件: {}", path.display()))?;
+            for batch in ParquetRecordBatchReaderBuilder::try_new(file)?.build()? {
+                // 市场只保存在目录名中，读取时补回市场列
+                let batch = with_market_column(&batch?, &partition.market)?;
+                read_batch(&schema::upcast(&batch, &stored)?, records, None)?;
+            }
+        }
+
+        if records.len() - start != partition.record_count {
+            return Err(anyhow::anyhow!(
+                "分区{}记录数不一致: 清单{}条，数据{}条",
+                partition.path,
+                partition.record_count,
+                records.len() - start
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 分区相对路径
+fn partition_path(market: &str, year: i32) -> String {
+    format!("market={}/year={}", market, year)
+}
+
+/// 删除数据集目录下的全部分区与清单
+fn remove_partitions(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("无法读取目录: {}", dir.display()))?
+    {
+        let path = entry?.path();
+        let is_partition = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("market="));
+        if is_partition && path.is_dir() {
+            fs::remove_dir_all(&path)
+                .with_context(|| format!("无法删除分区目录: {}", path.display()))?;
+        }
+    }
+    let manifest_path = dir.join(DATASET_MANIFEST_FILE);
+    if manifest_path.exists() {
+        fs::remove_file(&manifest_path)
+            .with_context(|| format!("无法删除数据集清单: {}", manifest_path.display()))?;
+    }
+    Ok(())
+}
+
+/// 给数据批次加上取值固定的市场列
+fn with_market_column(batch: &RecordBatch, market: &str) -> Result<RecordBatch> {
+    let mut fields: Vec<Field> = batch
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.as_ref().clone())
+        .collect();
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+    fields.push(Field::new("market", DataType::Utf8, false));
+    columns.push(Arc::new(StringArray::from(vec![market; batch.num_rows()])));
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+        .map_err(|e| anyhow::anyhow!("补充市场列失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_records() -> Vec<TDXDayRecord> {
+        let start = NaiveDate::from_ymd_opt(2023, 12, 1).unwrap();
+        (0..60)
+            .flat_map(|i| {
+                [("600000", "SH"), ("600036", "SH"), ("000001", "SZ")].map(|(symbol, market)| {
+                    TDXDayRecord {
+                        date: start + chrono::Duration::days(i),
+                        symbol: symbol.to_string(),
+                        open: 10.0 + i as f64 * 0.01,
+                        high: 10.5,
+                        low: 9.5,
+                        close: 10.2 + i as f64 * 0.02,
+                        volume: 1000 + i as u64,
+                        amount: 10_000.0,
+                        market: market.to_string(),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hive_layout_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let records = create_records();
+
+        let report = DatasetWriter::new()
+            .with_compression(ParquetCompression::Snappy)
+            .with_row_group_size(16)
+            .with_max_rows_per_file(40)
+            .write(temp_dir.path(), &records)
+            .unwrap();
+        assert_eq!(
+            report.written,
+            vec![
+                "market=SH/year=2023",
+                "market=SH/year=2024",
+                "market=SZ/year=2023",
+                "market=SZ/year=2024"
+            ]
+        );
+        let manifest = &report.manifest;
+        assert_eq!(manifest.record_count, 180);
+        // 2023年12月共31天，两只沪市股票62条，拆为两个文件
+        let sh_2023 = manifest.partition("SH", 2023).unwrap();
+        assert_eq!(sh_2023.record_count, 62);
+        assert_eq!(sh_2023.symbol_count, 2);
+        assert_eq!(
+            sh_2023.files,
+            vec!["part-00000.parquet", "part-00001.parquet"]
+        );
+        assert_eq!(
+            sh_2023.end_date,
+            NaiveDate::from_ymd_opt(2023, 12, 31).unwrap()
+        );
+        assert!(temp_dir
+            .path()
+            .join("market=SZ/year=2024/part-00000.parquet")
+            .exists());
+
+        // 数据文件不包含分区列，行组按设置拆分
+        let file = File::open(
+            temp_dir
+                .path()
+                .join("market=SH/year=2023/part-00000.parquet"),
+        )
+        .unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert!(builder.schema().field_with_name("market").is_err());
+        assert_eq!(builder.metadata().num_row_groups(), 3);
+
+        let dataset = Dataset::open(temp_dir.path()).unwrap();
+        let mut restored = dataset.records().unwrap();
+        assert_eq!(restored.len(), 180);
+        let sz = dataset.partition_records("sz", 2024).unwrap();
+        assert_eq!(sz.len(), 29);
+        assert!(sz.iter().all(|r| r.market == "SZ" && r.symbol == "000001"));
+
+        let key = |r: &TDXDayRecord| (r.date, r.symbol.clone());
+        let mut expected = records;
+        expected.sort_by_key(key);
+        restored.sort_by_key(key);
+        for (a, b) in restored.iter().zip(&expected) {
+            assert_eq!(
+                (a.date, &a.symbol, &a.market, a.close, a.volume),
+                (b.date, &b.symbol, &b.market, b.close, b.volume)
+            );
+        }
+    }
+
+    #[test]
+    fn test_append_writes_only_new_partitions() {
+        let temp_dir = TempDir::new().unwrap();
+        let records = create_records();
+        let (old, new): (Vec<_>, Vec<_>) =
+            records.iter().cloned().partition(|r| r.date.year() == 2023);
+
+        DatasetWriter::new().write(temp_dir.path(), &old).unwrap();
+        let first = DatasetManifest::read(temp_dir.path()).unwrap();
+
+        // 追加时2023年的分区已存在，即使数据不同也不改写
+        let mut changed = records.clone();
+        for record in &mut changed {
+            record.close = 0.0;
+        }
+        let appended: Vec<_> = changed
+            .into_iter()
+            .filter(|r| r.date.year() == 2023)
+            .chain(new)
+            .collect();
+        let report = DatasetWriter::new()
+            .with_mode(WriteMode::Append)
+            .write(temp_dir.path(), &appended)
+            .unwrap();
+        assert_eq!(
+            report.skipped,
+            vec!["market=SH/year=2023", "market=SZ/year=2023"]
+        );
+        assert_eq!(
+            report.written,
+            vec!["market=SH/year=2024", "market=SZ/year=2024"]
+        );
+        assert_eq!(report.manifest.record_count, 180);
+        assert_eq!(report.manifest.created_at, first.created_at);
+        assert_eq!(
+            report.manifest.partition("SH", 2023),
+            first.partition("SH", 2023)
+        );
+
+        let dataset = Dataset::open(temp_dir.path()).unwrap();
+        assert!(dataset
+            .partition_records("SH", 2023)
+            .unwrap()
+            .iter()
+            .all(|r| r.close > 0.0));
+
+        // 结构不一致的数据不能追加
+        let enhanced: Vec<EnhancedDayRecord> = records
+            .iter()
+            .map(|r| EnhancedDayRecord::from_record(r, IndicatorValues::default()))
+            .collect();
+        assert!(DatasetWriter::new()
+            .with_mode(WriteMode::Append)
+            .write_enhanced(temp_dir.path(), &enhanced)
+            .is_err());
+    }
+
+    #[test]
+    fn test_overwrite_removes_old_partitions() {
+        let temp_dir = TempDir::new().unwrap();
+        let records = create_records();
+        DatasetWriter::new()
+            .write(temp_dir.path(), &records)
+            .unwrap();
+
+        let sh: Vec<_> = records.into_iter().filter(|r| r.market == "SH").collect();
+        let report = DatasetWriter::new()
+            .with_compression(ParquetCompression::Uncompressed)
+            .write(temp_dir.path(), &sh)
+            .unwrap();
+        assert_eq!(report.manifest.partitions.len(), 2);
+        assert!(!temp_dir.path().join("market=SZ").exists());
+        assert_eq!(
+            Dataset::open(temp_dir.path())
+                .unwrap()
+                .records()
+                .unwrap()
+                .len(),
+            120
+        );
+
+        assert!(DatasetWriter::new()
+            .with_compression(ParquetCompression::Zstd(99))
+            .write(temp_dir.path(), &sh)
+            .is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! 数据存储模块（`storage` 特性）
+//!
+//! 提供数据集快照的导出与恢复、Hive风格分区数据集的导出、导出记录的结构版本管理、
+//! 冷热分层存储、存储后端的批量写入缓冲、计算结果的Redis发布（`redis` 特性），
+//! 以及基于DuckDB的嵌入式SQL查询（`duckdb` 特性）。
+
+pub mod backend;
+pub mod buffered;
+pub mod dataset;
+#[cfg(feature = "redis")]
+pub mod redis_pub;
+pub mod schema;
+pub mod snapshot;
+#[cfg(feature = "duckdb")]
+pub mod sql;
+pub mod tiering;
+
+pub use backend::Backend;
+pub use buffered::{BufferedSink, BufferedSinkStats};
+
+pub use dataset::{
+    Dataset, DatasetManifest, DatasetPartition, DatasetWriteReport, DatasetWriter,
+    ParquetCompression, WriteMode,
+};
+
+#[cfg(feature = "redis")]
+pub use redis_pub::{Delivery, Encoding, RedisPublisher, Topic};
+
+pub use schema::{upcast, RecordSchema, CURRENT_SCHEMA_VERSION};
+
+pub use snapshot::{Snapshot, SnapshotManifest, SnapshotWriter, SNAPSHOT_SCHEMA_VERSION};
+
+#[cfg(feature = "duckdb")]
+pub use sql::SqlEngine;
+
+pub use tiering::{ColdStore, HotStore, MaintenanceTask, RolloverReport, TieredStore};
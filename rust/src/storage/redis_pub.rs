@@ -0,0 +1,317 @@
+//! Redis发布模块（`redis` 特性）
+//!
+//! 把新计算出的日线（含指标）、指标交叉事件、涨跌停事件和交易信号发布到Redis，已经监听Redis的
+//! 服务无需新增基础设施即可消费Rust端的结果。每类事件对应一个频道/流 `{prefix}:{topic}`，
+//! 可以用PUBLISH发布到频道（订阅方不在线时消息丢失），也可以XADD追加到流
+//! （近似限制长度，消费者组可断点续读）。消息体为JSON或MessagePack（带字段名）。
+//!
+//! 连接在第一次发布时建立，发布失败后丢弃连接，下次发布时重新连接。
+
+use crate::pipeline::PipelineSink;
+use crate::processors::calculator::EnhancedDayRecord;
+use crate::processors::crossovers::CrossoverEvent;
+use crate::processors::limits::LimitEvent;
+use crate::processors::signals::Signal;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// 消息编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// JSON文本
+    #[default]
+    Json,
+    /// MessagePack（字段以名称保存）
+    MessagePack,
+}
+
+/// 投递方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Delivery {
+    /// PUBLISH到频道
+    #[default]
+    PubSub,
+    /// XADD到流，消息放在 `data` 字段，流长度近似保持在 `max_len` 以内
+    Stream {
+        /// 流的最大长度
+        max_len: usize,
+    },
+}
+
+/// 事件类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    /// 新计算出的日线（含指标）
+    Bars,
+    /// 指标交叉事件
+    Crossovers,
+    /// 涨跌停事件
+    Limits,
+    /// 交易信号
+    Signals,
+}
+
+impl Topic {
+    /// 频道/流名称中的类别部分
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topic::Bars => "bars",
+            Topic::Crossovers => "crossovers",
+            Topic::Limits => "limits",
+            Topic::Signals => "signals",
+        }
+    }
+}
+
+/// Redis事件发布器
+pub struct RedisPublisher {
+    client: redis::Client,
+    connection: Mutex<Option<redis::Connection>>,
+    prefix: String,
+    encoding: Encoding,
+    delivery: Delivery,
+    connect_timeout: Duration,
+}
+
+impl std::fmt::Debug for RedisPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisPublisher")
+            .field("prefix", &self.prefix)
+            .field("encoding", &self.encoding)
+            .field("delivery", &self.delivery)
+            .finish()
+    }
+}
+
+impl RedisPublisher {
+    /// 创建发布器，`url` 形如 `redis://:password@host:6379/0`；此时不连接服务器
+    pub fn new(url: &str) -> Result<Self> {
+        let client =
+            redis::Client::open(url).with_context(|| format!("无效的Redis地址: {}", url))?;
+        Ok(Self {
+            client,
+            connection: Mutex::new(None),
+            prefix: "pulse_trader".to_string(),
+            encoding: Encoding::default(),
+            delivery: Delivery::default(),
+            connect_timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// 设置频道/流名称前缀（默认 `pulse_trader`）
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// 设置消息编码
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// 设置投递方式
+    pub fn with_delivery(mut self, delivery: Delivery) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    /// 设置连接超时
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// 事件类别对应的频道/流名称
+    pub fn channel(&self, topic: Topic) -> String {
+        format!("{}:{}", self.prefix, topic.name())
+    }
+
+    /// 发布一批事件（同一批在一次往返中发送），返回发布的条数
+    pub fn publish<T: Serialize>(&self, topic: Topic, events: &[T]) -> Result<usize> {
+        if events.is_empty() {
+            return Ok(0);
+        }
+        let payloads = events
+            .iter()
+            .map(|event| self.encode(event))
+            .collect::<Result<Vec<_>>>()?;
+        let pipeline = self.build_pipeline(topic, &payloads);
+
+        let mut connection = self.lock();
+        if connection.is_none() {
+            *connection = Some(
+                self.client
+                    .get_connection_with_timeout(self.connect_timeout)
+                    .context("无法连接Redis")?,
+            );
+        }
+        let result: redis::RedisResult<()> = pipeline.query(connection.as_mut().unwrap());
+        if let Err(e) = result {
+            // 连接可能已失效，下次发布时重新连接
+            *connection = None;
+            return Err(anyhow::anyhow!("发布到{}失败: {}", self.channel(topic), e));
+        }
+        Ok(events.len())
+    }
+
+    /// 发布新计算出的日线
+    pub fn publish_bars(&self, records: &[EnhancedDayRecord]) -> Result<usize> {
+        self.publish(Topic::Bars, records)
+    }
+
+    /// 发布指标交叉事件
+    pub fn publish_crossovers(&self, events: &[CrossoverEvent]) -> Result<usize> {
+        self.publish(Topic::Crossovers, events)
+    }
+
+    /// 发布涨跌停事件
+    pub fn publish_limit_events(&self, events: &[LimitEvent]) -> Result<usize> {
+        self.publish(Topic::Limits, events)
+    }
+
+    /// 发布交易信号
+    pub fn publish_signals(&self, signals: &[Signal]) -> Result<usize> {
+        self.publish(Topic::Signals, signals)
+    }
+
+    fn encode<T: Serialize>(&self, event: &T) -> Result<Vec<u8>> {
+        match self.encoding {
+            Encoding::Json => serde_json::to_vec(event).context("事件JSON编码失败"),
+            Encoding::MessagePack => {
+                rmp_serde::to_vec_named(event).context("事件MessagePack编码失败")
+            }
+        }
+    }
+
+    fn build_pipeline(&self, topic: Topic, payloads: &[Vec<u8>]) -> redis::Pipeline {
+        let channel = self.channel(topic);
+        let mut pipeline = redis::pipe();
+        for payload in payloads {
+            match self.delivery {
+                Delivery::PubSub => {
+                    pipeline.cmd("PUBLISH").arg(&channel).arg(payload).ignore();
+                }
+                Delivery::Stream { max_len } => {
+                    pipeline
+                        .cmd("XADD")
+                        .arg(&channel)
+                        .arg("MAXLEN")
+                        .arg("~")
+                        .arg(max_len)
+                        .arg("*")
+                        .arg("data")
+                        .arg(payload)
+                        .ignore();
+                }
+            }
+        }
+        pipeline
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<redis::Connection>> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 流水线写入：每个批次的日线发布到 `bars` 频道/流
+impl PipelineSink for RedisPublisher {
+    fn write(&mut self, batch: &[EnhancedDayRecord]) -> Result<()> {
+        self.publish_bars(batch).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::limits::{Board, LimitKind};
+    use chrono::NaiveDate;
+
+    fn limit_event() -> LimitEvent {
+        LimitEvent {
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            kind: LimitKind::LimitUp,
+            board: Board::Main,
+            limit_percent: 10.0,
+            prev_close: 10.0,
+            limit_price: 11.0,
+            close: 11.0,
+        }
+    }
+
+    fn contains(haystack: &[u8], needle: &str) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle.as_bytes())
+    }
+
+    #[test]
+    fn test_encoding() {
+        let publisher = RedisPublisher::new("redis://127.0.0.1:6379").unwrap();
+        let json = publisher.encode(&limit_event()).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["kind"], "LimitUp");
+        assert_eq!(value["date"], "2024-01-02");
+
+        let publisher = publisher.with_encoding(Encoding::MessagePack);
+        let packed = publisher.encode(&limit_event()).unwrap();
+        let decoded: LimitEvent = rmp_serde::from_slice(&packed).unwrap();
+        assert_eq!(decoded.symbol, "600000");
+        assert_eq!(decoded.kind, LimitKind::LimitUp);
+        assert!(packed.len() < json.len());
+    }
+
+    #[test]
+    fn test_pipeline_commands() {
+        let publisher = RedisPublisher::new("redis://127.0.0.1:6379")
+            .unwrap()
+            .with_prefix("pt");
+        assert_eq!(publisher.channel(Topic::Crossovers), "pt:crossovers");
+
+        let payloads = vec![b"{}".to_vec(), b"[]".to_vec()];
+        let packed = publisher
+            .build_pipeline(Topic::Limits, &payloads)
+            .get_packed_pipeline();
+        assert!(contains(&packed, "PUBLISH"));
+        assert!(contains(&packed, "pt:limits"));
+        assert!(!contains(&packed, "XADD"));
+
+        let publisher = publisher.with_delivery(Delivery::Stream { max_len: 1000 });
+        let packed = publisher
+            .build_pipeline(Topic::Bars, &payloads)
+            .get_packed_pipeline();
+        assert_eq!(
+            packed
+                .windows(4)
+                .filter(|window| *window == b"XADD")
+                .count(),
+            2
+        );
+        assert!(contains(&packed, "MAXLEN"));
+        assert!(contains(&packed, "1000"));
+        assert!(contains(&packed, "pt:bars"));
+    }
+
+    #[test]
+    fn test_unreachable_server() {
+        assert!(RedisPublisher::new("not a url").is_err());
+
+        // 端口1上没有Redis，空批次不连接服务器
+        let publisher = RedisPublisher::new("redis://127.0.0.1:1")
+            .unwrap()
+            .with_connect_timeout(Duration::from_millis(200));
+        assert_eq!(
+            publisher.publish::<LimitEvent>(Topic::Limits, &[]).unwrap(),
+            0
+        );
+        assert!(publisher.publish_limit_events(&[limit_event()]).is_err());
+        assert!(publisher.lock().is_none());
+    }
+}
@@ -0,0 +1,253 @@
+//! 记录结构版本模块
+//!
+//! 定义各版本导出记录的列结构。快照在清单和Parquet文件元数据中都写入结构版本；
+//! 读取较早版本的数据时，`upcast` 按列的引入版本补齐后来新增的列（填空值）并按当前
+//! 列顺序排列，读取端只需处理当前结构。
+//!
+//! 版本历史：
+//! - 1：基础行情列，指标列至布林带
+//! - 2：新增相对强弱线、beta、超额收益及多窗口beta/alpha指标列
+//! - 3：新增换手率、流通市值列
+//! - 4：新增VWAP及5/20日VWAP、TWAP列
+//! - 5：新增52周高低点距离、距创新高天数及滚动最大回撤列
+
+use crate::processors::field::INDICATOR_NAMES;
+use anyhow::Result;
+use arrow_array::{new_null_array, ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field as ArrowField, Schema, SchemaRef};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 当前结构版本
+pub const CURRENT_SCHEMA_VERSION: u32 = 5;
+/// Parquet文件元数据中记录结构版本的键
+pub const SCHEMA_VERSION_KEY: &str = "pulse_trader.schema_version";
+
+/// 各版本包含的指标列数（`INDICATOR_NAMES` 只在末尾追加）
+const INDICATOR_COUNTS: [(u32, usize); 5] = [(1, 14), (2, 23), (3, 25), (4, 30), (5, 36)];
+
+// 新增指标列时必须同时提升结构版本
+const _: () = assert!(INDICATOR_COUNTS[INDICATOR_COUNTS.len() - 1].1 == INDICATOR_NAMES.len());
+const _: () = assert!(INDICATOR_COUNTS[INDICATOR_COUNTS.len() - 1].0 == CURRENT_SCHEMA_VERSION);
+
+/// 基础行情列
+const BASE_COLUMNS: [(&str, DataType); 9] = [
+    ("date", DataType::Date32),
+    ("symbol", DataType::Utf8),
+    ("market", DataType::Utf8),
+    ("open", DataType::Float64),
+    ("high", DataType::Float64),
+    ("low", DataType::Float64),
+    ("close", DataType::Float64),
+    ("volume", DataType::UInt64),
+    ("amount", DataType::Float64),
+];
+
+/// 列定义
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSpec {
+    /// 列名
+    pub name: &'static str,
+    /// 数据类型
+    pub data_type: DataType,
+    /// 是否可为空
+    pub nullable: bool,
+    /// 引入该列的结构版本
+    pub since: u32,
+}
+
+/// 某一版本的记录结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordSchema {
+    /// 结构版本
+    version: u32,
+    /// 是否包含技术指标列
+    include_indicators: bool,
+}
+
+impl RecordSchema {
+    /// 指定版本的结构，版本不存在时报错
+    pub fn new(version: u32, include_indicators: bool) -> Result<Self> {
+        if version == 0 || version > CURRENT_SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "不支持的结构版本{}（当前版本{}）",
+                version,
+                CURRENT_SCHEMA_VERSION
+            ));
+        }
+        Ok(Self {
+            version,
+            include_indicators,
+        })
+    }
+
+    /// 当前版本的结构
+    pub fn current(include_indicators: bool) -> Self {
+        Self {
+            version: CURRENT_SCHEMA_VERSION,
+            include_indicators,
+        }
+    }
+
+    /// 结构版本
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// 是否包含技术指标列
+    pub fn include_indicators(&self) -> bool {
+        self.include_indicators
+    }
+
+    /// 该版本的全部列
+    pub fn columns(&self) -> Vec<ColumnSpec> {
+        let mut columns: Vec<ColumnSpec> = BASE_COLUMNS
+            .iter()
+            .map(|(name, data_type)| ColumnSpec {
+                name,
+                data_type: data_type.clone(),
+                nullable: false,
+                since: 1,
+            })
+            .collect();
+
+        if self.include_indicators {
+            let mut start = 0;
+            for &(since, count) in INDICATOR_COUNTS.iter().filter(|(v, _)| *v <= self.version) {
+                columns.extend(INDICATOR_NAMES[start..count].iter().map(|name| ColumnSpec {
+                    name,
+                    data_type: DataType::Float64,
+                    nullable: true,
+                    since,
+                }));
+                start = count;
+            }
+        }
+        columns
+    }
+
+    /// 对应的Arrow结构，元数据中带结构版本
+    pub fn arrow_schema(&self) -> SchemaRef {
+        let fields: Vec<ArrowField> = self
+            .columns()
+            .into_iter()
+            .map(|column| ArrowField::new(column.name, column.data_type, column.nullable))
+            .collect();
+        let metadata = HashMap::from([(SCHEMA_VERSION_KEY.to_string(), self.version.to_string())]);
+        Arc::new(Schema::new_with_metadata(fields, metadata))
+    }
+}
+
+/// 读取Arrow结构元数据中的结构版本
+pub fn schema_version(schema: &Schema) -> Option<u32> {
+    schema.metadata().get(SCHEMA_VERSION_KEY)?.parse().ok()
+}
+
+/// 把 `from` 版本的数据批次升级为当前结构
+///
+/// 当前结构中的列若已存在则原样保留（类型须一致）；不存在且在 `from` 之后才引入的列
+/// 补为空值列；`from` 版本本应包含却缺失的列视为数据损坏。
+pub fn upcast(batch: &RecordBatch, from: &RecordSchema) -> Result<RecordBatch> {
+    let target = RecordSchema::current(from.include_indicators);
+    let columns: Vec<ArrayRef> = target
+        .columns()
+        .into_iter()
+        .map(|column| match batch.column_by_name(column.name) {
+            Some(array) if *array.data_type() == column.data_type => Ok(array.clone()),
+            Some(array) => Err(anyhow::anyhow!(
+                "列类型不一致: {}（{}，应为{}）",
+                column.name,
+                array.data_type(),
+                column.data_type
+            )),
+            None if column.since > from.version && column.nullable => {
+                Ok(new_null_array(&column.data_type, batch.num_rows()))
+            }
+            None => Err(anyhow::anyhow!(
+                "结构版本{}的数据缺少列: {}",
+                from.version,
+                column.name
+            )),
+        })
+        .collect::<Result<_>>()?;
+
+    RecordBatch::try_new(target.arrow_schema(), columns)
+        .map_err(|e| anyhow::anyhow!("升级数据批次失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, Date32Array, Float64Array, StringArray, UInt64Array};
+
+    /// 只含基础列的数据批次
+    fn base_batch(rows: usize) -> Vec<ArrayRef> {
+        let floats = || Arc::new(Float64Array::from(vec![10.0; rows])) as ArrayRef;
+        let strings = |value: &str| Arc::new(StringArray::from(vec![value; rows])) as ArrayRef;
+        vec![
+            Arc::new(Date32Array::from(vec![19724; rows])),
+            strings("600000"),
+            strings("SH"),
+            floats(),
+            floats(),
+            floats(),
+            floats(),
+            Arc::new(UInt64Array::from(vec![100u64; rows])),
+            floats(),
+        ]
+    }
+
+    #[test]
+    fn test_columns_by_version() {
+        let v1 = RecordSchema::new(1, true).unwrap();
+        assert_eq!(v1.columns().len(), 9 + 14);
+        let current = RecordSchema::current(true);
+        assert_eq!(current.columns().len(), 9 + INDICATOR_NAMES.len());
+        assert_eq!(current.columns()[23].name, "rs_line");
+        assert_eq!(current.columns()[23].since, 2);
+        assert_eq!(RecordSchema::current(false).columns().len(), 9);
+
+        assert_eq!(schema_version(&v1.arrow_schema()), Some(1));
+        assert!(RecordSchema::new(CURRENT_SCHEMA_VERSION + 1, true).is_err());
+    }
+
+    #[test]
+    fn test_upcast_adds_new_columns() {
+        let v1 = RecordSchema::new(1, true).unwrap();
+        let mut columns = base_batch(3);
+        columns.extend((0..14).map(|_| Arc::new(Float64Array::from(vec![1.0; 3])) as ArrayRef));
+        let batch = RecordBatch::try_new(v1.arrow_schema(), columns).unwrap();
+
+        let upcast = upcast(&batch, &v1).unwrap();
+        assert_eq!(upcast.num_columns(), 9 + INDICATOR_NAMES.len());
+        assert_eq!(
+            schema_version(&upcast.schema()),
+            Some(CURRENT_SCHEMA_VERSION)
+        );
+        assert_eq!(upcast.column_by_name("rsi").unwrap().null_count(), 0);
+        assert_eq!(upcast.column_by_name("beta_250").unwrap().null_count(), 3);
+    }
+
+    #[test]
+    fn test_upcast_rejects_missing_or_mistyped_columns() {
+        // 版本1本应包含指标列
+        let v1 = RecordSchema::new(1, true).unwrap();
+        let batch =
+            RecordBatch::try_new(RecordSchema::current(false).arrow_schema(), base_batch(2))
+                .unwrap();
+        assert!(upcast(&batch, &v1).is_err());
+
+        let mut columns = base_batch(2);
+        columns[7] = Arc::new(Float64Array::from(vec![100.0; 2]));
+        let fields: Vec<ArrowField> = BASE_COLUMNS
+            .iter()
+            .map(|(name, data_type)| match *name {
+                "volume" => ArrowField::new(*name, DataType::Float64, false),
+                _ => ArrowField::new(*name, data_type.clone(), false),
+            })
+            .collect();
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap();
+        let base = RecordSchema::new(1, false).unwrap();
+        assert!(upcast(&batch, &base).is_err());
+    }
+}
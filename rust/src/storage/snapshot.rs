@@ -0,0 +1,717 @@
+//! 数据集快照模块
+//!
+//! 将完整的解析结果（可选包含技术指标）导出为快照目录：
+//! `data.parquet`（zstd压缩的列式数据）加 `manifest.json`（结构版本、记录数、日期范围等）。
+//! 数据附带血缘时另写 `lineage.json`，按（股票代码, 市场, 时间）记录每条记录的来源和处理步骤。
+//! 快照可原样恢复，用于固定研究数据集、保证结果可复现；较早结构版本的快照在读取时
+//! 经 `schema::upcast` 升级到当前结构。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::calculator::{BollingerBands, EnhancedDayRecord, IndicatorValues, MACD};
+use crate::processors::diff::{DatasetDiff, DatasetDiffer};
+use crate::processors::field::INDICATOR_NAMES;
+use crate::processors::lineage::{KeyedLineage, Lineage};
+use crate::storage::schema::{self, RecordSchema, CURRENT_SCHEMA_VERSION};
+use anyhow::{Context, Result};
+use arrow_array::builder::{Date32Builder, Float64Builder, StringBuilder, UInt64Builder};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Date32Type, Float64Type, UInt64Type};
+use arrow_array::{Array, ArrayRef, RecordBatch};
+use arrow_schema::SchemaRef;
+use chrono::{DateTime, NaiveDate, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 当前快照结构版本
+pub const SNAPSHOT_SCHEMA_VERSION: u32 = CURRENT_SCHEMA_VERSION;
+/// 清单文件名
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// 数据文件名
+pub const DATA_FILE: &str = "data.parquet";
+/// 血缘文件名
+pub const LINEAGE_FILE: &str = "lineage.json";
+
+/// 快照清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// 结构版本
+    pub schema_version: u32,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 记录数
+    pub record_count: usize,
+    /// 股票数
+    pub symbol_count: usize,
+    /// 最早日期
+    pub start_date: Option<NaiveDate>,
+    /// 最晚日期
+    pub end_date: Option<NaiveDate>,
+    /// 是否包含技术指标列
+    pub include_indicators: bool,
+    /// 数据文件名
+    pub data_file: String,
+    /// 压缩方式
+    pub compression: String,
+    /// 血缘文件名，快照不含血缘时为None
+    #[serde(default)]
+    pub lineage_file: Option<String>,
+}
+
+/// 快照写入器
+#[derive(Debug, Clone)]
+pub struct SnapshotWriter {
+    /// zstd压缩级别（1-22）
+    compression_level: i32,
+    /// 每个批次的行数
+    batch_size: usize,
+}
+
+impl SnapshotWriter {
+    /// 创建写入器
+    pub fn new() -> Self {
+        Self {
+            compression_level: 3,
+            batch_size: 65_536,
+        }
+    }
+
+    /// 设置zstd压缩级别
+    pub fn with_compression_level(mut self, level: i32) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    /// 设置批次行数
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// 写入不含指标的快照
+    pub fn write<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        records: &[TDXDayRecord],
+    ) -> Result<SnapshotManifest> {
+        let rows: Vec<(&TDXDayRecord, Option<&IndicatorValues>)> =
+            records.iter().map(|record| (record, None)).collect();
+        self.write_rows(dir.as_ref(), rows, false, None)
+    }
+
+    /// 写入不含指标的快照，并保存与 `records` 一一对应的血缘
+    pub fn write_with_lineage<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        records: &[TDXDayRecord],
+        lineage: &Lineage,
+    ) -> Result<SnapshotManifest> {
+        let keyed = lineage.keyed(records)?;
+        let rows = records.iter().map(|record| (record, None)).collect();
+        self.write_rows(dir.as_ref(), rows, false, Some(keyed))
+    }
+
+    /// 写入包含技术指标的快照
+    pub fn write_enhanced<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        records: &[EnhancedDayRecord],
+    ) -> Result<SnapshotManifest> {
+        let rows = records
+            .iter()
+            .map(|record| (&record.base_record, Some(&record.indicators)))
+            .collect();
+        self.write_rows(dir.as_ref(), rows, true, None)
+    }
+
+    /// 写入包含技术指标的快照，并保存与 `records` 一一对应的血缘
+    pub fn write_enhanced_with_lineage<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        records: &[EnhancedDayRecord],
+        lineage: &Lineage,
+    ) -> Result<SnapshotManifest> {
+        let keyed = lineage.keyed(records)?;
+        let rows = records
+            .iter()
+            .map(|record| (&record.base_record, Some(&record.indicators)))
+            .collect();
+        self.write_rows(dir.as_ref(), rows, true, Some(keyed))
+    }
+
+    fn write_rows(
+        &self,
+        dir: &Path,
+        mut rows: Vec<(&TDXDayRecord, Option<&IndicatorValues>)>,
+        include_indicators: bool,
+        lineage: Option<KeyedLineage>,
+    ) -> Result<SnapshotManifest> {
+        fs::create_dir_all(dir).with_context(|| format!("无法创建快照目录: {}", dir.display()))?;
+
+        // 固定行顺序，同一数据集的快照内容一致
+        rows.sort_by(|(a, _), (b, _)| {
+            a.date
+                .cmp(&b.date)
+                .then(a.symbol.cmp(&b.symbol))
+                .then(a.market.cmp(&b.market))
+        });
+
+        let schema = RecordSchema::current(include_indicators).arrow_schema();
+        let level = ZstdLevel::try_new(self.compression_level)
+            .map_err(|e| anyhow::anyhow!("无效的压缩级别 {}: {}", self.compression_level, e))?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(level))
+            .build();
+
+        let data_path = dir.join(DATA_FILE);
+        let file = File::create(&data_path)
+            .with_context(|| format!("无法创建快照数据文件: {}", data_path.display()))?;
+        let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))?;
+        for chunk in rows.chunks(self.batch_size) {
+            writer.write(&build_batch(&schema, chunk, include_indicators)?)?;
+        }
+        writer.close()?;
+
+        let lineage_file = match lineage {
+            Some(lineage) => {
+                let lineage_path = dir.join(LINEAGE_FILE);
+                fs::write(&lineage_path, serde_json::to_string(&lineage)?)
+                    .with_context(|| format!("无法写入快照血缘: {}", lineage_path.display()))?;
+                Some(LINEAGE_FILE.to_string())
+            }
+            None => None,
+        };
+
+        let symbols: HashSet<(&str, &str)> = rows
+            .iter()
+            .map(|(record, _)| (record.symbol.as_str(), record.market.as_str()))
+            .collect();
+        let manifest = SnapshotManifest {
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            created_at: Utc::now(),
+            record_count: rows.len(),
+            symbol_count: symbols.len(),
+            start_date: rows.first().map(|(record, _)| record.date),
+            end_date: rows.last().map(|(record, _)| record.date),
+            include_indicators,
+            data_file: DATA_FILE.to_string(),
+            compression: format!("zstd({})", self.compression_level),
+            lineage_file,
+        };
+
+        let manifest_path = dir.join(MANIFEST_FILE);
+        fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| format!("无法写入快照清单: {}", manifest_path.display()))?;
+
+        Ok(manifest)
+    }
+}
+
+impl Default for SnapshotWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 已恢复的快照
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// 快照清单
+    pub manifest: SnapshotManifest,
+    /// 日线记录（按日期、股票代码排序）
+    pub records: Vec<TDXDayRecord>,
+    /// 技术指标（与 `records` 一一对应，快照不含指标时为None）
+    pub indicators: Option<Vec<IndicatorValues>>,
+    /// 血缘（与 `records` 一一对应，快照不含血缘时为None）
+    pub lineage: Option<Lineage>,
+}
+
+impl Snapshot {
+    /// 读取快照清单
+    pub fn read_manifest<P: AsRef<Path>>(dir: P) -> Result<SnapshotManifest> {
+        let path = dir.as_ref().join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("无法读取快照清单: {}", path.display()))?;
+        let manifest: SnapshotManifest = serde_json::from_str(&content)
+            .with_context(|| format!("快照清单格式错误: {}", path.display()))?;
+
+        if manifest.schema_version > SNAPSHOT_SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "快照结构版本{}高于当前支持的版本{}",
+                manifest.schema_version,
+                SNAPSHOT_SCHEMA_VERSION
+            ));
+        }
+
+        Ok(manifest)
+    }
+
+    /// 恢复快照
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let manifest = Self::read_manifest(dir)?;
+
+        let data_path = dir.join(&manifest.data_file);
+        let file = File::open(&data_path)
+            .with_context(|| format!("无法打开快照数据文件: {}", data_path.display()))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+        let file_version = schema::schema_version(builder.schema());
+        // 早期快照的数据文件不带版本元数据，以清单为准
+        if file_version.is_some_and(|version| version != manifest.schema_version) {
+            return Err(anyhow::anyhow!(
+                "快照数据文件结构版本{:?}与清单版本{}不一致",
+                file_version,
+                manifest.schema_version
+            ));
+        }
+        let stored = RecordSchema::new(manifest.schema_version, manifest.include_indicators)?;
+        let reader = builder.build()?;
+
+        let mut records = Vec::with_capacity(manifest.record_count);
+        let mut indicators = manifest
+            .include_indicators
+            .then(|| Vec::with_capacity(manifest.record_count));
+        for batch in reader {
+            let batch = schema::upcast(&batch?, &stored)?;
+            read_batch(&batch, &mut records, indicators.as_mut())?;
+        }
+
+        if records.len() != manifest.record_count {
+            return Err(anyhow::anyhow!(
+                "快照记录数不一致: 清单{}条，数据{}条",
+                manifest.record_count,
+                records.len()
+            ));
+        }
+
+        let lineage = match &manifest.lineage_file {
+            Some(file) => {
+                let path = dir.join(file);
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("无法读取快照血缘: {}", path.display()))?;
+                let keyed: KeyedLineage = serde_json::from_str(&content)
+                    .with_context(|| format!("快照血缘格式错误: {}", path.display()))?;
+                Some(keyed.align(&records)?)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            manifest,
+            records,
+            indicators,
+            lineage,
+        })
+    }
+
+    /// 与另一份快照比对，`self` 为旧数据
+    pub fn diff(&self, other: &Snapshot, differ: &DatasetDiffer) -> Result<DatasetDiff> {
+        differ.diff(&self.records, &other.records)
+    }
+
+    /// 带指标的记录，快照不含指标时返回None
+    pub fn enhanced_records(&self) -> Option<Vec<EnhancedDayRecord>> {
+        let indicators = self.indicators.as_ref()?;
+        Some(
+            self.records
+                .iter()
+                .zip(indicators)
+                .map(|(record, values)| EnhancedDayRecord::from_record(record, values.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// Date32 的起点
+fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+}
+
+pub(crate) fn build_batch(
+    schema: &SchemaRef,
+    rows: &[(&TDXDayRecord, Option<&IndicatorValues>)],
+    include_indicators: bool,
+) -> Result<RecordBatch> {
+    let mut dates = Date32Builder::with_capacity(rows.len());
+    let mut symbols = StringBuilder::new();
+    let mut markets = StringBuilder::new();
+    let mut prices: Vec<Float64Builder> = (0..4)
+        .map(|_| Float64Builder::with_capacity(rows.len()))
+        .collect();
+    let mut volumes = UInt64Builder::with_capacity(rows.len());
+    let mut amounts = Float64Builder::with_capacity(rows.len());
+
+    for (record, _) in rows {
+        dates.append_value(record.date.signed_duration_since(epoch()).num_days() as i32);
+        symbols.append_value(&record.symbol);
+        markets.append_value(&record.market);
+        for (builder, value) in
+            prices
+                .iter_mut()
+                .zip([record.open, record.high, record.low, record.close])
+        {
+            builder.append_value(value);
+        }
+        volumes.append_value(record.volume);
+        amounts.append_value(record.amount);
+    }
+
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(dates.finish()),
+        Arc::new(symbols.finish()),
+        Arc::new(markets.finish()),
+    ];
+    columns.extend(prices.iter_mut().map(|b| Arc::new(b.finish()) as ArrayRef));
+    columns.push(Arc::new(volumes.finish()));
+    columns.push(Arc::new(amounts.finish()));
+
+    if include_indicators {
+        for name in INDICATOR_NAMES {
+            let mut builder = Float64Builder::with_capacity(rows.len());
+            for (_, indicators) in rows {
+                builder.append_option(indicators.and_then(|values| values.get(name)));
+            }
+            columns.push(Arc::new(builder.finish()));
+        }
+    }
+
+    RecordBatch::try_new(schema.clone(), columns).with_context(|| "构建快照数据批次失败")
+}
+
+pub(crate) fn read_batch(
+    batch: &RecordBatch,
+    records: &mut Vec<TDXDayRecord>,
+    indicators: Option<&mut Vec<IndicatorValues>>,
+) -> Result<()> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .ok_or_else(|| anyhow::anyhow!("快照缺少列: {}", name))
+    };
+    let float = |name: &str| -> Result<_> {
+        column(name)?
+            .as_primitive_opt::<Float64Type>()
+            .ok_or_else(|| anyhow::anyhow!("快照列类型错误: {}", name))
+    };
+
+    let dates = column("date")?
+        .as_primitive_opt::<Date32Type>()
+        .ok_or_else(|| anyhow::anyhow!("快照列类型错误: date"))?;
+    let symbols = column("symbol")?
+        .as_string_opt::<i32>()
+        .ok_or_else(|| anyhow::anyhow!("快照列类型错误: symbol"))?;
+    let markets = column("market")?
+        .as_string_opt::<i32>()
+        .ok_or_else(|| anyhow::anyhow!("快照列类型错误: market"))?;
+    let volumes = column("volume")?
+        .as_primitive_opt::<UInt64Type>()
+        .ok_or_else(|| anyhow::anyhow!("快照列类型错误: volume"))?;
+    let (open, high, low, close, amount) = (
+        float("open")?,
+        float("high")?,
+        float("low")?,
+        float("close")?,
+        float("amount")?,
+    );
+
+    for row in 0..batch.num_rows() {
+        records.push(TDXDayRecord {
+            date: epoch() + chrono::Duration::days(dates.value(row) as i64),
+            symbol: symbols.value(row).to_string(),
+            open: open.value(row),
+            high: high.value(row),
+            low: low.value(row),
+            close: close.value(row),
+            volume: volumes.value(row),
+            amount: amount.value(row),
+            market: markets.value(row).to_string(),
+        });
+    }
+
+    if let Some(indicators) = indicators {
+        // 批次已升级到当前结构，指标列齐全
+        let columns: Vec<(&str, _)> = INDICATOR_NAMES
+            .iter()
+            .map(|name| Ok((*name, float(name)?)))
+            .collect::<Result<_>>()?;
+        for row in 0..batch.num_rows() {
+            let value = |name: &str| {
+                columns
+                    .iter()
+                    .find(|(column, _)| *column == name)
+                    .and_then(|(_, array)| (!array.is_null(row)).then(|| array.value(row)))
+            };
+            indicators.push(indicator_values(value));
+        }
+    }
+
+    Ok(())
+}
+
+/// 按指标名取值函数重建指标集合
+fn indicator_values(value: impl Fn(&str) -> Option<f64>) -> IndicatorValues {
+    let macd = match (
+        value("macd_dif"),
+        value("macd_signal"),
+        value("macd_histogram"),
+    ) {
+        (Some(dif), Some(signal), Some(histogram)) => Some(MACD {
+            dif,
+            signal,
+            histogram,
+        }),
+        _ => None,
+    };
+    let bollinger = match (
+        value("boll_upper"),
+        value("boll_middle"),
+        value("boll_lower"),
+    ) {
+        (Some(upper), Some(middle), Some(lower)) => Some(BollingerBands {
+            upper,
+            middle,
+            lower,
+            width: upper - lower,
+        }),
+        _ => None,
+    };
+
+    IndicatorValues {
+        ma5: value("ma5"),
+        ma10: value("ma10"),
+        ma20: value("ma20"),
+        ma60: value("ma60"),
+        volume_ma5: value("volume_ma5"),
+        change_percent: value("change_percent"),
+        amplitude: value("amplitude"),
+        rsi: value("rsi"),
+        macd,
+        bollinger,
+        rs_line: value("rs_line"),
+        beta: value("beta"),
+        excess_return: value("excess_return"),
+        beta_20: value("beta_20"),
+        beta_60: value("beta_60"),
+        beta_250: value("beta_250"),
+        alpha_20: value("alpha_20"),
+        alpha_60: value("alpha_60"),
+        alpha_250: value("alpha_250"),
+        turnover_rate: value("turnover_rate"),
+        float_market_cap: value("float_market_cap"),
+        vwap: value("vwap"),
+        vwap_5: value("vwap_5"),
+        vwap_20: value("vwap_20"),
+        twap_5: value("twap_5"),
+        twap_20: value("twap_20"),
+        dist_high_52w: value("dist_high_52w"),
+        dist_low_52w: value("dist_low_52w"),
+        days_since_high_52w: value("days_since_high_52w"),
+        max_drawdown_20: value("max_drawdown_20"),
+        max_drawdown_60: value("max_drawdown_60"),
+        max_drawdown_250: value("max_drawdown_250"),
+        indicators: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::IndicatorCalculator;
+    use tempfile::TempDir;
+
+    fn create_records() -> Vec<TDXDayRecord> {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (0..40)
+            .flat_map(|i| {
+                ["600000", "000001"].map(|symbol| TDXDayRecord {
+                    date: start + chrono::Duration::days(39 - i),
+                    symbol: symbol.to_string(),
+                    open: 10.0 + i as f64 * 0.01,
+                    high: 10.5 + i as f64 * 0.01,
+                    low: 9.5,
+                    close: 10.2 + (i % 7) as f64 * 0.03,
+                    volume: 1000 + i as u64,
+                    amount: 10_000.0 + i as f64,
+                    market: if symbol == "600000" { "SH" } else { "SZ" }.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let records = create_records();
+
+        let manifest = SnapshotWriter::new()
+            .with_batch_size(16)
+            .write(temp_dir.path(), &records)
+            .unwrap();
+        assert_eq!(manifest.record_count, 80);
+        assert_eq!(manifest.symbol_count, 2);
+        assert_eq!(manifest.start_date, NaiveDate::from_ymd_opt(2024, 1, 1));
+        assert!(!manifest.include_indicators);
+
+        let snapshot = Snapshot::load(temp_dir.path()).unwrap();
+        assert!(snapshot.indicators.is_none());
+        assert_eq!(snapshot.records.len(), 80);
+        // 恢复后按日期、代码排序
+        assert_eq!(snapshot.records[0].symbol, "000001");
+        assert_eq!(snapshot.records[1].symbol, "600000");
+        let original = records
+            .iter()
+            .find(|r| r.symbol == "600000" && r.date == snapshot.records[1].date)
+            .unwrap();
+        assert_eq!(snapshot.records[1].close, original.close);
+        assert_eq!(snapshot.records[1].volume, original.volume);
+
+        let diff = DatasetDiffer::new()
+            .diff(&records, &snapshot.records)
+            .unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(diff.unchanged, 80);
+    }
+
+    #[test]
+    fn test_snapshot_with_lineage() {
+        use crate::processors::lineage::LineageSource;
+
+        let temp_dir = TempDir::new().unwrap();
+        let records = create_records();
+        // 偶数位置来自上交所文件，奇数位置来自深交所文件
+        let mut lineage = Lineage::new();
+        for record in &records {
+            let location = format!("{}{}.day", record.market.to_lowercase(), record.symbol);
+            lineage.extend(Lineage::from_source(LineageSource::new(location), 1));
+        }
+        let step = lineage.add_step("FillMissing(close)");
+        lineage.tag(step, [0]);
+
+        let manifest = SnapshotWriter::new()
+            .write_with_lineage(temp_dir.path(), &records, &lineage)
+            .unwrap();
+        assert_eq!(manifest.lineage_file.as_deref(), Some(LINEAGE_FILE));
+
+        let snapshot = Snapshot::load(temp_dir.path()).unwrap();
+        let lineage = snapshot.lineage.as_ref().unwrap();
+        assert_eq!(lineage.len(), 80);
+        for (index, record) in snapshot.records.iter().enumerate() {
+            let trace = lineage.trace(index).unwrap();
+            assert!(trace
+                .source
+                .location
+                .ends_with(&format!("{}.day", record.symbol)));
+            let tagged = record.symbol == "600000" && record.date == records[0].date;
+            assert_eq!(trace.steps.len(), usize::from(tagged));
+        }
+
+        // 不含血缘的快照
+        SnapshotWriter::new()
+            .write(temp_dir.path(), &records)
+            .unwrap();
+        assert!(Snapshot::load(temp_dir.path()).unwrap().lineage.is_none());
+        assert!(SnapshotWriter::new()
+            .write_with_lineage(temp_dir.path(), &records[..1], lineage)
+            .is_err());
+    }
+
+    #[test]
+    fn test_snapshot_with_indicators() {
+        let temp_dir = TempDir::new().unwrap();
+        let enhanced = IndicatorCalculator::new()
+            .calculate_parallel(&create_records())
+            .unwrap();
+
+        SnapshotWriter::new()
+            .write_enhanced(temp_dir.path(), &enhanced)
+            .unwrap();
+        let restored = Snapshot::load(temp_dir.path())
+            .unwrap()
+            .enhanced_records()
+            .unwrap();
+
+        assert_eq!(restored.len(), enhanced.len());
+        for (a, b) in restored.iter().zip(&enhanced) {
+            assert_eq!(a.base_record.date, b.base_record.date);
+            for name in INDICATOR_NAMES {
+                assert_eq!(a.indicators.get(name), b.indicators.get(name), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_older_snapshot_upcast_on_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let enhanced = IndicatorCalculator::new()
+            .calculate_parallel(&create_records())
+            .unwrap();
+        let mut manifest = SnapshotWriter::new()
+            .write_enhanced(temp_dir.path(), &enhanced)
+            .unwrap();
+        assert_eq!(manifest.schema_version, SNAPSHOT_SCHEMA_VERSION);
+
+        // 改写为版本1的快照：去掉版本2新增的列，数据文件不带版本元数据
+        let data_path = temp_dir.path().join(DATA_FILE);
+        let batches: Vec<RecordBatch> =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(&data_path).unwrap())
+                .unwrap()
+                .build()
+                .unwrap()
+                .map(|batch| {
+                    batch
+                        .unwrap()
+                        .project(&(0..9 + 14).collect::<Vec<_>>())
+                        .unwrap()
+                })
+                .collect();
+        let v1_schema = Arc::new(arrow_schema::Schema::new(
+            batches[0].schema().fields().clone(),
+        ));
+        let mut writer =
+            ArrowWriter::try_new(File::create(&data_path).unwrap(), v1_schema.clone(), None)
+                .unwrap();
+        for batch in &batches {
+            writer
+                .write(&batch.clone().with_schema(v1_schema.clone()).unwrap())
+                .unwrap();
+        }
+        writer.close().unwrap();
+        manifest.schema_version = 1;
+        fs::write(
+            temp_dir.path().join(MANIFEST_FILE),
+            serde_json::to_string(&manifest).unwrap(),
+        )
+        .unwrap();
+
+        let restored = Snapshot::load(temp_dir.path())
+            .unwrap()
+            .enhanced_records()
+            .unwrap();
+        let last = restored.last().unwrap();
+        assert!(last.indicators.ma20.is_some());
+        assert!(last.indicators.rs_line.is_none());
+        assert!(last.indicators.beta_20.is_none());
+    }
+
+    #[test]
+    fn test_newer_schema_version_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut manifest = SnapshotWriter::new()
+            .write(temp_dir.path(), &create_records())
+            .unwrap();
+        manifest.schema_version = SNAPSHOT_SCHEMA_VERSION + 1;
+        fs::write(
+            temp_dir.path().join(MANIFEST_FILE),
+            serde_json::to_string(&manifest).unwrap(),
+        )
+        .unwrap();
+
+        assert!(Snapshot::load(temp_dir.path()).is_err());
+    }
+}
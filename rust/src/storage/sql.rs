@@ -0,0 +1,455 @@
+//! 嵌入式SQL查询模块（`duckdb` 特性）
+//!
+//! 基于DuckDB在进程内直接对导出的数据执行SQL，无需部署ClickHouse即可做临时分析：
+//! 分区数据集（`DatasetWriter` 导出）和快照注册为视图，内存中的记录或Arrow批次
+//! 注册为表。`query_sql` 把结果行按列名反序列化为任意实现 `Deserialize` 的类型。
+//!
+//! ```no_run
+//! # use pulse_trader_rust::storage::SqlEngine;
+//! # fn main() -> anyhow::Result<()> {
+//! #[derive(serde::Deserialize)]
+//! struct MaxClose {
+//!     symbol: String,
+//!     max_close: f64,
+//! }
+//!
+//! let engine = SqlEngine::new()?;
+//! engine.register_dataset("bars", "export/dataset")?;
+//! let rows: Vec<MaxClose> = engine.query_sql(
+//!     "SELECT symbol, max(close) AS max_close FROM bars WHERE year = 2024 GROUP BY symbol",
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::calculator::{EnhancedDayRecord, IndicatorValues};
+use crate::storage::schema::RecordSchema;
+use crate::storage::snapshot::{build_batch, DATA_FILE};
+use anyhow::{Context, Result};
+use arrow_array::RecordBatch;
+use arrow_schema::DataType;
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use duckdb::types::Value;
+use duckdb::Connection;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value as JsonValue};
+use std::path::Path;
+
+/// DuckDB单个数据块的行数，追加Arrow批次时按此拆分
+const APPEND_CHUNK_ROWS: usize = 2048;
+
+/// 嵌入式SQL查询引擎
+pub struct SqlEngine {
+    conn: Connection,
+}
+
+impl std::fmt::Debug for SqlEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqlEngine").finish_non_exhaustive()
+    }
+}
+
+impl SqlEngine {
+    /// 创建内存数据库
+    pub fn new() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("无法创建DuckDB内存数据库")?;
+        Ok(Self { conn })
+    }
+
+    /// 打开（或创建）数据库文件，注册的表会持久保存
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .with_context(|| format!("无法打开DuckDB数据库: {}", path.display()))?;
+        Ok(Self { conn })
+    }
+
+    /// 把Hive风格的分区数据集注册为视图，`market` 和 `year` 作为普通列参与查询和分区裁剪
+    pub fn register_dataset<P: AsRef<Path>>(&self, name: &str, dir: P) -> Result<()> {
+        let pattern = dir.as_ref().join("**").join("*.parquet");
+        self.create_view(
+            name,
+            &format!(
+                "read_parquet({}, hive_partitioning = true)",
+                quote_literal(&pattern.to_string_lossy())
+            ),
+        )
+    }
+
+    /// 把快照的数据文件注册为视图
+    pub fn register_snapshot<P: AsRef<Path>>(&self, name: &str, dir: P) -> Result<()> {
+        self.register_parquet(name, dir.as_ref().join(DATA_FILE))
+    }
+
+    /// 把Parquet文件（可含通配符）注册为视图
+    pub fn register_parquet<P: AsRef<Path>>(&self, name: &str, path: P) -> Result<()> {
+        self.create_view(
+            name,
+            &format!(
+                "read_parquet({})",
+                quote_literal(&path.as_ref().to_string_lossy())
+            ),
+        )
+    }
+
+    /// 把日线记录注册为表（列与快照一致）
+    pub fn register_records(&self, name: &str, records: &[TDXDayRecord]) -> Result<()> {
+        let rows: Vec<(&TDXDayRecord, Option<&IndicatorValues>)> =
+            records.iter().map(|record| (record, None)).collect();
+        let schema = RecordSchema::current(false).arrow_schema();
+        self.register_batches(name, &[build_batch(&schema, &rows, false)?])
+    }
+
+    /// 把带指标的记录注册为表（列与带指标的快照一致）
+    pub fn register_enhanced(&self, name: &str, records: &[EnhancedDayRecord]) -> Result<()> {
+        let rows: Vec<_> = records
+            .iter()
+            .map(|record| (&record.base_record, Some(&record.indicators)))
+            .collect();
+        let schema = RecordSchema::current(true).arrow_schema();
+        self.register_batches(name, &[build_batch(&schema, &rows, true)?])
+    }
+
+    /// 把Arrow批次注册为表，已存在的同名表会被替换；批次须结构相同
+    pub fn register_batches(&self, name: &str, batches: &[RecordBatch]) -> Result<()> {
+        check_name(name)?;
+        let schema = batches
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("注册表{}时没有数据批次", name))?
+            .schema();
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                Ok(format!(
+                    "{} {}",
+                    quote_identifier(field.name()),
+                    sql_type(field.data_type())?
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.execute(&format!(
+            "CREATE OR REPLACE TABLE {} ({})",
+            name,
+            columns.join(", ")
+        ))?;
+
+        let mut appender = self
+            .conn
+            .appender(name)
+            .with_context(|| format!("无法写入表: {}", name))?;
+        for batch in batches {
+            if batch.schema() != schema {
+                return Err(anyhow::anyhow!("注册表{}的数据批次结构不一致", name));
+            }
+            let mut offset = 0;
+            while offset < batch.num_rows() {
+                let len = APPEND_CHUNK_ROWS.min(batch.num_rows() - offset);
+                appender
+                    .append_record_batch(batch.slice(offset, len))
+                    .with_context(|| format!("写入表{}失败", name))?;
+                offset += len;
+            }
+        }
+        appender
+            .flush()
+            .with_context(|| format!("写入表{}失败", name))
+    }
+
+    /// 执行不返回结果的SQL
+    pub fn execute(&self, sql: &str) -> Result<()> {
+        self.conn
+            .execute_batch(sql)
+            .with_context(|| format!("SQL执行失败: {}", sql))
+    }
+
+    /// 执行查询，结果行按列名反序列化为 `T`
+    ///
+    /// 日期列反序列化为 `NaiveDate`，时间戳列为 `NaiveDateTime`，数值列为数字。
+    pub fn query_sql<T: DeserializeOwned>(&self, sql: &str) -> Result<Vec<T>> {
+        let mut statement = self
+            .conn
+            .prepare(sql)
+            .with_context(|| format!("SQL解析失败: {}", sql))?;
+        let mut rows = statement
+            .query([])
+            .with_context(|| format!("SQL查询失败: {}", sql))?;
+        let names = rows
+            .as_ref()
+            .map(|statement| statement.column_names())
+            .unwrap_or_default();
+
+        let mut results = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut object = Map::with_capacity(names.len());
+            for (index, name) in names.iter().enumerate() {
+                let value: Value = row.get(index)?;
+                object.insert(name.clone(), to_json(value));
+            }
+            let result = serde_json::from_value(JsonValue::Object(object))
+                .with_context(|| format!("第{}行结果转换失败", results.len() + 1))?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// 执行查询，结果为Arrow批次
+    pub fn query_arrow(&self, sql: &str) -> Result<Vec<RecordBatch>> {
+        let mut statement = self
+            .conn
+            .prepare(sql)
+            .with_context(|| format!("SQL解析失败: {}", sql))?;
+        let batches = statement
+            .query_arrow([])
+            .with_context(|| format!("SQL查询失败: {}", sql))?
+            .collect();
+        Ok(batches)
+    }
+
+    fn create_view(&self, name: &str, source: &str) -> Result<()> {
+        check_name(name)?;
+        self.execute(&format!(
+            "CREATE OR REPLACE VIEW {} AS SELECT * FROM {}",
+            name, source
+        ))
+    }
+}
+
+/// 表名和视图名直接拼入SQL，只接受字母、数字和下划线
+fn check_name(name: &str) -> Result<()> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!("无效的表名: {}", name));
+    }
+    Ok(())
+}
+
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn quote_identifier(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Arrow类型对应的DuckDB列类型
+fn sql_type(data_type: &DataType) -> Result<&'static str> {
+    Ok(match data_type {
+        DataType::Boolean => "BOOLEAN",
+        DataType::Int32 => "INTEGER",
+        DataType::Int64 => "BIGINT",
+        DataType::UInt32 => "UINTEGER",
+        DataType::UInt64 => "UBIGINT",
+        DataType::Float32 => "FLOAT",
+        DataType::Float64 => "DOUBLE",
+        DataType::Utf8 => "VARCHAR",
+        DataType::Date32 => "DATE",
+        other => return Err(anyhow::anyhow!("不支持的列类型: {}", other)),
+    })
+}
+
+/// DuckDB取值转换为JSON，用于反序列化为目标类型
+fn to_json(value: Value) -> JsonValue {
+    let float = |v: f64| Number::from_f64(v).map_or(JsonValue::Null, JsonValue::Number);
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(v) => JsonValue::Bool(v),
+        Value::TinyInt(v) => v.into(),
+        Value::SmallInt(v) => v.into(),
+        Value::Int(v) => v.into(),
+        Value::BigInt(v) => v.into(),
+        Value::HugeInt(v) => i64::try_from(v).map_or_else(|_| float(v as f64), JsonValue::from),
+        Value::UTinyInt(v) => v.into(),
+        Value::USmallInt(v) => v.into(),
+        Value::UInt(v) => v.into(),
+        Value::UBigInt(v) => v.into(),
+        Value::Float(v) => float(v as f64),
+        Value::Double(v) => float(v),
+        Value::Decimal(v) => v.to_string().parse().map_or(JsonValue::Null, float),
+        Value::Timestamp(unit, v) => DateTime::from_timestamp_micros(unit.to_micros(v))
+            .map_or(JsonValue::Null, |t| {
+                JsonValue::String(t.naive_utc().format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+            }),
+        Value::Text(v) | Value::Enum(v) => JsonValue::String(v),
+        Value::Blob(v) => v.into(),
+        Value::Date32(days) => NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(days as i64)))
+            .map_or(JsonValue::Null, |d| JsonValue::String(d.to_string())),
+        Value::Time64(unit, v) => {
+            let micros = unit.to_micros(v);
+            NaiveTime::from_num_seconds_from_midnight_opt(
+                (micros / 1_000_000) as u32,
+                (micros % 1_000_000 * 1000) as u32,
+            )
+            .map_or(JsonValue::Null, |t| JsonValue::String(t.to_string()))
+        }
+        Value::Interval {
+            months,
+            days,
+            nanos,
+        } => serde_json::json!({ "months": months, "days": days, "nanos": nanos }),
+        Value::List(values) | Value::Array(values) => {
+            JsonValue::Array(values.into_iter().map(to_json).collect())
+        }
+        Value::Struct(fields) => JsonValue::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), to_json(value.clone())))
+                .collect(),
+        ),
+        Value::Map(entries) => JsonValue::Array(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    JsonValue::Array(vec![to_json(key.clone()), to_json(value.clone())])
+                })
+                .collect(),
+        ),
+        Value::Union(value) => to_json(*value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DatasetWriter;
+    use serde::Deserialize;
+    use tempfile::TempDir;
+
+    fn create_records() -> Vec<TDXDayRecord> {
+        let start = NaiveDate::from_ymd_opt(2023, 12, 20).unwrap();
+        (0..30)
+            .flat_map(|i| {
+                [("600000", "SH", 10.0), ("000001", "SZ", 20.0)].map(|(symbol, market, base)| {
+                    TDXDayRecord {
+                        date: start + chrono::Duration::days(i),
+                        symbol: symbol.to_string(),
+                        open: base,
+                        high: base + 1.0,
+                        low: base - 1.0,
+                        close: base + i as f64 * 0.1,
+                        volume: 1000 + i as u64,
+                        amount: 10_000.0,
+                        market: market.to_string(),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct MaxClose {
+        symbol: String,
+        max_close: f64,
+        last_date: NaiveDate,
+        days: i64,
+    }
+
+    #[test]
+    fn test_query_in_memory_records() {
+        let engine = SqlEngine::new().unwrap();
+        engine.register_records("bars", &create_records()).unwrap();
+
+        let rows: Vec<MaxClose> = engine
+            .query_sql(
+                "SELECT symbol, max(close) AS max_close, max(date) AS last_date, count(*) AS days \
+                 FROM bars GROUP BY symbol ORDER BY symbol",
+            )
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].symbol, "000001");
+        assert!((rows[0].max_close - 22.9).abs() < 1e-9);
+        assert_eq!(
+            rows[1].last_date,
+            NaiveDate::from_ymd_opt(2024, 1, 18).unwrap()
+        );
+        assert_eq!(rows[1].days, 30);
+
+        // 缺少的字段报错而不是静默填充
+        let result: Result<Vec<MaxClose>> = engine.query_sql("SELECT symbol FROM bars");
+        assert!(result.is_err());
+        assert!(engine
+            .query_sql::<MaxClose>("SELECT * FROM missing")
+            .is_err());
+    }
+
+    #[test]
+    fn test_query_partitioned_dataset() {
+        let temp_dir = TempDir::new().unwrap();
+        DatasetWriter::new()
+            .write(temp_dir.path(), &create_records())
+            .unwrap();
+
+        let engine = SqlEngine::new().unwrap();
+        engine.register_dataset("bars", temp_dir.path()).unwrap();
+
+        #[derive(Deserialize)]
+        struct Count {
+            market: String,
+            year: i64,
+            rows: i64,
+        }
+        let counts: Vec<Count> = engine
+            .query_sql(
+                "SELECT market, year, count(*) AS rows FROM bars \
+                 GROUP BY market, year ORDER BY market, year",
+            )
+            .unwrap();
+        let counts: Vec<_> = counts
+            .iter()
+            .map(|c| (c.market.as_str(), c.year, c.rows))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("SH", 2023, 12),
+                ("SH", 2024, 18),
+                ("SZ", 2023, 12),
+                ("SZ", 2024, 18)
+            ]
+        );
+
+        let batches = engine
+            .query_arrow("SELECT close FROM bars WHERE market = 'SH' AND year = 2024")
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 18);
+    }
+
+    #[test]
+    fn test_register_batches_chunks_and_names() {
+        let engine = SqlEngine::new().unwrap();
+        let records: Vec<TDXDayRecord> = (0..5000)
+            .map(|i| TDXDayRecord {
+                date: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() + chrono::Duration::days(i),
+                symbol: "600000".to_string(),
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: i as f64,
+                volume: i as u64,
+                amount: 0.0,
+                market: "SH".to_string(),
+            })
+            .collect();
+        engine.register_records("long_history", &records).unwrap();
+
+        #[derive(Deserialize)]
+        struct Total {
+            rows: i64,
+            volume: f64,
+        }
+        let total: Vec<Total> = engine
+            .query_sql("SELECT count(*) AS rows, sum(volume) AS volume FROM long_history")
+            .unwrap();
+        assert_eq!(total[0].rows, 5000);
+        assert_eq!(total[0].volume, (0..5000u64).sum::<u64>() as f64);
+
+        assert!(engine.register_records("bars; DROP", &records).is_err());
+        assert!(engine.register_batches("empty", &[]).is_err());
+    }
+}
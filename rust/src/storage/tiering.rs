@@ -0,0 +1,660 @@
+//! 冷热分层存储模块
+//!
+//! 最近N个月的数据保留在热存储（ClickHouse或SQLite），更早的数据滚动到冷存储：
+//! 按月分区的Parquet快照目录 `{root}/{YYYY-MM}/`。冷存储记录已滚动数据的截止日期
+//! （水位线，`tiering.json`），读取时水位线及之前的部分从冷存储读取，之后的部分从热存储读取，
+//! 调用方看到的是一个连续的数据源。
+//!
+//! 滚动顺序为“写冷分区 → 推进水位线 → 删除热数据”，任一步中断后重新执行即可，
+//! 读取结果不会重复或缺失。
+
+use crate::parsers::TDXDayRecord;
+use crate::source::{DataSource, DateRange};
+use crate::storage::snapshot::{Snapshot, SnapshotWriter};
+use anyhow::{Context, Result};
+use chrono::{Datelike, Local, Months, NaiveDate};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// 分层清单文件名
+pub const TIERING_FILE: &str = "tiering.json";
+
+/// 热存储：在数据源基础上支持按日期导出和删除
+pub trait HotStore: DataSource {
+    /// 日期早于 `cutoff` 的全部记录
+    fn records_before(&self, cutoff: NaiveDate) -> Result<Vec<TDXDayRecord>>;
+
+    /// 删除日期早于 `cutoff` 的记录
+    fn delete_before(&self, cutoff: NaiveDate) -> Result<()>;
+}
+
+#[cfg(feature = "clickhouse")]
+impl HotStore for crate::source::ClickHouseSource {
+    fn records_before(&self, cutoff: NaiveDate) -> Result<Vec<TDXDayRecord>> {
+        crate::source::ClickHouseSource::records_before(self, cutoff)
+    }
+
+    fn delete_before(&self, cutoff: NaiveDate) -> Result<()> {
+        crate::source::ClickHouseSource::delete_before(self, cutoff)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl HotStore for crate::source::SqliteSource {
+    fn records_before(&self, cutoff: NaiveDate) -> Result<Vec<TDXDayRecord>> {
+        crate::source::SqliteSource::records_before(self, cutoff)
+    }
+
+    fn delete_before(&self, cutoff: NaiveDate) -> Result<()> {
+        crate::source::SqliteSource::delete_before(self, cutoff).map(|_| ())
+    }
+}
+
+/// 分层清单
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TieringManifest {
+    /// 已滚动到冷存储的截止日期（含）
+    watermark: Option<NaiveDate>,
+}
+
+/// 冷存储：按月分区的Parquet快照
+#[derive(Debug, Clone)]
+pub struct ColdStore {
+    root: PathBuf,
+    writer: SnapshotWriter,
+}
+
+impl ColdStore {
+    /// 创建冷存储，`root` 不存在时在首次写入时创建
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            writer: SnapshotWriter::new(),
+        }
+    }
+
+    /// 设置分区快照的写入器
+    pub fn with_writer(mut self, writer: SnapshotWriter) -> Self {
+        self.writer = writer;
+        self
+    }
+
+    /// 已滚动到冷存储的截止日期（含），尚未滚动过时为None
+    pub fn watermark(&self) -> Result<Option<NaiveDate>> {
+        let path = self.root.join(TIERING_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("无法读取分层清单: {}", path.display()))?;
+        let manifest: TieringManifest = serde_json::from_str(&content)
+            .with_context(|| format!("分层清单格式错误: {}", path.display()))?;
+        Ok(manifest.watermark)
+    }
+
+    fn set_watermark(&self, watermark: NaiveDate) -> Result<()> {
+        let path = self.root.join(TIERING_FILE);
+        let manifest = TieringManifest {
+            watermark: Some(watermark),
+        };
+        fs::create_dir_all(&self.root)
+            .with_context(|| format!("无法创建冷存储目录: {}", self.root.display()))?;
+        fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+            .with_context(|| format!("无法写入分层清单: {}", path.display()))
+    }
+
+    /// 已有分区（每月第一天），按时间排序
+    pub fn partitions(&self) -> Result<Vec<NaiveDate>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut months = Vec::new();
+        for entry in fs::read_dir(&self.root)
+            .with_context(|| format!("无法读取冷存储目录: {}", self.root.display()))?
+        {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            // 重写中的临时目录（带后缀）不是有效分区
+            let name = entry.file_name();
+            if let Some(month) = name.to_str().and_then(parse_partition) {
+                months.push(month);
+            }
+        }
+        months.sort();
+        Ok(months)
+    }
+
+    /// 把记录并入所在月份的分区，同一股票同一日期以新记录为准，返回写入的分区名
+    pub fn append(&self, records: &[TDXDayRecord]) -> Result<Vec<String>> {
+        let mut by_month: BTreeMap<NaiveDate, Vec<&TDXDayRecord>> = BTreeMap::new();
+        for record in records {
+            by_month
+                .entry(month_start(record.date))
+                .or_default()
+                .push(record);
+        }
+
+        let mut written = Vec::with_capacity(by_month.len());
+        for (month, new_records) in by_month {
+            let mut merged: BTreeMap<(NaiveDate, String, String), TDXDayRecord> = BTreeMap::new();
+            for record in self.load_partition(month)? {
+                merged.insert(
+                    (record.date, record.symbol.clone(), record.market.clone()),
+                    record,
+                );
+            }
+            for record in new_records {
+                merged.insert(
+                    (record.date, record.symbol.clone(), record.market.clone()),
+                    record.clone(),
+                );
+            }
+            let merged: Vec<TDXDayRecord> = merged.into_values().collect();
+            self.write_partition(month, &merged)?;
+            written.push(partition_name(month));
+        }
+        Ok(written)
+    }
+
+    /// 读取分区，分区不存在时返回空列表
+    fn load_partition(&self, month: NaiveDate) -> Result<Vec<TDXDayRecord>> {
+        let dir = self.root.join(partition_name(month));
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        Ok(Snapshot::load(&dir)
+            .with_context(|| format!("无法读取冷存储分区: {}", dir.display()))?
+            .records)
+    }
+
+    /// 先写临时目录再替换，避免中断时留下不完整的分区
+    fn write_partition(&self, month: NaiveDate, records: &[TDXDayRecord]) -> Result<()> {
+        let name = partition_name(month);
+        let dir = self.root.join(&name);
+        let tmp_dir = self.root.join(format!("{}.tmp", name));
+        let old_dir = self.root.join(format!("{}.old", name));
+
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir)?;
+        }
+        self.writer.write(&tmp_dir, records)?;
+        if dir.exists() {
+            if old_dir.exists() {
+                fs::remove_dir_all(&old_dir)?;
+            }
+            fs::rename(&dir, &old_dir)?;
+        }
+        fs::rename(&tmp_dir, &dir)
+            .with_context(|| format!("无法替换冷存储分区: {}", dir.display()))?;
+        if old_dir.exists() {
+            fs::remove_dir_all(&old_dir)?;
+        }
+        Ok(())
+    }
+}
+
+impl DataSource for ColdStore {
+    fn name(&self) -> &str {
+        "cold"
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        let mut symbols = BTreeSet::new();
+        for month in self.partitions()? {
+            for record in self.load_partition(month)? {
+                symbols.insert((record.symbol, record.market));
+            }
+        }
+        Ok(symbols.into_iter().collect())
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let market = market.to_uppercase();
+        let mut bars = Vec::new();
+        for month in self.partitions()? {
+            let month_end = next_month(month).pred_opt().unwrap_or(month);
+            if range.start.is_some_and(|start| month_end < start)
+                || range.end.is_some_and(|end| month > end)
+            {
+                continue;
+            }
+            // 分区按日期、代码排序，过滤后仍按日期升序
+            bars.extend(self.load_partition(month)?.into_iter().filter(|record| {
+                record.symbol == symbol && record.market == market && range.contains(record.date)
+            }));
+        }
+        Ok(bars)
+    }
+}
+
+/// 一次滚动的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RolloverReport {
+    /// 滚动截止日期（不含），早于该日期的数据移入冷存储
+    pub cutoff: NaiveDate,
+    /// 移入冷存储的记录数
+    pub records: usize,
+    /// 写入的冷存储分区
+    pub partitions: Vec<String>,
+}
+
+/// 冷热分层存储
+pub struct TieredStore {
+    hot: Arc<dyn HotStore>,
+    cold: ColdStore,
+    /// 热存储保留的月数（含当月）
+    hot_months: u32,
+}
+
+impl std::fmt::Debug for TieredStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TieredStore")
+            .field("hot", &self.hot.name())
+            .field("cold", &self.cold)
+            .field("hot_months", &self.hot_months)
+            .finish()
+    }
+}
+
+impl TieredStore {
+    /// 创建分层存储（热存储默认保留6个月）
+    pub fn new(hot: Arc<dyn HotStore>, cold: ColdStore) -> Self {
+        Self {
+            hot,
+            cold,
+            hot_months: 6,
+        }
+    }
+
+    /// 设置热存储保留的月数（含当月，至少为1）
+    pub fn with_hot_months(mut self, months: u32) -> Self {
+        self.hot_months = months.max(1);
+        self
+    }
+
+    /// 冷存储
+    pub fn cold(&self) -> &ColdStore {
+        &self.cold
+    }
+
+    /// `today` 时的滚动截止日期：热存储保留的最早月份的第一天
+    pub fn cutoff(&self, today: NaiveDate) -> NaiveDate {
+        month_start(today)
+            .checked_sub_months(Months::new(self.hot_months - 1))
+            .unwrap_or(NaiveDate::MIN)
+    }
+
+    /// 把早于截止日期的热数据滚动到冷存储
+    pub fn rollover(&self, today: NaiveDate) -> Result<RolloverReport> {
+        let cutoff = self.cutoff(today);
+        let records = self
+            .hot
+            .records_before(cutoff)
+            .context("读取待滚动的热数据失败")?;
+        let partitions = self.cold.append(&records)?;
+
+        let watermark = cutoff.pred_opt().unwrap_or(cutoff);
+        if self
+            .cold
+            .watermark()?
+            .is_none_or(|current| current < watermark)
+        {
+            self.cold.set_watermark(watermark)?;
+        }
+        if !records.is_empty() {
+            self.hot
+                .delete_before(cutoff)
+                .context("删除已滚动的热数据失败")?;
+        }
+
+        info!(
+            "冷热分层滚动完成: 截止{}，移入{}条记录，写入{}个分区",
+            cutoff,
+            records.len(),
+            partitions.len()
+        );
+        Ok(RolloverReport {
+            cutoff,
+            records: records.len(),
+            partitions,
+        })
+    }
+
+    /// 启动后台维护线程：立即滚动一次，之后每隔 `interval` 滚动一次
+    pub fn spawn_maintenance(self: &Arc<Self>, interval: Duration) -> MaintenanceTask {
+        let store = Arc::clone(self);
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = std::thread::spawn(move || loop {
+            if let Err(e) = store.rollover(Local::now().date_naive()) {
+                warn!("冷热分层滚动失败: {:#}", e);
+            }
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                _ => break,
+            }
+        });
+        MaintenanceTask {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl DataSource for TieredStore {
+    fn name(&self) -> &str {
+        "tiered"
+    }
+
+    fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+        let mut symbols: BTreeSet<(String, String)> =
+            self.hot.list_symbols()?.into_iter().collect();
+        symbols.extend(self.cold.list_symbols()?);
+        Ok(symbols.into_iter().collect())
+    }
+
+    fn fetch_bars(
+        &self,
+        symbol: &str,
+        market: &str,
+        range: &DateRange,
+    ) -> Result<Vec<TDXDayRecord>> {
+        let Some(watermark) = self.cold.watermark()? else {
+            return self.hot.fetch_bars(symbol, market, range);
+        };
+
+        // 水位线及之前从冷存储读取，之后从热存储读取
+        let mut bars = Vec::new();
+        if range.start.is_none_or(|start| start <= watermark) {
+            let cold_range = DateRange {
+                start: range.start,
+                end: Some(range.end.map_or(watermark, |end| end.min(watermark))),
+            };
+            bars.extend(self.cold.fetch_bars(symbol, market, &cold_range)?);
+        }
+        if range.end.is_none_or(|end| end > watermark) {
+            let after_watermark = watermark.succ_opt().unwrap_or(watermark);
+            let hot_range = DateRange {
+                start: Some(
+                    range
+                        .start
+                        .map_or(after_watermark, |start| start.max(after_watermark)),
+                ),
+                end: range.end,
+            };
+            bars.extend(self.hot.fetch_bars(symbol, market, &hot_range)?);
+        }
+        Ok(bars)
+    }
+}
+
+/// 后台维护线程，停止或释放时结束线程
+#[derive(Debug)]
+pub struct MaintenanceTask {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MaintenanceTask {
+    /// 停止维护线程并等待当前滚动完成
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("冷热分层维护线程异常退出");
+            }
+        }
+    }
+}
+
+impl Drop for MaintenanceTask {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// 所在月份的第一天
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// 下个月的第一天
+fn next_month(month: NaiveDate) -> NaiveDate {
+    month
+        .checked_add_months(Months::new(1))
+        .unwrap_or(NaiveDate::MAX)
+}
+
+fn partition_name(month: NaiveDate) -> String {
+    month.format("%Y-%m").to_string()
+}
+
+fn parse_partition(name: &str) -> Option<NaiveDate> {
+    if name.len() != 7 {
+        return None;
+    }
+    NaiveDate::parse_from_str(&format!("{}-01", name), "%Y-%m-%d").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    /// 测试用内存热存储
+    #[derive(Default)]
+    struct MemoryHot {
+        records: Mutex<Vec<TDXDayRecord>>,
+    }
+
+    impl MemoryHot {
+        fn with(records: Vec<TDXDayRecord>) -> Arc<Self> {
+            Arc::new(Self {
+                records: Mutex::new(records),
+            })
+        }
+
+        fn len(&self) -> usize {
+            self.records.lock().unwrap().len()
+        }
+    }
+
+    impl DataSource for MemoryHot {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        fn list_symbols(&self) -> Result<Vec<(String, String)>> {
+            let records = self.records.lock().unwrap();
+            let symbols: BTreeSet<_> = records
+                .iter()
+                .map(|r| (r.symbol.clone(), r.market.clone()))
+                .collect();
+            Ok(symbols.into_iter().collect())
+        }
+
+        fn fetch_bars(
+            &self,
+            symbol: &str,
+            market: &str,
+            range: &DateRange,
+        ) -> Result<Vec<TDXDayRecord>> {
+            let records = self.records.lock().unwrap();
+            Ok(records
+                .iter()
+                .filter(|r| r.symbol == symbol && r.market == market && range.contains(r.date))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl HotStore for MemoryHot {
+        fn records_before(&self, cutoff: NaiveDate) -> Result<Vec<TDXDayRecord>> {
+            let records = self.records.lock().unwrap();
+            Ok(records
+                .iter()
+                .filter(|r| r.date < cutoff)
+                .cloned()
+                .collect())
+        }
+
+        fn delete_before(&self, cutoff: NaiveDate) -> Result<()> {
+            self.records.lock().unwrap().retain(|r| r.date >= cutoff);
+            Ok(())
+        }
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn record(symbol: &str, date: NaiveDate, close: f64) -> TDXDayRecord {
+        TDXDayRecord {
+            date,
+            symbol: symbol.to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100,
+            amount: close * 100.0,
+            market: "SH".to_string(),
+        }
+    }
+
+    /// 2024年1月至6月每5天一条，两只股票
+    fn records() -> Vec<TDXDayRecord> {
+        let mut records = Vec::new();
+        let mut day = date(1, 1);
+        while day < date(7, 1) {
+            records.push(record("600000", day, 10.0));
+            records.push(record("600001", day, 20.0));
+            day += chrono::Duration::days(5);
+        }
+        records
+    }
+
+    #[test]
+    fn test_rollover_moves_old_months() {
+        let temp_dir = TempDir::new().unwrap();
+        let all = records();
+        let hot = MemoryHot::with(all.clone());
+        let store =
+            TieredStore::new(hot.clone(), ColdStore::new(temp_dir.path())).with_hot_months(3);
+        assert_eq!(store.cutoff(date(6, 15)), date(4, 1));
+
+        let report = store.rollover(date(6, 15)).unwrap();
+        let rolled = all.iter().filter(|r| r.date < date(4, 1)).count();
+        assert_eq!(report.records, rolled);
+        assert_eq!(report.partitions, vec!["2024-01", "2024-02", "2024-03"]);
+        assert_eq!(hot.len(), all.len() - rolled);
+        assert_eq!(store.cold().watermark().unwrap(), Some(date(3, 31)));
+        assert_eq!(
+            store.cold().partitions().unwrap(),
+            vec![date(1, 1), date(2, 1), date(3, 1)]
+        );
+
+        // 再次滚动没有可移动的数据
+        let again = store.rollover(date(6, 20)).unwrap();
+        assert_eq!(again.records, 0);
+        assert!(again.partitions.is_empty());
+        assert_eq!(store.list_symbols().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_read_stitches_tiers() {
+        let temp_dir = TempDir::new().unwrap();
+        let all = records();
+        let store = TieredStore::new(
+            MemoryHot::with(all.clone()),
+            ColdStore::new(temp_dir.path()),
+        )
+        .with_hot_months(3);
+
+        let expected = |range: &DateRange| -> Vec<NaiveDate> {
+            all.iter()
+                .filter(|r| r.symbol == "600000" && range.contains(r.date))
+                .map(|r| r.date)
+                .collect()
+        };
+        let dates = |store: &TieredStore, range: &DateRange| -> Vec<NaiveDate> {
+            store
+                .fetch_bars("600000", "SH", range)
+                .unwrap()
+                .iter()
+                .map(|r| r.date)
+                .collect()
+        };
+
+        let ranges = [
+            DateRange::all(),
+            DateRange::new(date(3, 10), date(4, 20)),
+            DateRange::new(date(1, 5), date(2, 10)),
+            DateRange::since(date(5, 1)),
+        ];
+        // 滚动前全部来自热存储
+        for range in &ranges {
+            assert_eq!(dates(&store, range), expected(range));
+        }
+        store.rollover(date(6, 15)).unwrap();
+        for range in &ranges {
+            assert_eq!(dates(&store, range), expected(range));
+        }
+    }
+
+    #[test]
+    fn test_rollover_merges_partitions_and_maintenance() {
+        let temp_dir = TempDir::new().unwrap();
+        let hot = MemoryHot::with(records());
+        let store = Arc::new(
+            TieredStore::new(hot.clone(), ColdStore::new(temp_dir.path())).with_hot_months(3),
+        );
+        store.rollover(date(6, 15)).unwrap();
+
+        // 补录的历史数据进入热存储，下次滚动并入已有分区并覆盖同日记录
+        hot.records.lock().unwrap().extend([
+            record("600000", date(1, 1), 11.0),
+            record("600002", date(1, 2), 5.0),
+        ]);
+        let report = store.rollover(date(6, 15)).unwrap();
+        assert_eq!(report.partitions, vec!["2024-01"]);
+
+        let january = DateRange::new(date(1, 1), date(1, 31));
+        let bars = store.fetch_bars("600000", "SH", &january).unwrap();
+        assert_eq!(bars.iter().filter(|r| r.date == date(1, 1)).count(), 1);
+        assert_eq!(bars[0].close, 11.0);
+        assert_eq!(store.fetch_bars("600002", "SH", &january).unwrap().len(), 1);
+
+        // 维护线程按当天日期滚动：2024年的数据全部移入冷存储
+        let task = store.spawn_maintenance(Duration::from_secs(3600));
+        task.stop();
+        assert_eq!(hot.len(), 0);
+        assert_eq!(store.cold().partitions().unwrap().len(), 6);
+        assert_eq!(
+            store
+                .fetch_bars("600001", "SH", &DateRange::all())
+                .unwrap()
+                .len(),
+            records().len() / 2
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! 测试数据生成与脱敏
+//!
+//! `Anonymizer` 把真实日线处理为可分享的测试数据：按种子抽样股票、把代码替换为同板块前缀的
+//! 随机代码、日期整体平移整周（星期几和周末间隔不变）、每只股票的价格乘以同一随机系数
+//! 并四舍五入到分。四舍五入是单调的，缩放后 `low <= open, close <= high` 仍然成立，
+//! 收益率序列近似不变。
+//!
+//! `SyntheticGenerator` 按几何布朗运动生成工作日日线，可按概率加入跳空、涨跌停
+//! （按板块规则封板）和停牌。两者都只依赖种子，相同参数的输出完全一致。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::limits::LimitDetector;
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// 每年交易日数
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// SplitMix64伪随机数发生器，不依赖外部库，同一种子的序列在各平台一致
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// [0, 1) 均匀分布
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// [low, high) 均匀分布
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    /// [0, n) 均匀整数
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    /// 标准正态分布（Box-Muller）
+    fn normal(&mut self) -> f64 {
+        let u1 = self.next_f64().max(f64::MIN_POSITIVE);
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+/// 四舍五入到分
+fn round_cent(price: f64) -> f64 {
+    (price * 100.0).round() / 100.0
+}
+
+/// 测试数据脱敏器
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    /// 随机种子
+    seed: u64,
+    /// 抽样的股票数，None为全部
+    sample_symbols: Option<usize>,
+    /// 日期平移的周数，None为按种子在过去1~10年内随机
+    shift_weeks: Option<i64>,
+    /// 价格缩放系数范围
+    price_scale: (f64, f64),
+    /// 是否替换股票代码
+    rename_symbols: bool,
+}
+
+impl Anonymizer {
+    /// 创建脱敏器：保留全部股票、随机平移日期、价格缩放0.5~2倍、替换股票代码
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            sample_symbols: None,
+            shift_weeks: None,
+            price_scale: (0.5, 2.0),
+            rename_symbols: true,
+        }
+    }
+
+    /// 只保留随机抽取的 `count` 只股票
+    pub fn with_sample_symbols(mut self, count: usize) -> Self {
+        self.sample_symbols = Some(count);
+        self
+    }
+
+    /// 设置日期平移的周数（负数表示提前）
+    pub fn with_date_shift_weeks(mut self, weeks: i64) -> Self {
+        self.shift_weeks = Some(weeks);
+        self
+    }
+
+    /// 设置价格缩放系数范围，每只股票在范围内取一个系数
+    pub fn with_price_scale(mut self, min: f64, max: f64) -> Self {
+        self.price_scale = (min.min(max), min.max(max));
+        self
+    }
+
+    /// 保留原股票代码
+    pub fn with_original_symbols(mut self) -> Self {
+        self.rename_symbols = false;
+        self
+    }
+
+    /// 脱敏，输出保持输入中的先后顺序
+    pub fn anonymize(&self, records: &[TDXDayRecord]) -> Result<Vec<TDXDayRecord>> {
+        if self.price_scale.0 <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "价格缩放系数必须为正数: {}",
+                self.price_scale.0
+            ));
+        }
+        let mut rng = Rng::new(self.seed);
+        let shift_weeks = self
+            .shift_weeks
+            .unwrap_or_else(|| -(52 + rng.below(52 * 9) as i64));
+        let shift = Duration::weeks(shift_weeks);
+
+        let symbols: BTreeSet<(&str, &str)> = records
+            .iter()
+            .map(|r| (r.symbol.as_str(), r.market.as_str()))
+            .collect();
+        let mut symbols: Vec<(&str, &str)> = symbols.into_iter().collect();
+        if let Some(count) = self.sample_symbols {
+            // Fisher-Yates洗牌后取前count只，再恢复排序使后续随机数与输入顺序无关
+            for i in (1..symbols.len()).rev() {
+                symbols.swap(i, rng.below(i as u64 + 1) as usize);
+            }
+            symbols.truncate(count);
+            symbols.sort();
+        }
+
+        let mut used: HashSet<(String, String)> = HashSet::new();
+        let mut mapping: HashMap<(&str, &str), (String, f64)> = HashMap::new();
+        for (symbol, market) in symbols {
+            let scale = rng.range(self.price_scale.0, self.price_scale.1);
+            let renamed = match self.rename_symbols {
+                true => self.rename(symbol, market, &mut rng, &mut used)?,
+                false => symbol.to_string(),
+            };
+            mapping.insert((symbol, market), (renamed, scale));
+        }
+
+        records
+            .iter()
+            .filter_map(|record| {
+                let (symbol, scale) =
+                    mapping.get(&(record.symbol.as_str(), record.market.as_str()))?;
+                let date = record.date.checked_add_signed(shift);
+                Some(
+                    date.map(|date| TDXDayRecord {
+                        date,
+                        symbol: symbol.clone(),
+                        open: round_cent(record.open * scale),
+                        high: round_cent(record.high * scale),
+                        low: round_cent(record.low * scale),
+                        close: round_cent(record.close * scale),
+                        volume: record.volume,
+                        amount: record.amount * scale,
+                        market: record.market.clone(),
+                    })
+                    .ok_or_else(|| anyhow::anyhow!("日期平移后超出范围: {}", record.date)),
+                )
+            })
+            .collect()
+    }
+
+    /// 保留前3位（板块前缀），其余位随机，同一市场内不重复
+    fn rename(
+        &self,
+        symbol: &str,
+        market: &str,
+        rng: &mut Rng,
+        used: &mut HashSet<(String, String)>,
+    ) -> Result<String> {
+        let prefix: String = symbol.chars().take(3).collect();
+        let width = symbol.chars().count().saturating_sub(3).max(1);
+        let space = 10u64.saturating_pow(width as u32);
+        for _ in 0..space.saturating_mul(4).min(100_000) {
+            let candidate = format!("{}{:0width$}", prefix, rng.below(space), width = width);
+            if used.insert((candidate.clone(), market.to_string())) {
+                return Ok(candidate);
+            }
+        }
+        Err(anyhow::anyhow!(
+            "前缀{}下可用的股票代码不足: {}",
+            prefix,
+            market
+        ))
+    }
+}
+
+/// 模拟日线生成器
+#[derive(Debug, Clone)]
+pub struct SyntheticGenerator {
+    /// 随机种子
+    seed: u64,
+    /// 股票（代码, 市场）
+    symbols: Vec<(String, String)>,
+    /// 起始日期（周末顺延）
+    start_date: NaiveDate,
+    /// 工作日数
+    days: usize,
+    /// 初始价格（每只股票在0.5~2倍之间浮动）
+    initial_price: f64,
+    /// 年化漂移率
+    drift: f64,
+    /// 年化波动率
+    volatility: f64,
+    /// 开盘跳空（3%~8%）的概率
+    gap_probability: f64,
+    /// 收盘涨停或跌停的概率
+    limit_probability: f64,
+    /// 停牌（当日无记录）的概率
+    suspension_probability: f64,
+}
+
+impl SyntheticGenerator {
+    /// 创建生成器：10只沪市主板股票，2020-01-02起250个工作日，
+    /// 初始价格10元，年化漂移5%、波动率30%，跳空2%，涨跌停1%，不停牌
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            symbols: Vec::new(),
+            start_date: NaiveDate::from_ymd_opt(2020, 1, 2).unwrap(),
+            days: 250,
+            initial_price: 10.0,
+            drift: 0.05,
+            volatility: 0.3,
+            gap_probability: 0.02,
+            limit_probability: 0.01,
+            suspension_probability: 0.0,
+        }
+        .with_symbol_count(10)
+    }
+
+    /// 生成 `count` 只沪市主板股票（600000起连续编号）
+    pub fn with_symbol_count(mut self, count: usize) -> Self {
+        self.symbols = (0..count)
+            .map(|i| (format!("{:06}", 600_000 + i), "SH".to_string()))
+            .collect();
+        self
+    }
+
+    /// 指定股票（代码, 市场）
+    pub fn with_symbols(mut self, symbols: Vec<(String, String)>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// 设置起始日期和工作日数
+    pub fn with_period(mut self, start_date: NaiveDate, days: usize) -> Self {
+        self.start_date = start_date;
+        self.days = days;
+        self
+    }
+
+    /// 设置初始价格
+    pub fn with_initial_price(mut self, price: f64) -> Self {
+        self.initial_price = price;
+        self
+    }
+
+    /// 设置年化漂移率和波动率
+    pub fn with_gbm(mut self, drift: f64, volatility: f64) -> Self {
+        self.drift = drift;
+        self.volatility = volatility.max(0.0);
+        self
+    }
+
+    /// 设置开盘跳空的概率
+    pub fn with_gap_probability(mut self, probability: f64) -> Self {
+        self.gap_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置收盘涨跌停的概率
+    pub fn with_limit_probability(mut self, probability: f64) -> Self {
+        self.limit_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 设置停牌的概率
+    pub fn with_suspension_probability(mut self, probability: f64) -> Self {
+        self.suspension_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 生成日线，按（股票, 日期）排序
+    pub fn generate(&self) -> Vec<TDXDayRecord> {
+        let dates: Vec<NaiveDate> = self
+            .start_date
+            .iter_days()
+            .filter(|date| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun))
+            .take(self.days)
+            .collect();
+        let detector = LimitDetector::new();
+
+        let mut records = Vec::with_capacity(self.symbols.len() * dates.len());
+        for (index, (symbol, market)) in self.symbols.iter().enumerate() {
+            // 每只股票独立的随机序列，增删其他股票不影响它的数据
+            let mut rng = Rng::new(self.seed ^ (index as u64).wrapping_mul(0xA076_1D64_78BD_642F));
+            let mut prev_close = round_cent(self.initial_price * rng.range(0.5, 2.0)).max(0.01);
+            for &date in &dates {
+                if rng.next_f64() < self.suspension_probability {
+                    continue;
+                }
+                let percent = detector.limit_percent(symbol, market, date).unwrap_or(10.0);
+                let record = self.bar(&mut rng, symbol, market, date, prev_close, percent);
+                prev_close = record.close;
+                records.push(record);
+            }
+        }
+        records
+    }
+
+    /// 生成一根日线，价格限制在涨跌停价之间
+    fn bar(
+        &self,
+        rng: &mut Rng,
+        symbol: &str,
+        market: &str,
+        date: NaiveDate,
+        prev_close: f64,
+        percent: f64,
+    ) -> TDXDayRecord {
+        let (up, down) = LimitDetector::limit_prices(prev_close, percent);
+        let down = down.max(0.01);
+        let clamp = |price: f64| round_cent(price).clamp(down, up.max(down));
+        let step = self.volatility / TRADING_DAYS_PER_YEAR.sqrt();
+
+        let open = match rng.next_f64() < self.gap_probability {
+            true => {
+                let sign = if rng.next_f64() < 0.5 { -1.0 } else { 1.0 };
+                prev_close * (1.0 + sign * rng.range(0.03, 0.08))
+            }
+            false => prev_close * (1.0 + 0.3 * step * rng.normal()),
+        };
+        let open = clamp(open);
+
+        let roll = rng.next_f64();
+        let close = if roll < self.limit_probability / 2.0 {
+            up
+        } else if roll < self.limit_probability {
+            down
+        } else {
+            let log_return = (self.drift - self.volatility.powi(2) / 2.0) / TRADING_DAYS_PER_YEAR
+                + step * rng.normal();
+            clamp(open * log_return.exp())
+        };
+        let high = clamp(open.max(close) * (1.0 + 0.5 * step * rng.normal().abs()));
+        let low = clamp(open.min(close) * (1.0 - 0.5 * step * rng.normal().abs()));
+
+        // A股以100股为一手
+        let volume = ((1_000_000.0 * (0.5 * rng.normal()).exp()) as u64 / 100).max(1) * 100;
+        TDXDayRecord {
+            date,
+            symbol: symbol.to_string(),
+            open,
+            high,
+            low,
+            close,
+            volume,
+            amount: volume as f64 * (open + high + low + close) / 4.0,
+            market: market.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::limits::LimitKind;
+
+    fn assert_ohlc(records: &[TDXDayRecord]) {
+        for r in records {
+            assert!(r.low > 0.0 && r.low <= r.open.min(r.close), "{:?}", r);
+            assert!(r.high >= r.open.max(r.close), "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn test_synthetic_generator() {
+        let symbols = vec![
+            ("600000".to_string(), "SH".to_string()),
+            ("300750".to_string(), "SZ".to_string()),
+        ];
+        let generator = SyntheticGenerator::new(7)
+            .with_symbols(symbols.clone())
+            .with_limit_probability(0.1)
+            .with_suspension_probability(0.05);
+        let records = generator.generate();
+        assert_eq!(
+            format!("{:?}", records),
+            format!("{:?}", generator.generate())
+        );
+        let other = SyntheticGenerator::new(8).with_symbols(symbols).generate();
+        assert_ne!(records[0].close, other[0].close);
+        assert_ohlc(&records);
+        assert!(records
+            .iter()
+            .all(|r| !matches!(r.date.weekday(), Weekday::Sat | Weekday::Sun)));
+        // 有停牌，记录数少于2×250
+        assert!(records.len() < 500 && records.len() > 400);
+
+        let events = LimitDetector::new().detect(&records).unwrap();
+        let limit_ups = events
+            .iter()
+            .filter(|event| event.kind == LimitKind::LimitUp)
+            .count();
+        assert!(limit_ups > 0);
+        // 所有涨跌幅都在板块限制之内
+        for pair in records.windows(2).filter(|p| p[0].symbol == p[1].symbol) {
+            let limit = if pair[1].symbol.starts_with("300") {
+                0.2
+            } else {
+                0.1
+            };
+            assert!((pair[1].close / pair[0].close - 1.0).abs() <= limit + 0.006);
+        }
+    }
+
+    #[test]
+    fn test_anonymize() {
+        let records = SyntheticGenerator::new(1).with_symbol_count(5).generate();
+        let anonymizer = Anonymizer::new(42).with_sample_symbols(2);
+        let shared = anonymizer.anonymize(&records).unwrap();
+        let again = anonymizer.anonymize(&records).unwrap();
+        assert_eq!(format!("{:?}", shared), format!("{:?}", again));
+        assert_eq!(shared.len(), 2 * 250);
+        assert_ohlc(&shared);
+
+        let symbols: BTreeSet<&str> = shared.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols.len(), 2);
+        assert!(symbols.iter().all(|s| s.starts_with("600") && s.len() == 6));
+
+        // 整周平移保留星期几；同一股票的收益率近似不变
+        let kept = Anonymizer::new(42)
+            .with_original_symbols()
+            .with_date_shift_weeks(-3)
+            .anonymize(&records)
+            .unwrap();
+        assert_eq!(kept.len(), records.len());
+        for (original, shifted) in records.iter().zip(&kept) {
+            assert_eq!(shifted.date, original.date - Duration::weeks(3));
+            assert_eq!(shifted.date.weekday(), original.date.weekday());
+            assert_eq!(shifted.symbol, original.symbol);
+        }
+        let ratio = |r: &[TDXDayRecord]| r[1].close / r[0].close;
+        assert!((ratio(&kept) - ratio(&records)).abs() < 0.01);
+
+        assert!(Anonymizer::new(1)
+            .with_price_scale(0.0, 1.0)
+            .anonymize(&records)
+            .is_err());
+    }
+}
@@ -0,0 +1,8 @@
+//! 测试数据工具
+//!
+//! `fixtures` 把真实解析出的日线处理为可对外分享的测试数据（抽样股票、平移日期、
+//! 缩放价格），并提供按种子确定生成的模拟日线，供单元测试和基准测试使用。
+
+pub mod fixtures;
+
+pub use fixtures::{Anonymizer, SyntheticGenerator};
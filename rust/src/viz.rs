@@ -0,0 +1,504 @@
+//! K线图绘制（`viz` 特性）
+//!
+//! 基于plotters把单只股票一段区间的日线绘制为PNG或SVG：上方为K线（A股习惯红涨绿跌）
+//! 叠加均线、布林带、支撑/阻力位和交易信号标记，下方为成交量柱。横轴按交易日排列，停牌和节假日
+//! 不留空白。用于快速目检解析结果和信号位置；Python端通过 `render_kline` 调用。
+
+use crate::parsers::TDXDayRecord;
+use crate::processors::kernels::{rolling_mean, rolling_std};
+use crate::processors::levels::{LevelKind, PriceLevel};
+use crate::processors::signals::{Signal, SignalKind};
+use crate::source::DateRange;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 上涨颜色
+const UP: RGBColor = RGBColor(214, 39, 40);
+/// 下跌颜色
+const DOWN: RGBColor = RGBColor(44, 160, 44);
+/// 支撑位颜色
+const SUPPORT: RGBColor = RGBColor(31, 119, 180);
+/// 阻力位颜色
+const RESISTANCE: RGBColor = RGBColor(255, 127, 14);
+
+/// 叠加指标
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Overlay {
+    /// 收盘价简单移动平均
+    Ma {
+        /// 周期
+        window: usize,
+    },
+    /// 布林带（中轨±k倍总体标准差）
+    Bollinger {
+        /// 周期
+        window: usize,
+        /// 标准差倍数
+        k: f64,
+    },
+}
+
+impl Overlay {
+    /// 各条线的名称和数值
+    fn lines(&self, closes: &[f64]) -> Vec<(String, Vec<f64>)> {
+        match *self {
+            Overlay::Ma { window } => vec![(format!("MA{}", window), rolling_mean(closes, window))],
+            Overlay::Bollinger { window, k } => {
+                let middle = rolling_mean(closes, window);
+                let std = rolling_std(closes, window);
+                let band = |sign: f64| -> Vec<f64> {
+                    middle
+                        .iter()
+                        .zip(&std)
+                        .map(|(m, s)| m + sign * k * s)
+                        .collect()
+                };
+                vec![
+                    (format!("BOLL{}上轨", window), band(1.0)),
+                    (format!("BOLL{}中轨", window), middle.clone()),
+                    (format!("BOLL{}下轨", window), band(-1.0)),
+                ]
+            }
+        }
+    }
+}
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// PNG位图
+    Png,
+    /// SVG矢量图
+    Svg,
+}
+
+impl ImageFormat {
+    /// 由文件扩展名判断格式
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("png") => Ok(ImageFormat::Png),
+            Some("svg") => Ok(ImageFormat::Svg),
+            _ => Err(anyhow::anyhow!(
+                "不支持的图片格式（仅支持png/svg）: {}",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// 按代码和日期范围选出一只股票的日线，按日期排序
+pub fn select<'a>(
+    bars: &'a [TDXDayRecord],
+    symbol: &str,
+    range: &DateRange,
+) -> Vec<&'a TDXDayRecord> {
+    let mut selected: Vec<&TDXDayRecord> = bars
+        .iter()
+        .filter(|bar| bar.symbol == symbol && range.contains(bar.date))
+        .collect();
+    selected.sort_by_key(|bar| bar.date);
+    selected
+}
+
+/// K线图
+#[derive(Debug, Clone)]
+pub struct KlineChart {
+    /// 图片宽度（像素）
+    width: u32,
+    /// 图片高度（像素）
+    height: u32,
+    /// 叠加指标
+    overlays: Vec<Overlay>,
+    /// 是否绘制成交量
+    volume: bool,
+    /// 标题（缺省为 `代码.市场`）
+    title: Option<String>,
+    /// 信号标记
+    signals: Vec<(NaiveDate, SignalKind)>,
+    /// 支撑/阻力位
+    levels: Vec<(f64, LevelKind)>,
+}
+
+impl Default for KlineChart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KlineChart {
+    /// 创建1200×800的K线图，叠加MA5、MA20和20日布林带，绘制成交量
+    pub fn new() -> Self {
+        Self {
+            width: 1200,
+            height: 800,
+            overlays: vec![
+                Overlay::Ma { window: 5 },
+                Overlay::Ma { window: 20 },
+                Overlay::Bollinger { window: 20, k: 2.0 },
+            ],
+            volume: true,
+            title: None,
+            signals: Vec::new(),
+            levels: Vec::new(),
+        }
+    }
+
+    /// 设置图片尺寸
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// 设置叠加指标（替换默认的均线和布林带）
+    pub fn with_overlays(mut self, overlays: Vec<Overlay>) -> Self {
+        self.overlays = overlays;
+        self
+    }
+
+    /// 设置是否绘制成交量
+    pub fn with_volume(mut self, volume: bool) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// 设置标题
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// 标记交易信号：入场信号画在最低价下方，出场信号画在最高价上方
+    pub fn with_signals(mut self, signals: &[Signal]) -> Self {
+        self.signals = signals
+            .iter()
+            .map(|signal| (signal.date, signal.kind))
+            .collect();
+        self
+    }
+
+    /// 绘制支撑/阻力位水平线（超出价格范围的不画）
+    pub fn with_levels(mut self, levels: &[PriceLevel]) -> Self {
+        self.levels = levels
+            .iter()
+            .map(|level| (level.price, level.kind))
+            .collect();
+        self
+    }
+
+    /// 绘制到文件，格式由扩展名决定（png/svg）
+    pub fn render<P: AsRef<Path>>(&self, bars: &[&TDXDayRecord], path: P) -> Result<()> {
+        let path = path.as_ref();
+        let size = (self.width, self.height);
+        match ImageFormat::from_path(path)? {
+            ImageFormat::Png => self.draw(BitMapBackend::new(path, size).into_drawing_area(), bars),
+            ImageFormat::Svg => self.draw(SVGBackend::new(path, size).into_drawing_area(), bars),
+        }
+        .with_context(|| format!("绘制K线图失败: {}", path.display()))
+    }
+
+    /// 绘制为SVG文本
+    pub fn render_svg(&self, bars: &[&TDXDayRecord]) -> Result<String> {
+        let mut svg = String::new();
+        self.draw(
+            SVGBackend::with_string(&mut svg, (self.width, self.height)).into_drawing_area(),
+            bars,
+        )?;
+        Ok(svg)
+    }
+
+    fn draw<DB>(&self, root: DrawingArea<DB, Shift>, bars: &[&TDXDayRecord]) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        let first = bars
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("没有可绘制的日线"))?;
+        if let Some(other) = bars.iter().find(|bar| bar.symbol != first.symbol) {
+            return Err(anyhow::anyhow!(
+                "K线图只能绘制一只股票: {} 与 {}",
+                first.symbol,
+                other.symbol
+            ));
+        }
+        let fail = |e: DrawingAreaErrorKind<DB::ErrorType>| anyhow::anyhow!("绘图失败: {:?}", e);
+
+        let title = self
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("{}.{}", first.symbol, first.market));
+        let dates: Vec<NaiveDate> = bars.iter().map(|bar| bar.date).collect();
+        let closes: Vec<f64> = bars.iter().map(|bar| bar.close).collect();
+        let lines: Vec<(String, Vec<f64>)> = self
+            .overlays
+            .iter()
+            .flat_map(|overlay| overlay.lines(&closes))
+            .collect();
+
+        root.fill(&WHITE).map_err(fail)?;
+        let (upper, lower) = if self.volume {
+            let split = self.height * 3 / 4;
+            let (upper, lower) = root.split_vertically(split);
+            (upper, Some(lower))
+        } else {
+            (root.clone(), None)
+        };
+
+        // 价格范围包含叠加线，留出信号标记的空间
+        let (mut lo, mut hi) = bars.iter().fold((f64::MAX, f64::MIN), |(lo, hi), bar| {
+            (lo.min(bar.low), hi.max(bar.high))
+        });
+        for value in lines.iter().flat_map(|(_, values)| values.iter()) {
+            if value.is_finite() {
+                lo = lo.min(*value);
+                hi = hi.max(*value);
+            }
+        }
+        let pad = ((hi - lo) * 0.05).max(hi.abs() * 0.001).max(0.01);
+        let (lo, hi) = (lo - pad, hi + pad);
+
+        let n = bars.len();
+        let x_range = -0.5f64..(n as f64 - 0.5);
+        let date_label = |x: &f64| {
+            let i = x.round();
+            if i >= 0.0 && (i as usize) < n {
+                dates[i as usize].format("%Y-%m-%d").to_string()
+            } else {
+                String::new()
+            }
+        };
+
+        let mut chart = ChartBuilder::on(&upper)
+            .caption(&title, ("sans-serif", 20))
+            .margin(10)
+            .x_label_area_size(if self.volume { 0 } else { 30 })
+            .y_label_area_size(60)
+            .build_cartesian_2d(x_range.clone(), lo..hi)
+            .map_err(fail)?;
+        chart
+            .configure_mesh()
+            .x_labels(8)
+            .x_label_formatter(&date_label)
+            .y_label_formatter(&|y| format!("{:.2}", y))
+            .light_line_style(WHITE)
+            .draw()
+            .map_err(fail)?;
+
+        let plot_width = chart.plotting_area().dim_in_pixel().0 as f64;
+        let candle_width = ((plot_width / n as f64) * 0.7).clamp(1.0, 30.0) as u32;
+        chart
+            .draw_series(bars.iter().enumerate().map(|(i, bar)| {
+                CandleStick::new(
+                    i as f64,
+                    bar.open,
+                    bar.high,
+                    bar.low,
+                    bar.close,
+                    UP.filled(),
+                    DOWN.filled(),
+                    candle_width,
+                )
+            }))
+            .map_err(fail)?;
+
+        for (index, (name, values)) in lines.iter().enumerate() {
+            let color = Palette99::pick(index).to_rgba();
+            chart
+                .draw_series(LineSeries::new(
+                    values
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, value)| value.is_finite())
+                        .map(|(i, value)| (i as f64, *value)),
+                    color.stroke_width(1),
+                ))
+                .map_err(fail)?
+                .label(name.as_str())
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 16, y)], color));
+        }
+
+        for &(price, kind) in self
+            .levels
+            .iter()
+            .filter(|(price, _)| (lo..hi).contains(price))
+        {
+            let color = match kind {
+                LevelKind::Support => SUPPORT,
+                LevelKind::Resistance => RESISTANCE,
+            };
+            chart
+                .draw_series(LineSeries::new(
+                    [(-0.5, price), (n as f64 - 0.5, price)],
+                    color.stroke_width(1),
+                ))
+                .map_err(fail)?;
+        }
+
+        let offset = pad * 0.5;
+        let markers: Vec<(f64, f64, SignalKind)> = self
+            .signals
+            .iter()
+            .filter_map(|(date, kind)| {
+                let i = dates.binary_search(date).ok()?;
+                let y = match kind {
+                    SignalKind::Entry => bars[i].low - offset,
+                    SignalKind::Exit => bars[i].high + offset,
+                };
+                Some((i as f64, y, *kind))
+            })
+            .collect();
+        chart
+            .draw_series(markers.iter().map(|&(x, y, kind)| {
+                let color = match kind {
+                    SignalKind::Entry => UP,
+                    SignalKind::Exit => DOWN,
+                };
+                TriangleMarker::new((x, y), 6, color.filled())
+            }))
+            .map_err(fail)?;
+
+        if !lines.is_empty() {
+            chart
+                .configure_series_labels()
+                .position(SeriesLabelPosition::UpperLeft)
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .draw()
+                .map_err(fail)?;
+        }
+
+        if let Some(lower) = lower {
+            let max_volume = bars.iter().map(|bar| bar.volume).max().unwrap_or(0).max(1) as f64;
+            let mut chart = ChartBuilder::on(&lower)
+                .margin(10)
+                .x_label_area_size(30)
+                .y_label_area_size(60)
+                .build_cartesian_2d(x_range, 0.0..max_volume * 1.1)
+                .map_err(fail)?;
+            chart
+                .configure_mesh()
+                .x_labels(8)
+                .x_label_formatter(&date_label)
+                .y_labels(3)
+                .y_label_formatter(&|v| format!("{:.0}", v))
+                .light_line_style(WHITE)
+                .draw()
+                .map_err(fail)?;
+            chart
+                .draw_series(bars.iter().enumerate().map(|(i, bar)| {
+                    let color = if bar.close >= bar.open { UP } else { DOWN };
+                    let x = i as f64;
+                    Rectangle::new(
+                        [(x - 0.35, 0.0), (x + 0.35, bar.volume as f64)],
+                        color.filled(),
+                    )
+                }))
+                .map_err(fail)?;
+        }
+
+        root.present().map_err(fail)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars() -> Vec<TDXDayRecord> {
+        (0..40)
+            .map(|i| {
+                let close = 10.0 + (i as f64 * 0.3).sin();
+                let open = close - 0.1 * (i % 3) as f64 + 0.1;
+                TDXDayRecord {
+                    date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap() + chrono::Duration::days(i),
+                    symbol: "600000".to_string(),
+                    open,
+                    high: open.max(close) + 0.2,
+                    low: open.min(close) - 0.2,
+                    close,
+                    volume: 100_000 + 1_000 * i as u64,
+                    amount: close * 100_000.0,
+                    market: "SH".to_string(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let bars = bars();
+        let selected = select(&bars, "600000", &DateRange::all());
+        let signal = Signal {
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            date: bars[25].date,
+            kind: SignalKind::Entry,
+            strength: 1.0,
+            rule: "test".to_string(),
+        };
+        let level = PriceLevel {
+            symbol: "600000".to_string(),
+            market: "SH".to_string(),
+            price: 10.5,
+            kind: LevelKind::Resistance,
+            touches: 2,
+            first_date: bars[5].date,
+            last_date: bars[26].date,
+            strength: 1.5,
+        };
+        let svg = KlineChart::new()
+            .with_signals(&[signal])
+            .with_levels(&[level])
+            .render_svg(&selected)
+            .unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("600000.SH"));
+        assert!(svg.contains("MA20"));
+        assert!(svg.contains("BOLL20"));
+        assert!(svg.contains("2024-01-01"));
+    }
+
+    #[test]
+    fn test_render_files() {
+        let bars = bars();
+        let range = DateRange::new(
+            NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+        );
+        let selected = select(&bars, "600000", &range);
+        assert_eq!(selected.len(), 22);
+
+        let dir = tempfile::tempdir().unwrap();
+        let chart = KlineChart::new().with_size(640, 480);
+        let png = dir.path().join("kline.png");
+        chart.render(&selected, &png).unwrap();
+        assert_eq!(&std::fs::read(&png).unwrap()[1..4], b"PNG");
+        let svg = dir.path().join("kline.SVG");
+        chart.with_volume(false).render(&selected, &svg).unwrap();
+        assert!(std::fs::read_to_string(&svg).unwrap().starts_with("<svg"));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let mut bars = bars();
+        let chart = KlineChart::new();
+        assert!(chart.render_svg(&[]).is_err());
+        assert!(chart
+            .render(&bars.iter().collect::<Vec<_>>(), "kline.jpg")
+            .is_err());
+        bars[1].symbol = "000001".to_string();
+        assert!(chart.render_svg(&bars.iter().collect::<Vec<_>>()).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! WebAssembly绑定模块（`wasm` 特性）
+//!
+//! 供Web界面在浏览器中直接解析用户拖入的day文件并计算技术指标，用于预览，无需上传到服务端。
+//! 只暴露内存缓冲区上的解析和计算；目录遍历、异步处理等依赖文件系统或运行时的接口
+//! 在浏览器中不可用。
+//!
+//! 构建：`cargo build --target wasm32-unknown-unknown --no-default-features --features wasm`，
+//! 再用 `wasm-bindgen` 生成JS胶水代码。
+
+use crate::parsers::{TDXDayParser, TDXDayRecord};
+use crate::processors::calculator::{EnhancedDayRecord, IndicatorCalculator};
+use crate::processors::field::INDICATOR_NAMES;
+use anyhow::Result;
+use chrono::Datelike;
+use wasm_bindgen::prelude::*;
+
+/// 解析后的日线数据，按列读取
+#[wasm_bindgen(js_name = DayData)]
+pub struct WasmDayData {
+    records: Vec<TDXDayRecord>,
+}
+
+/// 技术指标表，按列读取（缺失值为NaN）
+#[wasm_bindgen(js_name = IndicatorTable)]
+pub struct WasmIndicatorTable {
+    records: Vec<EnhancedDayRecord>,
+}
+
+/// 解析day文件内容
+#[wasm_bindgen(js_name = parseDayFile)]
+pub fn parse_day_file(buffer: &[u8], symbol: &str, market: &str) -> Result<WasmDayData, JsError> {
+    WasmDayData::parse(buffer, symbol, market).map_err(to_js_error)
+}
+
+/// 把错误转换为JS异常
+fn to_js_error(error: anyhow::Error) -> JsError {
+    JsError::new(&format!("{:#}", error))
+}
+
+impl WasmDayData {
+    fn parse(buffer: &[u8], symbol: &str, market: &str) -> Result<Self> {
+        let records = TDXDayParser::new("").parse_binary_data(buffer, symbol, market)?;
+        Ok(Self { records })
+    }
+
+    fn column(&self, value: fn(&TDXDayRecord) -> f64) -> Vec<f64> {
+        self.records.iter().map(value).collect()
+    }
+
+    fn calculate(&self, window_sizes: Option<Vec<usize>>) -> Result<WasmIndicatorTable> {
+        let mut calculator = IndicatorCalculator::new();
+        if let Some(window_sizes) = window_sizes {
+            calculator = calculator.with_window_sizes(window_sizes);
+        }
+        let records = calculator.calculate_all_indicators(&self.records)?;
+        Ok(WasmIndicatorTable { records })
+    }
+}
+
+#[wasm_bindgen(js_class = DayData)]
+impl WasmDayData {
+    /// 记录数
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.records.len()
+    }
+
+    /// 交易日期（YYYYMMDD）
+    pub fn dates(&self) -> Vec<u32> {
+        self.records
+            .iter()
+            .map(|r| r.date.year() as u32 * 10000 + r.date.month() * 100 + r.date.day())
+            .collect()
+    }
+
+    /// 开盘价
+    pub fn open(&self) -> Vec<f64> {
+        self.column(|r| r.open)
+    }
+
+    /// 最高价
+    pub fn high(&self) -> Vec<f64> {
+        self.column(|r| r.high)
+    }
+
+    /// 最低价
+    pub fn low(&self) -> Vec<f64> {
+        self.column(|r| r.low)
+    }
+
+    /// 收盘价
+    pub fn close(&self) -> Vec<f64> {
+        self.column(|r| r.close)
+    }
+
+    /// 成交量（JS数值可精确表示2^53以内的成交量）
+    pub fn volume(&self) -> Vec<f64> {
+        self.column(|r| r.volume as f64)
+    }
+
+    /// 成交额
+    pub fn amount(&self) -> Vec<f64> {
+        self.column(|r| r.amount)
+    }
+
+    /// 计算技术指标，`window_sizes` 为空时使用默认均线窗口
+    pub fn indicators(
+        &self,
+        window_sizes: Option<Vec<usize>>,
+    ) -> Result<WasmIndicatorTable, JsError> {
+        self.calculate(window_sizes).map_err(to_js_error)
+    }
+}
+
+#[wasm_bindgen(js_class = IndicatorTable)]
+impl WasmIndicatorTable {
+    /// 行数
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.records.len()
+    }
+
+    /// 全部指标列名
+    pub fn names() -> Vec<String> {
+        INDICATOR_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// 按列名读取指标，列名不存在时返回 `undefined`
+    pub fn column(&self, name: &str) -> Option<Vec<f64>> {
+        INDICATOR_NAMES.contains(&name).then(|| {
+            self.records
+                .iter()
+                .map(|r| r.indicators.get(name).unwrap_or(f64::NAN))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造通达信day文件内容
+    fn day_buffer(closes: &[u32]) -> Vec<u8> {
+        let mut content = Vec::new();
+        for (i, &close) in closes.iter().enumerate() {
+            for value in [20240102 + i as u32, close, close + 10, close - 10, close] {
+                content.extend_from_slice(&value.to_le_bytes());
+            }
+            content.extend_from_slice(&(close as f32 * 100.0).to_le_bytes());
+            content.extend_from_slice(&100u32.to_le_bytes());
+            content.extend_from_slice(&0u32.to_le_bytes());
+        }
+        content
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        let data = WasmDayData::parse(&day_buffer(&[1000, 1010]), "600000", "SH").unwrap();
+        assert_eq!(data.length(), 2);
+        assert_eq!(data.dates(), vec![20240102, 20240103]);
+        assert_eq!(data.close(), vec![10.0, 10.1]);
+        assert_eq!(data.volume(), vec![100.0, 100.0]);
+
+        assert!(WasmDayData::parse(&[0u8; 7], "600000", "SH").is_err());
+    }
+
+    #[test]
+    fn test_indicator_columns() {
+        let closes: Vec<u32> = (0..30).map(|i| 1000 + i * 5).collect();
+        let data = WasmDayData::parse(&day_buffer(&closes), "600000", "SH").unwrap();
+        let table = data.calculate(Some(vec![5])).unwrap();
+        assert_eq!(table.length(), 30);
+
+        let ma5 = table.column("ma5").unwrap();
+        assert!(ma5[3].is_nan());
+        assert!((ma5[29] - 11.35).abs() < 1e-9);
+        assert!(table.column("unknown").is_none());
+        assert_eq!(WasmIndicatorTable::names().len(), INDICATOR_NAMES.len());
+    }
+}
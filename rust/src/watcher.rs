@@ -0,0 +1,315 @@
+//! vipdoc目录监控模块
+//!
+//! 监控通达信vipdoc目录，在每日下载后检测发生变化的.day文件，
+//! 只解析文件中新增的记录，并将增量推送到异步通道，保持本地数据仓库实时更新。
+
+use crate::parsers::{TDXDayParser, TDXDayRecord};
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use walkdir::WalkDir;
+
+/// 日线记录大小（字节）
+const DAY_RECORD_SIZE: u64 = 32;
+
+/// 单个文件的增量更新
+#[derive(Debug, Clone)]
+pub struct FileUpdate {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 股票代码
+    pub symbol: String,
+    /// 市场
+    pub market: String,
+    /// 文件是否被重写（而非追加），此时 `records` 为文件全部记录
+    pub rewritten: bool,
+    /// 新增记录
+    pub records: Vec<TDXDayRecord>,
+}
+
+/// vipdoc目录监控器
+#[derive(Debug)]
+pub struct VipdocWatcher {
+    /// 监控根目录
+    root: PathBuf,
+    /// 解析器
+    parser: TDXDayParser,
+    /// 事件合并窗口
+    debounce: Duration,
+    /// 每个文件已处理的字节数
+    offsets: HashMap<PathBuf, u64>,
+}
+
+impl VipdocWatcher {
+    /// 创建新的监控器
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        let root = root.as_ref().to_path_buf();
+        Self {
+            parser: TDXDayParser::new(&root),
+            root,
+            debounce: Duration::from_millis(500),
+            offsets: HashMap::new(),
+        }
+    }
+
+    /// 设置事件合并窗口（下载过程中同一文件会触发多次写事件）
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// 记录现有文件的当前大小，之后只推送新增数据，返回登记的文件数
+    pub fn prime(&mut self) -> Result<usize> {
+        let mut count = 0;
+        for entry in WalkDir::new(&self.root).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if is_day_file(path) {
+                let len = entry
+                    .metadata()
+                    .with_context(|| format!("无法读取文件信息: {}", path.display()))?
+                    .len();
+                self.offsets
+                    .insert(path.to_path_buf(), len - len % DAY_RECORD_SIZE);
+                count += 1;
+            }
+        }
+        info!("已登记 {} 个day文件", count);
+        Ok(count)
+    }
+
+    /// 增量解析单个文件，无新增数据时返回None
+    pub fn process_path(&mut self, path: &Path) -> Result<Option<FileUpdate>> {
+        if !is_day_file(path) || !path.exists() {
+            return Ok(None);
+        }
+
+        let (symbol, market) = self.parser.extract_symbol_market(path)?;
+        let mut file =
+            File::open(path).with_context(|| format!("无法打开文件: {}", path.display()))?;
+        let len = file.metadata()?.len();
+        // 忽略尚未写完的不完整记录
+        let complete_len = len - len % DAY_RECORD_SIZE;
+
+        let previous = self.offsets.get(path).copied().unwrap_or(0);
+        let rewritten = complete_len < previous;
+        let start = if rewritten { 0 } else { previous };
+        if start == complete_len {
+            return Ok(None);
+        }
+
+        file.seek(SeekFrom::Start(start))?;
+        let mut buffer = vec![0u8; (complete_len - start) as usize];
+        file.read_exact(&mut buffer)
+            .with_context(|| format!("无法读取文件: {}", path.display()))?;
+
+        let records = self.parser.parse_binary_data(&buffer, &symbol, &market)?;
+        self.offsets.insert(path.to_path_buf(), complete_len);
+
+        debug!(
+            "文件更新: {}, 新增{}条记录{}",
+            path.display(),
+            records.len(),
+            if rewritten {
+                "（文件被重写）"
+            } else {
+                ""
+            }
+        );
+
+        Ok(Some(FileUpdate {
+            path: path.to_path_buf(),
+            symbol,
+            market,
+            rewritten,
+            records,
+        }))
+    }
+
+    /// 开始监控，增量更新推送到 `sender`
+    ///
+    /// 需要在tokio运行时中调用；返回的句柄被丢弃或调用 `stop` 时停止监控。
+    pub fn watch(mut self, sender: mpsc::Sender<FileUpdate>) -> Result<WatchHandle> {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel::<PathBuf>();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths {
+                            let _ = event_tx.send(path);
+                        }
+                    }
+                }
+                Err(e) => warn!("文件监控错误: {}", e),
+            })
+            .context("无法创建文件监控器")?;
+        watcher
+            .watch(&self.root, RecursiveMode::Recursive)
+            .with_context(|| format!("无法监控目录: {}", self.root.display()))?;
+        info!("开始监控目录: {}", self.root.display());
+
+        let task = tokio::spawn(async move {
+            while let Some(first) = event_rx.recv().await {
+                // 合并窗口内的重复事件
+                let mut pending: HashSet<PathBuf> = HashSet::from([first]);
+                let deadline = tokio::time::sleep(self.debounce);
+                tokio::pin!(deadline);
+                loop {
+                    tokio::select! {
+                        _ = &mut deadline => break,
+                        next = event_rx.recv() => match next {
+                            Some(path) => {
+                                pending.insert(path);
+                            }
+                            None => break,
+                        },
+                    }
+                }
+
+                for path in pending {
+                    match self.process_path(&path) {
+                        Ok(Some(update)) => {
+                            if sender.send(update).await.is_err() {
+                                // 接收端已关闭
+                                return;
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("增量解析失败 {}: {}", path.display(), e),
+                    }
+                }
+            }
+        });
+
+        Ok(WatchHandle {
+            _watcher: watcher,
+            task,
+        })
+    }
+}
+
+/// 监控句柄
+#[derive(Debug)]
+pub struct WatchHandle {
+    /// 底层监控器（保持存活）
+    _watcher: RecommendedWatcher,
+    /// 事件处理任务
+    task: JoinHandle<()>,
+}
+
+impl WatchHandle {
+    /// 停止监控
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 是否为day文件
+fn is_day_file(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("day")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn record_bytes(date: u32, close: u32) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32);
+        for value in [date, close, close, close, close] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&1000.0f32.to_le_bytes());
+        bytes.extend_from_slice(&100u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    fn append(path: &Path, bytes: &[u8]) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(bytes).unwrap();
+    }
+
+    fn setup() -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let sh_dir = temp_dir.path().join("sh").join("lday");
+        std::fs::create_dir_all(&sh_dir).unwrap();
+        let path = sh_dir.join("600000.day");
+        append(&path, &record_bytes(20240102, 1000));
+        (temp_dir, path)
+    }
+
+    #[test]
+    fn test_incremental_parse() {
+        let (temp_dir, path) = setup();
+        let mut watcher = VipdocWatcher::new(temp_dir.path());
+        assert_eq!(watcher.prime().unwrap(), 1);
+
+        // 无变化
+        assert!(watcher.process_path(&path).unwrap().is_none());
+
+        // 追加一条完整记录和半条记录
+        append(&path, &record_bytes(20240103, 1010));
+        append(&path, &record_bytes(20240104, 1020)[..16]);
+
+        let update = watcher.process_path(&path).unwrap().unwrap();
+        assert!(!update.rewritten);
+        assert_eq!(update.symbol, "600000");
+        assert_eq!(update.market, "SH");
+        assert_eq!(update.records.len(), 1);
+        assert_eq!(update.records[0].close, 10.1);
+    }
+
+    #[test]
+    fn test_rewritten_file() {
+        let (temp_dir, path) = setup();
+        append(&path, &record_bytes(20240103, 1010));
+
+        let mut watcher = VipdocWatcher::new(temp_dir.path());
+        watcher.prime().unwrap();
+
+        std::fs::write(&path, record_bytes(20240105, 1100)).unwrap();
+        let update = watcher.process_path(&path).unwrap().unwrap();
+        assert!(update.rewritten);
+        assert_eq!(update.records.len(), 1);
+        assert_eq!(update.records[0].close, 11.0);
+    }
+
+    #[tokio::test]
+    async fn test_watch_pushes_updates() {
+        let (temp_dir, path) = setup();
+        let mut watcher =
+            VipdocWatcher::new(temp_dir.path()).with_debounce(Duration::from_millis(50));
+        watcher.prime().unwrap();
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let handle = watcher.watch(tx).unwrap();
+
+        append(&path, &record_bytes(20240103, 1010));
+
+        let update = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("等待文件更新超时")
+            .unwrap();
+        assert_eq!(update.records.len(), 1);
+        handle.stop();
+    }
+}
@@ -1,9 +1,6 @@
-use anyhow::Result;
-use log::{debug, error, info, warn};
-use pulse_trader_rust::parsers::{TDXDayParser, TDXStatistics};
+use pulse_trader_rust::parsers::TDXDayParser;
 use std::fs;
-use std::path::PathBuf;
-use tempfile::TempDir; // 引入日志功能
+use tempfile::TempDir;
 #[test]
 /// 测试函数：验证TDXDayParser的创建功能
 ///